@@ -1,2 +1,3 @@
 pub mod account;
 pub mod transfer;
+pub mod transfer_notification;
@@ -0,0 +1,55 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::fmt;
+
+use super::account::{Account, Subaccount};
+use super::transfer::Memo;
+
+/// The argument the ledger passes to the method registered for an account
+/// after each block that credits the account.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TransferNotification {
+    pub block_index: Nat,
+    pub to: Account,
+    // The debited account, None for mints.
+    pub from: Option<Account>,
+    pub amount: Nat,
+    pub memo: Option<Memo>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RegisterTransferNotificationArgs {
+    #[serde(default)]
+    pub subaccount: Option<Subaccount>,
+    // The method of the caller that the ledger calls with a TransferNotification.
+    pub method: String,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum RegisterTransferNotificationError {
+    // Only canisters can register notifications.
+    CallerNotACanister,
+    MethodNameTooLong { max_length: u64 },
+    // The caller already registered the maximum number of its accounts.
+    TooManyRegistrationsForOwner { limit: u64 },
+}
+
+impl fmt::Display for RegisterTransferNotificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CallerNotACanister => {
+                write!(f, "only canisters can register notifications")
+            }
+            Self::MethodNameTooLong { max_length } => write!(
+                f,
+                "the method name is longer than the maximum length {}",
+                max_length
+            ),
+            Self::TooManyRegistrationsForOwner { limit } => write!(
+                f,
+                "the caller already registered the maximum number {} of its accounts",
+                limit
+            ),
+        }
+    }
+}
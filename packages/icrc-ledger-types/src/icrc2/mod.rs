@@ -1,3 +1,4 @@
 pub mod allowance;
 pub mod approve;
+pub mod spending_policy;
 pub mod transfer_from;
@@ -0,0 +1,155 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::fmt;
+
+use super::super::icrc1::account::{Account, Subaccount};
+use super::super::icrc1::transfer::Memo;
+
+/// Limits the amount a spender can use within each period of `period_nanos` nanoseconds.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PeriodCap {
+    pub amount: Nat,
+    pub period_nanos: u64,
+}
+
+/// The spending policy attached to an allowance and its usage in the current period.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SpendingPolicy {
+    pub period_cap: Option<PeriodCap>,
+    pub allowed_recipients: Option<Vec<Account>>,
+    pub period_start: u64,
+    pub spent_in_period: Nat,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SetSpendingPolicyArgs {
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    pub spender: Account,
+    #[serde(default)]
+    pub period_cap: Option<PeriodCap>,
+    // If set, the spender can only transfer tokens to these accounts.
+    #[serde(default)]
+    pub allowed_recipients: Option<Vec<Account>>,
+    #[serde(default)]
+    pub fee: Option<Nat>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SetSpendingPolicyError {
+    BadFee { expected_fee: Nat },
+    // The caller does not have enough funds to pay the fee.
+    InsufficientFunds { balance: Nat },
+    // The caller has no active approval for the spender.
+    ApprovalDoesNotExist,
+    // The period of the cap is zero.
+    InvalidPeriod,
+    // The policy allows more recipients than the ledger accepts.
+    TooManyAllowedRecipients { max_allowed_recipients: Nat },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: Nat },
+    TemporarilyUnavailable,
+    GenericError { error_code: Nat, message: String },
+}
+
+impl fmt::Display for SetSpendingPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadFee { expected_fee } => {
+                write!(f, "spending policy fee should be {}", expected_fee)
+            }
+            Self::InsufficientFunds { balance } => write!(
+                f,
+                "the debit account doesn't have enough funds to complete the transaction, current balance: {}",
+                balance
+            ),
+            Self::ApprovalDoesNotExist => {
+                write!(f, "there is no active approval for the spender")
+            }
+            Self::InvalidPeriod => write!(f, "the period of the cap must be positive"),
+            Self::TooManyAllowedRecipients {
+                max_allowed_recipients,
+            } => write!(
+                f,
+                "the policy cannot allow more than {} recipients",
+                max_allowed_recipients
+            ),
+            Self::TooOld => write!(f, "transaction's created_at_time is too far in the past"),
+            Self::CreatedInFuture { ledger_time } => write!(
+                f,
+                "transaction's created_at_time is in future, current ledger time is {}",
+                ledger_time
+            ),
+            Self::Duplicate { duplicate_of } => write!(
+                f,
+                "transaction is a duplicate of another transaction in block {}",
+                duplicate_of
+            ),
+            Self::TemporarilyUnavailable => write!(f, "the ledger is temporarily unavailable"),
+            Self::GenericError {
+                error_code,
+                message,
+            } => write!(f, "{} {}", error_code, message),
+        }
+    }
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RevokeAllApprovalsArgs {
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    #[serde(default)]
+    pub fee: Option<Nat>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum RevokeAllApprovalsError {
+    BadFee { expected_fee: Nat },
+    // The caller does not have enough funds to pay the fee.
+    InsufficientFunds { balance: Nat },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: Nat },
+    TemporarilyUnavailable,
+    GenericError { error_code: Nat, message: String },
+}
+
+impl fmt::Display for RevokeAllApprovalsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadFee { expected_fee } => {
+                write!(f, "revocation fee should be {}", expected_fee)
+            }
+            Self::InsufficientFunds { balance } => write!(
+                f,
+                "the debit account doesn't have enough funds to complete the transaction, current balance: {}",
+                balance
+            ),
+            Self::TooOld => write!(f, "transaction's created_at_time is too far in the past"),
+            Self::CreatedInFuture { ledger_time } => write!(
+                f,
+                "transaction's created_at_time is in future, current ledger time is {}",
+                ledger_time
+            ),
+            Self::Duplicate { duplicate_of } => write!(
+                f,
+                "transaction is a duplicate of another transaction in block {}",
+                duplicate_of
+            ),
+            Self::TemporarilyUnavailable => write!(f, "the ledger is temporarily unavailable"),
+            Self::GenericError {
+                error_code,
+                message,
+            } => write!(f, "{} {}", error_code, message),
+        }
+    }
+}
@@ -7,6 +7,7 @@ use crate::{
         account::Account,
         transfer::{BlockIndex, Memo},
     },
+    icrc2::spending_policy::PeriodCap,
 };
 
 use super::{
@@ -56,6 +57,25 @@ pub struct Approve {
     pub created_at_time: Option<u64>,
 }
 
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SetSpendingPolicy {
+    pub from: Account,
+    pub spender: Account,
+    pub period_cap: Option<PeriodCap>,
+    pub allowed_recipients: Option<Vec<Account>>,
+    pub memo: Option<Memo>,
+    pub fee: Option<Nat>,
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RevokeAllApprovals {
+    pub from: Account,
+    pub memo: Option<Memo>,
+    pub fee: Option<Nat>,
+    pub created_at_time: Option<u64>,
+}
+
 // Representation of a Transaction which supports the Icrc1 Standard functionalities
 #[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Transaction {
@@ -64,6 +84,10 @@ pub struct Transaction {
     pub burn: Option<Burn>,
     pub transfer: Option<Transfer>,
     pub approve: Option<Approve>,
+    #[serde(default)]
+    pub set_spending_policy: Option<SetSpendingPolicy>,
+    #[serde(default)]
+    pub revoke_all_approvals: Option<RevokeAllApprovals>,
     pub timestamp: u64,
 }
 
@@ -76,6 +100,8 @@ impl Transaction {
             burn: Some(burn),
             transfer: None,
             approve: None,
+            set_spending_policy: None,
+            revoke_all_approvals: None,
         }
     }
 
@@ -87,6 +113,8 @@ impl Transaction {
             burn: None,
             transfer: None,
             approve: None,
+            set_spending_policy: None,
+            revoke_all_approvals: None,
         }
     }
 
@@ -98,6 +126,8 @@ impl Transaction {
             burn: None,
             transfer: Some(transfer),
             approve: None,
+            set_spending_policy: None,
+            revoke_all_approvals: None,
         }
     }
 
@@ -109,6 +139,34 @@ impl Transaction {
             burn: None,
             transfer: None,
             approve: Some(approve),
+            set_spending_policy: None,
+            revoke_all_approvals: None,
+        }
+    }
+
+    pub fn set_spending_policy(set_spending_policy: SetSpendingPolicy, timestamp: u64) -> Self {
+        Self {
+            kind: "spend_policy".into(),
+            timestamp,
+            mint: None,
+            burn: None,
+            transfer: None,
+            approve: None,
+            set_spending_policy: Some(set_spending_policy),
+            revoke_all_approvals: None,
+        }
+    }
+
+    pub fn revoke_all_approvals(revoke_all_approvals: RevokeAllApprovals, timestamp: u64) -> Self {
+        Self {
+            kind: "revoke_all".into(),
+            timestamp,
+            mint: None,
+            burn: None,
+            transfer: None,
+            approve: None,
+            set_spending_policy: None,
+            revoke_all_approvals: Some(revoke_all_approvals),
         }
     }
 }
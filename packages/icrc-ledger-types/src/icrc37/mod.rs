@@ -0,0 +1,3 @@
+pub mod approve;
+pub mod revoke;
+pub mod transfer_from;
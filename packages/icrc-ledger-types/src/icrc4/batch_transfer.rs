@@ -0,0 +1,10 @@
+use candid::Nat;
+
+use super::super::icrc1::transfer::{TransferArg, TransferError};
+
+/// The argument of `icrc4_transfer_batch`: the transfers to execute, in order.
+pub type TransferBatchArgs = Vec<TransferArg>;
+
+/// The outcome of a single entry of a batch, in the same position as the corresponding
+/// [TransferArg] in [TransferBatchArgs].
+pub type TransferBatchResult = Result<Nat, TransferError>;
@@ -38,6 +38,7 @@ use crate::common::rest::{
     InstanceId, MockCanisterHttpResponse, RawEffectivePrincipal, RawMessageId, SubnetId,
     SubnetSpec, Topology,
 };
+pub use crate::mock_canister::{MockCanisterCall, MockCanisterMethod, MockCanisterResponse};
 use crate::nonblocking::PocketIc as PocketIcAsync;
 use candid::{
     decode_args, encode_args,
@@ -62,6 +63,7 @@ use std::{
 use tracing::{instrument, warn};
 
 pub mod common;
+mod mock_canister;
 pub mod nonblocking;
 
 // the default timeout of a PocketIC operation
@@ -921,6 +923,56 @@ impl PocketIc {
                 .await
         })
     }
+
+    /// Install a mock canister with the given methods at the specified canister ID.
+    /// The canister is created (with the sender as its controller) if it does not exist yet
+    /// and reinstalled by the sender otherwise (dropping all its pending calls),
+    /// i.e., the sender must be a controller of an existing canister.
+    /// Calls to methods with a static response are answered immediately.
+    /// Calls to all other methods are kept open until they are answered
+    /// by a controller of the mock canister using `PocketIc::mock_canister_reply`.
+    #[instrument(skip(self, methods), fields(instance_id=self.pocket_ic.instance_id, canister_id = %canister_id.to_string(), sender = %sender.unwrap_or(Principal::anonymous()).to_string()))]
+    pub fn mock_canister(
+        &self,
+        canister_id: CanisterId,
+        methods: Vec<MockCanisterMethod>,
+        sender: Option<Principal>,
+    ) -> Result<(), CallError> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async {
+            self.pocket_ic
+                .mock_canister(canister_id, methods, sender)
+                .await
+        })
+    }
+
+    /// Get the pending calls to a mock canister that have not been answered
+    /// by the test driver yet.
+    /// Note that an additional `PocketIc::tick` is necessary after a canister
+    /// makes a call to a mock canister for the call to be retrievable here.
+    #[instrument(ret, skip(self), fields(instance_id=self.pocket_ic.instance_id, canister_id = %canister_id.to_string()))]
+    pub fn get_mock_canister_calls(&self, canister_id: CanisterId) -> Vec<MockCanisterCall> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.get_mock_canister_calls(canister_id).await })
+    }
+
+    /// Answer a pending call to a mock canister.
+    /// The sender must be a controller of the mock canister.
+    /// The response is delivered to the caller within the next rounds.
+    #[instrument(skip(self, response), fields(instance_id=self.pocket_ic.instance_id, canister_id = %mock_canister_call.canister_id.to_string(), call_id = %mock_canister_call.call_id, sender = %sender.unwrap_or(Principal::anonymous()).to_string()))]
+    pub fn mock_canister_reply(
+        &self,
+        mock_canister_call: &MockCanisterCall,
+        response: MockCanisterResponse,
+        sender: Option<Principal>,
+    ) -> Result<(), UserError> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async {
+            self.pocket_ic
+                .mock_canister_reply(mock_canister_call, response, sender)
+                .await
+        })
+    }
 }
 
 impl Default for PocketIc {
@@ -0,0 +1,401 @@
+//! Mock canisters whose responses are scripted by the test driver.
+//!
+//! A mock canister is backed by a WASM module generated from the list of its methods.
+//! Methods with a static response reply (or reject) immediately.
+//! Calls to all other methods are recorded in the stable memory of the mock canister
+//! and left open until the test driver answers them using `PocketIc::mock_canister_reply`:
+//! until then, the mock canister keeps the call open by polling the management canister
+//! (`raw_rand`) once per round and checking if an answer has been provided.
+//!
+//! Stable memory of a mock canister is an append-only log of little-endian records:
+//! - calls: `[total_len: u32][0: u32][method_len: u32][caller_len: u32][method][caller][arg]`;
+//! - answers: `[total_len: u32][1: u32][call_id: u32][is_reject: u32][payload]`.
+//!
+//! The ID of a call is its ordinal number among all call records.
+
+use candid::Principal;
+use ic_cdk::api::management_canister::main::CanisterId;
+use std::collections::BTreeSet;
+
+/// The (internal) method used to answer pending calls to a mock canister.
+pub(crate) const MOCK_CANISTER_REPLY_METHOD: &str = "__pocket_ic_mock_canister_reply";
+
+// The maximum number of calls to a mock canister that are answered by the test driver.
+const MAX_DEFERRED_CALLS: u32 = 4096;
+
+// Layout of the heap memory of a mock canister.
+const LOG_END_OFFSET: u32 = 0;
+const NEXT_CALL_OFFSET: u32 = 8;
+const RAW_RAND_OFFSET: u32 = 16;
+const EMPTY_ARGS_OFFSET: u32 = 24;
+const CALL_LIMIT_ERROR_OFFSET: u32 = 32;
+const NOT_CONTROLLER_ERROR_OFFSET: u32 = 64;
+const INVALID_ANSWER_ERROR_OFFSET: u32 = 96;
+const ANSWERS_OFFSET: u32 = 128;
+const ANSWER_ENTRY_SIZE: u32 = 16;
+const STATIC_DATA_OFFSET: u32 = ANSWERS_OFFSET + MAX_DEFERRED_CALLS * ANSWER_ENTRY_SIZE;
+// The scratch buffer must fit a call record with the largest possible
+// argument (2 MiB plus message overhead).
+const SCRATCH_SIZE: u32 = 3 << 20;
+const WASM_PAGE_SIZE: u32 = 1 << 16;
+
+const CALL_RECORD: u32 = 0;
+const ANSWER_RECORD: u32 = 1;
+const RECORD_HEADER_SIZE: usize = 16;
+
+/// A response of a mock canister to a call.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum MockCanisterResponse {
+    /// Reply with the given (typically Candid-encoded) data.
+    Reply(Vec<u8>),
+    /// Reject with the given message (and reject code `CANISTER_REJECT`).
+    Reject(String),
+}
+
+/// A method of a mock canister.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct MockCanisterMethod {
+    pub name: String,
+    pub is_query: bool,
+    /// The static response to every call of this method.
+    /// If `None`, calls are recorded and must be answered
+    /// by the test driver using `PocketIc::mock_canister_reply`.
+    pub response: Option<MockCanisterResponse>,
+}
+
+impl MockCanisterMethod {
+    /// An update method with a static response to every call.
+    pub fn update(name: impl ToString, response: MockCanisterResponse) -> Self {
+        Self {
+            name: name.to_string(),
+            is_query: false,
+            response: Some(response),
+        }
+    }
+
+    /// A query method with a static response to every call.
+    pub fn query(name: impl ToString, response: MockCanisterResponse) -> Self {
+        Self {
+            name: name.to_string(),
+            is_query: true,
+            response: Some(response),
+        }
+    }
+
+    /// An update method whose calls are answered by the test driver.
+    pub fn deferred(name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            is_query: false,
+            response: None,
+        }
+    }
+}
+
+/// A call to a mock canister that has not been answered by the test driver yet.
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct MockCanisterCall {
+    pub canister_id: CanisterId,
+    pub call_id: u32,
+    pub caller: Principal,
+    pub method: String,
+    pub arg: Vec<u8>,
+}
+
+fn wat_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("\\{:02x}", b)).collect()
+}
+
+/// Generates the WASM module of a mock canister with the given methods.
+///
+/// # Panics
+///
+/// This function panics if a query method has no static response
+/// or if a method is specified more than once.
+pub(crate) fn mock_canister_wasm(methods: &[MockCanisterMethod]) -> Vec<u8> {
+    let mut names = BTreeSet::new();
+    let mut static_data = vec![];
+    let mut exports = String::new();
+    for method in methods {
+        assert!(
+            names.insert(method.name.clone()),
+            "Method {} of the mock canister is specified more than once.",
+            method.name
+        );
+        assert_ne!(
+            method.name, MOCK_CANISTER_REPLY_METHOD,
+            "Method {} is reserved.",
+            MOCK_CANISTER_REPLY_METHOD
+        );
+        let kind = if method.is_query {
+            "canister_query"
+        } else {
+            "canister_update"
+        };
+        let export_name = wat_bytes(format!("{} {}", kind, method.name).as_bytes());
+        let (data, body) = match &method.response {
+            Some(MockCanisterResponse::Reply(data)) => (
+                data.clone(),
+                "(call $msg_reply_data_append (i32.const {offset}) (i32.const {len}))\n      (call $msg_reply)",
+            ),
+            Some(MockCanisterResponse::Reject(message)) => (
+                message.as_bytes().to_vec(),
+                "(call $msg_reject (i32.const {offset}) (i32.const {len}))",
+            ),
+            None => {
+                assert!(
+                    !method.is_query,
+                    "Query method {} of the mock canister must have a static response.",
+                    method.name
+                );
+                (
+                    method.name.as_bytes().to_vec(),
+                    "(call $defer (i32.const {offset}) (i32.const {len}))",
+                )
+            }
+        };
+        let offset = STATIC_DATA_OFFSET as usize + static_data.len();
+        let body = body
+            .replace("{offset}", &offset.to_string())
+            .replace("{len}", &data.len().to_string());
+        exports.push_str(&format!(
+            "    (func (export \"{}\")\n      {})\n",
+            export_name, body
+        ));
+        static_data.extend(data);
+    }
+
+    let scratch = (STATIC_DATA_OFFSET + static_data.len() as u32 + 7) / 8 * 8;
+    let memory_pages = (scratch + SCRATCH_SIZE + WASM_PAGE_SIZE - 1) / WASM_PAGE_SIZE;
+    let wat = format!(
+        r#"(module
+    (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+    (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+    (import "ic0" "msg_caller_size" (func $msg_caller_size (result i32)))
+    (import "ic0" "msg_caller_copy" (func $msg_caller_copy (param i32 i32 i32)))
+    (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+    (import "ic0" "msg_reply" (func $msg_reply))
+    (import "ic0" "msg_reject" (func $msg_reject (param i32 i32)))
+    (import "ic0" "is_controller" (func $is_controller (param i32 i32) (result i32)))
+    (import "ic0" "call_new" (func $call_new (param i32 i32 i32 i32 i32 i32 i32 i32)))
+    (import "ic0" "call_data_append" (func $call_data_append (param i32 i32)))
+    (import "ic0" "call_perform" (func $call_perform (result i32)))
+    (import "ic0" "stable64_size" (func $stable64_size (result i64)))
+    (import "ic0" "stable64_grow" (func $stable64_grow (param i64) (result i64)))
+    (import "ic0" "stable64_read" (func $stable64_read (param i64 i64 i64)))
+    (import "ic0" "stable64_write" (func $stable64_write (param i64 i64 i64)))
+    (import "ic0" "trap" (func $trap (param i32 i32)))
+
+    (memory {memory_pages})
+    (table funcref (elem $on_poll))
+
+    (data (i32.const {raw_rand_offset}) "raw_rand")
+    (data (i32.const {empty_args_offset}) "DIDL\00\00")
+    (data (i32.const {static_data_offset}) "{static_data}")
+    (data (i32.const {call_limit_error_offset}) "call limit reached")
+    (data (i32.const {not_controller_error_offset}) "caller is not a controller")
+    (data (i32.const {invalid_answer_error_offset}) "call cannot be answered")
+
+    ;; Appends the record at `src` of size `len` to the log in stable memory.
+    (func $append (param $src i32) (param $len i32)
+      (local $end i64)
+      (local.set $end (i64.add (i64.load (i32.const {log_end_offset})) (i64.extend_i32_u (local.get $len))))
+      (if (i64.gt_u (local.get $end) (i64.mul (call $stable64_size) (i64.const {wasm_page_size})))
+        (then
+          (if (i64.eq
+                (call $stable64_grow
+                  (i64.sub
+                    (i64.div_u (i64.add (local.get $end) (i64.const {wasm_page_size_minus_one})) (i64.const {wasm_page_size}))
+                    (call $stable64_size)))
+                (i64.const -1))
+            (then (unreachable)))))
+      (call $stable64_write
+        (i64.load (i32.const {log_end_offset}))
+        (i64.extend_i32_u (local.get $src))
+        (i64.extend_i32_u (local.get $len)))
+      (i64.store (i32.const {log_end_offset}) (local.get $end)))
+
+    ;; Makes a call to the management canister whose callback checks
+    ;; if the call with the given ID has been answered.
+    (func $poll (param $call_id i32)
+      (call $call_new
+        (i32.const 0) (i32.const 0)
+        (i32.const {raw_rand_offset}) (i32.const 8)
+        (i32.const 0) (local.get $call_id)
+        (i32.const 0) (local.get $call_id))
+      (call $call_data_append (i32.const {empty_args_offset}) (i32.const 6))
+      (if (call $call_perform)
+        (then (unreachable))))
+
+    (func $on_poll (param $call_id i32)
+      (local $entry i32)
+      (local $status i32)
+      (local $len i32)
+      (local.set $entry (i32.add (i32.const {answers_offset}) (i32.mul (local.get $call_id) (i32.const {answer_entry_size}))))
+      (local.set $status (i32.load (i32.add (local.get $entry) (i32.const 12))))
+      (if (i32.eqz (local.get $status))
+        (then
+          (call $poll (local.get $call_id))
+          (return)))
+      (local.set $len (i32.load (i32.add (local.get $entry) (i32.const 8))))
+      (call $stable64_read
+        (i64.const {scratch})
+        (i64.load (local.get $entry))
+        (i64.extend_i32_u (local.get $len)))
+      (if (i32.eq (local.get $status) (i32.const 1))
+        (then
+          (call $msg_reply_data_append (i32.const {scratch}) (local.get $len))
+          (call $msg_reply))
+        (else
+          (call $msg_reject (i32.const {scratch}) (local.get $len)))))
+
+    ;; Records the call of the method with the name at `name_src` of size `name_len`
+    ;; and keeps the call open until it is answered.
+    (func $defer (param $name_src i32) (param $name_len i32)
+      (local $call_id i32)
+      (local $caller_len i32)
+      (local $arg_len i32)
+      (local $total_len i32)
+      (local.set $call_id (i32.load (i32.const {next_call_offset})))
+      (if (i32.ge_u (local.get $call_id) (i32.const {max_deferred_calls}))
+        (then (call $trap (i32.const {call_limit_error_offset}) (i32.const 18))))
+      (local.set $caller_len (call $msg_caller_size))
+      (local.set $arg_len (call $msg_arg_data_size))
+      (local.set $total_len
+        (i32.add (i32.const {record_header_size})
+          (i32.add (local.get $name_len) (i32.add (local.get $caller_len) (local.get $arg_len)))))
+      (i32.store (i32.const {scratch}) (local.get $total_len))
+      (i32.store (i32.const {scratch_plus_4}) (i32.const {call_record}))
+      (i32.store (i32.const {scratch_plus_8}) (local.get $name_len))
+      (i32.store (i32.const {scratch_plus_12}) (local.get $caller_len))
+      (memory.copy (i32.const {scratch_plus_16}) (local.get $name_src) (local.get $name_len))
+      (call $msg_caller_copy
+        (i32.add (i32.const {scratch_plus_16}) (local.get $name_len))
+        (i32.const 0)
+        (local.get $caller_len))
+      (call $msg_arg_data_copy
+        (i32.add (i32.const {scratch_plus_16}) (i32.add (local.get $name_len) (local.get $caller_len)))
+        (i32.const 0)
+        (local.get $arg_len))
+      (call $append (i32.const {scratch}) (local.get $total_len))
+      (i32.store (i32.const {next_call_offset}) (i32.add (local.get $call_id) (i32.const 1)))
+      (call $poll (local.get $call_id)))
+
+    ;; Answers a call: the argument is `[call_id: u32][is_reject: u32][payload]`.
+    (func (export "canister_update {reply_method}")
+      (local $caller_len i32)
+      (local $arg_len i32)
+      (local $call_id i32)
+      (local $entry i32)
+      (local.set $caller_len (call $msg_caller_size))
+      (call $msg_caller_copy (i32.const {scratch}) (i32.const 0) (local.get $caller_len))
+      (if (i32.eqz (call $is_controller (i32.const {scratch}) (local.get $caller_len)))
+        (then (call $trap (i32.const {not_controller_error_offset}) (i32.const 26))))
+      (local.set $arg_len (call $msg_arg_data_size))
+      (if (i32.lt_u (local.get $arg_len) (i32.const 8))
+        (then (call $trap (i32.const {invalid_answer_error_offset}) (i32.const 23))))
+      (call $msg_arg_data_copy (i32.const {scratch_plus_8}) (i32.const 0) (local.get $arg_len))
+      (local.set $call_id (i32.load (i32.const {scratch_plus_8})))
+      (if (i32.ge_u (local.get $call_id) (i32.load (i32.const {next_call_offset})))
+        (then (call $trap (i32.const {invalid_answer_error_offset}) (i32.const 23))))
+      (local.set $entry (i32.add (i32.const {answers_offset}) (i32.mul (local.get $call_id) (i32.const {answer_entry_size}))))
+      (if (i32.load (i32.add (local.get $entry) (i32.const 12)))
+        (then (call $trap (i32.const {invalid_answer_error_offset}) (i32.const 23))))
+      (if (i32.gt_u (i32.load (i32.const {scratch_plus_12})) (i32.const 1))
+        (then (call $trap (i32.const {invalid_answer_error_offset}) (i32.const 23))))
+      (i32.store (i32.const {scratch}) (i32.add (local.get $arg_len) (i32.const 8)))
+      (i32.store (i32.const {scratch_plus_4}) (i32.const {answer_record}))
+      (i64.store (local.get $entry) (i64.add (i64.load (i32.const {log_end_offset})) (i64.const {record_header_size})))
+      (i32.store (i32.add (local.get $entry) (i32.const 8)) (i32.sub (local.get $arg_len) (i32.const 8)))
+      (i32.store (i32.add (local.get $entry) (i32.const 12)) (i32.add (i32.load (i32.const {scratch_plus_12})) (i32.const 1)))
+      (call $append (i32.const {scratch}) (i32.add (local.get $arg_len) (i32.const 8)))
+      (call $msg_reply))
+
+{exports})"#,
+        memory_pages = memory_pages,
+        raw_rand_offset = RAW_RAND_OFFSET,
+        empty_args_offset = EMPTY_ARGS_OFFSET,
+        static_data_offset = STATIC_DATA_OFFSET,
+        static_data = wat_bytes(&static_data),
+        scratch = scratch,
+        scratch_plus_4 = scratch + 4,
+        scratch_plus_8 = scratch + 8,
+        scratch_plus_12 = scratch + 12,
+        scratch_plus_16 = scratch + 16,
+        call_limit_error_offset = CALL_LIMIT_ERROR_OFFSET,
+        not_controller_error_offset = NOT_CONTROLLER_ERROR_OFFSET,
+        invalid_answer_error_offset = INVALID_ANSWER_ERROR_OFFSET,
+        log_end_offset = LOG_END_OFFSET,
+        next_call_offset = NEXT_CALL_OFFSET,
+        answers_offset = ANSWERS_OFFSET,
+        answer_entry_size = ANSWER_ENTRY_SIZE,
+        max_deferred_calls = MAX_DEFERRED_CALLS,
+        record_header_size = RECORD_HEADER_SIZE,
+        call_record = CALL_RECORD,
+        answer_record = ANSWER_RECORD,
+        wasm_page_size = WASM_PAGE_SIZE,
+        wasm_page_size_minus_one = WASM_PAGE_SIZE - 1,
+        reply_method = MOCK_CANISTER_REPLY_METHOD,
+        exports = exports,
+    );
+    wat::parse_str(wat).expect("Failed to generate the WASM module of a mock canister")
+}
+
+/// Encodes the argument of `MOCK_CANISTER_REPLY_METHOD` answering the call with the given ID.
+pub(crate) fn encode_mock_canister_reply(call_id: u32, response: MockCanisterResponse) -> Vec<u8> {
+    let (is_reject, payload) = match response {
+        MockCanisterResponse::Reply(data) => (0_u32, data),
+        MockCanisterResponse::Reject(message) => (1_u32, message.into_bytes()),
+    };
+    let mut arg = call_id.to_le_bytes().to_vec();
+    arg.extend(is_reject.to_le_bytes());
+    arg.extend(payload);
+    arg
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Decodes the calls to a mock canister that have not been answered yet
+/// from the stable memory of the mock canister.
+pub(crate) fn decode_pending_mock_canister_calls(
+    canister_id: CanisterId,
+    stable_memory: &[u8],
+) -> Vec<MockCanisterCall> {
+    let mut calls = vec![];
+    let mut answered = BTreeSet::new();
+    let mut offset = 0;
+    while offset + RECORD_HEADER_SIZE <= stable_memory.len() {
+        let total_len = read_u32(stable_memory, offset) as usize;
+        if total_len == 0 {
+            break;
+        }
+        let record = &stable_memory[offset..offset + total_len];
+        match read_u32(record, 4) {
+            CALL_RECORD => {
+                let method_len = read_u32(record, 8) as usize;
+                let caller_len = read_u32(record, 12) as usize;
+                let caller_offset = RECORD_HEADER_SIZE + method_len;
+                let arg_offset = caller_offset + caller_len;
+                calls.push(MockCanisterCall {
+                    canister_id,
+                    call_id: calls.len() as u32,
+                    caller: Principal::from_slice(&record[caller_offset..arg_offset]),
+                    method: String::from_utf8_lossy(&record[RECORD_HEADER_SIZE..caller_offset])
+                        .to_string(),
+                    arg: record[arg_offset..].to_vec(),
+                });
+            }
+            ANSWER_RECORD => {
+                answered.insert(read_u32(record, 8));
+            }
+            kind => panic!("Unknown record kind {} in a mock canister log.", kind),
+        }
+        offset += total_len;
+    }
+    calls
+        .into_iter()
+        .filter(|call| !answered.contains(&call.call_id))
+        .collect()
+}
@@ -7,6 +7,10 @@ use crate::common::rest::{
     RawSetStableMemory, RawStableMemory, RawSubmitIngressResult, RawSubnetId, RawTime,
     RawVerifyCanisterSigArg, RawWasmResult, SubnetId, Topology,
 };
+use crate::mock_canister::{
+    decode_pending_mock_canister_calls, encode_mock_canister_reply, mock_canister_wasm,
+    MockCanisterCall, MockCanisterMethod, MockCanisterResponse, MOCK_CANISTER_REPLY_METHOD,
+};
 use crate::{CallError, PocketIcBuilder, UserError, WasmResult, DEFAULT_MAX_REQUEST_TIME_MS};
 use candid::{
     decode_args, encode_args,
@@ -38,6 +42,10 @@ const LOG_DIR_LEVELS_ENV_NAME: &str = "POCKET_IC_LOG_DIR_LEVELS";
 
 const LOCALHOST: &str = "localhost";
 
+// The cycles balance of a newly created mock canister
+// that polls the management canister to keep pending calls open.
+const MOCK_CANISTER_CYCLES: u128 = 100_000_000_000_000;
+
 // The minimum joint size of a canister's WASM
 // and its initial argument blob for which
 // we install the canister WASM as a sequence of chunks.
@@ -1224,6 +1232,80 @@ impl PocketIc {
             mock_canister_http_response.into();
         self.post(endpoint, raw_mock_canister_http_response).await
     }
+
+    /// Install a mock canister with the given methods at the specified canister ID.
+    /// The canister is created (with the sender as its controller) if it does not exist yet
+    /// and reinstalled by the sender otherwise (dropping all its pending calls),
+    /// i.e., the sender must be a controller of an existing canister.
+    /// Calls to methods with a static response are answered immediately.
+    /// Calls to all other methods are kept open until they are answered
+    /// by a controller of the mock canister using `PocketIc::mock_canister_reply`.
+    #[instrument(skip(self, methods), fields(instance_id=self.instance_id, canister_id = %canister_id.to_string(), sender = %sender.unwrap_or(Principal::anonymous()).to_string()))]
+    pub async fn mock_canister(
+        &self,
+        canister_id: CanisterId,
+        methods: Vec<MockCanisterMethod>,
+        sender: Option<Principal>,
+    ) -> Result<(), CallError> {
+        let wasm_module = mock_canister_wasm(&methods);
+        if self.canister_exists(canister_id).await {
+            return self
+                .reinstall_canister(canister_id, wasm_module, vec![], sender)
+                .await;
+        }
+        call_candid_as::<(ProvisionalCreateCanisterArgument,), (CanisterIdRecord,)>(
+            self,
+            Principal::management_canister(),
+            RawEffectivePrincipal::CanisterId(canister_id.as_slice().to_vec()),
+            sender.unwrap_or(Principal::anonymous()),
+            "provisional_create_canister_with_cycles",
+            (ProvisionalCreateCanisterArgument {
+                settings: None,
+                specified_id: Some(canister_id),
+                amount: Some(0_u64.into()),
+            },),
+        )
+        .await?;
+        self.add_cycles(canister_id, MOCK_CANISTER_CYCLES).await;
+        self.install_canister_helper(
+            CanisterInstallMode::Install,
+            canister_id,
+            wasm_module,
+            vec![],
+            sender,
+        )
+        .await
+    }
+
+    /// Get the pending calls to a mock canister that have not been answered
+    /// by the test driver yet.
+    /// Note that an additional `PocketIc::tick` is necessary after a canister
+    /// makes a call to a mock canister for the call to be retrievable here.
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id, canister_id = %canister_id.to_string()))]
+    pub async fn get_mock_canister_calls(&self, canister_id: CanisterId) -> Vec<MockCanisterCall> {
+        let stable_memory = self.get_stable_memory(canister_id).await;
+        decode_pending_mock_canister_calls(canister_id, &stable_memory)
+    }
+
+    /// Answer a pending call to a mock canister.
+    /// The sender must be a controller of the mock canister.
+    /// The response is delivered to the caller within the next rounds.
+    #[instrument(skip(self, response), fields(instance_id=self.instance_id, canister_id = %mock_canister_call.canister_id.to_string(), call_id = %mock_canister_call.call_id, sender = %sender.unwrap_or(Principal::anonymous()).to_string()))]
+    pub async fn mock_canister_reply(
+        &self,
+        mock_canister_call: &MockCanisterCall,
+        response: MockCanisterResponse,
+        sender: Option<Principal>,
+    ) -> Result<(), UserError> {
+        self.update_call(
+            mock_canister_call.canister_id,
+            sender.unwrap_or(Principal::anonymous()),
+            MOCK_CANISTER_REPLY_METHOD,
+            encode_mock_canister_reply(mock_canister_call.call_id, response),
+        )
+        .await
+        .map(|_| ())
+    }
 }
 
 /// Call a canister candid method, authenticated. The sender can be impersonated (i.e., the
@@ -14,7 +14,7 @@ use pocket_ic::{
         BlobCompression, CanisterHttpReply, CanisterHttpResponse, MockCanisterHttpResponse,
         SubnetConfigSet, SubnetKind,
     },
-    update_candid, PocketIc, PocketIcBuilder, WasmResult,
+    update_candid, MockCanisterMethod, MockCanisterResponse, PocketIc, PocketIcBuilder, WasmResult,
 };
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -1517,3 +1517,123 @@ fn test_canister_http_with_one_additional_response() {
     };
     pic.mock_canister_http_response(mock_canister_http_response);
 }
+
+#[test]
+fn test_mock_canister() {
+    let pic = PocketIcBuilder::new()
+        .with_nns_subnet()
+        .with_application_subnet()
+        .build();
+
+    // Create a canister and charge it with 2T cycles.
+    let can_id = pic.create_canister();
+    pic.add_cycles(can_id, INIT_CYCLES);
+
+    // Install the universal canister making calls to the mock canister.
+    pic.install_canister(can_id, UNIVERSAL_CANISTER_WASM.to_vec(), vec![], None);
+
+    // Mock the ICP ledger canister controlled by a non-anonymous principal.
+    let ledger_id = Principal::from_text("ryjl3-tyaaa-aaaaa-aaaba-cai").unwrap();
+    let controller = Principal::from_slice(&[0xFF; 29]);
+    let methods = vec![
+        MockCanisterMethod::query(
+            "symbol",
+            MockCanisterResponse::Reply(encode_one("ICP").unwrap()),
+        ),
+        MockCanisterMethod::update(
+            "icrc1_transfer",
+            MockCanisterResponse::Reject("transfers are disabled".to_string()),
+        ),
+        MockCanisterMethod::deferred("icrc1_balance_of"),
+    ];
+    pic.mock_canister(ledger_id, methods.clone(), Some(controller))
+        .unwrap();
+    // Only a controller can reinstall the mock canister.
+    assert!(pic.mock_canister(ledger_id, methods.clone(), None).is_err());
+    pic.mock_canister(ledger_id, methods, Some(controller))
+        .unwrap();
+
+    // Static responses are returned immediately.
+    let symbol: String = decode_one(&match pic
+        .query_call(
+            ledger_id,
+            Principal::anonymous(),
+            "symbol",
+            encode_one(()).unwrap(),
+        )
+        .unwrap()
+    {
+        WasmResult::Reply(data) => data,
+        WasmResult::Reject(msg) => panic!("Unexpected reject {}", msg),
+    })
+    .unwrap();
+    assert_eq!(symbol, "ICP");
+    let symbol: String = cross_canister_call(&pic, can_id, ledger_id, "symbol", ());
+    assert_eq!(symbol, "ICP");
+    let reject = pic.update_call(
+        can_id,
+        Principal::anonymous(),
+        "update",
+        wasm()
+            .call_simple(
+                ledger_id,
+                "icrc1_transfer",
+                CallArgs::default()
+                    .other_side(encode_one(()).unwrap())
+                    .on_reject(wasm().reject_message().reject()),
+            )
+            .build(),
+    );
+    assert_eq!(
+        reject,
+        Ok(WasmResult::Reject("transfers are disabled".to_string()))
+    );
+
+    // Calls to a deferred method are pending until answered by the test driver.
+    let call_id = pic
+        .submit_call(
+            can_id,
+            Principal::anonymous(),
+            "update",
+            wasm()
+                .call_simple(
+                    ledger_id,
+                    "icrc1_balance_of",
+                    CallArgs::default().other_side(b"account".to_vec()),
+                )
+                .build(),
+        )
+        .unwrap();
+    pic.tick();
+    pic.tick();
+    let calls = pic.get_mock_canister_calls(ledger_id);
+    assert_eq!(calls.len(), 1);
+    let call = &calls[0];
+    assert_eq!(call.caller, can_id);
+    assert_eq!(call.method, "icrc1_balance_of");
+    assert_eq!(call.arg, b"account".to_vec());
+
+    let balance = encode_one(candid::Nat::from(42_u64)).unwrap();
+    // Only a controller can answer a call.
+    assert!(pic
+        .mock_canister_reply(call, MockCanisterResponse::Reply(balance.clone()), None)
+        .is_err());
+    pic.mock_canister_reply(
+        call,
+        MockCanisterResponse::Reply(balance.clone()),
+        Some(controller),
+    )
+    .unwrap();
+    // A call can only be answered once.
+    assert!(pic
+        .mock_canister_reply(
+            call,
+            MockCanisterResponse::Reply(balance.clone()),
+            Some(controller)
+        )
+        .is_err());
+    assert_eq!(pic.get_mock_canister_calls(ledger_id).len(), 0);
+
+    let reply = pic.await_call(call_id).unwrap();
+    assert_eq!(reply, WasmResult::Reply(balance));
+}
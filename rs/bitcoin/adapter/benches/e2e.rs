@@ -139,6 +139,7 @@ fn e2e(criterion: &mut Criterion) {
             .map(|h| h[..].to_vec())
             .collect::<Vec<Vec<u8>>>(),
         network: ic_btc_interface::Network::Regtest,
+        chain: None,
     };
 
     let wrapped = BitcoinAdapterRequestWrapper::GetSuccessorsRequest(get_successors_request);
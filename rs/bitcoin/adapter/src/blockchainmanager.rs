@@ -373,7 +373,8 @@ impl BlockchainManager {
             }
 
             match maybe_err {
-                Some(AddHeaderError::InvalidHeader(_, _)) => {
+                Some(AddHeaderError::InvalidHeader(_, _))
+                | Some(AddHeaderError::CheckpointMismatch(_, _)) => {
                     return Err(ReceivedHeadersMessageError::ReceivedInvalidHeader)
                 }
                 Some(AddHeaderError::PrevHeaderNotCached(stop_hash)) => {
@@ -1,8 +1,13 @@
 //! The module is responsible for keeping track of the blockchain state.
 //!
-use crate::{common::BlockHeight, config::Config, metrics::BlockchainStateMetrics};
-use bitcoin::{blockdata::constants::genesis_block, Block, BlockHash, BlockHeader, Network};
-use ic_btc_validation::{validate_header, HeaderStore, ValidateHeaderError};
+use crate::{
+    chain::ChainParams,
+    common::BlockHeight,
+    config::{Config, HeaderCheckpoint},
+    metrics::BlockchainStateMetrics,
+};
+use bitcoin::{Block, BlockHash, BlockHeader};
+use ic_btc_validation::{HeaderStore, ValidateHeaderError};
 use ic_metrics::MetricsRegistry;
 use std::collections::HashMap;
 use thiserror::Error;
@@ -22,12 +27,13 @@ pub struct Tip {
     pub work: Work,
 }
 
-/// Creates a new cache with a set genesis header determined by the
-/// provided network.
-fn init_cache_with_genesis(genesis_block_header: BlockHeader) -> HashMap<BlockHash, HeaderNode> {
+/// Creates a new cache with a set genesis header, which is either the genesis
+/// header of the chain or an assumed-valid checkpoint.
+fn init_cache_with_genesis(initial_header: HeaderCheckpoint) -> HashMap<BlockHash, HeaderNode> {
+    let genesis_block_header = initial_header.header;
     let cached_header = HeaderNode {
         header: genesis_block_header,
-        height: 0,
+        height: initial_header.height,
         work: genesis_block_header.work(),
         children: vec![],
     };
@@ -68,6 +74,9 @@ pub enum AddHeaderError {
     /// This variant is used when the predecessor of the input header is not part of header_cache.
     #[error("Received a block header where we do not have the previous header in the cache: {0}")]
     PrevHeaderNotCached(BlockHash),
+    /// This variant is used when the input header conflicts with the checkpoint at its height.
+    #[error("Received a block header {0} that conflicts with the checkpoint at height {1}")]
+    CheckpointMismatch(BlockHash, BlockHeight),
 }
 
 #[derive(Debug, Error)]
@@ -97,8 +106,11 @@ pub struct BlockchainState {
     /// This field contains the known tips of the header cache.
     tips: Vec<Tip>,
 
-    /// Used to determine how validation should be handled with `validate_header`.
-    network: Network,
+    /// Used to determine how validation should be handled with `ChainParams::validate_header`.
+    chain_params: ChainParams,
+
+    /// The block hashes of the checkpointed headers by height.
+    checkpoints: HashMap<BlockHeight, BlockHash>,
     metrics: BlockchainStateMetrics,
 }
 
@@ -106,12 +118,14 @@ impl BlockchainState {
     /// This function is used to create a new BlockChainState object.  
     pub fn new(config: &Config, metrics_registry: &MetricsRegistry) -> Self {
         // Create a header cache and inserting dummy header corresponding the `adapter_genesis_hash`.
-        let genesis_block_header = genesis_block(config.network).header;
-        let header_cache = init_cache_with_genesis(genesis_block_header);
+        let chain_params = config.chain_params();
+        let initial_header = config.initial_header();
+        let genesis_block_header = initial_header.header;
+        let header_cache = init_cache_with_genesis(initial_header);
         let block_cache = HashMap::new();
         let tips = vec![Tip {
             header: genesis_block_header,
-            height: 0,
+            height: initial_header.height,
             work: genesis_block_header.work(),
         }];
 
@@ -120,12 +134,18 @@ impl BlockchainState {
             header_cache,
             block_cache,
             tips,
-            network: config.network,
+            chain_params,
+            checkpoints: config
+                .checkpoints
+                .iter()
+                .map(|checkpoint| (checkpoint.height, checkpoint.header.block_hash()))
+                .collect(),
             metrics: BlockchainStateMetrics::new(metrics_registry),
         }
     }
 
-    /// Returns the genesis header that the store is initialized with.
+    /// Returns the genesis header that the store is initialized with. When syncing from an
+    /// assumed-valid checkpoint, this is the checkpointed header.
     pub fn genesis(&self) -> &BlockHeader {
         &self.genesis_block_header
     }
@@ -179,7 +199,14 @@ impl BlockchainState {
             return Ok(AddHeaderResult::HeaderAlreadyExists);
         }
 
-        if let Err(err) = validate_header(&self.network, self, &header) {
+        if let Some(parent) = self.header_cache.get(&header.prev_blockhash) {
+            let height = parent.height + 1;
+            if matches!(self.checkpoints.get(&height), Some(hash) if *hash != block_hash) {
+                return Err(AddHeaderError::CheckpointMismatch(block_hash, height));
+            }
+        }
+
+        if let Err(err) = self.chain_params.validate_header(self, &header) {
             return Err(AddHeaderError::InvalidHeader(block_hash, err));
         }
 
@@ -349,11 +376,11 @@ impl HeaderStore for BlockchainState {
 
 #[cfg(test)]
 mod test {
-    use bitcoin::TxMerkleNode;
+    use bitcoin::{blockdata::constants::genesis_block, Network, TxMerkleNode};
     use ic_metrics::MetricsRegistry;
 
     use super::*;
-    use crate::{common::test_common::TestState, config::test::ConfigBuilder};
+    use crate::{chain::Chain, common::test_common::TestState, config::test::ConfigBuilder};
     use ic_btc_adapter_test_utils::{block_1, block_2, generate_header, generate_headers};
     use std::collections::HashSet;
 
@@ -707,4 +734,132 @@ mod test {
             }
         }
     }
+
+    /// Mines a Litecoin header on top of `prev_header` by solving its scrypt proof of work.
+    fn mine_scrypt_header(
+        chain_params: &ChainParams,
+        prev_header: &BlockHeader,
+        bits: u32,
+    ) -> BlockHeader {
+        let mut header = BlockHeader {
+            version: 1,
+            prev_blockhash: prev_header.block_hash(),
+            merkle_root: prev_header.merkle_root,
+            time: prev_header.time + 150,
+            bits,
+            nonce: 0,
+        };
+        while chain_params.pow_hash(&header) > header.target() {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    /// Tests that headers of a scrypt chain are validated with the chain's
+    /// proof-of-work algorithm and difficulty rules.
+    #[test]
+    fn test_adding_litecoin_regtest_headers() {
+        let config = ConfigBuilder::new()
+            .with_chain(Chain::Litecoin)
+            .with_network(Network::Regtest)
+            .build();
+        let chain_params = config.chain_params();
+        let mut state = BlockchainState::new(&config, &MetricsRegistry::default());
+        assert_eq!(*state.genesis(), chain_params.genesis_header);
+
+        let mut chain = vec![];
+        let mut prev_header = *state.genesis();
+        for _ in 0..5 {
+            let header = mine_scrypt_header(&chain_params, &prev_header, prev_header.bits);
+            chain.push(header);
+            prev_header = header;
+        }
+
+        let (added_headers, maybe_err) = state.add_headers(&chain);
+        assert!(maybe_err.is_none());
+        assert_eq!(added_headers.len(), 5);
+        assert_eq!(state.get_active_chain_tip().height, 5);
+
+        // Regtest does not retarget, so a header with different bits is rejected.
+        let header = mine_scrypt_header(&chain_params, &prev_header, 0x1f7fffff);
+        let (added_headers, maybe_err) = state.add_headers(&[header]);
+        assert!(added_headers.is_empty());
+        assert!(matches!(
+            maybe_err,
+            Some(AddHeaderError::InvalidHeader(
+                _,
+                ValidateHeaderError::InvalidPoWForComputedTarget
+            ))
+        ));
+    }
+
+    /// Tests that the header chain can be synced from an assumed-valid checkpoint.
+    #[test]
+    fn test_sync_from_assume_valid_checkpoint() {
+        let genesis = genesis_block(Network::Regtest).header;
+        let chain = generate_headers(genesis.block_hash(), genesis.time, 20, &[]);
+        let checkpoint = HeaderCheckpoint {
+            height: 10,
+            header: chain[9],
+        };
+        let config = ConfigBuilder::new()
+            .with_network(Network::Regtest)
+            .with_checkpoints(vec![checkpoint])
+            .with_assume_valid_height(15)
+            .build();
+        let mut state = BlockchainState::new(&config, &MetricsRegistry::default());
+        assert_eq!(*state.genesis(), chain[9]);
+        assert_eq!(state.get_active_chain_tip().height, 10);
+
+        // Headers below the checkpoint are never added.
+        let (added_headers, maybe_err) = state.add_headers(&chain[..9]);
+        assert!(added_headers.is_empty());
+        assert!(maybe_err.is_some());
+
+        let (added_headers, maybe_err) = state.add_headers(&chain[10..]);
+        assert!(maybe_err.is_none());
+        assert_eq!(added_headers.len(), 10);
+        let tip = state.get_active_chain_tip();
+        assert_eq!(tip.height, 20);
+        assert_eq!(tip.header, chain[19]);
+        assert_eq!(
+            *state.locator_hashes().last().unwrap(),
+            chain[9].block_hash()
+        );
+    }
+
+    /// Tests that headers conflicting with a checkpoint are rejected.
+    #[test]
+    fn test_headers_conflicting_with_checkpoint_are_rejected() {
+        let genesis = genesis_block(Network::Regtest).header;
+        let chain = generate_headers(genesis.block_hash(), genesis.time, 12, &[]);
+        let checkpoint = HeaderCheckpoint {
+            height: 12,
+            header: chain[11],
+        };
+        let config = ConfigBuilder::new()
+            .with_network(Network::Regtest)
+            .with_checkpoints(vec![checkpoint])
+            .build();
+        let mut state = BlockchainState::new(&config, &MetricsRegistry::default());
+        assert_eq!(*state.genesis(), genesis);
+
+        let (added_headers, maybe_err) = state.add_headers(&chain[..11]);
+        assert!(maybe_err.is_none());
+        assert_eq!(added_headers.len(), 11);
+
+        // The checkpoint is checked before the header is validated.
+        let mut fork = chain[11];
+        fork.nonce += 1;
+        let (added_headers, maybe_err) = state.add_headers(&[fork]);
+        assert!(added_headers.is_empty());
+        assert!(matches!(
+            maybe_err,
+            Some(AddHeaderError::CheckpointMismatch(hash, 12)) if hash == fork.block_hash()
+        ));
+
+        let (added_headers, maybe_err) = state.add_headers(&chain[11..]);
+        assert!(maybe_err.is_none());
+        assert_eq!(added_headers, vec![chain[11].block_hash()]);
+    }
 }
@@ -0,0 +1,786 @@
+use bitcoin::{
+    bech32::{self, FromBase32},
+    blockdata::script::Builder,
+    hashes::{hex::FromHex, Hash},
+    util::{base58, uint::Uint256},
+    BlockHash, BlockHeader, Network, PubkeyHash, Script, ScriptHash, TxMerkleNode,
+};
+use ic_btc_validation::{validate_header, HeaderStore, ValidateHeaderError};
+use serde::{Deserialize, Serialize};
+
+/// The merkle root of the Litecoin genesis block, shared by all Litecoin networks.
+const LITECOIN_GENESIS_MERKLE_ROOT: &str =
+    "97ddfbbae6be97fd6cdf3e7ca13232a3afff2353e29badfab7f73011edd4ced9";
+
+/// The merkle root of the Dogecoin genesis block, shared by all Dogecoin networks.
+const DOGECOIN_GENESIS_MERKLE_ROOT: &str =
+    "5b2a3f53f605d62c53e62932dac6925e3d74afa5a4b459745c36d42d0ed26a69";
+
+/// The compact proof-of-work limit of scrypt main and test networks.
+const SCRYPT_POW_LIMIT_BITS: u32 = 0x1e0fffff;
+
+/// The compact proof-of-work limit of regtest-style networks.
+const REGTEST_POW_LIMIT_BITS: u32 = 0x207fffff;
+
+/// The number of headers between two Bitcoin or Litecoin difficulty adjustments.
+const DIFFICULTY_ADJUSTMENT_INTERVAL: u32 = 2016;
+
+/// The expected time between two Litecoin blocks, in seconds.
+const LITECOIN_POW_TARGET_SPACING: u32 = 150;
+
+/// The expected duration of a Litecoin difficulty adjustment period, in seconds.
+const LITECOIN_POW_TARGET_TIMESPAN: u32 =
+    DIFFICULTY_ADJUSTMENT_INTERVAL * LITECOIN_POW_TARGET_SPACING;
+
+/// The number of past headers used to compute the median time past.
+const MEDIAN_TIME_SPAN: usize = 11;
+
+/// The UTXO chain that the adapter follows. Each chain is combined with a
+/// [Network] that selects its main network (`bitcoin`), test network
+/// (`testnet`) or regtest network (`regtest`).
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Chain {
+    /// The Bitcoin chain.
+    #[default]
+    Bitcoin,
+    /// The Litecoin chain.
+    Litecoin,
+    /// The Dogecoin chain.
+    ///
+    /// The adapter can only follow the regtest network: Dogecoin mainnet
+    /// and testnet headers are merge-mined (AuxPoW), which the adapter cannot
+    /// parse, and use the DigiShield difficulty adjustment.
+    Dogecoin,
+}
+
+/// The hash function that block headers are mined with.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum PowAlgorithm {
+    /// Double SHA-256 of the header, which is also the block hash.
+    Sha256d,
+    /// Scrypt with N = 1024, r = 1, p = 1, using the header as password and salt.
+    Scrypt,
+}
+
+/// The address encoding of a chain.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct AddressFormat {
+    /// The version byte of base58 pay-to-public-key-hash addresses.
+    pub p2pkh_prefix: u8,
+    /// The version byte of base58 pay-to-script-hash addresses.
+    pub p2sh_prefix: u8,
+    /// The human-readable part of segwit addresses, if the chain supports segwit.
+    pub bech32_hrp: Option<&'static str>,
+}
+
+/// The parameters that make a UTXO chain distinct from Bitcoin as far as the
+/// adapter is concerned.
+#[derive(Clone, Debug)]
+pub struct ChainParams {
+    /// The chain.
+    pub chain: Chain,
+    /// The network of the chain.
+    pub network: Network,
+    /// The magic value that starts every P2P message.
+    pub magic: u32,
+    /// The header of the genesis block.
+    pub genesis_header: BlockHeader,
+    /// The default P2P port.
+    pub default_port: u16,
+    /// The hash function of the proof of work.
+    pub pow_algorithm: PowAlgorithm,
+    /// The easiest target that a header may have, in compact form.
+    pub pow_limit_bits: u32,
+    /// Whether the difficulty stays the same for all blocks.
+    pub pow_no_retargeting: bool,
+    /// Whether a block may have the easiest target if it is mined long
+    /// enough after its parent, as on test networks.
+    pub pow_allow_min_difficulty_blocks: bool,
+    /// The address encoding.
+    pub address_format: AddressFormat,
+}
+
+impl ChainParams {
+    /// Returns the parameters of the given network of the given chain, or an
+    /// error if the chain does not have such a network.
+    pub fn new(chain: Chain, network: Network) -> Result<Self, String> {
+        match chain {
+            Chain::Bitcoin => Ok(bitcoin_params(network)),
+            Chain::Litecoin => litecoin_params(network),
+            Chain::Dogecoin => dogecoin_params(network),
+        }
+    }
+
+    /// Validates a header against its ancestors in the `store`.
+    ///
+    /// Bitcoin headers are validated with the full Bitcoin rules, including
+    /// difficulty adjustments and checkpoints. For other chains, the adapter
+    /// checks the timestamp, the proof of work, the proof-of-work limit and
+    /// that the target matches the difficulty adjustment rules of the chain.
+    pub fn validate_header(
+        &self,
+        store: &impl HeaderStore,
+        header: &BlockHeader,
+    ) -> Result<(), ValidateHeaderError> {
+        if self.chain == Chain::Bitcoin {
+            return validate_header(&self.network, store, header);
+        }
+
+        let (prev_header, prev_height) = store
+            .get_header(&header.prev_blockhash)
+            .ok_or(ValidateHeaderError::PrevHeaderNotFound)?;
+
+        if header.time <= median_time_past(store, &prev_header) {
+            return Err(ValidateHeaderError::HeaderIsOld);
+        }
+
+        let target = header.target();
+        if target > BlockHeader::u256_from_compact_target(self.pow_limit_bits) {
+            return Err(ValidateHeaderError::TargetDifficultyAboveMax);
+        }
+
+        let expected_bits = match self.chain {
+            _ if self.pow_no_retargeting => prev_header.bits,
+            Chain::Litecoin => self.litecoin_next_bits(store, &prev_header, prev_height, header)?,
+            Chain::Bitcoin | Chain::Dogecoin => {
+                unreachable!("bug: {:?} {:?} must not retarget", self.chain, self.network)
+            }
+        };
+        if header.bits != expected_bits {
+            return Err(ValidateHeaderError::InvalidPoWForComputedTarget);
+        }
+
+        if self.pow_hash(header) > target {
+            return Err(ValidateHeaderError::InvalidPoWForHeaderTarget);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the compact target that a Litecoin header following
+    /// `prev_header` must have, as computed by `GetNextWorkRequired` in
+    /// Litecoin Core.
+    fn litecoin_next_bits(
+        &self,
+        store: &impl HeaderStore,
+        prev_header: &BlockHeader,
+        prev_height: u32,
+        header: &BlockHeader,
+    ) -> Result<u32, ValidateHeaderError> {
+        let height = prev_height + 1;
+
+        if height % DIFFICULTY_ADJUSTMENT_INTERVAL != 0 {
+            if !self.pow_allow_min_difficulty_blocks {
+                return Ok(prev_header.bits);
+            }
+            if header.time > prev_header.time + 2 * LITECOIN_POW_TARGET_SPACING {
+                return Ok(self.pow_limit_bits);
+            }
+            // Return the target of the last block that was not mined with the
+            // easiest target.
+            let (mut last_header, mut last_height) = (*prev_header, prev_height);
+            while last_height % DIFFICULTY_ADJUSTMENT_INTERVAL != 0
+                && last_header.bits == self.pow_limit_bits
+            {
+                match store.get_header(&last_header.prev_blockhash) {
+                    Some((parent, parent_height)) => {
+                        (last_header, last_height) = (parent, parent_height)
+                    }
+                    None => break,
+                }
+            }
+            return Ok(last_header.bits);
+        }
+
+        // Unlike Bitcoin, Litecoin measures the timespan over a full
+        // interval, except for the first difficulty adjustment.
+        let blocks_to_go_back = if height == DIFFICULTY_ADJUSTMENT_INTERVAL {
+            DIFFICULTY_ADJUSTMENT_INTERVAL - 1
+        } else {
+            DIFFICULTY_ADJUSTMENT_INTERVAL
+        };
+        let first_header = ancestor(store, prev_header, blocks_to_go_back)
+            .ok_or(ValidateHeaderError::PrevHeaderNotFound)?;
+
+        let timespan = (prev_header.time as i64 - first_header.time as i64).clamp(
+            (LITECOIN_POW_TARGET_TIMESPAN / 4) as i64,
+            (LITECOIN_POW_TARGET_TIMESPAN * 4) as i64,
+        ) as u32;
+
+        let pow_limit = BlockHeader::u256_from_compact_target(self.pow_limit_bits);
+        let mut target = prev_header.target();
+        // Litecoin Core shifts the target to avoid overflowing 256 bits.
+        let shift = target.bits() > pow_limit.bits() - 1;
+        if shift {
+            target = target >> 1;
+        }
+        target = target.mul_u32(timespan)
+            / Uint256::from_u64(LITECOIN_POW_TARGET_TIMESPAN as u64)
+                .expect("bug: the timespan fits into 256 bits");
+        if shift {
+            target = target << 1;
+        }
+        if target > pow_limit {
+            target = pow_limit;
+        }
+
+        Ok(BlockHeader::compact_target_from_u256(&target))
+    }
+
+    /// Returns the proof-of-work hash of the header as a number.
+    pub fn pow_hash(&self, header: &BlockHeader) -> Uint256 {
+        match self.pow_algorithm {
+            PowAlgorithm::Sha256d => le_bytes_to_uint256(&header.block_hash().into_inner()),
+            PowAlgorithm::Scrypt => {
+                let bytes = bitcoin::consensus::serialize(header);
+                let params = scrypt::Params::new(10, 1, 1, 32)
+                    .expect("bug: the scrypt parameters are valid");
+                let mut hash = [0u8; 32];
+                scrypt::scrypt(&bytes, &bytes, &params, &mut hash)
+                    .expect("bug: the scrypt output length is valid");
+                le_bytes_to_uint256(&hash)
+            }
+        }
+    }
+
+    /// Returns true if header validation can start from a header at the given
+    /// height without knowing its ancestors. Bitcoin headers need the first
+    /// header of the difficulty adjustment period to compute the next target,
+    /// so syncing must start at a difficulty adjustment. Litecoin headers
+    /// need the last header of the previous period instead.
+    pub fn can_sync_from_height(&self, height: u32) -> bool {
+        match self.chain {
+            _ if self.pow_no_retargeting => true,
+            Chain::Bitcoin => height % DIFFICULTY_ADJUSTMENT_INTERVAL == 0,
+            Chain::Litecoin => (height + 1) % DIFFICULTY_ADJUSTMENT_INTERVAL == 0,
+            Chain::Dogecoin => true,
+        }
+    }
+
+    /// Parses an address of the chain and returns the script that locks
+    /// outputs to it. Base58 P2PKH and P2SH addresses are accepted on all
+    /// chains, segwit addresses only on chains that support segwit.
+    pub fn address_to_script_pubkey(&self, address: &str) -> Result<Script, String> {
+        let format = &self.address_format;
+        if let Some(hrp) = format.bech32_hrp {
+            if let Some((prefix, _)) = address.rsplit_once('1') {
+                if prefix.eq_ignore_ascii_case(hrp) {
+                    return segwit_script_pubkey(address);
+                }
+            }
+        }
+
+        let payload = base58::from_check(address)
+            .map_err(|err| format!("invalid address {}: {}", address, err))?;
+        match payload.split_first() {
+            Some((prefix, hash)) if *prefix == format.p2pkh_prefix => {
+                let hash = PubkeyHash::from_slice(hash)
+                    .map_err(|_| format!("invalid P2PKH address {}", address))?;
+                Ok(Script::new_p2pkh(&hash))
+            }
+            Some((prefix, hash)) if *prefix == format.p2sh_prefix => {
+                let hash = ScriptHash::from_slice(hash)
+                    .map_err(|_| format!("invalid P2SH address {}", address))?;
+                Ok(Script::new_p2sh(&hash))
+            }
+            _ => Err(format!(
+                "address {} does not belong to {:?} {:?}",
+                address, self.chain, self.network
+            )),
+        }
+    }
+
+    /// Returns true if the header chain with the given height is past the
+    /// checkpoints of the chain. Only Bitcoin has checkpoints.
+    pub fn is_beyond_last_checkpoint(&self, height: u32) -> bool {
+        match self.chain {
+            Chain::Bitcoin => ic_btc_validation::is_beyond_last_checkpoint(&self.network, height),
+            Chain::Litecoin | Chain::Dogecoin => true,
+        }
+    }
+}
+
+fn bitcoin_params(network: Network) -> ChainParams {
+    ChainParams {
+        chain: Chain::Bitcoin,
+        network,
+        magic: network.magic(),
+        genesis_header: bitcoin::blockdata::constants::genesis_block(network).header,
+        default_port: match network {
+            Network::Bitcoin => 8333,
+            Network::Testnet => 18333,
+            _ => 8333,
+        },
+        pow_algorithm: PowAlgorithm::Sha256d,
+        pow_limit_bits: match network {
+            Network::Bitcoin | Network::Testnet => 0x1d00ffff,
+            Network::Signet => 0x1e0377ae,
+            Network::Regtest => REGTEST_POW_LIMIT_BITS,
+        },
+        pow_no_retargeting: network == Network::Regtest,
+        pow_allow_min_difficulty_blocks: matches!(network, Network::Testnet | Network::Regtest),
+        address_format: match network {
+            Network::Bitcoin => AddressFormat {
+                p2pkh_prefix: 0,
+                p2sh_prefix: 5,
+                bech32_hrp: Some("bc"),
+            },
+            Network::Testnet | Network::Signet => AddressFormat {
+                p2pkh_prefix: 111,
+                p2sh_prefix: 196,
+                bech32_hrp: Some("tb"),
+            },
+            Network::Regtest => AddressFormat {
+                p2pkh_prefix: 111,
+                p2sh_prefix: 196,
+                bech32_hrp: Some("bcrt"),
+            },
+        },
+    }
+}
+
+fn litecoin_params(network: Network) -> Result<ChainParams, String> {
+    let (magic, time, bits, nonce, default_port, pow_limit_bits) = match network {
+        Network::Bitcoin => (
+            0xdbb6c0fb,
+            1317972665,
+            0x1e0ffff0,
+            2084524493,
+            9333,
+            SCRYPT_POW_LIMIT_BITS,
+        ),
+        Network::Testnet => (
+            0xf1c8d2fd,
+            1486949366,
+            0x1e0ffff0,
+            293345,
+            19335,
+            SCRYPT_POW_LIMIT_BITS,
+        ),
+        Network::Regtest => (
+            0xdab5bffa,
+            1296688602,
+            REGTEST_POW_LIMIT_BITS,
+            0,
+            19444,
+            REGTEST_POW_LIMIT_BITS,
+        ),
+        Network::Signet => return Err("Litecoin does not have a signet network".to_string()),
+    };
+    Ok(ChainParams {
+        chain: Chain::Litecoin,
+        network,
+        magic,
+        genesis_header: genesis_header(LITECOIN_GENESIS_MERKLE_ROOT, time, bits, nonce),
+        default_port,
+        pow_algorithm: PowAlgorithm::Scrypt,
+        pow_limit_bits,
+        pow_no_retargeting: network == Network::Regtest,
+        pow_allow_min_difficulty_blocks: matches!(network, Network::Testnet | Network::Regtest),
+        address_format: AddressFormat {
+            p2pkh_prefix: if network == Network::Bitcoin { 48 } else { 111 },
+            p2sh_prefix: if network == Network::Bitcoin { 50 } else { 58 },
+            bech32_hrp: Some(match network {
+                Network::Bitcoin => "ltc",
+                Network::Testnet => "tltc",
+                _ => "rltc",
+            }),
+        },
+    })
+}
+
+fn dogecoin_params(network: Network) -> Result<ChainParams, String> {
+    if network != Network::Regtest {
+        return Err(
+            "the adapter can only follow the Dogecoin regtest network: Dogecoin mainnet and \
+             testnet headers are merge-mined (AuxPoW)"
+                .to_string(),
+        );
+    }
+    Ok(ChainParams {
+        chain: Chain::Dogecoin,
+        network,
+        magic: 0xdab5bffa,
+        genesis_header: genesis_header(
+            DOGECOIN_GENESIS_MERKLE_ROOT,
+            1296688602,
+            REGTEST_POW_LIMIT_BITS,
+            2,
+        ),
+        default_port: 18444,
+        pow_algorithm: PowAlgorithm::Scrypt,
+        pow_limit_bits: REGTEST_POW_LIMIT_BITS,
+        pow_no_retargeting: true,
+        pow_allow_min_difficulty_blocks: true,
+        // Dogecoin does not support segwit.
+        address_format: AddressFormat {
+            p2pkh_prefix: 111,
+            p2sh_prefix: 196,
+            bech32_hrp: None,
+        },
+    })
+}
+
+fn genesis_header(merkle_root: &str, time: u32, bits: u32, nonce: u32) -> BlockHeader {
+    BlockHeader {
+        version: 1,
+        prev_blockhash: BlockHash::default(),
+        merkle_root: TxMerkleNode::from_hex(merkle_root)
+            .expect("bug: the genesis merkle root is valid hex"),
+        time,
+        bits,
+        nonce,
+    }
+}
+
+/// Returns the script of a segwit address, checking the encoding required by
+/// its witness version (BIP-173 and BIP-350).
+fn segwit_script_pubkey(address: &str) -> Result<Script, String> {
+    let (_, data, variant) =
+        bech32::decode(address).map_err(|err| format!("invalid address {}: {}", address, err))?;
+    let (version, program) = data
+        .split_first()
+        .ok_or_else(|| format!("address {} has no witness version", address))?;
+    let version = version.to_u8();
+    let program = Vec::<u8>::from_base32(program)
+        .map_err(|err| format!("invalid address {}: {}", address, err))?;
+    let valid = match version {
+        0 => variant == bech32::Variant::Bech32 && matches!(program.len(), 20 | 32),
+        1..=16 => variant == bech32::Variant::Bech32m && (2..=40).contains(&program.len()),
+        _ => false,
+    };
+    if !valid {
+        return Err(format!("invalid witness program in address {}", address));
+    }
+    Ok(Builder::new()
+        .push_int(version as i64)
+        .push_slice(&program)
+        .into_script())
+}
+
+/// Returns the median timestamp of the given header and its ancestors, up to
+/// [MEDIAN_TIME_SPAN] headers.
+fn median_time_past(store: &impl HeaderStore, header: &BlockHeader) -> u32 {
+    let mut times = vec![header.time];
+    let mut prev_hash = header.prev_blockhash;
+    while times.len() < MEDIAN_TIME_SPAN {
+        match store.get_header(&prev_hash) {
+            Some((prev_header, _)) => {
+                times.push(prev_header.time);
+                prev_hash = prev_header.prev_blockhash;
+            }
+            None => break,
+        }
+    }
+    times.sort_unstable();
+    times.get(times.len() / 2).copied().unwrap_or_default()
+}
+
+/// Returns the ancestor of the given header that is `depth` headers below it.
+fn ancestor(store: &impl HeaderStore, header: &BlockHeader, depth: u32) -> Option<BlockHeader> {
+    let mut current = *header;
+    for _ in 0..depth {
+        current = store.get_header(&current.prev_blockhash)?.0;
+    }
+    Some(current)
+}
+
+/// Interprets 32 little-endian bytes as a 256-bit number.
+fn le_bytes_to_uint256(bytes: &[u8; 32]) -> Uint256 {
+    let mut words = [0u64; 4];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().expect("bug: chunks are 8 bytes long"));
+    }
+    Uint256(words)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Checks that the genesis headers hash to the published genesis block
+    /// hashes and satisfy their own proof of work.
+    #[test]
+    fn test_genesis_headers() {
+        let cases = [
+            (
+                Chain::Litecoin,
+                Network::Bitcoin,
+                "12a765e31ffd4059bada1e25190f6e98c99d9714d334efa41a195a7e7e04bfe2",
+            ),
+            (
+                Chain::Litecoin,
+                Network::Testnet,
+                "4966625a4b2851d9fdee139e56211a0d88575f59ed816ff5e6a63deb4e3e29a0",
+            ),
+            (
+                Chain::Litecoin,
+                Network::Regtest,
+                "530827f38f93b43ed12af0b3ad25a288dc02ed74d6d7857862df51fc56c416f9",
+            ),
+            (
+                Chain::Dogecoin,
+                Network::Regtest,
+                "3d2160a3b5dc4a9d62e7e66a295f70313ac808440ef7400d6c0772171ce973a5",
+            ),
+        ];
+        for (chain, network, expected_hash) in cases {
+            let params = ChainParams::new(chain, network).unwrap();
+            let header = params.genesis_header;
+            assert_eq!(
+                header.block_hash(),
+                BlockHash::from_hex(expected_hash).unwrap(),
+                "unexpected genesis hash for {:?} {:?}",
+                chain,
+                network
+            );
+            assert!(params.pow_hash(&header) <= header.target());
+        }
+    }
+
+    #[test]
+    fn test_bitcoin_params_match_rust_bitcoin() {
+        for network in [
+            Network::Bitcoin,
+            Network::Testnet,
+            Network::Signet,
+            Network::Regtest,
+        ] {
+            let params = ChainParams::new(Chain::Bitcoin, network).unwrap();
+            assert_eq!(params.magic, network.magic());
+            assert_eq!(
+                params.genesis_header,
+                bitcoin::blockdata::constants::genesis_block(network).header
+            );
+            assert_eq!(
+                params.pow_hash(&params.genesis_header),
+                le_bytes_to_uint256(&params.genesis_header.block_hash().into_inner())
+            );
+        }
+    }
+
+    #[test]
+    fn test_signet_is_bitcoin_only() {
+        assert!(ChainParams::new(Chain::Litecoin, Network::Signet).is_err());
+        assert!(ChainParams::new(Chain::Dogecoin, Network::Signet).is_err());
+    }
+
+    struct TestHeaderStore(HashMap<BlockHash, (BlockHeader, u32)>);
+
+    impl HeaderStore for TestHeaderStore {
+        fn get_header(&self, hash: &BlockHash) -> Option<(BlockHeader, u32)> {
+            self.0.get(hash).copied()
+        }
+
+        fn get_height(&self) -> u32 {
+            self.0
+                .values()
+                .map(|(_, height)| *height)
+                .max()
+                .unwrap_or_default()
+        }
+
+        fn get_initial_hash(&self) -> BlockHash {
+            self.0
+                .iter()
+                .find(|(_, (_, height))| *height == 0)
+                .map(|(hash, _)| *hash)
+                .unwrap()
+        }
+    }
+
+    /// Builds a chain of `len` headers on top of the genesis header, mined
+    /// `spacing` seconds apart with the bits returned by `bits_at(height)`.
+    fn build_chain(
+        params: &ChainParams,
+        len: u32,
+        spacing: u32,
+        bits_at: impl Fn(u32) -> u32,
+    ) -> (TestHeaderStore, BlockHeader, u32) {
+        let mut store = HashMap::new();
+        let mut tip = params.genesis_header;
+        store.insert(tip.block_hash(), (tip, 0));
+        for height in 1..=len {
+            tip = BlockHeader {
+                version: 1,
+                prev_blockhash: tip.block_hash(),
+                merkle_root: TxMerkleNode::default(),
+                time: tip.time + spacing,
+                bits: bits_at(height),
+                nonce: 0,
+            };
+            store.insert(tip.block_hash(), (tip, height));
+        }
+        (TestHeaderStore(store), tip, len)
+    }
+
+    fn next_header(prev_header: &BlockHeader, spacing: u32) -> BlockHeader {
+        BlockHeader {
+            version: 1,
+            prev_blockhash: prev_header.block_hash(),
+            merkle_root: TxMerkleNode::default(),
+            time: prev_header.time + spacing,
+            bits: 0,
+            nonce: 0,
+        }
+    }
+
+    #[test]
+    fn test_litecoin_retarget() {
+        const BITS: u32 = 0x1e0ffff0;
+        let params = ChainParams::new(Chain::Litecoin, Network::Bitcoin).unwrap();
+
+        // (chain length, block spacing, expected bits of the next header)
+        let cases = [
+            // No difficulty adjustment.
+            (100, 30, BITS),
+            // The first difficulty adjustment, blocks 5x faster than expected:
+            // the target is divided by at most 4.
+            (2015, 30, 0x1e03fffc),
+            // The first difficulty adjustment, blocks 10x slower than
+            // expected: the target is capped by the proof-of-work limit.
+            (2015, 1500, SCRYPT_POW_LIMIT_BITS),
+            // The timespan is measured over a full interval.
+            (4031, LITECOIN_POW_TARGET_SPACING, BITS),
+            (4031, LITECOIN_POW_TARGET_SPACING / 2, 0x1e07fff8),
+        ];
+        for (len, spacing, expected_bits) in cases {
+            let (store, tip, height) = build_chain(&params, len, spacing, |_| BITS);
+            assert_eq!(
+                params
+                    .litecoin_next_bits(&store, &tip, height, &next_header(&tip, spacing))
+                    .unwrap(),
+                expected_bits,
+                "unexpected bits after {} headers mined {}s apart",
+                len,
+                spacing
+            );
+        }
+    }
+
+    #[test]
+    fn test_litecoin_testnet_min_difficulty_blocks() {
+        const BITS: u32 = 0x1e0ffff0;
+        let params = ChainParams::new(Chain::Litecoin, Network::Testnet).unwrap();
+
+        // The last two headers were mined with the easiest target.
+        let (store, tip, height) = build_chain(&params, 10, 1_000, |height| {
+            if height > 8 {
+                SCRYPT_POW_LIMIT_BITS
+            } else {
+                BITS
+            }
+        });
+
+        // A header mined long after its parent may use the easiest target.
+        assert_eq!(
+            params
+                .litecoin_next_bits(&store, &tip, height, &next_header(&tip, 301))
+                .unwrap(),
+            SCRYPT_POW_LIMIT_BITS
+        );
+        // Otherwise, it uses the last target that was not the easiest one.
+        assert_eq!(
+            params
+                .litecoin_next_bits(&store, &tip, height, &next_header(&tip, 300))
+                .unwrap(),
+            BITS
+        );
+
+        // Main networks always use the target of the parent between
+        // difficulty adjustments.
+        let mainnet_params = ChainParams::new(Chain::Litecoin, Network::Bitcoin).unwrap();
+        assert_eq!(
+            mainnet_params
+                .litecoin_next_bits(&store, &tip, height, &next_header(&tip, 300))
+                .unwrap(),
+            tip.bits
+        );
+    }
+
+    #[test]
+    fn test_litecoin_sync_heights() {
+        let params = ChainParams::new(Chain::Litecoin, Network::Bitcoin).unwrap();
+        assert!(params.can_sync_from_height(2015));
+        assert!(params.can_sync_from_height(4031));
+        assert!(!params.can_sync_from_height(2016));
+
+        let params = ChainParams::new(Chain::Litecoin, Network::Regtest).unwrap();
+        assert!(params.can_sync_from_height(2016));
+    }
+
+    #[test]
+    fn test_dogecoin_is_regtest_only() {
+        assert!(ChainParams::new(Chain::Dogecoin, Network::Bitcoin).is_err());
+        assert!(ChainParams::new(Chain::Dogecoin, Network::Testnet).is_err());
+        assert!(ChainParams::new(Chain::Dogecoin, Network::Regtest).is_ok());
+    }
+
+    #[test]
+    fn test_address_to_script_pubkey() {
+        let p2pkh = |byte| Script::new_p2pkh(&PubkeyHash::from_slice(&[byte; 20]).unwrap());
+        let p2sh = |byte| Script::new_p2sh(&ScriptHash::from_slice(&[byte; 20]).unwrap());
+        let segwit = |version: i64, program: &[u8]| {
+            Builder::new()
+                .push_int(version)
+                .push_slice(program)
+                .into_script()
+        };
+
+        let litecoin = ChainParams::new(Chain::Litecoin, Network::Bitcoin).unwrap();
+        assert_eq!(
+            litecoin.address_to_script_pubkey("LKKG9A9a8n7CeHK8Nk7RdNZiCuHZW2U789"),
+            Ok(p2pkh(1))
+        );
+        assert_eq!(
+            litecoin.address_to_script_pubkey("M85mzKatcFu6fdDGdBuBwycETbijJW1fbT"),
+            Ok(p2sh(2))
+        );
+        assert_eq!(
+            litecoin.address_to_script_pubkey("ltc1qqszqgpqyqszqgpqyqszqgpqyqszqgpqys39sh2"),
+            Ok(segwit(0, &[4; 20]))
+        );
+        // Bitcoin addresses are not Litecoin addresses.
+        assert!(litecoin
+            .address_to_script_pubkey("16Jswqk47s9PUcyCc88MMVwzgvHPvtEpf")
+            .is_err());
+
+        let litecoin_regtest = ChainParams::new(Chain::Litecoin, Network::Regtest).unwrap();
+        assert_eq!(
+            litecoin_regtest.address_to_script_pubkey(
+                "rltc1pq5zs2pg9q5zs2pg9q5zs2pg9q5zs2pg9q5zs2pg9q5zs2pg9q5zs26xv5w"
+            ),
+            Ok(segwit(1, &[5; 32]))
+        );
+        // Witness version 0 programs must use the bech32 checksum.
+        assert!(litecoin_regtest
+            .address_to_script_pubkey(
+                "rltc1qq5zs2pg9q5zs2pg9q5zs2pg9q5zs2pg9q5zs2pg9q5zs2pg9q5zs43kffs"
+            )
+            .is_err());
+        assert!(litecoin_regtest
+            .address_to_script_pubkey("bcrt1qqszqgpqyqszqgpqyqszqgpqyqszqgpqyuza2rq")
+            .is_err());
+
+        let dogecoin = ChainParams::new(Chain::Dogecoin, Network::Regtest).unwrap();
+        assert_eq!(
+            dogecoin.address_to_script_pubkey("mfnsvtcCyP2gwYLXKPLksyiAr4MZ6ewZNw"),
+            Ok(p2pkh(3))
+        );
+        // Dogecoin does not support segwit.
+        assert!(dogecoin
+            .address_to_script_pubkey(
+                "rltc1pq5zs2pg9q5zs2pg9q5zs2pg9q5zs2pg9q5zs2pg9q5zs2pg9q5zs26xv5w"
+            )
+            .is_err());
+
+        let bitcoin_regtest = ChainParams::new(Chain::Bitcoin, Network::Regtest).unwrap();
+        assert_eq!(
+            bitcoin_regtest
+                .address_to_script_pubkey("bcrt1qqszqgpqyqszqgpqyqszqgpqyqszqgpqyuza2rq"),
+            Ok(segwit(0, &[4; 20]))
+        );
+    }
+}
@@ -1,5 +1,8 @@
 //! A parser for the command line flags and configuration file.
-use crate::config::{address_limits, Config};
+use crate::{
+    chain::ChainParams,
+    config::{address_limits, Config},
+};
 use clap::Parser;
 use http::Uri;
 use std::{fs::File, io, path::PathBuf};
@@ -31,6 +34,26 @@ impl Cli {
         let mut config: Config =
             serde_json::from_reader(file).map_err(|err| CliError::Deserialize(err.to_string()))?;
 
+        // Validate that the specified chain has the specified network.
+        let chain_params =
+            ChainParams::new(config.chain, config.network).map_err(CliError::Validation)?;
+
+        // Validate that the header chain can be synced from the assumed-valid checkpoint.
+        if config.assume_valid_height.is_some() {
+            let initial_header = config.initial_header();
+            if initial_header.height == 0 {
+                return Err(CliError::Validation(
+                    "assume_valid_height requires a checkpoint at or below it".to_string(),
+                ));
+            }
+            if !chain_params.can_sync_from_height(initial_header.height) {
+                return Err(CliError::Validation(format!(
+                    "Cannot sync headers from the checkpoint at height {}",
+                    initial_header.height
+                )));
+            }
+        }
+
         // Set the address limits based on the specified network.
         config.address_limits = address_limits(config.network);
 
@@ -54,7 +77,7 @@ impl Cli {
 #[cfg(test)]
 pub mod test {
     use super::*;
-    use crate::config::IncomingSource;
+    use crate::{chain::Chain, config::IncomingSource};
     use bitcoin::Network;
     use std::io::Write;
     use std::path::PathBuf;
@@ -99,6 +122,37 @@ pub mod test {
         "socks_proxy": "socks5.notaproxy.com"        
     }"#;
 
+    const LITECOIN_TESTNET_CONFIG: &str = r#"{
+        "chain": "litecoin",
+        "network": "testnet",
+        "dns_seeds": [
+            "testnet-seed.litecointools.com"
+        ]
+    }"#;
+
+    const LITECOIN_SIGNET_CONFIG: &str = r#"{
+        "chain": "litecoin",
+        "network": "signet"
+    }"#;
+
+    const MAINNET_CHECKPOINT_CONFIG: &str = r#"{
+        "network": "bitcoin",
+        "checkpoints": [
+            {
+                "height": 4032,
+                "header": {
+                    "version": 1,
+                    "prev_blockhash": "000000000000000000000000000000000000000000000000000000000000000a",
+                    "merkle_root": "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+                    "time": 1231006505,
+                    "bits": 486604799,
+                    "nonce": 2083236893
+                }
+            }
+        ],
+        "assume_valid_height": ASSUME_VALID_HEIGHT
+    }"#;
+
     #[test]
     fn test_cli_get_config_error_opening_file() {
         let cli = Cli {
@@ -180,4 +234,75 @@ pub mod test {
             IncomingSource::Path(PathBuf::from("/tmp/ic-btc-adapter.socket"))
         );
     }
+
+    #[test]
+    fn test_cli_get_config_good_litecoin_testnet_json() {
+        let mut tmpfile = NamedTempFile::new().expect("Failed to create tmp file");
+        writeln!(tmpfile, "{}", LITECOIN_TESTNET_CONFIG).expect("Failed to write to tmp file");
+        let cli = Cli {
+            config: tmpfile.path().to_owned(),
+        };
+        let config = cli.get_config().unwrap();
+        assert_eq!(config.chain, Chain::Litecoin);
+        assert_eq!(config.network, Network::Testnet);
+        assert_eq!(config.network_port(), 19335);
+    }
+
+    #[test]
+    fn test_cli_litecoin_signet_is_rejected() {
+        let mut tmpfile = NamedTempFile::new().expect("Failed to create tmp file");
+        writeln!(tmpfile, "{}", LITECOIN_SIGNET_CONFIG).expect("Failed to write to tmp file");
+        let cli = Cli {
+            config: tmpfile.path().to_owned(),
+        };
+        let error = cli.get_config().unwrap_err();
+        assert!(matches!(error, CliError::Validation(_)));
+    }
+
+    fn get_checkpoint_config(assume_valid_height: &str) -> Result<Config, CliError> {
+        let mut tmpfile = NamedTempFile::new().expect("Failed to create tmp file");
+        let config = MAINNET_CHECKPOINT_CONFIG.replace("ASSUME_VALID_HEIGHT", assume_valid_height);
+        writeln!(tmpfile, "{}", config).expect("Failed to write to tmp file");
+        let cli = Cli {
+            config: tmpfile.path().to_owned(),
+        };
+        cli.get_config()
+    }
+
+    #[test]
+    fn test_cli_get_config_assume_valid_checkpoint() {
+        let config = get_checkpoint_config("5000").unwrap();
+        assert_eq!(config.checkpoints.len(), 1);
+        assert_eq!(config.initial_header(), config.checkpoints[0]);
+
+        // Without an assumed-valid height, the header chain is synced from genesis.
+        let config = get_checkpoint_config("null").unwrap();
+        assert_eq!(config.initial_header().height, 0);
+        assert_eq!(
+            config.initial_header().header,
+            config.chain_params().genesis_header
+        );
+    }
+
+    #[test]
+    fn test_cli_assume_valid_height_without_checkpoint_is_rejected() {
+        let error = get_checkpoint_config("4031").unwrap_err();
+        assert!(
+            matches!(error, CliError::Validation(message) if message.contains("requires a checkpoint"))
+        );
+    }
+
+    #[test]
+    fn test_cli_checkpoint_not_at_difficulty_adjustment_is_rejected() {
+        let config = MAINNET_CHECKPOINT_CONFIG
+            .replace("4032", "4033")
+            .replace("ASSUME_VALID_HEIGHT", "5000");
+        let mut tmpfile = NamedTempFile::new().expect("Failed to create tmp file");
+        writeln!(tmpfile, "{}", config).expect("Failed to write to tmp file");
+        let cli = Cli {
+            config: tmpfile.path().to_owned(),
+        };
+        let error = cli.get_config().unwrap_err();
+        assert!(matches!(error, CliError::Validation(message) if message.contains("height 4033")));
+    }
 }
@@ -1,4 +1,8 @@
-use bitcoin::Network;
+use crate::{
+    chain::{Chain, ChainParams},
+    common::BlockHeight,
+};
+use bitcoin::{BlockHeader, Network};
 use ic_config::logger::Config as LoggerConfig;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
@@ -15,9 +19,21 @@ pub enum IncomingSource {
     Path(PathBuf),
 }
 
+/// A header that is known to be part of the chain at the given height.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct HeaderCheckpoint {
+    /// The height of the checkpointed header.
+    pub height: BlockHeight,
+    /// The checkpointed header.
+    pub header: BlockHeader,
+}
+
 /// This struct contains configuration options for the BTC Adapter.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
+    /// The UTXO chain we plan to communicate to (e.g. Bitcoin, Litecoin, etc.).
+    #[serde(default)]
+    pub chain: Chain,
     /// The type of Bitcoin network we plan to communicate to (e.g. Mainnet, Testnet, etc.).
     pub network: Network,
     /// A list of DNS seeds for address discovery.
@@ -49,6 +65,16 @@ pub struct Config {
     /// Specifies the address limits used by the `AddressBook`.
     #[serde(default)]
     pub address_limits: (usize, usize),
+    /// Headers that the adapter's header chain must contain. Headers conflicting
+    /// with a checkpoint are rejected.
+    #[serde(default)]
+    pub checkpoints: Vec<HeaderCheckpoint>,
+    /// When set, the adapter starts syncing headers from the highest checkpoint at
+    /// or below this height instead of from the genesis header. That checkpoint and
+    /// its ancestors are assumed to be valid and are never downloaded, so only
+    /// `get_successors` requests anchored at or above it can be served.
+    #[serde(default)]
+    pub assume_valid_height: Option<BlockHeight>,
 }
 
 /// Set the default idle seconds to one hour.
@@ -68,13 +94,32 @@ pub(crate) fn address_limits(network: Network) -> (usize, usize) {
 }
 
 impl Config {
-    /// This function returns the port to use based on the Bitcoin network provided.
+    /// This function returns the parameters of the configured chain and network.
+    /// The combination is validated when the config is loaded, see [crate::cli].
+    pub fn chain_params(&self) -> ChainParams {
+        ChainParams::new(self.chain, self.network).expect("invalid chain and network combination")
+    }
+
+    /// This function returns the header that the header chain is synced from: the
+    /// highest checkpoint at or below `assume_valid_height`, or the genesis header.
+    pub fn initial_header(&self) -> HeaderCheckpoint {
+        self.assume_valid_height
+            .and_then(|assume_valid_height| {
+                self.checkpoints
+                    .iter()
+                    .filter(|checkpoint| checkpoint.height <= assume_valid_height)
+                    .max_by_key(|checkpoint| checkpoint.height)
+                    .copied()
+            })
+            .unwrap_or_else(|| HeaderCheckpoint {
+                height: 0,
+                header: self.chain_params().genesis_header,
+            })
+    }
+
+    /// This function returns the port to use based on the chain and network provided.
     pub fn network_port(&self) -> u16 {
-        match self.network {
-            Network::Bitcoin => 8333,
-            Network::Testnet => 18333,
-            _ => 8333,
-        }
+        self.chain_params().default_port
     }
 }
 
@@ -82,6 +127,7 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             dns_seeds: Default::default(),
+            chain: Chain::Bitcoin,
             network: Network::Bitcoin,
             socks_proxy: Default::default(),
             nodes: vec![],
@@ -90,6 +136,8 @@ impl Default for Config {
             logger: LoggerConfig::default(),
             incoming_source: Default::default(),
             address_limits: address_limits(Network::Bitcoin), // Address limits used for Bitcoin mainnet
+            checkpoints: vec![],
+            assume_valid_height: None,
         }
     }
 }
@@ -121,12 +169,27 @@ pub mod test {
             self
         }
 
+        pub fn with_chain(mut self, chain: Chain) -> Self {
+            self.config.chain = chain;
+            self
+        }
+
         pub fn with_network(mut self, network: Network) -> Self {
             self.config.network = network;
             self.config.address_limits = address_limits(network);
             self
         }
 
+        pub fn with_checkpoints(mut self, checkpoints: Vec<HeaderCheckpoint>) -> Self {
+            self.config.checkpoints = checkpoints;
+            self
+        }
+
+        pub fn with_assume_valid_height(mut self, assume_valid_height: BlockHeight) -> Self {
+            self.config.assume_valid_height = Some(assume_valid_height);
+            self
+        }
+
         pub fn with_ipv6_only(mut self, ipv6_only: bool) -> Self {
             self.config.ipv6_only = ipv6_only;
             self
@@ -122,7 +122,7 @@ impl ConnectionManager {
             initial_address_discovery: !address_book.has_enough_addresses(),
             address_book,
             logger,
-            magic: config.network.magic(),
+            magic: config.chain_params().magic,
             max_connections,
             min_connections,
             current_height: 0,
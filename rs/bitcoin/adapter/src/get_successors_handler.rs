@@ -4,14 +4,16 @@ use std::{
 };
 
 use bitcoin::{Block, BlockHash, BlockHeader, Network};
-use ic_btc_validation::is_beyond_last_checkpoint;
 use ic_metrics::MetricsRegistry;
 use tokio::sync::{mpsc::Sender, Mutex};
 use tonic::{Code, Status};
 
 use crate::{
-    common::BlockHeight, config::Config, metrics::GetSuccessorMetrics, BlockchainManagerRequest,
-    BlockchainState,
+    chain::{Chain, ChainParams},
+    common::BlockHeight,
+    config::Config,
+    metrics::GetSuccessorMetrics,
+    BlockchainManagerRequest, BlockchainState,
 };
 
 // Max size of the `GetSuccessorsResponse` message.
@@ -62,7 +64,7 @@ pub struct GetSuccessorsResponse {
 pub struct GetSuccessorsHandler {
     state: Arc<Mutex<BlockchainState>>,
     blockchain_manager_tx: Sender<BlockchainManagerRequest>,
-    network: Network,
+    chain_params: ChainParams,
     metrics: GetSuccessorMetrics,
 }
 
@@ -78,7 +80,7 @@ impl GetSuccessorsHandler {
         Self {
             state,
             blockchain_manager_tx,
-            network: config.network,
+            chain_params: config.chain_params(),
             metrics: GetSuccessorMetrics::new(metrics_registry),
         }
     }
@@ -105,14 +107,21 @@ impl GetSuccessorsHandler {
 
             // Wait with downloading blocks until we synced the header chain above the last checkpoint
             // to make sure we are following the correct chain.
-            if !is_beyond_last_checkpoint(&self.network, state.get_active_chain_tip().height) {
+            if !self
+                .chain_params
+                .is_beyond_last_checkpoint(state.get_active_chain_tip().height)
+            {
                 return Err(Status::new(
                     Code::Unavailable,
                     "Header chain not yet synced past last checkpoint",
                 ));
             }
 
-            let allow_multiple_blocks = are_multiple_blocks_allowed(self.network, anchor_height);
+            let allow_multiple_blocks = are_multiple_blocks_allowed(
+                self.chain_params.chain,
+                self.chain_params.network,
+                anchor_height,
+            );
             let blocks = get_successor_blocks(
                 &state,
                 &request.anchor,
@@ -246,10 +255,13 @@ fn get_next_headers(
 }
 
 /// Helper used to determine if multiple blocks should be returned.
-fn are_multiple_blocks_allowed(network: Network, anchor_height: BlockHeight) -> bool {
-    match network {
-        Network::Bitcoin => anchor_height <= MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT,
-        Network::Testnet | Network::Signet | Network::Regtest => true,
+/// Only Bitcoin mainnet blocks are large enough to be limited to one per response.
+fn are_multiple_blocks_allowed(chain: Chain, network: Network, anchor_height: BlockHeight) -> bool {
+    match (chain, network) {
+        (Chain::Bitcoin, Network::Bitcoin) => {
+            anchor_height <= MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT
+        }
+        _ => true,
     }
 }
 
@@ -799,40 +811,56 @@ mod test {
     fn test_are_multiple_blocks_allowed() {
         // Mainnet
         assert!(
-            are_multiple_blocks_allowed(Network::Bitcoin, 100_500),
+            are_multiple_blocks_allowed(Chain::Bitcoin, Network::Bitcoin, 100_500),
             "Multiple blocks are allowed at 100_500"
         );
         assert!(
-            are_multiple_blocks_allowed(Network::Bitcoin, MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT),
+            are_multiple_blocks_allowed(
+                Chain::Bitcoin,
+                Network::Bitcoin,
+                MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT
+            ),
             "Multiple blocks are allowed at {}",
             MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT
         );
         assert!(
-            !are_multiple_blocks_allowed(Network::Bitcoin, 900_000),
+            !are_multiple_blocks_allowed(Chain::Bitcoin, Network::Bitcoin, 900_000),
             "Multiple blocks are not allowed at 900_000"
         );
 
         // Testnet
         assert!(
-            are_multiple_blocks_allowed(Network::Testnet, 1_000_000),
+            are_multiple_blocks_allowed(Chain::Bitcoin, Network::Testnet, 1_000_000),
             "Multiple blocks are allowed at 1_000_000"
         );
         assert!(
-            are_multiple_blocks_allowed(Network::Testnet, u32::MAX),
+            are_multiple_blocks_allowed(Chain::Bitcoin, Network::Testnet, u32::MAX),
             "Multiple blocks are allowed at {}",
             u32::MAX
         );
 
         // Regtest
         assert!(
-            are_multiple_blocks_allowed(Network::Regtest, 1),
+            are_multiple_blocks_allowed(Chain::Bitcoin, Network::Regtest, 1),
             "Multiple blocks are allowed at 1"
         );
         assert!(
-            are_multiple_blocks_allowed(Network::Regtest, u32::MAX),
+            are_multiple_blocks_allowed(Chain::Bitcoin, Network::Regtest, u32::MAX),
             "Multiple blocks are allowed at {}",
             u32::MAX
         );
+
+        // Other chains
+        assert!(
+            are_multiple_blocks_allowed(Chain::Litecoin, Network::Bitcoin, u32::MAX),
+            "Multiple blocks are allowed on Litecoin mainnet at {}",
+            u32::MAX
+        );
+        assert!(
+            are_multiple_blocks_allowed(Chain::Dogecoin, Network::Regtest, u32::MAX),
+            "Multiple blocks are allowed on Dogecoin regtest at {}",
+            u32::MAX
+        );
     }
 
     #[test]
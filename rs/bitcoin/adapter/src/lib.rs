@@ -21,6 +21,9 @@ mod addressbook;
 mod blockchainmanager;
 /// This module contains the data structure for storing the current state of the Bitcoin ledger
 mod blockchainstate;
+/// This module contains the consensus parameters of the supported UTXO chains
+/// (Bitcoin, Litecoin and Dogecoin).
+pub mod chain;
 /// This module contains command line arguments parser.
 pub mod cli;
 /// This module contains constants and types that are shared by many modules.
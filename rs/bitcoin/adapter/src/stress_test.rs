@@ -1,6 +1,6 @@
 use std::{convert::TryFrom, path::PathBuf, time::Duration};
 
-use bitcoin::{blockdata::constants::genesis_block, consensus::Decodable, Block, BlockHash};
+use bitcoin::{consensus::Decodable, Block, BlockHash};
 use clap::Parser;
 use ic_btc_service::{
     btc_service_client::BtcServiceClient, BtcServiceGetSuccessorsRequest,
@@ -42,10 +42,10 @@ async fn main() {
     let interval_sleep_ms = Duration::from_millis(1000);
     let request_timeout_ms = Duration::from_millis(50);
 
-    let block_0 = genesis_block(config.network);
+    let genesis_header = config.chain_params().genesis_header;
     let mut total_processed_block_hashes: usize = 0;
     let mut processed_block_hashes: Vec<BlockHash> = vec![];
-    let mut current_anchor = block_0.block_hash();
+    let mut current_anchor = genesis_header.block_hash();
     let mut rpc_client = setup_client(uds_path).await;
     let total_timer = Instant::now();
 
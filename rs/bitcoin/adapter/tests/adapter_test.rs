@@ -2,6 +2,7 @@ use bitcoin::{consensus::encode::deserialize, Address, Amount, Block, BlockHash}
 use bitcoincore_rpc::{bitcoincore_rpc_json::CreateRawTransactionInput, Auth, Client, RpcApi};
 use bitcoind::{BitcoinD, Conf, P2P};
 use ic_btc_adapter::{
+    chain::Chain,
     config::{Config, IncomingSource},
     start_grpc_server_and_router, AdapterState,
 };
@@ -59,6 +60,7 @@ fn make_get_successors_request(
         network: Network::Regtest,
         anchor,
         processed_block_hashes: headers,
+        chain: None,
     });
 
     adapter_client.send_blocking(
@@ -76,6 +78,7 @@ fn make_send_tx_request(
     let request = BitcoinAdapterRequestWrapper::SendTransactionRequest(SendTransactionRequest {
         network: Network::Regtest,
         transaction: raw_tx.to_vec(),
+        chain: None,
     });
 
     adapter_client.send_blocking(
@@ -91,9 +94,11 @@ async fn start_adapter(
     metrics_registry: MetricsRegistry,
     nodes: Vec<SocketAddr>,
     uds_path: &Path,
+    chain: Chain,
     network: bitcoin::Network,
 ) {
     let config = Config {
+        chain,
         network,
         incoming_source: IncomingSource::Path(uds_path.to_path_buf()),
         nodes,
@@ -120,6 +125,18 @@ fn get_default_bitcoind() -> BitcoinD {
     bitcoind::BitcoinD::with_conf(path, &conf).unwrap()
 }
 
+/// Starts a Litecoin Core node in regtest mode. Litecoin Core accepts the
+/// command line and RPC calls that `bitcoind` uses to start Bitcoin Core.
+fn get_default_litecoind() -> BitcoinD {
+    let mut conf = Conf::default();
+    conf.p2p = P2P::Yes;
+
+    let path =
+        std::env::var("LITECOIN_CORE_PATH").expect("Failed to get litecoin core path env variable");
+
+    bitcoind::BitcoinD::with_conf(path, &conf).unwrap()
+}
+
 async fn start_client(
     metrics_registry: MetricsRegistry,
     logger: ReplicaLogger,
@@ -127,11 +144,7 @@ async fn start_client(
 ) -> BitcoinAdapterClient {
     let adapters_config = AdaptersConfig {
         bitcoin_mainnet_uds_path: Some(uds_path.into()),
-        bitcoin_mainnet_uds_metrics_path: None,
-        bitcoin_testnet_uds_path: None,
-        bitcoin_testnet_uds_metrics_path: None,
-        https_outcalls_uds_path: None,
-        https_outcalls_uds_metrics_path: None,
+        ..Default::default()
     };
 
     setup_bitcoin_adapter_clients(
@@ -165,6 +178,16 @@ fn start_adapter_and_client(
     urls: Vec<SocketAddr>,
     logger: ReplicaLogger,
     network: bitcoin::Network,
+) -> (BitcoinAdapterClient, TempPath) {
+    start_chain_adapter_and_client(rt, urls, logger, Chain::Bitcoin, network)
+}
+
+fn start_chain_adapter_and_client(
+    rt: &Runtime,
+    urls: Vec<SocketAddr>,
+    logger: ReplicaLogger,
+    chain: Chain,
+    network: bitcoin::Network,
 ) -> (BitcoinAdapterClient, TempPath) {
     Builder::new()
         .make(|uds_path| {
@@ -176,6 +199,7 @@ fn start_adapter_and_client(
                     metrics_registry.clone(),
                     urls.clone(),
                     uds_path,
+                    chain,
                     network,
                 )
                 .await;
@@ -501,6 +525,47 @@ fn test_receives_blocks() {
     assert_eq!(blocks.len(), 150);
 }
 
+/// Checks that the adapter follows a Litecoin regtest node: it connects with
+/// the Litecoin magic and starts from the Litecoin genesis block.
+#[test]
+#[ignore = "requires a Litecoin Core binary in LITECOIN_CORE_PATH"]
+fn test_receives_litecoin_blocks() {
+    let logger = no_op_logger();
+    let litecoind = get_default_litecoind();
+    let client = Client::new(
+        litecoind.rpc_url().as_str(),
+        Auth::CookieFile(litecoind.params.cookie_file.clone()),
+    )
+    .unwrap();
+
+    assert_eq!(0, client.get_block_count().unwrap());
+
+    // Litecoin addresses cannot be parsed as Bitcoin addresses, so the RPC
+    // calls that involve addresses are made without the typed helpers.
+    let address: String = client.call("getnewaddress", &[]).unwrap();
+    let _: Vec<BlockHash> = client
+        .call("generatetoaddress", &[150.into(), address.into()])
+        .unwrap();
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+
+    let (adapter_client, _path) = start_chain_adapter_and_client(
+        &rt,
+        vec![SocketAddr::V4(get_bitcoind_url(&litecoind).unwrap())],
+        logger,
+        Chain::Litecoin,
+        bitcoin::Network::Regtest,
+    );
+
+    let blocks = sync_until_end_block(&adapter_client, &client, 0, &mut vec![], 15);
+
+    assert_eq!(blocks.len(), 150);
+    assert_eq!(
+        blocks[0].header.prev_blockhash,
+        client.get_block_hash(0).unwrap()
+    );
+}
+
 /// Checks that the adapter can connect to multiple BitcoinD peers.
 #[test]
 fn test_connection_to_multiple_peers() {
@@ -1,6 +1,7 @@
 use candid::{CandidType, Deserialize, Principal};
 use ic_agent::Agent;
 use ic_canisters_http_types::{HttpRequest, HttpResponse};
+use ic_ckbtc_minter::address::AddressType;
 use ic_ckbtc_minter::queries::RetrieveBtcStatusRequest;
 use ic_ckbtc_minter::state::eventlog::{Event, GetEventsArg};
 use ic_ckbtc_minter::state::RetrieveBtcStatus;
@@ -9,6 +10,7 @@ use ic_ckbtc_minter::updates::{
     retrieve_btc::{RetrieveBtcArgs, RetrieveBtcError, RetrieveBtcOk},
     update_balance::{UpdateBalanceArgs, UpdateBalanceError, UtxoStatus},
 };
+use ic_ckbtc_minter::{MinterInfo, MinterPublicKeys};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use std::collections::BTreeMap;
 
@@ -81,8 +83,32 @@ impl CkBtcMinterAgent {
         owner: Option<Principal>,
         subaccount: Option<Subaccount>,
     ) -> Result<String, CkBtcMinterAgentError> {
-        self.update("get_btc_address", GetBtcAddressArgs { owner, subaccount })
-            .await
+        self.update(
+            "get_btc_address",
+            GetBtcAddressArgs {
+                owner,
+                subaccount,
+                address_type: None,
+            },
+        )
+        .await
+    }
+
+    pub async fn get_btc_address_of_type(
+        &self,
+        owner: Option<Principal>,
+        subaccount: Option<Subaccount>,
+        address_type: AddressType,
+    ) -> Result<String, CkBtcMinterAgentError> {
+        self.update(
+            "get_btc_address",
+            GetBtcAddressArgs {
+                owner,
+                subaccount,
+                address_type: Some(address_type),
+            },
+        )
+        .await
     }
 
     pub async fn get_withdrawal_account(&self) -> Result<Account, CkBtcMinterAgentError> {
@@ -127,6 +153,14 @@ impl CkBtcMinterAgent {
             .await
     }
 
+    pub async fn get_minter_info(&self) -> Result<MinterInfo, CkBtcMinterAgentError> {
+        self.query("get_minter_info", ()).await
+    }
+
+    pub async fn get_public_keys(&self) -> Result<MinterPublicKeys, CkBtcMinterAgentError> {
+        self.query("get_public_keys", ()).await
+    }
+
     pub async fn get_metrics(&self) -> Result<HttpResponse, CkBtcMinterAgentError> {
         self.query(
             "http_request",
@@ -147,7 +181,7 @@ impl CkBtcMinterAgent {
 }
 
 /// Parse the fields that can be found in the metrics
-fn parse_metrics(text: &str) -> BTreeMap<String, Metric> {
+pub fn parse_metrics(text: &str) -> BTreeMap<String, Metric> {
     let mut map = BTreeMap::new();
     for line in text.lines() {
         if let Some((key, value, ts)) = parse_metric(line) {
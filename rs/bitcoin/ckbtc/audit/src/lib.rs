@@ -0,0 +1,750 @@
+//! Offline audit of the ckBTC minter event log.
+//!
+//! The audit tool replays the events of the minter to reconstruct its state at
+//! any event index, computes the reserves of the minter (the UTXOs it controls)
+//! and its outstanding withdrawals, and compares them with the state reported
+//! by a running minter via `get_minter_info` and its metrics.
+//!
+//! Since the minter produces both its event log and its metrics, the audit also
+//! checks the replayed reserves against independent sources: the ckBTC supply
+//! reported by the ledger and the unspent outputs reported by the Bitcoin
+//! canister.
+use candid::{Decode, Encode, Nat, Principal};
+use ic_btc_interface::{GetUtxosRequest, GetUtxosResponse, Network, Utxo, UtxosFilterInRequest};
+use ic_ckbtc_agent::{parse_metrics, CkBtcMinterAgent, Metric};
+use ic_ckbtc_minter::address::{account_to_p2tr_address, account_to_p2wpkh_address, AddressType};
+use ic_ckbtc_minter::state::eventlog::{replay, Event};
+use ic_ckbtc_minter::state::CkBtcMinterState;
+use ic_ckbtc_minter::{MinterInfo, MinterPublicKeys};
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+/// The maximum number of times the audit downloads the new events because the
+/// minter emitted events while its state was being compared.
+const MAX_SYNC_ATTEMPTS: usize = 3;
+
+/// The Bitcoin canister that serves the Bitcoin mainnet.
+const BITCOIN_MAINNET_CANISTER_ID: &str = "ghsi2-tqaaa-aaaan-aaaca-cai";
+
+/// The Bitcoin canister that serves the Bitcoin testnet.
+const BITCOIN_TESTNET_CANISTER_ID: &str = "g4xu7-jiaaa-aaaan-aaaaq-cai";
+
+/// Reconstructs the minter state after the first `event_count` events.
+pub fn replay_events(events: &[Event], event_count: usize) -> Result<CkBtcMinterState, String> {
+    if event_count > events.len() {
+        return Err(format!(
+            "Cannot replay {} events, the log only contains {} events",
+            event_count,
+            events.len()
+        ));
+    }
+    replay(events.iter().take(event_count).cloned())
+        .map_err(|err| format!("Failed to replay the event log: {:?}", err))
+}
+
+/// Encodes events in the format of the files read by [decode_events].
+pub fn encode_events(events: &[Event]) -> Result<Vec<u8>, String> {
+    candid::encode_one(events).map_err(|err| format!("Failed to encode the events: {}", err))
+}
+
+/// Decodes events exported with [encode_events].
+pub fn decode_events(bytes: &[u8]) -> Result<Vec<Event>, String> {
+    candid::decode_one(bytes).map_err(|err| format!("Failed to decode the events: {}", err))
+}
+
+/// The reserves and liabilities of the minter at some point of its event log.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReserveSummary {
+    /// The number of events replayed to obtain this summary.
+    pub event_count: u64,
+    /// The UTXOs that the minter can use for withdrawals.
+    pub available_utxos: BTreeSet<Utxo>,
+    /// The value of the available UTXOs and of the change outputs of the
+    /// transactions waiting for finalization, in Satoshi.
+    pub btc_balance: u64,
+    /// The number of withdrawal requests waiting to be served.
+    pub pending_withdrawals: usize,
+    /// The amount of the withdrawal requests waiting to be served.
+    pub pending_withdrawal_amount: u64,
+    /// The number of withdrawal requests with a transaction waiting for finalization.
+    pub submitted_withdrawals: usize,
+    /// The amount of the withdrawal requests with a transaction waiting for finalization.
+    pub submitted_withdrawal_amount: u64,
+    pub submitted_transactions: usize,
+    pub stuck_transactions: usize,
+    pub tokens_minted: u64,
+    pub tokens_burned: u64,
+    pub owed_kyt_amount: u64,
+    pub min_confirmations: u32,
+    pub retrieve_btc_min_amount: u64,
+    pub kyt_fee: u64,
+    /// The ckBTC ledger of the minter.
+    pub ledger_id: Principal,
+    /// The Bitcoin network of the minter.
+    pub btc_network: Network,
+    /// The available UTXOs grouped by the deposit address that received them.
+    pub utxos_by_address: BTreeMap<(Account, AddressType), BTreeSet<Utxo>>,
+}
+
+impl ReserveSummary {
+    pub fn new(state: &CkBtcMinterState, event_count: u64) -> Self {
+        let submitted_requests = state
+            .submitted_transactions
+            .iter()
+            .flat_map(|tx| tx.requests.iter());
+        let mut utxos_by_address: BTreeMap<(Account, AddressType), BTreeSet<Utxo>> =
+            BTreeMap::new();
+        for utxo in state.available_utxos.iter() {
+            if let Some(account) = state.outpoint_account.get(&utxo.outpoint) {
+                utxos_by_address
+                    .entry((*account, state.outpoint_address_type(&utxo.outpoint)))
+                    .or_default()
+                    .insert(utxo.clone());
+            }
+        }
+        Self {
+            event_count,
+            available_utxos: state.available_utxos.clone(),
+            btc_balance: state.available_utxos.iter().map(|u| u.value).sum::<u64>()
+                + state
+                    .submitted_transactions
+                    .iter()
+                    .filter_map(|tx| tx.change_output.as_ref().map(|out| out.value))
+                    .sum::<u64>(),
+            pending_withdrawals: state.pending_retrieve_btc_requests.len(),
+            pending_withdrawal_amount: state
+                .pending_retrieve_btc_requests
+                .iter()
+                .map(|req| req.amount)
+                .sum(),
+            submitted_withdrawals: submitted_requests.clone().count(),
+            submitted_withdrawal_amount: submitted_requests.map(|req| req.amount).sum(),
+            submitted_transactions: state.submitted_transactions.len(),
+            stuck_transactions: state.stuck_transactions.len(),
+            tokens_minted: state.tokens_minted,
+            tokens_burned: state.tokens_burned,
+            owed_kyt_amount: state.owed_kyt_amount.values().sum(),
+            min_confirmations: state.min_confirmations,
+            retrieve_btc_min_amount: state.retrieve_btc_min_amount,
+            kyt_fee: state.kyt_fee,
+            ledger_id: state.ledger_id.get().0,
+            btc_network: state.btc_network,
+            utxos_by_address,
+        }
+    }
+
+    /// The amount of ckBTC burned for withdrawals that are not finalized yet.
+    pub fn outstanding_withdrawal_amount(&self) -> u64 {
+        self.pending_withdrawal_amount + self.submitted_withdrawal_amount
+    }
+}
+
+/// Compares the expected reserves with the minter info and the metrics of a
+/// running minter and returns the differences.
+///
+/// Requests that the minter is signing or sending are not recorded in the
+/// event log, so they are counted as pending.
+pub fn diff(
+    expected: &ReserveSummary,
+    minter_info: &MinterInfo,
+    metrics: &BTreeMap<String, Metric>,
+) -> Vec<String> {
+    let mut mismatches = vec![];
+    compare(
+        &mut mismatches,
+        "min_confirmations",
+        minter_info.min_confirmations as u64,
+        expected.min_confirmations as u64,
+    );
+    compare(
+        &mut mismatches,
+        "retrieve_btc_min_amount",
+        minter_info.retrieve_btc_min_amount,
+        expected.retrieve_btc_min_amount,
+    );
+    compare(
+        &mut mismatches,
+        "kyt_fee",
+        minter_info.kyt_fee,
+        expected.kyt_fee,
+    );
+
+    let expected_metrics: [(&[&str], u64); 9] = [
+        (
+            &["ckbtc_minter_utxos_available"],
+            expected.available_utxos.len() as u64,
+        ),
+        (&["ckbtc_minter_btc_balance"], expected.btc_balance),
+        (&["ckbtc_minter_minted_tokens"], expected.tokens_minted),
+        (&["ckbtc_minter_burned_tokens"], expected.tokens_burned),
+        (&["ckbtc_minter_owed_kyt_amount"], expected.owed_kyt_amount),
+        (
+            &[
+                "ckbtc_minter_retrieve_btc_request_count{status=\"pending\"}",
+                "ckbtc_minter_retrieve_btc_request_count{status=\"signing\"}",
+                "ckbtc_minter_retrieve_btc_request_count{status=\"sending\"}",
+            ],
+            expected.pending_withdrawals as u64,
+        ),
+        (
+            &["ckbtc_minter_retrieve_btc_request_count{status=\"submitted\"}"],
+            expected.submitted_withdrawals as u64,
+        ),
+        (
+            &["ckbtc_minter_btc_transaction_count{status=\"submitted\"}"],
+            expected.submitted_transactions as u64,
+        ),
+        (
+            &["ckbtc_minter_btc_transaction_count{status=\"stuck\"}"],
+            expected.stuck_transactions as u64,
+        ),
+    ];
+    for (names, expected_value) in expected_metrics {
+        let mut actual_value = 0;
+        for name in names {
+            match metrics.get(*name) {
+                Some(metric) => actual_value += metric.value as u64,
+                None => mismatches.push(format!("The minter does not report the metric {}", name)),
+            }
+        }
+        compare(
+            &mut mismatches,
+            &names.join(" + "),
+            actual_value,
+            expected_value,
+        );
+    }
+    mismatches
+}
+
+/// Checks that the ckBTC supply reported by the ledger is backed by the BTC
+/// reserves of the minter.
+///
+/// Besides the supply, the reserves must cover the pending withdrawals, whose
+/// ckBTC is burned but whose BTC is still in the available UTXOs, and the KYT
+/// fees that the minter owes and will mint. The BTC of withdrawals with a
+/// submitted transaction already left the balance. The reserves may exceed
+/// these liabilities by the fees that the minter collects.
+pub fn check_total_supply(expected: &ReserveSummary, total_supply: u64) -> Vec<String> {
+    let liabilities = total_supply as u128
+        + expected.pending_withdrawal_amount as u128
+        + expected.owed_kyt_amount as u128;
+    if liabilities > expected.btc_balance as u128 {
+        return vec![format!(
+            "The ledger reports a total supply of {} but the event log implies a BTC balance of {} minus {} for pending withdrawals and {} owed KYT fees",
+            total_supply,
+            expected.btc_balance,
+            expected.pending_withdrawal_amount,
+            expected.owed_kyt_amount
+        )];
+    }
+    vec![]
+}
+
+/// Returns the UTXOs of the minter that are not among the unspent outputs
+/// of their deposit address reported by the Bitcoin canister.
+pub fn missing_utxos<'a>(
+    minter_utxos: &'a BTreeSet<Utxo>,
+    bitcoin_utxos: &[Utxo],
+) -> Vec<&'a Utxo> {
+    let unspent: BTreeSet<_> = bitcoin_utxos
+        .iter()
+        .map(|utxo| (&utxo.outpoint, utxo.value))
+        .collect();
+    minter_utxos
+        .iter()
+        .filter(|utxo| !unspent.contains(&(&utxo.outpoint, utxo.value)))
+        .collect()
+}
+
+/// Returns the Bitcoin canister of the given network, if there is a canonical one.
+pub fn default_bitcoin_canister_id(network: Network) -> Option<Principal> {
+    match network {
+        Network::Mainnet => Some(BITCOIN_MAINNET_CANISTER_ID),
+        Network::Testnet => Some(BITCOIN_TESTNET_CANISTER_ID),
+        Network::Regtest => None,
+    }
+    .map(|id| Principal::from_str(id).expect("bug: invalid Bitcoin canister ID"))
+}
+
+/// Reads the total supply of the ledger with a replicated call.
+async fn get_total_supply(agent: &CkBtcMinterAgent, ledger_id: Principal) -> Result<u64, String> {
+    let reply = agent
+        .agent
+        .update(&ledger_id, "icrc1_total_supply")
+        .with_arg(Encode!().map_err(|err| format!("Failed to encode the arguments: {}", err))?)
+        .call_and_wait()
+        .await
+        .map_err(|err| format!("Failed to get the total supply of the ledger: {:?}", err))?;
+    let total_supply = Decode!(&reply, Nat)
+        .map_err(|err| format!("Failed to decode the total supply: {}", err))?;
+    u64::try_from(total_supply.0)
+        .map_err(|err| format!("The total supply does not fit into u64: {}", err))
+}
+
+/// Downloads all the unspent outputs of the address from the Bitcoin canister.
+async fn get_bitcoin_utxos(
+    agent: &CkBtcMinterAgent,
+    bitcoin_canister_id: Principal,
+    network: Network,
+    address: String,
+) -> Result<Vec<Utxo>, String> {
+    let mut utxos = vec![];
+    let mut filter = None;
+    loop {
+        let request = GetUtxosRequest {
+            address: address.clone(),
+            network: network.into(),
+            filter,
+        };
+        let reply = agent
+            .agent
+            .query(&bitcoin_canister_id, "bitcoin_get_utxos_query")
+            .with_arg(
+                Encode!(&request)
+                    .map_err(|err| format!("Failed to encode the arguments: {}", err))?,
+            )
+            .call()
+            .await
+            .map_err(|err| format!("Failed to get the UTXOs of {}: {:?}", address, err))?;
+        let response = Decode!(&reply, GetUtxosResponse)
+            .map_err(|err| format!("Failed to decode the UTXOs of {}: {}", address, err))?;
+        utxos.extend(response.utxos);
+        match response.next_page {
+            Some(page) => filter = Some(UtxosFilterInRequest::Page(page)),
+            None => return Ok(utxos),
+        }
+    }
+}
+
+/// Derives the deposit address of `account` from the master public keys of
+/// the minter, without relying on the minter to compute it.
+pub fn derive_deposit_address(
+    network: Network,
+    public_keys: &MinterPublicKeys,
+    account: &Account,
+    address_type: AddressType,
+) -> Result<String, String> {
+    let missing_key = || {
+        format!(
+            "The minter does not report the public key of its {:?} address of {}",
+            address_type, account
+        )
+    };
+    match address_type {
+        AddressType::P2wpkh => {
+            let public_key = public_keys
+                .ecdsa_public_key
+                .as_ref()
+                .ok_or_else(missing_key)?;
+            Ok(account_to_p2wpkh_address(network, public_key, account))
+        }
+        AddressType::P2tr => {
+            let public_key = public_keys
+                .schnorr_public_key
+                .as_ref()
+                .ok_or_else(missing_key)?;
+            Ok(account_to_p2tr_address(network, public_key, account))
+        }
+    }
+}
+
+/// Checks that the Bitcoin canister reports each available UTXO of the
+/// minter as an unspent output of its deposit address.
+async fn check_utxos(
+    agent: &CkBtcMinterAgent,
+    bitcoin_canister_id: Principal,
+    public_keys: &MinterPublicKeys,
+    expected: &ReserveSummary,
+) -> Result<Vec<String>, String> {
+    let mut mismatches = vec![];
+    for ((account, address_type), minter_utxos) in expected.utxos_by_address.iter() {
+        let address =
+            derive_deposit_address(expected.btc_network, public_keys, account, *address_type)?;
+        let bitcoin_utxos = get_bitcoin_utxos(
+            agent,
+            bitcoin_canister_id,
+            expected.btc_network,
+            address.clone(),
+        )
+        .await?;
+        for utxo in missing_utxos(minter_utxos, &bitcoin_utxos) {
+            mismatches.push(format!(
+                "The Bitcoin canister does not report the UTXO {}:{} of {} satoshi as unspent on {}",
+                utxo.outpoint.txid, utxo.outpoint.vout, utxo.value, address
+            ));
+        }
+    }
+    Ok(mismatches)
+}
+
+fn compare(mismatches: &mut Vec<String>, name: &str, actual: u64, expected: u64) {
+    if actual != expected {
+        mismatches.push(format!(
+            "The minter reports {} = {} but the event log implies {}",
+            name, actual, expected
+        ));
+    }
+}
+
+/// Downloads the events of the minter starting at index `start`.
+pub async fn fetch_events(
+    agent: &CkBtcMinterAgent,
+    start: u64,
+    max_events_per_request: u64,
+) -> Result<Vec<Event>, String> {
+    let mut events = vec![];
+    loop {
+        let batch = agent
+            .get_events(start + events.len() as u64, max_events_per_request)
+            .await
+            .map_err(|err| format!("Failed to get events from the minter: {:?}", err))?;
+        if batch.is_empty() {
+            return Ok(events);
+        }
+        events.extend(batch);
+    }
+}
+
+/// The result of an audit of a minter.
+#[derive(Clone, Debug)]
+pub struct AuditReport {
+    /// The reserves obtained by replaying the whole event log.
+    pub summary: ReserveSummary,
+    /// The total supply reported by the ledger.
+    pub total_supply: u64,
+    /// The differences between the replayed state and the minter state.
+    pub mismatches: Vec<String>,
+}
+
+impl AuditReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Downloads the event log of the minter, replays it and compares the
+/// resulting reserves with the state reported by the minter, the total supply
+/// of the ledger and the UTXOs known to the Bitcoin canister.
+///
+/// If `exported_events` is set, the audit also checks that the event log of
+/// the minter starts with these events. If `bitcoin_canister_id` is not set,
+/// the audit uses the Bitcoin canister of the network of the minter.
+///
+/// The minter state is read after the events are downloaded. If the minter
+/// emits events in the meantime then the new events are downloaded and the
+/// comparison restarts.
+pub async fn audit_minter(
+    agent: &CkBtcMinterAgent,
+    exported_events: Option<&[Event]>,
+    max_events_per_request: u64,
+    bitcoin_canister_id: Option<Principal>,
+) -> Result<AuditReport, String> {
+    let mut events = vec![];
+    for _ in 0..MAX_SYNC_ATTEMPTS {
+        events.extend(fetch_events(agent, events.len() as u64, max_events_per_request).await?);
+
+        let mut mismatches = vec![];
+        if let Some(exported_events) = exported_events {
+            if let Some(index) = exported_events
+                .iter()
+                .zip(events.iter())
+                .position(|(exported, live)| exported != live)
+            {
+                mismatches.push(format!(
+                    "The exported event {} differs from the event of the minter",
+                    index
+                ));
+            } else if exported_events.len() > events.len() {
+                mismatches.push(format!(
+                    "The export contains {} events but the minter only has {} events",
+                    exported_events.len(),
+                    events.len()
+                ));
+            }
+        }
+
+        let state = replay_events(&events, events.len())?;
+        let summary = ReserveSummary::new(&state, events.len() as u64);
+
+        let minter_info = agent
+            .get_minter_info()
+            .await
+            .map_err(|err| format!("Failed to get the minter info: {:?}", err))?;
+        let metrics = agent
+            .get_metrics()
+            .await
+            .map_err(|err| format!("Failed to get the minter metrics: {:?}", err))?;
+        let metrics = parse_metrics(
+            std::str::from_utf8(&metrics.body)
+                .map_err(|err| format!("The minter metrics are not valid UTF-8: {}", err))?,
+        );
+        mismatches.extend(diff(&summary, &minter_info, &metrics));
+
+        let total_supply = get_total_supply(agent, summary.ledger_id).await?;
+        mismatches.extend(check_total_supply(&summary, total_supply));
+
+        let bitcoin_canister_id = bitcoin_canister_id
+            .or_else(|| default_bitcoin_canister_id(summary.btc_network))
+            .ok_or_else(|| {
+                format!(
+                    "The Bitcoin canister of the {} network must be specified",
+                    summary.btc_network
+                )
+            })?;
+        let public_keys = agent
+            .get_public_keys()
+            .await
+            .map_err(|err| format!("Failed to get the minter public keys: {:?}", err))?;
+        mismatches.extend(check_utxos(agent, bitcoin_canister_id, &public_keys, &summary).await?);
+
+        let new_events = agent
+            .get_events(events.len() as u64, 1)
+            .await
+            .map_err(|err| format!("Failed to get events from the minter: {:?}", err))?;
+        if new_events.is_empty() {
+            return Ok(AuditReport {
+                summary,
+                total_supply,
+                mismatches,
+            });
+        }
+    }
+    Err(format!(
+        "The minter emitted new events during each of the {} audit attempts",
+        MAX_SYNC_ATTEMPTS
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+    use ic_base_types::CanisterId;
+    use ic_btc_interface::OutPoint;
+    use ic_ckbtc_minter::address::BitcoinAddress;
+    use ic_ckbtc_minter::lifecycle::init::{BtcNetwork, InitArgs};
+    use ic_ckbtc_minter::state::{Mode, RetrieveBtcRequest};
+
+    fn utxo(n: u8, value: u64) -> Utxo {
+        Utxo {
+            outpoint: OutPoint {
+                txid: [n; 32].into(),
+                vout: 0,
+            },
+            value,
+            height: 10,
+        }
+    }
+
+    fn events() -> Vec<Event> {
+        let account = Account {
+            owner: Principal::from_slice(&[1; 29]),
+            subaccount: None,
+        };
+        vec![
+            Event::Init(InitArgs {
+                btc_network: BtcNetwork::Regtest,
+                ecdsa_key_name: "key".to_string(),
+                retrieve_btc_min_amount: 10_000,
+                ledger_id: CanisterId::from_u64(42),
+                max_time_in_queue_nanos: 0,
+                min_confirmations: Some(6),
+                mode: Mode::GeneralAvailability,
+                kyt_fee: Some(100),
+                kyt_principal: Some(CanisterId::from_u64(43)),
+            }),
+            Event::ReceivedUtxos {
+                mint_txid: Some(0),
+                to_account: account,
+                utxos: vec![utxo(1, 100_000), utxo(2, 50_000)],
+                address_type: None,
+            },
+            Event::AcceptedRetrieveBtcRequest(RetrieveBtcRequest {
+                amount: 30_000,
+                address: BitcoinAddress::P2wpkhV0([2; 20]),
+                block_index: 1,
+                received_at: 0,
+                kyt_provider: Some(Principal::from_slice(&[3; 29])),
+                reimbursement_account: None,
+                batch_outputs: None,
+                fee_priority: None,
+            }),
+        ]
+    }
+
+    fn metrics(summary: &ReserveSummary) -> BTreeMap<String, Metric> {
+        [
+            (
+                "ckbtc_minter_utxos_available",
+                summary.available_utxos.len() as u64,
+            ),
+            ("ckbtc_minter_btc_balance", summary.btc_balance),
+            ("ckbtc_minter_minted_tokens", summary.tokens_minted),
+            ("ckbtc_minter_burned_tokens", summary.tokens_burned),
+            ("ckbtc_minter_owed_kyt_amount", summary.owed_kyt_amount),
+            (
+                "ckbtc_minter_retrieve_btc_request_count{status=\"pending\"}",
+                0,
+            ),
+            (
+                "ckbtc_minter_retrieve_btc_request_count{status=\"signing\"}",
+                summary.pending_withdrawals as u64,
+            ),
+            (
+                "ckbtc_minter_retrieve_btc_request_count{status=\"sending\"}",
+                0,
+            ),
+            (
+                "ckbtc_minter_retrieve_btc_request_count{status=\"submitted\"}",
+                0,
+            ),
+            (
+                "ckbtc_minter_btc_transaction_count{status=\"submitted\"}",
+                0,
+            ),
+            ("ckbtc_minter_btc_transaction_count{status=\"stuck\"}", 0),
+        ]
+        .into_iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                Metric {
+                    value: value as f64,
+                    timestamp: 0,
+                },
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn should_compute_reserves_at_any_event_index() {
+        let events = events();
+
+        let state = replay_events(&events, 2).unwrap();
+        let summary = ReserveSummary::new(&state, 2);
+        assert_eq!(summary.available_utxos.len(), 2);
+        assert_eq!(summary.btc_balance, 150_000);
+        assert_eq!(summary.pending_withdrawals, 0);
+        assert_eq!(summary.outstanding_withdrawal_amount(), 0);
+
+        let state = replay_events(&events, 3).unwrap();
+        let summary = ReserveSummary::new(&state, 3);
+        assert_eq!(summary.btc_balance, 150_000);
+        assert_eq!(summary.pending_withdrawals, 1);
+        assert_eq!(summary.outstanding_withdrawal_amount(), 30_000);
+        assert_eq!(summary.tokens_burned, 30_000);
+        assert_eq!(summary.owed_kyt_amount, 100);
+
+        assert!(replay_events(&events, 0).is_err());
+        assert!(replay_events(&events, 4).is_err());
+    }
+
+    #[test]
+    fn should_round_trip_exported_events() {
+        let events = events();
+        assert_eq!(
+            decode_events(&encode_events(&events).unwrap()).unwrap(),
+            events
+        );
+    }
+
+    #[test]
+    fn should_diff_against_minter_state() {
+        let events = events();
+        let summary = ReserveSummary::new(&replay_events(&events, 3).unwrap(), 3);
+        let minter_info = MinterInfo {
+            min_confirmations: 6,
+            retrieve_btc_min_amount: 10_000,
+            kyt_fee: 100,
+        };
+        let mut metrics = metrics(&summary);
+        assert_eq!(diff(&summary, &minter_info, &metrics), Vec::<String>::new());
+
+        metrics.get_mut("ckbtc_minter_btc_balance").unwrap().value = 1.0;
+        metrics.remove("ckbtc_minter_btc_transaction_count{status=\"stuck\"}");
+        let mismatches = diff(
+            &summary,
+            &MinterInfo {
+                kyt_fee: 200,
+                ..minter_info
+            },
+            &metrics,
+        );
+        assert_eq!(
+            mismatches,
+            vec![
+                "The minter reports kyt_fee = 200 but the event log implies 100".to_string(),
+                "The minter reports ckbtc_minter_btc_balance = 1 but the event log implies 150000"
+                    .to_string(),
+                "The minter does not report the metric ckbtc_minter_btc_transaction_count{status=\"stuck\"}"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_check_total_supply_against_reserves() {
+        let events = events();
+        let summary = ReserveSummary::new(&replay_events(&events, 3).unwrap(), 3);
+        // 150_000 satoshi of reserves minus 30_000 for the pending withdrawal
+        // and 100 owed KYT fees.
+        assert_eq!(check_total_supply(&summary, 119_900), Vec::<String>::new());
+        assert_eq!(check_total_supply(&summary, 100_000), Vec::<String>::new());
+        assert_eq!(
+            check_total_supply(&summary, 119_901),
+            vec![
+                "The ledger reports a total supply of 119901 but the event log implies a BTC balance of 150000 minus 30000 for pending withdrawals and 100 owed KYT fees"
+                    .to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn should_find_utxos_missing_from_the_bitcoin_canister() {
+        let events = events();
+        let summary = ReserveSummary::new(&replay_events(&events, 3).unwrap(), 3);
+        let account = Account {
+            owner: Principal::from_slice(&[1; 29]),
+            subaccount: None,
+        };
+        assert_eq!(
+            summary.utxos_by_address.keys().collect::<Vec<_>>(),
+            vec![&(account, AddressType::P2wpkh)]
+        );
+        let minter_utxos = &summary.utxos_by_address[&(account, AddressType::P2wpkh)];
+
+        assert_eq!(
+            missing_utxos(
+                minter_utxos,
+                &[utxo(1, 100_000), utxo(2, 50_000), utxo(3, 1)]
+            ),
+            Vec::<&Utxo>::new()
+        );
+        assert_eq!(
+            missing_utxos(minter_utxos, &[utxo(1, 100_000), utxo(2, 40_000)]),
+            vec![&utxo(2, 50_000)]
+        );
+        assert_eq!(missing_utxos(minter_utxos, &[]).len(), 2);
+    }
+
+    #[test]
+    fn should_not_derive_addresses_without_public_key() {
+        let account = Account {
+            owner: Principal::from_slice(&[1; 29]),
+            subaccount: None,
+        };
+        let public_keys = MinterPublicKeys {
+            ecdsa_public_key: None,
+            schnorr_public_key: None,
+        };
+        for address_type in [AddressType::P2wpkh, AddressType::P2tr] {
+            assert!(
+                derive_deposit_address(Network::Regtest, &public_keys, &account, address_type)
+                    .is_err()
+            );
+        }
+    }
+}
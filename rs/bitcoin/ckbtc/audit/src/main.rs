@@ -0,0 +1,199 @@
+use anyhow::{bail, Context, Result};
+use candid::Principal;
+use clap::{Parser, Subcommand};
+use ic_agent::{
+    agent::http_transport::reqwest_transport::ReqwestTransport, identity::AnonymousIdentity, Agent,
+};
+use ic_ckbtc_agent::CkBtcMinterAgent;
+use ic_ckbtc_audit::{
+    audit_minter, decode_events, encode_events, fetch_events, replay_events, ReserveSummary,
+};
+use ic_ckbtc_minter::state::eventlog::Event;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+const MAINNET_URL: &str = "https://ic0.app";
+
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, clap::Args)]
+struct MinterArgs {
+    /// The ckBTC minter to query.
+    #[arg(short, long)]
+    minter_id: Principal,
+
+    /// The URL of the replica to connect to, e.g. a local replica or a PocketIC instance.
+    #[arg(long, default_value = MAINNET_URL)]
+    network_url: String,
+
+    /// Fetches the root key from the replica. Only use this with a local replica or PocketIC,
+    /// never with the mainnet.
+    #[arg(long, default_value_t = false)]
+    fetch_root_key: bool,
+
+    /// The maximum number of events requested to the minter per call.
+    #[arg(long, default_value_t = 2_000)]
+    max_events_per_request: u64,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Downloads the event log of a minter to a file.
+    Export {
+        #[command(flatten)]
+        minter: MinterArgs,
+
+        /// The file to write the events to.
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Replays exported events and prints the reserves of the minter.
+    Replay {
+        /// The file containing the exported events.
+        #[arg(short, long)]
+        events: PathBuf,
+
+        /// The number of events to replay. Defaults to all the events.
+        #[arg(long)]
+        event_count: Option<usize>,
+
+        /// Prints the available UTXOs.
+        #[arg(long, default_value_t = false)]
+        list_utxos: bool,
+    },
+    /// Replays the event log of a minter and compares the reserves with the
+    /// state reported by the minter, the ledger and the Bitcoin canister.
+    Audit {
+        #[command(flatten)]
+        minter: MinterArgs,
+
+        /// A file with previously exported events that the event log of the
+        /// minter must start with.
+        #[arg(short, long)]
+        events: Option<PathBuf>,
+
+        /// The Bitcoin canister to check the UTXOs of the minter against.
+        /// Defaults to the Bitcoin canister of the network of the minter.
+        #[arg(long)]
+        bitcoin_canister_id: Option<Principal>,
+    },
+}
+
+async fn minter_agent(args: &MinterArgs) -> Result<CkBtcMinterAgent> {
+    let agent = Agent::builder()
+        .with_identity(AnonymousIdentity)
+        .with_transport(ReqwestTransport::create(
+            Url::parse(&args.network_url)
+                .context(format!("Failed to parse URL {}", args.network_url))?,
+        )?)
+        .build()?;
+    if args.fetch_root_key {
+        agent.fetch_root_key().await?;
+    }
+    Ok(CkBtcMinterAgent {
+        agent,
+        minter_canister_id: args.minter_id,
+    })
+}
+
+fn read_events(path: &Path) -> Result<Vec<Event>> {
+    let bytes = std::fs::read(path).context(format!("Failed to read {}", path.display()))?;
+    decode_events(&bytes).map_err(anyhow::Error::msg)
+}
+
+fn print_summary(summary: &ReserveSummary) {
+    println!("events replayed: {}", summary.event_count);
+    println!("available utxos: {}", summary.available_utxos.len());
+    println!("btc balance: {}", summary.btc_balance);
+    println!(
+        "pending withdrawals: {} ({} satoshi)",
+        summary.pending_withdrawals, summary.pending_withdrawal_amount
+    );
+    println!(
+        "submitted withdrawals: {} ({} satoshi) in {} transaction(s), {} stuck",
+        summary.submitted_withdrawals,
+        summary.submitted_withdrawal_amount,
+        summary.submitted_transactions,
+        summary.stuck_transactions
+    );
+    println!(
+        "outstanding withdrawal amount: {}",
+        summary.outstanding_withdrawal_amount()
+    );
+    println!("tokens minted: {}", summary.tokens_minted);
+    println!("tokens burned: {}", summary.tokens_burned);
+    println!("owed kyt amount: {}", summary.owed_kyt_amount);
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    match Args::parse().command {
+        Command::Export { minter, output } => {
+            let agent = minter_agent(&minter).await?;
+            let events = fetch_events(&agent, 0, minter.max_events_per_request)
+                .await
+                .map_err(anyhow::Error::msg)?;
+            let bytes = encode_events(&events).map_err(anyhow::Error::msg)?;
+            std::fs::write(&output, bytes)
+                .context(format!("Failed to write {}", output.display()))?;
+            println!("exported {} events to {}", events.len(), output.display());
+        }
+        Command::Replay {
+            events,
+            event_count,
+            list_utxos,
+        } => {
+            let events = read_events(&events)?;
+            let event_count = event_count.unwrap_or(events.len());
+            let state = replay_events(&events, event_count).map_err(anyhow::Error::msg)?;
+            let summary = ReserveSummary::new(&state, event_count as u64);
+            print_summary(&summary);
+            if list_utxos {
+                for utxo in &summary.available_utxos {
+                    println!(
+                        "utxo: {}:{} value {} height {}",
+                        utxo.outpoint.txid, utxo.outpoint.vout, utxo.value, utxo.height
+                    );
+                }
+            }
+        }
+        Command::Audit {
+            minter,
+            events,
+            bitcoin_canister_id,
+        } => {
+            let exported_events = events.as_deref().map(read_events).transpose()?;
+            let agent = minter_agent(&minter).await?;
+            let report = audit_minter(
+                &agent,
+                exported_events.as_deref(),
+                minter.max_events_per_request,
+                bitcoin_canister_id,
+            )
+            .await
+            .map_err(anyhow::Error::msg)
+            .context(format!("Failed to audit the minter {}", minter.minter_id))?;
+
+            println!("minter: {}", minter.minter_id);
+            print_summary(&report.summary);
+            println!("ledger total supply: {}", report.total_supply);
+            for mismatch in &report.mismatches {
+                println!("MISMATCH: {}", mismatch);
+            }
+            if !report.is_ok() {
+                bail!(
+                    "The minter {} failed the audit with {} mismatch(es)",
+                    minter.minter_id,
+                    report.mismatches.len()
+                );
+            }
+            println!("OK");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,283 @@
+use candid::{Decode, Encode, Principal};
+use ic_agent::{
+    agent::http_transport::reqwest_transport::ReqwestTransport, identity::AnonymousIdentity, Agent,
+};
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_bitcoin_canister_mock::{OutPoint, PushUtxoToAddress, Utxo};
+use ic_btc_interface::Network;
+use ic_ckbtc_agent::CkBtcMinterAgent;
+use ic_ckbtc_audit::{audit_minter, derive_deposit_address};
+use ic_ckbtc_kyt::{InitArg as KytInitArg, KytMode, LifecycleArg, SetApiKeyArg};
+use ic_ckbtc_minter::address::AddressType;
+use ic_ckbtc_minter::lifecycle::init::{InitArgs as CkbtcMinterInitArgs, MinterArg};
+use ic_ckbtc_minter::state::Mode;
+use ic_ckbtc_minter::updates::get_btc_address::GetBtcAddressArgs;
+use ic_ckbtc_minter::updates::update_balance::{UpdateBalanceArgs, UpdateBalanceError, UtxoStatus};
+use ic_icrc1_ledger::{InitArgsBuilder as LedgerInitArgsBuilder, LedgerArgument};
+use icrc_ledger_types::icrc1::account::Account;
+use pocket_ic::{PocketIc, PocketIcBuilder, WasmResult};
+use tokio::runtime::Runtime;
+
+const BITCOIN_TESTNET_CANISTER_ID: &str = "g4xu7-jiaaa-aaaan-aaaaq-cai";
+const KYT_FEE: u64 = 2_000;
+const TRANSFER_FEE: u64 = 10;
+const DEPOSIT_VALUE: u64 = 100_000_000;
+const STARTING_CYCLES: u128 = 100_000_000_000_000;
+
+fn wasm(env_var: &str) -> Vec<u8> {
+    let path = std::env::var(env_var).unwrap_or_else(|_| panic!("{} is not set", env_var));
+    std::fs::read(&path).unwrap_or_else(|err| panic!("failed to read {}: {}", path, err))
+}
+
+fn canister_id(principal: Principal) -> CanisterId {
+    CanisterId::try_from(PrincipalId(principal)).unwrap()
+}
+
+fn update_call(
+    pocket_ic: &PocketIc,
+    canister_id: Principal,
+    sender: Principal,
+    method: &str,
+    arg: Vec<u8>,
+) -> Vec<u8> {
+    match pocket_ic
+        .update_call(canister_id, sender, method, arg)
+        .unwrap_or_else(|err| panic!("failed to call {}: {}", method, err))
+    {
+        WasmResult::Reply(reply) => reply,
+        WasmResult::Reject(reject) => panic!("{} was rejected: {}", method, reject),
+    }
+}
+
+struct CkBtcSetup {
+    pocket_ic: PocketIc,
+    bitcoin_id: Principal,
+    minter_id: Principal,
+}
+
+impl CkBtcSetup {
+    fn new() -> Self {
+        let pocket_ic = PocketIcBuilder::new()
+            .with_fiduciary_subnet()
+            .with_bitcoin_subnet()
+            .build();
+        let fiduciary_subnet = pocket_ic.topology().get_fiduciary().unwrap();
+
+        let bitcoin_id = Principal::from_text(BITCOIN_TESTNET_CANISTER_ID).unwrap();
+        pocket_ic
+            .create_canister_with_id(None, None, bitcoin_id)
+            .expect("failed to create the Bitcoin canister");
+        pocket_ic.install_canister(
+            bitcoin_id,
+            wasm("IC_BITCOIN_CANISTER_MOCK_WASM_PATH"),
+            Encode!(&Network::Regtest).unwrap(),
+            None,
+        );
+
+        let ledger_id = pocket_ic.create_canister_on_subnet(None, None, fiduciary_subnet);
+        let minter_id = pocket_ic.create_canister_on_subnet(None, None, fiduciary_subnet);
+        let kyt_id = pocket_ic.create_canister_on_subnet(None, None, fiduciary_subnet);
+        for canister in [ledger_id, minter_id, kyt_id] {
+            pocket_ic.add_cycles(canister, STARTING_CYCLES);
+        }
+
+        pocket_ic.install_canister(
+            ledger_id,
+            wasm("IC_ICRC1_LEDGER_WASM_PATH"),
+            Encode!(&LedgerArgument::Init(
+                LedgerInitArgsBuilder::with_symbol_and_name("ckBTC", "ckBTC")
+                    .with_minting_account(minter_id)
+                    .with_transfer_fee(TRANSFER_FEE)
+                    .build()
+            ))
+            .unwrap(),
+            None,
+        );
+
+        pocket_ic.install_canister(
+            minter_id,
+            wasm("IC_CKBTC_MINTER_WASM_PATH"),
+            Encode!(&MinterArg::Init(CkbtcMinterInitArgs {
+                btc_network: Network::Regtest.into(),
+                ecdsa_key_name: "test_key_1".to_string(),
+                retrieve_btc_min_amount: 10_000,
+                ledger_id: canister_id(ledger_id),
+                max_time_in_queue_nanos: 0,
+                min_confirmations: Some(1),
+                mode: Mode::GeneralAvailability,
+                kyt_fee: Some(KYT_FEE),
+                kyt_principal: Some(canister_id(kyt_id)),
+            }))
+            .unwrap(),
+            None,
+        );
+
+        let kyt_provider = PrincipalId::new_user_test_id(2).0;
+        pocket_ic.install_canister(
+            kyt_id,
+            wasm("IC_CKBTC_KYT_WASM_PATH"),
+            Encode!(&LifecycleArg::InitArg(KytInitArg {
+                minter_id,
+                maintainers: vec![kyt_provider],
+                mode: KytMode::AcceptAll,
+            }))
+            .unwrap(),
+            None,
+        );
+        update_call(
+            &pocket_ic,
+            kyt_id,
+            kyt_provider,
+            "set_api_key",
+            Encode!(&SetApiKeyArg {
+                api_key: "api key".to_string(),
+            })
+            .unwrap(),
+        );
+
+        Self {
+            pocket_ic,
+            bitcoin_id,
+            minter_id,
+        }
+    }
+
+    fn deposit(&self, owner: Principal, utxo: Utxo) -> String {
+        let address = Decode!(
+            &update_call(
+                &self.pocket_ic,
+                self.minter_id,
+                owner,
+                "get_btc_address",
+                Encode!(&GetBtcAddressArgs {
+                    owner: None,
+                    subaccount: None,
+                    address_type: None,
+                })
+                .unwrap(),
+            ),
+            String
+        )
+        .unwrap();
+
+        update_call(
+            &self.pocket_ic,
+            self.bitcoin_id,
+            Principal::anonymous(),
+            "push_utxo_to_address",
+            Encode!(&PushUtxoToAddress {
+                address: address.clone(),
+                utxo,
+            })
+            .unwrap(),
+        );
+
+        let statuses = Decode!(
+            &update_call(
+                &self.pocket_ic,
+                self.minter_id,
+                owner,
+                "update_balance",
+                Encode!(&UpdateBalanceArgs {
+                    owner: None,
+                    subaccount: None,
+                })
+                .unwrap(),
+            ),
+            Result<Vec<UtxoStatus>, UpdateBalanceError>
+        )
+        .unwrap()
+        .expect("failed to update the balance");
+        assert!(
+            matches!(statuses.as_slice(), [UtxoStatus::Minted { .. }]),
+            "unexpected UTXO statuses: {:?}",
+            statuses
+        );
+
+        address
+    }
+}
+
+#[test]
+fn should_audit_minter_against_ledger_and_bitcoin_canister() {
+    let mut ckbtc = CkBtcSetup::new();
+    let utxo = Utxo {
+        height: 0,
+        value: DEPOSIT_VALUE,
+        outpoint: OutPoint {
+            txid: [1; 32].into(),
+            vout: 0,
+        },
+    };
+    let owner = PrincipalId::new_user_test_id(1).0;
+    let address = ckbtc.deposit(owner, utxo.clone());
+
+    let (bitcoin_id, minter_id) = (ckbtc.bitcoin_id, ckbtc.minter_id);
+    let endpoint = ckbtc.pocket_ic.make_live(None);
+    Runtime::new().unwrap().block_on(async {
+        let agent = Agent::builder()
+            .with_identity(AnonymousIdentity)
+            .with_transport(ReqwestTransport::create(endpoint).unwrap())
+            .build()
+            .unwrap();
+        agent.fetch_root_key().await.unwrap();
+        let agent = CkBtcMinterAgent {
+            agent,
+            minter_canister_id: minter_id,
+        };
+
+        // The audit derives the deposit address from the minter public keys
+        // instead of asking the minter for it.
+        let public_keys = agent.get_public_keys().await.unwrap();
+        assert_eq!(
+            derive_deposit_address(
+                Network::Regtest,
+                &public_keys,
+                &Account {
+                    owner,
+                    subaccount: None
+                },
+                AddressType::P2wpkh
+            ),
+            Ok(address.clone())
+        );
+        assert!(derive_deposit_address(
+            Network::Regtest,
+            &public_keys,
+            &Account {
+                owner,
+                subaccount: None
+            },
+            AddressType::P2tr
+        )
+        .is_err());
+
+        let report = audit_minter(&agent, None, 100, Some(bitcoin_id))
+            .await
+            .unwrap();
+        assert!(report.is_ok(), "mismatches: {:?}", report.mismatches);
+        assert_eq!(report.summary.available_utxos.len(), 1);
+        assert_eq!(report.summary.btc_balance, DEPOSIT_VALUE);
+        assert_eq!(report.total_supply, DEPOSIT_VALUE - KYT_FEE);
+
+        // The deposit disappears from the Bitcoin canister, e.g., because
+        // the minter state diverged from the Bitcoin network.
+        agent
+            .agent
+            .update(&bitcoin_id, "remove_utxo")
+            .with_arg(Encode!(&utxo).unwrap())
+            .call_and_wait()
+            .await
+            .unwrap();
+
+        let report = audit_minter(&agent, None, 100, Some(bitcoin_id))
+            .await
+            .unwrap();
+        assert_eq!(report.mismatches.len(), 1, "{:?}", report.mismatches);
+        assert!(
+            report.mismatches[0].contains(&address),
+            "unexpected mismatch: {}",
+            report.mismatches[0]
+        );
+    });
+}
@@ -37,6 +37,30 @@ pub enum BitcoinAddress {
     P2sh([u8; 20]),
 }
 
+/// The kind of deposit address that the minter derives for an account.
+#[derive(
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Debug,
+    Default,
+    Deserialize,
+    Serialize,
+    candid::CandidType,
+)]
+pub enum AddressType {
+    /// Pay to witness public key hash address controlled by the tECDSA key.
+    #[default]
+    #[serde(rename = "p2wpkh")]
+    P2wpkh,
+    /// Pay to taproot address controlled by the threshold BIP-340 key.
+    #[serde(rename = "p2tr")]
+    P2tr,
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum WitnessVersion {
     V0 = 0,
@@ -148,6 +172,28 @@ pub fn account_to_bitcoin_address(
     BitcoinAddress::P2wpkhV0(crate::tx::hash160(&pk))
 }
 
+/// Returns the x-only Taproot output key that holds deposits of the specified
+/// account.
+///
+/// The output key is the untweaked BIP-340 key derived for the account, i.e.,
+/// the output can only be spent through the key path. The threshold Schnorr
+/// API signs with the derived key as is and does not apply BIP-341 tweaks.
+pub fn derive_p2tr_output_key(schnorr_public_key: &ECDSAPublicKey, account: &Account) -> [u8; 32] {
+    let pk = derive_public_key(schnorr_public_key, account).public_key;
+    pk[1..]
+        .try_into()
+        .expect("bug: compressed public keys must be 33 bytes long")
+}
+
+/// Constructs the Taproot deposit address of the specified account.
+pub fn account_to_p2tr_address(
+    network: Network,
+    schnorr_public_key: &ECDSAPublicKey,
+    account: &Account,
+) -> String {
+    BitcoinAddress::P2trV1(derive_p2tr_output_key(schnorr_public_key, account)).display(network)
+}
+
 fn encode_bech32(network: Network, hash: &[u8], version: WitnessVersion) -> String {
     use bech32::u5;
 
@@ -1,8 +1,8 @@
-use crate::address::BitcoinAddress;
+use crate::address::{AddressType, BitcoinAddress};
 use crate::logs::{P0, P1};
 use crate::memo::Status;
 use crate::queries::WithdrawalFee;
-use crate::state::ReimbursementReason;
+use crate::state::{FeePriority, ReimbursementReason};
 use crate::tasks::schedule_after;
 use candid::{CandidType, Deserialize};
 use ic_btc_interface::{MillisatoshiPerByte, Network, OutPoint, Satoshi, Txid, Utxo};
@@ -96,6 +96,16 @@ pub struct MinterInfo {
     pub kyt_fee: u64,
 }
 
+/// The master public keys from which the minter derives its addresses.
+///
+/// A key is not set until the minter first derives an address of the
+/// corresponding type.
+#[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
+pub struct MinterPublicKeys {
+    pub ecdsa_public_key: Option<ECDSAPublicKey>,
+    pub schnorr_public_key: Option<ECDSAPublicKey>,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize, Serialize)]
 pub struct ECDSAPublicKey {
     pub public_key: Vec<u8>,
@@ -106,14 +116,17 @@ struct SignTxRequest {
     key_name: String,
     network: Network,
     ecdsa_public_key: ECDSAPublicKey,
+    schnorr_public_key: Option<ECDSAPublicKey>,
     unsigned_tx: tx::UnsignedTransaction,
     change_output: state::ChangeOutput,
-    outpoint_account: BTreeMap<OutPoint, Account>,
+    outpoint_account: BTreeMap<OutPoint, (Account, AddressType)>,
     /// The original requests that we keep around to place back to the queue
     /// if the signature fails.
     requests: Vec<state::RetrieveBtcRequest>,
     /// The list of UTXOs we use as transaction inputs.
     utxos: Vec<Utxo>,
+    /// The fee per vbyte (in millisatoshi) of the transaction.
+    fee_per_vbyte: MillisatoshiPerByte,
 }
 
 /// Undoes changes we make to the ckBTC state when we construct a pending transaction.
@@ -230,6 +243,25 @@ pub async fn estimate_fee_per_vbyte() -> Option<MillisatoshiPerByte> {
     }
 }
 
+/// Returns the fee in millisatoshi per vbyte for the given priority, given the
+/// median fee and the latest fee percentiles of the Bitcoin network.
+pub fn fee_per_vbyte_for_priority(
+    median_fee: MillisatoshiPerByte,
+    fee_percentiles: &[MillisatoshiPerByte],
+    priority: FeePriority,
+) -> MillisatoshiPerByte {
+    match priority {
+        FeePriority::Low => fee_percentiles
+            .get(25)
+            .map_or(median_fee, |fee| median_fee.min(*fee))
+            .max(MIN_RELAY_FEE_PER_VBYTE),
+        FeePriority::Medium => median_fee,
+        FeePriority::High => fee_percentiles
+            .get(75)
+            .map_or(median_fee, |fee| median_fee.max(*fee)),
+    }
+}
+
 /// Constructs and sends out signed bitcoin transactions for pending retrieve
 /// requests.
 async fn submit_pending_requests() {
@@ -245,6 +277,7 @@ async fn submit_pending_requests() {
     };
 
     let ecdsa_public_key = updates::get_btc_address::init_ecdsa_public_key().await;
+    let schnorr_public_key = init_schnorr_public_key_if_needed().await;
     let main_address = address::account_to_bitcoin_address(&ecdsa_public_key, &main_account);
 
     let fee_millisatoshi_per_vbyte = match estimate_fee_per_vbyte().await {
@@ -259,16 +292,18 @@ async fn submit_pending_requests() {
             return None;
         }
 
-        let outputs: Vec<_> = batch
-            .iter()
-            .map(|req| (req.address.clone(), req.amount))
-            .collect();
+        let fee_per_vbyte = fee_per_vbyte_for_priority(
+            fee_millisatoshi_per_vbyte,
+            &s.last_fee_per_vbyte,
+            batch[0].fee_priority(),
+        );
+        let outputs: Vec<_> = batch.iter().flat_map(|req| req.outputs()).collect();
 
         match build_unsigned_transaction(
             &mut s.available_utxos,
             outputs,
             main_address,
-            fee_millisatoshi_per_vbyte,
+            fee_per_vbyte,
         ) {
             Ok((unsigned_tx, change_output, utxos)) => {
                 for req in batch.iter() {
@@ -278,12 +313,14 @@ async fn submit_pending_requests() {
                 Some(SignTxRequest {
                     key_name: s.ecdsa_key_name.clone(),
                     ecdsa_public_key,
+                    schnorr_public_key,
                     change_output,
                     outpoint_account: filter_output_accounts(s, &unsigned_tx),
                     network: s.btc_network,
                     unsigned_tx,
                     requests: batch,
                     utxos,
+                    fee_per_vbyte,
                 })
             }
             Err(BuildTxError::AmountTooLow) => {
@@ -308,8 +345,9 @@ async fn submit_pending_requests() {
 
                 let mut requests_to_put_back = vec![];
                 for request in batch {
-                    if request.address == address && request.amount == amount {
-                        // Finalize the request that we cannot fulfill.
+                    if request.outputs().contains(&(address.clone(), amount)) {
+                        // Finalize the request that we cannot fulfill. Batched
+                        // requests are dropped as a whole.
                         state::audit::remove_retrieve_btc_request(s, request);
                     } else {
                         // Keep the rest of the requests in the batch, we will
@@ -352,6 +390,7 @@ async fn submit_pending_requests() {
         match sign_transaction(
             req.key_name,
             &req.ecdsa_public_key,
+            req.schnorr_public_key.as_ref(),
             &req.outpoint_account,
             req.unsigned_tx,
         )
@@ -394,7 +433,9 @@ async fn submit_pending_requests() {
                                     used_utxos,
                                     change_output: Some(req.change_output),
                                     submitted_at: ic_cdk::api::time(),
-                                    fee_per_vbyte: Some(fee_millisatoshi_per_vbyte),
+                                    fee_per_vbyte: Some(req.fee_per_vbyte),
+                                    fee_bump: 0,
+                                    applied_fee_bump: 0,
                                 },
                             );
                         });
@@ -481,6 +522,7 @@ async fn finalize_requests() {
     }
 
     let ecdsa_public_key = updates::get_btc_address::init_ecdsa_public_key().await;
+    let schnorr_public_key = init_schnorr_public_key_if_needed().await;
     let now = ic_cdk::api::time();
 
     // The list of transactions that are likely to be finalized, indexed by the transaction id.
@@ -489,7 +531,10 @@ async fn finalize_requests() {
             let wait_time = finalization_time_estimate(s.min_confirmations, s.btc_network);
             s.submitted_transactions
                 .iter()
-                .filter(|&req| (req.submitted_at + (wait_time.as_nanos() as u64) < now))
+                .filter(|&req| {
+                    req.submitted_at + (wait_time.as_nanos() as u64) < now
+                        || req.pending_fee_bump() > 0
+                })
                 .map(|req| (req.txid, req.clone()))
                 .collect()
         });
@@ -519,7 +564,7 @@ async fn finalize_requests() {
 
     state::mutate_state(|s| {
         if !new_utxos.is_empty() {
-            state::audit::add_utxos(s, None, main_account, new_utxos);
+            state::audit::add_utxos(s, None, main_account, new_utxos, AddressType::P2wpkh);
         }
         for txid in &confirmed_transactions {
             state::audit::confirm_transaction(s, txid);
@@ -548,8 +593,11 @@ async fn finalize_requests() {
 
     // Do not replace transactions if less than MIN_RESUBMISSION_DELAY passed since their
     // submission. This strategy works around short-term fee spikes.
-    maybe_finalized_transactions
-        .retain(|_txid, tx| tx.submitted_at + MIN_RESUBMISSION_DELAY.as_nanos() as u64 <= now);
+    // Transactions that users accelerated are replaced as soon as possible.
+    maybe_finalized_transactions.retain(|_txid, tx| {
+        tx.submitted_at + MIN_RESUBMISSION_DELAY.as_nanos() as u64 <= now
+            || tx.pending_fee_bump() > 0
+    });
 
     if maybe_finalized_transactions.is_empty() {
         // There are no transactions eligible for replacement.
@@ -615,25 +663,44 @@ async fn finalize_requests() {
         None => return,
     };
 
-    let key_name = state::read_state(|s| s.ecdsa_key_name.clone());
+    let (key_name, fee_percentiles) =
+        state::read_state(|s| (s.ecdsa_key_name.clone(), s.last_fee_per_vbyte.clone()));
 
     for (old_txid, submitted_tx) in maybe_finalized_transactions {
         let mut utxos: BTreeSet<_> = submitted_tx.used_utxos.iter().cloned().collect();
 
-        let tx_fee_per_vbyte = match submitted_tx.fee_per_vbyte {
+        let fee_priority = submitted_tx
+            .requests
+            .first()
+            .map(|req| req.fee_priority())
+            .unwrap_or_default();
+        let priority_fee_per_vbyte =
+            fee_per_vbyte_for_priority(fee_per_vbyte, &fee_percentiles, fee_priority);
+        let mut tx_fee_per_vbyte = match submitted_tx.fee_per_vbyte {
             Some(prev_fee) => {
                 // Ensure that the fee is at least min relay fee higher than the previous
                 // transaction fee to comply with BIP-125 (https://en.bitcoin.it/wiki/BIP_0125).
-                fee_per_vbyte.max(prev_fee + MIN_RELAY_FEE_PER_VBYTE)
+                priority_fee_per_vbyte.max(prev_fee + MIN_RELAY_FEE_PER_VBYTE)
             }
-            None => fee_per_vbyte,
+            None => priority_fee_per_vbyte,
         };
 
-        let outputs = submitted_tx
-            .requests
-            .iter()
-            .map(|req| (req.address.clone(), req.amount))
-            .collect();
+        let outputs = replacement_outputs(&submitted_tx);
+
+        let pending_fee_bump = submitted_tx.pending_fee_bump();
+        if pending_fee_bump > 0 {
+            // Spend the fee bump on top of the regular replacement fee.
+            let mut scratch_utxos = utxos.clone();
+            if let Ok((unsigned_tx, _, _)) = build_unsigned_transaction(
+                &mut scratch_utxos,
+                outputs.clone(),
+                main_address.clone(),
+                tx_fee_per_vbyte,
+            ) {
+                let vsize = fake_sign(&unsigned_tx).vsize() as u64;
+                tx_fee_per_vbyte += pending_fee_bump * 1000 / vsize;
+            }
+        }
 
         let (unsigned_tx, change_output, used_utxos) = match build_unsigned_transaction(
             &mut utxos,
@@ -668,6 +735,7 @@ async fn finalize_requests() {
         let maybe_signed_tx = sign_transaction(
             key_name.clone(),
             &ecdsa_public_key,
+            schnorr_public_key.as_ref(),
             &outpoint_account,
             unsigned_tx,
         )
@@ -712,6 +780,8 @@ async fn finalize_requests() {
                     submitted_at: ic_cdk::api::time(),
                     change_output: Some(change_output),
                     fee_per_vbyte: Some(tx_fee_per_vbyte),
+                    fee_bump: submitted_tx.fee_bump,
+                    applied_fee_bump: submitted_tx.fee_bump,
                 };
 
                 state::mutate_state(|s| {
@@ -730,26 +800,50 @@ async fn finalize_requests() {
     }
 }
 
-/// Builds the minimal OutPoint -> Account map required to sign a transaction.
+/// Returns the outputs of a replacement for the given transaction. The outputs
+/// include the fee bump that users paid to accelerate the transaction, so that
+/// the bump covers the additional fee instead of the withdrawn amounts.
+fn replacement_outputs(tx: &state::SubmittedBtcTransaction) -> Vec<(BitcoinAddress, Satoshi)> {
+    let outputs: Vec<_> = tx.requests.iter().flat_map(|req| req.outputs()).collect();
+    let bump_shares = distribute(tx.fee_bump, outputs.len() as u64);
+    outputs
+        .into_iter()
+        .zip(bump_shares)
+        .map(|((address, amount), share)| (address, amount + share))
+        .collect()
+}
+
+/// Fetches the Schnorr public key if the minter holds Taproot UTXOs that it
+/// might need to sign.
+async fn init_schnorr_public_key_if_needed() -> Option<ECDSAPublicKey> {
+    if state::read_state(|s| s.p2tr_outpoints.is_empty()) {
+        return None;
+    }
+    Some(updates::get_btc_address::init_schnorr_public_key().await)
+}
+
+/// Builds the minimal OutPoint -> (Account, AddressType) map required to sign
+/// a transaction.
 fn filter_output_accounts(
     state: &state::CkBtcMinterState,
     unsigned_tx: &tx::UnsignedTransaction,
-) -> BTreeMap<OutPoint, Account> {
+) -> BTreeMap<OutPoint, (Account, AddressType)> {
     unsigned_tx
         .inputs
         .iter()
         .map(|input| {
+            let account = *state
+                .outpoint_account
+                .get(&input.previous_output)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "bug: missing account for output point {:?}",
+                        input.previous_output
+                    )
+                });
             (
                 input.previous_output.clone(),
-                *state
-                    .outpoint_account
-                    .get(&input.previous_output)
-                    .unwrap_or_else(|| {
-                        panic!(
-                            "bug: missing account for output point {:?}",
-                            input.previous_output
-                        )
-                    }),
+                (account, state.outpoint_address_type(&input.previous_output)),
             )
         })
         .collect()
@@ -828,46 +922,104 @@ fn greedy(target: u64, available_utxos: &mut BTreeSet<Utxo>) -> Vec<Utxo> {
     solution
 }
 
-/// Gathers ECDSA signatures for all the inputs in the specified unsigned
-/// transaction.
+/// Gathers signatures for all the inputs in the specified unsigned
+/// transaction: ECDSA signatures for P2WPKH inputs and BIP-340 signatures for
+/// Taproot inputs.
 ///
 /// # Panics
 ///
 /// This function panics if the `output_account` map does not have an entry for
-/// at least one of the transaction previous output points.
+/// at least one of the transaction previous output points, or if the
+/// transaction spends a Taproot output and the Schnorr public key is missing.
 pub async fn sign_transaction(
     key_name: String,
     ecdsa_public_key: &ECDSAPublicKey,
-    output_account: &BTreeMap<tx::OutPoint, Account>,
+    schnorr_public_key: Option<&ECDSAPublicKey>,
+    output_account: &BTreeMap<tx::OutPoint, (Account, AddressType)>,
     unsigned_tx: tx::UnsignedTransaction,
 ) -> Result<tx::SignedTransaction, management::CallError> {
-    use crate::address::{derivation_path, derive_public_key};
+    use crate::address::{derivation_path, derive_p2tr_output_key, derive_public_key};
+
+    let input_accounts: Vec<(Account, AddressType)> = unsigned_tx
+        .inputs
+        .iter()
+        .map(|input| {
+            *output_account
+                .get(&input.previous_output)
+                .unwrap_or_else(|| {
+                    panic!("bug: no account for outpoint {:?}", input.previous_output)
+                })
+        })
+        .collect();
 
-    let mut signed_inputs = Vec::with_capacity(unsigned_tx.inputs.len());
     let sighasher = tx::TxSigHasher::new(&unsigned_tx);
-    for input in &unsigned_tx.inputs {
-        let outpoint = &input.previous_output;
+    // The Taproot signature message commits to all the spent outputs, so we
+    // only compute it if the transaction spends a Taproot output.
+    let taproot_sighasher = if input_accounts
+        .iter()
+        .any(|(_, address_type)| *address_type == AddressType::P2tr)
+    {
+        let schnorr_public_key =
+            schnorr_public_key.expect("bug: the Schnorr public key must be initialized");
+        let spent_addresses: Vec<BitcoinAddress> = input_accounts
+            .iter()
+            .map(|(account, address_type)| match address_type {
+                AddressType::P2wpkh => {
+                    address::account_to_bitcoin_address(ecdsa_public_key, account)
+                }
+                AddressType::P2tr => {
+                    BitcoinAddress::P2trV1(derive_p2tr_output_key(schnorr_public_key, account))
+                }
+            })
+            .collect();
+        Some(tx::TaprootSigHasher::new(&unsigned_tx, &spent_addresses))
+    } else {
+        None
+    };
 
-        let account = output_account
-            .get(outpoint)
-            .unwrap_or_else(|| panic!("bug: no account for outpoint {:?}", outpoint));
+    let mut signed_inputs = Vec::with_capacity(unsigned_tx.inputs.len());
+    for (index, (input, (account, address_type))) in unsigned_tx
+        .inputs
+        .iter()
+        .zip(input_accounts.iter())
+        .enumerate()
+    {
+        let path = DerivationPath::new(derivation_path(account));
 
-        let path = derivation_path(account);
-        let pubkey = ByteBuf::from(derive_public_key(ecdsa_public_key, account).public_key);
-        let pkhash = tx::hash160(&pubkey);
+        match address_type {
+            AddressType::P2wpkh => {
+                let pubkey = ByteBuf::from(derive_public_key(ecdsa_public_key, account).public_key);
+                let pkhash = tx::hash160(&pubkey);
 
-        let sighash = sighasher.sighash(input, &pkhash);
+                let sighash = sighasher.sighash(input, &pkhash);
 
-        let sec1_signature =
-            management::sign_with_ecdsa(key_name.clone(), DerivationPath::new(path), sighash)
-                .await?;
+                let sec1_signature =
+                    management::sign_with_ecdsa(key_name.clone(), path, sighash).await?;
 
-        signed_inputs.push(tx::SignedInput {
-            signature: signature::EncodedSignature::from_sec1(&sec1_signature),
-            pubkey,
-            previous_output: outpoint.clone(),
-            sequence: input.sequence,
-        });
+                signed_inputs.push(tx::SignedInput {
+                    signature: signature::EncodedSignature::from_sec1(&sec1_signature),
+                    pubkey,
+                    previous_output: input.previous_output.clone(),
+                    sequence: input.sequence,
+                });
+            }
+            AddressType::P2tr => {
+                let sighash = taproot_sighasher
+                    .as_ref()
+                    .expect("bug: missing Taproot sighasher")
+                    .sighash(index);
+
+                let bip340_signature =
+                    management::sign_with_schnorr(key_name.clone(), path, sighash).await?;
+
+                signed_inputs.push(tx::SignedInput {
+                    signature: signature::EncodedSignature::from_bip340(&bip340_signature),
+                    pubkey: ByteBuf::new(),
+                    previous_output: input.previous_output.clone(),
+                    sequence: input.sequence,
+                });
+            }
+        }
     }
     Ok(tx::SignedTransaction {
         inputs: signed_inputs,
@@ -876,6 +1028,10 @@ pub async fn sign_transaction(
     })
 }
 
+/// Signs all the inputs with the longest possible P2WPKH witness.
+///
+/// Taproot key-path witnesses are shorter, so the size of a fake-signed
+/// transaction is an upper bound for the size of the real one.
 pub fn fake_sign(unsigned_tx: &tx::UnsignedTransaction) -> tx::SignedTransaction {
     tx::SignedTransaction {
         inputs: unsigned_tx
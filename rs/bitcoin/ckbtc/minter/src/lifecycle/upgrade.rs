@@ -32,6 +32,11 @@ pub struct UpgradeArgs {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kyt_principal: Option<CanisterId>,
+
+    /// Enables Taproot deposit addresses derived with the threshold Schnorr
+    /// API.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_p2tr_deposits: Option<bool>,
 }
 
 pub fn post_upgrade(upgrade_args: Option<UpgradeArgs>) {
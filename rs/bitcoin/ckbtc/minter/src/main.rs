@@ -13,7 +13,8 @@ use ic_ckbtc_minter::state::{
 };
 use ic_ckbtc_minter::tasks::{schedule_now, TaskType};
 use ic_ckbtc_minter::updates::retrieve_btc::{
-    RetrieveBtcArgs, RetrieveBtcError, RetrieveBtcOk, RetrieveBtcWithApprovalArgs,
+    AccelerateRetrieveBtcArgs, RetrieveBtcArgs, RetrieveBtcBatchArgs,
+    RetrieveBtcBatchWithApprovalArgs, RetrieveBtcError, RetrieveBtcOk, RetrieveBtcWithApprovalArgs,
     RetrieveBtcWithApprovalError,
 };
 use ic_ckbtc_minter::updates::{
@@ -21,11 +22,11 @@ use ic_ckbtc_minter::updates::{
     get_btc_address::GetBtcAddressArgs,
     update_balance::{UpdateBalanceArgs, UpdateBalanceError, UtxoStatus},
 };
-use ic_ckbtc_minter::MinterInfo;
 use ic_ckbtc_minter::{
     state::eventlog::{Event, GetEventsArg},
     storage, {Log, LogEntry, Priority},
 };
+use ic_ckbtc_minter::{MinterInfo, MinterPublicKeys};
 use icrc_ledger_types::icrc1::account::Account;
 use std::str::FromStr;
 
@@ -156,6 +157,28 @@ async fn retrieve_btc_with_approval(
     check_postcondition(updates::retrieve_btc::retrieve_btc_with_approval(args).await)
 }
 
+#[update]
+async fn retrieve_btc_batch(args: RetrieveBtcBatchArgs) -> Result<RetrieveBtcOk, RetrieveBtcError> {
+    check_anonymous_caller();
+    check_postcondition(updates::retrieve_btc::retrieve_btc_batch(args).await)
+}
+
+#[update]
+async fn retrieve_btc_batch_with_approval(
+    args: RetrieveBtcBatchWithApprovalArgs,
+) -> Result<RetrieveBtcOk, RetrieveBtcWithApprovalError> {
+    check_anonymous_caller();
+    check_postcondition(updates::retrieve_btc::retrieve_btc_batch_with_approval(args).await)
+}
+
+#[update]
+async fn accelerate_retrieve_btc(
+    args: AccelerateRetrieveBtcArgs,
+) -> Result<RetrieveBtcOk, RetrieveBtcError> {
+    check_anonymous_caller();
+    check_postcondition(updates::retrieve_btc::accelerate_retrieve_btc(args).await)
+}
+
 #[query]
 fn retrieve_btc_status(req: RetrieveBtcStatusRequest) -> RetrieveBtcStatus {
     read_state(|s| s.retrieve_btc_status(req.block_index))
@@ -220,6 +243,14 @@ fn get_minter_info() -> MinterInfo {
     })
 }
 
+#[query]
+fn get_public_keys() -> MinterPublicKeys {
+    read_state(|s| MinterPublicKeys {
+        ecdsa_public_key: s.ecdsa_public_key.clone(),
+        schnorr_public_key: s.schnorr_public_key.clone(),
+    })
+}
+
 #[query]
 fn get_deposit_fee() -> u64 {
     read_state(|s| s.kyt_fee)
@@ -13,7 +13,8 @@ use ic_cdk::api::call::RejectionCode;
 use ic_ckbtc_kyt::{DepositRequest, Error as KytError, FetchAlertsResponse, WithdrawalAttempt};
 use ic_management_canister_types::{
     DerivationPath, ECDSAPublicKeyArgs, ECDSAPublicKeyResponse, EcdsaCurve, EcdsaKeyId,
-    SignWithECDSAArgs, SignWithECDSAReply,
+    SchnorrAlgorithm, SchnorrKeyId, SchnorrPublicKeyArgs, SchnorrPublicKeyResponse,
+    SignWithECDSAArgs, SignWithECDSAReply, SignWithSchnorrArgs, SignWithSchnorrReply,
 };
 use serde::de::DeserializeOwned;
 use std::fmt;
@@ -293,6 +294,56 @@ pub async fn sign_with_ecdsa(
     Ok(reply.signature)
 }
 
+/// Fetches the BIP-340 public key of this canister at the given derivation
+/// path from the threshold Schnorr API.
+pub async fn schnorr_public_key(
+    key_name: String,
+    derivation_path: DerivationPath,
+) -> Result<ECDSAPublicKey, CallError> {
+    call(
+        "schnorr_public_key",
+        /*payment=*/ 0,
+        &SchnorrPublicKeyArgs {
+            canister_id: None,
+            derivation_path,
+            key_id: SchnorrKeyId {
+                algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+                name: key_name,
+            },
+        },
+    )
+    .await
+    .map(|response: SchnorrPublicKeyResponse| ECDSAPublicKey {
+        public_key: response.public_key,
+        chain_code: response.chain_code,
+    })
+}
+
+/// Signs a message using the threshold Schnorr API and returns a 64-byte
+/// BIP-340 signature.
+pub async fn sign_with_schnorr(
+    key_name: String,
+    derivation_path: DerivationPath,
+    message: [u8; 32],
+) -> Result<Vec<u8>, CallError> {
+    const CYCLES_PER_SIGNATURE: u64 = 25_000_000_000;
+
+    let reply: SignWithSchnorrReply = call(
+        "sign_with_schnorr",
+        CYCLES_PER_SIGNATURE,
+        &SignWithSchnorrArgs {
+            message: message.to_vec(),
+            derivation_path,
+            key_id: SchnorrKeyId {
+                algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+                name: key_name,
+            },
+        },
+    )
+    .await?;
+    Ok(reply.signature)
+}
+
 /// Requests alerts for the given UTXO.
 pub async fn fetch_utxo_alerts(
     kyt_principal: Principal,
@@ -69,4 +69,11 @@ pub enum BurnMemo<'a> {
         /// The status of the KYT check.
         status: Option<Status>,
     },
+    #[n(1)]
+    /// The user increased the fee of a submitted retrieve_btc request.
+    Accelerate {
+        #[n(0)]
+        /// The burn block index of the accelerated retrieve_btc request.
+        retrieve_btc_block_index: Option<u64>,
+    },
 }
@@ -43,6 +43,22 @@ impl EncodedSignature {
         Self(Cow::Owned(sig))
     }
 
+    /// Wraps a 64-byte BIP-340 signature for a Taproot key-path spend.
+    ///
+    /// Signatures with the SIGHASH_DEFAULT type carry no sighash byte.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the signature is not 64 bytes long.
+    pub fn from_bip340(signature: &[u8]) -> Self {
+        assert_eq!(
+            signature.len(),
+            64,
+            "BIP-340 signatures must be 64 bytes long"
+        );
+        Self(Cow::Owned(signature.to_vec()))
+    }
+
     /// Returns the longest valid encoded signature.
     pub fn fake() -> Self {
         Self(Cow::Borrowed(&FAKE_SIG[..]))
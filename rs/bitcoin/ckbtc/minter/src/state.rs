@@ -14,7 +14,10 @@ pub mod eventlog;
 use crate::lifecycle::init::InitArgs;
 use crate::lifecycle::upgrade::UpgradeArgs;
 use crate::logs::P0;
-use crate::{address::BitcoinAddress, ECDSAPublicKey};
+use crate::{
+    address::{AddressType, BitcoinAddress},
+    ECDSAPublicKey,
+};
 use candid::{CandidType, Deserialize, Principal};
 use ic_base_types::CanisterId;
 pub use ic_btc_interface::Network;
@@ -56,6 +59,70 @@ pub struct RetrieveBtcRequest {
     #[serde(rename = "reimbursement_account")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reimbursement_account: Option<Account>,
+    /// All destinations of a request accepted by the retrieve_btc_batch
+    /// endpoints. For such requests, `address` is the first destination and
+    /// `amount` is the total amount of all outputs.
+    #[serde(rename = "batch_outputs")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_outputs: Option<Vec<RetrieveBtcOutput>>,
+    /// The fee priority requested by the user.
+    /// The minter uses the median fee rate if the field is not set.
+    #[serde(rename = "fee_priority")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_priority: Option<FeePriority>,
+}
+
+impl RetrieveBtcRequest {
+    /// Returns the transaction outputs that fulfill this request.
+    pub fn outputs(&self) -> Vec<(BitcoinAddress, u64)> {
+        match &self.batch_outputs {
+            Some(outputs) => outputs
+                .iter()
+                .map(|output| (output.address.clone(), output.amount))
+                .collect(),
+            None => vec![(self.address.clone(), self.amount)],
+        }
+    }
+
+    /// Returns the fee priority of this request.
+    pub fn fee_priority(&self) -> FeePriority {
+        self.fee_priority.unwrap_or_default()
+    }
+
+    /// Returns the number of transaction outputs that fulfill this request.
+    pub fn num_outputs(&self) -> usize {
+        self.batch_outputs
+            .as_ref()
+            .map_or(1, |outputs| outputs.len())
+    }
+}
+
+/// The fee rate the minter uses for transactions fulfilling a retrieve_btc
+/// request, relative to the current Bitcoin network fee percentiles.
+#[derive(
+    Copy, Clone, Eq, PartialEq, Debug, Default, Deserialize, Serialize, candid::CandidType,
+)]
+pub enum FeePriority {
+    /// The 25th percentile of the fees, but not more than the median.
+    #[serde(rename = "low")]
+    Low,
+    /// The median fee.
+    #[default]
+    #[serde(rename = "medium")]
+    Medium,
+    /// The 75th percentile of the fees, but not less than the median.
+    #[serde(rename = "high")]
+    High,
+}
+
+/// A single destination of a batched retrieve_btc request.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize, candid::CandidType)]
+pub struct RetrieveBtcOutput {
+    /// The destination BTC address.
+    pub address: BitcoinAddress,
+    /// The amount to send to the address, the minter withdraws a share of the
+    /// BTC transfer fees from this amount.
+    pub amount: u64,
 }
 
 /// A transaction output storing the minter's change.
@@ -84,6 +151,22 @@ pub struct SubmittedBtcTransaction {
     /// Fee per vbyte in millisatoshi.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fee_per_vbyte: Option<u64>,
+    /// The total amount of ckBTC that users burned to accelerate this
+    /// transaction.
+    #[serde(default)]
+    pub fee_bump: u64,
+    /// The part of the `fee_bump` that the minter already added to the fee of
+    /// this transaction. The minter replaces the transaction as soon as
+    /// possible if this amount is lower than `fee_bump`.
+    #[serde(default)]
+    pub applied_fee_bump: u64,
+}
+
+impl SubmittedBtcTransaction {
+    /// Returns the part of the fee bump that still needs to be applied.
+    pub fn pending_fee_bump(&self) -> u64 {
+        self.fee_bump.saturating_sub(self.applied_fee_bump)
+    }
 }
 
 /// Pairs a retrieve_btc request with its outcome.
@@ -276,6 +359,17 @@ pub struct CkBtcMinterState {
     /// The Minter ECDSA public key
     pub ecdsa_public_key: Option<ECDSAPublicKey>,
 
+    /// The Minter BIP-340 public key used to derive Taproot deposit addresses.
+    pub schnorr_public_key: Option<ECDSAPublicKey>,
+
+    /// Whether the minter issues new Taproot deposit addresses.
+    pub p2tr_deposits_enabled: bool,
+
+    /// Whether Taproot deposits were ever enabled, i.e., whether the minter
+    /// might have issued Taproot deposit addresses. The minter keeps scanning
+    /// these addresses even if it no longer issues new ones.
+    pub p2tr_deposits_ever_enabled: bool,
+
     /// The minimum number of confirmations on the Bitcoin chain.
     pub min_confirmations: u32,
 
@@ -342,6 +436,10 @@ pub struct CkBtcMinterState {
     /// belong.
     pub outpoint_account: BTreeMap<OutPoint, Account>,
 
+    /// The output points received on Taproot deposit addresses. All other
+    /// output points belong to P2WPKH addresses.
+    pub p2tr_outpoints: BTreeSet<OutPoint>,
+
     /// The map of known addresses to their utxos.
     pub utxos_state_addresses: BTreeMap<Account, BTreeSet<Utxo>>,
 
@@ -453,6 +551,7 @@ impl CkBtcMinterState {
             mode,
             kyt_principal,
             kyt_fee,
+            enable_p2tr_deposits,
         }: UpgradeArgs,
     ) {
         if let Some(retrieve_btc_min_amount) = retrieve_btc_min_amount {
@@ -482,6 +581,10 @@ impl CkBtcMinterState {
         if let Some(kyt_fee) = kyt_fee {
             self.kyt_fee = kyt_fee;
         }
+        if let Some(enable_p2tr_deposits) = enable_p2tr_deposits {
+            self.p2tr_deposits_enabled = enable_p2tr_deposits;
+            self.p2tr_deposits_ever_enabled |= enable_p2tr_deposits;
+        }
     }
 
     pub fn validate_config(&self) {
@@ -524,6 +627,14 @@ impl CkBtcMinterState {
             }
         }
 
+        for outpoint in self.p2tr_outpoints.iter() {
+            ensure!(
+                self.outpoint_account.contains_key(outpoint),
+                "the output_account map is missing an entry for Taproot outpoint {:?}",
+                outpoint
+            );
+        }
+
         for (l, r) in self
             .pending_retrieve_btc_requests
             .iter()
@@ -602,6 +713,22 @@ impl CkBtcMinterState {
             .expect("state invariants are violated");
     }
 
+    /// Adds UTXOs received on the Taproot deposit address of the account.
+    pub(crate) fn add_p2tr_utxos(&mut self, account: Account, utxos: Vec<Utxo>) {
+        self.p2tr_outpoints
+            .extend(utxos.iter().map(|utxo| utxo.outpoint.clone()));
+        self.add_utxos(account, utxos);
+    }
+
+    /// Returns the type of the address that received the specified output.
+    pub fn outpoint_address_type(&self, outpoint: &OutPoint) -> AddressType {
+        if self.p2tr_outpoints.contains(outpoint) {
+            AddressType::P2tr
+        } else {
+            AddressType::P2wpkh
+        }
+    }
+
     pub fn retrieve_btc_status_v2_by_account(
         &self,
         target: Option<Account>,
@@ -709,16 +836,29 @@ impl CkBtcMinterState {
     }
 
     /// Forms a batch of retrieve_btc requests that the minter can fulfill.
+    /// The batch contains at most `max_size` transaction outputs and only
+    /// requests with the same fee priority.
     pub fn build_batch(&mut self, max_size: usize) -> Vec<RetrieveBtcRequest> {
         let available_utxos_value = self.available_utxos.iter().map(|u| u.value).sum::<u64>();
         let mut batch = vec![];
         let mut tx_amount = 0;
+        let mut tx_outputs = 0;
+        // All requests in a transaction share the same fee rate, so we batch
+        // only requests with the priority of the oldest request.
+        let fee_priority = match self.pending_retrieve_btc_requests.first() {
+            Some(req) => req.fee_priority(),
+            None => return batch,
+        };
         for req in std::mem::take(&mut self.pending_retrieve_btc_requests) {
-            if available_utxos_value < req.amount + tx_amount || batch.len() >= max_size {
+            if available_utxos_value < req.amount + tx_amount
+                || tx_outputs + req.num_outputs() > max_size
+                || req.fee_priority() != fee_priority
+            {
                 // Put this request back to the queue until we have enough liquid UTXOs.
                 self.pending_retrieve_btc_requests.push(req);
             } else {
                 tx_amount += req.amount;
+                tx_outputs += req.num_outputs();
                 batch.push(req);
             }
         }
@@ -747,6 +887,7 @@ impl CkBtcMinterState {
     }
 
     fn forget_utxo(&mut self, utxo: &Utxo) {
+        self.p2tr_outpoints.remove(&utxo.outpoint);
         if let Some(account) = self.outpoint_account.remove(&utxo.outpoint) {
             if self.update_balance_principals.contains(&account.owner) {
                 self.finalized_utxos
@@ -872,6 +1013,9 @@ impl CkBtcMinterState {
             .position(|tx| &tx.txid == old_txid)
             .expect("BUG: attempted to replace an unknown transaction");
 
+        // The users might have accelerated the transaction while we were
+        // building its replacement.
+        tx.fee_bump = self.submitted_transactions[pos].fee_bump;
         std::mem::swap(&mut self.submitted_transactions[pos], &mut tx);
         // tx points to the old transaction now.
         debug_assert_eq!(&tx.txid, old_txid);
@@ -946,7 +1090,8 @@ impl CkBtcMinterState {
         }
         self.tokens_burned += request.amount;
         if let Some(kyt_provider) = request.kyt_provider {
-            *self.owed_kyt_amount.entry(kyt_provider).or_insert(0) += self.kyt_fee;
+            *self.owed_kyt_amount.entry(kyt_provider).or_insert(0) +=
+                self.kyt_fee * request.num_outputs() as u64;
         }
         self.pending_retrieve_btc_requests.push(request);
     }
@@ -966,6 +1111,20 @@ impl CkBtcMinterState {
         self.submitted_transactions.push(tx);
     }
 
+    /// Adds the specified amount to the fee bump of the submitted transaction
+    /// fulfilling the retrieve_btc request with the given identifier. Returns
+    /// the identifier of the transaction, or None if the request is not part
+    /// of a submitted transaction.
+    pub fn accelerate_transaction(&mut self, block_index: u64, amount: u64) -> Option<Txid> {
+        let tx = self
+            .submitted_transactions
+            .iter_mut()
+            .find(|tx| tx.requests.iter().any(|r| r.block_index == block_index))?;
+        tx.fee_bump += amount;
+        self.tokens_burned += amount;
+        Some(tx.txid)
+    }
+
     /// Marks the specified retrieve_btc request as finalized.
     ///
     /// # Panics
@@ -1140,6 +1299,21 @@ impl CkBtcMinterState {
             other.utxos_state_addresses,
             "utxos_state_addresses do not match"
         );
+        ensure_eq!(
+            self.p2tr_outpoints,
+            other.p2tr_outpoints,
+            "p2tr_outpoints do not match"
+        );
+        ensure_eq!(
+            self.p2tr_deposits_enabled,
+            other.p2tr_deposits_enabled,
+            "p2tr_deposits_enabled does not match"
+        );
+        ensure_eq!(
+            self.p2tr_deposits_ever_enabled,
+            other.p2tr_deposits_ever_enabled,
+            "p2tr_deposits_ever_enabled does not match"
+        );
         ensure_eq!(
             self.quarantined_utxos,
             other.quarantined_utxos,
@@ -1229,6 +1403,9 @@ impl From<InitArgs> for CkBtcMinterState {
             btc_network: args.btc_network.into(),
             ecdsa_key_name: args.ecdsa_key_name,
             ecdsa_public_key: None,
+            schnorr_public_key: None,
+            p2tr_deposits_enabled: false,
+            p2tr_deposits_ever_enabled: false,
             min_confirmations: args
                 .min_confirmations
                 .unwrap_or(crate::lifecycle::init::DEFAULT_MIN_CONFIRMATIONS),
@@ -1252,6 +1429,7 @@ impl From<InitArgs> for CkBtcMinterState {
             kyt_principal: args.kyt_principal,
             available_utxos: Default::default(),
             outpoint_account: Default::default(),
+            p2tr_outpoints: Default::default(),
             utxos_state_addresses: Default::default(),
             finalized_utxos: Default::default(),
             is_timer_running: false,
@@ -4,6 +4,7 @@ use super::{
     eventlog::Event, CkBtcMinterState, FinalizedBtcRetrieval, FinalizedStatus, RetrieveBtcRequest,
     SubmittedBtcTransaction, UtxoCheckStatus,
 };
+use crate::address::AddressType;
 use crate::state::{ReimburseDepositTask, ReimbursedDeposit};
 use crate::storage::record_event;
 use crate::ReimbursementReason;
@@ -22,7 +23,8 @@ pub fn accept_retrieve_btc_request(state: &mut CkBtcMinterState, request: Retrie
             .or_insert(vec![request.block_index]);
     }
     if let Some(kyt_provider) = request.kyt_provider {
-        *state.owed_kyt_amount.entry(kyt_provider).or_insert(0) += state.kyt_fee;
+        *state.owed_kyt_amount.entry(kyt_provider).or_insert(0) +=
+            state.kyt_fee * request.num_outputs() as u64;
     }
 }
 
@@ -31,14 +33,22 @@ pub fn add_utxos(
     mint_txid: Option<u64>,
     account: Account,
     utxos: Vec<Utxo>,
+    address_type: AddressType,
 ) {
     record_event(&Event::ReceivedUtxos {
         mint_txid,
         to_account: account,
         utxos: utxos.clone(),
+        address_type: match address_type {
+            AddressType::P2wpkh => None,
+            AddressType::P2tr => Some(AddressType::P2tr),
+        },
     });
 
-    state.add_utxos(account, utxos);
+    match address_type {
+        AddressType::P2wpkh => state.add_utxos(account, utxos),
+        AddressType::P2tr => state.add_p2tr_utxos(account, utxos),
+    }
 }
 
 pub fn remove_retrieve_btc_request(state: &mut CkBtcMinterState, request: RetrieveBtcRequest) {
@@ -107,6 +117,7 @@ pub fn replace_transaction(
         fee_per_vbyte: new_tx
             .fee_per_vbyte
             .expect("bug: all replacement transactions must have the fee"),
+        applied_fee_bump: new_tx.applied_fee_bump,
     });
     state.replace_transaction(&old_txid, new_tx);
 }
@@ -133,6 +144,7 @@ pub fn retrieve_btc_kyt_failed(
     kyt_provider: Principal,
     uuid: String,
     block_index: u64,
+    kyt_fee: Option<u64>,
 ) {
     record_event(&Event::RetrieveBtcKytFailed {
         owner,
@@ -141,8 +153,25 @@ pub fn retrieve_btc_kyt_failed(
         kyt_provider,
         uuid,
         block_index,
+        kyt_fee,
+    });
+    *state.owed_kyt_amount.entry(kyt_provider).or_insert(0) += kyt_fee.unwrap_or(state.kyt_fee);
+}
+
+pub fn accelerate_retrieve_btc_request(
+    state: &mut CkBtcMinterState,
+    block_index: u64,
+    amount: u64,
+    burn_block_index: u64,
+) {
+    record_event(&Event::AcceleratedRetrieveBtcRequest {
+        block_index,
+        amount,
+        burn_block_index,
     });
-    *state.owed_kyt_amount.entry(kyt_provider).or_insert(0) += state.kyt_fee;
+    state
+        .accelerate_transaction(block_index, amount)
+        .expect("bug: accelerated request must be part of a submitted transaction");
 }
 
 pub fn schedule_deposit_reimbursement(
@@ -1,3 +1,4 @@
+use crate::address::AddressType;
 use crate::lifecycle::init::InitArgs;
 use crate::lifecycle::upgrade::UpgradeArgs;
 use crate::state::{
@@ -41,6 +42,11 @@ pub enum Event {
         to_account: Account,
         #[serde(rename = "utxos")]
         utxos: Vec<Utxo>,
+        /// The type of the deposit address that received the UTXOs.
+        /// Absent for P2WPKH addresses.
+        #[serde(rename = "address_type")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        address_type: Option<AddressType>,
     },
 
     /// Indicates that the minter accepted a new retrieve_btc request.
@@ -102,6 +108,10 @@ pub enum Event {
         /// The fee per vbyte (in millisatoshi) that we used for the transaction.
         #[serde(rename = "fee")]
         fee_per_vbyte: u64,
+        /// The part of the fee bump that the minter added to the transaction fee.
+        #[serde(rename = "applied_fee_bump")]
+        #[serde(default, skip_serializing_if = "is_zero")]
+        applied_fee_bump: u64,
     },
 
     /// Indicates that the minter received enough confirmations for a bitcoin
@@ -154,6 +164,25 @@ pub enum Event {
         kyt_provider: Principal,
         /// The block index where the failed check occurred.
         block_index: u64,
+        /// The KYT fee burned for all the checks performed for a batch. The
+        /// KYT fee of a single check if absent.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        kyt_fee: Option<u64>,
+    },
+
+    /// Indicates that the user burned additional ckBTC to accelerate the
+    /// transaction fulfilling a retrieve_btc request.
+    #[serde(rename = "accelerated_retrieve_btc_request")]
+    AcceleratedRetrieveBtcRequest {
+        /// The identifier of the accelerated retrieve_btc request.
+        #[serde(rename = "block_index")]
+        block_index: u64,
+        /// The amount of ckBTC added to the transaction fee.
+        #[serde(rename = "amount")]
+        amount: u64,
+        /// The burn block on the ledger.
+        #[serde(rename = "burn_block_index")]
+        burn_block_index: u64,
     },
 
     /// Indicates a reimbursement.
@@ -179,6 +208,10 @@ pub enum Event {
     },
 }
 
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
 #[derive(Debug)]
 pub enum ReplayLogError {
     /// There are no events in the event log.
@@ -207,8 +240,14 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
             }
             Event::Upgrade(args) => state.upgrade(args),
             Event::ReceivedUtxos {
-                to_account, utxos, ..
-            } => state.add_utxos(to_account, utxos),
+                to_account,
+                utxos,
+                address_type,
+                ..
+            } => match address_type.unwrap_or_default() {
+                AddressType::P2wpkh => state.add_utxos(to_account, utxos),
+                AddressType::P2tr => state.add_p2tr_utxos(to_account, utxos),
+            },
             Event::AcceptedRetrieveBtcRequest(req) => {
                 if let Some(account) = req.reimbursement_account {
                     state
@@ -260,6 +299,8 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
                     fee_per_vbyte,
                     change_output,
                     submitted_at,
+                    fee_bump: 0,
+                    applied_fee_bump: 0,
                 });
             }
             Event::ReplacedBtcTransaction {
@@ -268,6 +309,7 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
                 change_output,
                 submitted_at,
                 fee_per_vbyte,
+                applied_fee_bump,
             } => {
                 let (requests, used_utxos) = match state
                     .submitted_transactions
@@ -292,6 +334,8 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
                         change_output: Some(change_output),
                         submitted_at,
                         fee_per_vbyte: Some(fee_per_vbyte),
+                        fee_bump: 0,
+                        applied_fee_bump,
                     },
                 );
             }
@@ -333,8 +377,25 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
                     return Err(ReplayLogError::InconsistentLog(format!("Attempted to distribute {amount} to {kyt_provider}, causing an overdraft of {overdraft}")));
                 }
             }
-            Event::RetrieveBtcKytFailed { kyt_provider, .. } => {
-                *state.owed_kyt_amount.entry(kyt_provider).or_insert(0) += state.kyt_fee;
+            Event::RetrieveBtcKytFailed {
+                kyt_provider,
+                kyt_fee,
+                ..
+            } => {
+                *state.owed_kyt_amount.entry(kyt_provider).or_insert(0) +=
+                    kyt_fee.unwrap_or(state.kyt_fee);
+            }
+            Event::AcceleratedRetrieveBtcRequest {
+                block_index,
+                amount,
+                ..
+            } => {
+                if state.accelerate_transaction(block_index, amount).is_none() {
+                    return Err(ReplayLogError::InconsistentLog(format!(
+                        "Attempted to accelerate a non-submitted retrieve_btc request {}",
+                        block_index
+                    )));
+                }
             }
             Event::ScheduleDepositReimbursement {
                 account,
@@ -1,13 +1,13 @@
 use crate::MINTER_FEE_CONSTANT;
 use crate::{
-    address::BitcoinAddress, build_unsigned_transaction, estimate_fee, fake_sign, greedy,
-    signature::EncodedSignature, tx, BuildTxError,
+    address::BitcoinAddress, build_unsigned_transaction, estimate_fee, fake_sign,
+    fee_per_vbyte_for_priority, greedy, signature::EncodedSignature, tx, BuildTxError,
 };
 use crate::{
     lifecycle::init::InitArgs,
     state::{
-        ChangeOutput, CkBtcMinterState, Mode, RetrieveBtcRequest, RetrieveBtcStatus,
-        SubmittedBtcTransaction,
+        ChangeOutput, CkBtcMinterState, FeePriority, Mode, RetrieveBtcOutput, RetrieveBtcRequest,
+        RetrieveBtcStatus, SubmittedBtcTransaction,
     },
 };
 use bitcoin::network::constants::Network as BtcNetwork;
@@ -22,7 +22,7 @@ use proptest::{
     array::uniform32,
     collection::{btree_set, vec as pvec, SizeRange},
     option,
-    prelude::{any, Strategy},
+    prelude::{any, Just, Strategy},
 };
 use proptest::{prop_assert, prop_assert_eq, prop_assume, prop_oneof};
 use serde_bytes::ByteBuf;
@@ -153,10 +153,14 @@ fn signed_tx_to_bitcoin_tx(tx: &tx::SignedTransaction) -> bitcoin::Transaction {
                 },
                 sequence: txin.sequence,
                 script_sig: bitcoin::Script::default(),
-                witness: bitcoin::Witness::from_vec(vec![
-                    txin.signature.as_slice().to_vec(),
-                    txin.pubkey.to_vec(),
-                ]),
+                witness: if txin.pubkey.is_empty() {
+                    bitcoin::Witness::from_vec(vec![txin.signature.as_slice().to_vec()])
+                } else {
+                    bitcoin::Witness::from_vec(vec![
+                        txin.signature.as_slice().to_vec(),
+                        txin.pubkey.to_vec(),
+                    ])
+                },
             })
             .collect(),
         output: tx
@@ -373,6 +377,37 @@ fn test_no_dust_outputs() {
     assert_eq!(available_utxos.len(), 1);
 }
 
+#[test]
+fn fee_priority_selects_percentile() {
+    let percentiles: Vec<u64> = (0..100).map(|i| 1_000 + i * 100).collect();
+    let median = percentiles[50];
+
+    assert_eq!(
+        fee_per_vbyte_for_priority(median, &percentiles, FeePriority::Low),
+        percentiles[25]
+    );
+    assert_eq!(
+        fee_per_vbyte_for_priority(median, &percentiles, FeePriority::Medium),
+        median
+    );
+    assert_eq!(
+        fee_per_vbyte_for_priority(median, &percentiles, FeePriority::High),
+        percentiles[75]
+    );
+
+    // The low priority never goes below the minimum relay fee, and the high
+    // priority never goes below the median fee.
+    let stale_percentiles = vec![1; 100];
+    assert_eq!(
+        fee_per_vbyte_for_priority(5_000, &stale_percentiles, FeePriority::Low),
+        crate::MIN_RELAY_FEE_PER_VBYTE
+    );
+    assert_eq!(
+        fee_per_vbyte_for_priority(5_000, &stale_percentiles, FeePriority::High),
+        5_000
+    );
+}
+
 #[test]
 fn blocklist_is_sorted() {
     use crate::blocklist::BTC_ADDRESS_BLOCKLIST;
@@ -429,6 +464,17 @@ fn arb_signed_input() -> impl Strategy<Value = tx::SignedInput> {
         )
 }
 
+fn arb_taproot_signed_input() -> impl Strategy<Value = tx::SignedInput> {
+    (arb_out_point(), any::<u32>(), pvec(any::<u8>(), 64)).prop_map(
+        |(previous_output, sequence, bip340)| tx::SignedInput {
+            previous_output,
+            sequence,
+            signature: EncodedSignature::from_bip340(&bip340),
+            pubkey: ByteBuf::new(),
+        },
+    )
+}
+
 fn arb_address() -> impl Strategy<Value = BitcoinAddress> {
     prop_oneof![
         uniform20(any::<u8>()).prop_map(BitcoinAddress::P2wpkhV0),
@@ -463,6 +509,14 @@ fn arb_account() -> impl Strategy<Value = Account> {
     })
 }
 
+fn arb_fee_priority() -> impl Strategy<Value = FeePriority> {
+    prop_oneof![
+        Just(FeePriority::Low),
+        Just(FeePriority::Medium),
+        Just(FeePriority::High),
+    ]
+}
+
 fn arb_retrieve_btc_requests(
     amount: impl Strategy<Value = Satoshi>,
     num: impl Into<SizeRange>,
@@ -474,9 +528,18 @@ fn arb_retrieve_btc_requests(
         1569975147000..2069975147000u64,
         option::of(any::<u64>()),
         option::of(arb_account()),
+        option::of(arb_fee_priority()),
     )
         .prop_map(
-            |(amount, address, block_index, received_at, provider, reimbursement_account)| {
+            |(
+                amount,
+                address,
+                block_index,
+                received_at,
+                provider,
+                reimbursement_account,
+                fee_priority,
+            )| {
                 RetrieveBtcRequest {
                     amount,
                     address,
@@ -485,6 +548,8 @@ fn arb_retrieve_btc_requests(
                     kyt_provider: provider
                         .map(|id| Principal::from(CanisterId::from_u64(id).get())),
                     reimbursement_account,
+                    batch_outputs: None,
+                    fee_priority,
                 }
             },
         );
@@ -650,9 +715,91 @@ proptest! {
         }
     }
 
+    #[test]
+    fn taproot_sighash_model(
+        inputs_data in pvec(
+            (
+                arb_utxo(5_000u64..1_000_000_000),
+                any::<u32>(),
+                arb_address()
+            ),
+            1..20
+        ),
+        outputs in pvec(arb_tx_out(), 1..20),
+        lock_time in any::<u32>(),
+    ) {
+        use bitcoin::util::sighash::{Prevouts, SchnorrSighashType};
+
+        let inputs: Vec<tx::UnsignedInput> = inputs_data
+            .iter()
+            .map(|(utxo, seq, _)| tx::UnsignedInput {
+                previous_output: utxo.outpoint.clone(),
+                value: utxo.value,
+                sequence: *seq,
+            })
+            .collect();
+        let spent_addresses: Vec<BitcoinAddress> = inputs_data
+            .iter()
+            .map(|(_, _, address)| address.clone())
+            .collect();
+        let prevouts: Vec<bitcoin::TxOut> = inputs_data
+            .iter()
+            .map(|(utxo, _, address)| bitcoin::TxOut {
+                value: utxo.value,
+                script_pubkey: address_to_script_pubkey(address),
+            })
+            .collect();
+        let arb_tx = tx::UnsignedTransaction { inputs, outputs, lock_time };
+        let btc_tx = unsigned_tx_to_bitcoin_tx(&arb_tx);
+
+        let sighasher = tx::TaprootSigHasher::new(&arb_tx, &spent_addresses);
+        let mut btc_sighasher = bitcoin::util::sighash::SighashCache::new(&btc_tx);
+
+        for i in 0..arb_tx.inputs.len() {
+            let mut buf = Vec::<u8>::new();
+            sighasher.encode_sighash_data(i, &mut buf);
+
+            let mut btc_buf = Vec::<u8>::new();
+            btc_sighasher.taproot_encode_signing_data_to(&mut btc_buf, i, &Prevouts::All(&prevouts), None, None, SchnorrSighashType::Default)
+                .expect("failed to encode taproot sighash data");
+            prop_assert_eq!(hex::encode(&buf), hex::encode(&btc_buf));
+
+            let sighash = sighasher.sighash(i);
+            let btc_sighash = btc_sighasher.taproot_key_spend_signature_hash(i, &Prevouts::All(&prevouts), SchnorrSighashType::Default).unwrap();
+            prop_assert_eq!(hex::encode(sighash), hex::encode(btc_sighash));
+        }
+    }
+
+    #[test]
+    fn p2tr_address_uses_untweaked_derived_key(
+        account in arb_account(),
+        chain_code in uniform32(any::<u8>()),
+    ) {
+        use bitcoin::secp256k1::XOnlyPublicKey;
+        use bitcoin::util::schnorr::TweakedPublicKey;
+
+        let schnorr_public_key = crate::ECDSAPublicKey {
+            public_key: hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798").unwrap(),
+            chain_code: chain_code.to_vec(),
+        };
+        let derived_key = crate::address::derive_public_key(&schnorr_public_key, &account).public_key;
+        // The threshold Schnorr API signs with the derived key as is.
+        let btc_address = bitcoin::Address::p2tr_tweaked(
+            TweakedPublicKey::dangerous_assume_tweaked(
+                XOnlyPublicKey::from_slice(&derived_key[1..]).unwrap(),
+            ),
+            BtcNetwork::Regtest,
+        );
+
+        prop_assert_eq!(
+            crate::address::account_to_p2tr_address(Network::Regtest, &schnorr_public_key, &account),
+            btc_address.to_string()
+        );
+    }
+
     #[test]
     fn signed_tx_encoding_model(
-        inputs in pvec(arb_signed_input(), 1..20),
+        inputs in pvec(prop_oneof![arb_signed_input(), arb_taproot_signed_input()], 1..20),
         outputs in pvec(arb_tx_out(), 1..20),
         lock_time in any::<u32>(),
     ) {
@@ -884,6 +1031,7 @@ proptest! {
 
         prop_assert!(batch.iter().map(|req| req.amount).sum::<u64>() <= available_amount);
         prop_assert!(batch.len() <= limit);
+        prop_assert!(batch.iter().all(|req| req.fee_priority() == batch[0].fee_priority()));
 
         state.check_invariants().expect("invariant check failed");
     }
@@ -930,6 +1078,8 @@ proptest! {
             submitted_at,
             change_output: Some(change_output),
             fee_per_vbyte: Some(fee_per_vbyte),
+            fee_bump: 0,
+            applied_fee_bump: 0,
         });
 
         state.check_invariants().expect("violated invariants");
@@ -954,6 +1104,8 @@ proptest! {
                 submitted_at,
                 change_output: Some(change_output),
                 fee_per_vbyte: Some(fee_per_vbyte),
+                fee_bump: 0,
+                applied_fee_bump: 0,
             });
 
             for txid in &txids {
@@ -1144,6 +1296,8 @@ fn can_form_a_batch_conditions() {
         received_at: 10000,
         kyt_provider: None,
         reimbursement_account: None,
+        batch_outputs: None,
+        fee_priority: None,
     };
     state.pending_retrieve_btc_requests.push(req);
     // One request, >= min_pending, pass.
@@ -1170,12 +1324,133 @@ fn can_form_a_batch_conditions() {
         received_at: 10501,
         kyt_provider: None,
         reimbursement_account: None,
+        batch_outputs: None,
+        fee_priority: None,
     };
     state.pending_retrieve_btc_requests.push(req);
     // Two request, long enough since last_transaction_submission_time, pass.
     assert!(state.can_form_a_batch(10, 10600));
 }
 
+#[test]
+fn build_batch_keeps_batched_requests_whole() {
+    let mut state = CkBtcMinterState::from(InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 0,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+    });
+    state.add_utxos(
+        Account::from(Principal::anonymous()),
+        vec![dummy_utxo_from_value(1_000_000)],
+    );
+
+    let batch_outputs: Vec<_> = (0..3u8)
+        .map(|i| RetrieveBtcOutput {
+            address: BitcoinAddress::P2wpkhV0([i; 20]),
+            amount: 10_000,
+        })
+        .collect();
+    let batched_request = RetrieveBtcRequest {
+        amount: 30_000,
+        address: BitcoinAddress::P2wpkhV0([0; 20]),
+        block_index: 0,
+        received_at: 0,
+        kyt_provider: None,
+        reimbursement_account: None,
+        batch_outputs: Some(batch_outputs),
+        fee_priority: None,
+    };
+    let single_request = RetrieveBtcRequest {
+        amount: 10_000,
+        address: BitcoinAddress::P2wpkhV0([9; 20]),
+        block_index: 1,
+        received_at: 1,
+        kyt_provider: None,
+        reimbursement_account: None,
+        batch_outputs: None,
+        fee_priority: None,
+    };
+    assert_eq!(batched_request.num_outputs(), 3);
+    assert_eq!(
+        batched_request.outputs(),
+        (0..3u8)
+            .map(|i| (BitcoinAddress::P2wpkhV0([i; 20]), 10_000))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        single_request.outputs(),
+        vec![(BitcoinAddress::P2wpkhV0([9; 20]), 10_000)]
+    );
+
+    state.push_back_pending_request(batched_request.clone());
+    state.push_back_pending_request(single_request.clone());
+
+    // The batched request does not fit into a transaction with two outputs.
+    assert_eq!(state.build_batch(2), vec![single_request.clone()]);
+    assert_eq!(state.retrieve_btc_status(0), RetrieveBtcStatus::Pending);
+
+    state.push_from_in_flight_to_pending_requests(vec![single_request.clone()]);
+    assert_eq!(state.build_batch(4), vec![batched_request, single_request]);
+}
+
+#[test]
+fn accelerated_transaction_keeps_fee_bump_across_replacements() {
+    let mut state = CkBtcMinterState::from(InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 0,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+    });
+    let request = RetrieveBtcRequest {
+        amount: 10_000,
+        address: BitcoinAddress::P2wpkhV0([1; 20]),
+        block_index: 7,
+        received_at: 0,
+        kyt_provider: None,
+        reimbursement_account: None,
+        batch_outputs: None,
+        fee_priority: Some(FeePriority::High),
+    };
+    let submitted_tx = |txid: [u8; 32], applied_fee_bump: u64| SubmittedBtcTransaction {
+        requests: vec![request.clone()],
+        txid: txid.into(),
+        used_utxos: vec![],
+        submitted_at: 0,
+        change_output: Some(ChangeOutput { vout: 1, value: 0 }),
+        fee_per_vbyte: Some(1_000),
+        fee_bump: 0,
+        applied_fee_bump,
+    };
+
+    // Only submitted requests can be accelerated.
+    assert_eq!(state.accelerate_transaction(7, 1_000), None);
+
+    state.push_submitted_transaction(submitted_tx([1; 32], 0));
+    assert_eq!(state.accelerate_transaction(7, 1_000), Some([1; 32].into()));
+    assert_eq!(state.accelerate_transaction(8, 1_000), None);
+    assert_eq!(state.submitted_transactions[0].pending_fee_bump(), 1_000);
+    assert_eq!(state.tokens_burned, 1_000);
+
+    state.replace_transaction(&[1; 32].into(), submitted_tx([2; 32], 1_000));
+    assert_eq!(state.submitted_transactions[0].fee_bump, 1_000);
+    assert_eq!(state.submitted_transactions[0].pending_fee_bump(), 0);
+
+    state.accelerate_transaction(7, 500);
+    assert_eq!(state.submitted_transactions[0].fee_bump, 1_500);
+    assert_eq!(state.submitted_transactions[0].pending_fee_bump(), 500);
+}
+
 #[test]
 fn test_build_account_to_utxos_table_pagination() {
     use crate::dashboard;
@@ -1240,3 +1515,37 @@ fn test_build_account_to_utxos_table_pagination() {
         assert!(!no_utxo_page.contains(&format!("{}", utxo.outpoint.txid)));
     }
 }
+
+#[test]
+fn tracks_address_type_of_received_utxos() {
+    use crate::address::AddressType;
+
+    let mut state = CkBtcMinterState::from(InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 0,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+    });
+    let account = Account::from(Principal::anonymous());
+    let p2wpkh_utxo = dummy_utxo_from_value(10_000);
+    let p2tr_utxo = dummy_utxo_from_value(20_000);
+
+    state.add_utxos(account, vec![p2wpkh_utxo.clone()]);
+    state.add_p2tr_utxos(account, vec![p2tr_utxo.clone()]);
+
+    assert_eq!(
+        state.outpoint_address_type(&p2wpkh_utxo.outpoint),
+        AddressType::P2wpkh
+    );
+    assert_eq!(
+        state.outpoint_address_type(&p2tr_utxo.outpoint),
+        AddressType::P2tr
+    );
+    assert_eq!(state.tokens_minted, 30_000);
+    state.check_invariants().unwrap();
+}
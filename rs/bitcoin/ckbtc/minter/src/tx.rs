@@ -22,6 +22,9 @@ const MARKER: u8 = 0;
 const FLAGS: u8 = 1;
 // The signature applies to all inputs and outputs.
 pub const SIGHASH_ALL: u32 = 1;
+// The Taproot signature applies to all inputs and outputs, the signature
+// carries no sighash byte.
+pub const SIGHASH_DEFAULT: u8 = 0;
 
 /// Bitcoin script opcodes.
 mod ops {
@@ -202,7 +205,8 @@ pub struct SignedInput {
     pub sequence: u32,
     pub signature: EncodedSignature,
     // The public key bytes.
-    // Must be PUBKEY_LEN bytes long.
+    // Must be PUBKEY_LEN bytes long for P2WPKH inputs.
+    // Empty for Taproot key-path inputs, which carry only the signature.
     pub pubkey: ByteBuf,
 }
 
@@ -340,6 +344,135 @@ impl<'a> TxSigHasher<'a> {
     }
 }
 
+/// Computes signature hashes for Taproot key-path inputs.
+///
+/// Unlike BIP-143, the BIP-341 signature message commits to the amounts and
+/// the scriptPubKeys of all the outputs that the transaction spends.
+pub struct TaprootSigHasher<'a> {
+    tx: &'a UnsignedTransaction,
+    sha_prevouts: [u8; 32],
+    sha_amounts: [u8; 32],
+    sha_scriptpubkeys: [u8; 32],
+    sha_sequences: [u8; 32],
+    sha_outputs: [u8; 32],
+}
+
+impl<'a> TaprootSigHasher<'a> {
+    /// Creates a new hasher for the transaction, where `spent_addresses` holds
+    /// the address of the output spent by each transaction input, in order.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the number of addresses does not match the
+    /// number of inputs.
+    pub fn new(tx: &'a UnsignedTransaction, spent_addresses: &[BitcoinAddress]) -> Self {
+        assert_eq!(
+            tx.inputs.len(),
+            spent_addresses.len(),
+            "bug: expected one spent address per transaction input"
+        );
+
+        let sha_prevouts = {
+            let mut hasher = Sha256::new();
+            for input in tx.inputs.iter() {
+                input.previous_output.encode(&mut hasher);
+            }
+            hasher.finish()
+        };
+
+        let sha_amounts = {
+            let mut hasher = Sha256::new();
+            for input in tx.inputs.iter() {
+                input.value.encode(&mut hasher);
+            }
+            hasher.finish()
+        };
+
+        let sha_scriptpubkeys = {
+            let mut hasher = Sha256::new();
+            for address in spent_addresses.iter() {
+                encode_address_script_pubkey(address, &mut hasher);
+            }
+            hasher.finish()
+        };
+
+        let sha_sequences = {
+            let mut hasher = Sha256::new();
+            for input in tx.inputs.iter() {
+                input.sequence.encode(&mut hasher);
+            }
+            hasher.finish()
+        };
+
+        let sha_outputs = {
+            let mut hasher = Sha256::new();
+            for output in tx.outputs.iter() {
+                output.encode(&mut hasher);
+            }
+            hasher.finish()
+        };
+
+        Self {
+            tx,
+            sha_prevouts,
+            sha_amounts,
+            sha_scriptpubkeys,
+            sha_sequences,
+            sha_outputs,
+        }
+    }
+
+    pub fn encode_sighash_data(&self, input_index: usize, buf: &mut impl Buffer) {
+        debug_assert!(input_index < self.tx.inputs.len());
+
+        // The signature message for SIGHASH_DEFAULT key-path spending:
+        //      0. sighash epoch (1 byte)
+        buf.write(&[0]);
+        //      1. hash_type (1 byte)
+        buf.write(&[SIGHASH_DEFAULT]);
+        //      2. nVersion of the transaction (4-byte little endian)
+        TX_VERSION.encode(buf);
+        //      3. nLockTime of the transaction (4-byte little endian)
+        self.tx.lock_time.encode(buf);
+        //      4. sha_prevouts, sha_amounts, sha_scriptpubkeys, sha_sequences (32-byte hashes)
+        buf.write(&self.sha_prevouts[..]);
+        buf.write(&self.sha_amounts[..]);
+        buf.write(&self.sha_scriptpubkeys[..]);
+        buf.write(&self.sha_sequences[..]);
+        //      5. sha_outputs (32-byte hash)
+        buf.write(&self.sha_outputs[..]);
+        //      6. spend_type: key path, no annex (1 byte)
+        buf.write(&[0]);
+        //      7. input_index (4-byte little endian)
+        (input_index as u32).encode(buf);
+    }
+
+    /// Returns the message that the Taproot input with the specified index
+    /// needs to sign.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the `input_index` is invalid transaction input index.
+    pub fn sighash(&self, input_index: usize) -> [u8; 32] {
+        // Spec:
+        // https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki#common-signature-message
+        assert!(input_index < self.tx.inputs.len());
+
+        let mut hasher = tagged_hasher(b"TapSighash");
+        self.encode_sighash_data(input_index, &mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Returns a hasher for the BIP-340 tagged hash with the specified tag.
+fn tagged_hasher(tag: &[u8]) -> Sha256 {
+    let tag_hash = Sha256::hash(tag);
+    let mut hasher = Sha256::new();
+    hasher.write(&tag_hash);
+    hasher.write(&tag_hash);
+    hasher
+}
+
 #[derive(Eq, PartialEq, Debug)]
 pub struct UnsignedTransaction {
     pub inputs: Vec<UnsignedInput>,
@@ -538,11 +671,17 @@ impl Encode for SignedTransaction {
         self.inputs.encode(buf);
         self.outputs.encode(buf);
         for txin in self.inputs.iter() {
-            [
-                Bytes::new(txin.signature.as_slice()),
-                Bytes::new(&txin.pubkey),
-            ][..]
-                .encode(buf);
+            if txin.pubkey.is_empty() {
+                // Taproot key-path spends carry only the signature.
+                // See https://github.com/bitcoin/bips/blob/master/bip-0341.mediawiki#script-validation-rules
+                [Bytes::new(txin.signature.as_slice())][..].encode(buf);
+            } else {
+                [
+                    Bytes::new(txin.signature.as_slice()),
+                    Bytes::new(&txin.pubkey),
+                ][..]
+                    .encode(buf);
+            }
         }
         self.lock_time.encode(buf)
     }
@@ -1,4 +1,5 @@
 use crate::{
+    address::AddressType,
     logs::P1,
     state::{mutate_state, read_state, CkBtcMinterState},
     ECDSAPublicKey,
@@ -13,6 +14,8 @@ use serde::Serialize;
 pub struct GetBtcAddressArgs {
     pub owner: Option<Principal>,
     pub subaccount: Option<Subaccount>,
+    /// The kind of the deposit address, P2WPKH if not specified.
+    pub address_type: Option<AddressType>,
 }
 
 /// PRECONDITION: s.ecdsa_public_key.is_some()
@@ -26,20 +29,37 @@ pub fn account_to_p2wpkh_address_from_state(s: &CkBtcMinterState, account: &Acco
     )
 }
 
+/// PRECONDITION: s.schnorr_public_key.is_some()
+pub fn account_to_p2tr_address_from_state(s: &CkBtcMinterState, account: &Account) -> String {
+    crate::address::account_to_p2tr_address(
+        s.btc_network,
+        s.schnorr_public_key
+            .as_ref()
+            .expect("bug: the Schnorr public key must be initialized"),
+        account,
+    )
+}
+
 pub async fn get_btc_address(args: GetBtcAddressArgs) -> String {
     let owner = args.owner.unwrap_or_else(ic_cdk::caller);
+    let account = Account {
+        owner,
+        subaccount: args.subaccount,
+    };
 
-    init_ecdsa_public_key().await;
-
-    read_state(|s| {
-        account_to_p2wpkh_address_from_state(
-            s,
-            &Account {
-                owner,
-                subaccount: args.subaccount,
-            },
-        )
-    })
+    match args.address_type.unwrap_or_default() {
+        AddressType::P2wpkh => {
+            init_ecdsa_public_key().await;
+            read_state(|s| account_to_p2wpkh_address_from_state(s, &account))
+        }
+        AddressType::P2tr => {
+            if !read_state(|s| s.p2tr_deposits_enabled) {
+                ic_cdk::trap("Taproot deposit addresses are not enabled");
+            }
+            init_schnorr_public_key().await;
+            read_state(|s| account_to_p2tr_address_from_state(s, &account))
+        }
+    }
 }
 
 /// Initializes the Minter ECDSA public key. This function must be called
@@ -66,6 +86,32 @@ pub async fn init_ecdsa_public_key() -> ECDSAPublicKey {
     ecdsa_public_key
 }
 
+/// Initializes the Minter BIP-340 public key used to derive Taproot deposit
+/// addresses.
+pub async fn init_schnorr_public_key() -> ECDSAPublicKey {
+    if let Some(key) = read_state(|s| s.schnorr_public_key.clone()) {
+        return key;
+    };
+    let key_name = read_state(|s| s.ecdsa_key_name.clone());
+    log!(P1, "Fetching the Schnorr public key {}", &key_name);
+    let schnorr_public_key =
+        crate::management::schnorr_public_key(key_name, DerivationPath::new(vec![]))
+            .await
+            .unwrap_or_else(|e| {
+                ic_cdk::trap(&format!("failed to retrieve Schnorr public key: {e}"))
+            });
+    log!(
+        P1,
+        "Schnorr public key set to {}, chain code to {}",
+        hex::encode(&schnorr_public_key.public_key),
+        hex::encode(&schnorr_public_key.chain_code)
+    );
+    mutate_state(|s| {
+        s.schnorr_public_key = Some(schnorr_public_key.clone());
+    });
+    schnorr_public_key
+}
+
 #[cfg(test)]
 mod tests {
     use ic_btc_interface::Network;
@@ -8,10 +8,11 @@ use crate::tasks::{schedule_now, TaskType};
 use crate::{
     address::{account_to_bitcoin_address, BitcoinAddress, ParseAddressError},
     guard::{retrieve_btc_guard, GuardError},
-    state::{self, mutate_state, read_state, RetrieveBtcRequest},
+    state::{self, mutate_state, read_state, FeePriority, RetrieveBtcOutput, RetrieveBtcRequest},
 };
 use candid::{CandidType, Deserialize, Nat, Principal};
 use ic_base_types::PrincipalId;
+use ic_btc_interface::Network;
 use ic_canister_log::log;
 use ic_ckbtc_kyt::Error as KytError;
 use icrc_ledger_client_cdk::{CdkRuntime, ICRC1Client};
@@ -25,6 +26,9 @@ use std::cmp::max;
 
 const MAX_CONCURRENT_PENDING_REQUESTS: usize = 1000;
 
+/// The minimum amount of ckBTC a user can burn to accelerate a withdrawal.
+const MIN_ACCELERATION_AMOUNT: u64 = 1_000;
+
 /// The arguments of the [retrieve_btc] endpoint.
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct RetrieveBtcArgs {
@@ -33,6 +37,9 @@ pub struct RetrieveBtcArgs {
 
     // address where to send bitcoins
     pub address: String,
+
+    // fee rate of the withdrawal transaction, the median fee if not set
+    pub fee_priority: Option<FeePriority>,
 }
 
 /// The arguments of the [retrieve_btc_with_approval] endpoint.
@@ -46,6 +53,52 @@ pub struct RetrieveBtcWithApprovalArgs {
 
     // The subaccount to burn ckBTC from.
     pub from_subaccount: Option<Subaccount>,
+
+    // fee rate of the withdrawal transaction, the median fee if not set
+    pub fee_priority: Option<FeePriority>,
+}
+
+/// A single destination of a [retrieve_btc_batch] request.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RetrieveBtcBatchOutput {
+    // amount to send to the address in satoshi
+    pub amount: u64,
+
+    // address where to send bitcoins
+    pub address: String,
+}
+
+/// The arguments of the [retrieve_btc_batch] endpoint.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RetrieveBtcBatchArgs {
+    // destinations of the withdrawal, at most MAX_REQUESTS_PER_BATCH
+    pub outputs: Vec<RetrieveBtcBatchOutput>,
+
+    // fee rate of the withdrawal transaction, the median fee if not set
+    pub fee_priority: Option<FeePriority>,
+}
+
+/// The arguments of the [retrieve_btc_batch_with_approval] endpoint.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RetrieveBtcBatchWithApprovalArgs {
+    // destinations of the withdrawal, at most MAX_REQUESTS_PER_BATCH
+    pub outputs: Vec<RetrieveBtcBatchOutput>,
+
+    // The subaccount to burn ckBTC from.
+    pub from_subaccount: Option<Subaccount>,
+
+    // fee rate of the withdrawal transaction, the median fee if not set
+    pub fee_priority: Option<FeePriority>,
+}
+
+/// The arguments of the [accelerate_retrieve_btc] endpoint.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct AccelerateRetrieveBtcArgs {
+    // the index of the burn block of the withdrawal to accelerate
+    pub block_index: u64,
+
+    // amount of ckBTC in satoshi to add to the transaction fee
+    pub amount: u64,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
@@ -58,6 +111,11 @@ pub enum ErrorCode {
     // The retrieval address didn't pass the KYT check.
     TaintedAddress = 1,
     KytCallFailed = 2,
+    // The batch is empty, has too many outputs, or its total amount overflows.
+    InvalidBatch = 3,
+    // The withdrawal does not exist, is not submitted yet, or is already
+    // confirmed, or the caller does not own it.
+    CannotAccelerate = 4,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
@@ -147,6 +205,39 @@ impl From<ParseAddressError> for RetrieveBtcWithApprovalError {
     }
 }
 
+/// The reasons why the minter rejects the outputs of a batched request.
+enum InvalidBatchError {
+    InvalidBatch(String),
+    AmountTooLow(u64),
+    MalformedAddress(ParseAddressError),
+}
+
+impl From<InvalidBatchError> for RetrieveBtcError {
+    fn from(e: InvalidBatchError) -> Self {
+        match e {
+            InvalidBatchError::InvalidBatch(error_message) => Self::GenericError {
+                error_message,
+                error_code: ErrorCode::InvalidBatch as u64,
+            },
+            InvalidBatchError::AmountTooLow(min_amount) => Self::AmountTooLow(min_amount),
+            InvalidBatchError::MalformedAddress(e) => e.into(),
+        }
+    }
+}
+
+impl From<InvalidBatchError> for RetrieveBtcWithApprovalError {
+    fn from(e: InvalidBatchError) -> Self {
+        match e {
+            InvalidBatchError::InvalidBatch(error_message) => Self::GenericError {
+                error_message,
+                error_code: ErrorCode::InvalidBatch as u64,
+            },
+            InvalidBatchError::AmountTooLow(min_amount) => Self::AmountTooLow(min_amount),
+            InvalidBatchError::MalformedAddress(e) => e.into(),
+        }
+    }
+}
+
 pub async fn retrieve_btc(args: RetrieveBtcArgs) -> Result<RetrieveBtcOk, RetrieveBtcError> {
     let caller = ic_cdk::caller();
 
@@ -224,6 +315,7 @@ pub async fn retrieve_btc(args: RetrieveBtcArgs) -> Result<RetrieveBtcOk, Retrie
                     kyt_provider,
                     uuid,
                     block_index,
+                    None,
                 )
             });
             return Err(RetrieveBtcError::GenericError {
@@ -257,6 +349,8 @@ pub async fn retrieve_btc(args: RetrieveBtcArgs) -> Result<RetrieveBtcOk, Retrie
             owner: caller,
             subaccount: None,
         }),
+        batch_outputs: None,
+        fee_priority: args.fee_priority,
     };
 
     log!(
@@ -383,6 +477,8 @@ pub async fn retrieve_btc_with_approval(
                     owner: caller,
                     subaccount: args.from_subaccount,
                 }),
+                batch_outputs: None,
+                fee_priority: args.fee_priority,
             };
 
             mutate_state(|s| state::audit::accept_retrieve_btc_request(s, request));
@@ -424,6 +520,428 @@ pub async fn retrieve_btc_with_approval(
     }
 }
 
+/// Traps if one of the batch destinations is blocked or is the minter's main
+/// address, mirroring the checks of [retrieve_btc].
+async fn check_batch_destinations(outputs: &[RetrieveBtcBatchOutput]) {
+    for output in outputs {
+        if crate::blocklist::BTC_ADDRESS_BLOCKLIST
+            .binary_search(&output.address.trim())
+            .is_ok()
+        {
+            ic_cdk::trap("attempted to retrieve BTC to a blocked address");
+        }
+    }
+
+    let ecdsa_public_key = init_ecdsa_public_key().await;
+    let main_address = account_to_bitcoin_address(
+        &ecdsa_public_key,
+        &Account {
+            owner: ic_cdk::id(),
+            subaccount: None,
+        },
+    )
+    .display(state::read_state(|s| s.btc_network));
+
+    if outputs.iter().any(|output| output.address == main_address) {
+        ic_cdk::trap("illegal retrieve_btc target");
+    }
+}
+
+/// Validates the outputs of a batched request and returns the total amount
+/// to burn together with the parsed outputs, with the KYT fee charged from
+/// each output.
+fn parse_batch_outputs(
+    outputs: &[RetrieveBtcBatchOutput],
+    btc_network: Network,
+    min_amount: u64,
+    kyt_fee: u64,
+) -> Result<(u64, Vec<RetrieveBtcOutput>), InvalidBatchError> {
+    if outputs.is_empty() || outputs.len() > crate::MAX_REQUESTS_PER_BATCH {
+        return Err(InvalidBatchError::InvalidBatch(format!(
+            "a batch must have between 1 and {} outputs, got {}",
+            crate::MAX_REQUESTS_PER_BATCH,
+            outputs.len()
+        )));
+    }
+
+    let mut total_amount: u64 = 0;
+    let mut parsed_outputs = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        if output.amount < min_amount {
+            return Err(InvalidBatchError::AmountTooLow(min_amount));
+        }
+        let address = BitcoinAddress::parse(&output.address, btc_network)
+            .map_err(InvalidBatchError::MalformedAddress)?;
+        total_amount = total_amount.checked_add(output.amount).ok_or_else(|| {
+            InvalidBatchError::InvalidBatch("the total batch amount overflows".to_string())
+        })?;
+        parsed_outputs.push(RetrieveBtcOutput {
+            address,
+            // NB. We charge the KYT fee from each output.
+            amount: output.amount - kyt_fee,
+        });
+    }
+
+    Ok((total_amount, parsed_outputs))
+}
+
+/// Builds a pending request paying out all `outputs` from a single burn.
+fn batch_request(
+    outputs: Vec<RetrieveBtcOutput>,
+    block_index: u64,
+    kyt_provider: Principal,
+    reimbursement_account: Account,
+    fee_priority: Option<FeePriority>,
+) -> RetrieveBtcRequest {
+    RetrieveBtcRequest {
+        amount: outputs.iter().map(|output| output.amount).sum(),
+        address: outputs
+            .first()
+            .expect("BUG: a batch must have at least one output")
+            .address
+            .clone(),
+        block_index,
+        received_at: ic_cdk::api::time(),
+        kyt_provider: Some(kyt_provider),
+        reimbursement_account: Some(reimbursement_account),
+        batch_outputs: Some(outputs),
+        fee_priority,
+    }
+}
+
+pub async fn retrieve_btc_batch(
+    args: RetrieveBtcBatchArgs,
+) -> Result<RetrieveBtcOk, RetrieveBtcError> {
+    let caller = ic_cdk::caller();
+
+    state::read_state(|s| s.mode.is_withdrawal_available_for(&caller))
+        .map_err(RetrieveBtcError::TemporarilyUnavailable)?;
+
+    check_batch_destinations(&args.outputs).await;
+
+    let _guard = retrieve_btc_guard(caller)?;
+    let (min_retrieve_amount, btc_network, kyt_fee) =
+        read_state(|s| (s.retrieve_btc_min_amount, s.btc_network, s.kyt_fee));
+
+    let min_amount = max(min_retrieve_amount, kyt_fee);
+    let (total_amount, outputs) =
+        parse_batch_outputs(&args.outputs, btc_network, min_amount, kyt_fee)?;
+    if read_state(|s| s.count_incomplete_retrieve_btc_requests() >= MAX_CONCURRENT_PENDING_REQUESTS)
+    {
+        return Err(RetrieveBtcError::TemporarilyUnavailable(
+            "too many pending retrieve_btc requests".to_string(),
+        ));
+    }
+
+    let balance = balance_of(caller).await?;
+    if total_amount > balance {
+        return Err(RetrieveBtcError::InsufficientFunds { balance });
+    }
+
+    let mut kyt_provider = None;
+    for (i, output) in args.outputs.iter().enumerate() {
+        let (uuid, status, provider) =
+            kyt_check_address(caller, output.address.clone(), output.amount).await?;
+
+        match status {
+            BtcAddressCheckStatus::Tainted => {
+                // NB. We charge the KYT fee for each check performed so far.
+                let checks_fee = kyt_fee * (i as u64 + 1);
+                let burn_memo = BurnMemo::Convert {
+                    address: Some(&output.address),
+                    kyt_fee: Some(checks_fee),
+                    status: Some(Status::Rejected),
+                };
+                let block_index =
+                    burn_ckbtcs(caller, checks_fee, crate::memo::encode(&burn_memo).into()).await?;
+                log!(
+                    P1,
+                    "rejected an attempt to withdraw {} BTC in a batch to address {} due to failed KYT check (burnt {} ckBTC in block {})",
+                    crate::tx::DisplayAmount(output.amount),
+                    output.address,
+                    crate::tx::DisplayAmount(checks_fee),
+                    block_index
+                );
+                mutate_state(|s| {
+                    state::audit::retrieve_btc_kyt_failed(
+                        s,
+                        caller,
+                        output.address.clone(),
+                        output.amount,
+                        provider,
+                        uuid,
+                        block_index,
+                        Some(checks_fee),
+                    )
+                });
+                return Err(RetrieveBtcError::GenericError {
+                    error_message: format!(
+                        "Destination address {} is tainted, KYT check fee deducted: {}",
+                        output.address,
+                        crate::tx::DisplayAmount(checks_fee),
+                    ),
+                    error_code: ErrorCode::TaintedAddress as u64,
+                });
+            }
+            BtcAddressCheckStatus::Clean => {}
+        }
+        kyt_provider = Some(provider);
+    }
+    let kyt_provider = kyt_provider.expect("BUG: a batch must have at least one output");
+
+    let burn_memo = BurnMemo::Convert {
+        address: None,
+        kyt_fee: Some(kyt_fee * outputs.len() as u64),
+        status: Some(Status::Accepted),
+    };
+    let block_index =
+        burn_ckbtcs(caller, total_amount, crate::memo::encode(&burn_memo).into()).await?;
+    let request = batch_request(
+        outputs,
+        block_index,
+        kyt_provider,
+        Account {
+            owner: caller,
+            subaccount: None,
+        },
+        args.fee_priority,
+    );
+
+    log!(
+        P1,
+        "accepted a batched retrieve btc request for {} BTC to {} addresses (block_index = {})",
+        crate::tx::DisplayAmount(request.amount),
+        request.num_outputs(),
+        request.block_index
+    );
+
+    mutate_state(|s| state::audit::accept_retrieve_btc_request(s, request));
+
+    assert_eq!(
+        crate::state::RetrieveBtcStatus::Pending,
+        read_state(|s| s.retrieve_btc_status(block_index))
+    );
+
+    schedule_now(TaskType::ProcessLogic);
+
+    Ok(RetrieveBtcOk { block_index })
+}
+
+pub async fn retrieve_btc_batch_with_approval(
+    args: RetrieveBtcBatchWithApprovalArgs,
+) -> Result<RetrieveBtcOk, RetrieveBtcWithApprovalError> {
+    let caller = ic_cdk::caller();
+
+    state::read_state(|s| s.mode.is_withdrawal_available_for(&caller))
+        .map_err(RetrieveBtcWithApprovalError::TemporarilyUnavailable)?;
+
+    check_batch_destinations(&args.outputs).await;
+
+    let _guard = retrieve_btc_guard(caller)?;
+    let (min_retrieve_amount, btc_network, kyt_fee) =
+        read_state(|s| (s.retrieve_btc_min_amount, s.btc_network, s.kyt_fee));
+    let min_amount = max(min_retrieve_amount, kyt_fee);
+    let (total_amount, outputs) =
+        parse_batch_outputs(&args.outputs, btc_network, min_amount, kyt_fee)?;
+    if read_state(|s| s.count_incomplete_retrieve_btc_requests() >= MAX_CONCURRENT_PENDING_REQUESTS)
+    {
+        return Err(RetrieveBtcWithApprovalError::TemporarilyUnavailable(
+            "too many pending retrieve_btc requests".to_string(),
+        ));
+    }
+
+    let from = Account {
+        owner: caller,
+        subaccount: args.from_subaccount,
+    };
+    let burn_memo_icrc2 = BurnMemo::Convert {
+        address: None,
+        kyt_fee: Some(kyt_fee * outputs.len() as u64),
+        status: None,
+    };
+    let block_index = burn_ckbtcs_icrc2(
+        from,
+        total_amount,
+        crate::memo::encode(&burn_memo_icrc2).into(),
+    )
+    .await?;
+
+    let mut kyt_provider = None;
+    for (i, output) in args.outputs.iter().enumerate() {
+        match kyt_check_address(caller, output.address.clone(), output.amount).await {
+            Ok((_uuid, BtcAddressCheckStatus::Tainted, provider)) => {
+                // NB. We charge the KYT fee for each check performed so far.
+                let checks_fee = kyt_fee * (i as u64 + 1);
+                mutate_state(|s| {
+                    state::audit::schedule_deposit_reimbursement(
+                        s,
+                        from,
+                        total_amount,
+                        ReimbursementReason::TaintedDestination {
+                            kyt_provider: provider,
+                            kyt_fee: checks_fee,
+                        },
+                        block_index,
+                    );
+                });
+                schedule_now(TaskType::ProcessLogic);
+                return Err(RetrieveBtcWithApprovalError::GenericError {
+                    error_message: format!(
+                        "Destination address {} is tainted, KYT check fee deducted: {}",
+                        output.address,
+                        crate::tx::DisplayAmount(checks_fee),
+                    ),
+                    error_code: ErrorCode::TaintedAddress as u64,
+                });
+            }
+            Ok((_uuid, BtcAddressCheckStatus::Clean, provider)) => {
+                kyt_provider = Some(provider);
+            }
+            Err(error) => {
+                mutate_state(|s| {
+                    state::audit::schedule_deposit_reimbursement(
+                        s,
+                        from,
+                        total_amount,
+                        ReimbursementReason::CallFailed,
+                        block_index,
+                    );
+                });
+
+                schedule_now(TaskType::ProcessLogic);
+
+                return Err(RetrieveBtcWithApprovalError::GenericError {
+                    error_message: format!(
+                        "Failed to call KYT canister with error: {:?}, will reimburse {} ckBTC",
+                        error,
+                        crate::tx::DisplayAmount(total_amount),
+                    ),
+                    error_code: ErrorCode::KytCallFailed as u64,
+                });
+            }
+        }
+    }
+    let kyt_provider = kyt_provider.expect("BUG: a batch must have at least one output");
+
+    let request = batch_request(outputs, block_index, kyt_provider, from, args.fee_priority);
+
+    mutate_state(|s| state::audit::accept_retrieve_btc_request(s, request));
+
+    assert_eq!(
+        crate::state::RetrieveBtcStatus::Pending,
+        read_state(|s| s.retrieve_btc_status(block_index))
+    );
+
+    schedule_now(TaskType::ProcessLogic);
+
+    Ok(RetrieveBtcOk { block_index })
+}
+
+/// Burns additional ckBTC from the caller's withdrawal account to increase the
+/// fee of the transaction fulfilling a submitted withdrawal.
+pub async fn accelerate_retrieve_btc(
+    args: AccelerateRetrieveBtcArgs,
+) -> Result<RetrieveBtcOk, RetrieveBtcError> {
+    let caller = ic_cdk::caller();
+
+    state::read_state(|s| s.mode.is_withdrawal_available_for(&caller))
+        .map_err(RetrieveBtcError::TemporarilyUnavailable)?;
+
+    let _guard = retrieve_btc_guard(caller)?;
+
+    if args.amount < MIN_ACCELERATION_AMOUNT {
+        return Err(RetrieveBtcError::AmountTooLow(MIN_ACCELERATION_AMOUNT));
+    }
+
+    let cannot_accelerate = |reason: &str| RetrieveBtcError::GenericError {
+        error_message: format!(
+            "cannot accelerate withdrawal {}: {}",
+            args.block_index, reason
+        ),
+        error_code: ErrorCode::CannotAccelerate as u64,
+    };
+
+    let owner = read_state(|s| {
+        s.submitted_transactions
+            .iter()
+            .flat_map(|tx| tx.requests.iter())
+            .find(|req| req.block_index == args.block_index)
+            .map(|req| req.reimbursement_account.map(|account| account.owner))
+    });
+    match owner {
+        None => {
+            return Err(cannot_accelerate(
+                "the withdrawal is not part of a submitted transaction",
+            ))
+        }
+        Some(owner) if owner != Some(caller) => {
+            return Err(cannot_accelerate("the caller does not own the withdrawal"))
+        }
+        Some(_) => {}
+    }
+
+    let balance = balance_of(caller).await?;
+    if args.amount > balance {
+        return Err(RetrieveBtcError::InsufficientFunds { balance });
+    }
+
+    let burn_memo = BurnMemo::Accelerate {
+        retrieve_btc_block_index: Some(args.block_index),
+    };
+    let burn_block_index =
+        burn_ckbtcs(caller, args.amount, crate::memo::encode(&burn_memo).into()).await?;
+
+    // The transaction might have been confirmed while we were burning ckBTC.
+    let is_submitted = read_state(|s| {
+        matches!(
+            s.retrieve_btc_status(args.block_index),
+            crate::state::RetrieveBtcStatus::Submitted { .. }
+        )
+    });
+    if !is_submitted {
+        mutate_state(|s| {
+            state::audit::schedule_deposit_reimbursement(
+                s,
+                Account {
+                    owner: caller,
+                    subaccount: None,
+                },
+                args.amount,
+                ReimbursementReason::CallFailed,
+                burn_block_index,
+            );
+        });
+        schedule_now(TaskType::ProcessLogic);
+        return Err(cannot_accelerate(&format!(
+            "the withdrawal is no longer pending, will reimburse {} ckBTC",
+            crate::tx::DisplayAmount(args.amount)
+        )));
+    }
+
+    log!(
+        P1,
+        "accelerating withdrawal {} with {} ckBTC (burn block_index = {})",
+        args.block_index,
+        crate::tx::DisplayAmount(args.amount),
+        burn_block_index
+    );
+
+    mutate_state(|s| {
+        state::audit::accelerate_retrieve_btc_request(
+            s,
+            args.block_index,
+            args.amount,
+            burn_block_index,
+        )
+    });
+
+    schedule_now(TaskType::ProcessLogic);
+
+    Ok(RetrieveBtcOk {
+        block_index: burn_block_index,
+    })
+}
+
 async fn balance_of(user: Principal) -> Result<u64, RetrieveBtcError> {
     let client = ICRC1Client {
         runtime: CdkRuntime,
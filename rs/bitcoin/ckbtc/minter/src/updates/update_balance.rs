@@ -13,9 +13,10 @@ use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
 use num_traits::ToPrimitive;
 use serde::Serialize;
 
-use super::get_btc_address::init_ecdsa_public_key;
+use super::get_btc_address::{init_ecdsa_public_key, init_schnorr_public_key};
 
 use crate::{
+    address::AddressType,
     guard::{balance_update_guard, GuardError},
     management::{fetch_utxo_alerts, get_utxos, CallError, CallSource},
     state,
@@ -139,6 +140,12 @@ pub async fn update_balance(
         .map_err(UpdateBalanceError::TemporarilyUnavailable)?;
 
     init_ecdsa_public_key().await;
+    // NB. We keep scanning Taproot addresses after the minter stops issuing
+    // them so that deposits to previously issued addresses are not lost.
+    let scan_p2tr_addresses = state::read_state(|s| s.p2tr_deposits_ever_enabled);
+    if scan_p2tr_addresses {
+        init_schnorr_public_key().await;
+    }
     let _guard = balance_update_guard(args.owner.unwrap_or(caller))?;
 
     let caller_account = Account {
@@ -146,23 +153,39 @@ pub async fn update_balance(
         subaccount: args.subaccount,
     };
 
-    let address = state::read_state(|s| {
-        get_btc_address::account_to_p2wpkh_address_from_state(s, &caller_account)
+    let addresses = state::read_state(|s| {
+        let mut addresses = vec![(
+            AddressType::P2wpkh,
+            get_btc_address::account_to_p2wpkh_address_from_state(s, &caller_account),
+        )];
+        if scan_p2tr_addresses {
+            addresses.push((
+                AddressType::P2tr,
+                get_btc_address::account_to_p2tr_address_from_state(s, &caller_account),
+            ));
+        }
+        addresses
     });
 
     let (btc_network, min_confirmations) =
         state::read_state(|s| (s.btc_network, s.min_confirmations));
 
-    let utxos = get_utxos(btc_network, &address, min_confirmations, CallSource::Client)
-        .await?
-        .utxos;
-
-    let new_utxos = state::read_state(|s| s.new_utxos_for_account(utxos, &caller_account));
+    let mut new_utxos: Vec<(AddressType, Utxo)> = vec![];
+    for (address_type, address) in addresses.iter() {
+        let utxos = get_utxos(btc_network, address, min_confirmations, CallSource::Client)
+            .await?
+            .utxos;
+        new_utxos.extend(
+            state::read_state(|s| s.new_utxos_for_account(utxos, &caller_account))
+                .into_iter()
+                .map(|utxo| (*address_type, utxo)),
+        );
+    }
 
     // Remove pending finalized transactions for the affected principal.
     state::mutate_state(|s| s.finalized_utxos.remove(&caller_account.owner));
 
-    let satoshis_to_mint = new_utxos.iter().map(|u| u.value).sum::<u64>();
+    let satoshis_to_mint = new_utxos.iter().map(|(_, u)| u.value).sum::<u64>();
 
     if satoshis_to_mint == 0 {
         // We bail out early if there are no UTXOs to avoid creating a new entry
@@ -172,34 +195,34 @@ pub async fn update_balance(
         // We get the entire list of UTXOs again with a zero
         // confirmation limit so that we can indicate the approximate
         // wait time to the caller.
-        let GetUtxosResponse {
-            tip_height,
-            mut utxos,
-            ..
-        } = get_utxos(
-            btc_network,
-            &address,
-            /*min_confirmations=*/ 0,
-            CallSource::Client,
-        )
-        .await?;
-
-        utxos.retain(|u| {
-            tip_height
-                < u.height
-                    .checked_add(min_confirmations)
-                    .expect("bug: this shouldn't overflow")
-                    .checked_sub(1)
-                    .expect("bug: this shouldn't underflow")
-        });
-        let pending_utxos: Vec<PendingUtxo> = utxos
-            .iter()
-            .map(|u| PendingUtxo {
+        let mut pending_utxos: Vec<PendingUtxo> = vec![];
+        for (_, address) in addresses.iter() {
+            let GetUtxosResponse {
+                tip_height,
+                mut utxos,
+                ..
+            } = get_utxos(
+                btc_network,
+                address,
+                /*min_confirmations=*/ 0,
+                CallSource::Client,
+            )
+            .await?;
+
+            utxos.retain(|u| {
+                tip_height
+                    < u.height
+                        .checked_add(min_confirmations)
+                        .expect("bug: this shouldn't overflow")
+                        .checked_sub(1)
+                        .expect("bug: this shouldn't underflow")
+            });
+            pending_utxos.extend(utxos.iter().map(|u| PendingUtxo {
                 outpoint: u.outpoint.clone(),
                 value: u.value,
                 confirmations: tip_height - u.height + 1,
-            })
-            .collect();
+            }));
+        }
 
         let current_confirmations = pending_utxos.iter().map(|u| u.confirmations).max();
 
@@ -217,7 +240,7 @@ pub async fn update_balance(
 
     let kyt_fee = read_state(|s| s.kyt_fee);
     let mut utxo_statuses: Vec<UtxoStatus> = vec![];
-    for utxo in new_utxos {
+    for (address_type, utxo) in new_utxos {
         if utxo.value <= kyt_fee {
             mutate_state(|s| crate::state::audit::ignore_utxo(s, utxo.clone()));
             log!(
@@ -259,6 +282,7 @@ pub async fn update_balance(
                         Some(block_index),
                         caller_account,
                         vec![utxo.clone()],
+                        address_type,
                     )
                 });
                 utxo_statuses.push(UtxoStatus::Minted {
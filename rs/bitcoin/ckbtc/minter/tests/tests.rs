@@ -7,6 +7,7 @@ use ic_bitcoin_canister_mock::{OutPoint, PushUtxoToAddress, Utxo};
 use ic_btc_interface::{Network, Txid};
 use ic_canisters_http_types::{HttpRequest, HttpResponse};
 use ic_ckbtc_kyt::{InitArg as KytInitArg, KytMode, LifecycleArg, SetApiKeyArg};
+use ic_ckbtc_minter::address::AddressType;
 use ic_ckbtc_minter::lifecycle::init::{InitArgs as CkbtcMinterInitArgs, MinterArg};
 use ic_ckbtc_minter::lifecycle::upgrade::UpgradeArgs;
 use ic_ckbtc_minter::queries::{EstimateFeeArg, RetrieveBtcStatusRequest, WithdrawalFee};
@@ -27,6 +28,7 @@ use ic_ckbtc_minter::{
     Log, MinterInfo, CKBTC_LEDGER_MEMO_SIZE, MIN_RELAY_FEE_PER_VBYTE, MIN_RESUBMISSION_DELAY,
 };
 use ic_icrc1_ledger::{InitArgsBuilder as LedgerInitArgsBuilder, LedgerArgument};
+use ic_management_canister_types::{MasterPublicKeyId, SchnorrAlgorithm, SchnorrKeyId};
 use ic_state_machine_tests::{Cycles, StateMachine, StateMachineBuilder, WasmResult};
 use ic_test_utilities_load_wasm::load_wasm;
 use icrc_ledger_types::icrc1::account::Account;
@@ -216,6 +218,7 @@ fn test_wrong_upgrade_parameter() {
         mode: Some(Mode::ReadOnly),
         kyt_principal: None,
         kyt_fee: None,
+        enable_p2tr_deposits: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     if env
@@ -244,6 +247,7 @@ fn test_upgrade_read_only() {
         mode: Some(Mode::ReadOnly),
         kyt_principal: Some(CanisterId::from(0)),
         kyt_fee: None,
+        enable_p2tr_deposits: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     env.upgrade_canister(minter_id, minter_wasm(), Encode!(&minter_arg).unwrap())
@@ -275,6 +279,7 @@ fn test_upgrade_read_only() {
     let retrieve_btc_args = RetrieveBtcArgs {
         amount: 10,
         address: "".into(),
+        fee_priority: None,
     };
     let res = env
         .execute_ingress_as(
@@ -314,6 +319,7 @@ fn test_upgrade_restricted() {
         mode: Some(Mode::RestrictedTo(vec![authorized_principal])),
         kyt_fee: None,
         kyt_principal: Some(CanisterId::from(0)),
+        enable_p2tr_deposits: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     env.upgrade_canister(minter_id, minter_wasm(), Encode!(&minter_arg).unwrap())
@@ -345,6 +351,7 @@ fn test_upgrade_restricted() {
     let retrieve_btc_args = RetrieveBtcArgs {
         amount: 10,
         address: "".into(),
+        fee_priority: None,
     };
     let res = env
         .execute_ingress_as(
@@ -369,6 +376,7 @@ fn test_upgrade_restricted() {
         mode: Some(Mode::DepositsRestrictedTo(vec![authorized_principal])),
         kyt_principal: Some(CanisterId::from(0)),
         kyt_fee: None,
+        enable_p2tr_deposits: None,
     };
     env.upgrade_canister(minter_id, minter_wasm(), Encode!(&upgrade_args).unwrap())
         .expect("Failed to upgrade the minter canister");
@@ -454,6 +462,7 @@ fn update_balance_should_return_correct_confirmations() {
         mode: None,
         kyt_principal: None,
         kyt_fee: None,
+        enable_p2tr_deposits: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     ckbtc
@@ -582,6 +591,7 @@ fn test_minter() {
         &GetBtcAddressArgs {
             owner: None,
             subaccount: None,
+            address_type: None,
         },
     );
     let address_1 = Address::from_str(&btc_address_1).expect("invalid bitcoin address");
@@ -591,6 +601,7 @@ fn test_minter() {
         &GetBtcAddressArgs {
             owner: None,
             subaccount: Some([1; 32]),
+            address_type: None,
         },
     );
     let address_2 = Address::from_str(&btc_address_2).expect("invalid bitcoin address");
@@ -637,6 +648,10 @@ impl CkBtcSetup {
         let bitcoin_id = bitcoin_canister_id(btc_network);
         let env = StateMachineBuilder::new()
             .with_master_ecdsa_public_key()
+            .with_idkg_key(MasterPublicKeyId::Schnorr(SchnorrKeyId {
+                algorithm: SchnorrAlgorithm::Bip340Secp256k1,
+                name: "master_ecdsa_public_key".to_string(),
+            }))
             .with_default_canister_range()
             .with_extra_canister_range(bitcoin_id..=bitcoin_id)
             .build();
@@ -773,6 +788,7 @@ impl CkBtcSetup {
                         Encode!(&GetBtcAddressArgs {
                             owner: Some(account.owner),
                             subaccount: account.subaccount,
+                            address_type: None,
                         })
                         .unwrap(),
                     )
@@ -1011,6 +1027,7 @@ impl CkBtcSetup {
                 self.env.execute_ingress_as(self.caller, self.minter_id, "retrieve_btc", Encode!(&RetrieveBtcArgs {
                     address,
                     amount,
+                    fee_priority: None,
                 }).unwrap())
                 .expect("failed to execute retrieve_btc request")
             ),
@@ -1029,7 +1046,8 @@ impl CkBtcSetup {
                 self.env.execute_ingress_as(self.caller, self.minter_id, "retrieve_btc_with_approval", Encode!(&RetrieveBtcWithApprovalArgs {
                     address,
                     amount,
-                    from_subaccount
+                    from_subaccount,
+                    fee_priority: None,
                 }).unwrap())
                 .expect("failed to execute retrieve_btc request")
             ),
@@ -1687,6 +1705,141 @@ fn test_taproot_transaction_finalization() {
     assert_eq!(ckbtc.await_finalization(block_index, 10), txid);
 }
 
+#[test]
+fn test_taproot_deposit_signature_matches_output_key() {
+    use bitcoin::secp256k1::{schnorr::Signature, Message, Secp256k1, XOnlyPublicKey};
+    use bitcoin::util::address::Payload;
+    use bitcoin::util::sighash::{Prevouts, SchnorrSighashType, SighashCache};
+
+    let ckbtc = CkBtcSetup::new();
+    ckbtc
+        .env
+        .upgrade_canister(
+            ckbtc.minter_id,
+            minter_wasm(),
+            Encode!(&MinterArg::Upgrade(Some(UpgradeArgs {
+                enable_p2tr_deposits: Some(true),
+                ..UpgradeArgs::default()
+            })))
+            .unwrap(),
+        )
+        .expect("failed to upgrade the minter");
+
+    // Step 1: deposit BTC to the Taproot address of the user
+
+    let user = Principal::from(ckbtc.caller);
+    let deposit_address = Decode!(
+        &assert_reply(
+            ckbtc
+                .env
+                .execute_ingress_as(
+                    ckbtc.caller,
+                    ckbtc.minter_id,
+                    "get_btc_address",
+                    Encode!(&GetBtcAddressArgs {
+                        owner: Some(user),
+                        subaccount: None,
+                        address_type: Some(AddressType::P2tr),
+                    })
+                    .unwrap(),
+                )
+                .expect("failed to get btc address")
+        ),
+        String
+    )
+    .unwrap();
+    let deposit_value = 100_000_000;
+    let utxo = Utxo {
+        height: 0,
+        outpoint: OutPoint {
+            txid: range_to_txid(1..=32),
+            vout: 1,
+        },
+        value: deposit_value,
+    };
+    ckbtc.push_utxo(deposit_address.clone(), utxo.clone());
+
+    let utxo_status = Decode!(
+        &assert_reply(
+            ckbtc
+                .env
+                .execute_ingress_as(
+                    ckbtc.caller,
+                    ckbtc.minter_id,
+                    "update_balance",
+                    Encode!(&UpdateBalanceArgs {
+                        owner: Some(user),
+                        subaccount: None,
+                    })
+                    .unwrap()
+                )
+                .expect("failed to update balance")
+        ),
+        Result<Vec<UtxoStatus>, UpdateBalanceError>
+    )
+    .unwrap();
+    assert_eq!(
+        utxo_status.unwrap(),
+        vec![UtxoStatus::Minted {
+            block_index: 0,
+            minted_amount: deposit_value - KYT_FEE,
+            utxo,
+        }]
+    );
+
+    // Step 2: withdraw, which spends the Taproot deposit
+
+    let withdrawal_amount = 50_000_000;
+    let withdrawal_account = ckbtc.withdrawal_account(user.into());
+    ckbtc.transfer(user, withdrawal_account, withdrawal_amount);
+
+    let RetrieveBtcOk { block_index } = ckbtc
+        .retrieve_btc(WITHDRAWAL_ADDRESS.to_string(), withdrawal_amount)
+        .expect("retrieve_btc failed");
+
+    ckbtc.env.advance_time(MAX_TIME_IN_QUEUE);
+
+    let txid = ckbtc.await_btc_transaction(block_index, 10);
+    let mempool = ckbtc.mempool();
+    let tx = mempool
+        .get(&txid)
+        .expect("the mempool does not contain the withdrawal transaction");
+
+    // Step 3: check the key-path signature against the output key of the deposit address
+
+    assert_eq!(tx.input.len(), 1);
+    let witness = tx.input[0].witness.to_vec();
+    assert_eq!(witness.len(), 1, "expected a single key-path signature");
+
+    let deposit_address = BtcAddress::from_str(&deposit_address).unwrap();
+    let output_key = match &deposit_address.payload {
+        Payload::WitnessProgram { version, program } => {
+            assert_eq!(version.to_num(), 1);
+            XOnlyPublicKey::from_slice(program).unwrap()
+        }
+        payload => panic!("unexpected Taproot address payload: {:?}", payload),
+    };
+    let prevout = bitcoin::TxOut {
+        value: deposit_value,
+        script_pubkey: deposit_address.script_pubkey(),
+    };
+    let sighash = SighashCache::new(tx)
+        .taproot_key_spend_signature_hash(
+            0,
+            &Prevouts::All(&[prevout]),
+            SchnorrSighashType::Default,
+        )
+        .unwrap();
+
+    Secp256k1::verification_only()
+        .verify_schnorr(
+            &Signature::from_slice(&witness[0]).unwrap(),
+            &Message::from_slice(&sighash[..]).unwrap(),
+            &output_key,
+        )
+        .expect("the Taproot signature does not match the output key of the deposit address");
+}
+
 #[test]
 fn test_ledger_memo() {
     let ckbtc = CkBtcSetup::new();
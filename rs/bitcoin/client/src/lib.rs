@@ -211,6 +211,33 @@ pub struct BitcoinAdapterClients {
             Response = BitcoinAdapterResponseWrapper,
         >,
     >,
+    pub ltc_testnet_client: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
+    pub ltc_mainnet_client: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
+    /// Dogecoin adapters can only follow the regtest network, which is served
+    /// like the Bitcoin regtest network by the testnet client.
+    pub doge_testnet_client: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
+    /// Always fails since there are no Dogecoin mainnet adapters.
+    pub doge_mainnet_client: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
 }
 
 pub fn setup_bitcoin_adapter_clients(
@@ -221,34 +248,47 @@ pub fn setup_bitcoin_adapter_clients(
 ) -> BitcoinAdapterClients {
     let metrics = Metrics::new(metrics_registry);
 
-    // Register bitcoin adapters metrics.
-    if let Some(metrics_uds_path) = adapters_config.bitcoin_testnet_uds_metrics_path {
-        metrics_registry.register_adapter(AdapterMetrics::new(
+    // Register the metrics of the adapters of all chains.
+    for (name, metrics_uds_path) in [
+        (
             "btctestnet",
-            metrics_uds_path,
-            rt_handle.clone(),
-        ));
-    }
-    if let Some(metrics_uds_path) = adapters_config.bitcoin_mainnet_uds_metrics_path {
-        metrics_registry.register_adapter(AdapterMetrics::new(
+            adapters_config.bitcoin_testnet_uds_metrics_path,
+        ),
+        (
             "btcmainnet",
-            metrics_uds_path,
-            rt_handle.clone(),
-        ));
+            adapters_config.bitcoin_mainnet_uds_metrics_path,
+        ),
+        (
+            "ltctestnet",
+            adapters_config.litecoin_testnet_uds_metrics_path,
+        ),
+        (
+            "ltcmainnet",
+            adapters_config.litecoin_mainnet_uds_metrics_path,
+        ),
+        (
+            "dogetestnet",
+            adapters_config.dogecoin_testnet_uds_metrics_path,
+        ),
+    ] {
+        if let Some(metrics_uds_path) = metrics_uds_path {
+            metrics_registry.register_adapter(AdapterMetrics::new(
+                name,
+                metrics_uds_path,
+                rt_handle.clone(),
+            ));
+        }
     }
 
+    let setup_client = |uds_path| {
+        setup_bitcoin_adapter_client(log.clone(), metrics.clone(), rt_handle.clone(), uds_path)
+    };
     BitcoinAdapterClients {
-        btc_testnet_client: setup_bitcoin_adapter_client(
-            log.clone(),
-            metrics.clone(),
-            rt_handle.clone(),
-            adapters_config.bitcoin_testnet_uds_path,
-        ),
-        btc_mainnet_client: setup_bitcoin_adapter_client(
-            log,
-            metrics,
-            rt_handle,
-            adapters_config.bitcoin_mainnet_uds_path,
-        ),
+        btc_testnet_client: setup_client(adapters_config.bitcoin_testnet_uds_path),
+        btc_mainnet_client: setup_client(adapters_config.bitcoin_mainnet_uds_path),
+        ltc_testnet_client: setup_client(adapters_config.litecoin_testnet_uds_path),
+        ltc_mainnet_client: setup_client(adapters_config.litecoin_mainnet_uds_path),
+        doge_testnet_client: setup_client(adapters_config.dogecoin_testnet_uds_path),
+        doge_mainnet_client: setup_client(None),
     }
 }
@@ -1,3 +1,3 @@
 mod metrics;
 mod payload_builder;
-pub use payload_builder::BitcoinPayloadBuilder;
+pub use payload_builder::{AdapterClients, BitcoinPayloadBuilder};
@@ -11,7 +11,7 @@ use crate::metrics::BitcoinPayloadBuilderMetrics;
 use ic_btc_interface::Network;
 use ic_btc_replica_types::{
     BitcoinAdapterRequestWrapper, BitcoinAdapterResponse, BitcoinAdapterResponseWrapper,
-    BitcoinReject,
+    BitcoinReject, Chain,
 };
 use ic_config::bitcoin_payload_builder_config::Config;
 use ic_error_types::RejectCode;
@@ -24,7 +24,7 @@ use ic_interfaces::{
     },
     validation::ValidationError,
 };
-use ic_interfaces_adapter_client::{Options, RpcAdapterClient};
+use ic_interfaces_adapter_client::{Options, RpcAdapterClient, RpcError};
 use ic_interfaces_registry::RegistryClient;
 use ic_interfaces_state_manager::{StateManagerError, StateReader};
 use ic_logger::{log, warn, ReplicaLogger};
@@ -35,7 +35,11 @@ use ic_types::{
     messages::CallbackId,
     CountBytes, Height, NumBytes, SubnetId,
 };
-use std::{collections::BTreeSet, sync::Arc, time::Instant};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::Instant,
+};
 use thiserror::Error;
 
 const ADAPTER_REQUEST_STATUS_FAILURE: &str = "failed";
@@ -66,6 +70,24 @@ impl GetPayloadError {
     }
 }
 
+/// The adapters of a UTXO chain other than Bitcoin.
+pub struct AdapterClients {
+    /// The adapter of the main network.
+    pub mainnet: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
+    /// The adapter of the test and regtest networks.
+    pub testnet: Box<
+        dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    >,
+}
+
 pub struct BitcoinPayloadBuilder {
     state_manager: Arc<dyn StateReader<State = ReplicatedState>>,
     metrics: Arc<BitcoinPayloadBuilderMetrics>,
@@ -81,6 +103,7 @@ pub struct BitcoinPayloadBuilder {
             Response = BitcoinAdapterResponseWrapper,
         >,
     >,
+    other_chain_adapter_clients: BTreeMap<Chain, AdapterClients>,
     subnet_id: SubnetId,
     registry: Arc<dyn RegistryClient + Send + Sync>,
     config: Config,
@@ -113,6 +136,7 @@ impl BitcoinPayloadBuilder {
             metrics: Arc::new(BitcoinPayloadBuilderMetrics::new(metrics_registry)),
             bitcoin_mainnet_adapter_client,
             bitcoin_testnet_adapter_client,
+            other_chain_adapter_clients: BTreeMap::new(),
             subnet_id,
             registry,
             config,
@@ -120,6 +144,45 @@ impl BitcoinPayloadBuilder {
         }
     }
 
+    /// Sends the requests for `chain` to the given adapters. Requests for
+    /// chains other than Bitcoin that have no adapters are rejected.
+    pub fn with_adapter_clients(mut self, chain: Chain, adapter_clients: AdapterClients) -> Self {
+        assert_ne!(
+            chain,
+            Chain::Bitcoin,
+            "the Bitcoin adapters are passed to BitcoinPayloadBuilder::new"
+        );
+        self.other_chain_adapter_clients
+            .insert(chain, adapter_clients);
+        self
+    }
+
+    /// Returns the adapter that serves the chain and network of `request`.
+    fn adapter_client(
+        &self,
+        request: &BitcoinAdapterRequestWrapper,
+    ) -> Option<
+        &dyn RpcAdapterClient<
+            BitcoinAdapterRequestWrapper,
+            Response = BitcoinAdapterResponseWrapper,
+        >,
+    > {
+        let (mainnet, testnet) = match request.chain() {
+            Chain::Bitcoin => (
+                &self.bitcoin_mainnet_adapter_client,
+                &self.bitcoin_testnet_adapter_client,
+            ),
+            chain => {
+                let adapter_clients = self.other_chain_adapter_clients.get(&chain)?;
+                (&adapter_clients.mainnet, &adapter_clients.testnet)
+            }
+        };
+        Some(match request.network() {
+            Network::Mainnet => mainnet.as_ref(),
+            Network::Testnet | Network::Regtest => testnet.as_ref(),
+        })
+    }
+
     fn get_self_validating_payload_impl(
         &self,
         validation_context: &ValidationContext,
@@ -144,19 +207,20 @@ impl BitcoinPayloadBuilder {
                 continue;
             }
 
-            let adapter_client = match request.network() {
-                Network::Mainnet => &self.bitcoin_mainnet_adapter_client,
-                Network::Testnet | Network::Regtest => &self.bitcoin_testnet_adapter_client,
-            };
-
             // Send request to the adapter.
             let since = Instant::now();
-            let result = adapter_client.send_blocking(
-                request.clone(),
-                Options {
-                    timeout: self.config.adapter_timeout,
-                },
-            );
+            let result = match self.adapter_client(&request) {
+                Some(adapter_client) => adapter_client.send_blocking(
+                    request.clone(),
+                    Options {
+                        timeout: self.config.adapter_timeout,
+                    },
+                ),
+                None => Err(RpcError::Unavailable(format!(
+                    "No adapter is configured for {:?}",
+                    request.chain()
+                ))),
+            };
 
             // Update logs and metrics.
             match &result {
@@ -57,6 +57,7 @@ fn proptest_round(
                 processed_block_hashes: vec![vec![10; 32]],
                 anchor: vec![10; 32],
                 network: Network::Testnet,
+                chain: None,
             },
         )]);
 
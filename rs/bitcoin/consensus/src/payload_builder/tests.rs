@@ -1,8 +1,8 @@
-use crate::{payload_builder::parse, BitcoinPayloadBuilder};
+use crate::{payload_builder::parse, AdapterClients, BitcoinPayloadBuilder};
 use ic_btc_interface::Network;
 use ic_btc_replica_types::{
     BitcoinAdapterRequestWrapper, BitcoinAdapterResponse, BitcoinAdapterResponseWrapper,
-    BitcoinReject, GetSuccessorsRequestInitial, GetSuccessorsResponseComplete,
+    BitcoinReject, Chain, GetSuccessorsRequestInitial, GetSuccessorsResponseComplete,
 };
 use ic_config::bitcoin_payload_builder_config::Config;
 use ic_error_types::RejectCode;
@@ -157,6 +157,7 @@ fn can_successfully_create_bitcoin_payload() {
                 processed_block_hashes: vec![vec![10; 32]],
                 anchor: vec![10; 32],
                 network: Network::Testnet,
+                chain: None,
             },
         )]);
 
@@ -220,11 +221,13 @@ fn includes_responses_in_the_payload() {
             processed_block_hashes: vec![vec![10; 32]],
             anchor: vec![10; 32],
             network: Network::Testnet,
+            chain: None,
         }),
         BitcoinAdapterRequestWrapper::GetSuccessorsRequest(GetSuccessorsRequestInitial {
             processed_block_hashes: vec![vec![20; 32]],
             anchor: vec![20; 32],
             network: Network::Testnet,
+            chain: None,
         }),
     ]);
 
@@ -312,11 +315,13 @@ fn includes_only_responses_for_callback_ids_not_seen_in_past_payloads() {
             processed_block_hashes: vec![vec![10; 32]],
             anchor: vec![10; 32],
             network: Network::Testnet,
+            chain: None,
         }),
         BitcoinAdapterRequestWrapper::GetSuccessorsRequest(GetSuccessorsRequestInitial {
             processed_block_hashes: vec![vec![20; 32]],
             anchor: vec![20; 32],
             network: Network::Testnet,
+            chain: None,
         }),
     ]);
 
@@ -416,6 +421,7 @@ fn bitcoin_payload_builder_fits_largest_blocks() {
                 processed_block_hashes: vec![vec![10; 32]],
                 anchor: vec![10; 32],
                 network: Network::Testnet,
+                chain: None,
             },
         )]);
 
@@ -466,3 +472,99 @@ fn bitcoin_payload_builder_fits_largest_blocks() {
         },
     );
 }
+
+#[test]
+fn sends_requests_to_the_adapters_of_their_chain() {
+    // Create a mock adapter client that only accepts requests for `chain` and
+    // returns `next` as the next headers.
+    fn mock_adapter(chain: Chain, next: u8) -> MockBitcoinAdapterClient {
+        let mut adapter_client = MockBitcoinAdapterClient::new();
+        adapter_client
+            .expect_send_blocking()
+            .withf(move |request, _| request.chain() == chain)
+            .times(1)
+            .returning(move |_, _| {
+                Ok(BitcoinAdapterResponseWrapper::GetSuccessorsResponse(
+                    GetSuccessorsResponseComplete {
+                        blocks: vec![],
+                        next: vec![vec![next; 80]],
+                    },
+                ))
+            });
+        adapter_client
+    }
+
+    let request = |chain| {
+        BitcoinAdapterRequestWrapper::GetSuccessorsRequest(GetSuccessorsRequestInitial {
+            processed_block_hashes: vec![],
+            anchor: vec![10; 32],
+            network: Network::Testnet,
+            chain,
+        })
+    };
+    let state_manager = mock_state_manager(vec![
+        request(None),
+        request(Some(Chain::Litecoin)),
+        request(Some(Chain::Dogecoin)),
+    ]);
+
+    let registry_client = mock_registry_client(MAX_BLOCK_PAYLOAD_SIZE);
+
+    bitcoin_payload_builder_test(
+        MockBitcoinAdapterClient::new(),
+        mock_adapter(Chain::Bitcoin, 1),
+        state_manager,
+        registry_client,
+        |proposal_context, bitcoin_payload_builder| {
+            // There are no Dogecoin adapters.
+            let bitcoin_payload_builder = bitcoin_payload_builder.with_adapter_clients(
+                Chain::Litecoin,
+                AdapterClients {
+                    mainnet: Box::new(MockBitcoinAdapterClient::new()),
+                    testnet: Box::new(mock_adapter(Chain::Litecoin, 2)),
+                },
+            );
+
+            let response = |next| {
+                BitcoinAdapterResponseWrapper::GetSuccessorsResponse(
+                    GetSuccessorsResponseComplete {
+                        blocks: vec![],
+                        next: vec![vec![next; 80]],
+                    },
+                )
+            };
+            let expected_payload = FakeSelfValidatingPayloadBuilder::new()
+                .with_responses(vec![
+                    BitcoinAdapterResponse {
+                        response: response(1),
+                        callback_id: 0,
+                    },
+                    BitcoinAdapterResponse {
+                        response: response(2),
+                        callback_id: 1,
+                    },
+                    BitcoinAdapterResponse {
+                        response: BitcoinAdapterResponseWrapper::GetSuccessorsReject(
+                            BitcoinReject {
+                                reject_code: RejectCode::SysTransient,
+                                message: "Unavailable(No adapter is configured for Dogecoin)"
+                                    .to_string(),
+                            },
+                        ),
+                        callback_id: 2,
+                    },
+                ])
+                .build();
+
+            let payload = bitcoin_payload_builder
+                .get_self_validating_payload(
+                    proposal_context.validation_context,
+                    &[],
+                    SELF_VALIDATING_PAYLOAD_BYTE_LIMIT,
+                    0,
+                )
+                .0;
+            assert_eq!(payload, expected_payload);
+        },
+    );
+}
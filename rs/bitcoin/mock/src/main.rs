@@ -4,7 +4,7 @@ use ic_btc_interface::{
     MillisatoshiPerByte, Network, Utxo, UtxosFilterInRequest,
 };
 use ic_cdk::api::management_canister::bitcoin::{BitcoinNetwork, SendTransactionRequest};
-use ic_cdk_macros::{init, update};
+use ic_cdk_macros::{init, query, update};
 use serde_bytes::ByteBuf;
 use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet};
@@ -86,6 +86,16 @@ fn set_tip_height(tip_height: u32) {
 #[candid_method(update)]
 #[update]
 fn bitcoin_get_utxos(utxos_request: GetUtxosRequest) -> GetUtxosResponse {
+    get_utxos(utxos_request)
+}
+
+#[candid_method(query)]
+#[query]
+fn bitcoin_get_utxos_query(utxos_request: GetUtxosRequest) -> GetUtxosResponse {
+    get_utxos(utxos_request)
+}
+
+fn get_utxos(utxos_request: GetUtxosRequest) -> GetUtxosResponse {
     read_state(|s| {
         assert_eq!(utxos_request.network, s.network.into());
 
@@ -16,11 +16,64 @@ use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 use std::mem::size_of_val;
 
+/// The UTXO chain that an adapter request is for. Each chain is served by its
+/// own adapters.
+#[derive(
+    Clone,
+    Copy,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    Debug,
+    Default,
+    CandidType,
+    Deserialize,
+    Serialize,
+)]
+pub enum Chain {
+    #[default]
+    #[serde(rename = "bitcoin")]
+    Bitcoin,
+    #[serde(rename = "litecoin")]
+    Litecoin,
+    #[serde(rename = "dogecoin")]
+    Dogecoin,
+}
+
+impl From<Option<Chain>> for v1::Chain {
+    fn from(chain: Option<Chain>) -> Self {
+        match chain {
+            None => v1::Chain::Unspecified,
+            Some(Chain::Bitcoin) => v1::Chain::Bitcoin,
+            Some(Chain::Litecoin) => v1::Chain::Litecoin,
+            Some(Chain::Dogecoin) => v1::Chain::Dogecoin,
+        }
+    }
+}
+
+fn chain_from_proto(chain: i32, field: &'static str) -> Result<Option<Chain>, ProxyDecodeError> {
+    match v1::Chain::try_from(chain) {
+        Ok(v1::Chain::Unspecified) => Ok(None),
+        Ok(v1::Chain::Bitcoin) => Ok(Some(Chain::Bitcoin)),
+        Ok(v1::Chain::Litecoin) => Ok(Some(Chain::Litecoin)),
+        Ok(v1::Chain::Dogecoin) => Ok(Some(Chain::Dogecoin)),
+        Err(_) => Err(ProxyDecodeError::ValueOutOfRange {
+            typ: field,
+            err: format!("unknown chain {}", chain),
+        }),
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize, Serialize)]
 pub struct SendTransactionRequest {
     pub network: Network,
     #[serde(with = "serde_bytes")]
     pub transaction: Vec<u8>,
+    /// The chain of the transaction. Bitcoin if not set.
+    #[serde(default)]
+    pub chain: Option<Chain>,
 }
 
 impl From<&SendTransactionRequest> for v1::SendTransactionRequest {
@@ -32,6 +85,7 @@ impl From<&SendTransactionRequest> for v1::SendTransactionRequest {
                 Network::Regtest => 3,
             },
             transaction: request.transaction.clone(),
+            chain: v1::Chain::from(request.chain).into(),
         }
     }
 }
@@ -51,6 +105,7 @@ impl TryFrom<v1::SendTransactionRequest> for SendTransactionRequest {
                 }
             },
             transaction: request.transaction,
+            chain: chain_from_proto(request.chain, "SendTransactionRequest::chain")?,
         })
     }
 }
@@ -69,7 +124,21 @@ impl BitcoinAdapterRequestWrapper {
         }
     }
 
-    /// Returns which bitcoin network the request is for.
+    /// Returns which chain the request is for.
+    pub fn chain(&self) -> Chain {
+        match self {
+            BitcoinAdapterRequestWrapper::GetSuccessorsRequest(GetSuccessorsRequestInitial {
+                chain,
+                ..
+            }) => chain.unwrap_or_default(),
+            BitcoinAdapterRequestWrapper::SendTransactionRequest(SendTransactionRequest {
+                chain,
+                ..
+            }) => chain.unwrap_or_default(),
+        }
+    }
+
+    /// Returns which network of its chain the request is for.
     pub fn network(&self) -> Network {
         match self {
             BitcoinAdapterRequestWrapper::GetSuccessorsRequest(GetSuccessorsRequestInitial {
@@ -640,6 +709,9 @@ pub struct GetSuccessorsRequestInitial {
     pub network: Network,
     pub anchor: BlockHash,
     pub processed_block_hashes: Vec<BlockHash>,
+    /// The chain to retrieve blocks from. Bitcoin if not set.
+    #[serde(default)]
+    pub chain: Option<Chain>,
 }
 
 impl From<&GetSuccessorsRequestInitial> for v1::GetSuccessorsRequestInitial {
@@ -652,6 +724,7 @@ impl From<&GetSuccessorsRequestInitial> for v1::GetSuccessorsRequestInitial {
             },
             anchor: request.anchor.clone(),
             processed_block_hashes: request.processed_block_hashes.clone(),
+            chain: v1::Chain::from(request.chain).into(),
         }
     }
 }
@@ -672,6 +745,7 @@ impl TryFrom<v1::GetSuccessorsRequestInitial> for GetSuccessorsRequestInitial {
             },
             anchor: request.anchor,
             processed_block_hashes: request.processed_block_hashes,
+            chain: chain_from_proto(request.chain, "GetSuccessorsRequestInitial::chain")?,
         })
     }
 }
@@ -786,4 +860,55 @@ mod test {
             12
         );
     }
+
+    #[test]
+    fn chain_round_trips_through_protobuf() {
+        for chain in [
+            None,
+            Some(Chain::Bitcoin),
+            Some(Chain::Litecoin),
+            Some(Chain::Dogecoin),
+        ] {
+            let request = GetSuccessorsRequestInitial {
+                network: Network::Regtest,
+                anchor: vec![1; 32],
+                processed_block_hashes: vec![],
+                chain,
+            };
+            let proto = v1::GetSuccessorsRequestInitial::from(&request);
+            assert_eq!(
+                GetSuccessorsRequestInitial::try_from(proto).unwrap(),
+                request
+            );
+
+            let request = SendTransactionRequest {
+                network: Network::Testnet,
+                transaction: vec![1, 2, 3],
+                chain,
+            };
+            let proto = v1::SendTransactionRequest::from(&request);
+            assert_eq!(SendTransactionRequest::try_from(proto).unwrap(), request);
+        }
+
+        // Requests stored before chains were introduced are Bitcoin requests.
+        let request = SendTransactionRequest::try_from(v1::SendTransactionRequest {
+            network: 2,
+            transaction: vec![],
+            chain: 0,
+        })
+        .unwrap();
+        assert_eq!(
+            BitcoinAdapterRequestWrapper::SendTransactionRequest(request).chain(),
+            Chain::Bitcoin
+        );
+
+        assert!(
+            SendTransactionRequest::try_from(v1::SendTransactionRequest {
+                network: 2,
+                transaction: vec![],
+                chain: 42,
+            })
+            .is_err()
+        );
+    }
 }
@@ -7,6 +7,12 @@ pub struct AdaptersConfig {
     pub bitcoin_mainnet_uds_metrics_path: Option<PathBuf>,
     pub bitcoin_testnet_uds_path: Option<PathBuf>,
     pub bitcoin_testnet_uds_metrics_path: Option<PathBuf>,
+    pub litecoin_mainnet_uds_path: Option<PathBuf>,
+    pub litecoin_mainnet_uds_metrics_path: Option<PathBuf>,
+    pub litecoin_testnet_uds_path: Option<PathBuf>,
+    pub litecoin_testnet_uds_metrics_path: Option<PathBuf>,
+    pub dogecoin_testnet_uds_path: Option<PathBuf>,
+    pub dogecoin_testnet_uds_metrics_path: Option<PathBuf>,
     pub https_outcalls_uds_path: Option<PathBuf>,
     pub https_outcalls_uds_metrics_path: Option<PathBuf>,
 }
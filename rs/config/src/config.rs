@@ -201,13 +201,17 @@ impl ConfigValidate for ConfigOptional {
         let mut same_uds_paths = false;
         if let Some(adapters_config) = &self.adapters_config {
             let mut uds_paths = HashSet::new();
-            if let Some(uds_path) = &adapters_config.bitcoin_mainnet_uds_path {
-                same_uds_paths |= !uds_paths.insert(uds_path.clone());
-            }
-            if let Some(uds_path) = &adapters_config.bitcoin_testnet_uds_path {
-                same_uds_paths |= !uds_paths.insert(uds_path.clone());
-            }
-            if let Some(uds_path) = &adapters_config.https_outcalls_uds_path {
+            for uds_path in [
+                &adapters_config.bitcoin_mainnet_uds_path,
+                &adapters_config.bitcoin_testnet_uds_path,
+                &adapters_config.litecoin_mainnet_uds_path,
+                &adapters_config.litecoin_testnet_uds_path,
+                &adapters_config.dogecoin_testnet_uds_path,
+                &adapters_config.https_outcalls_uds_path,
+            ]
+            .into_iter()
+            .flatten()
+            {
                 same_uds_paths |= !uds_paths.insert(uds_path.clone());
             }
             if same_uds_paths {
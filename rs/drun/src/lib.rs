@@ -1,11 +1,12 @@
 //! Standalone interface for testing application canisters.
 
-use crate::message::{msg_stream_from_file, Message};
+use crate::message::{msg_stream_from_file, Expectation, ExpectedPayload, Message};
+use candid::{IDLArgs, TypeEnv};
 use hex::encode;
 use ic_config::{subnet_config::SubnetConfig, Config};
 use ic_crypto_test_utils_ni_dkg::dummy_initial_dkg_transcript_with_master_key;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_error_types::{ErrorCode, UserError};
+use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_execution_environment::ExecutionServices;
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
 use ic_interfaces::{
@@ -42,11 +43,13 @@ use ic_types::{
     messages::{MessageId, SignedIngress},
     replica_config::ReplicaConfig,
     time, CanisterId, NodeId, NumInstructions, PrincipalId, Randomness, RegistryVersion, SubnetId,
+    Time,
 };
 use rand::distributions::{Distribution, Uniform};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use slog::{Drain, Logger};
+use std::cell::Cell;
 use std::collections::BTreeMap;
 use std::fs::OpenOptions;
 use std::path::PathBuf;
@@ -77,14 +80,22 @@ fn deliver_message(
     message_routing: &dyn MessageRouting,
     ingress_hist_reader: &dyn IngressHistoryReader,
     extra_batches: u64,
-) {
+    time_offset: Duration,
+) -> Result<WasmResult, UserError> {
     let message_id = msg.id();
 
-    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader);
+    let result = execute_ingress_message(
+        message_routing,
+        msg,
+        &message_id,
+        ingress_hist_reader,
+        time_offset,
+    );
     // print result after waiting, to not interleave the result
     // with debug.print messages from subsequent calls. revise after DFN-1269.
-    wait_extra_batches(message_routing, extra_batches);
+    wait_extra_batches(message_routing, extra_batches, time_offset);
     print_ingress_result(&message_id, ingress_hist_reader);
+    result
 }
 
 fn setup_logger(log_file: PathBuf) -> Logger {
@@ -192,7 +203,9 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
         subnet_id,
     };
 
-    let msg_stream = msg_stream_from_file(&msg_filename)?;
+    // The offset of the batch time from the current (system) time.
+    let time_offset = Cell::new(Duration::ZERO);
+    let msg_stream = msg_stream_from_file(&msg_filename, || batch_time(time_offset.get()))?;
     let log = match log_file {
         Some(log_file) => setup_logger(log_file),
         None => slog::Logger::root(slog::Discard, slog::o!()),
@@ -260,15 +273,18 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
         MaliciousFlags::default(),
     );
 
+    // The result of the last message (if any) that subsequent expectations are checked against.
+    let mut last_result = None;
     for parse_result in msg_stream {
         match parse_result? {
             Message::Install(msg) => {
-                deliver_message(
+                last_result = Some(deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
-                );
+                    time_offset.get(),
+                ));
             }
 
             Message::Query(q) => {
@@ -285,31 +301,116 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
                         panic!("Certified state unavailable for query call.")
                     }
                 };
-                print_query_result(query_result);
+                print_query_result(query_result.clone());
+                last_result = Some(query_result);
             }
 
             Message::Ingress(msg) => {
-                deliver_message(
+                last_result = Some(deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
-                );
+                    time_offset.get(),
+                ));
             }
 
             Message::Create(msg) => {
-                deliver_message(
+                last_result = Some(deliver_message(
                     msg,
                     &message_routing,
                     ingress_hist_reader.as_ref(),
                     extra_batches,
-                );
+                    time_offset.get(),
+                ));
             }
+
+            Message::TopUp(msg) => {
+                last_result = Some(deliver_message(
+                    msg,
+                    &message_routing,
+                    ingress_hist_reader.as_ref(),
+                    extra_batches,
+                    time_offset.get(),
+                ));
+            }
+
+            Message::AdvanceTime(duration) => {
+                time_offset.set(time_offset.get() + duration);
+                // Deliver a batch with the advanced time so that
+                // canister timers and heartbeats observe it.
+                wait_extra_batches(&message_routing, 1, time_offset.get());
+            }
+
+            Message::Expect(expectation) => match &last_result {
+                Some(result) => check_expectation(&expectation, result)?,
+                None => {
+                    return Err(format!(
+                        "Expectation {:?} does not follow any message",
+                        expectation
+                    ))
+                }
+            },
         }
     }
     Ok(())
 }
 
+/// Checks that the result of a message meets the given expectation.
+fn check_expectation(
+    expectation: &Expectation,
+    result: &Result<WasmResult, UserError>,
+) -> Result<(), String> {
+    let met = match (expectation, result) {
+        (Expectation::Reply(None), Ok(WasmResult::Reply(_))) => true,
+        (Expectation::Reply(Some(ExpectedPayload::Raw(expected))), Ok(WasmResult::Reply(v))) => {
+            expected == v
+        }
+        (Expectation::Reply(Some(ExpectedPayload::Candid(expected))), Ok(WasmResult::Reply(v))) => {
+            candid_reply_matches(v, expected)?
+        }
+        (Expectation::Reject(expected), Ok(WasmResult::Reject(_))) => {
+            *expected == RejectCode::CanisterReject
+        }
+        (Expectation::Reject(expected), Err(error)) => *expected == error.reject_code(),
+        (Expectation::Reply(_), _) | (Expectation::Reject(_), Ok(WasmResult::Reply(_))) => false,
+    };
+    if met {
+        Ok(())
+    } else {
+        Err(format!(
+            "Expectation {:?} not met by result {}",
+            expectation,
+            format_result(result)
+        ))
+    }
+}
+
+/// Checks whether the Candid-encoded reply decodes to the given Candid values.
+fn candid_reply_matches(reply: &[u8], expected: &str) -> Result<bool, String> {
+    let actual = IDLArgs::from_bytes(reply)
+        .map_err(|e| format!("Failed to decode reply as Candid: {}", e))?;
+    // The expected values are typed like the actual reply and re-decoded so that both
+    // use the same representation (e.g., of record field labels).
+    let expected = candid_parser::parse_idl_args(expected)
+        .map_err(|e| e.to_string())?
+        .to_bytes_with_types(&TypeEnv::new(), &actual.get_types())
+        .map_err(|e| format!("Failed to type {} like the reply: {}", expected, e))?;
+    let expected = IDLArgs::from_bytes(&expected).map_err(|e| e.to_string())?;
+    Ok(actual.to_string() == expected.to_string())
+}
+
+fn format_result(result: &Result<WasmResult, UserError>) -> String {
+    match result {
+        Ok(WasmResult::Reply(v)) => match IDLArgs::from_bytes(v) {
+            Ok(args) => format!("Reply: {}", args),
+            Err(_) => format!("Reply: 0x{}", encode(v)),
+        },
+        Ok(WasmResult::Reject(e)) => format!("Reject: {}", e),
+        Err(e) => format!("Err: {}", e),
+    }
+}
+
 fn print_query_result(res: Result<WasmResult, UserError>) {
     match res {
         Ok(payload) => {
@@ -353,7 +454,16 @@ fn get_random_seed() -> [u8; 32] {
     seed.try_into().unwrap()
 }
 
-fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -> Batch {
+/// Returns the time of batches delivered with the given offset from the current (system) time.
+fn batch_time(time_offset: Duration) -> Time {
+    time::current_time() + time_offset
+}
+
+fn build_batch(
+    message_routing: &dyn MessageRouting,
+    msgs: Vec<SignedIngress>,
+    time_offset: Duration,
+) -> Batch {
     Batch {
         batch_number: message_routing.expected_batch_height(),
         batch_summary: None,
@@ -366,7 +476,7 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
         idkg_subnet_public_keys: BTreeMap::new(),
         idkg_pre_signature_ids: BTreeMap::new(),
         registry_version: RegistryVersion::from(1),
-        time: time::current_time(),
+        time: batch_time(time_offset),
         consensus_responses: vec![],
         blockmaker_metrics: BlockmakerMetrics::new_for_test(),
     }
@@ -380,8 +490,9 @@ fn execute_ingress_message(
     msg: SignedIngress,
     msg_id: &MessageId,
     ingress_history: &dyn IngressHistoryReader,
+    time_offset: Duration,
 ) -> Result<WasmResult, UserError> {
-    let mut batch = build_batch(message_routing, vec![msg]);
+    let mut batch = build_batch(message_routing, vec![msg], time_offset);
     for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
         // In the first batch we try to send the ingress message itself. If it fails, we
         // repeat with the same batch.
@@ -392,7 +503,7 @@ fn execute_ingress_message(
         // potential inter-canister messages that the ingress message may have
         // triggered.
         if message_routing.deliver_batch(batch.clone()).is_ok() {
-            batch = build_batch(message_routing, vec![])
+            batch = build_batch(message_routing, vec![], time_offset)
         }
         sleep(WAIT_PER_BATCH);
 
@@ -438,10 +549,14 @@ fn execute_ingress_message(
 ///
 /// This is a temporary measure until DFN-1269 is resolved. In that ticket, we
 /// will actually try to wait until all messages have been executed.
-fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64) {
+fn wait_extra_batches(
+    message_routing: &dyn MessageRouting,
+    extra_batches: u64,
+    time_offset: Duration,
+) {
     for _ in 0..extra_batches {
         loop {
-            let batch = build_batch(message_routing, vec![]);
+            let batch = build_batch(message_routing, vec![], time_offset);
             let ok = message_routing.deliver_batch(batch).is_ok();
             sleep(WAIT_PER_BATCH);
             if ok {
@@ -454,6 +569,8 @@ fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ic_config::flag_status::FlagStatus;
+    use ic_universal_canister::{wasm, UNIVERSAL_CANISTER_WASM};
     #[test]
     fn test_get_random_seed() {
         let seed_1 = get_random_seed();
@@ -467,4 +584,43 @@ mod tests {
         }
         assert_ne!(equal, len);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn should_execute_ingress_after_advancing_time_beyond_ingress_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let wasm_file = dir.path().join("universal_canister.wasm.gz");
+        std::fs::write(&wasm_file, UNIVERSAL_CANISTER_WASM).unwrap();
+        let canister_id = CanisterId::from_u64(0);
+        let msg_file = dir.path().join("messages.txt");
+        std::fs::write(
+            &msg_file,
+            format!(
+                "create\n\
+                 install {canister_id} {} \"\"\n\
+                 advance_time 600\n\
+                 ingress {canister_id} update 0x{}\n\
+                 expect reply 0x{}\n",
+                wasm_file.display(),
+                encode(wasm().reply_data(b"pong").build()),
+                encode(b"pong"),
+            ),
+        )
+        .unwrap();
+
+        let result = Config::run_with_temp_config(|mut cfg| async move {
+            cfg.hypervisor.canister_sandboxing_flag = FlagStatus::Disabled;
+            run_drun(DrunOptions {
+                msg_filename: msg_file.to_str().unwrap().to_string(),
+                cfg,
+                extra_batches: 0,
+                log_file: None,
+                instruction_limit: None,
+                subnet_type: SubnetType::System,
+            })
+            .await
+        })
+        .await;
+
+        assert_eq!(result, Ok(()));
+    }
 }
@@ -1,14 +1,15 @@
 use super::CanisterId;
 
 use hex::decode;
+use ic_error_types::RejectCode;
 use ic_execution_environment::execution::upgrade::ENHANCED_ORTHOGONAL_PERSISTENCE_SECTION;
+use ic_limits::{MAX_INGRESS_TTL, PERMITTED_DRIFT};
 use ic_management_canister_types::{
     self as ic00, CanisterInstallModeV2, CanisterUpgradeOptions, Payload, WasmMemoryPersistence,
 };
 use ic_types::{
     messages::{Query, QuerySource, SignedIngress},
-    time::expiry_time_from_now,
-    PrincipalId, UserId,
+    PrincipalId, Time, UserId,
 };
 
 use std::{
@@ -17,6 +18,7 @@ use std::{
     io::{self, Read},
     str::Chars,
     string::FromUtf8Error,
+    time::Duration,
 };
 
 #[derive(PartialEq, Debug)]
@@ -25,6 +27,26 @@ pub(crate) enum Message {
     Query(Query),
     Install(SignedIngress),
     Create(SignedIngress),
+    TopUp(SignedIngress),
+    AdvanceTime(Duration),
+    Expect(Expectation),
+}
+
+/// An assertion on the result of the preceding message.
+#[derive(PartialEq, Debug)]
+pub(crate) enum Expectation {
+    /// The message was replied to (with the given payload, if any).
+    Reply(Option<ExpectedPayload>),
+    /// The message was rejected with the given reject code.
+    Reject(RejectCode),
+}
+
+#[derive(PartialEq, Debug)]
+pub(crate) enum ExpectedPayload {
+    /// The reply must match the given octet-string exactly.
+    Raw(Vec<u8>),
+    /// The reply must decode to the given Candid values (in textual representation).
+    Candid(String),
 }
 
 #[derive(Debug)]
@@ -124,8 +146,12 @@ impl<R: Read> Iterator for LineIterator<R> {
     }
 }
 
-pub(crate) fn msg_stream_from_file(
+/// Returns the messages parsed from the given file. Since messages are parsed lazily,
+/// their ingress expiry is derived from the batch time (given by `batch_time`) at which
+/// they are parsed, so that they do not expire when the batch time is advanced.
+pub(crate) fn msg_stream_from_file<F: Fn() -> Time>(
     filename: &str,
+    batch_time: F,
 ) -> Result<impl Iterator<Item = Result<Message, String>>, String> {
     let f = File::open(filename).map_err(|e| e.to_string())?;
     let line_iterator = LineIterator::new(f);
@@ -137,15 +163,19 @@ pub(crate) fn msg_stream_from_file(
             Ok(s) => !s.is_empty() && !s.starts_with('#'),
             _ => true,
         })
-        .map(|(i, line)| match line {
-            Ok(line) => {
-                parse_message(&line, i as u64).map_err(|e| format!("Line {}: {}", i + 1, e))
-            }
+        .map(move |(i, line)| match line {
+            Ok(line) => parse_message(&line, i as u64, ingress_expiry(batch_time()))
+                .map_err(|e| format!("Line {}: {}", i + 1, e)),
             Err(e) => Err(format!("Error while reading line {}: {}", i, e)),
         }))
 }
 
-fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
+/// Returns the latest ingress expiry accepted at the given batch time.
+fn ingress_expiry(batch_time: Time) -> Time {
+    batch_time + (MAX_INGRESS_TTL - PERMITTED_DRIFT)
+}
+
+fn parse_message(s: &str, nonce: u64, expiry_time: Time) -> Result<Message, String> {
     let s = s.trim_end();
     let tokens: Vec<&str> = s.splitn(4, char::is_whitespace).collect();
 
@@ -165,29 +195,57 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
                 .method_name(method_name)
                 .method_payload(method_payload)
                 .nonce(nonce)
+                .expiry_time(expiry_time)
                 .build();
             Ok(Message::Ingress(signed_ingress))
         }
         ["query", canister_id, method_name, payload] => Ok(Message::Query(Query {
             source: QuerySource::User {
                 user_id: UserId::from(PrincipalId::new_anonymous()),
-                ingress_expiry: expiry_time_from_now().as_nanos_since_unix_epoch(),
+                ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
                 nonce: Some(nonce.to_le_bytes().to_vec()),
             },
             receiver: parse_canister_id(canister_id)?,
             method_name: validate_method_name(method_name)?,
             method_payload: parse_octet_string(payload)?,
         })),
-        ["create"] => parse_create(nonce),
-        ["install", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "install")
-        }
-        ["reinstall", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "reinstall")
-        }
-        ["upgrade", canister_id, wasm_file, payload] => {
-            parse_install(nonce, canister_id, payload, wasm_file, "upgrade")
-        }
+        ["create"] => parse_create(nonce, expiry_time),
+        ["top_up", canister_id, cycles] => parse_top_up(nonce, expiry_time, canister_id, cycles),
+        ["advance_time", seconds] => seconds
+            .parse::<u64>()
+            .map(|seconds| Message::AdvanceTime(Duration::from_secs(seconds)))
+            .map_err(|e| format!("Failed to parse number of seconds {}: {}", seconds, e)),
+        ["expect", "reply"] => Ok(Message::Expect(Expectation::Reply(None))),
+        ["expect", "reply", payload] => parse_expected_payload(payload)
+            .map(|payload| Message::Expect(Expectation::Reply(Some(payload)))),
+        ["expect", "reply", first, rest] => parse_expected_payload(&format!("{} {}", first, rest))
+            .map(|payload| Message::Expect(Expectation::Reply(Some(payload)))),
+        ["expect", "reject", reject_code] => parse_reject_code(reject_code)
+            .map(|reject_code| Message::Expect(Expectation::Reject(reject_code))),
+        ["install", canister_id, wasm_file, payload] => parse_install(
+            nonce,
+            expiry_time,
+            canister_id,
+            payload,
+            wasm_file,
+            "install",
+        ),
+        ["reinstall", canister_id, wasm_file, payload] => parse_install(
+            nonce,
+            expiry_time,
+            canister_id,
+            payload,
+            wasm_file,
+            "reinstall",
+        ),
+        ["upgrade", canister_id, wasm_file, payload] => parse_install(
+            nonce,
+            expiry_time,
+            canister_id,
+            payload,
+            wasm_file,
+            "upgrade",
+        ),
         _ => Err(format!(
             "Failed to parse line {}, don't have a pattern to match this with",
             s
@@ -206,7 +264,7 @@ fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
     }
 }
 
-fn parse_create(nonce: u64) -> Result<Message, String> {
+fn parse_create(nonce: u64, expiry_time: Time) -> Result<Message, String> {
     use ic_test_utilities_types::messages::SignedIngressBuilder;
 
     let signed_ingress = SignedIngressBuilder::new()
@@ -214,11 +272,56 @@ fn parse_create(nonce: u64) -> Result<Message, String> {
         .canister_id(ic00::IC_00)
         .method_payload(ic00::ProvisionalCreateCanisterWithCyclesArgs::new(None, None).encode())
         .nonce(nonce)
+        .expiry_time(expiry_time)
         .build();
 
     Ok(Message::Create(signed_ingress))
 }
 
+fn parse_top_up(
+    nonce: u64,
+    expiry_time: Time,
+    canister_id: &str,
+    cycles: &str,
+) -> Result<Message, String> {
+    use ic_test_utilities_types::messages::SignedIngressBuilder;
+
+    let canister_id = parse_canister_id(canister_id)?;
+    let cycles = cycles
+        .parse::<u128>()
+        .map_err(|e| format!("Failed to parse amount of cycles {}: {}", cycles, e))?;
+
+    let signed_ingress = SignedIngressBuilder::new()
+        .method_name(ic00::Method::ProvisionalTopUpCanister)
+        .canister_id(ic00::IC_00)
+        .method_payload(ic00::ProvisionalTopUpCanisterArgs::new(canister_id, cycles).encode())
+        .nonce(nonce)
+        .expiry_time(expiry_time)
+        .build();
+
+    Ok(Message::TopUp(signed_ingress))
+}
+
+fn parse_expected_payload(payload: &str) -> Result<ExpectedPayload, String> {
+    if payload.starts_with('(') {
+        // Validate the Candid values, but keep their textual representation
+        // since they can only be typed once the actual reply is known.
+        candid_parser::parse_idl_args(payload)
+            .map_err(|e| format!("Failed to parse Candid arguments {}: {}", payload, e))?;
+        Ok(ExpectedPayload::Candid(payload.to_string()))
+    } else {
+        parse_octet_string(payload).map(ExpectedPayload::Raw)
+    }
+}
+
+fn parse_reject_code(reject_code: &str) -> Result<RejectCode, String> {
+    reject_code
+        .parse::<u64>()
+        .map_err(|e| e.to_string())
+        .and_then(|code| RejectCode::try_from(code).map_err(|e| format!("{:?}", e)))
+        .map_err(|e| format!("Illegal reject code {}: {}", reject_code, e))
+}
+
 fn contains_icp_private_custom_section(wasm_binary: &[u8], name: &str) -> Result<bool, String> {
     use wasmparser::{Parser, Payload::CustomSection};
 
@@ -236,6 +339,7 @@ fn contains_icp_private_custom_section(wasm_binary: &[u8], name: &str) -> Result
 
 fn parse_install(
     nonce: u64,
+    expiry_time: Time,
     canister_id: &str,
     payload: &str,
     wasm_file: &str,
@@ -285,6 +389,7 @@ fn parse_install(
                 .encode(),
         )
         .nonce(nonce)
+        .expiry_time(expiry_time)
         .build();
     Ok(Message::Install(signed_ingress))
 }
@@ -312,6 +417,8 @@ fn validate_method_name(method_name: &str) -> Result<String, String> {
 fn parse_octet_string(input_str: &str) -> Result<Vec<u8>, String> {
     if input_str.starts_with('"') {
         parse_quoted(input_str)
+    } else if input_str.starts_with('(') {
+        parse_candid(input_str)
     } else {
         parse_hex(input_str)
     }
@@ -380,6 +487,13 @@ fn parse_escape(chars: &mut Chars<'_>, radix: Radix) -> Result<u8, String> {
     }
 }
 
+fn parse_candid(s: &str) -> Result<Vec<u8>, String> {
+    candid_parser::parse_idl_args(s)
+        .map_err(|e| format!("Failed to parse Candid arguments {}: {}", s, e))?
+        .to_bytes()
+        .map_err(|e| format!("Failed to encode Candid arguments {}: {}", s, e))
+}
+
 fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
     if let Some(s) = s.strip_prefix("0x") {
         decode(s).map_err(|e| e.to_string())
@@ -398,6 +512,7 @@ enum Radix {
 mod tests {
     use super::*;
     use ic_test_utilities_types::{ids::canister_test_id, messages::SignedIngressBuilder};
+    use ic_types::time::expiry_time_from_now;
     use std::io::Cursor;
 
     const APP_CANISTER_URL: &str = "ryjl3-tyaaa-aaaaa-aaaba-cai";
@@ -409,7 +524,7 @@ mod tests {
             "ingress {} write \"payload \\x0a\\b00010001\"",
             APP_CANISTER_URL
         );
-        let parsed_message = parse_message(s, 0).unwrap();
+        let parsed_message = parse_message(s, 0, expiry_time_from_now()).unwrap();
         let expiry_time = match &parsed_message {
             Message::Ingress(signed_ingress) => signed_ingress.expiry_time(),
             _ => panic!(
@@ -432,7 +547,7 @@ mod tests {
     #[test]
     fn test_parse_message_hex_payload_succeeds() {
         let s = &format!("ingress {} write 0x010203", APP_CANISTER_URL);
-        let parsed_message = parse_message(s, 0).unwrap();
+        let parsed_message = parse_message(s, 0, expiry_time_from_now()).unwrap();
         let expiry_time = match &parsed_message {
             Message::Ingress(signed_ingress) => signed_ingress.expiry_time(),
             _ => panic!(
@@ -453,7 +568,7 @@ mod tests {
 
         let s = &format!("query {} read 0x010203", APP_CANISTER_URL);
         let nonce: u64 = 0;
-        let parsed_message = parse_message(s, 0).unwrap();
+        let parsed_message = parse_message(s, 0, expiry_time_from_now()).unwrap();
         let ingress_expiry = match &parsed_message {
             Message::Query(query) => match query.source {
                 QuerySource::User { ingress_expiry, .. } => ingress_expiry,
@@ -480,25 +595,106 @@ mod tests {
     #[test]
     fn test_parse_message_invalid_escapes_fails() {
         let s = &format!("query {} read \"\\xzz\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
 
         let s = &format!("query {} read \"\\b01\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
 
         let s = &format!("query {} read \"\\x1\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
 
         let s = &format!("query {} read \"\\b2\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
     }
 
     #[test]
     fn test_illegal_method_name_must_fail() {
         let s = &format!("query {} 0read \"\\xzz\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
 
         let s = &format!("query {} üread \"\\xzz\"", APP_CANISTER_URL);
-        assert!(parse_message(s, 0).is_err());
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_message_candid_payload_succeeds() {
+        let s = &format!("query {} read (42 : nat, \"text\")", APP_CANISTER_URL);
+        let parsed_message = parse_message(s, 0, expiry_time_from_now()).unwrap();
+        let method_payload = match &parsed_message {
+            Message::Query(query) => query.method_payload.clone(),
+            _ => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                parsed_message
+            ),
+        };
+        assert_eq!(
+            method_payload,
+            candid::encode_args((candid::Nat::from(42_u64), "text")).unwrap()
+        );
+
+        let s = &format!("query {} read (42 : nat", APP_CANISTER_URL);
+        assert!(parse_message(s, 0, expiry_time_from_now()).is_err());
+    }
+
+    #[test]
+    fn test_parse_expectations_and_directives() {
+        assert_eq!(
+            parse_message("expect reply", 0, expiry_time_from_now()).unwrap(),
+            Message::Expect(Expectation::Reply(None))
+        );
+        assert_eq!(
+            parse_message("expect reply 0x010203", 0, expiry_time_from_now()).unwrap(),
+            Message::Expect(Expectation::Reply(Some(ExpectedPayload::Raw(vec![
+                1, 2, 3
+            ]))))
+        );
+        assert_eq!(
+            parse_message("expect reply (record { a = 1 })", 0, expiry_time_from_now()).unwrap(),
+            Message::Expect(Expectation::Reply(Some(ExpectedPayload::Candid(
+                "(record { a = 1 })".to_string()
+            ))))
+        );
+        assert_eq!(
+            parse_message("expect reject 4", 0, expiry_time_from_now()).unwrap(),
+            Message::Expect(Expectation::Reject(RejectCode::CanisterReject))
+        );
+        assert!(parse_message("expect reject 7", 0, expiry_time_from_now()).is_err());
+        assert_eq!(
+            parse_message("advance_time 60", 0, expiry_time_from_now()).unwrap(),
+            Message::AdvanceTime(Duration::from_secs(60))
+        );
+        assert!(parse_message("advance_time -1", 0, expiry_time_from_now()).is_err());
+        assert!(matches!(
+            parse_message(
+                &format!("top_up {} 1000000", APP_CANISTER_URL),
+                0,
+                expiry_time_from_now()
+            )
+            .unwrap(),
+            Message::TopUp(_)
+        ));
+        assert!(parse_message(
+            &format!("top_up {} lots", APP_CANISTER_URL),
+            0,
+            expiry_time_from_now()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_ingress_expiry_follows_batch_time() {
+        let batch_time = Time::from_secs_since_unix_epoch(1_700_000_000).unwrap();
+        let s = &format!("ingress {} write 0x010203", APP_CANISTER_URL);
+        match parse_message(s, 0, ingress_expiry(batch_time)).unwrap() {
+            Message::Ingress(signed_ingress) => assert_eq!(
+                signed_ingress.expiry_time(),
+                batch_time + Duration::from_secs(4 * 60)
+            ),
+            parsed_message => panic!(
+                "parse_message() returned an unexpected message type: {:?}",
+                parsed_message
+            ),
+        }
     }
 
     #[test]
@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: Apache-2.0
+
+pragma solidity 0.8.20;
+
+/**
+ * @dev Minimal subset of the ERC-20 interface used by the helper contract.
+ */
+interface IERC20 {
+    function transferFrom(address from, address to, uint256 value) external returns (bool);
+}
+
+/**
+ * @title A helper smart contract for ETH <-> ckETH and ERC20 <-> ckERC20 conversion
+ * to an ICRC-1 account with an arbitrary subaccount.
+ * @notice This smart contract deposits incoming ETH or ERC-20 to the ckETH minter account and emits deposit events.
+ * ETH deposits are emitted with the zero address as ERC-20 contract address.
+ * The zero subaccount designates the default subaccount of the given principal.
+ */
+contract CkDepositWithSubaccount {
+    address payable private immutable cketh_minter_main_address;
+
+    event ReceivedEthOrErc20(
+        address indexed erc20ContractAddress,
+        address indexed owner,
+        uint256 amount,
+        bytes32 indexed principal,
+        bytes32 subaccount
+    );
+
+    error Erc20TransferFailed(address erc20ContractAddress);
+
+    /**
+     * @dev Set cketh_minter_main_address.
+     */
+    constructor(address _cketh_minter_main_address) {
+        cketh_minter_main_address = payable(_cketh_minter_main_address);
+    }
+
+    /**
+     * @dev Return ckETH minter main address.
+     * @return address of ckETH minter main address.
+     */
+    function getMinterAddress() public view returns (address) {
+        return cketh_minter_main_address;
+    }
+
+    /**
+     * @dev Emits the `ReceivedEthOrErc20` event if the ETH transfer succeeds.
+     */
+    function depositEth(bytes32 principal, bytes32 subaccount) public payable {
+        emit ReceivedEthOrErc20(address(0), msg.sender, msg.value, principal, subaccount);
+        cketh_minter_main_address.transfer(msg.value);
+    }
+
+    /**
+     * @dev Emits the `ReceivedEthOrErc20` event if the ERC-20 transfer succeeds.
+     * Tokens that return no value on `transferFrom` are supported; non-reverting calls are assumed to be successful.
+     */
+    function depositErc20(address erc20Address, uint256 amount, bytes32 principal, bytes32 subaccount) public {
+        (bool success, bytes memory returndata) = erc20Address.call(
+            abi.encodeCall(IERC20.transferFrom, (msg.sender, cketh_minter_main_address, amount))
+        );
+        if (!success || (returndata.length != 0 && !abi.decode(returndata, (bool))) || erc20Address.code.length == 0) {
+            revert Erc20TransferFailed(erc20Address);
+        }
+
+        emit ReceivedEthOrErc20(erc20Address, msg.sender, amount, principal, subaccount);
+    }
+}
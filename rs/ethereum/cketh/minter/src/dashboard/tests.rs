@@ -955,6 +955,7 @@ fn initial_state() -> State {
         minimum_withdrawal_amount: Nat::from(10_000_000_000_000_000_u64),
         next_transaction_nonce: TransactionNonce::ZERO.into(),
         last_scraped_block_number: candid::Nat::from(3_956_206_u32),
+        evm_network: None,
     })
     .expect("valid init args")
 }
@@ -980,6 +981,7 @@ fn received_eth_event() -> ReceivedEthEvent {
         principal: "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae"
             .parse()
             .unwrap(),
+        subaccount: None,
     }
 }
 
@@ -1000,6 +1002,7 @@ fn received_erc20_event() -> ReceivedErc20Event {
         erc20_contract_address: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
             .parse()
             .unwrap(),
+        subaccount: None,
     }
 }
 
@@ -1011,6 +1014,8 @@ pub fn ckusdc() -> CkErc20Token {
             .unwrap(),
         ckerc20_token_symbol: "ckUSDC".parse().unwrap(),
         ckerc20_ledger_id: "mxzaz-hqaaa-aaaar-qaada-cai".parse().unwrap(),
+        balance_diff_accounting: Some(false),
+        decimals: Some(6),
     }
 }
 
@@ -1022,6 +1027,8 @@ pub fn ckusdt() -> CkErc20Token {
             .unwrap(),
         ckerc20_token_symbol: "ckUSDT".parse().unwrap(),
         ckerc20_ledger_id: "sa4so-piaaa-aaaar-qacnq-cai".parse().unwrap(),
+        balance_diff_accounting: Some(false),
+        decimals: Some(6),
     }
 }
 
@@ -1113,6 +1120,7 @@ fn cketh_withdrawal_flow(
         gas_used: signed_tx.transaction().gas_limit,
         status: tx_status,
         transaction_hash: signed_tx.hash(),
+        l1_fee: None,
     };
     (
         withdrawal_request.into(),
@@ -1145,6 +1153,7 @@ fn ckerc20_withdrawal_flow(
         gas_fee,
         GasAmount::from(65_000_u32),
         EthereumNetwork::Sepolia,
+        Wei::ZERO,
     )
     .unwrap();
     let dummy_signature = Eip1559Signature {
@@ -1166,6 +1175,7 @@ fn ckerc20_withdrawal_flow(
         gas_used: signed_tx.transaction().gas_limit,
         status: tx_status,
         transaction_hash: signed_tx.hash(),
+        l1_fee: None,
     };
     (
         withdrawal_request.into(),
@@ -1,9 +1,11 @@
-use crate::eth_logs::{report_transaction_error, ReceivedEvent, ReceivedEventError};
-use crate::eth_rpc::{BlockSpec, HttpOutcallError};
-use crate::eth_rpc_client::EthRpcClient;
+use crate::erc20::MinterBalanceDiff;
+use crate::eth_logs::{report_transaction_error, Erc20Transfer, ReceivedEvent, ReceivedEventError};
+use crate::eth_rpc::{BlockSpec, Data, HttpOutcallError, LogEntry};
+use crate::eth_rpc_client::requests::{EthCallParams, TransactionCall};
+use crate::eth_rpc_client::{EthRpcClient, MultiCallError};
 use crate::guard::TimerGuard;
 use crate::logs::{DEBUG, INFO};
-use crate::numeric::{BlockNumber, LedgerMintIndex};
+use crate::numeric::{BlockNumber, Erc20Value, LedgerMintIndex};
 use crate::state::{
     audit::process_event, event::EventType, mutate_state, read_state, State, TaskType,
 };
@@ -13,6 +15,7 @@ use ic_ethereum_types::Address;
 use num_traits::ToPrimitive;
 use scopeguard::ScopeGuard;
 use std::cmp::{min, Ordering};
+use std::collections::BTreeSet;
 use std::time::Duration;
 
 pub(crate) const RECEIVED_ETH_EVENT_TOPIC: [u8; 32] =
@@ -21,6 +24,14 @@ pub(crate) const RECEIVED_ETH_EVENT_TOPIC: [u8; 32] =
 pub(crate) const RECEIVED_ERC20_EVENT_TOPIC: [u8; 32] =
     hex!("4d69d0bd4287b7f66c548f90154dc81bc98f65a1b362775df5ae171a2ccd262b");
 
+pub(crate) const RECEIVED_ETH_OR_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC: [u8; 32] =
+    hex!("918adbebdb8f3b36fc337ab76df10b147b2def5c9dd62cb3456d9aeca40e0b07");
+
+pub(crate) const ERC20_TRANSFER_EVENT_TOPIC: [u8; 32] =
+    hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+
+pub(crate) const ERC20_BALANCE_OF_SELECTOR: [u8; 4] = hex!("70a08231");
+
 async fn mint() {
     use icrc_ledger_client_cdk::{CdkRuntime, ICRC1Client};
     use icrc_ledger_types::icrc1::transfer::TransferArg;
@@ -69,7 +80,7 @@ async fn mint() {
         let block_index = match client
             .transfer(TransferArg {
                 from_subaccount: None,
-                to: (event.principal()).into(),
+                to: event.beneficiary(),
                 fee: None,
                 created_at_time: None,
                 memo: Some((&event).into()),
@@ -118,7 +129,7 @@ async fn mint() {
             INFO,
             "Minted {} {token_symbol} to {} in block {block_index}",
             event.value(),
-            event.principal()
+            event.beneficiary()
         );
         // minting succeeded, defuse guard
         ScopeGuard::into_inner(prevent_double_minting_guard);
@@ -163,8 +174,8 @@ where
                 last_block_number
             );
 
-            let (transaction_events, errors) = loop {
-                match crate::eth_logs::last_received_events(
+            let (transaction_events, errors, transfers) = loop {
+                let result = match crate::eth_logs::last_received_events(
                     topic,
                     helper_contract_address,
                     token_contract_addresses,
@@ -173,7 +184,13 @@ where
                 )
                 .await
                 {
-                    Ok((events, errors)) => break (events, errors),
+                    Ok((events, errors)) => transfers_from_minter(&events, from, last_block_number)
+                        .await
+                        .map(|transfers| (events, errors, transfers)),
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(result) => break result,
                     Err(e) => {
                         log!(
                         INFO,
@@ -210,6 +227,18 @@ where
                     }
                 };
             };
+            let balance_diffs = match minter_balance_diffs(&transaction_events).await {
+                Ok(balance_diffs) => balance_diffs,
+                Err(e) => {
+                    log!(
+                        INFO,
+                        "Failed to get the ERC-20 balances of the minter from block {from} to block {last_block_number}: {e:?}",
+                    );
+                    return None;
+                }
+            };
+            let transaction_events =
+                credit_received_amounts(transaction_events, &balance_diffs, &transfers).await;
 
             for event in transaction_events {
                 log!(
@@ -267,6 +296,161 @@ where
     }
 }
 
+/// Addresses of the ERC-20 tokens using balance-diff accounting among the deposits of the given events.
+fn balance_diff_accounting_tokens(events: &[ReceivedEvent]) -> BTreeSet<Address> {
+    read_state(|s| {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                ReceivedEvent::Erc20(deposit)
+                    if s.uses_balance_diff_accounting(&deposit.erc20_contract_address) =>
+                {
+                    Some(deposit.erc20_contract_address)
+                }
+                _ => None,
+            })
+            .collect()
+    })
+}
+
+/// Retrieves the ERC-20 `Transfer` events sent by the minter that are needed to credit
+/// the deposits of tokens using balance-diff accounting among the given events, if any.
+async fn transfers_from_minter(
+    events: &[ReceivedEvent],
+    from: BlockNumber,
+    to: BlockNumber,
+) -> Result<Vec<Erc20Transfer>, MultiCallError<Vec<LogEntry>>> {
+    let token_contract_addresses = balance_diff_accounting_tokens(events);
+    if token_contract_addresses.is_empty() {
+        return Ok(vec![]);
+    }
+    crate::eth_logs::last_erc20_transfers_from(
+        &token_contract_addresses.into_iter().collect::<Vec<_>>(),
+        crate::state::minter_address().await,
+        from,
+        to,
+    )
+    .await
+}
+
+/// Retrieves the balances of the minter before and after each block containing deposits
+/// of tokens using balance-diff accounting among the given events, by calling `balanceOf`
+/// on the ERC-20 smart contracts.
+async fn minter_balance_diffs(events: &[ReceivedEvent]) -> Result<Vec<MinterBalanceDiff>, String> {
+    let token_contract_addresses = balance_diff_accounting_tokens(events);
+    let blocks: BTreeSet<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            ReceivedEvent::Erc20(deposit)
+                if token_contract_addresses.contains(&deposit.erc20_contract_address) =>
+            {
+                Some((deposit.erc20_contract_address, deposit.block_number))
+            }
+            _ => None,
+        })
+        .collect();
+    if blocks.is_empty() {
+        return Ok(vec![]);
+    }
+    let minter_address = crate::state::minter_address().await;
+    let mut balance_diffs = Vec::with_capacity(blocks.len());
+    for (erc20_contract_address, block_number) in blocks {
+        let balance_before = match block_number.checked_decrement() {
+            Some(previous_block_number) => {
+                erc20_balance_of(
+                    erc20_contract_address,
+                    minter_address,
+                    previous_block_number,
+                )
+                .await?
+            }
+            None => Erc20Value::ZERO,
+        };
+        let balance_after =
+            erc20_balance_of(erc20_contract_address, minter_address, block_number).await?;
+        balance_diffs.push(MinterBalanceDiff {
+            erc20_contract_address,
+            block_number,
+            balance_before,
+            balance_after,
+        });
+    }
+    Ok(balance_diffs)
+}
+
+/// Retrieves the balance of `owner` in the given ERC-20 token at the end of the given block.
+///
+/// This requires the JSON-RPC providers to serve the state of that block, which is the case
+/// for recent blocks even without an archive node.
+async fn erc20_balance_of(
+    erc20_contract_address: Address,
+    owner: Address,
+    block_number: BlockNumber,
+) -> Result<Erc20Value, String> {
+    let mut data = ERC20_BALANCE_OF_SELECTOR.to_vec();
+    data.extend_from_slice(&<[u8; 32]>::from(&owner));
+    let result = read_state(EthRpcClient::from_state)
+        .eth_call(EthCallParams {
+            transaction: TransactionCall {
+                to: erc20_contract_address,
+                data: Data(data),
+            },
+            block: BlockSpec::Number(block_number),
+        })
+        .await
+        .reduce_with_equality()
+        .map_err(|e| format!("failed to call balanceOf on {erc20_contract_address}: {e:?}"))?;
+    <[u8; 32]>::try_from(result.0.as_slice())
+        .map(Erc20Value::from_be_bytes)
+        .map_err(|_| format!("invalid result of balanceOf on {erc20_contract_address}: {result:?}"))
+}
+
+/// Replaces the value of deposits of ERC-20 tokens using balance-diff accounting by the amount
+/// the minter actually received, see [`crate::erc20::credit_received_amounts`].
+/// Deposits for which the minter received nothing are recorded as invalid.
+async fn credit_received_amounts(
+    events: Vec<ReceivedEvent>,
+    balance_diffs: &[MinterBalanceDiff],
+    transfers_from_minter: &[Erc20Transfer],
+) -> Vec<ReceivedEvent> {
+    let mut credited_events = Vec::with_capacity(events.len());
+    let mut balance_diff_deposits = Vec::new();
+    read_state(|s| {
+        for event in events {
+            match event {
+                ReceivedEvent::Erc20(deposit)
+                    if s.uses_balance_diff_accounting(&deposit.erc20_contract_address) =>
+                {
+                    balance_diff_deposits.push(deposit)
+                }
+                event => credited_events.push(event),
+            }
+        }
+    });
+    if balance_diff_deposits.is_empty() {
+        return credited_events;
+    }
+    let (credited_deposits, rejected_deposits) = crate::erc20::credit_received_amounts(
+        balance_diff_deposits,
+        balance_diffs,
+        transfers_from_minter,
+    );
+    for (deposit, reason) in rejected_deposits {
+        log!(INFO, "Rejected deposit {deposit:?}: {reason}");
+        mutate_state(|s| {
+            process_event(
+                s,
+                EventType::InvalidDeposit {
+                    event_source: deposit.source(),
+                    reason,
+                },
+            )
+        });
+    }
+    credited_events.extend(credited_deposits.into_iter().map(ReceivedEvent::from));
+    credited_events
+}
+
 async fn scrape_contract_logs<F>(
     topic: &[u8; 32],
     topic_name: &str,
@@ -353,6 +537,34 @@ async fn scrape_erc20_logs(last_block_number: BlockNumber, max_block_spread: u16
     .await
 }
 
+async fn scrape_deposit_with_subaccount_logs(
+    last_block_number: BlockNumber,
+    max_block_spread: u16,
+) {
+    // ETH deposits are emitted with the zero address as ERC-20 contract address,
+    // so that both ETH and supported ERC-20 deposits match the topic filter.
+    let token_contract_addresses = read_state(|s| {
+        std::iter::once(Address::ZERO)
+            .chain(s.ckerc20_tokens.alt_keys().cloned())
+            .collect::<Vec<_>>()
+    });
+    scrape_contract_logs(
+        &RECEIVED_ETH_OR_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC,
+        "ETH or ERC-20 with subaccount",
+        read_state(|s| s.deposit_with_subaccount_helper_contract_address),
+        &token_contract_addresses,
+        last_block_number,
+        read_state(|s| s.last_deposit_with_subaccount_scraped_block_number),
+        max_block_spread,
+        &|last_block_number| {
+            mutate_state(|s| {
+                s.last_deposit_with_subaccount_scraped_block_number = last_block_number
+            })
+        },
+    )
+    .await
+}
+
 pub async fn scrape_logs() {
     let _guard = match TimerGuard::new(TaskType::ScrapEthLogs) {
         Ok(guard) => guard,
@@ -371,16 +583,23 @@ pub async fn scrape_logs() {
     let max_block_spread = read_state(|s| s.max_block_spread_for_logs_scraping());
     scrape_eth_logs(last_block_number, max_block_spread).await;
     scrape_erc20_logs(last_block_number, max_block_spread).await;
+    scrape_deposit_with_subaccount_logs(last_block_number, max_block_spread).await;
 }
 
 pub async fn update_last_observed_block_number() -> Option<BlockNumber> {
     let block_height = read_state(State::ethereum_block_height);
+    let block_confirmations = read_state(State::block_confirmations);
     match read_state(EthRpcClient::from_state)
         .eth_get_block_by_number(BlockSpec::Tag(block_height))
         .await
     {
         Ok(latest_block) => {
-            let block_number = Some(latest_block.number);
+            let block_number = Some(
+                latest_block
+                    .number
+                    .checked_sub(BlockNumber::from(block_confirmations))
+                    .unwrap_or(BlockNumber::ZERO),
+            );
             mutate_state(|s| s.last_observed_block_number = block_number);
             block_number
         }
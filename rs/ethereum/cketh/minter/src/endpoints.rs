@@ -42,6 +42,8 @@ pub struct CkErc20Token {
     pub ckerc20_token_symbol: String,
     pub erc20_contract_address: String,
     pub ledger_canister_id: Principal,
+    pub balance_diff_accounting: Option<bool>,
+    pub decimals: Option<u8>,
 }
 
 impl From<crate::erc20::CkErc20Token> for CkErc20Token {
@@ -50,6 +52,8 @@ impl From<crate::erc20::CkErc20Token> for CkErc20Token {
             ckerc20_token_symbol: value.ckerc20_token_symbol.to_string(),
             erc20_contract_address: value.erc20_contract_address.to_string(),
             ledger_canister_id: value.ckerc20_ledger_id,
+            balance_diff_accounting: value.balance_diff_accounting,
+            decimals: value.decimals,
         }
     }
 }
@@ -67,6 +71,7 @@ pub struct MinterInfo {
     pub smart_contract_address: Option<String>,
     pub eth_helper_contract_address: Option<String>,
     pub erc20_helper_contract_address: Option<String>,
+    pub deposit_with_subaccount_helper_contract_address: Option<String>,
     pub supported_ckerc20_tokens: Option<Vec<CkErc20Token>>,
     pub minimum_withdrawal_amount: Option<Nat>,
     pub ethereum_block_height: Option<CandidBlockTag>,
@@ -76,6 +81,7 @@ pub struct MinterInfo {
     pub erc20_balances: Option<Vec<Erc20Balance>>,
     pub last_eth_scraped_block_number: Option<Nat>,
     pub last_erc20_scraped_block_number: Option<Nat>,
+    pub last_deposit_with_subaccount_scraped_block_number: Option<Nat>,
     pub cketh_ledger_id: Option<Principal>,
 }
 
@@ -276,6 +282,8 @@ pub struct AddCkErc20Token {
     pub address: String,
     pub ckerc20_token_symbol: String,
     pub ckerc20_ledger_id: Principal,
+    pub balance_diff_accounting: Option<bool>,
+    pub decimals: Option<u8>,
 }
 
 pub mod events {
@@ -353,6 +361,7 @@ pub mod events {
         pub gas_used: Nat,
         pub status: TransactionStatus,
         pub transaction_hash: String,
+        pub l1_fee: Option<Nat>,
     }
 
     #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
@@ -366,6 +375,7 @@ pub mod events {
             from_address: String,
             value: Nat,
             principal: Principal,
+            subaccount: Option<[u8; 32]>,
         },
         AcceptedErc20Deposit {
             transaction_hash: String,
@@ -375,6 +385,7 @@ pub mod events {
             value: Nat,
             principal: Principal,
             erc20_contract_address: String,
+            subaccount: Option<[u8; 32]>,
         },
         InvalidDeposit {
             event_source: EventSource,
@@ -390,6 +401,9 @@ pub mod events {
         SyncedErc20ToBlock {
             block_number: Nat,
         },
+        SyncedDepositWithSubaccountToBlock {
+            block_number: Nat,
+        },
         AcceptedEthWithdrawalRequest {
             withdrawal_amount: Nat,
             destination: String,
@@ -437,6 +451,8 @@ pub mod events {
             address: String,
             ckerc20_token_symbol: String,
             ckerc20_ledger_id: Principal,
+            balance_diff_accounting: Option<bool>,
+            decimals: Option<u8>,
         },
         AcceptedErc20WithdrawalRequest {
             max_transaction_fee: Nat,
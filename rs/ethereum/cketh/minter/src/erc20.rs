@@ -4,12 +4,15 @@ pub mod test_fixtures;
 mod tests;
 
 use crate::endpoints::AddCkErc20Token;
+use crate::eth_logs::{Erc20Transfer, ReceivedErc20Event};
 use crate::lifecycle::EthereumNetwork;
+use crate::numeric::{BlockNumber, Erc20Value};
 use crate::state::State;
 use candid::Principal;
 use ic_ethereum_types::Address;
 use minicbor::{Decode, Encode};
 use num_traits::ToPrimitive;
+use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::str::FromStr;
 
@@ -25,6 +28,18 @@ pub struct CkErc20Token {
     pub ckerc20_token_symbol: CkTokenSymbol,
     #[cbor(n(3), with = "crate::cbor::principal")]
     pub ckerc20_ledger_id: Principal,
+    /// Whether deposits of the ERC-20 token are credited using balance-diff accounting,
+    /// i.e., with the amount by which the balance of the minter actually increased,
+    /// see [`credit_received_amounts`]. This is required for tokens that charge a fee on
+    /// transfers or whose balances change without transfers, such as rebasing tokens.
+    /// Withdrawals are not adjusted, so that the recipient of a withdrawal receives the
+    /// withdrawn amount minus any fee charged by the token.
+    /// `None` is equivalent to `Some(false)`.
+    #[n(4)]
+    pub balance_diff_accounting: Option<bool>,
+    /// Number of decimals of the ERC-20 token, when provided by the orchestrator.
+    #[n(5)]
+    pub decimals: Option<u8>,
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Decode, Encode)]
@@ -42,6 +57,12 @@ impl CkTokenSymbol {
         match state.ethereum_network {
             EthereumNetwork::Mainnet => Self::from_str("ckETH").unwrap(),
             EthereumNetwork::Sepolia => Self::from_str("ckSepoliaETH").unwrap(),
+            EthereumNetwork::Custom(_) => state
+                .evm_network_config
+                .as_ref()
+                .expect("BUG: a custom EVM network must be configured")
+                .cketh_token_symbol
+                .clone(),
         }
     }
 }
@@ -85,6 +106,102 @@ impl TryFrom<AddCkErc20Token> for CkErc20Token {
             erc20_contract_address,
             ckerc20_token_symbol: value.ckerc20_token_symbol.parse()?,
             ckerc20_ledger_id: value.ckerc20_ledger_id,
+            balance_diff_accounting: value.balance_diff_accounting,
+            decimals: value.decimals,
         })
     }
 }
+
+/// Balance of the minter in an ERC-20 token at the end of a block and at the end of the previous block.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MinterBalanceDiff {
+    pub erc20_contract_address: Address,
+    pub block_number: BlockNumber,
+    pub balance_before: Erc20Value,
+    pub balance_after: Erc20Value,
+}
+
+/// Credits deposits of ERC-20 tokens using balance-diff accounting, i.e., with the amount
+/// the minter actually received.
+///
+/// The amount received by the minter in a block is the increase of its balance in that block,
+/// plus the value of the `Transfer` events sent by the minter in that block, e.g., for withdrawals.
+/// That amount is distributed among the deposits of that token in that block by increasing
+/// log index, each deposit being credited with at most its declared value, so that a token can
+/// never inflate the minted amount.
+///
+/// Since only balances at the end of consecutive blocks are compared, a rebasing token only
+/// affects the credited amounts if the rebase happens in the same block as the deposits.
+///
+/// Returns the credited deposits and the deposits for which the minter received nothing,
+/// together with the reason for rejecting them.
+pub fn credit_received_amounts(
+    deposits: Vec<ReceivedErc20Event>,
+    balance_diffs: &[MinterBalanceDiff],
+    transfers_from_minter: &[Erc20Transfer],
+) -> (Vec<ReceivedErc20Event>, Vec<(ReceivedErc20Event, String)>) {
+    let mut deposits_by_block: BTreeMap<(Address, BlockNumber), Vec<ReceivedErc20Event>> =
+        BTreeMap::new();
+    for deposit in deposits {
+        deposits_by_block
+            .entry((deposit.erc20_contract_address, deposit.block_number))
+            .or_default()
+            .push(deposit);
+    }
+    let mut credited = Vec::new();
+    let mut rejected = Vec::new();
+    for ((erc20_contract_address, block_number), mut deposits) in deposits_by_block {
+        deposits.sort_unstable_by_key(|deposit| deposit.log_index);
+        let received = balance_diffs
+            .iter()
+            .find(|diff| {
+                diff.erc20_contract_address == erc20_contract_address
+                    && diff.block_number == block_number
+            })
+            .ok_or("missing balance of the minter in the block of this deposit")
+            .and_then(|diff| {
+                transfers_from_minter
+                    .iter()
+                    .filter(|transfer| {
+                        transfer.erc20_contract_address == erc20_contract_address
+                            && transfer.block_number == block_number
+                    })
+                    .try_fold(diff.balance_after, |total, transfer| {
+                        total.checked_add(transfer.value)
+                    })
+                    .map(|total| {
+                        total
+                            .checked_sub(diff.balance_before)
+                            .unwrap_or(Erc20Value::ZERO)
+                    })
+                    .ok_or("overflow when computing the amount received by the minter")
+            });
+        let mut remaining = match received {
+            Ok(received) => received,
+            Err(reason) => {
+                rejected.extend(
+                    deposits
+                        .into_iter()
+                        .map(|deposit| (deposit, reason.to_string())),
+                );
+                continue;
+            }
+        };
+        for mut deposit in deposits {
+            let value = remaining.min(deposit.value);
+            if value == Erc20Value::ZERO {
+                rejected.push((
+                    deposit,
+                    "the balance of the minter did not increase for this deposit".to_string(),
+                ));
+                continue;
+            }
+            remaining = remaining
+                .checked_sub(value)
+                .expect("BUG: credited value cannot exceed the remaining received amount");
+            deposit.value = value;
+            credited.push(deposit);
+        }
+    }
+    (credited, rejected)
+}
@@ -38,3 +38,252 @@ mod ck_token_symbol {
         }
     }
 }
+
+mod credit_received_amounts {
+    use crate::erc20::{credit_received_amounts, MinterBalanceDiff};
+    use crate::eth_logs::{Erc20Transfer, ReceivedErc20Event};
+    use crate::eth_rpc::Hash;
+    use crate::numeric::{BlockNumber, Erc20Value, LogIndex};
+
+    const MINTER_ADDRESS: &str = "0xb44b5e756a894775fc32eddf3314bb1b1944dc34";
+    const DEPOSITOR_ADDRESS: &str = "0xdd2851cdd40ae6536831558dd46db62fac7a844d";
+    const TOKEN_ADDRESS: &str = "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238";
+    const OTHER_TOKEN_ADDRESS: &str = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+    const BLOCK_NUMBER: u64 = 5_462_969;
+
+    #[test]
+    fn should_credit_amount_received_after_transfer_fee() {
+        let deposit = deposit(1, 2, 1_000_000);
+
+        let (credited, rejected) = credit_received_amounts(
+            vec![deposit.clone()],
+            &[balance_diff(BLOCK_NUMBER, 5_000_000, 5_990_000)],
+            &[],
+        );
+
+        assert_eq!(
+            credited,
+            vec![ReceivedErc20Event {
+                value: Erc20Value::from(990_000_u64),
+                ..deposit
+            }]
+        );
+        assert_eq!(rejected, vec![]);
+    }
+
+    #[test]
+    fn should_cap_credited_amount_by_declared_value() {
+        let deposit = deposit(1, 2, 1_000_000);
+
+        let (credited, rejected) = credit_received_amounts(
+            vec![deposit.clone()],
+            &[balance_diff(BLOCK_NUMBER, 5_000_000, 6_000_001)],
+            &[],
+        );
+
+        assert_eq!(credited, vec![deposit]);
+        assert_eq!(rejected, vec![]);
+    }
+
+    #[test]
+    fn should_credit_negative_rebase_in_deposit_block() {
+        let deposit = deposit(1, 2, 1_000_000);
+
+        let (credited, rejected) = credit_received_amounts(
+            vec![deposit.clone()],
+            // the minter received 1_000_000 while its balance of 5_000_000 was rebased down by 1%.
+            &[balance_diff(BLOCK_NUMBER, 5_000_000, 5_950_000)],
+            &[],
+        );
+
+        assert_eq!(
+            credited,
+            vec![ReceivedErc20Event {
+                value: Erc20Value::from(950_000_u64),
+                ..deposit
+            }]
+        );
+        assert_eq!(rejected, vec![]);
+    }
+
+    #[test]
+    fn should_add_back_transfers_sent_by_minter_in_deposit_block() {
+        let deposit = deposit(1, 2, 1_000_000);
+        let withdrawal = transfer_from_minter(BLOCK_NUMBER, 2_000_000);
+        let withdrawal_in_other_block = transfer_from_minter(BLOCK_NUMBER - 1, 3_000_000);
+        let withdrawal_of_other_token = Erc20Transfer {
+            erc20_contract_address: OTHER_TOKEN_ADDRESS.parse().unwrap(),
+            ..transfer_from_minter(BLOCK_NUMBER, 4_000_000)
+        };
+
+        let (credited, rejected) = credit_received_amounts(
+            vec![deposit.clone()],
+            &[balance_diff(BLOCK_NUMBER, 5_000_000, 3_990_000)],
+            &[
+                withdrawal,
+                withdrawal_in_other_block,
+                withdrawal_of_other_token,
+            ],
+        );
+
+        assert_eq!(
+            credited,
+            vec![ReceivedErc20Event {
+                value: Erc20Value::from(990_000_u64),
+                ..deposit
+            }]
+        );
+        assert_eq!(rejected, vec![]);
+    }
+
+    #[test]
+    fn should_reject_deposit_when_balance_did_not_increase() {
+        let deposit = deposit(1, 2, 1_000_000);
+
+        let (credited, rejected) = credit_received_amounts(
+            vec![deposit.clone()],
+            &[balance_diff(BLOCK_NUMBER, 5_000_000, 4_000_000)],
+            &[],
+        );
+
+        assert_eq!(credited, vec![]);
+        assert_eq!(
+            rejected,
+            vec![(
+                deposit,
+                "the balance of the minter did not increase for this deposit".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_reject_deposit_without_balance_of_its_block() {
+        let deposit = deposit(1, 2, 1_000_000);
+        let balance_of_other_token = MinterBalanceDiff {
+            erc20_contract_address: OTHER_TOKEN_ADDRESS.parse().unwrap(),
+            ..balance_diff(BLOCK_NUMBER, 5_000_000, 6_000_000)
+        };
+
+        let (credited, rejected) = credit_received_amounts(
+            vec![deposit.clone()],
+            &[
+                balance_diff(BLOCK_NUMBER + 1, 5_000_000, 6_000_000),
+                balance_of_other_token,
+            ],
+            &[],
+        );
+
+        assert_eq!(credited, vec![]);
+        assert_eq!(
+            rejected,
+            vec![(
+                deposit,
+                "missing balance of the minter in the block of this deposit".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_distribute_received_amount_among_deposits_of_a_block_by_log_index() {
+        let first_deposit = deposit(1, 2, 1_000_000);
+        let second_deposit = deposit(2, 4, 2_000_000);
+        let third_deposit = deposit(2, 7, 3_000_000);
+
+        let (credited, rejected) = credit_received_amounts(
+            vec![
+                third_deposit.clone(),
+                second_deposit.clone(),
+                first_deposit.clone(),
+            ],
+            &[balance_diff(BLOCK_NUMBER, 5_000_000, 7_500_000)],
+            &[],
+        );
+
+        assert_eq!(
+            credited,
+            vec![
+                first_deposit,
+                ReceivedErc20Event {
+                    value: Erc20Value::from(1_500_000_u64),
+                    ..second_deposit
+                }
+            ]
+        );
+        assert_eq!(
+            rejected,
+            vec![(
+                third_deposit,
+                "the balance of the minter did not increase for this deposit".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_credit_deposits_of_each_block_with_the_balance_diff_of_that_block() {
+        let first_deposit = deposit(1, 2, 1_000_000);
+        let second_deposit = ReceivedErc20Event {
+            block_number: BlockNumber::new(BLOCK_NUMBER + 1),
+            ..deposit(2, 1, 2_000_000)
+        };
+
+        let (credited, rejected) = credit_received_amounts(
+            vec![second_deposit.clone(), first_deposit.clone()],
+            &[
+                balance_diff(BLOCK_NUMBER, 5_000_000, 5_990_000),
+                balance_diff(BLOCK_NUMBER + 1, 5_990_000, 7_970_000),
+            ],
+            &[],
+        );
+
+        assert_eq!(
+            credited,
+            vec![
+                ReceivedErc20Event {
+                    value: Erc20Value::from(990_000_u64),
+                    ..first_deposit
+                },
+                ReceivedErc20Event {
+                    value: Erc20Value::from(1_980_000_u64),
+                    ..second_deposit
+                }
+            ]
+        );
+        assert_eq!(rejected, vec![]);
+    }
+
+    fn deposit(transaction: u8, log_index: u8, value: u64) -> ReceivedErc20Event {
+        ReceivedErc20Event {
+            transaction_hash: Hash([transaction; 32]),
+            block_number: BlockNumber::new(BLOCK_NUMBER),
+            log_index: LogIndex::from(log_index),
+            from_address: DEPOSITOR_ADDRESS.parse().unwrap(),
+            value: Erc20Value::from(value),
+            principal: "hkroy-sm7vs-yyjs7-ekppe-qqnwx-hm4zf-n7ybs-titsi-k6e3k-ucuiu-uqe"
+                .parse()
+                .unwrap(),
+            erc20_contract_address: TOKEN_ADDRESS.parse().unwrap(),
+            subaccount: None,
+        }
+    }
+
+    fn balance_diff(block_number: u64, before: u64, after: u64) -> MinterBalanceDiff {
+        MinterBalanceDiff {
+            erc20_contract_address: TOKEN_ADDRESS.parse().unwrap(),
+            block_number: BlockNumber::new(block_number),
+            balance_before: Erc20Value::from(before),
+            balance_after: Erc20Value::from(after),
+        }
+    }
+
+    fn transfer_from_minter(block_number: u64, value: u64) -> Erc20Transfer {
+        Erc20Transfer {
+            transaction_hash: Hash([0xff; 32]),
+            block_number: BlockNumber::new(block_number),
+            log_index: LogIndex::from(0_u8),
+            erc20_contract_address: TOKEN_ADDRESS.parse().unwrap(),
+            from_address: MINTER_ADDRESS.parse().unwrap(),
+            to_address: DEPOSITOR_ADDRESS.parse().unwrap(),
+            value: Erc20Value::from(value),
+        }
+    }
+}
@@ -9,6 +9,7 @@ use crate::state::read_state;
 use candid::Principal;
 use ic_canister_log::log;
 use ic_ethereum_types::Address;
+use icrc_ledger_types::icrc1::account::Account;
 use minicbor::{Decode, Encode};
 use std::fmt;
 use thiserror::Error;
@@ -27,6 +28,8 @@ pub struct ReceivedEthEvent {
     pub value: Wei,
     #[cbor(n(5), with = "crate::cbor::principal")]
     pub principal: Principal,
+    #[n(6)]
+    pub subaccount: Option<LedgerSubaccount>,
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Decode, Encode)]
@@ -45,6 +48,37 @@ pub struct ReceivedErc20Event {
     pub principal: Principal,
     #[n(6)]
     pub erc20_contract_address: Address,
+    #[n(7)]
+    pub subaccount: Option<LedgerSubaccount>,
+}
+
+/// A non-default ICRC-1 subaccount of the beneficiary of a deposit.
+///
+/// The all-zero subaccount is the default subaccount and is never wrapped
+/// in this type, so that a deposit to the default account is always
+/// recorded with `subaccount: None`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Decode, Encode)]
+#[cbor(transparent)]
+pub struct LedgerSubaccount(#[cbor(n(0), with = "minicbor::bytes")] [u8; 32]);
+
+impl LedgerSubaccount {
+    /// Returns `None` for the default (all-zero) subaccount.
+    pub fn from_bytes(bytes: [u8; 32]) -> Option<Self> {
+        if bytes == [0; 32] {
+            return None;
+        }
+        Some(Self(bytes))
+    }
+
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl fmt::Debug for LedgerSubaccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -74,6 +108,7 @@ impl fmt::Debug for ReceivedEthEvent {
             .field("from_address", &self.from_address)
             .field("value", &self.value)
             .field("principal", &format_args!("{}", self.principal))
+            .field("subaccount", &self.subaccount)
             .finish()
     }
 }
@@ -88,6 +123,7 @@ impl fmt::Debug for ReceivedErc20Event {
             .field("value", &self.value)
             .field("principal", &format_args!("{}", self.principal))
             .field("contract_address", &self.erc20_contract_address)
+            .field("subaccount", &self.subaccount)
             .finish()
     }
 }
@@ -149,6 +185,19 @@ impl ReceivedEvent {
             ReceivedEvent::Erc20(evt) => evt.principal,
         }
     }
+    pub fn subaccount(&self) -> Option<LedgerSubaccount> {
+        match self {
+            ReceivedEvent::Eth(evt) => evt.subaccount,
+            ReceivedEvent::Erc20(evt) => evt.subaccount,
+        }
+    }
+    /// The ICRC-1 account to which the deposit is minted.
+    pub fn beneficiary(&self) -> Account {
+        Account {
+            owner: self.principal(),
+            subaccount: self.subaccount().map(LedgerSubaccount::to_bytes),
+        }
+    }
     pub fn block_number(&self) -> BlockNumber {
         match self {
             ReceivedEvent::Eth(evt) => evt.block_number,
@@ -221,6 +270,53 @@ pub async fn last_received_events(
     Ok((valid_transactions, errors))
 }
 
+/// Retrieves the `Transfer` events of the given ERC-20 tokens sent by the given address
+/// to any recipient.
+///
+/// Log entries that cannot be parsed are ignored.
+pub async fn last_erc20_transfers_from(
+    token_contract_addresses: &[Address],
+    from_address: Address,
+    from: BlockNumber,
+    to: BlockNumber,
+) -> Result<Vec<Erc20Transfer>, MultiCallError<Vec<LogEntry>>> {
+    use crate::eth_rpc::GetLogsParam;
+
+    if from > to {
+        ic_cdk::trap(&format!(
+            "BUG: invalid block range. {:?} should not be greater than {:?}",
+            from, to
+        ));
+    }
+    let topics = vec![
+        FixedSizeData(crate::deposit::ERC20_TRANSFER_EVENT_TOPIC).into(),
+        FixedSizeData((&from_address).into()).into(),
+    ];
+
+    let result = read_state(EthRpcClient::from_state)
+        .eth_get_logs(GetLogsParam {
+            from_block: from.into(),
+            to_block: to.into(),
+            address: token_contract_addresses.to_vec(),
+            topics,
+        })
+        .await?;
+
+    Ok(result
+        .into_iter()
+        .filter_map(|entry| match Erc20Transfer::try_from(entry) {
+            Ok(transfer) => Some(transfer),
+            Err(error) => {
+                log!(
+                    INFO,
+                    "[last_erc20_transfers_from]: ignoring invalid ERC-20 Transfer log entry: {error}"
+                );
+                None
+            }
+        })
+        .collect())
+}
+
 pub fn report_transaction_error(error: ReceivedEventError) {
     match error {
         ReceivedEventError::PendingLogEntry => {
@@ -238,6 +334,62 @@ pub fn report_transaction_error(error: ReceivedEventError) {
     }
 }
 
+/// An ERC-20 `Transfer(address,address,uint256)` event.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Erc20Transfer {
+    pub transaction_hash: Hash,
+    pub block_number: BlockNumber,
+    pub log_index: LogIndex,
+    pub erc20_contract_address: Address,
+    pub from_address: Address,
+    pub to_address: Address,
+    pub value: Erc20Value,
+}
+
+impl TryFrom<LogEntry> for Erc20Transfer {
+    type Error = String;
+
+    fn try_from(entry: LogEntry) -> Result<Self, Self::Error> {
+        let transaction_hash = entry
+            .transaction_hash
+            .ok_or("pending log entry".to_string())?;
+        let block_number = entry.block_number.ok_or("pending log entry".to_string())?;
+        let log_index = entry.log_index.ok_or("pending log entry".to_string())?;
+        if entry.removed {
+            return Err(format!(
+                "log entry 0x{transaction_hash}:{log_index} has been removed from the chain"
+            ));
+        }
+        if entry.topics.len() != 3
+            || entry.topics[0] != FixedSizeData(crate::deposit::ERC20_TRANSFER_EVENT_TOPIC)
+        {
+            return Err(format!(
+                "Expected 3 topics for a Transfer event, got {:?}",
+                entry.topics
+            ));
+        }
+        let parse_address = |address: &FixedSizeData| -> Result<Address, String> {
+            Address::try_from(&address.0)
+                .map_err(|err| format!("Invalid address in log entry: {}", err))
+        };
+        let value_bytes: [u8; 32] = entry.data.0.as_slice().try_into().map_err(|_| {
+            format!(
+                "Invalid data length; expected 32 bytes, got {}",
+                hex::encode(&entry.data.0)
+            )
+        })?;
+        Ok(Self {
+            transaction_hash,
+            block_number,
+            log_index,
+            erc20_contract_address: entry.address,
+            from_address: parse_address(&entry.topics[1])?,
+            to_address: parse_address(&entry.topics[2])?,
+            value: Erc20Value::from_be_bytes(value_bytes),
+        })
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum ReceivedEventError {
     PendingLogEntry,
@@ -307,19 +459,29 @@ impl TryFrom<LogEntry> for ReceivedEvent {
             })
         };
 
-        // We have only one non-indexed data field for both ETH and ERC20 events.
-        let value_bytes: [u8; 32] = entry.data.0.clone().try_into().map_err(|data| {
-            ReceivedEventError::InvalidEventSource {
-                source: event_source,
-                error: EventSourceError::InvalidEvent(format!(
-                    "Invalid data length; expected 32-byte value, got {}",
-                    hex::encode(data)
-                )),
+        let check_data_len = |expected_len: usize| -> Result<(), ReceivedEventError> {
+            if entry.data.0.len() != expected_len {
+                return Err(ReceivedEventError::InvalidEventSource {
+                    source: event_source,
+                    error: EventSourceError::InvalidEvent(format!(
+                        "Invalid data length; expected {expected_len} bytes, got {}",
+                        hex::encode(&entry.data.0)
+                    )),
+                });
             }
-        })?;
+            Ok(())
+        };
+        let to_word = |bytes: &[u8]| -> [u8; 32] {
+            bytes
+                .try_into()
+                .expect("BUG: data length was already checked")
+        };
 
         // We either have 3 indexed topics for ETH events: (hash, from_address, principal),
-        // or 4 indexed topics for ERC20 events: (hash, erc20_contract_address, from_address, principal)
+        // or 4 indexed topics for ERC20 events: (hash, erc20_contract_address, from_address, principal),
+        // or 4 indexed topics for deposits with a subaccount: (hash, erc20_contract_address, from_address, principal).
+        // ETH and ERC20 events have a single non-indexed data field (value), while deposits
+        // with a subaccount have two (value, subaccount).
         match entry.topics[0] {
             FixedSizeData(crate::deposit::RECEIVED_ETH_EVENT_TOPIC) => {
                 if entry.topics.len() != 3 {
@@ -333,6 +495,8 @@ impl TryFrom<LogEntry> for ReceivedEvent {
                 };
                 let from_address = parse_address(&entry.topics[1])?;
                 let principal = parse_principal(&entry.topics[2])?;
+                check_data_len(32)?;
+                let value_bytes = to_word(&entry.data.0);
                 Ok(ReceivedEthEvent {
                     transaction_hash,
                     block_number,
@@ -340,6 +504,7 @@ impl TryFrom<LogEntry> for ReceivedEvent {
                     from_address,
                     value: Wei::from_be_bytes(value_bytes),
                     principal,
+                    subaccount: None,
                 }
                 .into())
             }
@@ -356,6 +521,8 @@ impl TryFrom<LogEntry> for ReceivedEvent {
                 let erc20_contract_address = parse_address(&entry.topics[1])?;
                 let from_address = parse_address(&entry.topics[2])?;
                 let principal = parse_principal(&entry.topics[3])?;
+                check_data_len(32)?;
+                let value_bytes = to_word(&entry.data.0);
                 Ok(ReceivedErc20Event {
                     transaction_hash,
                     block_number,
@@ -364,13 +531,57 @@ impl TryFrom<LogEntry> for ReceivedEvent {
                     value: Erc20Value::from_be_bytes(value_bytes),
                     principal,
                     erc20_contract_address,
+                    subaccount: None,
                 }
                 .into())
             }
+            FixedSizeData(crate::deposit::RECEIVED_ETH_OR_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC) => {
+                if entry.topics.len() != 4 {
+                    return Err(ReceivedEventError::InvalidEventSource {
+                        source: event_source,
+                        error: EventSourceError::InvalidEvent(format!(
+                            "Expected 4 topics for ReceivedEthOrErc20 event, got {}",
+                            entry.topics.len()
+                        )),
+                    });
+                };
+                let erc20_contract_address = parse_address(&entry.topics[1])?;
+                let from_address = parse_address(&entry.topics[2])?;
+                let principal = parse_principal(&entry.topics[3])?;
+                check_data_len(64)?;
+                let (value_bytes, subaccount_bytes) = entry.data.0.split_at(32);
+                let value_bytes = to_word(value_bytes);
+                let subaccount = LedgerSubaccount::from_bytes(to_word(subaccount_bytes));
+                // ETH deposits are emitted with the zero address as ERC-20 contract address.
+                if erc20_contract_address == Address::ZERO {
+                    Ok(ReceivedEthEvent {
+                        transaction_hash,
+                        block_number,
+                        log_index,
+                        from_address,
+                        value: Wei::from_be_bytes(value_bytes),
+                        principal,
+                        subaccount,
+                    }
+                    .into())
+                } else {
+                    Ok(ReceivedErc20Event {
+                        transaction_hash,
+                        block_number,
+                        log_index,
+                        from_address,
+                        value: Erc20Value::from_be_bytes(value_bytes),
+                        principal,
+                        erc20_contract_address,
+                        subaccount,
+                    }
+                    .into())
+                }
+            }
             _ => Err(ReceivedEventError::InvalidEventSource {
                 source: event_source,
                 error: EventSourceError::InvalidEvent(format!(
-                    "Expected either ReceivedEth or ReceivedERC20 topics, got {}",
+                    "Expected either ReceivedEth, ReceivedERC20 or ReceivedEthOrErc20 topics, got {}",
                     entry.topics[0]
                 )),
             }),
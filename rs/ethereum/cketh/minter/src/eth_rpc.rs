@@ -197,6 +197,8 @@ impl std::str::FromStr for Hash {
 
 impl HttpResponsePayload for Hash {}
 
+impl HttpResponsePayload for Data {}
+
 /// Block tags.
 /// See <https://ethereum.org/en/developers/docs/apis/json-rpc/#default-block>
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Deserialize, Serialize)]
@@ -5,9 +5,10 @@ use crate::eth_rpc::{
     SendRawTransactionResult, Topic, HEADER_SIZE_LIMIT,
 };
 use crate::eth_rpc_client::providers::{
-    EthereumProvider, RpcNodeProvider, SepoliaProvider, MAINNET_PROVIDERS, SEPOLIA_PROVIDERS,
+    custom_providers, evm_rpc_node_providers, EthereumProvider, RpcNodeProvider, SepoliaProvider,
+    MAINNET_PROVIDERS, SEPOLIA_PROVIDERS,
 };
-use crate::eth_rpc_client::requests::GetTransactionCountParams;
+use crate::eth_rpc_client::requests::{EthCallParams, GetTransactionCountParams};
 use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
 use crate::lifecycle::EthereumNetwork;
 use crate::logs::{PrintProxySink, DEBUG, INFO, TRACE_HTTP};
@@ -48,24 +49,37 @@ const ETH_GET_LOGS_INITIAL_RESPONSE_SIZE_ESTIMATE: u64 = 100;
 pub struct EthRpcClient {
     evm_rpc_client: Option<EvmRpcClient<IcRuntime, PrintProxySink>>,
     chain: EthereumNetwork,
+    providers: Vec<RpcNodeProvider>,
 }
 
 impl EthRpcClient {
-    const fn new(chain: EthereumNetwork) -> Self {
+    fn new(chain: EthereumNetwork) -> Self {
+        let providers = match chain {
+            EthereumNetwork::Mainnet => MAINNET_PROVIDERS.to_vec(),
+            EthereumNetwork::Sepolia => SEPOLIA_PROVIDERS.to_vec(),
+            EthereumNetwork::Custom(_) => vec![],
+        };
         Self {
             evm_rpc_client: None,
             chain,
+            providers,
         }
     }
 
     pub fn from_state(state: &State) -> Self {
         let mut client = Self::new(state.ethereum_network());
+        if let Some(config) = &state.evm_network_config {
+            client.providers = custom_providers(&config.rpc_providers);
+        }
         if let Some(evm_rpc_id) = state.evm_rpc_id {
             const MIN_ATTACHED_CYCLES: u128 = 300_000_000_000;
 
             let providers = match client.chain {
                 EthereumNetwork::Mainnet => EthereumProvider::evm_rpc_node_providers(),
                 EthereumNetwork::Sepolia => SepoliaProvider::evm_rpc_node_providers(),
+                EthereumNetwork::Custom(_) => {
+                    evm_rpc_node_providers(&client.chain, &client.providers)
+                }
             };
             client.evm_rpc_client = Some(
                 EvmRpcClient::builder_for_ic(TRACE_HTTP)
@@ -87,10 +101,7 @@ impl EthRpcClient {
     }
 
     fn providers(&self) -> &[RpcNodeProvider] {
-        match self.chain {
-            EthereumNetwork::Mainnet => &MAINNET_PROVIDERS,
-            EthereumNetwork::Sepolia => &SEPOLIA_PROVIDERS,
-        }
+        &self.providers
     }
 
     /// Query all providers in sequence until one returns an ok result
@@ -205,7 +216,7 @@ impl EthRpcClient {
 
         let expected_block_size = match self.chain {
             EthereumNetwork::Sepolia => 12 * 1024,
-            EthereumNetwork::Mainnet => 24 * 1024,
+            EthereumNetwork::Mainnet | EthereumNetwork::Custom(_) => 24 * 1024,
         };
 
         let results: MultiCallResults<Block> = self
@@ -342,6 +353,16 @@ impl EthRpcClient {
         )
         .await
     }
+
+    /// Executes a message call without creating a transaction.
+    ///
+    /// The EVM RPC canister client does not support `eth_call`, so the JSON-RPC providers
+    /// are always called directly. Only a custom EVM network is guaranteed to have some.
+    pub async fn eth_call(&self, params: EthCallParams) -> MultiCallResults<Data> {
+        // A typical response is an ABI-encoded 32-byte word.
+        self.parallel_call("eth_call", params, ResponseSizeEstimate::new(256))
+            .await
+    }
 }
 
 /// Aggregates responses of different providers to the same query.
@@ -743,6 +764,8 @@ impl Reduce for EvmMultiRpcResult<Option<EvmTransactionReceipt>> {
                                 .ok_or("invalid transaction status")?,
                         )?,
                         transaction_hash: Hash::from_str(&evm_receipt.transaction_hash)?,
+                        // The EVM RPC canister does not return the L1 data fee of OP-stack networks.
+                        l1_fee: None,
                     })
                 })
                 .transpose()
@@ -896,6 +919,18 @@ impl<T: Debug + PartialEq> MultiCallResults<T> {
         Ok(min)
     }
 
+    pub fn reduce_with_max_by_key<F: FnMut(&T) -> K, K: Ord>(
+        self,
+        extractor: F,
+    ) -> Result<T, MultiCallError<T>> {
+        let max = self
+            .at_least_two_ok()?
+            .into_values()
+            .max_by_key(extractor)
+            .expect("BUG: MultiCallResults is guaranteed to be non-empty");
+        Ok(max)
+    }
+
     pub fn reduce_with_strict_majority_by_key<F: Fn(&T) -> K, K: Ord>(
         self,
         extractor: F,
@@ -16,6 +16,8 @@ pub(crate) const SEPOLIA_PROVIDERS: [RpcNodeProvider; 2] = [
 pub(crate) enum RpcNodeProvider {
     Ethereum(EthereumProvider),
     Sepolia(SepoliaProvider),
+    /// Provider of a custom EVM network, identified by its URL.
+    Custom(String),
     EvmRpc(EvmRpcService),
 }
 
@@ -25,6 +27,7 @@ impl RpcNodeProvider {
         match self {
             Self::Ethereum(provider) => provider.ethereum_mainnet_endpoint_url(),
             Self::Sepolia(provider) => provider.ethereum_sepolia_endpoint_url(),
+            Self::Custom(url) => url,
             RpcNodeProvider::EvmRpc(_) => {
                 panic!("BUG: should not need URL of provider from EVM RPC canister")
             }
@@ -54,7 +57,7 @@ impl EthereumProvider {
     // TODO XC-131: Replace using Custom providers with EthMainnetService,
     // when LlamaNodes is supported as a provider.
     pub(crate) fn evm_rpc_node_providers() -> EvmRpcServices {
-        evm_rpc_node_providers(&EthereumNetwork::Mainnet)
+        evm_rpc_node_providers(&EthereumNetwork::Mainnet, &MAINNET_PROVIDERS)
     }
 }
 
@@ -75,17 +78,23 @@ impl SepoliaProvider {
     }
 
     pub(crate) fn evm_rpc_node_providers() -> EvmRpcServices {
-        evm_rpc_node_providers(&EthereumNetwork::Sepolia)
+        evm_rpc_node_providers(&EthereumNetwork::Sepolia, &SEPOLIA_PROVIDERS)
     }
 }
 
-fn evm_rpc_node_providers(ethereum_network: &EthereumNetwork) -> EvmRpcServices {
+/// Providers of a custom EVM network given by their URLs.
+pub(crate) fn custom_providers(urls: &[String]) -> Vec<RpcNodeProvider> {
+    urls.iter()
+        .map(|url| RpcNodeProvider::Custom(url.clone()))
+        .collect()
+}
+
+pub(crate) fn evm_rpc_node_providers(
+    ethereum_network: &EthereumNetwork,
+    providers: &[RpcNodeProvider],
+) -> EvmRpcServices {
     use evm_rpc_client::types::candid::RpcApi as EvmRpcApi;
 
-    let providers = match ethereum_network {
-        EthereumNetwork::Mainnet => MAINNET_PROVIDERS.as_slice(),
-        EthereumNetwork::Sepolia => SEPOLIA_PROVIDERS.as_slice(),
-    };
     let chain_id = ethereum_network.chain_id();
     let services = providers
         .iter()
@@ -1,4 +1,4 @@
-use crate::eth_rpc::BlockSpec;
+use crate::eth_rpc::{BlockSpec, Data};
 use ic_ethereum_types::Address;
 use serde::Serialize;
 
@@ -17,3 +17,28 @@ impl From<GetTransactionCountParams> for (Address, BlockSpec) {
         (params.address, params.block)
     }
 }
+
+/// Parameters of the [`eth_call`](https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_call) call.
+#[derive(Clone, Debug, Serialize)]
+#[serde(into = "(TransactionCall, BlockSpec)")]
+pub struct EthCallParams {
+    /// The message call to execute without creating a transaction.
+    pub transaction: TransactionCall,
+    /// Integer block number, or "latest" for the last mined block or "pending", "earliest" for not yet mined transactions.
+    pub block: BlockSpec,
+}
+
+/// Message call executed by [`EthCallParams`].
+#[derive(Clone, Debug, Serialize)]
+pub struct TransactionCall {
+    /// The address of the called smart contract.
+    pub to: Address,
+    /// The ABI-encoded function selector and arguments.
+    pub data: Data,
+}
+
+impl From<EthCallParams> for (TransactionCall, BlockSpec) {
+    fn from(params: EthCallParams) -> Self {
+        (params.transaction, params.block)
+    }
+}
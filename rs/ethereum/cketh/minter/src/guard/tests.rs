@@ -145,6 +145,7 @@ fn init_state() {
                 minimum_withdrawal_amount: wei_from_milli_ether(10).into(),
                 next_transaction_nonce: Default::default(),
                 last_scraped_block_number: Default::default(),
+                evm_network: None,
             })
             .expect("init args should be valid"),
         );
@@ -1,7 +1,7 @@
 //! Module dealing with the lifecycle methods of the ckETH Minter.
 use crate::lifecycle::init::InitArg;
 use crate::lifecycle::upgrade::UpgradeArg;
-use candid::{CandidType, Deserialize};
+use candid::{CandidType, Deserialize, Nat};
 use minicbor::{Decode, Encode};
 use std::fmt::{Display, Formatter};
 
@@ -19,16 +19,17 @@ pub enum MinterArg {
     UpgradeArg(UpgradeArg),
 }
 
-#[derive(
-    Copy, Clone, Eq, PartialEq, Hash, Debug, Default, CandidType, Decode, Deserialize, Encode,
-)]
-#[cbor(index_only)]
+/// The EVM network the minter interacts with.
+///
+/// Networks other than the Ethereum mainnet and Sepolia, such as the layer-2 networks Arbitrum,
+/// Base or Optimism, are identified by their chain ID and require an [`EvmNetworkArg`]
+/// describing their RPC providers, finality and fee model.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, CandidType, Deserialize)]
 pub enum EthereumNetwork {
-    #[n(1)]
     Mainnet,
-    #[n(11155111)]
     #[default]
     Sepolia,
+    Custom(u64),
 }
 
 impl EthereumNetwork {
@@ -36,6 +37,7 @@ impl EthereumNetwork {
         match self {
             EthereumNetwork::Mainnet => 1,
             EthereumNetwork::Sepolia => 11155111,
+            EthereumNetwork::Custom(chain_id) => *chain_id,
         }
     }
 }
@@ -45,9 +47,10 @@ impl TryFrom<u64> for EthereumNetwork {
 
     fn try_from(value: u64) -> Result<Self, Self::Error> {
         match value {
+            0 => Err("Invalid chain ID 0".to_string()),
             1 => Ok(EthereumNetwork::Mainnet),
             11155111 => Ok(EthereumNetwork::Sepolia),
-            _ => Err("Unknown Ethereum Network".to_string()),
+            chain_id => Ok(EthereumNetwork::Custom(chain_id)),
         }
     }
 }
@@ -57,6 +60,77 @@ impl Display for EthereumNetwork {
         match self {
             EthereumNetwork::Mainnet => write!(f, "Ethereum Mainnet"),
             EthereumNetwork::Sepolia => write!(f, "Ethereum Testnet Sepolia"),
+            EthereumNetwork::Custom(chain_id) => write!(f, "EVM Network (chain ID {chain_id})"),
         }
     }
 }
+
+// The network is encoded as its chain ID, which is backwards compatible with
+// the encoding of the `Mainnet` and `Sepolia` variants as CBOR indices.
+impl<C> Encode<C> for EthereumNetwork {
+    fn encode<W: minicbor::encode::Write>(
+        &self,
+        e: &mut minicbor::Encoder<W>,
+        _ctx: &mut C,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        e.u64(self.chain_id())?.ok()
+    }
+}
+
+impl<'b, C> Decode<'b, C> for EthereumNetwork {
+    fn decode(
+        d: &mut minicbor::Decoder<'b>,
+        _ctx: &mut C,
+    ) -> Result<Self, minicbor::decode::Error> {
+        EthereumNetwork::try_from(d.u64()?)
+            .map_err(|_| minicbor::decode::Error::message("invalid chain ID"))
+    }
+}
+
+/// Configuration of an EVM network other than the Ethereum mainnet and Sepolia.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Decode, Deserialize, Encode)]
+pub struct EvmNetworkArg {
+    /// URLs of at least [`crate::state::MIN_EVM_RPC_PROVIDERS`] distinct JSON-RPC providers of
+    /// the network. The minter requires at least two providers to agree on each result.
+    #[n(0)]
+    pub rpc_providers: Vec<String>,
+    /// Number of blocks that must be built on top of the block with tag `ethereum_block_height`
+    /// before the minter considers it final.
+    #[n(1)]
+    pub block_confirmations: u64,
+    /// Symbol of the ck-token of the native currency of the network, e.g., "ckArbETH".
+    #[n(2)]
+    pub cketh_token_symbol: String,
+    /// Lower bound on the max priority fee per gas of withdrawal transactions, in Wei.
+    #[cbor(n(3), with = "crate::cbor::nat")]
+    pub min_max_priority_fee_per_gas: Nat,
+    /// Gas limit of transactions withdrawing the native currency of the network.
+    #[cbor(n(4), with = "crate::cbor::nat")]
+    pub cketh_withdrawal_gas_limit: Nat,
+    /// Gas limit of transactions withdrawing ERC-20 tokens.
+    #[cbor(n(5), with = "crate::cbor::nat")]
+    pub ckerc20_withdrawal_gas_limit: Nat,
+    /// Fee model of the network, which determines the fees of withdrawal transactions.
+    #[n(6)]
+    pub fee_model: FeeModelArg,
+    /// Transfer fee of the ledger of the ck-token of the native currency of the network, in Wei.
+    #[cbor(n(7), with = "crate::cbor::nat")]
+    pub cketh_ledger_transfer_fee: Nat,
+}
+
+/// Fee model of an EVM network.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Decode, Deserialize, Encode)]
+pub enum FeeModelArg {
+    /// Transactions only pay the EIP-1559 gas fees.
+    #[n(0)]
+    Eip1559,
+    /// OP-stack rollups, e.g., Optimism or Base, which charge an L1 data fee
+    /// on top of the EIP-1559 gas fees.
+    #[n(1)]
+    OpStack {
+        /// Address of the `GasPriceOracle` predeploy that estimates the L1 data fee,
+        /// usually `0x420000000000000000000000000000000000000F`.
+        #[n(0)]
+        gas_price_oracle_address: String,
+    },
+}
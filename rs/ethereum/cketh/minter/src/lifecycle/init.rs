@@ -1,9 +1,9 @@
 use crate::endpoints::CandidBlockTag;
 use crate::eth_rpc::BlockTag;
-use crate::lifecycle::EthereumNetwork;
+use crate::lifecycle::{EthereumNetwork, EvmNetworkArg};
 use crate::numeric::{BlockNumber, TransactionNonce, Wei};
 use crate::state::transactions::EthTransactions;
-use crate::state::{InvalidStateError, State};
+use crate::state::{EvmNetworkConfig, InvalidStateError, State};
 use candid::types::number::Nat;
 use candid::types::principal::Principal;
 use candid::{CandidType, Deserialize};
@@ -28,6 +28,8 @@ pub struct InitArg {
     pub next_transaction_nonce: Nat,
     #[cbor(n(8), with = "crate::cbor::nat")]
     pub last_scraped_block_number: Nat,
+    #[n(9)]
+    pub evm_network: Option<EvmNetworkArg>,
 }
 
 impl TryFrom<InitArg> for State {
@@ -42,6 +44,7 @@ impl TryFrom<InitArg> for State {
             minimum_withdrawal_amount,
             next_transaction_nonce,
             last_scraped_block_number,
+            evm_network,
         }: InitArg,
     ) -> Result<Self, Self::Error> {
         use std::str::FromStr;
@@ -69,11 +72,13 @@ impl TryFrom<InitArg> for State {
                         "ERROR: last_scraped_block_number is at maximum value".to_string(),
                     )
                 })?;
+        let evm_network_config = evm_network.map(EvmNetworkConfig::try_from).transpose()?;
         let state = Self {
             ethereum_network,
             ecdsa_key_name,
             eth_helper_contract_address,
             erc20_helper_contract_address: None,
+            deposit_with_subaccount_helper_contract_address: None,
             pending_withdrawal_principals: Default::default(),
             eth_transactions: EthTransactions::new(initial_nonce),
             cketh_ledger_id: ledger_id,
@@ -82,6 +87,7 @@ impl TryFrom<InitArg> for State {
             first_scraped_block_number,
             last_scraped_block_number,
             last_erc20_scraped_block_number: last_scraped_block_number,
+            last_deposit_with_subaccount_scraped_block_number: last_scraped_block_number,
             last_observed_block_number: None,
             events_to_mint: Default::default(),
             minted_events: Default::default(),
@@ -92,9 +98,13 @@ impl TryFrom<InitArg> for State {
             active_tasks: Default::default(),
             http_request_counter: 0,
             last_transaction_price_estimate: None,
+            last_l1_fee_estimate: None,
             ledger_suite_orchestrator_id: None,
             evm_rpc_id: None,
+            evm_network_config,
             ckerc20_tokens: Default::default(),
+            erc20_balance_diff_accounting: Default::default(),
+            erc20_decimals: Default::default(),
             erc20_balances: Default::default(),
         };
         state.validate_config()?;
@@ -1,7 +1,9 @@
 mod init {
+    use crate::erc20::CkTokenSymbol;
     use crate::lifecycle::init::InitArg;
-    use crate::numeric::{TransactionNonce, Wei};
-    use crate::state::{InvalidStateError, State};
+    use crate::lifecycle::{EthereumNetwork, EvmNetworkArg, FeeModelArg};
+    use crate::numeric::{GasAmount, TransactionNonce, Wei, WeiPerGas};
+    use crate::state::{FeeModel, InvalidStateError, State};
     use assert_matches::assert_matches;
     use candid::{Nat, Principal};
     use num_bigint::BigUint;
@@ -91,6 +93,185 @@ mod init {
         );
     }
 
+    #[test]
+    fn should_require_evm_network_config_for_custom_network() {
+        assert_matches!(
+            State::try_from(InitArg {
+                ethereum_network: EthereumNetwork::Custom(42161),
+                ..valid_init_arg()
+            }),
+            Err(InvalidStateError::InvalidEvmNetwork(_))
+        );
+
+        assert_matches!(
+            State::try_from(InitArg {
+                ethereum_network: EthereumNetwork::Mainnet,
+                evm_network: Some(valid_evm_network_arg()),
+                ..valid_init_arg()
+            }),
+            Err(InvalidStateError::InvalidEvmNetwork(_))
+        );
+
+        assert_matches!(
+            State::try_from(InitArg {
+                ethereum_network: EthereumNetwork::Custom(42161),
+                evm_network: Some(EvmNetworkArg {
+                    rpc_providers: vec![],
+                    ..valid_evm_network_arg()
+                }),
+                ..valid_init_arg()
+            }),
+            Err(InvalidStateError::InvalidEvmNetwork(_))
+        );
+
+        assert_matches!(
+            State::try_from(InitArg {
+                ethereum_network: EthereumNetwork::Custom(42161),
+                evm_network: Some(EvmNetworkArg {
+                    rpc_providers: vec![
+                        "https://arb1.arbitrum.io/rpc".to_string(),
+                        "https://arbitrum.llamarpc.com".to_string(),
+                    ],
+                    ..valid_evm_network_arg()
+                }),
+                ..valid_init_arg()
+            }),
+            Err(InvalidStateError::InvalidEvmNetwork(_))
+        );
+
+        assert_matches!(
+            State::try_from(InitArg {
+                ethereum_network: EthereumNetwork::Custom(42161),
+                evm_network: Some(EvmNetworkArg {
+                    rpc_providers: vec![
+                        "https://arb1.arbitrum.io/rpc".to_string(),
+                        "https://arbitrum.llamarpc.com".to_string(),
+                        "https://arb1.arbitrum.io/rpc".to_string(),
+                    ],
+                    ..valid_evm_network_arg()
+                }),
+                ..valid_init_arg()
+            }),
+            Err(InvalidStateError::InvalidEvmNetwork(_))
+        );
+
+        assert_matches!(
+            State::try_from(InitArg {
+                ethereum_network: EthereumNetwork::Custom(42161),
+                evm_network: Some(EvmNetworkArg {
+                    cketh_withdrawal_gas_limit: Nat::from(0_u8),
+                    ..valid_evm_network_arg()
+                }),
+                ..valid_init_arg()
+            }),
+            Err(InvalidStateError::InvalidEvmNetwork(_))
+        );
+    }
+
+    #[test]
+    fn should_succeed_with_custom_network() {
+        let state = State::try_from(InitArg {
+            ethereum_network: EthereumNetwork::Custom(42161),
+            evm_network: Some(valid_evm_network_arg()),
+            ..valid_init_arg()
+        })
+        .expect("valid init args");
+
+        assert_eq!(state.ethereum_network.chain_id(), 42161);
+        assert_eq!(
+            CkTokenSymbol::cketh_symbol_from_state(&state).to_string(),
+            "ckArbETH"
+        );
+        assert_eq!(state.block_confirmations(), 10);
+        assert_eq!(
+            state.min_max_priority_fee_per_gas(),
+            WeiPerGas::new(10_000_000)
+        );
+        assert_eq!(state.cketh_withdrawal_gas_limit(), GasAmount::new(500_000));
+        assert_eq!(
+            state.ckerc20_withdrawal_gas_limit(),
+            GasAmount::new(1_000_000)
+        );
+        assert_eq!(state.fee_model(), FeeModel::Eip1559);
+        assert_eq!(
+            state.cketh_ledger_transfer_fee(),
+            Wei::new(2_000_000_000_000)
+        );
+    }
+
+    #[test]
+    fn should_configure_op_stack_fee_model() {
+        let state = State::try_from(InitArg {
+            ethereum_network: EthereumNetwork::Custom(8453),
+            evm_network: Some(EvmNetworkArg {
+                fee_model: FeeModelArg::OpStack {
+                    gas_price_oracle_address: "0x420000000000000000000000000000000000000F"
+                        .to_string(),
+                },
+                ..valid_evm_network_arg()
+            }),
+            ..valid_init_arg()
+        })
+        .expect("valid init args");
+
+        assert_eq!(
+            state.fee_model(),
+            FeeModel::OpStack {
+                gas_price_oracle_address: "0x420000000000000000000000000000000000000F"
+                    .parse()
+                    .unwrap()
+            }
+        );
+
+        for gas_price_oracle_address in ["0x4200", "0x0000000000000000000000000000000000000000"] {
+            assert_matches!(
+                State::try_from(InitArg {
+                    ethereum_network: EthereumNetwork::Custom(8453),
+                    evm_network: Some(EvmNetworkArg {
+                        fee_model: FeeModelArg::OpStack {
+                            gas_price_oracle_address: gas_price_oracle_address.to_string(),
+                        },
+                        ..valid_evm_network_arg()
+                    }),
+                    ..valid_init_arg()
+                }),
+                Err(InvalidStateError::InvalidEvmNetwork(_))
+            );
+        }
+    }
+
+    #[test]
+    fn should_require_minimum_withdrawal_amount_to_cover_custom_ledger_fee() {
+        assert_matches!(
+            State::try_from(InitArg {
+                ethereum_network: EthereumNetwork::Custom(42161),
+                evm_network: Some(EvmNetworkArg {
+                    cketh_ledger_transfer_fee: Nat::from(20_000_000_000_000_000_u64),
+                    ..valid_evm_network_arg()
+                }),
+                ..valid_init_arg()
+            }),
+            Err(InvalidStateError::InvalidMinimumWithdrawalAmount(_))
+        );
+    }
+
+    fn valid_evm_network_arg() -> EvmNetworkArg {
+        EvmNetworkArg {
+            rpc_providers: vec![
+                "https://arb1.arbitrum.io/rpc".to_string(),
+                "https://arbitrum.llamarpc.com".to_string(),
+                "https://arbitrum-one-rpc.publicnode.com".to_string(),
+            ],
+            block_confirmations: 10,
+            cketh_token_symbol: "ckArbETH".to_string(),
+            min_max_priority_fee_per_gas: Nat::from(10_000_000_u64),
+            cketh_withdrawal_gas_limit: Nat::from(500_000_u64),
+            ckerc20_withdrawal_gas_limit: Nat::from(1_000_000_u64),
+            fee_model: FeeModelArg::Eip1559,
+            cketh_ledger_transfer_fee: Nat::from(2_000_000_000_000_u64),
+        }
+    }
+
     fn valid_init_arg() -> InitArg {
         InitArg {
             ethereum_network: Default::default(),
@@ -102,6 +283,53 @@ mod init {
             minimum_withdrawal_amount: Nat::from(10_000_000_000_000_000_u64),
             next_transaction_nonce: TransactionNonce::ZERO.into(),
             last_scraped_block_number: Default::default(),
+            evm_network: None,
+        }
+    }
+}
+
+mod ethereum_network {
+    use crate::lifecycle::EthereumNetwork;
+
+    #[test]
+    fn should_encode_known_networks_as_before() {
+        // `Mainnet` and `Sepolia` used to be encoded as CBOR indices equal to their chain ID.
+        for (network, index) in [
+            (EthereumNetwork::Mainnet, 1_u32),
+            (EthereumNetwork::Sepolia, 11155111_u32),
+        ] {
+            let mut expected = vec![];
+            minicbor::Encoder::new(&mut expected).u32(index).unwrap();
+
+            let mut encoded = vec![];
+            minicbor::encode(network, &mut encoded).unwrap();
+
+            assert_eq!(encoded, expected);
+            assert_eq!(
+                minicbor::decode::<EthereumNetwork>(&expected).unwrap(),
+                network
+            );
         }
     }
+
+    #[test]
+    fn should_encode_and_decode_custom_network() {
+        for chain_id in [10, 8453, 42161, u64::MAX] {
+            let network = EthereumNetwork::try_from(chain_id).unwrap();
+            assert_eq!(network, EthereumNetwork::Custom(chain_id));
+
+            let mut encoded = vec![];
+            minicbor::encode(network, &mut encoded).unwrap();
+
+            assert_eq!(
+                minicbor::decode::<EthereumNetwork>(&encoded).unwrap(),
+                network
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_chain_id_zero() {
+        assert!(EthereumNetwork::try_from(0).is_err());
+    }
 }
@@ -1,4 +1,5 @@
 use crate::endpoints::CandidBlockTag;
+use crate::lifecycle::EvmNetworkArg;
 use crate::logs::INFO;
 use crate::state::audit::{process_event, replay_events, EventType};
 use crate::state::mutate_state;
@@ -26,6 +27,24 @@ pub struct UpgradeArg {
     pub last_erc20_scraped_block_number: Option<Nat>,
     #[cbor(n(7), with = "crate::cbor::principal::option")]
     pub evm_rpc_id: Option<Principal>,
+    #[n(8)]
+    pub evm_network: Option<EvmNetworkArg>,
+    #[n(9)]
+    pub deposit_with_subaccount_helper_contract_address: Option<String>,
+    #[cbor(n(10), with = "crate::cbor::nat::option")]
+    pub last_deposit_with_subaccount_scraped_block_number: Option<Nat>,
+    #[n(11)]
+    pub erc20_balance_diff_accounting: Option<Vec<Erc20BalanceDiffAccountingArg>>,
+}
+
+/// Enables or disables balance-diff accounting for the deposits of a supported ERC-20 token,
+/// see [`crate::erc20::CkErc20Token::balance_diff_accounting`].
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Decode, Deserialize, Encode)]
+pub struct Erc20BalanceDiffAccountingArg {
+    #[n(0)]
+    pub erc20_contract_address: String,
+    #[n(1)]
+    pub enabled: bool,
 }
 
 pub fn post_upgrade(upgrade_args: Option<UpgradeArg>) {
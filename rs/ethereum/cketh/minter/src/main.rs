@@ -31,11 +31,8 @@ use ic_cketh_minter::state::transactions::{
 use ic_cketh_minter::state::{
     lazy_call_ecdsa_public_key, mutate_state, read_state, transactions, State, STATE,
 };
-use ic_cketh_minter::tx::lazy_refresh_gas_fee_estimate;
-use ic_cketh_minter::withdraw::{
-    process_reimbursement, process_retrieve_eth_requests, CKERC20_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
-    CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
-};
+use ic_cketh_minter::tx::{lazy_refresh_gas_fee_estimate, lazy_refresh_l1_fee_estimate};
+use ic_cketh_minter::withdraw::{process_reimbursement, process_retrieve_eth_requests};
 use ic_cketh_minter::{endpoints, erc20};
 use ic_cketh_minter::{
     state, storage, PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL, PROCESS_REIMBURSEMENT,
@@ -50,7 +47,6 @@ use std::time::Duration;
 mod dashboard;
 
 pub const SEPOLIA_TEST_CHAIN_ID: u64 = 11155111;
-pub const CKETH_LEDGER_TRANSACTION_FEE: Wei = Wei::new(2_000_000_000_000_u128);
 
 fn validate_caller_not_anonymous() -> candid::Principal {
     let principal = ic_cdk::caller();
@@ -110,6 +106,9 @@ fn emit_preupgrade_events() {
         storage::record_event(EventType::SyncedErc20ToBlock {
             block_number: s.last_erc20_scraped_block_number,
         });
+        storage::record_event(EventType::SyncedDepositWithSubaccountToBlock {
+            block_number: s.last_deposit_with_subaccount_scraped_block_number,
+        });
     });
 }
 
@@ -150,13 +149,13 @@ async fn eip_1559_transaction_price(
     token: Option<Eip1559TransactionPriceArg>,
 ) -> Eip1559TransactionPrice {
     let gas_limit = match token {
-        None => CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
+        None => read_state(State::cketh_withdrawal_gas_limit),
         Some(Eip1559TransactionPriceArg { ckerc20_ledger_id }) => {
             match read_state(|s| s.find_ck_erc20_token_by_ledger_id(&ckerc20_ledger_id)) {
-                Some(_) => CKERC20_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
+                Some(_) => read_state(State::ckerc20_withdrawal_gas_limit),
                 None => {
                     if ckerc20_ledger_id == read_state(|s| s.cketh_ledger_id) {
-                        CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT
+                        read_state(State::cketh_withdrawal_gas_limit)
                     } else {
                         ic_cdk::trap(&format!(
                             "ERROR: Unsupported ckERC20 token ledger {}",
@@ -169,7 +168,15 @@ async fn eip_1559_transaction_price(
     };
     match read_state(|s| s.last_transaction_price_estimate.clone()) {
         Some((ts, estimate)) => {
-            let mut result = Eip1559TransactionPrice::from(estimate.to_price(gas_limit));
+            let price = estimate.to_price(gas_limit);
+            // Networks charging an L1 data fee require it on top of the gas fees.
+            let l1_fee = read_state(|s| s.last_l1_fee_estimate).map_or(Wei::ZERO, |(_, fee)| fee);
+            let max_transaction_fee = price
+                .max_transaction_fee()
+                .checked_add(l1_fee)
+                .unwrap_or(Wei::MAX);
+            let mut result = Eip1559TransactionPrice::from(price);
+            result.max_transaction_fee = max_transaction_fee.into();
             result.timestamp = Some(ts);
             result
         }
@@ -210,6 +217,9 @@ async fn get_minter_info() -> MinterInfo {
             smart_contract_address: s.eth_helper_contract_address.map(|a| a.to_string()),
             eth_helper_contract_address: s.eth_helper_contract_address.map(|a| a.to_string()),
             erc20_helper_contract_address: s.erc20_helper_contract_address.map(|a| a.to_string()),
+            deposit_with_subaccount_helper_contract_address: s
+                .deposit_with_subaccount_helper_contract_address
+                .map(|a| a.to_string()),
             supported_ckerc20_tokens,
             minimum_withdrawal_amount: Some(s.cketh_minimum_withdrawal_amount.into()),
             ethereum_block_height: Some(s.ethereum_block_height.into()),
@@ -225,6 +235,9 @@ async fn get_minter_info() -> MinterInfo {
             erc20_balances,
             last_eth_scraped_block_number: Some(s.last_scraped_block_number.into()),
             last_erc20_scraped_block_number: Some(s.last_erc20_scraped_block_number.into()),
+            last_deposit_with_subaccount_scraped_block_number: Some(
+                s.last_deposit_with_subaccount_scraped_block_number.into(),
+            ),
             cketh_ledger_id: Some(s.cketh_ledger_id),
         }
     })
@@ -455,7 +468,7 @@ async fn withdraw_erc20(
                         LedgerBurnError::InsufficientFunds { .. }
                         | LedgerBurnError::AmountTooLow { .. }
                         | LedgerBurnError::InsufficientAllowance { .. } => erc20_tx_fee
-                            .checked_sub(CKETH_LEDGER_TRANSACTION_FEE)
+                            .checked_sub(read_state(State::cketh_ledger_transfer_fee))
                             .unwrap_or(Wei::ZERO),
                     };
                     if reimbursed_amount > Wei::ZERO {
@@ -487,13 +500,15 @@ async fn withdraw_erc20(
 }
 
 async fn estimate_erc20_transaction_fee() -> Option<Wei> {
-    lazy_refresh_gas_fee_estimate()
-        .await
-        .map(|gas_fee_estimate| {
-            gas_fee_estimate
-                .to_price(CKERC20_WITHDRAWAL_TRANSACTION_GAS_LIMIT)
-                .max_transaction_fee()
-        })
+    let gas_fee_estimate = lazy_refresh_gas_fee_estimate().await?;
+    let l1_fee_estimate = lazy_refresh_l1_fee_estimate().await?;
+    Some(
+        gas_fee_estimate
+            .to_price(read_state(State::ckerc20_withdrawal_gas_limit))
+            .max_transaction_fee()
+            .checked_add(l1_fee_estimate)
+            .unwrap_or(Wei::MAX),
+    )
 }
 
 #[query]
@@ -610,6 +625,7 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                 TransactionStatus::Failure => CandidTransactionStatus::Failure,
             },
             transaction_hash: receipt.transaction_hash.to_string(),
+            l1_fee: receipt.l1_fee.map(Nat::from),
         }
     }
 
@@ -627,6 +643,7 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     from_address,
                     value,
                     principal,
+                    subaccount,
                 }) => EP::AcceptedDeposit {
                     transaction_hash: transaction_hash.to_string(),
                     block_number: block_number.into(),
@@ -634,6 +651,7 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     from_address: from_address.to_string(),
                     value: value.into(),
                     principal,
+                    subaccount: subaccount.map(|s| s.to_bytes()),
                 },
                 EventType::AcceptedErc20Deposit(ReceivedErc20Event {
                     transaction_hash,
@@ -643,6 +661,7 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     value,
                     principal,
                     erc20_contract_address,
+                    subaccount,
                 }) => EP::AcceptedErc20Deposit {
                     transaction_hash: transaction_hash.to_string(),
                     block_number: block_number.into(),
@@ -651,6 +670,7 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     value: value.into(),
                     principal,
                     erc20_contract_address: erc20_contract_address.to_string(),
+                    subaccount: subaccount.map(|s| s.to_bytes()),
                 },
                 EventType::InvalidDeposit {
                     event_source,
@@ -672,6 +692,11 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                 EventType::SyncedErc20ToBlock { block_number } => EP::SyncedErc20ToBlock {
                     block_number: block_number.into(),
                 },
+                EventType::SyncedDepositWithSubaccountToBlock { block_number } => {
+                    EP::SyncedDepositWithSubaccountToBlock {
+                        block_number: block_number.into(),
+                    }
+                }
                 EventType::AcceptedEthWithdrawalRequest(EthWithdrawalRequest {
                     withdrawal_amount,
                     destination,
@@ -750,6 +775,8 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     address: token.erc20_contract_address.to_string(),
                     ckerc20_token_symbol: token.ckerc20_token_symbol.to_string(),
                     ckerc20_ledger_id: token.ckerc20_ledger_id,
+                    balance_diff_accounting: token.balance_diff_accounting,
+                    decimals: token.decimals,
                 },
                 EventType::AcceptedErc20WithdrawalRequest(Erc20WithdrawalRequest {
                     max_transaction_fee,
@@ -873,6 +900,12 @@ fn http_request(req: HttpRequest) -> HttpResponse {
                     "The last Ethereum block the ckETH minter checked for ckERC20 deposits.",
                 )?;
 
+                w.encode_gauge(
+                    "cketh_minter_last_processed_block_for_deposit_with_subaccount",
+                    s.last_deposit_with_subaccount_scraped_block_number.as_f64(),
+                    "The last Ethereum block the ckETH minter checked for deposits with a subaccount.",
+                )?;
+
                 w.encode_counter(
                     "cketh_minter_skipped_blocks",
                     s.skipped_blocks
@@ -917,6 +950,11 @@ fn http_request(req: HttpRequest) -> HttpResponse {
                     s.eth_balance.total_unspent_tx_fees().as_f64(),
                     "Total amount of unspent fees across all finalized transaction ckETH -> ETH",
                 )?;
+                w.encode_gauge(
+                    "cketh_minter_total_tx_fees_deficit",
+                    s.eth_balance.total_tx_fees_deficit().as_f64(),
+                    "Total amount of fees paid by the minter in excess of the fees charged across all finalized transactions ckETH -> ETH",
+                )?;
 
                 let now_nanos = ic_cdk::api::time();
                 let age_nanos = now_nanos.saturating_sub(
@@ -4,15 +4,19 @@ use crate::eth_logs::{EventSource, ReceivedEvent};
 use crate::eth_rpc::BlockTag;
 use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
 use crate::lifecycle::upgrade::UpgradeArg;
-use crate::lifecycle::EthereumNetwork;
-use crate::logs::DEBUG;
+use crate::lifecycle::{EthereumNetwork, EvmNetworkArg, FeeModelArg};
+use crate::logs::{DEBUG, INFO};
 use crate::map::DedupMultiKeyMap;
 use crate::numeric::{
-    BlockNumber, Erc20Value, LedgerBurnIndex, LedgerMintIndex, TransactionNonce, Wei,
+    BlockNumber, Erc20Value, GasAmount, LedgerBurnIndex, LedgerMintIndex, TransactionNonce, Wei,
+    WeiPerGas,
 };
 use crate::state::transactions::{Erc20WithdrawalRequest, TransactionCallData, WithdrawalRequest};
-use crate::tx::GasFeeEstimate;
-use candid::Principal;
+use crate::tx::{GasFeeEstimate, MIN_MAX_PRIORITY_FEE_PER_GAS};
+use crate::withdraw::{
+    CKERC20_WITHDRAWAL_TRANSACTION_GAS_LIMIT, CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
+};
+use candid::{Nat, Principal};
 use ic_canister_log::log;
 use ic_cdk::api::management_canister::ecdsa::EcdsaPublicKeyResponse;
 use ic_crypto_secp256k1::PublicKey;
@@ -55,12 +59,14 @@ pub struct State {
     pub cketh_ledger_id: Principal,
     pub eth_helper_contract_address: Option<Address>,
     pub erc20_helper_contract_address: Option<Address>,
+    pub deposit_with_subaccount_helper_contract_address: Option<Address>,
     pub ecdsa_public_key: Option<EcdsaPublicKeyResponse>,
     pub cketh_minimum_withdrawal_amount: Wei,
     pub ethereum_block_height: BlockTag,
     pub first_scraped_block_number: BlockNumber,
     pub last_scraped_block_number: BlockNumber,
     pub last_erc20_scraped_block_number: BlockNumber,
+    pub last_deposit_with_subaccount_scraped_block_number: BlockNumber,
     pub last_observed_block_number: Option<BlockNumber>,
     pub events_to_mint: BTreeMap<EventSource, ReceivedEvent>,
     pub minted_events: BTreeMap<EventSource, MintedEvent>,
@@ -88,6 +94,10 @@ pub struct State {
 
     pub last_transaction_price_estimate: Option<(u64, GasFeeEstimate)>,
 
+    /// Last estimate of the L1 data fee of withdrawal transactions on OP-stack networks,
+    /// with the IC time at which it was made.
+    pub last_l1_fee_estimate: Option<(u64, Wei)>,
+
     /// Canister ID of the ledger suite orchestrator that
     /// can add new ERC-20 token to the minter
     pub ledger_suite_orchestrator_id: Option<Principal>,
@@ -96,11 +106,137 @@ pub struct State {
     /// handles communication with Ethereum
     pub evm_rpc_id: Option<Principal>,
 
+    /// Configuration of the EVM network when it's neither the Ethereum mainnet nor Sepolia.
+    pub evm_network_config: Option<EvmNetworkConfig>,
+
     /// ERC-20 tokens that the minter can mint:
     /// - primary key: ledger ID for the ckERC20 token
     /// - secondary key: ERC-20 contract address on Ethereum
     /// - value: ckERC20 token symbol
     pub ckerc20_tokens: DedupMultiKeyMap<Principal, Address, CkTokenSymbol>,
+
+    /// Whether deposits of the supported ERC-20 tokens are credited using balance-diff accounting,
+    /// by ERC-20 contract address, when provided by the orchestrator or set by an upgrade.
+    pub erc20_balance_diff_accounting: BTreeMap<Address, bool>,
+
+    /// Number of decimals of the supported ERC-20 tokens,
+    /// by ERC-20 contract address, when provided by the orchestrator.
+    pub erc20_decimals: BTreeMap<Address, u8>,
+}
+
+/// The minimum number of RPC providers of an EVM network. The minter requires
+/// at least two providers to agree on a result, so a single failing provider
+/// must not be able to block it.
+pub const MIN_EVM_RPC_PROVIDERS: usize = 3;
+
+/// Configuration of an EVM network other than the Ethereum mainnet and Sepolia,
+/// see [`EvmNetworkArg`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct EvmNetworkConfig {
+    pub rpc_providers: Vec<String>,
+    pub block_confirmations: u64,
+    pub cketh_token_symbol: CkTokenSymbol,
+    pub min_max_priority_fee_per_gas: WeiPerGas,
+    pub cketh_withdrawal_gas_limit: GasAmount,
+    pub ckerc20_withdrawal_gas_limit: GasAmount,
+    pub fee_model: FeeModel,
+    pub cketh_ledger_transfer_fee: Wei,
+}
+
+/// Fee model of an EVM network, see [`FeeModelArg`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum FeeModel {
+    Eip1559,
+    OpStack { gas_price_oracle_address: Address },
+}
+
+impl TryFrom<FeeModelArg> for FeeModel {
+    type Error = InvalidStateError;
+
+    fn try_from(fee_model: FeeModelArg) -> Result<Self, Self::Error> {
+        match fee_model {
+            FeeModelArg::Eip1559 => Ok(Self::Eip1559),
+            FeeModelArg::OpStack {
+                gas_price_oracle_address,
+            } => {
+                let gas_price_oracle_address = gas_price_oracle_address
+                    .parse::<Address>()
+                    .map_err(|e| InvalidStateError::InvalidEvmNetwork(format!("ERROR: {}", e)))?;
+                if gas_price_oracle_address == Address::ZERO {
+                    return Err(InvalidStateError::InvalidEvmNetwork(
+                        "gas_price_oracle_address cannot be the zero address".to_string(),
+                    ));
+                }
+                Ok(Self::OpStack {
+                    gas_price_oracle_address,
+                })
+            }
+        }
+    }
+}
+
+impl TryFrom<EvmNetworkArg> for EvmNetworkConfig {
+    type Error = InvalidStateError;
+
+    fn try_from(
+        EvmNetworkArg {
+            rpc_providers,
+            block_confirmations,
+            cketh_token_symbol,
+            min_max_priority_fee_per_gas,
+            cketh_withdrawal_gas_limit,
+            ckerc20_withdrawal_gas_limit,
+            fee_model,
+            cketh_ledger_transfer_fee,
+        }: EvmNetworkArg,
+    ) -> Result<Self, Self::Error> {
+        if rpc_providers.len() < MIN_EVM_RPC_PROVIDERS {
+            return Err(InvalidStateError::InvalidEvmNetwork(format!(
+                "ERROR: at least {MIN_EVM_RPC_PROVIDERS} RPC providers are required, got {}",
+                rpc_providers.len()
+            )));
+        }
+        if rpc_providers.iter().collect::<BTreeSet<_>>().len() != rpc_providers.len() {
+            return Err(InvalidStateError::InvalidEvmNetwork(
+                "ERROR: RPC providers must be distinct".to_string(),
+            ));
+        }
+        if let Some(url) = rpc_providers
+            .iter()
+            .find(|url| !url.starts_with("https://"))
+        {
+            return Err(InvalidStateError::InvalidEvmNetwork(format!(
+                "ERROR: RPC provider URL {url} does not use HTTPS"
+            )));
+        }
+        let cketh_token_symbol = cketh_token_symbol
+            .parse::<CkTokenSymbol>()
+            .map_err(InvalidStateError::InvalidEvmNetwork)?;
+        let min_max_priority_fee_per_gas = WeiPerGas::try_from(min_max_priority_fee_per_gas)
+            .map_err(|e| InvalidStateError::InvalidEvmNetwork(format!("ERROR: {}", e)))?;
+        let parse_gas_limit = |gas_limit: Nat| {
+            let gas_limit = GasAmount::try_from(gas_limit)
+                .map_err(|e| InvalidStateError::InvalidEvmNetwork(format!("ERROR: {}", e)))?;
+            if gas_limit == GasAmount::ZERO {
+                return Err(InvalidStateError::InvalidEvmNetwork(
+                    "gas limits must be positive".to_string(),
+                ));
+            }
+            Ok(gas_limit)
+        };
+        let cketh_ledger_transfer_fee = Wei::try_from(cketh_ledger_transfer_fee)
+            .map_err(|e| InvalidStateError::InvalidEvmNetwork(format!("ERROR: {}", e)))?;
+        Ok(Self {
+            rpc_providers,
+            block_confirmations,
+            cketh_token_symbol,
+            min_max_priority_fee_per_gas,
+            cketh_withdrawal_gas_limit: parse_gas_limit(cketh_withdrawal_gas_limit)?,
+            ckerc20_withdrawal_gas_limit: parse_gas_limit(ckerc20_withdrawal_gas_limit)?,
+            fee_model: FeeModel::try_from(fee_model)?,
+            cketh_ledger_transfer_fee,
+        })
+    }
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -113,6 +249,10 @@ pub enum InvalidStateError {
     InvalidMinimumWithdrawalAmount(String),
     InvalidLastScrapedBlockNumber(String),
     InvalidLastErc20ScrapedBlockNumber(String),
+    InvalidDepositWithSubaccountHelperContractAddress(String),
+    InvalidLastDepositWithSubaccountScrapedBlockNumber(String),
+    InvalidEvmNetwork(String),
+    InvalidErc20BalanceDiffAccounting(String),
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -168,11 +308,20 @@ impl State {
                 "minimum_withdrawal_amount must be positive".to_string(),
             ));
         }
-        let cketh_ledger_transfer_fee = match self.ethereum_network {
-            EthereumNetwork::Mainnet => Wei::new(2_000_000_000_000),
-            EthereumNetwork::Sepolia => Wei::new(10_000_000_000),
-        };
-        if self.cketh_minimum_withdrawal_amount < cketh_ledger_transfer_fee {
+        match (&self.ethereum_network, &self.evm_network_config) {
+            (EthereumNetwork::Custom(_), None) => {
+                return Err(InvalidStateError::InvalidEvmNetwork(
+                    "a custom EVM network requires evm_network to be set".to_string(),
+                ));
+            }
+            (EthereumNetwork::Mainnet | EthereumNetwork::Sepolia, Some(_)) => {
+                return Err(InvalidStateError::InvalidEvmNetwork(
+                    "evm_network can only be set for a custom EVM network".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if self.cketh_minimum_withdrawal_amount < self.cketh_ledger_transfer_fee() {
             return Err(InvalidStateError::InvalidMinimumWithdrawalAmount(
                 "minimum_withdrawal_amount must cover ledger transaction fee, \
                 otherwise ledger can return a BadBurn error that should be returned to the user"
@@ -234,6 +383,11 @@ impl State {
                 ckerc20_ledger_id: *ckerc20_ledger_id,
                 erc20_ethereum_network: self.ethereum_network,
                 ckerc20_token_symbol: symbol.clone(),
+                balance_diff_accounting: self
+                    .erc20_balance_diff_accounting
+                    .get(erc20_address)
+                    .copied(),
+                decimals: self.erc20_decimals.get(erc20_address).copied(),
             })
     }
 
@@ -245,9 +399,23 @@ impl State {
                 ckerc20_ledger_id: *ledger_id,
                 erc20_ethereum_network: self.ethereum_network,
                 ckerc20_token_symbol: symbol.clone(),
+                balance_diff_accounting: self
+                    .erc20_balance_diff_accounting
+                    .get(erc20_address)
+                    .copied(),
+                decimals: self.erc20_decimals.get(erc20_address).copied(),
             })
     }
 
+    /// Whether deposits of the given ERC-20 token are credited with the amount the minter
+    /// actually received, see [`CkErc20Token::balance_diff_accounting`].
+    pub fn uses_balance_diff_accounting(&self, erc20_contract_address: &Address) -> bool {
+        self.erc20_balance_diff_accounting
+            .get(erc20_contract_address)
+            .copied()
+            .unwrap_or_default()
+    }
+
     /// Quarantine the deposit event to prevent double minting.
     /// WARNING!: It's crucial that this method does not panic,
     /// since it's called inside the clean-up callback, when an unexpected panic did occur before.
@@ -369,9 +537,25 @@ impl State {
                 .expect("BUG: withdrawal amount MUST always be at least the transaction amount"),
             WithdrawalRequest::CkErc20(req) => req.max_transaction_fee,
         };
-        let unspent_tx_fee = charged_tx_fee.checked_sub(tx_fee).expect(
-            "BUG: charged transaction fee MUST always be at least the effective transaction fee",
-        );
+        let (unspent_tx_fee, tx_fee_deficit) = match charged_tx_fee.checked_sub(tx_fee) {
+            Some(unspent_tx_fee) => (unspent_tx_fee, Wei::ZERO),
+            None => {
+                // The L1 data fee depends on the L1 base fee when the transaction is included,
+                // which may exceed the L1 fee reserved when the transaction was created.
+                assert!(
+                    receipt.l1_fee.is_some(),
+                    "BUG: charged transaction fee MUST always be at least the effective transaction fee"
+                );
+                let deficit = tx_fee
+                    .checked_sub(charged_tx_fee)
+                    .expect("BUG: effective transaction fee is greater than the charged fee");
+                log!(
+                    INFO,
+                    "[update_balance_upon_withdrawal]: the effective fee {tx_fee} of withdrawal {withdrawal_id} exceeds the charged fee {charged_tx_fee} by {deficit}",
+                );
+                (Wei::ZERO, deficit)
+            }
+        };
         let debited_amount = match receipt.status {
             TransactionStatus::Success => tx
                 .transaction()
@@ -383,6 +567,7 @@ impl State {
         self.eth_balance.eth_balance_sub(debited_amount);
         self.eth_balance.total_effective_tx_fees_add(tx_fee);
         self.eth_balance.total_unspent_tx_fees_add(unspent_tx_fee);
+        self.eth_balance.total_tx_fees_deficit_add(tx_fee_deficit);
 
         if receipt.status == TransactionStatus::Success && !tx.transaction_data().is_empty() {
             let TransactionCallData::Erc20Transfer { to: _, value } = TransactionCallData::decode(
@@ -440,6 +625,16 @@ impl State {
             Ok(()),
             "ERROR: some ckERC20 tokens use the same ckERC20 ledger ID or ERC-20 address"
         );
+        if let Some(balance_diff_accounting) = ckerc20_token.balance_diff_accounting {
+            self.erc20_balance_diff_accounting.insert(
+                ckerc20_token.erc20_contract_address,
+                balance_diff_accounting,
+            );
+        }
+        if let Some(decimals) = ckerc20_token.decimals {
+            self.erc20_decimals
+                .insert(ckerc20_token.erc20_contract_address, decimals);
+        }
     }
 
     pub fn erc20_balances_by_token_symbol(&self) -> BTreeMap<&CkTokenSymbol, &Erc20Value> {
@@ -466,6 +661,64 @@ impl State {
         self.ethereum_block_height
     }
 
+    /// Number of blocks that must be built on top of the block with tag
+    /// `ethereum_block_height` before the minter considers it final.
+    pub fn block_confirmations(&self) -> u64 {
+        self.evm_network_config
+            .as_ref()
+            .map_or(0, |config| config.block_confirmations)
+    }
+
+    pub fn min_max_priority_fee_per_gas(&self) -> WeiPerGas {
+        self.evm_network_config
+            .as_ref()
+            .map_or(MIN_MAX_PRIORITY_FEE_PER_GAS, |config| {
+                config.min_max_priority_fee_per_gas
+            })
+    }
+
+    pub fn cketh_withdrawal_gas_limit(&self) -> GasAmount {
+        self.evm_network_config
+            .as_ref()
+            .map_or(CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT, |config| {
+                config.cketh_withdrawal_gas_limit
+            })
+    }
+
+    pub fn ckerc20_withdrawal_gas_limit(&self) -> GasAmount {
+        self.evm_network_config
+            .as_ref()
+            .map_or(CKERC20_WITHDRAWAL_TRANSACTION_GAS_LIMIT, |config| {
+                config.ckerc20_withdrawal_gas_limit
+            })
+    }
+
+    pub fn fee_model(&self) -> FeeModel {
+        self.evm_network_config
+            .as_ref()
+            .map_or(FeeModel::Eip1559, |config| config.fee_model.clone())
+    }
+
+    /// Transfer fee of the ckETH ledger, or of the ledger of the ck-token
+    /// of the native currency of a custom EVM network.
+    pub fn cketh_ledger_transfer_fee(&self) -> Wei {
+        match self.ethereum_network {
+            EthereumNetwork::Mainnet => Wei::new(2_000_000_000_000),
+            EthereumNetwork::Sepolia => Wei::new(10_000_000_000),
+            EthereumNetwork::Custom(_) => self
+                .evm_network_config
+                .as_ref()
+                .map_or(Wei::ZERO, |config| config.cketh_ledger_transfer_fee),
+        }
+    }
+
+    pub fn withdrawal_gas_limit(&self, withdrawal_request: &WithdrawalRequest) -> GasAmount {
+        match withdrawal_request {
+            WithdrawalRequest::CkEth(_) => self.cketh_withdrawal_gas_limit(),
+            WithdrawalRequest::CkErc20(_) => self.ckerc20_withdrawal_gas_limit(),
+        }
+    }
+
     fn upgrade(&mut self, upgrade_args: UpgradeArg) -> Result<(), InvalidStateError> {
         use std::str::FromStr;
 
@@ -478,6 +731,10 @@ impl State {
             erc20_helper_contract_address,
             last_erc20_scraped_block_number,
             evm_rpc_id,
+            evm_network,
+            deposit_with_subaccount_helper_contract_address,
+            last_deposit_with_subaccount_scraped_block_number,
+            erc20_balance_diff_accounting,
         } = upgrade_args;
         if let Some(nonce) = next_transaction_nonce {
             let nonce = TransactionNonce::try_from(nonce)
@@ -508,6 +765,26 @@ impl State {
                     InvalidStateError::InvalidLastErc20ScrapedBlockNumber(format!("ERROR: {}", e))
                 })?;
         }
+        if let Some(address) = deposit_with_subaccount_helper_contract_address {
+            let deposit_with_subaccount_helper_contract_address = Address::from_str(&address)
+                .map_err(|e| {
+                    InvalidStateError::InvalidDepositWithSubaccountHelperContractAddress(format!(
+                        "ERROR: {}",
+                        e
+                    ))
+                })?;
+            self.deposit_with_subaccount_helper_contract_address =
+                Some(deposit_with_subaccount_helper_contract_address);
+        }
+        if let Some(block_number) = last_deposit_with_subaccount_scraped_block_number {
+            self.last_deposit_with_subaccount_scraped_block_number =
+                BlockNumber::try_from(block_number).map_err(|e| {
+                    InvalidStateError::InvalidLastDepositWithSubaccountScrapedBlockNumber(format!(
+                        "ERROR: {}",
+                        e
+                    ))
+                })?;
+        }
         if let Some(block_height) = ethereum_block_height {
             self.ethereum_block_height = block_height.into();
         }
@@ -521,6 +798,22 @@ impl State {
                 self.evm_rpc_id = Some(evm_id);
             }
         }
+        if let Some(evm_network) = evm_network {
+            self.evm_network_config = Some(EvmNetworkConfig::try_from(evm_network)?);
+        }
+        for arg in erc20_balance_diff_accounting.unwrap_or_default() {
+            let erc20_contract_address =
+                Address::from_str(&arg.erc20_contract_address).map_err(|e| {
+                    InvalidStateError::InvalidErc20BalanceDiffAccounting(format!("ERROR: {}", e))
+                })?;
+            if !self.ckerc20_tokens.contains_alt(&erc20_contract_address) {
+                return Err(InvalidStateError::InvalidErc20BalanceDiffAccounting(
+                    format!("ERROR: unsupported ERC-20 token {erc20_contract_address}"),
+                ));
+            }
+            self.erc20_balance_diff_accounting
+                .insert(erc20_contract_address, arg.enabled);
+        }
         self.validate_config()
     }
 
@@ -563,6 +856,12 @@ impl State {
             other.ledger_suite_orchestrator_id
         );
         ensure_eq!(self.ckerc20_tokens, other.ckerc20_tokens);
+        ensure_eq!(
+            self.erc20_balance_diff_accounting,
+            other.erc20_balance_diff_accounting
+        );
+        ensure_eq!(self.erc20_decimals, other.erc20_decimals);
+        ensure_eq!(self.evm_network_config, other.evm_network_config);
 
         self.eth_transactions
             .is_equivalent_to(&other.eth_transactions)
@@ -659,6 +958,11 @@ pub struct EthBalance {
     /// Total amount of fees that were charged to the user during the withdrawal
     /// but not consumed by the finalized transaction ckETH -> ETH
     total_unspent_tx_fees: Wei,
+    /// Total amount of fees consumed by finalized transactions ckETH -> ETH
+    /// in excess of what was charged to the user, which the minter paid.
+    /// This only happens on OP-stack networks when the L1 data fee exceeds
+    /// the reserved L1 fee.
+    total_tx_fees_deficit: Wei,
 }
 
 impl Default for EthBalance {
@@ -667,6 +971,7 @@ impl Default for EthBalance {
             eth_balance: Wei::ZERO,
             total_effective_tx_fees: Wei::ZERO,
             total_unspent_tx_fees: Wei::ZERO,
+            total_tx_fees_deficit: Wei::ZERO,
         }
     }
 }
@@ -714,6 +1019,18 @@ impl EthBalance {
             })
     }
 
+    fn total_tx_fees_deficit_add(&mut self, value: Wei) {
+        self.total_tx_fees_deficit = self
+            .total_tx_fees_deficit
+            .checked_add(value)
+            .unwrap_or_else(|| {
+                panic!(
+                    "BUG: overflow when adding {} to {}",
+                    value, self.total_tx_fees_deficit
+                )
+            })
+    }
+
     pub fn eth_balance(&self) -> Wei {
         self.eth_balance
     }
@@ -725,6 +1042,10 @@ impl EthBalance {
     pub fn total_unspent_tx_fees(&self) -> Wei {
         self.total_unspent_tx_fees
     }
+
+    pub fn total_tx_fees_deficit(&self) -> Wei {
+        self.total_tx_fees_deficit
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Default)]
@@ -62,6 +62,9 @@ pub fn apply_state_transition(state: &mut State, payload: &EventType) {
         EventType::SyncedErc20ToBlock { block_number } => {
             state.last_erc20_scraped_block_number = *block_number;
         }
+        EventType::SyncedDepositWithSubaccountToBlock { block_number } => {
+            state.last_deposit_with_subaccount_scraped_block_number = *block_number;
+        }
         EventType::AcceptedEthWithdrawalRequest(request) => {
             state
                 .eth_transactions
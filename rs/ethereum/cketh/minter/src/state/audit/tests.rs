@@ -1,7 +1,7 @@
 use crate::checked_amount::CheckedAmountOf;
 use crate::endpoints::events::{Event as CandidEvent, EventPayload, UnsignedTransaction};
 use crate::erc20::CkErc20Token;
-use crate::eth_logs::{ReceivedErc20Event, ReceivedEthEvent};
+use crate::eth_logs::{LedgerSubaccount, ReceivedErc20Event, ReceivedEthEvent};
 use crate::eth_rpc_client::responses::TransactionReceipt;
 use crate::lifecycle::EthereumNetwork;
 use crate::numeric::Wei;
@@ -249,6 +249,7 @@ impl GetEventsFile {
                     from_address,
                     value,
                     principal,
+                    subaccount,
                 } => ET::AcceptedDeposit(ReceivedEthEvent {
                     transaction_hash: transaction_hash.parse().unwrap(),
                     block_number: block_number.try_into().unwrap(),
@@ -256,6 +257,7 @@ impl GetEventsFile {
                     from_address: from_address.parse().unwrap(),
                     value: value.try_into().unwrap(),
                     principal,
+                    subaccount: subaccount.and_then(LedgerSubaccount::from_bytes),
                 }),
                 EventPayload::AcceptedErc20Deposit {
                     transaction_hash,
@@ -265,6 +267,7 @@ impl GetEventsFile {
                     value,
                     principal,
                     erc20_contract_address,
+                    subaccount,
                 } => ET::AcceptedErc20Deposit(ReceivedErc20Event {
                     transaction_hash: transaction_hash.parse().unwrap(),
                     block_number: block_number.try_into().unwrap(),
@@ -273,6 +276,7 @@ impl GetEventsFile {
                     value: value.try_into().unwrap(),
                     principal,
                     erc20_contract_address: erc20_contract_address.parse().unwrap(),
+                    subaccount: subaccount.and_then(LedgerSubaccount::from_bytes),
                 }),
                 EventPayload::InvalidDeposit {
                     event_source,
@@ -294,6 +298,11 @@ impl GetEventsFile {
                 EventPayload::SyncedErc20ToBlock { block_number } => ET::SyncedErc20ToBlock {
                     block_number: block_number.try_into().unwrap(),
                 },
+                EventPayload::SyncedDepositWithSubaccountToBlock { block_number } => {
+                    ET::SyncedDepositWithSubaccountToBlock {
+                        block_number: block_number.try_into().unwrap(),
+                    }
+                }
                 EventPayload::AcceptedEthWithdrawalRequest {
                     withdrawal_amount,
                     destination,
@@ -348,6 +357,9 @@ impl GetEventsFile {
                             CandidTransactionStatus::Failure => TransactionStatus::Failure,
                         },
                         transaction_hash: transaction_receipt.transaction_hash.parse().unwrap(),
+                        l1_fee: transaction_receipt
+                            .l1_fee
+                            .map(|fee| fee.try_into().unwrap()),
                     },
                 },
                 EventPayload::ReimbursedEthWithdrawal {
@@ -390,12 +402,16 @@ impl GetEventsFile {
                     address,
                     ckerc20_token_symbol,
                     ckerc20_ledger_id,
+                    balance_diff_accounting,
+                    decimals,
                 } => ET::AddedCkErc20Token(CkErc20Token {
                     erc20_ethereum_network: EthereumNetwork::try_from(chain_id.0.to_u64().unwrap())
                         .unwrap(),
                     erc20_contract_address: address.parse().unwrap(),
                     ckerc20_token_symbol: ckerc20_token_symbol.parse().unwrap(),
                     ckerc20_ledger_id,
+                    balance_diff_accounting,
+                    decimals,
                 }),
                 EventPayload::AcceptedErc20WithdrawalRequest {
                     max_transaction_fee,
@@ -164,6 +164,15 @@ pub enum EventType {
         #[n(1)]
         block_number: BlockNumber,
     },
+    /// The minter processed the helper smart contract logs for deposits
+    /// with a subaccount up to the specified height.
+    #[n(24)]
+    SyncedDepositWithSubaccountToBlock {
+        /// The last processed block number for the deposit with subaccount
+        /// helper contract (inclusive).
+        #[n(0)]
+        block_number: BlockNumber,
+    },
 }
 
 impl ReceivedEvent {
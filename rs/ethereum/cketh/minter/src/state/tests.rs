@@ -1,5 +1,7 @@
 use crate::endpoints::CandidBlockTag;
-use crate::eth_logs::{EventSource, ReceivedErc20Event, ReceivedEthEvent, ReceivedEvent};
+use crate::eth_logs::{
+    EventSource, LedgerSubaccount, ReceivedErc20Event, ReceivedEthEvent, ReceivedEvent,
+};
 use crate::eth_rpc::BlockTag;
 use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
 use crate::lifecycle::init::InitArg;
@@ -61,6 +63,7 @@ fn initial_state() -> State {
         minimum_withdrawal_amount: wei_from_milli_ether(10).into(),
         next_transaction_nonce: Default::default(),
         last_scraped_block_number: Default::default(),
+        evm_network: None,
     })
     .expect("init args should be valid")
 }
@@ -232,7 +235,8 @@ mod mint_transaction {
           log_index: 29, \
           from_address: 0xdd2851Cdd40aE6536831558DD46db62fAc7A844d, \
           value: 10_000_000_000_000_000, \
-          principal: k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae \
+          principal: k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae, \
+          subaccount: None \
         }";
         assert_eq!(format!("{:?}", received_eth_event()), expected);
     }
@@ -246,7 +250,8 @@ mod mint_transaction {
           from_address: 0xdd2851Cdd40aE6536831558DD46db62fAc7A844d, \
           value: 5_000_000, \
           principal: hkroy-sm7vs-yyjs7-ekppe-qqnwx-hm4zf-n7ybs-titsi-k6e3k-ucuiu-uqe, \
-          contract_address: 0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238 \
+          contract_address: 0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238, \
+          subaccount: None \
         }";
         assert_eq!(format!("{:?}", received_erc20_event()), expected);
     }
@@ -266,6 +271,7 @@ fn received_eth_event() -> ReceivedEthEvent {
         principal: "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae"
             .parse()
             .unwrap(),
+        subaccount: None,
     }
 }
 
@@ -287,6 +293,7 @@ fn received_erc20_event() -> ReceivedErc20Event {
         erc20_contract_address: "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238"
             .parse()
             .unwrap(),
+        subaccount: None,
     }
 }
 
@@ -396,6 +403,53 @@ mod upgrade {
         );
         assert_eq!(state.ethereum_block_height, BlockTag::Safe);
     }
+
+    #[test]
+    fn should_toggle_balance_diff_accounting_of_supported_token() {
+        use crate::lifecycle::upgrade::Erc20BalanceDiffAccountingArg;
+        use crate::state::tests::erc20::record_add_ckerc20_token::cksepolia_usdc;
+
+        let mut state = initial_state();
+        state.ethereum_network = EthereumNetwork::Sepolia;
+        let ckusdc = cksepolia_usdc();
+        state.record_add_ckerc20_token(ckusdc.clone());
+        assert!(!state.uses_balance_diff_accounting(&ckusdc.erc20_contract_address));
+
+        for enabled in [true, false] {
+            state
+                .upgrade(UpgradeArg {
+                    erc20_balance_diff_accounting: Some(vec![Erc20BalanceDiffAccountingArg {
+                        erc20_contract_address: ckusdc.erc20_contract_address.to_string(),
+                        enabled,
+                    }]),
+                    ..Default::default()
+                })
+                .expect("valid upgrade args");
+
+            assert_eq!(
+                state.uses_balance_diff_accounting(&ckusdc.erc20_contract_address),
+                enabled
+            );
+        }
+    }
+
+    #[test]
+    fn should_fail_to_toggle_balance_diff_accounting_of_unsupported_token() {
+        use crate::lifecycle::upgrade::Erc20BalanceDiffAccountingArg;
+
+        let mut state = initial_state();
+        assert_matches!(
+            state.upgrade(UpgradeArg {
+                erc20_balance_diff_accounting: Some(vec![Erc20BalanceDiffAccountingArg {
+                    erc20_contract_address: "0xdac17f958d2ee523a2206206994597c13d831ec7"
+                        .to_string(),
+                    enabled: true,
+                }]),
+                ..Default::default()
+            }),
+            Err(InvalidStateError::InvalidErc20BalanceDiffAccounting(_))
+        );
+    }
 }
 
 mod erc20 {
@@ -437,10 +491,52 @@ mod erc20 {
                     erc20_contract_address: ckerc20.erc20_contract_address,
                     ckerc20_token_symbol: ckerc20.ckerc20_token_symbol,
                     ckerc20_ledger_id: ckerc20.ckerc20_ledger_id,
+                    balance_diff_accounting: ckerc20.balance_diff_accounting,
+                    decimals: ckerc20.decimals,
                 }]
             );
         }
 
+        #[test]
+        fn should_record_balance_diff_accounting() {
+            let mut state = initial_state();
+            state.ethereum_network = EthereumNetwork::Mainnet;
+            let ckusdc = ckusdc();
+            let ckusdt = CkErc20Token {
+                balance_diff_accounting: Some(true),
+                ..ckusdt()
+            };
+
+            state.record_add_ckerc20_token(ckusdc.clone());
+            state.record_add_ckerc20_token(ckusdt.clone());
+
+            assert!(!state.uses_balance_diff_accounting(&ckusdc.erc20_contract_address));
+            assert!(state.uses_balance_diff_accounting(&ckusdt.erc20_contract_address));
+            assert_eq!(
+                state.find_ck_erc20_token_by_ledger_id(&ckusdt.ckerc20_ledger_id),
+                Some(ckusdt)
+            );
+        }
+
+        #[test]
+        fn should_treat_missing_balance_diff_accounting_as_disabled() {
+            let mut state = initial_state();
+            state.ethereum_network = EthereumNetwork::Mainnet;
+            let ckusdc = CkErc20Token {
+                balance_diff_accounting: None,
+                decimals: None,
+                ..ckusdc()
+            };
+
+            state.record_add_ckerc20_token(ckusdc.clone());
+
+            assert!(!state.uses_balance_diff_accounting(&ckusdc.erc20_contract_address));
+            assert_eq!(
+                state.supported_ck_erc20_tokens().collect::<Vec<_>>(),
+                vec![ckusdc]
+            );
+        }
+
         #[test]
         fn should_panic_when_duplicate_ledger_id() {
             let mut state = initial_state();
@@ -500,6 +596,8 @@ mod erc20 {
                     .unwrap(),
                 ckerc20_token_symbol: "ckUSDC".parse().unwrap(),
                 ckerc20_ledger_id: "mxzaz-hqaaa-aaaar-qaada-cai".parse().unwrap(),
+                balance_diff_accounting: Some(false),
+                decimals: Some(6),
             }
         }
 
@@ -511,6 +609,8 @@ mod erc20 {
                     .unwrap(),
                 ckerc20_token_symbol: "ckSepoliaUSDC".parse().unwrap(),
                 ckerc20_ledger_id: "mxzaz-hqaaa-aaaar-qaada-cai".parse().unwrap(),
+                balance_diff_accounting: Some(false),
+                decimals: Some(6),
             }
         }
 
@@ -522,6 +622,8 @@ mod erc20 {
                     .unwrap(),
                 ckerc20_token_symbol: "ckUSDT".parse().unwrap(),
                 ckerc20_ledger_id: "nbsys-saaaa-aaaar-qaaga-cai".parse().unwrap(),
+                balance_diff_accounting: Some(false),
+                decimals: Some(6),
             }
         }
     }
@@ -531,6 +633,10 @@ fn arb_principal() -> impl Strategy<Value = Principal> {
     pvec(any::<u8>(), 0..=29).prop_map(|bytes| Principal::from_slice(&bytes))
 }
 
+fn arb_ledger_subaccount() -> impl Strategy<Value = LedgerSubaccount> {
+    uniform32(any::<u8>()).prop_filter_map("default subaccount", LedgerSubaccount::from_bytes)
+}
+
 fn arb_u256() -> impl Strategy<Value = u256> {
     uniform32(any::<u8>()).prop_map(u256::from_be_bytes)
 }
@@ -590,6 +696,7 @@ prop_compose! {
             minimum_withdrawal_amount,
             next_transaction_nonce,
             last_scraped_block_number,
+            evm_network: None,
         }
     }
 }
@@ -604,6 +711,8 @@ prop_compose! {
         erc20_helper_contract_address in proptest::option::of(arb_address()),
         last_erc20_scraped_block_number in proptest::option::of(arb_nat()),
         evm_rpc_id in proptest::option::of(arb_principal()),
+        deposit_with_subaccount_helper_contract_address in proptest::option::of(arb_address()),
+        last_deposit_with_subaccount_scraped_block_number in proptest::option::of(arb_nat()),
     ) -> UpgradeArg {
         UpgradeArg {
             ethereum_contract_address: contract_address.map(|addr| addr.to_string()),
@@ -613,7 +722,11 @@ prop_compose! {
             ledger_suite_orchestrator_id,
             erc20_helper_contract_address: erc20_helper_contract_address.map(|addr| addr.to_string()),
             last_erc20_scraped_block_number,
-            evm_rpc_id
+            evm_rpc_id,
+            evm_network: None,
+            deposit_with_subaccount_helper_contract_address: deposit_with_subaccount_helper_contract_address.map(|addr| addr.to_string()),
+            last_deposit_with_subaccount_scraped_block_number,
+            erc20_balance_diff_accounting: None,
         }
     }
 }
@@ -626,6 +739,7 @@ prop_compose! {
         from_address in arb_address(),
         value in arb_checked_amount_of(),
         principal in arb_principal(),
+        subaccount in proptest::option::of(arb_ledger_subaccount()),
     ) -> ReceivedEthEvent {
         ReceivedEthEvent {
             transaction_hash,
@@ -634,6 +748,7 @@ prop_compose! {
             from_address,
             value,
             principal,
+            subaccount,
         }
     }
 }
@@ -647,6 +762,7 @@ prop_compose! {
         value in arb_checked_amount_of(),
         principal in arb_principal(),
         erc20_contract_address in arb_address(),
+        subaccount in proptest::option::of(arb_ledger_subaccount()),
     ) -> ReceivedErc20Event {
         ReceivedErc20Event {
             transaction_hash,
@@ -656,6 +772,7 @@ prop_compose! {
             value,
             principal,
             erc20_contract_address,
+            subaccount,
         }
     }
 }
@@ -727,6 +844,7 @@ prop_compose! {
             gas_used,
             status,
             transaction_hash,
+            l1_fee: None,
         }
     }
 }
@@ -750,6 +868,9 @@ fn arb_event_type() -> impl Strategy<Value = EventType> {
         arb_checked_amount_of().prop_map(|block_number| EventType::SyncedToBlock { block_number }),
         arb_checked_amount_of()
             .prop_map(|block_number| EventType::SyncedErc20ToBlock { block_number }),
+        arb_checked_amount_of().prop_map(|block_number| {
+            EventType::SyncedDepositWithSubaccountToBlock { block_number }
+        }),
         (any::<u64>(), arb_unsigned_tx()).prop_map(|(withdrawal_id, transaction)| {
             EventType::CreatedTransaction {
                 withdrawal_id: withdrawal_id.into(),
@@ -946,6 +1067,7 @@ fn state_equivalence() {
                     "0x06afc3c693dc2ba2c19b5c287c4dddce040d766bea5fd13c8a7268b04aa94f2d"
                         .parse()
                         .unwrap(),
+                l1_fee: None,
             })
             .expect("valid receipt"),
         ),
@@ -995,6 +1117,7 @@ fn state_equivalence() {
                 .parse()
                 .unwrap(),
         ),
+        deposit_with_subaccount_helper_contract_address: None,
         ecdsa_public_key: Some(EcdsaPublicKeyResponse {
             public_key: vec![1; 32],
             chain_code: vec![2; 32],
@@ -1004,6 +1127,7 @@ fn state_equivalence() {
         first_scraped_block_number: BlockNumber::new(1_000_001),
         last_scraped_block_number: BlockNumber::new(1_000_000),
         last_erc20_scraped_block_number: BlockNumber::new(1_000_000),
+        last_deposit_with_subaccount_scraped_block_number: BlockNumber::new(1_000_000),
         last_observed_block_number: Some(BlockNumber::new(2_000_000)),
         events_to_mint: btreemap! {
             source("0xac493fb20c93bd3519a4a5d90ce72d69455c41c5b7e229dafee44344242ba467", 100) => ReceivedEthEvent {
@@ -1013,6 +1137,7 @@ fn state_equivalence() {
                 from_address: "0x9d68bd6F351bE62ed6dBEaE99d830BECD356Ed25".parse().unwrap(),
                 value: Wei::new(500_000_000_000_000_000),
                 principal: "lsywz-sl5vm-m6tct-7fhwt-6gdrw-4uzsg-ibknl-44d6d-a2oyt-c2cxu-7ae".parse().unwrap(),
+                subaccount: None,
             }.into()
         },
         minted_events: btreemap! {
@@ -1024,6 +1149,7 @@ fn state_equivalence() {
                     from_address: "0x9d68bd6F351bE62ed6dBEaE99d830BECD356Ed25".parse().unwrap(),
                     value: Wei::new(10_000_000_000_000_000),
                     principal: "2chl6-4hpzw-vqaaa-aaaaa-c".parse().unwrap(),
+                    subaccount: None,
                 }.into(),
                 mint_block_index: LedgerMintIndex::new(1),
                 erc20_contract_address: None,
@@ -1041,9 +1167,13 @@ fn state_equivalence() {
         erc20_balances: Default::default(),
         skipped_blocks: Default::default(),
         last_transaction_price_estimate: None,
+        last_l1_fee_estimate: None,
         ledger_suite_orchestrator_id: Some("2s5qh-7aaaa-aaaar-qadya-cai".parse().unwrap()),
         evm_rpc_id: Some("7hfb6-caaaa-aaaar-qadga-cai".parse().unwrap()),
+        evm_network_config: None,
         ckerc20_tokens,
+        erc20_balance_diff_accounting: Default::default(),
+        erc20_decimals: Default::default(),
     };
 
     assert_eq!(
@@ -1423,6 +1553,7 @@ mod eth_balance {
                     .total_unspent_tx_fees
                     .checked_add(Wei::from(65_945_724_957_000_u64))
                     .unwrap(),
+                total_tx_fees_deficit: eth_balance_before_withdrawal.total_tx_fees_deficit,
             }
         );
         assert_eq!(
@@ -1461,6 +1592,61 @@ mod eth_balance {
         );
     }
 
+    #[test]
+    fn should_record_tx_fees_deficit_when_l1_fee_exceeds_charged_fee() {
+        let mut state = initial_state();
+        apply_state_transition(
+            &mut state,
+            &EventType::AcceptedDeposit(received_eth_event()),
+        );
+        let eth_balance_before_withdrawal = state.eth_balance.clone();
+        let withdrawal_request = EthWithdrawalRequest {
+            withdrawal_amount: Wei::new(10_000_000_000_000_000),
+            destination: "0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34"
+                .parse()
+                .unwrap(),
+            ledger_burn_index: LedgerBurnIndex::new(0),
+            from: "k2t6j-2nvnp-4zjm3-25dtz-6xhaa-c7boj-5gayf-oj3xs-i43lp-teztq-6ae"
+                .parse()
+                .unwrap(),
+            from_subaccount: None,
+            created_at: Some(1699527697000000000),
+        };
+        let receipt = WithdrawalFlow {
+            l1_fee: Some(Wei::new(1_000_000_000)),
+            ..WithdrawalFlow::for_request(withdrawal_request.clone())
+        }
+        .apply(&mut state);
+
+        let tx_amount = state
+            .eth_transactions
+            .get_finalized_transaction(&withdrawal_request.ledger_burn_index)
+            .unwrap()
+            .transaction()
+            .amount;
+        let charged_tx_fee = withdrawal_request
+            .withdrawal_amount
+            .checked_sub(tx_amount)
+            .unwrap();
+        let effective_tx_fee = receipt.effective_transaction_fee();
+        let deficit = effective_tx_fee.checked_sub(charged_tx_fee).unwrap();
+        assert!(deficit > Wei::ZERO);
+        assert_eq!(
+            state.eth_balance,
+            EthBalance {
+                eth_balance: eth_balance_before_withdrawal
+                    .eth_balance
+                    .checked_sub(withdrawal_request.withdrawal_amount)
+                    .unwrap()
+                    .checked_sub(deficit)
+                    .unwrap(),
+                total_effective_tx_fees: effective_tx_fee,
+                total_unspent_tx_fees: Wei::ZERO,
+                total_tx_fees_deficit: deficit,
+            }
+        );
+    }
+
     #[test]
     fn should_update_after_successful_and_failed_erc20_withdrawal() {
         let mut state_before_withdrawal = initial_erc20_state();
@@ -1523,6 +1709,7 @@ mod eth_balance {
                     .total_unspent_tx_fees
                     .checked_add(unspent_tx_fee)
                     .unwrap(),
+                total_tx_fees_deficit: eth_balance_before_withdrawal.total_tx_fees_deficit,
             }
         );
         assert_eq!(
@@ -1561,6 +1748,7 @@ mod eth_balance {
         effective_gas_price: WeiPerGas,
         effective_gas_used: GasAmount,
         tx_status: TransactionStatus,
+        l1_fee: Option<Wei>,
     }
 
     impl WithdrawalFlow {
@@ -1576,6 +1764,7 @@ mod eth_balance {
                 effective_gas_price: WeiPerGas::ONE,
                 effective_gas_used: GasAmount::from(21_000_u32),
                 tx_status: TransactionStatus::Success,
+                l1_fee: None,
             }
         }
 
@@ -1596,6 +1785,7 @@ mod eth_balance {
                 self.tx_fee,
                 self.gas_limit,
                 EthereumNetwork::Sepolia,
+                Wei::ZERO,
             )
             .expect("BUG: failed to create transaction");
             apply_state_transition(
@@ -1630,6 +1820,7 @@ mod eth_balance {
                 gas_used: self.effective_gas_used,
                 status: self.tx_status,
                 transaction_hash: signed_tx.hash(),
+                l1_fee: self.l1_fee,
             };
             apply_state_transition(
                 state,
@@ -1659,6 +1850,8 @@ mod eth_balance {
                     .unwrap(),
                 ckerc20_token_symbol: "ckSepoliaUSDC".parse().unwrap(),
                 ckerc20_ledger_id: Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap(),
+                balance_diff_accounting: Some(false),
+                decimals: Some(6),
             }),
         );
     }
@@ -1788,6 +1981,8 @@ fn add_erc20_token(state: &mut State) {
                 .unwrap(),
             ckerc20_token_symbol: "ckSepoliaUSDC".parse().unwrap(),
             ckerc20_ledger_id: Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap(),
+            balance_diff_accounting: Some(false),
+            decimals: Some(6),
         }),
     );
 }
@@ -592,6 +592,7 @@ impl EthTransactions {
         &self,
         latest_transaction_count: TransactionCount,
         current_gas_fee: GasFeeEstimate,
+        current_l1_fee: Wei,
     ) -> Vec<Result<(LedgerBurnIndex, Eip1559TransactionRequest), ResubmitTransactionError>> {
         // If transaction count at block height H is c > 0, then transactions with nonces
         // 0, 1, ..., c - 1 were mined. If transaction count is 0, then no transactions were mined.
@@ -604,7 +605,7 @@ impl EthTransactions {
             .filter(|(nonce, _burn_index, _signed_tx)| *nonce >= &first_pending_tx_nonce)
         {
             let last_signed_tx = signed_tx.last().expect("BUG: empty sent transactions list");
-            match last_signed_tx.resubmit(current_gas_fee.clone()) {
+            match last_signed_tx.resubmit(current_gas_fee.clone(), current_l1_fee) {
                 Ok(Some(new_tx)) => {
                     transactions_to_resubmit.push(Ok((*burn_index, new_tx)));
                 }
@@ -1093,6 +1094,7 @@ impl EthTransactions {
 /// Creates an EIP-1559 transaction for the given withdrawal request.
 /// The transaction fees are paid by the beneficiary,
 /// meaning that the fees will be deducted from the withdrawal amount.
+/// The `l1_fee` is reserved on top of the gas fees for networks charging an L1 data fee.
 ///
 /// # Errors
 /// * `CreateTransactionError::InsufficientTransactionFee` if the ETH withdrawal amount does not cover the transaction fee.
@@ -1102,6 +1104,7 @@ pub fn create_transaction(
     gas_fee_estimate: GasFeeEstimate,
     gas_limit: GasAmount,
     ethereum_network: EthereumNetwork,
+    l1_fee: Wei,
 ) -> Result<Eip1559TransactionRequest, CreateTransactionError> {
     assert!(
         gas_limit > GasAmount::ZERO,
@@ -1110,7 +1113,10 @@ pub fn create_transaction(
     match withdrawal_request {
         WithdrawalRequest::CkEth(request) => {
             let transaction_price = gas_fee_estimate.to_price(gas_limit);
-            let max_transaction_fee = transaction_price.max_transaction_fee();
+            let max_transaction_fee = transaction_price
+                .max_transaction_fee()
+                .checked_add(l1_fee)
+                .unwrap_or(Wei::MAX);
             let tx_amount = match request.withdrawal_amount.checked_sub(max_transaction_fee) {
                 Some(tx_amount) => tx_amount,
                 None => {
@@ -1141,19 +1147,25 @@ pub fn create_transaction(
             // the transaction could still make it as long as `transaction.max_fee_per_gas >=  block.base_fee_per_gas`,
             // since the `priority_fee_per_gas` received by the miner is capped to (see https://eips.ethereum.org/EIPS/eip-1559)
             // min(transaction.max_priority_fee_per_gas, transaction.max_fee_per_gas - block.base_fee_per_gas).
-            let request_max_fee_per_gas = request
-                .max_transaction_fee
-                .into_wei_per_gas(gas_limit)
-                .expect("BUG: gas_limit should be non-zero");
+            // The L1 fee is reserved from the `max_transaction_fee` before allocating the rest to gas.
             let actual_min_max_fee_per_gas = gas_fee_estimate.min_max_fee_per_gas();
-            if actual_min_max_fee_per_gas > request_max_fee_per_gas {
-                return Err(CreateTransactionError::InsufficientTransactionFee {
+            let insufficient_transaction_fee =
+                || CreateTransactionError::InsufficientTransactionFee {
                     cketh_ledger_burn_index: request.cketh_ledger_burn_index,
                     allowed_max_transaction_fee: request.max_transaction_fee,
                     actual_max_transaction_fee: actual_min_max_fee_per_gas
                         .transaction_cost(gas_limit)
+                        .and_then(|gas_fee| gas_fee.checked_add(l1_fee))
                         .unwrap_or(Wei::MAX),
-                });
+                };
+            let request_max_fee_per_gas = request
+                .max_transaction_fee
+                .checked_sub(l1_fee)
+                .ok_or_else(insufficient_transaction_fee)?
+                .into_wei_per_gas(gas_limit)
+                .expect("BUG: gas_limit should be non-zero");
+            if actual_min_max_fee_per_gas > request_max_fee_per_gas {
+                return Err(insufficient_transaction_fee());
             }
             Ok(Eip1559TransactionRequest {
                 chain_id: ethereum_network.chain_id(),
@@ -325,6 +325,7 @@ mod eth_transactions {
                 gas_fee_estimate(),
                 estimate_gas_limit(&withdrawal_request),
                 EthereumNetwork::Sepolia,
+                Wei::ZERO,
             )
             .unwrap();
 
@@ -346,6 +347,7 @@ mod eth_transactions {
                 gas_fee_estimate(),
                 estimate_gas_limit(&withdrawal_request.clone().into()),
                 EthereumNetwork::Sepolia,
+                Wei::ZERO,
             )
             .unwrap();
 
@@ -397,6 +399,7 @@ mod eth_transactions {
                 gas_fee_estimate(),
                 estimate_gas_limit(&withdrawal_request.clone().into()),
                 EthereumNetwork::Sepolia,
+                Wei::ZERO,
             )
             .unwrap();
             let tx_mixing_payee_address_with_erc20_address = Eip1559TransactionRequest {
@@ -445,6 +448,7 @@ mod eth_transactions {
                     gas_fee_estimate(),
                     CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
                     EthereumNetwork::Sepolia,
+                    Wei::ZERO,
                 )
                 .unwrap();
 
@@ -705,8 +709,11 @@ mod eth_transactions {
         #[test]
         fn should_be_empty_when_no_sent_transactions() {
             let transactions = EthTransactions::new(TransactionNonce::ZERO);
-            let resubmitted_txs = transactions
-                .create_resubmit_transactions(TransactionCount::ZERO, gas_fee_estimate());
+            let resubmitted_txs = transactions.create_resubmit_transactions(
+                TransactionCount::ZERO,
+                gas_fee_estimate(),
+                Wei::ZERO,
+            );
 
             assert_eq!(resubmitted_txs, vec![]);
         }
@@ -737,6 +744,7 @@ mod eth_transactions {
                 let resubmitted_txs = transactions.create_resubmit_transactions(
                     TransactionCount::from((num_tx as u64) + 1),
                     higher_new_price.clone(),
+                    Wei::ZERO,
                 );
 
                 assert_eq!(resubmitted_txs, vec![]);
@@ -783,8 +791,11 @@ mod eth_transactions {
             );
 
             for updated_price in updated_prices {
-                let resubmitted_txs = transactions
-                    .create_resubmit_transactions(TransactionCount::from(10_u8), updated_price);
+                let resubmitted_txs = transactions.create_resubmit_transactions(
+                    TransactionCount::from(10_u8),
+                    updated_price,
+                    Wei::ZERO,
+                );
                 assert_eq!(resubmitted_txs, vec![]);
             }
         }
@@ -871,6 +882,7 @@ mod eth_transactions {
                 let resubmitted_txs = transactions.create_resubmit_transactions(
                     TransactionCount::ZERO,
                     test.price_at_tx_resubmission.clone(),
+                    Wei::ZERO,
                 );
                 let expected_resubmitted_tx_amount = match withdrawal_request {
                     WithdrawalRequest::CkEth(_) => initial_tx
@@ -923,8 +935,11 @@ mod eth_transactions {
                 ..initial_price
             };
 
-            let resubmitted_txs = transactions
-                .create_resubmit_transactions(TransactionCount::from(30_u8), higher_price.clone());
+            let resubmitted_txs = transactions.create_resubmit_transactions(
+                TransactionCount::from(30_u8),
+                higher_price.clone(),
+                Wei::ZERO,
+            );
             assert_eq!(resubmitted_txs.len(), 70);
             for (i, (withdrawal_id, resubmitted_tx)) in resubmitted_txs
                 .into_iter()
@@ -977,6 +992,7 @@ mod eth_transactions {
             let resubmitted_txs = transactions.create_resubmit_transactions(
                 TransactionCount::from(30_u8),
                 higher_base_fee_per_gas_price.clone(),
+                Wei::ZERO,
             );
             assert_eq!(resubmitted_txs, vec![]);
 
@@ -990,6 +1006,7 @@ mod eth_transactions {
             let resubmitted_txs = transactions.create_resubmit_transactions(
                 TransactionCount::from(30_u8),
                 higher_max_priority_fee_per_gas_price.clone(),
+                Wei::ZERO,
             );
             assert_eq!(resubmitted_txs.len(), 70);
             for (i, (withdrawal_id, resubmitted_tx)) in resubmitted_txs
@@ -1017,6 +1034,7 @@ mod eth_transactions {
             let resubmitted_txs = transactions.create_resubmit_transactions(
                 TransactionCount::from(30_u8),
                 too_high_price.clone(),
+                Wei::ZERO,
             );
             assert_eq!(
                 resubmitted_txs,
@@ -1212,8 +1230,11 @@ mod eth_transactions {
                 let _signed_tx =
                     create_and_record_signed_transaction(&mut transactions, created_tx.clone());
 
-                let resubmitted_txs_1 = transactions
-                    .create_resubmit_transactions(TransactionCount::ZERO, resubmit_price_1.clone());
+                let resubmitted_txs_1 = transactions.create_resubmit_transactions(
+                    TransactionCount::ZERO,
+                    resubmit_price_1.clone(),
+                    Wei::ZERO,
+                );
                 let resubmitted_tx1 = Eip1559TransactionRequest {
                     max_fee_per_gas: created_tx.max_fee_per_gas,
                     max_priority_fee_per_gas: WeiPerGas::from(3_u8),
@@ -1241,8 +1262,11 @@ mod eth_transactions {
                     max_priority_fee_per_gas: WeiPerGas::from(4_u8),
                     ..resubmit_price_1
                 };
-                let resubmitted_txs_2 = transactions
-                    .create_resubmit_transactions(TransactionCount::ZERO, resubmit_price_2.clone());
+                let resubmitted_txs_2 = transactions.create_resubmit_transactions(
+                    TransactionCount::ZERO,
+                    resubmit_price_2.clone(),
+                    Wei::ZERO,
+                );
                 let resubmitted_tx2 = Eip1559TransactionRequest {
                     max_fee_per_gas: created_tx.max_fee_per_gas,
                     max_priority_fee_per_gas: resubmit_price_2.max_priority_fee_per_gas,
@@ -2288,6 +2312,7 @@ mod create_transaction {
                 gas_fee.clone(),
                 gas_limit,
                 EthereumNetwork::Sepolia,
+                Wei::ZERO,
             );
             prop_assert_eq!(
                 result,
@@ -2310,6 +2335,7 @@ mod create_transaction {
                 gas_fee,
                 gas_limit,
                 EthereumNetwork::Sepolia,
+                Wei::ZERO,
             );
             prop_assert_eq!(
                 result,
@@ -2346,6 +2372,7 @@ mod create_transaction {
                 gas_fee,
                 gas_limit,
                 EthereumNetwork::Sepolia,
+                Wei::ZERO,
             );
 
             prop_assert_eq!(result, Ok(Eip1559TransactionRequest {
@@ -2400,6 +2427,7 @@ mod create_transaction {
                 gas_fee.clone(),
                 gas_limit,
                 EthereumNetwork::Mainnet,
+                Wei::ZERO,
             ).unwrap();
             let tx_max_fee_per_gas = result.max_fee_per_gas;
             let max_tx_fee = tx_max_fee_per_gas.transaction_cost(gas_limit).unwrap();
@@ -2420,6 +2448,93 @@ mod create_transaction {
         }
     }
 
+    #[test]
+    fn should_reserve_l1_fee() {
+        let gas_fee = gas_fee_estimate();
+        let gas_limit = CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT;
+        let max_gas_fee = gas_fee.clone().to_price(gas_limit).max_transaction_fee();
+        let l1_fee = Wei::from(1_000_000_000_u64);
+        let max_transaction_fee = max_gas_fee.checked_add(l1_fee).unwrap();
+        let cketh_ledger_burn_index = LedgerBurnIndex::new(15);
+
+        let cketh_withdrawal_request = EthWithdrawalRequest {
+            withdrawal_amount: max_transaction_fee.checked_add(Wei::ONE).unwrap(),
+            ..cketh_withdrawal_request_with_index(cketh_ledger_burn_index)
+        };
+        let tx = create_transaction(
+            &cketh_withdrawal_request.clone().into(),
+            TransactionNonce::TWO,
+            gas_fee.clone(),
+            gas_limit,
+            EthereumNetwork::Sepolia,
+            l1_fee,
+        )
+        .unwrap();
+        assert_eq!(tx.amount, Wei::ONE);
+
+        let cketh_withdrawal_request = EthWithdrawalRequest {
+            withdrawal_amount: max_gas_fee,
+            ..cketh_withdrawal_request
+        };
+        assert_eq!(
+            create_transaction(
+                &cketh_withdrawal_request.clone().into(),
+                TransactionNonce::TWO,
+                gas_fee.clone(),
+                gas_limit,
+                EthereumNetwork::Sepolia,
+                l1_fee,
+            ),
+            Err(CreateTransactionError::InsufficientTransactionFee {
+                cketh_ledger_burn_index,
+                allowed_max_transaction_fee: max_gas_fee,
+                actual_max_transaction_fee: max_transaction_fee,
+            })
+        );
+
+        let min_gas_fee = gas_fee
+            .min_max_fee_per_gas()
+            .transaction_cost(gas_limit)
+            .unwrap();
+        let ckerc20_withdrawal_request = Erc20WithdrawalRequest {
+            max_transaction_fee: min_gas_fee.checked_add(l1_fee).unwrap(),
+            ..ckerc20_withdrawal_request_with_index(
+                cketh_ledger_burn_index,
+                LedgerBurnIndex::new(2),
+            )
+        };
+        let tx = create_transaction(
+            &ckerc20_withdrawal_request.clone().into(),
+            TransactionNonce::TWO,
+            gas_fee.clone(),
+            gas_limit,
+            EthereumNetwork::Sepolia,
+            l1_fee,
+        )
+        .unwrap();
+        assert_eq!(tx.max_fee_per_gas, gas_fee.min_max_fee_per_gas());
+
+        let ckerc20_withdrawal_request = Erc20WithdrawalRequest {
+            max_transaction_fee: min_gas_fee,
+            ..ckerc20_withdrawal_request
+        };
+        assert_eq!(
+            create_transaction(
+                &ckerc20_withdrawal_request.into(),
+                TransactionNonce::TWO,
+                gas_fee,
+                gas_limit,
+                EthereumNetwork::Sepolia,
+                l1_fee,
+            ),
+            Err(CreateTransactionError::InsufficientTransactionFee {
+                cketh_ledger_burn_index,
+                allowed_max_transaction_fee: min_gas_fee,
+                actual_max_transaction_fee: min_gas_fee.checked_add(l1_fee).unwrap(),
+            })
+        );
+    }
+
     proptest! {
          #[test]
          fn should_encode_decode_transaction_call_data(to in arb_address(), value in arb_checked_amount_of()) {
@@ -2439,7 +2554,7 @@ mod create_transaction {
 
 mod withdrawal_flow {
     use super::arbitrary::{arb_checked_amount_of, arb_gas_fee_estimate, arb_withdrawal_request};
-    use crate::numeric::TransactionNonce;
+    use crate::numeric::{TransactionNonce, Wei};
     use crate::state::transactions::tests::sign_transaction;
     use crate::state::transactions::{create_transaction, EthTransactions, EthereumNetwork};
     use crate::withdraw::estimate_gas_limit;
@@ -2457,7 +2572,7 @@ mod withdrawal_flow {
         });
 
         proptest!(|(gas_fee_estimate in arb_gas_fee_estimate(), transaction_count in arb_checked_amount_of())| {
-            let resubmit_txs = wrapped_txs.borrow().create_resubmit_transactions(transaction_count, gas_fee_estimate.clone());
+            let resubmit_txs = wrapped_txs.borrow().create_resubmit_transactions(transaction_count, gas_fee_estimate.clone(), Wei::ZERO);
             for (_withdrawal_id, resubmit_tx) in resubmit_txs.into_iter().flatten() {
                 wrapped_txs.borrow_mut().record_resubmit_transaction(resubmit_tx);
             }
@@ -2471,6 +2586,7 @@ mod withdrawal_flow {
                     gas_fee_estimate.clone(),
                     estimate_gas_limit(&request),
                     EthereumNetwork::Sepolia,
+                    Wei::ZERO,
                 ){
                     wrapped_txs.borrow_mut().record_created_transaction(request.cketh_ledger_burn_index(), created_tx);
                 }
@@ -2854,6 +2970,7 @@ fn create_and_record_transaction<R: Into<WithdrawalRequest>>(
         gas_fee_estimate,
         estimate_gas_limit(&withdrawal_request),
         EthereumNetwork::Sepolia,
+        Wei::ZERO,
     )
     .expect("failed to create transaction");
     transactions.record_created_transaction(withdrawal_request.cketh_ledger_burn_index(), tx);
@@ -2906,6 +3023,7 @@ fn transaction_receipt(
         gas_used: signed_tx.transaction().gas_limit,
         status,
         transaction_hash: signed_tx.hash(),
+        l1_fee: None,
     }
 }
 
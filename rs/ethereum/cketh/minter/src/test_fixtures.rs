@@ -166,6 +166,7 @@ pub mod arb {
                         gas_used,
                         status,
                         transaction_hash,
+                        l1_fee: None,
                     }
                 },
             )
@@ -48,7 +48,7 @@ fn deserialize_json_reply() {
 }
 
 mod eth_get_logs {
-    use crate::eth_logs::{ReceivedErc20Event, ReceivedEthEvent, ReceivedEvent};
+    use crate::eth_logs::{LedgerSubaccount, ReceivedErc20Event, ReceivedEthEvent, ReceivedEvent};
     use crate::eth_rpc::LogEntry;
     use crate::numeric::{BlockNumber, Erc20Value, LogIndex, Wei};
     use candid::Principal;
@@ -106,6 +106,16 @@ mod eth_get_logs {
         assert_eq!(topic, RECEIVED_ETH_EVENT_TOPIC)
     }
 
+    #[test]
+    fn should_have_correct_deposit_with_subaccount_topic() {
+        use crate::deposit::RECEIVED_ETH_OR_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC;
+
+        //must match event signature in DepositHelperWithSubaccount.sol
+        let event_signature = "ReceivedEthOrErc20(address,address,uint256,bytes32,bytes32)";
+        let topic = Keccak256::hash(event_signature);
+        assert_eq!(topic, RECEIVED_ETH_OR_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC)
+    }
+
     #[test]
     fn should_parse_received_eth_event() {
         let event = r#"{
@@ -136,6 +146,7 @@ mod eth_get_logs {
                 .unwrap(),
             value: Wei::from(10_000_000_000_000_000_u128),
             principal: Principal::from_str("2chl6-4hpzw-vqaaa-aaaaa-c").unwrap(),
+            subaccount: None,
         }
         .into();
 
@@ -179,12 +190,134 @@ mod eth_get_logs {
             erc20_contract_address: "0x7439e9bb6d8a84dd3a23fe621a30f95403f87fb9"
                 .parse()
                 .unwrap(),
+            subaccount: None,
         }
         .into();
 
         assert_eq!(parsed_event, expected_event);
     }
 
+    #[test]
+    fn should_parse_received_erc20_event_with_subaccount() {
+        let event = r#"{
+            "address": "0x2d39863d30716aaf2b7fffd85dd03dda2bfc2e38",
+            "topics": [
+                "0x918adbebdb8f3b36fc337ab76df10b147b2def5c9dd62cb3456d9aeca40e0b07",
+                "0x0000000000000000000000007439e9bb6d8a84dd3a23fe621a30f95403f87fb9",
+                "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                "0x1d9facb184cbe453de4841b6b9d9cc95bfc065344e485789b550544529020000"
+            ],
+            "data": "0x0000000000000000000000000000000000000000000000008ac7230489e80000ff00000000000000000000000000000000000000000000000000000000000001",
+            "blockNumber": "0x5146a4",
+            "transactionHash": "0x44d8e93a8f4bbc89ad35fc4fbbdb12cb597b4832da09c0b2300777be180fde87",
+            "transactionIndex": "0x22",
+            "blockHash": "0x0cbfb260e2e589ef110e63314279eb3ef2e307e46fa5409f08c101976858f80a",
+            "logIndex": "0x27",
+            "removed": false
+        }"#;
+        let parsed_event =
+            ReceivedEvent::try_from(serde_json::from_str::<LogEntry>(event).unwrap()).unwrap();
+        let mut expected_subaccount = [0_u8; 32];
+        expected_subaccount[0] = 0xff;
+        expected_subaccount[31] = 0x01;
+        let expected_event = ReceivedErc20Event {
+            transaction_hash: "0x44d8e93a8f4bbc89ad35fc4fbbdb12cb597b4832da09c0b2300777be180fde87"
+                .parse()
+                .unwrap(),
+            block_number: BlockNumber::new(5326500),
+            log_index: LogIndex::from(39_u8),
+            from_address: "0xdd2851Cdd40aE6536831558DD46db62fAc7A844d"
+                .parse()
+                .unwrap(),
+            value: Erc20Value::from(10_000_000_000_000_000_000_u128),
+            principal: Principal::from_str(
+                "hkroy-sm7vs-yyjs7-ekppe-qqnwx-hm4zf-n7ybs-titsi-k6e3k-ucuiu-uqe",
+            )
+            .unwrap(),
+            erc20_contract_address: "0x7439e9bb6d8a84dd3a23fe621a30f95403f87fb9"
+                .parse()
+                .unwrap(),
+            subaccount: LedgerSubaccount::from_bytes(expected_subaccount),
+        }
+        .into();
+
+        assert_eq!(parsed_event, expected_event);
+        assert_eq!(
+            parsed_event.beneficiary().subaccount,
+            Some(expected_subaccount)
+        );
+    }
+
+    #[test]
+    fn should_parse_received_eth_event_with_default_subaccount() {
+        let event = r#"{
+            "address": "0x2d39863d30716aaf2b7fffd85dd03dda2bfc2e38",
+            "topics": [
+                "0x918adbebdb8f3b36fc337ab76df10b147b2def5c9dd62cb3456d9aeca40e0b07",
+                "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                "0x09efcdab00000000000100000000000000000000000000000000000000000000"
+            ],
+            "data": "0x000000000000000000000000000000000000000000000000002386f26fc100000000000000000000000000000000000000000000000000000000000000000000",
+            "blockNumber": "0x3ca487",
+            "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+            "transactionIndex": "0x22",
+            "blockHash": "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c",
+            "logIndex": "0x27",
+            "removed": false
+        }"#;
+        let parsed_event =
+            ReceivedEvent::try_from(serde_json::from_str::<LogEntry>(event).unwrap()).unwrap();
+        let expected_event = ReceivedEthEvent {
+            transaction_hash: "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3"
+                .parse()
+                .unwrap(),
+            block_number: BlockNumber::new(3974279),
+            log_index: LogIndex::from(39_u8),
+            from_address: "0xdd2851cdd40ae6536831558dd46db62fac7a844d"
+                .parse()
+                .unwrap(),
+            value: Wei::from(10_000_000_000_000_000_u128),
+            principal: Principal::from_str("2chl6-4hpzw-vqaaa-aaaaa-c").unwrap(),
+            subaccount: None,
+        }
+        .into();
+
+        assert_eq!(parsed_event, expected_event);
+    }
+
+    #[test]
+    fn should_not_parse_event_with_subaccount_with_invalid_data_length() {
+        use crate::eth_logs::{EventSourceError, ReceivedEventError};
+        let event = r#"{
+            "address": "0x2d39863d30716aaf2b7fffd85dd03dda2bfc2e38",
+            "topics": [
+                "0x918adbebdb8f3b36fc337ab76df10b147b2def5c9dd62cb3456d9aeca40e0b07",
+                "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                "0x09efcdab00000000000100000000000000000000000000000000000000000000"
+            ],
+            "data": "0x000000000000000000000000000000000000000000000000002386f26fc10000",
+            "blockNumber": "0x3ca487",
+            "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+            "transactionIndex": "0x22",
+            "blockHash": "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c",
+            "logIndex": "0x27",
+            "removed": false
+        }"#;
+
+        let parsed_event =
+            ReceivedEvent::try_from(serde_json::from_str::<LogEntry>(event).unwrap());
+
+        assert_matches::assert_matches!(
+            parsed_event,
+            Err(ReceivedEventError::InvalidEventSource {
+                error: EventSourceError::InvalidEvent(msg),
+                ..
+            }) if msg.starts_with("Invalid data length; expected 64 bytes")
+        );
+    }
+
     #[test]
     fn should_not_parse_removed_event() {
         use crate::eth_logs::{EventSource, EventSourceError, ReceivedEventError};
@@ -220,6 +353,96 @@ mod eth_get_logs {
         });
         assert_eq!(parsed_event, expected_error);
     }
+
+    #[test]
+    fn should_have_correct_erc20_transfer_topic() {
+        use crate::deposit::ERC20_TRANSFER_EVENT_TOPIC;
+
+        //must match event signature in the ERC-20 standard
+        let event_signature = "Transfer(address,address,uint256)";
+        let topic = Keccak256::hash(event_signature);
+        assert_eq!(topic, ERC20_TRANSFER_EVENT_TOPIC)
+    }
+
+    #[test]
+    fn should_have_correct_erc20_balance_of_selector() {
+        use crate::deposit::ERC20_BALANCE_OF_SELECTOR;
+
+        //must match function signature in the ERC-20 standard
+        let function_signature = "balanceOf(address)";
+        let hash = Keccak256::hash(function_signature);
+        assert_eq!(hash[..4], ERC20_BALANCE_OF_SELECTOR)
+    }
+
+    #[test]
+    fn should_parse_erc20_transfer_event() {
+        use crate::eth_logs::Erc20Transfer;
+
+        let event = r#"{
+            "address": "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238",
+            "topics": [
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                "0x000000000000000000000000b44b5e756a894775fc32eddf3314bb1b1944dc34"
+            ],
+            "data": "0x00000000000000000000000000000000000000000000000000000000004c4b40",
+            "blockNumber": "0x5359b9",
+            "transactionHash": "0x44d8e93a8f4bbc89ad35fc4fbbdb12cb597b4832da09c0b2300777be180fde87",
+            "transactionIndex": "0x22",
+            "blockHash": "0x0cbfb260a6e7a3b2bd3ea35b4e5a3c4d7d3bf6d0d9b6e1f7fd0b4a18b8b8a3f1",
+            "logIndex": "0x1f",
+            "removed": false
+        }"#;
+        let parsed_event =
+            Erc20Transfer::try_from(serde_json::from_str::<LogEntry>(event).unwrap()).unwrap();
+        let expected_event = Erc20Transfer {
+            transaction_hash: "0x44d8e93a8f4bbc89ad35fc4fbbdb12cb597b4832da09c0b2300777be180fde87"
+                .parse()
+                .unwrap(),
+            block_number: BlockNumber::new(5_462_457),
+            log_index: LogIndex::from(31_u8),
+            erc20_contract_address: "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238"
+                .parse()
+                .unwrap(),
+            from_address: "0xdd2851cdd40ae6536831558dd46db62fac7a844d"
+                .parse()
+                .unwrap(),
+            to_address: "0xb44b5e756a894775fc32eddf3314bb1b1944dc34"
+                .parse()
+                .unwrap(),
+            value: Erc20Value::from(5_000_000_u64),
+        };
+
+        assert_eq!(parsed_event, expected_event);
+    }
+
+    #[test]
+    fn should_not_parse_erc20_transfer_event_with_wrong_topic() {
+        use crate::eth_logs::Erc20Transfer;
+
+        let event = r#"{
+            "address": "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238",
+            "topics": [
+                "0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925",
+                "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                "0x000000000000000000000000b44b5e756a894775fc32eddf3314bb1b1944dc34"
+            ],
+            "data": "0x00000000000000000000000000000000000000000000000000000000004c4b40",
+            "blockNumber": "0x5359b9",
+            "transactionHash": "0x44d8e93a8f4bbc89ad35fc4fbbdb12cb597b4832da09c0b2300777be180fde87",
+            "transactionIndex": "0x22",
+            "blockHash": "0x0cbfb260a6e7a3b2bd3ea35b4e5a3c4d7d3bf6d0d9b6e1f7fd0b4a18b8b8a3f1",
+            "logIndex": "0x1f",
+            "removed": false
+        }"#;
+        let parsed_event =
+            Erc20Transfer::try_from(serde_json::from_str::<LogEntry>(event).unwrap());
+
+        assert_matches::assert_matches!(
+            parsed_event,
+            Err(msg) if msg.starts_with("Expected 3 topics for a Transfer event")
+        );
+    }
 }
 
 #[test]
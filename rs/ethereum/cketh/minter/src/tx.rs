@@ -1,13 +1,16 @@
 #[cfg(test)]
 mod tests;
 
-use crate::eth_rpc::{BlockSpec, BlockTag, FeeHistory, FeeHistoryParams, Hash, Quantity};
+use crate::eth_rpc::{BlockSpec, BlockTag, Data, FeeHistory, FeeHistoryParams, Hash, Quantity};
+use crate::eth_rpc_client::requests::{EthCallParams, TransactionCall};
 use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
 use crate::eth_rpc_client::{EthRpcClient, MultiCallError};
 use crate::guard::TimerGuard;
 use crate::logs::{DEBUG, INFO};
 use crate::numeric::{BlockNumber, GasAmount, TransactionNonce, Wei, WeiPerGas};
-use crate::state::{lazy_call_ecdsa_public_key, mutate_state, read_state, TaskType};
+use crate::state::{
+    lazy_call_ecdsa_public_key, mutate_state, read_state, FeeModel, State, TaskType,
+};
 use ethnum::u256;
 use ic_canister_log::log;
 use ic_crypto_secp256k1::RecoveryId;
@@ -146,9 +149,12 @@ pub enum ResubmitTransactionError {
 }
 
 impl SignedTransactionRequest {
+    /// Resubmit the transaction with the new gas fee, while reserving `l1_fee` to pay
+    /// for the L1 data fee that some networks charge on top of the gas fees.
     pub fn resubmit(
         &self,
         new_gas_fee: GasFeeEstimate,
+        l1_fee: Wei,
     ) -> Result<Option<Eip1559TransactionRequest>, ResubmitTransactionError> {
         let transaction_request = self.transaction.transaction();
         let last_tx_price = transaction_request.transaction_price();
@@ -159,15 +165,19 @@ impl SignedTransactionRequest {
             return Ok(None);
         }
 
-        if new_tx_price.max_transaction_fee() > self.resubmission.allowed_max_transaction_fee() {
+        let new_max_transaction_fee = new_tx_price
+            .max_transaction_fee()
+            .checked_add(l1_fee)
+            .unwrap_or(Wei::MAX);
+        if new_max_transaction_fee > self.resubmission.allowed_max_transaction_fee() {
             return Err(ResubmitTransactionError::InsufficientTransactionFee {
                 allowed_max_transaction_fee: self.resubmission.allowed_max_transaction_fee(),
-                actual_max_transaction_fee: new_tx_price.max_transaction_fee(),
+                actual_max_transaction_fee: new_max_transaction_fee,
             });
         }
         let new_amount = match self.resubmission {
             ResubmissionStrategy::ReduceEthAmount { withdrawal_amount } => {
-                withdrawal_amount.checked_sub(new_tx_price.max_transaction_fee())
+                withdrawal_amount.checked_sub(new_max_transaction_fee)
                     .expect("BUG: withdrawal_amount covers new transaction fee because it was checked before")
             }
             ResubmissionStrategy::GuaranteeEthAmount { .. } => transaction_request.amount,
@@ -622,22 +632,24 @@ pub async fn lazy_refresh_gas_fee_estimate() -> Option<GasFeeEstimate> {
             }
         };
 
-        let gas_fee_estimate = match estimate_transaction_fee(&fee_history) {
-            Ok(estimate) => {
-                mutate_state(|s| {
-                    s.last_transaction_price_estimate =
-                        Some((ic_cdk::api::time(), estimate.clone()));
-                });
-                estimate
-            }
-            Err(e) => {
-                log!(
-                    INFO,
-                    "[refresh_gas_fee_estimate]: Failed estimating gas fee: {e:?}",
-                );
-                return None;
-            }
-        };
+        let min_max_priority_fee_per_gas = read_state(State::min_max_priority_fee_per_gas);
+        let gas_fee_estimate =
+            match estimate_transaction_fee(&fee_history, min_max_priority_fee_per_gas) {
+                Ok(estimate) => {
+                    mutate_state(|s| {
+                        s.last_transaction_price_estimate =
+                            Some((ic_cdk::api::time(), estimate.clone()));
+                    });
+                    estimate
+                }
+                Err(e) => {
+                    log!(
+                        INFO,
+                        "[refresh_gas_fee_estimate]: Failed estimating gas fee: {e:?}",
+                    );
+                    return None;
+                }
+            };
         log!(
             INFO,
             "[refresh_gas_fee_estimate]: Estimated transaction fee: {:?}",
@@ -666,24 +678,117 @@ pub async fn lazy_refresh_gas_fee_estimate() -> Option<GasFeeEstimate> {
         _ => do_refresh().await,
     }
 }
+/// Upper bound on the size in bytes of an unsigned withdrawal transaction.
+///
+/// An unsigned EIP-1559 transaction is its type byte followed by the RLP encoding of its fields,
+/// each numeric field taking at most 33 bytes. The 68 bytes of call data of an ERC-20 `transfer`
+/// make ckERC20 withdrawals the largest ones, with at most 238 bytes.
+pub const MAX_UNSIGNED_WITHDRAWAL_TRANSACTION_SIZE: u64 = 240;
+
+/// Factor applied to the L1 fee upper bound returned by the OP-stack `GasPriceOracle`.
+///
+/// The L1 base fee can increase by 12.5% per L1 block, and the estimate may be up to a minute old
+/// when a withdrawal is requested. Tripling the upper bound covers 9 consecutive maximal increases,
+/// i.e., almost 2 minutes of L1 blocks, before the minter has to pay the difference.
+const L1_FEE_ESTIMATE_MULTIPLIER: u8 = 3;
+
+/// Selector of `getL1FeeUpperBound(uint256)` of the OP-stack `GasPriceOracle`.
+const GET_L1_FEE_UPPER_BOUND_SELECTOR: [u8; 4] = [0xf1, 0xc7, 0xa5, 0x8b];
+
+/// Estimate the L1 data fee that the network charges on top of the gas fees of a withdrawal transaction.
+///
+/// The fee is zero for networks using the EIP-1559 fee model. On OP-stack networks, the `GasPriceOracle`
+/// returns an upper bound on the L1 data fee of a transaction of [`MAX_UNSIGNED_WITHDRAWAL_TRANSACTION_SIZE`] bytes,
+/// which is multiplied by [`L1_FEE_ESTIMATE_MULTIPLIER`] since the L1 base fee may increase until the
+/// transaction is included, similarly to the max fee per gas in [`estimate_transaction_fee`].
+/// If the L1 fee still exceeds the reserved amount, the minter pays the difference, which is
+/// recorded in [`crate::state::EthBalance::total_tx_fees_deficit`].
+pub async fn lazy_refresh_l1_fee_estimate() -> Option<Wei> {
+    const MAX_AGE_NS: u64 = 60_000_000_000_u64; //60 seconds
+
+    let gas_price_oracle_address = match read_state(State::fee_model) {
+        FeeModel::Eip1559 => return Some(Wei::ZERO),
+        FeeModel::OpStack {
+            gas_price_oracle_address,
+        } => gas_price_oracle_address,
+    };
+    let now_ns = ic_cdk::api::time();
+    if let Some((last_estimate_timestamp_ns, estimate)) = read_state(|s| s.last_l1_fee_estimate) {
+        if now_ns < last_estimate_timestamp_ns.saturating_add(MAX_AGE_NS) {
+            return Some(estimate);
+        }
+    }
+
+    let mut data = GET_L1_FEE_UPPER_BOUND_SELECTOR.to_vec();
+    data.extend_from_slice(&u256::from(MAX_UNSIGNED_WITHDRAWAL_TRANSACTION_SIZE).to_be_bytes());
+    let results = read_state(EthRpcClient::from_state)
+        .eth_call(EthCallParams {
+            transaction: TransactionCall {
+                to: gas_price_oracle_address,
+                data: Data(data),
+            },
+            block: BlockSpec::Tag(BlockTag::Latest),
+        })
+        .await;
+    let decode_fee = |result: &Data| {
+        <[u8; 32]>::try_from(result.0.as_slice())
+            .ok()
+            .map(Wei::from_be_bytes)
+    };
+    let l1_fee_upper_bound = match results.reduce_with_max_by_key(decode_fee) {
+        Ok(result) => match decode_fee(&result) {
+            Some(fee) => fee,
+            None => {
+                log!(
+                    INFO,
+                    "[refresh_l1_fee_estimate]: Invalid result of getL1FeeUpperBound: {result:?}",
+                );
+                return None;
+            }
+        },
+        Err(e) => {
+            log!(
+                INFO,
+                "[refresh_l1_fee_estimate]: Failed retrieving L1 fee upper bound: {e:?}",
+            );
+            return None;
+        }
+    };
+    let estimate = l1_fee_upper_bound
+        .checked_mul(L1_FEE_ESTIMATE_MULTIPLIER)
+        .unwrap_or(Wei::MAX);
+    log!(
+        INFO,
+        "[refresh_l1_fee_estimate]: Estimated L1 fee: {:?}",
+        estimate,
+    );
+    mutate_state(|s| s.last_l1_fee_estimate = Some((now_ns, estimate)));
+    Some(estimate)
+}
+
 #[derive(Eq, PartialEq, Debug)]
 pub enum TransactionFeeEstimationError {
     InvalidFeeHistory(String),
     Overflow(String),
 }
 
+/// Lower bound on the max priority fee per gas on the Ethereum mainnet and Sepolia.
+///
+/// Average value between the `minSuggestedMaxPriorityFeePerGas`
+/// used by Metamask, see
+/// <https://github.com/MetaMask/core/blob/f5a4f52e17f407c6411e4ef9bd6685aab184b91d/packages/gas-fee-controller/src/fetchGasEstimatesViaEthFeeHistory/calculateGasFeeEstimatesForPriorityLevels.ts#L14>
+pub const MIN_MAX_PRIORITY_FEE_PER_GAS: WeiPerGas = WeiPerGas::new(1_500_000_000); //1.5 gwei
+
 /// Estimate the transaction fee based on the fee history.
 ///
 /// From the fee history, the current base fee per gas and the max priority fee per gas are determined.
+/// The max priority fee per gas is at least `min_max_priority_fee_per_gas`, which is much lower on layer-2 networks.
 /// Then, the max fee per gas is computed as `2 * base_fee_per_gas + max_priority_fee_per_gas` to ensure that
 /// the estimate remains valid for the next few blocks, see `<https://www.blocknative.com/blog/eip-1559-fees>`.
 pub fn estimate_transaction_fee(
     fee_history: &FeeHistory,
+    min_max_priority_fee_per_gas: WeiPerGas,
 ) -> Result<GasFeeEstimate, TransactionFeeEstimationError> {
-    // average value between the `minSuggestedMaxPriorityFeePerGas`
-    // used by Metamask, see
-    // https://github.com/MetaMask/core/blob/f5a4f52e17f407c6411e4ef9bd6685aab184b91d/packages/gas-fee-controller/src/fetchGasEstimatesViaEthFeeHistory/calculateGasFeeEstimatesForPriorityLevels.ts#L14
-    const MIN_MAX_PRIORITY_FEE_PER_GAS: WeiPerGas = WeiPerGas::new(1_500_000_000); //1.5 gwei
     let base_fee_per_gas_next_block = *fee_history.base_fee_per_gas.last().ok_or(
         TransactionFeeEstimationError::InvalidFeeHistory(
             "base_fee_per_gas should not be empty to be able to evaluate transaction price"
@@ -696,7 +801,7 @@ pub fn estimate_transaction_fee(
             **median(&mut rewards).ok_or(TransactionFeeEstimationError::InvalidFeeHistory(
                 "should be non-empty with rewards of the last 5 blocks".to_string(),
             ))?;
-        historic_max_priority_fee_per_gas.max(MIN_MAX_PRIORITY_FEE_PER_GAS)
+        historic_max_priority_fee_per_gas.max(min_max_priority_fee_per_gas)
     };
     let gas_fee_estimate = GasFeeEstimate {
         base_fee_per_gas: base_fee_per_gas_next_block,
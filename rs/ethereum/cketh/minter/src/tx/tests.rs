@@ -4,7 +4,10 @@ use proptest::strategy::Strategy;
 mod estimate_transaction_price {
     use crate::eth_rpc::FeeHistory;
     use crate::numeric::{BlockNumber, WeiPerGas};
-    use crate::tx::{estimate_transaction_fee, GasFeeEstimate, TransactionFeeEstimationError};
+    use crate::tx::{
+        estimate_transaction_fee, GasFeeEstimate, TransactionFeeEstimationError,
+        MIN_MAX_PRIORITY_FEE_PER_GAS,
+    };
     use assert_matches::assert_matches;
     use proptest::collection::vec;
     use proptest::prelude::any;
@@ -26,7 +29,7 @@ mod estimate_transaction_price {
             };
             let fee_history = fee_history(base_fee_per_gas, reward);
 
-            let result = estimate_transaction_fee(&fee_history);
+            let result = estimate_transaction_fee(&fee_history, MIN_MAX_PRIORITY_FEE_PER_GAS);
 
             prop_assert_eq!(
                 result,
@@ -52,7 +55,7 @@ mod estimate_transaction_price {
             vec![0_u8, 0, 0, 0, 0],
         );
 
-        let result = estimate_transaction_fee(&fee_history);
+        let result = estimate_transaction_fee(&fee_history, MIN_MAX_PRIORITY_FEE_PER_GAS);
 
         assert_matches!(result, Err(TransactionFeeEstimationError::Overflow(_)));
     }
@@ -60,7 +63,7 @@ mod estimate_transaction_price {
     #[test]
     fn should_fail_when_max_priority_fee_per_gas_overflows() {
         let fee_history = fee_history(vec![0_u8, 0, 0, 0, 0, 1], [WeiPerGas::MAX; 5].to_vec());
-        let result = estimate_transaction_fee(&fee_history);
+        let result = estimate_transaction_fee(&fee_history, MIN_MAX_PRIORITY_FEE_PER_GAS);
         assert_matches!(result, Err(TransactionFeeEstimationError::Overflow(_)));
     }
 
@@ -4,14 +4,14 @@ use crate::eth_rpc_client::EthRpcClient;
 use crate::eth_rpc_client::MultiCallError;
 use crate::guard::TimerGuard;
 use crate::logs::{DEBUG, INFO};
-use crate::numeric::{GasAmount, LedgerBurnIndex, LedgerMintIndex, TransactionCount};
+use crate::numeric::{GasAmount, LedgerBurnIndex, LedgerMintIndex, TransactionCount, Wei};
 use crate::state::audit::{process_event, EventType};
 use crate::state::transactions::{
     create_transaction, CreateTransactionError, Reimbursed, ReimbursementIndex,
     ReimbursementRequest, WithdrawalRequest,
 };
 use crate::state::{mutate_state, read_state, State, TaskType};
-use crate::tx::{lazy_refresh_gas_fee_estimate, GasFeeEstimate};
+use crate::tx::{lazy_refresh_gas_fee_estimate, lazy_refresh_l1_fee_estimate, GasFeeEstimate};
 use candid::Nat;
 use futures::future::join_all;
 use ic_canister_log::log;
@@ -160,9 +160,20 @@ pub async fn process_retrieve_eth_requests() {
         }
     };
 
+    let l1_fee_estimate = match lazy_refresh_l1_fee_estimate().await {
+        Some(l1_fee_estimate) => l1_fee_estimate,
+        None => {
+            log!(
+                INFO,
+                "Failed retrieving L1 fee estimate to process ETH requests",
+            );
+            return;
+        }
+    };
+
     let latest_transaction_count = latest_transaction_count().await;
-    resubmit_transactions_batch(latest_transaction_count, &gas_fee_estimate).await;
-    create_transactions_batch(gas_fee_estimate);
+    resubmit_transactions_batch(latest_transaction_count, &gas_fee_estimate, l1_fee_estimate).await;
+    create_transactions_batch(gas_fee_estimate, l1_fee_estimate);
     sign_transactions_batch().await;
     send_transactions_batch(latest_transaction_count).await;
     finalize_transactions_batch().await;
@@ -190,6 +201,7 @@ async fn latest_transaction_count() -> Option<TransactionCount> {
 async fn resubmit_transactions_batch(
     latest_transaction_count: Option<TransactionCount>,
     gas_fee_estimate: &GasFeeEstimate,
+    l1_fee_estimate: Wei,
 ) {
     if read_state(|s| s.eth_transactions.is_sent_tx_empty()) {
         return;
@@ -201,8 +213,11 @@ async fn resubmit_transactions_batch(
         }
     };
     let transactions_to_resubmit = read_state(|s| {
-        s.eth_transactions
-            .create_resubmit_transactions(latest_transaction_count, gas_fee_estimate.clone())
+        s.eth_transactions.create_resubmit_transactions(
+            latest_transaction_count,
+            gas_fee_estimate.clone(),
+            l1_fee_estimate,
+        )
     });
     for result in transactions_to_resubmit {
         match result {
@@ -228,7 +243,7 @@ async fn resubmit_transactions_batch(
     }
 }
 
-fn create_transactions_batch(gas_fee_estimate: GasFeeEstimate) {
+fn create_transactions_batch(gas_fee_estimate: GasFeeEstimate, l1_fee_estimate: Wei) {
     for request in read_state(|s| {
         s.eth_transactions
             .withdrawal_requests_batch(WITHDRAWAL_REQUESTS_BATCH_SIZE)
@@ -236,13 +251,14 @@ fn create_transactions_batch(gas_fee_estimate: GasFeeEstimate) {
         log!(DEBUG, "[create_transactions_batch]: processing {request:?}",);
         let ethereum_network = read_state(State::ethereum_network);
         let nonce = read_state(|s| s.eth_transactions.next_transaction_nonce());
-        let gas_limit = estimate_gas_limit(&request);
+        let gas_limit = read_state(|s| s.withdrawal_gas_limit(&request));
         match create_transaction(
             &request,
             nonce,
             gas_fee_estimate.clone(),
             gas_limit,
             ethereum_network,
+            l1_fee_estimate,
         ) {
             Ok(transaction) => {
                 log!(
@@ -275,6 +291,9 @@ fn create_transactions_batch(gas_fee_estimate: GasFeeEstimate) {
     }
 }
 
+/// Gas limit of a withdrawal transaction on the Ethereum mainnet and Sepolia.
+///
+/// Other EVM networks configure their own gas limits, see [`State::withdrawal_gas_limit`].
 pub fn estimate_gas_limit(withdrawal_request: &WithdrawalRequest) -> GasAmount {
     match withdrawal_request {
         WithdrawalRequest::CkEth(_) => CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
@@ -52,6 +52,8 @@ fn should_refuse_to_add_ckerc20_token_from_unauthorized_principal() {
             address: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
             ckerc20_token_symbol: "ckUSDC".to_string(),
             ckerc20_ledger_id: "mxzaz-hqaaa-aaaar-qaada-cai".parse().unwrap(),
+            balance_diff_accounting: None,
+            decimals: None,
         }
     }
 }
@@ -80,6 +82,8 @@ fn should_add_ckusdc_and_ckusdt_to_minter_via_orchestrator() {
                 address: format_ethereum_address_to_eip_55(&token.contract.address),
                 ckerc20_token_symbol: token.ledger_init_arg.token_symbol,
                 ckerc20_ledger_id: new_ledger_id,
+                balance_diff_accounting: Some(false),
+                decimals: Some(token.ledger_init_arg.decimals),
             }]);
     }
 }
@@ -126,6 +130,8 @@ fn should_retry_to_add_usdc_when_minter_stopped() {
             address: format_ethereum_address_to_eip_55(&usdc.contract.address),
             ckerc20_token_symbol: usdc.ledger_init_arg.token_symbol,
             ckerc20_ledger_id: new_ledger_id,
+            balance_diff_accounting: Some(false),
+            decimals: Some(usdc.ledger_init_arg.decimals),
         }]);
 }
 
@@ -801,6 +807,7 @@ mod withdraw_erc20 {
                             status: transaction_status.clone(),
                             transaction_hash: DEFAULT_CKERC20_WITHDRAWAL_TRANSACTION_HASH
                                 .to_string(),
+                            l1_fee: None,
                         },
                     },
                 ]);
@@ -1108,6 +1115,7 @@ mod withdraw_erc20 {
                         gas_used: Nat::from(21_000_u64),
                         status: TransactionStatus::Success,
                         transaction_hash: format!("{:?}", resubmitted_tx_hash),
+                        l1_fee: None,
                     },
                 },
             ]);
@@ -1356,6 +1364,7 @@ fn should_deposit_cketh_and_ckerc20_when_ledger_temporary_offline() {
                 from_address: format_ethereum_address_to_eip_55(DEFAULT_DEPOSIT_FROM_ADDRESS),
                 value: CKETH_MINIMUM_WITHDRAWAL_AMOUNT.into(),
                 principal: caller,
+                subaccount: None,
             },
             EventPayload::AcceptedErc20Deposit {
                 transaction_hash: DEFAULT_ERC20_DEPOSIT_TRANSACTION_HASH.to_string(),
@@ -1365,6 +1374,7 @@ fn should_deposit_cketh_and_ckerc20_when_ledger_temporary_offline() {
                 value: ONE_USDC.into(),
                 principal: caller,
                 erc20_contract_address: ckusdc.erc20_contract_address.clone(),
+                subaccount: None,
             },
         ])
         .check_events()
@@ -1511,6 +1521,8 @@ fn should_retrieve_minter_info() {
                 .unwrap()
                 .ledger
                 .unwrap(),
+            balance_diff_accounting: Some(false),
+            decimals: Some(token.ledger_init_arg.decimals),
         })
         .collect::<Vec<_>>();
     assert!(!supported_ckerc20_tokens.is_empty());
@@ -1537,6 +1549,7 @@ fn should_retrieve_minter_info() {
             erc20_helper_contract_address: Some(format_ethereum_address_to_eip_55(
                 ERC20_HELPER_CONTRACT_ADDRESS
             )),
+            deposit_with_subaccount_helper_contract_address: None,
             supported_ckerc20_tokens: Some(supported_ckerc20_tokens),
             minimum_withdrawal_amount: Some(Nat::from(CKETH_MINIMUM_WITHDRAWAL_AMOUNT)),
             ethereum_block_height: Some(Finalized),
@@ -1546,6 +1559,9 @@ fn should_retrieve_minter_info() {
             erc20_balances: Some(erc20_balances),
             last_eth_scraped_block_number: Some(LAST_SCRAPED_BLOCK_NUMBER_AT_INSTALL.into()),
             last_erc20_scraped_block_number: Some(LAST_SCRAPED_BLOCK_NUMBER_AT_INSTALL.into()),
+            last_deposit_with_subaccount_scraped_block_number: Some(
+                LAST_SCRAPED_BLOCK_NUMBER_AT_INSTALL.into(),
+            ),
             cketh_ledger_id: Some(ckerc20.cketh_ledger_id()),
         }
     );
@@ -135,6 +135,7 @@ fn should_deposit_and_withdraw() {
                     status: TransactionStatus::Success,
                     transaction_hash:
                     "0x2cf1763e8ee3990103a31a5709b17b83f167738abb400844e67f608a98b0bdb5".to_string(),
+                    l1_fee: None,
                 },
             },
         ]);
@@ -527,6 +528,7 @@ fn should_reimburse() {
                     status: TransactionStatus::Failure,
                     transaction_hash:
                     "0x2cf1763e8ee3990103a31a5709b17b83f167738abb400844e67f608a98b0bdb5".to_string(),
+                    l1_fee: None,
                 },
             },
             EventPayload::ReimbursedEthWithdrawal {
@@ -634,6 +636,7 @@ fn should_resubmit_new_transaction_when_price_increased() {
                     gas_used: Nat::from(21_000_u32),
                     status: TransactionStatus::Success,
                     transaction_hash: format!("{:?}", resubmitted_tx_hash),
+                    l1_fee: None,
                 },
             },
         ]);
@@ -1033,6 +1036,7 @@ fn should_retrieve_minter_info() {
                 ETH_HELPER_CONTRACT_ADDRESS
             )),
             erc20_helper_contract_address: None,
+            deposit_with_subaccount_helper_contract_address: None,
             supported_ckerc20_tokens: None,
             minimum_withdrawal_amount: Some(Nat::from(CKETH_MINIMUM_WITHDRAWAL_AMOUNT)),
             ethereum_block_height: Some(Finalized),
@@ -1042,6 +1046,9 @@ fn should_retrieve_minter_info() {
             erc20_balances: None,
             last_eth_scraped_block_number: Some(LAST_SCRAPED_BLOCK_NUMBER_AT_INSTALL.into()),
             last_erc20_scraped_block_number: Some(LAST_SCRAPED_BLOCK_NUMBER_AT_INSTALL.into()),
+            last_deposit_with_subaccount_scraped_block_number: Some(
+                LAST_SCRAPED_BLOCK_NUMBER_AT_INSTALL.into(),
+            ),
             cketh_ledger_id: Some(cketh.ledger_id.into()),
         }
     );
@@ -119,6 +119,8 @@ impl CkErc20Setup {
                     address: format_ethereum_address_to_eip_55(&token.contract.address),
                     ckerc20_token_symbol: token.ledger_init_arg.token_symbol.clone(),
                     ckerc20_ledger_id: new_ledger_id,
+                    balance_diff_accounting: Some(false),
+                    decimals: Some(token.ledger_init_arg.decimals),
                 },
             ]);
         }
@@ -484,6 +486,7 @@ impl CkErc20DepositFlow {
                         ),
                         value: amount.into(),
                         principal: self.params.recipient,
+                        subaccount: None,
                     },
                     EventPayload::MintedCkEth {
                         event_source: EventSource {
@@ -505,6 +508,7 @@ impl CkErc20DepositFlow {
                 value: self.params.ckerc20_amount.into(),
                 principal: self.params.recipient,
                 erc20_contract_address: self.params.token.erc20_contract_address.clone(),
+                subaccount: None,
             },
             EventPayload::MintedCkErc20 {
                 event_source: EventSource {
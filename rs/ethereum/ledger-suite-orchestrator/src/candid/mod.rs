@@ -26,6 +26,8 @@ pub struct UpgradeArg {
     pub archive_compressed_wasm_hash: Option<String>,
     pub cycles_management: Option<UpdateCyclesManagement>,
     pub manage_ledger_suites: Option<Vec<InstalledLedgerSuite>>,
+    /// Upgrade the managed ledger suites in stages instead of all at once.
+    pub rollout: Option<RolloutArg>,
 }
 
 impl UpgradeArg {
@@ -36,10 +38,24 @@ impl UpgradeArg {
     }
 }
 
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RolloutArg {
+    /// Symbol of the token whose ledger suite is upgraded first, e.g., "ckUSDC".
+    /// Defaults to the first managed ledger suite.
+    pub canary_token_symbol: Option<String>,
+    /// Maximum number of ledger suites upgraded together after the canary.
+    /// Defaults to [`crate::state::DEFAULT_ROLLOUT_BATCH_SIZE`].
+    pub batch_size: Option<u64>,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct AddErc20Arg {
     pub contract: Erc20Contract,
     pub ledger_init_arg: LedgerInitArg,
+    /// Whether the minter credits deposits using balance-diff accounting, i.e., only mints the
+    /// amount by which its balance actually increased, as required for tokens that charge a fee
+    /// on transfers and rebasing tokens. Defaults to `false`.
+    pub balance_diff_accounting: Option<bool>,
 }
 
 impl AddErc20Arg {
@@ -118,6 +134,8 @@ pub struct AddCkErc20Token {
     pub address: String,
     pub ckerc20_token_symbol: String,
     pub ckerc20_ledger_id: Principal,
+    pub balance_diff_accounting: Option<bool>,
+    pub decimals: Option<u8>,
 }
 
 #[derive(
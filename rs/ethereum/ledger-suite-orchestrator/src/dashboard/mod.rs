@@ -5,8 +5,8 @@ pub use askama::Template;
 use candid::Principal;
 use ic_ledger_suite_orchestrator::scheduler::Erc20Token;
 use ic_ledger_suite_orchestrator::state::{
-    Archive, Canisters, GitCommitHash, Index, IndexCanister, Ledger, LedgerCanister, State,
-    TokenId, WasmHash,
+    Archive, Canisters, GitCommitHash, Index, IndexCanister, Ledger, LedgerCanister,
+    LedgerSuiteRollout, RolloutBatchStatus, State, TokenId, WasmHash,
 };
 use ic_ledger_suite_orchestrator::storage::{StorableWasm, StoredWasm, WasmStore};
 use std::cmp::Reverse;
@@ -32,6 +32,7 @@ mod filters {
 pub struct DashboardTemplate {
     managed_canisters: BTreeMap<Erc20Token, CanistersDashboardData>,
     other_canisters: BTreeMap<String, Vec<CanisterDashboardData>>,
+    rollout: Option<RolloutDashboardData>,
     wasm_store: Vec<DashboardStoredWasm>,
 }
 
@@ -107,6 +108,54 @@ impl From<&IndexCanister> for CanisterDashboardData {
     }
 }
 
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RolloutDashboardData {
+    pub status: String,
+    pub batches: Vec<RolloutBatchDashboardData>,
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RolloutBatchDashboardData {
+    pub ledger_suites: String,
+    pub status: String,
+}
+
+impl RolloutDashboardData {
+    pub fn from_rollout(state: &State, rollout: &LedgerSuiteRollout) -> Self {
+        let status = if rollout.is_in_progress() {
+            "In progress"
+        } else if rollout
+            .batches()
+            .iter()
+            .any(|batch| matches!(batch.status, RolloutBatchStatus::RolledBack { .. }))
+        {
+            "Rolled back"
+        } else {
+            "Completed"
+        }
+        .to_string();
+        let batches = rollout
+            .batches()
+            .iter()
+            .map(|batch| RolloutBatchDashboardData {
+                ledger_suites: batch
+                    .ledger_suites
+                    .keys()
+                    .map(|token_id| {
+                        state
+                            .managed_canisters(token_id)
+                            .map(|canisters| canisters.metadata.token_symbol.clone())
+                            .unwrap_or_else(|| format!("{:?}", token_id))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                status: batch.status.to_string(),
+            })
+            .collect();
+        Self { status, batches }
+    }
+}
+
 struct DashboardStoredWasm {
     pub timestamp: u64,
     pub wasm_hash: WasmHash,
@@ -171,6 +220,9 @@ impl DashboardTemplate {
                     )
                 })
                 .collect(),
+            rollout: state
+                .ledger_suite_rollout()
+                .map(|rollout| RolloutDashboardData::from_rollout(state, rollout)),
             wasm_store,
         }
     }
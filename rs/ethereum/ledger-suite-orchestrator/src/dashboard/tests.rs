@@ -5,11 +5,11 @@ use crate::dashboard::tests::fixtures::{
 use crate::dashboard::DashboardTemplate;
 use candid::Principal;
 use fixtures::{usdc, usdt, USDC_ADDRESS, USDT_ADDRESS};
-use ic_ledger_suite_orchestrator::candid::InitArg;
-use ic_ledger_suite_orchestrator::scheduler::Erc20Token;
+use ic_ledger_suite_orchestrator::candid::{InitArg, RolloutArg, UpgradeArg};
+use ic_ledger_suite_orchestrator::scheduler::{Erc20Token, UpgradeOrchestratorArgs};
 use ic_ledger_suite_orchestrator::state::{
-    ArchiveWasm, CanistersMetadata, GitCommitHash, Index, IndexWasm, Ledger, LedgerWasm, State,
-    WasmHash,
+    ArchiveWasm, CanistersMetadata, GitCommitHash, Index, IndexWasm, Ledger, LedgerSuiteRollout,
+    LedgerSuiteVersion, LedgerWasm, RolloutBatchStatus, State, WasmHash,
 };
 use ic_ledger_suite_orchestrator::storage::{wasm_store_try_insert, WasmStore};
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager};
@@ -21,6 +21,7 @@ fn should_be_empty_initially() {
     DashboardAssert::assert_that(initial_dashboard())
         .has_no_elements_matching("#managed-canisters")
         .has_no_elements_matching("#other-canisters")
+        .has_no_elements_matching("#ledger-suite-rollout")
         .has_no_elements_matching("#wasm-store");
 }
 
@@ -42,6 +43,8 @@ fn should_display_managed_canisters() {
         usdc(),
         CanistersMetadata {
             token_symbol: "ckUSDC".to_string(),
+            balance_diff_accounting: false,
+            decimals: Some(6),
         },
     );
     state.record_created_canister::<Ledger>(&usdc(), Principal::from_str(USDC_LEDGER_ID).unwrap());
@@ -73,6 +76,8 @@ fn should_display_managed_canisters() {
         usdt(),
         CanistersMetadata {
             token_symbol: "ckUSDT".to_string(),
+            balance_diff_accounting: false,
+            decimals: Some(6),
         },
     );
     state.record_created_canister::<Ledger>(&usdt(), Principal::from_str(USDT_LEDGER_ID).unwrap());
@@ -238,6 +243,74 @@ fn should_display_stored_wasms() {
         );
 }
 
+#[test]
+fn should_display_ledger_suite_rollout() {
+    const LEDGER_WASM_HASH: &str =
+        "fe14010b4fe83303852f0467c919ef9a7ca089b91e96e3aad7d426dd87079297";
+    let mut store = empty_wasm_store();
+    let ledger_wasm = LedgerWasm::new("ledger".as_bytes().to_vec());
+    wasm_store_try_insert(&mut store, 0, GitCommitHash::default(), ledger_wasm).unwrap();
+
+    let mut state = initial_state();
+    state.record_new_erc20_token(usdc(), usdc_metadata());
+    state.record_new_erc20_token(
+        usdt(),
+        CanistersMetadata {
+            token_symbol: "ckUSDT".to_string(),
+            balance_diff_accounting: false,
+            decimals: Some(6),
+        },
+    );
+    state.record_manage_other_canisters(cketh_ledger_suite(&state));
+    state.init_ledger_suite_version(LedgerSuiteVersion {
+        ledger_compressed_wasm_hash: WasmHash::default(),
+        index_compressed_wasm_hash: WasmHash::default(),
+        archive_compressed_wasm_hash: WasmHash::default(),
+    });
+    let upgrade = UpgradeOrchestratorArgs::validate_upgrade_arg(
+        &store,
+        UpgradeArg {
+            git_commit_hash: None,
+            ledger_compressed_wasm_hash: Some(LEDGER_WASM_HASH.to_string()),
+            index_compressed_wasm_hash: None,
+            archive_compressed_wasm_hash: None,
+            cycles_management: None,
+            manage_ledger_suites: None,
+            rollout: None,
+        },
+    )
+    .unwrap();
+    let rollout = LedgerSuiteRollout::validate(
+        &state,
+        upgrade,
+        RolloutArg {
+            canary_token_symbol: Some("ckUSDT".to_string()),
+            batch_size: Some(2),
+        },
+    )
+    .unwrap();
+    state.start_ledger_suite_rollout(rollout);
+
+    DashboardAssert::assert_that_dashboard_from_state(&state)
+        .has_rollout_status("In progress")
+        .has_rollout_batch(1, &vec!["0", "ckUSDT", "Pending"])
+        .has_rollout_batch(2, &vec!["1", "ckUSDC, ckETH", "Pending"]);
+
+    let rollout = state.ledger_suite_rollout_mut().unwrap();
+    rollout.current_batch_mut().unwrap().status = RolloutBatchStatus::Healthy;
+    rollout.current_batch_mut().unwrap().status = RolloutBatchStatus::RolledBack {
+        reason: "index not synced".to_string(),
+    };
+
+    DashboardAssert::assert_that_dashboard_from_state(&state)
+        .has_rollout_status("Rolled back")
+        .has_rollout_batch(1, &vec!["0", "ckUSDT", "Healthy"])
+        .has_rollout_batch(
+            2,
+            &vec!["1", "ckUSDC, ckETH", "Rolled back: index not synced"],
+        );
+}
+
 fn initial_dashboard() -> DashboardTemplate {
     DashboardTemplate::from_state(&initial_state(), &empty_wasm_store())
 }
@@ -304,6 +377,22 @@ mod assertions {
             )
         }
 
+        pub fn has_rollout_status(&self, expected_status: &str) -> &Self {
+            self.has_string_value(
+                "#ledger-suite-rollout-status td",
+                expected_status,
+                "wrong rollout status",
+            )
+        }
+
+        pub fn has_rollout_batch(&self, row_index: u8, expected_batch: &Vec<&str>) -> &Self {
+            self.has_table_row_string_value(
+                &format!("#ledger-suite-rollout-batches > tbody > tr:nth-child({row_index})"),
+                expected_batch,
+                "wrong rollout batch",
+            )
+        }
+
         pub fn has_erc20(
             self,
             ckerc20_token_symbol: &str,
@@ -518,6 +607,8 @@ mod fixtures {
     pub fn usdc_metadata() -> CanistersMetadata {
         CanistersMetadata {
             token_symbol: "ckUSDC".to_string(),
+            balance_diff_accounting: false,
+            decimals: Some(6),
         }
     }
 
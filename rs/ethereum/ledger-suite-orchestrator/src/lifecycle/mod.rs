@@ -4,7 +4,8 @@ use crate::scheduler::{
     schedule_now, InstallLedgerSuiteArgs, Task, UpgradeOrchestratorArgs, IC_CANISTER_RUNTIME,
 };
 use crate::state::{
-    init_state, mutate_state, read_state, GitCommitHash, InstalledLedgerSuite, State,
+    init_state, mutate_state, read_state, GitCommitHash, InstalledLedgerSuite, LedgerSuiteRollout,
+    State,
 };
 use crate::storage::{mutate_wasm_store, read_wasm_store, record_icrc1_ledger_suite_wasms};
 use ic_canister_log::log;
@@ -49,26 +50,60 @@ pub fn post_upgrade(upgrade_arg: Option<UpgradeArg>) {
         match read_wasm_store(|w| UpgradeOrchestratorArgs::validate_upgrade_arg(w, arg.clone())) {
             Ok(valid_upgrade_args) => {
                 if valid_upgrade_args.upgrade_ledger_suite() {
-                    let current_ledger_suite_version =
-                        read_state(|s| s.ledger_suite_version().cloned())
-                            .expect("BUG: missing ledger suite version");
-                    mutate_state(|s| {
-                        s.update_ledger_suite_version(
-                            valid_upgrade_args
-                                .clone()
-                                .new_ledger_suite_version(current_ledger_suite_version),
-                        )
-                    });
-                    for token_id in
-                        read_state(|s| s.all_managed_tokens_ids_iter().collect::<Vec<_>>())
-                    {
-                        schedule_now(
-                            Task::UpgradeLedgerSuite(
-                                valid_upgrade_args.clone().into_task(token_id),
-                            ),
-                            &IC_CANISTER_RUNTIME,
+                    if read_state(|s| s.is_ledger_suite_rollout_in_progress()) {
+                        ic_cdk::trap(
+                            "[post_upgrade]: ERROR: cannot upgrade ledger suites while a rollout is in progress",
                         );
                     }
+                    let rollout = arg.rollout.clone().map(|rollout_arg| {
+                        read_state(|s| {
+                            LedgerSuiteRollout::validate(
+                                s,
+                                valid_upgrade_args.clone(),
+                                rollout_arg.clone(),
+                            )
+                        })
+                        .unwrap_or_else(|e| {
+                            ic_cdk::trap(&format!(
+                                "[post_upgrade]: ERROR: invalid rollout arguments {:?}: {:?}",
+                                rollout_arg, e
+                            ))
+                        })
+                    });
+                    match rollout {
+                        Some(rollout) => {
+                            // the new ledger suite version is recorded once the canary is healthy.
+                            log!(
+                                INFO,
+                                "[post_upgrade]: starting ledger suite rollout: {:?}",
+                                rollout
+                            );
+                            mutate_state(|s| s.start_ledger_suite_rollout(rollout));
+                            schedule_now(Task::UpgradeLedgerSuiteRollout, &IC_CANISTER_RUNTIME);
+                        }
+                        None => {
+                            let current_ledger_suite_version =
+                                read_state(|s| s.ledger_suite_version().cloned())
+                                    .expect("BUG: missing ledger suite version");
+                            mutate_state(|s| {
+                                s.update_ledger_suite_version(
+                                    valid_upgrade_args
+                                        .clone()
+                                        .new_ledger_suite_version(current_ledger_suite_version),
+                                )
+                            });
+                            for token_id in
+                                read_state(|s| s.all_managed_tokens_ids_iter().collect::<Vec<_>>())
+                            {
+                                schedule_now(
+                                    Task::UpgradeLedgerSuite(
+                                        valid_upgrade_args.clone().into_task(token_id),
+                                    ),
+                                    &IC_CANISTER_RUNTIME,
+                                );
+                            }
+                        }
+                    }
                 }
             }
             Err(e) => {
@@ -9,6 +9,7 @@ use ic_management_canister_types::{
     InstallCodeArgs,
 };
 use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Debug;
 
@@ -91,6 +92,23 @@ impl Reason {
     }
 }
 
+/// Identifier of a canister snapshot, as returned by the management canister.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, CandidType, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct SnapshotId(#[serde(with = "serde_bytes")] Vec<u8>);
+
+impl From<Vec<u8>> for SnapshotId {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0))
+    }
+}
+
 #[async_trait]
 pub trait CanisterRuntime {
     /// Returns the canister id of the current canister.
@@ -132,6 +150,24 @@ pub trait CanisterRuntime {
         wasm_module: Vec<u8>,
     ) -> Result<(), CallError>;
 
+    /// Takes a snapshot of the given canister, which should be stopped.
+    async fn take_canister_snapshot(&self, canister_id: Principal)
+        -> Result<SnapshotId, CallError>;
+
+    /// Restores the given canister, which must be stopped, from one of its snapshots.
+    async fn load_canister_snapshot(
+        &self,
+        canister_id: Principal,
+        snapshot_id: SnapshotId,
+    ) -> Result<(), CallError>;
+
+    /// Deletes a snapshot of the given canister.
+    async fn delete_canister_snapshot(
+        &self,
+        canister_id: Principal,
+        snapshot_id: SnapshotId,
+    ) -> Result<(), CallError>;
+
     async fn canister_cycles(&self, canister_id: Principal) -> Result<u128, CallError>;
 
     fn send_cycles(&self, canister_id: Principal, cycles: u128) -> Result<(), CallError>;
@@ -293,6 +329,84 @@ impl CanisterRuntime for IcCanisterRuntime {
         Ok(())
     }
 
+    async fn take_canister_snapshot(
+        &self,
+        canister_id: Principal,
+    ) -> Result<SnapshotId, CallError> {
+        // See https://internetcomputer.org/docs/current/references/ic-interface-spec#ic-take_canister_snapshot
+        #[derive(CandidType)]
+        struct TakeCanisterSnapshotArgs {
+            canister_id: Principal,
+            replace_snapshot: Option<SnapshotId>,
+        }
+
+        #[derive(CandidType, Deserialize)]
+        struct Snapshot {
+            id: SnapshotId,
+        }
+
+        let snapshot: Snapshot = self
+            .call(
+                "take_canister_snapshot",
+                0,
+                &TakeCanisterSnapshotArgs {
+                    canister_id,
+                    replace_snapshot: None,
+                },
+            )
+            .await?;
+
+        Ok(snapshot.id)
+    }
+
+    async fn load_canister_snapshot(
+        &self,
+        canister_id: Principal,
+        snapshot_id: SnapshotId,
+    ) -> Result<(), CallError> {
+        // See https://internetcomputer.org/docs/current/references/ic-interface-spec#ic-load_canister_snapshot
+        #[derive(CandidType)]
+        struct LoadCanisterSnapshotArgs {
+            canister_id: Principal,
+            snapshot_id: SnapshotId,
+            sender_canister_version: Option<u64>,
+        }
+
+        self.call(
+            "load_canister_snapshot",
+            0,
+            &LoadCanisterSnapshotArgs {
+                canister_id,
+                snapshot_id,
+                sender_canister_version: None,
+            },
+        )
+        .await
+    }
+
+    async fn delete_canister_snapshot(
+        &self,
+        canister_id: Principal,
+        snapshot_id: SnapshotId,
+    ) -> Result<(), CallError> {
+        // See https://internetcomputer.org/docs/current/references/ic-interface-spec#ic-delete_canister_snapshot
+        #[derive(CandidType)]
+        struct DeleteCanisterSnapshotArgs {
+            canister_id: Principal,
+            snapshot_id: SnapshotId,
+        }
+
+        self.call(
+            "delete_canister_snapshot",
+            0,
+            &DeleteCanisterSnapshotArgs {
+                canister_id,
+                snapshot_id,
+            },
+        )
+        .await
+    }
+
     async fn canister_cycles(&self, canister_id: Principal) -> Result<u128, CallError> {
         let result = ic_cdk::api::management_canister::main::canister_status(
             ic_cdk::api::management_canister::main::CanisterIdRecord { canister_id },
@@ -49,6 +49,7 @@ impl OrchestratorMetrics {
                 Task::NotifyErc20Added { .. } => "notify_erc20_added",
                 Task::DiscoverArchives => "discover_archives",
                 Task::UpgradeLedgerSuite(_) => "upgrade_ledger_suite",
+                Task::UpgradeLedgerSuiteRollout => "upgrade_ledger_suite_rollout",
             }
             .to_string(),
             result: match result {
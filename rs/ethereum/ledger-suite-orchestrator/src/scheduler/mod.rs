@@ -8,11 +8,12 @@ use crate::candid::{AddCkErc20Token, AddErc20Arg, CyclesManagement, LedgerInitAr
 use crate::logs::DEBUG;
 use crate::logs::INFO;
 use crate::management::IcCanisterRuntime;
-use crate::management::{CallError, CanisterRuntime, Reason};
+use crate::management::{CallError, CanisterRuntime, Reason, SnapshotId};
 use crate::state::{
     mutate_state, read_state, Archive, Canister, Canisters, CanistersMetadata, Index, Ledger,
-    LedgerSuiteVersion, ManageSingleCanister, ManagedCanisterStatus, State, TokenId, TokenSymbol,
-    WasmHash,
+    LedgerHealth, LedgerSuiteRollback, LedgerSuiteUpgradeProgress, LedgerSuiteUpgradeStatus,
+    LedgerSuiteVersion, ManageSingleCanister, ManagedCanisterStatus, RolloutBatch,
+    RolloutBatchStatus, State, TokenId, TokenSymbol, WasmHash,
 };
 use crate::storage::{
     read_wasm_store, validate_wasm_hashes, wasm_store_contain, wasm_store_try_get, StorableWasm,
@@ -23,9 +24,11 @@ use futures::future;
 use ic_base_types::PrincipalId;
 use ic_canister_log::log;
 use ic_ethereum_types::Address;
-use ic_icrc1_index_ng::{IndexArg, InitArg as IndexInitArg};
+use ic_icrc1_index_ng::{IndexArg, InitArg as IndexInitArg, Status as IndexStatus};
 use ic_icrc1_ledger::{ArchiveOptions, InitArgs as LedgerInitArgs, LedgerArgument};
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
 use icrc_ledger_types::icrc3::archive::{GetArchivesArgs, GetArchivesResult};
+use icrc_ledger_types::icrc3::blocks::{BlockWithId, GetBlocksRequest, GetBlocksResult};
 pub use metrics::encode_orchestrator_metrics;
 use metrics::observe_task_duration;
 use num_traits::ToPrimitive;
@@ -42,6 +45,17 @@ const SEC_NANOS: u64 = 1_000_000_000;
 
 const THREE_GIGA_BYTES: u64 = 3_221_225_472;
 
+/// Delay between two checks of the progress of a ledger suite rollout.
+const ROLLOUT_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum duration for the ledger suites of a rollout batch to be upgraded, before the ledger suites
+/// that are still being upgraded are considered failed and the batch is rolled back.
+const ROLLOUT_UPGRADE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Maximum duration for the ledger suites of a rollout batch to become healthy after their upgrade,
+/// before the batch is rolled back.
+const ROLLOUT_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
 pub const IC_CANISTER_RUNTIME: IcCanisterRuntime = IcCanisterRuntime {};
 
 thread_local! {
@@ -53,6 +67,7 @@ thread_local! {
 pub enum Task {
     InstallLedgerSuite(InstallLedgerSuiteArgs),
     UpgradeLedgerSuite(UpgradeLedgerSuite),
+    UpgradeLedgerSuiteRollout,
     MaybeTopUp,
     DiscoverArchives,
     NotifyErc20Added {
@@ -69,6 +84,7 @@ impl Task {
             Task::NotifyErc20Added { .. } => false,
             Task::DiscoverArchives => true,
             Task::UpgradeLedgerSuite(_) => false,
+            Task::UpgradeLedgerSuiteRollout => false,
         }
     }
 }
@@ -226,6 +242,10 @@ async fn run_task<R: CanisterRuntime>(task: TaskExecution, runtime: R) {
 pub struct UpgradeLedgerSuite {
     subtasks: Vec<UpgradeLedgerSuiteSubtask>,
     next_subtask_index: usize,
+    /// Whether the upgrade is part of a ledger suite rollout, in which case a snapshot of each canister
+    /// is taken before upgrading it and the outcome of the upgrade is recorded in the rollout.
+    #[serde(default)]
+    rollout: bool,
 }
 
 impl UpgradeLedgerSuite {
@@ -252,6 +272,7 @@ impl UpgradeLedgerSuite {
         ledger_compressed_wasm_hash: Option<WasmHash>,
         index_compressed_wasm_hash: Option<WasmHash>,
         archive_compressed_wasm_hash: Option<WasmHash>,
+        rollout: bool,
     ) -> Self {
         let mut subtasks = Vec::new();
         if let Some(index_compressed_wasm_hash) = index_compressed_wasm_hash {
@@ -278,6 +299,7 @@ impl UpgradeLedgerSuite {
         Self {
             subtasks,
             next_subtask_index: 0,
+            rollout,
         }
     }
 
@@ -291,6 +313,7 @@ struct UpgradeLedgerSuiteBuilder {
     ledger_wasm_hash: Option<WasmHash>,
     index_wasm_hash: Option<WasmHash>,
     archive_wasm_hash: Option<WasmHash>,
+    rollout: bool,
 }
 
 impl UpgradeLedgerSuiteBuilder {
@@ -300,6 +323,7 @@ impl UpgradeLedgerSuiteBuilder {
             ledger_wasm_hash: None,
             index_wasm_hash: None,
             archive_wasm_hash: None,
+            rollout: false,
         }
     }
 
@@ -318,12 +342,18 @@ impl UpgradeLedgerSuiteBuilder {
         self
     }
 
+    fn rollout(mut self, rollout: bool) -> Self {
+        self.rollout = rollout;
+        self
+    }
+
     fn build(self) -> UpgradeLedgerSuite {
         UpgradeLedgerSuite::new(
             self.token_id,
             self.ledger_wasm_hash,
             self.index_wasm_hash,
             self.archive_wasm_hash,
+            self.rollout,
         )
     }
 }
@@ -348,10 +378,23 @@ pub enum UpgradeLedgerSuiteSubtask {
 }
 
 impl UpgradeLedgerSuiteSubtask {
+    pub fn token_id(&self) -> &TokenId {
+        match self {
+            UpgradeLedgerSuiteSubtask::UpgradeIndex { token_id, .. } => token_id,
+            UpgradeLedgerSuiteSubtask::UpgradeLedger { token_id, .. } => token_id,
+            UpgradeLedgerSuiteSubtask::DiscoverArchives { token_id } => token_id,
+            UpgradeLedgerSuiteSubtask::UpgradeArchives { token_id, .. } => token_id,
+        }
+    }
+
+    /// Executes the subtask. When it is part of a rollout,
+    /// a snapshot of each canister is taken right before upgrading it.
     pub async fn execute<R: CanisterRuntime>(
         &self,
+        rollout: bool,
         runtime: &R,
     ) -> Result<(), UpgradeLedgerSuiteError> {
+        let snapshot_for = rollout.then_some(self.token_id());
         match self {
             UpgradeLedgerSuiteSubtask::UpgradeIndex {
                 token_id,
@@ -366,7 +409,13 @@ impl UpgradeLedgerSuiteSubtask {
                 let canisters = read_state(|s| s.managed_canisters(token_id).cloned())
                     .ok_or(UpgradeLedgerSuiteError::TokenNotFound(token_id.clone()))?;
                 let canister_id = ensure_canister_is_installed(token_id, canisters.index)?;
-                upgrade_canister::<Index, _>(canister_id, compressed_wasm_hash, runtime).await
+                upgrade_canister::<Index, _>(
+                    canister_id,
+                    compressed_wasm_hash,
+                    snapshot_for,
+                    runtime,
+                )
+                .await
             }
             UpgradeLedgerSuiteSubtask::UpgradeLedger {
                 token_id,
@@ -381,7 +430,13 @@ impl UpgradeLedgerSuiteSubtask {
                 let canisters = read_state(|s| s.managed_canisters(token_id).cloned())
                     .ok_or(UpgradeLedgerSuiteError::TokenNotFound(token_id.clone()))?;
                 let canister_id = ensure_canister_is_installed(token_id, canisters.ledger)?;
-                upgrade_canister::<Ledger, _>(canister_id, compressed_wasm_hash, runtime).await
+                upgrade_canister::<Ledger, _>(
+                    canister_id,
+                    compressed_wasm_hash,
+                    snapshot_for,
+                    runtime,
+                )
+                .await
             }
             UpgradeLedgerSuiteSubtask::DiscoverArchives { token_id } => {
                 log!(INFO, "Discovering archive canister(s) for {:?}", token_id);
@@ -413,8 +468,13 @@ impl UpgradeLedgerSuiteSubtask {
                 );
                 //We expect usually 0 or 1 archive, so a simple sequential strategy is good enough.
                 for canister_id in archives {
-                    upgrade_canister::<Archive, _>(canister_id, compressed_wasm_hash, runtime)
-                        .await?;
+                    upgrade_canister::<Archive, _>(
+                        canister_id,
+                        compressed_wasm_hash,
+                        snapshot_for,
+                        runtime,
+                    )
+                    .await?;
                 }
                 Ok(())
             }
@@ -495,11 +555,14 @@ impl UpgradeOrchestratorArgs {
     }
 
     pub fn into_task(self, token_id: TokenId) -> UpgradeLedgerSuite {
+        self.into_builder(token_id).build()
+    }
+
+    fn into_builder(self, token_id: TokenId) -> UpgradeLedgerSuiteBuilder {
         UpgradeLedgerSuite::builder(token_id)
             .ledger_wasm_hash(self.ledger_compressed_wasm_hash)
             .index_wasm_hash(self.index_compressed_wasm_hash)
             .archive_wasm_hash(self.archive_compressed_wasm_hash)
-            .build()
     }
 }
 
@@ -510,6 +573,8 @@ pub struct InstallLedgerSuiteArgs {
     ledger_init_arg: LedgerInitArg,
     ledger_compressed_wasm_hash: WasmHash,
     index_compressed_wasm_hash: WasmHash,
+    #[serde(default)]
+    balance_diff_accounting: bool,
 }
 
 impl PartialOrd for InstallLedgerSuiteArgs {
@@ -534,10 +599,15 @@ impl InstallLedgerSuiteArgs {
 pub enum InvalidAddErc20ArgError {
     InvalidErc20Contract(String),
     Erc20ContractAlreadyManaged(Erc20Token),
+    InvalidDecimals(u8),
     WasmHashError(WasmHashError),
     InternalError(String),
 }
 
+/// Maximum number of decimals of an ERC-20 token, so that at least one whole token
+/// can be represented by the 256-bit amounts of a ckERC20 ledger.
+pub const MAX_ERC20_DECIMALS: u8 = 77;
+
 impl InstallLedgerSuiteArgs {
     pub fn validate_add_erc20(
         state: &State,
@@ -559,6 +629,11 @@ impl InstallLedgerSuiteArgs {
                 contract,
             ));
         }
+        if args.ledger_init_arg.decimals > MAX_ERC20_DECIMALS {
+            return Err(InvalidAddErc20ArgError::InvalidDecimals(
+                args.ledger_init_arg.decimals,
+            ));
+        }
         let (ledger_compressed_wasm_hash, index_compressed_wasm_hash) = {
             let LedgerSuiteVersion {
                 ledger_compressed_wasm_hash,
@@ -589,6 +664,7 @@ impl InstallLedgerSuiteArgs {
             ledger_init_arg: args.ledger_init_arg,
             ledger_compressed_wasm_hash,
             index_compressed_wasm_hash,
+            balance_diff_accounting: args.balance_diff_accounting.unwrap_or_default(),
         })
     }
 }
@@ -605,6 +681,7 @@ pub enum TaskError {
     InsufficientCyclesToTopUp { required: u128, available: u128 },
     DiscoverArchivesError(DiscoverArchivesError),
     UpgradeLedgerSuiteError(UpgradeLedgerSuiteError),
+    UpgradeLedgerSuiteRolloutError(UpgradeLedgerSuiteRolloutError),
 }
 
 impl TaskError {
@@ -622,6 +699,7 @@ impl TaskError {
             TaskError::InsufficientCyclesToTopUp { .. } => false, //top-up task is periodic, will retry on next interval
             TaskError::DiscoverArchivesError(e) => e.is_recoverable(),
             TaskError::UpgradeLedgerSuiteError(e) => e.is_recoverable(),
+            TaskError::UpgradeLedgerSuiteRolloutError(e) => e.is_recoverable(),
         }
     }
 }
@@ -654,14 +732,31 @@ pub enum UpgradeLedgerSuiteError {
         message: String,
     },
     StopCanisterError(CallError),
+    TakeCanisterSnapshotError(CallError),
     StartCanisterError(CallError),
     UpgradeCanisterError(CallError),
     WasmHashNotFound(WasmHash),
     WasmStoreError(WasmStoreError),
     DiscoverArchivesError(DiscoverArchivesError),
+    /// The upgrade failed as part of a rollout, whose batch will be rolled back.
+    RolloutAborted {
+        token_id: TokenId,
+        error: Box<UpgradeLedgerSuiteError>,
+    },
 }
 
 impl UpgradeLedgerSuiteError {
+    /// Whether the error should abort the rollout the upgrade is part of.
+    /// In addition to unrecoverable errors, this is the case when the canister
+    /// rejected its upgrade, e.g., because its post-upgrade hook trapped,
+    /// since retrying would most likely fail again.
+    fn aborts_rollout(&self) -> bool {
+        match self {
+            UpgradeLedgerSuiteError::UpgradeCanisterError(e) => !is_recoverable(e),
+            _ => !self.is_recoverable(),
+        }
+    }
+
     fn is_recoverable(&self) -> bool {
         match self {
             UpgradeLedgerSuiteError::TokenNotFound(_) => false,
@@ -669,9 +764,11 @@ impl UpgradeLedgerSuiteError {
             UpgradeLedgerSuiteError::WasmHashNotFound(_) => false,
             UpgradeLedgerSuiteError::WasmStoreError(_) => false,
             UpgradeLedgerSuiteError::StopCanisterError(_) => true,
+            UpgradeLedgerSuiteError::TakeCanisterSnapshotError(e) => is_recoverable(e),
             UpgradeLedgerSuiteError::StartCanisterError(_) => true,
             UpgradeLedgerSuiteError::UpgradeCanisterError(_) => true,
             UpgradeLedgerSuiteError::DiscoverArchivesError(e) => e.is_recoverable(),
+            UpgradeLedgerSuiteError::RolloutAborted { .. } => false,
         }
     }
 }
@@ -682,16 +779,107 @@ impl From<UpgradeLedgerSuiteError> for TaskError {
     }
 }
 
+#[derive(Clone, PartialEq, Debug)]
+pub enum UpgradeLedgerSuiteRolloutError {
+    BaselineError(LedgerSuiteHealthError),
+    StopCanisterError(CallError),
+    LoadCanisterSnapshotError(CallError),
+    StartCanisterError(CallError),
+    DowngradeCanisterError(UpgradeLedgerSuiteError),
+}
+
+impl UpgradeLedgerSuiteRolloutError {
+    fn is_recoverable(&self) -> bool {
+        match self {
+            // the batch was not upgraded yet, so it is safe to keep trying.
+            UpgradeLedgerSuiteRolloutError::BaselineError(_) => true,
+            // restoring a batch must be retried until it succeeds.
+            UpgradeLedgerSuiteRolloutError::StopCanisterError(_) => true,
+            UpgradeLedgerSuiteRolloutError::LoadCanisterSnapshotError(_) => true,
+            UpgradeLedgerSuiteRolloutError::StartCanisterError(_) => true,
+            UpgradeLedgerSuiteRolloutError::DowngradeCanisterError(_) => true,
+        }
+    }
+}
+
+impl From<UpgradeLedgerSuiteRolloutError> for TaskError {
+    fn from(value: UpgradeLedgerSuiteRolloutError) -> Self {
+        TaskError::UpgradeLedgerSuiteRolloutError(value)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum LedgerSuiteHealthError {
+    LedgerNotFound(TokenId),
+    CanisterUnreachable {
+        canister_id: Principal,
+        error: CallError,
+    },
+    LedgerLogChangedDuringCheck {
+        ledger_id: Principal,
+    },
+    LedgerLogLengthDecreased {
+        ledger_id: Principal,
+        before: Nat,
+        after: Nat,
+    },
+    BlocksNotFound {
+        ledger_id: Principal,
+        block_index: Nat,
+    },
+    InvalidBlock {
+        ledger_id: Principal,
+        block_index: Nat,
+        reason: String,
+    },
+    TotalSupplyMismatch {
+        ledger_id: Principal,
+        before: Nat,
+        minted: Nat,
+        burned: Nat,
+        after: Nat,
+    },
+    IndexNotSynced {
+        index_id: Principal,
+        num_blocks_synced: Nat,
+        expected: Nat,
+    },
+}
+
+impl LedgerSuiteHealthError {
+    /// Whether the ledger suite may still become healthy, e.g., once the index caught up with the ledger.
+    fn is_transient(&self) -> bool {
+        match self {
+            LedgerSuiteHealthError::LedgerNotFound(_) => false,
+            LedgerSuiteHealthError::CanisterUnreachable { .. } => true,
+            LedgerSuiteHealthError::LedgerLogChangedDuringCheck { .. } => true,
+            LedgerSuiteHealthError::LedgerLogLengthDecreased { .. } => false,
+            LedgerSuiteHealthError::BlocksNotFound { .. } => true,
+            LedgerSuiteHealthError::InvalidBlock { .. } => false,
+            LedgerSuiteHealthError::TotalSupplyMismatch { .. } => false,
+            LedgerSuiteHealthError::IndexNotSynced { .. } => true,
+        }
+    }
+}
+
 fn is_recoverable(e: &CallError) -> bool {
     match &e.reason {
         Reason::OutOfCycles => true,
-        Reason::CanisterError(msg) => msg.ends_with("is stopped") || msg.ends_with("is stopping"),
+        Reason::CanisterError(_) => is_stopped(e),
         Reason::Rejected(_) => false,
         Reason::TransientInternalError(_) => true,
         Reason::InternalError(_) => false,
     }
 }
 
+/// Whether the call failed because the called canister is stopped or stopping.
+fn is_stopped(e: &CallError) -> bool {
+    match &e.reason {
+        Reason::CanisterError(msg) => msg.ends_with("is stopped") || msg.ends_with("is stopping"),
+        _ => false,
+    }
+}
+
 impl TaskExecution {
     pub async fn execute<R: CanisterRuntime>(&self, runtime: &R) -> Result<(), TaskError> {
         match &self.task_type {
@@ -703,6 +891,7 @@ impl TaskExecution {
             } => notify_erc20_added(erc20_token, minter_id, runtime).await,
             Task::DiscoverArchives => Ok(discover_archives(select_all(), runtime).await?),
             Task::UpgradeLedgerSuite(upgrade) => Ok(upgrade_ledger_suite(upgrade, runtime).await?),
+            Task::UpgradeLedgerSuiteRollout => Ok(upgrade_ledger_suite_rollout(runtime).await?),
         }
     }
 }
@@ -823,6 +1012,8 @@ async fn install_ledger_suite<R: CanisterRuntime>(
         args.contract.clone(),
         CanistersMetadata {
             token_symbol: args.ledger_init_arg.token_symbol.clone(),
+            balance_diff_accounting: args.balance_diff_accounting,
+            decimals: Some(args.ledger_init_arg.decimals),
         },
     );
     let CyclesManagement {
@@ -859,6 +1050,7 @@ async fn install_ledger_suite<R: CanisterRuntime>(
     let index_arg = Some(IndexArg::Init(IndexInitArg {
         ledger_id: ledger_canister_id,
         retrieve_blocks_from_ledger_interval_seconds: None,
+        bootstrap_from_ledger_snapshot: None,
     }));
     install_canister_once::<Index, _, _>(
         &args.contract,
@@ -1125,6 +1317,8 @@ async fn notify_erc20_added<R: CanisterRuntime>(
                 address: token.address().to_string(),
                 ckerc20_token_symbol: metadata.token_symbol,
                 ckerc20_ledger_id: *ledger.canister_id(),
+                balance_diff_accounting: Some(metadata.balance_diff_accounting),
+                decimals: metadata.decimals,
             };
             runtime
                 .call_canister(*minter_id, "add_ckerc20_token", args)
@@ -1218,16 +1412,67 @@ async fn upgrade_ledger_suite<R: CanisterRuntime>(
     upgrade_ledger_suite: &UpgradeLedgerSuite,
     runtime: &R,
 ) -> Result<(), UpgradeLedgerSuiteError> {
+    let rollout = upgrade_ledger_suite.rollout;
     let mut upgrade_ledger_suite = upgrade_ledger_suite.clone();
     if let Some(subtask) = upgrade_ledger_suite.next() {
-        subtask.execute(runtime).await?;
+        if rollout && rollout_upgrade_failed(subtask.token_id()) {
+            log!(
+                INFO,
+                "Upgrade of {:?} already failed during rollout. Dropping remaining upgrade tasks.",
+                subtask.token_id()
+            );
+            return Ok(());
+        }
+        match subtask.execute(rollout, runtime).await {
+            Ok(()) => {}
+            Err(e) if rollout && e.aborts_rollout() => {
+                let token_id = subtask.token_id().clone();
+                log!(
+                    INFO,
+                    "Upgrade of {:?} failed during rollout: {:?}. Batch will be rolled back.",
+                    token_id,
+                    e
+                );
+                record_rollout_upgrade_status(
+                    &token_id,
+                    LedgerSuiteUpgradeStatus::Failed {
+                        reason: format!("{:?}", e),
+                    },
+                );
+                return Err(UpgradeLedgerSuiteError::RolloutAborted {
+                    token_id,
+                    error: Box::new(e),
+                });
+            }
+            Err(e) => return Err(e),
+        }
         if upgrade_ledger_suite.len() > 0 {
             schedule_now(Task::UpgradeLedgerSuite(upgrade_ledger_suite), runtime);
+        } else if rollout {
+            record_rollout_upgrade_status(subtask.token_id(), LedgerSuiteUpgradeStatus::Upgraded);
         }
     }
     Ok(())
 }
 
+fn rollout_upgrade_failed(token_id: &TokenId) -> bool {
+    read_state(|s| {
+        s.ledger_suite_rollout()
+            .and_then(|rollout| rollout.ledger_suite(token_id))
+            .map(|progress| matches!(progress.status, LedgerSuiteUpgradeStatus::Failed { .. }))
+            .unwrap_or(false)
+    })
+}
+
+fn record_rollout_upgrade_status(token_id: &TokenId, status: LedgerSuiteUpgradeStatus) {
+    mutate_state(|s| {
+        s.ledger_suite_rollout_mut()
+            .and_then(|rollout| rollout.ledger_suite_mut(token_id))
+            .expect("BUG: ledger suite is not part of the rollout")
+            .status = status
+    });
+}
+
 fn ensure_canister_is_installed<T>(
     token_id: &TokenId,
     canister: Option<Canister<T>>,
@@ -1259,6 +1504,7 @@ fn ensure_canister_is_installed<T>(
 async fn upgrade_canister<T: StorableWasm, R: CanisterRuntime>(
     canister_id: Principal,
     wasm_hash: &WasmHash,
+    snapshot_for: Option<&TokenId>,
     runtime: &R,
 ) -> Result<(), UpgradeLedgerSuiteError> {
     let wasm = match read_wasm_store(|s| wasm_store_try_get::<T>(s, wasm_hash)) {
@@ -1273,6 +1519,10 @@ async fn upgrade_canister<T: StorableWasm, R: CanisterRuntime>(
         .await
         .map_err(UpgradeLedgerSuiteError::StopCanisterError)?;
 
+    if let Some(token_id) = snapshot_for {
+        take_canister_snapshot_once(token_id, canister_id, runtime).await?;
+    }
+
     log!(
         DEBUG,
         "Upgrading wasm module of canister {} to {}",
@@ -1299,6 +1549,760 @@ async fn upgrade_canister<T: StorableWasm, R: CanisterRuntime>(
     Ok(())
 }
 
+/// Takes a snapshot of the given (stopped) canister, unless one was already taken during the current rollout,
+/// for example, when retrying a failed upgrade, since the canister may already have been upgraded.
+async fn take_canister_snapshot_once<R: CanisterRuntime>(
+    token_id: &TokenId,
+    canister_id: Principal,
+    runtime: &R,
+) -> Result<(), UpgradeLedgerSuiteError> {
+    let snapshot_id = read_state(|s| {
+        s.ledger_suite_rollout()
+            .and_then(|rollout| rollout.ledger_suite(token_id))
+            .and_then(|progress| progress.snapshots.get(&canister_id).cloned())
+    });
+    if let Some(snapshot_id) = snapshot_id {
+        log!(
+            DEBUG,
+            "Snapshot {} of canister {} already taken",
+            snapshot_id,
+            canister_id
+        );
+        return Ok(());
+    }
+
+    log!(DEBUG, "Taking snapshot of canister {}", canister_id);
+    let snapshot_id = runtime
+        .take_canister_snapshot(canister_id)
+        .await
+        .map_err(UpgradeLedgerSuiteError::TakeCanisterSnapshotError)?;
+    mutate_state(|s| {
+        s.ledger_suite_rollout_mut()
+            .and_then(|rollout| rollout.ledger_suite_mut(token_id))
+            .expect("BUG: ledger suite is not part of the rollout")
+            .snapshots
+            .insert(canister_id, snapshot_id)
+    });
+    Ok(())
+}
+
+async fn upgrade_ledger_suite_rollout<R: CanisterRuntime>(
+    runtime: &R,
+) -> Result<(), UpgradeLedgerSuiteRolloutError> {
+    let current_batch = read_state(|s| {
+        s.ledger_suite_rollout().and_then(|rollout| {
+            rollout
+                .current_batch_index()
+                .map(|index| (index, rollout.batches()[index].clone()))
+        })
+    });
+    let (batch_index, batch) = match current_batch {
+        Some(current_batch) => current_batch,
+        None => {
+            log!(
+                INFO,
+                "[upgrade_ledger_suite_rollout]: no ledger suite rollout in progress"
+            );
+            return Ok(());
+        }
+    };
+    match batch.status.clone() {
+        RolloutBatchStatus::Pending => start_rollout_batch(batch_index, &batch, runtime).await,
+        RolloutBatchStatus::Upgrading { since } => {
+            check_rollout_batch_upgraded(batch_index, &batch, since, runtime);
+            Ok(())
+        }
+        RolloutBatchStatus::CheckingHealth { since } => {
+            check_rollout_batch_health(batch_index, &batch, since, runtime).await;
+            Ok(())
+        }
+        RolloutBatchStatus::RollingBack { reason } => {
+            roll_back_rollout_batch(batch_index, &batch, reason, runtime).await
+        }
+        RolloutBatchStatus::Healthy | RolloutBatchStatus::RolledBack { .. } => {
+            panic!("BUG: batch {} is not in progress", batch_index)
+        }
+    }
+}
+
+fn mutate_current_rollout_batch<F: FnOnce(&mut RolloutBatch)>(f: F) {
+    mutate_state(|s| {
+        f(s.ledger_suite_rollout_mut()
+            .and_then(|rollout| rollout.current_batch_mut())
+            .expect("BUG: no ledger suite rollout in progress"))
+    })
+}
+
+/// Records the health of the ledger suites of the batch before upgrading them.
+async fn start_rollout_batch<R: CanisterRuntime>(
+    batch_index: usize,
+    batch: &RolloutBatch,
+    runtime: &R,
+) -> Result<(), UpgradeLedgerSuiteRolloutError> {
+    let token_ids: Vec<_> = batch.ledger_suites.keys().cloned().collect();
+    let results = future::join_all(token_ids.iter().map(|token_id| async move {
+        let ledger_id = managed_ledger_id(token_id)?;
+        ledger_health(ledger_id, runtime).await
+    }))
+    .await;
+    let baselines = results
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(UpgradeLedgerSuiteRolloutError::BaselineError)?;
+
+    log!(
+        INFO,
+        "[upgrade_ledger_suite_rollout]: upgrading batch {} with ledger suites {:?}",
+        batch_index,
+        token_ids
+    );
+    let upgrade = read_state(|s| {
+        s.ledger_suite_rollout()
+            .map(|rollout| rollout.upgrade().clone())
+            .expect("BUG: no ledger suite rollout in progress")
+    });
+    mutate_current_rollout_batch(|batch| {
+        for (token_id, baseline) in token_ids.iter().zip(baselines) {
+            batch
+                .ledger_suites
+                .get_mut(token_id)
+                .expect("BUG: ledger suite is not part of the batch")
+                .baseline = Some(baseline);
+        }
+        batch.status = RolloutBatchStatus::Upgrading {
+            since: runtime.time(),
+        };
+    });
+    for token_id in token_ids {
+        schedule_now(
+            Task::UpgradeLedgerSuite(upgrade.clone().into_builder(token_id).rollout(true).build()),
+            runtime,
+        );
+    }
+    schedule_after(
+        ROLLOUT_CHECK_INTERVAL,
+        Task::UpgradeLedgerSuiteRollout,
+        runtime,
+    );
+    Ok(())
+}
+
+/// Waits until all ledger suites of the batch were either upgraded or failed to upgrade,
+/// so that no upgrade is still running when the batch is checked or rolled back.
+///
+/// Ledger suites that are still being upgraded after [`ROLLOUT_UPGRADE_TIMEOUT`] are considered failed,
+/// so that the batch is rolled back and their remaining upgrade tasks are dropped.
+fn check_rollout_batch_upgraded<R: CanisterRuntime>(
+    batch_index: usize,
+    batch: &RolloutBatch,
+    since: u64,
+    runtime: &R,
+) {
+    let pending: Vec<_> = batch
+        .ledger_suites
+        .iter()
+        .filter(|(_, progress)| progress.status == LedgerSuiteUpgradeStatus::Pending)
+        .map(|(token_id, _)| token_id.clone())
+        .collect();
+    if !pending.is_empty() {
+        let deadline = since.saturating_add(ROLLOUT_UPGRADE_TIMEOUT.as_nanos() as u64);
+        if runtime.time() < deadline {
+            schedule_after(
+                ROLLOUT_CHECK_INTERVAL,
+                Task::UpgradeLedgerSuiteRollout,
+                runtime,
+            );
+            return;
+        }
+        log!(
+            INFO,
+            "[upgrade_ledger_suite_rollout]: upgrade of {:?} in batch {} timed out",
+            pending,
+            batch_index
+        );
+        for token_id in &pending {
+            record_rollout_upgrade_status(
+                token_id,
+                LedgerSuiteUpgradeStatus::Failed {
+                    reason: "upgrade timed out".to_string(),
+                },
+            );
+        }
+    }
+    let batch = read_state(|s| {
+        s.ledger_suite_rollout()
+            .and_then(|rollout| rollout.batches().get(batch_index).cloned())
+            .expect("BUG: no ledger suite rollout in progress")
+    });
+    let failures: Vec<_> = batch
+        .ledger_suites
+        .iter()
+        .filter_map(|(token_id, progress)| match &progress.status {
+            LedgerSuiteUpgradeStatus::Failed { reason } => {
+                Some(format!("failed to upgrade {:?}: {}", token_id, reason))
+            }
+            _ => None,
+        })
+        .collect();
+    let status = if failures.is_empty() {
+        RolloutBatchStatus::CheckingHealth {
+            since: runtime.time(),
+        }
+    } else {
+        RolloutBatchStatus::RollingBack {
+            reason: failures.join("; "),
+        }
+    };
+    log!(
+        INFO,
+        "[upgrade_ledger_suite_rollout]: batch {} upgraded. New status: {:?}",
+        batch_index,
+        status
+    );
+    mutate_current_rollout_batch(|batch| batch.status = status);
+    schedule_now(Task::UpgradeLedgerSuiteRollout, runtime);
+}
+
+/// Checks that for each ledger suite of the batch:
+/// 1. the ledger did not lose any block and its total supply only changed by the amounts
+///    minted and burned by the blocks recorded since the upgrade;
+/// 2. the index synced at least all blocks that existed before the upgrade;
+/// 3. all archives are reachable.
+///
+/// The batch is rolled back if a check definitely fails or if the ledger suites
+/// are not healthy within [`ROLLOUT_HEALTH_CHECK_TIMEOUT`].
+async fn check_rollout_batch_health<R: CanisterRuntime>(
+    batch_index: usize,
+    batch: &RolloutBatch,
+    since: u64,
+    runtime: &R,
+) {
+    let results = future::join_all(batch.ledger_suites.iter().map(
+        |(token_id, progress)| async move {
+            let baseline = progress
+                .baseline
+                .as_ref()
+                .expect("BUG: missing health baseline of upgraded ledger suite");
+            check_ledger_suite_health(token_id, baseline, runtime)
+                .await
+                .map_err(|e| (token_id.clone(), e))
+        },
+    ))
+    .await;
+    let errors: Vec<_> = results.into_iter().filter_map(Result::err).collect();
+
+    if errors.is_empty() {
+        log!(
+            INFO,
+            "[upgrade_ledger_suite_rollout]: batch {} is healthy",
+            batch_index
+        );
+        for (token_id, progress) in &batch.ledger_suites {
+            for (canister_id, snapshot_id) in &progress.snapshots {
+                delete_canister_snapshot(token_id, *canister_id, snapshot_id.clone(), runtime)
+                    .await;
+            }
+        }
+        mutate_current_rollout_batch(|batch| batch.status = RolloutBatchStatus::Healthy);
+        if batch_index == 0 {
+            // the canary is healthy, so that new ledger suites can be created with the new version.
+            mutate_state(|s| {
+                let new_version = s
+                    .ledger_suite_rollout()
+                    .map(|rollout| rollout.new_version())
+                    .expect("BUG: no ledger suite rollout in progress");
+                s.update_ledger_suite_version(new_version)
+            });
+        }
+        match read_state(|s| {
+            s.ledger_suite_rollout()
+                .and_then(|r| r.current_batch_index())
+        }) {
+            Some(_) => schedule_now(Task::UpgradeLedgerSuiteRollout, runtime),
+            None => log!(
+                INFO,
+                "[upgrade_ledger_suite_rollout]: all ledger suites were upgraded"
+            ),
+        }
+        return;
+    }
+
+    let deadline = since.saturating_add(ROLLOUT_HEALTH_CHECK_TIMEOUT.as_nanos() as u64);
+    if runtime.time() < deadline && errors.iter().all(|(_, e)| e.is_transient()) {
+        log!(
+            INFO,
+            "[upgrade_ledger_suite_rollout]: batch {} not yet healthy: {:?}. Will check again later.",
+            batch_index,
+            errors
+        );
+        schedule_after(
+            ROLLOUT_CHECK_INTERVAL,
+            Task::UpgradeLedgerSuiteRollout,
+            runtime,
+        );
+        return;
+    }
+
+    let reason = errors
+        .iter()
+        .map(|(token_id, e)| format!("{:?} is unhealthy: {:?}", token_id, e))
+        .collect::<Vec<_>>()
+        .join("; ");
+    log!(
+        INFO,
+        "[upgrade_ledger_suite_rollout]: batch {} is unhealthy and will be rolled back: {}",
+        batch_index,
+        reason
+    );
+    mutate_current_rollout_batch(|batch| batch.status = RolloutBatchStatus::RollingBack { reason });
+    schedule_now(Task::UpgradeLedgerSuiteRollout, runtime);
+}
+
+async fn check_ledger_suite_health<R: CanisterRuntime>(
+    token_id: &TokenId,
+    baseline: &LedgerHealth,
+    runtime: &R,
+) -> Result<(), LedgerSuiteHealthError> {
+    let canisters = read_state(|s| s.managed_canisters(token_id).cloned())
+        .ok_or(LedgerSuiteHealthError::LedgerNotFound(token_id.clone()))?;
+    let ledger_id = canisters
+        .ledger_canister_id()
+        .cloned()
+        .ok_or(LedgerSuiteHealthError::LedgerNotFound(token_id.clone()))?;
+
+    let health = ledger_health(ledger_id, runtime).await?;
+    if health.log_length < baseline.log_length {
+        return Err(LedgerSuiteHealthError::LedgerLogLengthDecreased {
+            ledger_id,
+            before: baseline.log_length.clone(),
+            after: health.log_length,
+        });
+    }
+    let changes = if health.log_length > baseline.log_length {
+        supply_changes(
+            ledger_id,
+            baseline.log_length.clone(),
+            health.log_length.clone(),
+            runtime,
+        )
+        .await?
+    } else {
+        SupplyChanges::default()
+    };
+    if health.total_supply.clone() + changes.burned.clone()
+        != baseline.total_supply.clone() + changes.minted.clone()
+    {
+        return Err(LedgerSuiteHealthError::TotalSupplyMismatch {
+            ledger_id,
+            before: baseline.total_supply.clone(),
+            minted: changes.minted,
+            burned: changes.burned,
+            after: health.total_supply,
+        });
+    }
+
+    if let Some(index_id) = canisters.index_canister_id().cloned() {
+        let status: IndexStatus = runtime
+            .call_canister(index_id, "status", ())
+            .await
+            .map_err(|error| LedgerSuiteHealthError::CanisterUnreachable {
+                canister_id: index_id,
+                error,
+            })?;
+        if status.num_blocks_synced < baseline.log_length {
+            return Err(LedgerSuiteHealthError::IndexNotSynced {
+                index_id,
+                num_blocks_synced: status.num_blocks_synced,
+                expected: baseline.log_length.clone(),
+            });
+        }
+    }
+
+    for archive_id in canisters.archive_canister_ids() {
+        call_icrc3_log_length(*archive_id, runtime).await?;
+    }
+    Ok(())
+}
+
+fn managed_ledger_id(token_id: &TokenId) -> Result<Principal, LedgerSuiteHealthError> {
+    read_state(|s| {
+        s.managed_canisters(token_id)
+            .and_then(|canisters| canisters.ledger_canister_id().cloned())
+    })
+    .ok_or(LedgerSuiteHealthError::LedgerNotFound(token_id.clone()))
+}
+
+/// Retrieves the total supply of the ledger together with the length of its block log.
+/// Since both values cannot be retrieved atomically, the log length is retrieved before and after
+/// the total supply to ensure that no block was recorded in between.
+async fn ledger_health<R: CanisterRuntime>(
+    ledger_id: Principal,
+    runtime: &R,
+) -> Result<LedgerHealth, LedgerSuiteHealthError> {
+    let log_length = call_icrc3_log_length(ledger_id, runtime).await?;
+    let total_supply: Nat = runtime
+        .call_canister(ledger_id, "icrc1_total_supply", ())
+        .await
+        .map_err(|error| LedgerSuiteHealthError::CanisterUnreachable {
+            canister_id: ledger_id,
+            error,
+        })?;
+    if call_icrc3_log_length(ledger_id, runtime).await? != log_length {
+        return Err(LedgerSuiteHealthError::LedgerLogChangedDuringCheck { ledger_id });
+    }
+    Ok(LedgerHealth {
+        total_supply,
+        log_length,
+    })
+}
+
+async fn call_icrc3_log_length<R: CanisterRuntime>(
+    canister_id: Principal,
+    runtime: &R,
+) -> Result<Nat, LedgerSuiteHealthError> {
+    let result: GetBlocksResult = runtime
+        .call_canister(
+            canister_id,
+            "icrc3_get_blocks",
+            Vec::<GetBlocksRequest>::new(),
+        )
+        .await
+        .map_err(|error| LedgerSuiteHealthError::CanisterUnreachable { canister_id, error })?;
+    Ok(result.log_length)
+}
+
+/// Total amounts of tokens minted and burned by a range of ledger blocks.
+#[derive(Clone, PartialEq, Debug, Default)]
+struct SupplyChanges {
+    minted: Nat,
+    burned: Nat,
+}
+
+impl SupplyChanges {
+    /// Records the tokens minted or burned by the given ICRC-3 block.
+    /// Fees are burned, unless the fee of a transfer is credited to a fee collector.
+    fn record(&mut self, block: &ICRC3Value) -> Result<(), String> {
+        let block = match block {
+            ICRC3Value::Map(block) => block,
+            _ => return Err("block is not a map".to_string()),
+        };
+        let tx = match block.get("tx") {
+            Some(ICRC3Value::Map(tx)) => tx,
+            _ => return Err("missing transaction".to_string()),
+        };
+        let op = match tx.get("op") {
+            Some(ICRC3Value::Text(op)) => op.as_str(),
+            _ => return Err("missing operation".to_string()),
+        };
+        let amount = || match tx.get("amt") {
+            Some(ICRC3Value::Nat(amount)) => Ok(amount.clone()),
+            _ => Err(format!("missing amount of {} operation", op)),
+        };
+        let fee = || match tx.get("fee").or_else(|| block.get("fee")) {
+            Some(ICRC3Value::Nat(fee)) => Ok(fee.clone()),
+            None => Ok(Nat::from(0_u8)),
+            Some(_) => Err(format!("invalid fee of {} operation", op)),
+        };
+        match op {
+            "mint" => self.minted += amount()?,
+            "burn" => self.burned += amount()?,
+            "xfer" => {
+                let has_fee_collector =
+                    block.contains_key("fee_col") || block.contains_key("fee_col_block");
+                if !has_fee_collector {
+                    self.burned += fee()?;
+                }
+            }
+            "approve" | "spend_policy" | "revoke_all" => self.burned += fee()?,
+            _ => return Err(format!("unknown operation {}", op)),
+        }
+        Ok(())
+    }
+}
+
+/// Adds up the tokens minted and burned by the ledger blocks in the range `[start, end)`,
+/// including the blocks that were already moved to an archive.
+async fn supply_changes<R: CanisterRuntime>(
+    ledger_id: Principal,
+    start: Nat,
+    end: Nat,
+    runtime: &R,
+) -> Result<SupplyChanges, LedgerSuiteHealthError> {
+    let mut changes = SupplyChanges::default();
+    let mut next = start;
+    while next < end {
+        let mut blocks = get_blocks(ledger_id, next.clone(), end.clone() - next.clone(), runtime)
+            .await?
+            .into_iter()
+            .map(|block| (block.id, block.block))
+            .collect::<BTreeMap<_, _>>();
+        let first = next.clone();
+        while next < end {
+            let block = match blocks.remove(&next) {
+                Some(block) => block,
+                None => break,
+            };
+            changes
+                .record(&block)
+                .map_err(|reason| LedgerSuiteHealthError::InvalidBlock {
+                    ledger_id,
+                    block_index: next.clone(),
+                    reason,
+                })?;
+            next += Nat::from(1_u8);
+        }
+        if next == first {
+            return Err(LedgerSuiteHealthError::BlocksNotFound {
+                ledger_id,
+                block_index: next,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+async fn get_blocks<R: CanisterRuntime>(
+    ledger_id: Principal,
+    start: Nat,
+    length: Nat,
+    runtime: &R,
+) -> Result<Vec<BlockWithId>, LedgerSuiteHealthError> {
+    let result: GetBlocksResult = runtime
+        .call_canister(
+            ledger_id,
+            "icrc3_get_blocks",
+            vec![GetBlocksRequest { start, length }],
+        )
+        .await
+        .map_err(|error| LedgerSuiteHealthError::CanisterUnreachable {
+            canister_id: ledger_id,
+            error,
+        })?;
+    let mut blocks = result.blocks;
+    for archived in result.archived_blocks {
+        let canister_id = archived.callback.canister_id;
+        let archived_result: GetBlocksResult = runtime
+            .call_canister(canister_id, &archived.callback.method, archived.args)
+            .await
+            .map_err(|error| LedgerSuiteHealthError::CanisterUnreachable { canister_id, error })?;
+        blocks.extend(archived_result.blocks);
+    }
+    Ok(blocks)
+}
+
+/// Rolls back the canisters of the batch to the previous ledger suite version,
+/// either by restoring them from their snapshots or by downgrading them, see [`rollback_method`].
+/// When restoring, the ledger is restored last.
+async fn roll_back_rollout_batch<R: CanisterRuntime>(
+    batch_index: usize,
+    batch: &RolloutBatch,
+    reason: String,
+    runtime: &R,
+) -> Result<(), UpgradeLedgerSuiteRolloutError> {
+    log!(
+        INFO,
+        "[upgrade_ledger_suite_rollout]: rolling back batch {}: {}",
+        batch_index,
+        reason
+    );
+    let previous_version = read_state(|s| {
+        s.ledger_suite_rollout()
+            .map(|rollout| rollout.previous_version().clone())
+            .expect("BUG: no ledger suite rollout in progress")
+    });
+    for (token_id, progress) in &batch.ledger_suites {
+        if progress.snapshots.is_empty() {
+            continue;
+        }
+        let canisters = read_state(|s| s.managed_canisters(token_id).cloned())
+            .expect("BUG: ledger suite of rollout is not managed");
+        let method = match progress.rollback {
+            Some(method) => method,
+            None => {
+                let method = rollback_method(&canisters, progress, runtime).await;
+                log!(
+                    INFO,
+                    "[upgrade_ledger_suite_rollout]: rolling back {:?} with {:?}",
+                    token_id,
+                    method
+                );
+                mutate_state(|s| {
+                    s.ledger_suite_rollout_mut()
+                        .and_then(|rollout| rollout.ledger_suite_mut(token_id))
+                        .expect("BUG: ledger suite is not part of the rollout")
+                        .rollback = Some(method)
+                });
+                method
+            }
+        };
+        match method {
+            LedgerSuiteRollback::RestoreSnapshots => {
+                let ledger_id = canisters.ledger_canister_id().cloned();
+                let mut snapshots: Vec<_> = progress.snapshots.clone().into_iter().collect();
+                snapshots.sort_by_key(|(canister_id, _)| Some(*canister_id) == ledger_id);
+                for (canister_id, snapshot_id) in snapshots {
+                    restore_canister_snapshot(canister_id, snapshot_id.clone(), runtime).await?;
+                    delete_canister_snapshot(token_id, canister_id, snapshot_id, runtime).await;
+                }
+            }
+            LedgerSuiteRollback::Downgrade => {
+                for (canister_id, snapshot_id) in &progress.snapshots {
+                    downgrade_canister(*canister_id, &canisters, &previous_version, runtime)
+                        .await?;
+                    delete_canister_snapshot(token_id, *canister_id, snapshot_id.clone(), runtime)
+                        .await;
+                }
+            }
+        }
+    }
+
+    log!(
+        INFO,
+        "[upgrade_ledger_suite_rollout]: batch {} rolled back: {}",
+        batch_index,
+        reason
+    );
+    mutate_state(|s| {
+        let rollout = s
+            .ledger_suite_rollout_mut()
+            .expect("BUG: no ledger suite rollout in progress");
+        let previous_version = rollout.previous_version().clone();
+        rollout
+            .current_batch_mut()
+            .expect("BUG: no ledger suite rollout in progress")
+            .status = RolloutBatchStatus::RolledBack { reason };
+        s.update_ledger_suite_version(previous_version);
+    });
+    Ok(())
+}
+
+/// Upgrades the given canister of the ledger suite to its wasm in the previous ledger suite version.
+async fn downgrade_canister<R: CanisterRuntime>(
+    canister_id: Principal,
+    canisters: &Canisters,
+    previous_version: &LedgerSuiteVersion,
+    runtime: &R,
+) -> Result<(), UpgradeLedgerSuiteRolloutError> {
+    let result = if Some(&canister_id) == canisters.ledger_canister_id() {
+        upgrade_canister::<Ledger, _>(
+            canister_id,
+            &previous_version.ledger_compressed_wasm_hash,
+            None,
+            runtime,
+        )
+        .await
+    } else if Some(&canister_id) == canisters.index_canister_id() {
+        upgrade_canister::<Index, _>(
+            canister_id,
+            &previous_version.index_compressed_wasm_hash,
+            None,
+            runtime,
+        )
+        .await
+    } else {
+        upgrade_canister::<Archive, _>(
+            canister_id,
+            &previous_version.archive_compressed_wasm_hash,
+            None,
+            runtime,
+        )
+        .await
+    };
+    result.map_err(UpgradeLedgerSuiteRolloutError::DowngradeCanisterError)
+}
+
+/// Restoring a ledger or its archives from their snapshots would lose any block recorded since their upgrade.
+/// Since a running ledger may record a new block at any time, even right before it is stopped to be restored,
+/// the canisters of a ledger suite are only restored from their snapshots when the ledger was stopped
+/// to be upgraded and did not restart since, or when only the index was upgraded.
+/// Otherwise, they are downgraded to the wasms of the previous ledger suite version.
+async fn rollback_method<R: CanisterRuntime>(
+    canisters: &Canisters,
+    progress: &LedgerSuiteUpgradeProgress,
+    runtime: &R,
+) -> LedgerSuiteRollback {
+    let index_id = canisters.index_canister_id().cloned();
+    let only_index_upgraded = progress
+        .snapshots
+        .keys()
+        .all(|canister_id| Some(*canister_id) == index_id);
+    if only_index_upgraded {
+        return LedgerSuiteRollback::RestoreSnapshots;
+    }
+    let ledger_stopped = match canisters.ledger_canister_id() {
+        Some(ledger_id) => matches!(
+            call_icrc3_log_length(*ledger_id, runtime).await,
+            Err(LedgerSuiteHealthError::CanisterUnreachable { error, .. }) if is_stopped(&error)
+        ),
+        None => false,
+    };
+    if ledger_stopped {
+        LedgerSuiteRollback::RestoreSnapshots
+    } else {
+        LedgerSuiteRollback::Downgrade
+    }
+}
+
+async fn restore_canister_snapshot<R: CanisterRuntime>(
+    canister_id: Principal,
+    snapshot_id: SnapshotId,
+    runtime: &R,
+) -> Result<(), UpgradeLedgerSuiteRolloutError> {
+    log!(DEBUG, "Stopping canister {}", canister_id);
+    runtime
+        .stop_canister(canister_id)
+        .await
+        .map_err(UpgradeLedgerSuiteRolloutError::StopCanisterError)?;
+
+    log!(
+        DEBUG,
+        "Loading snapshot {} of canister {}",
+        snapshot_id,
+        canister_id
+    );
+    runtime
+        .load_canister_snapshot(canister_id, snapshot_id)
+        .await
+        .map_err(UpgradeLedgerSuiteRolloutError::LoadCanisterSnapshotError)?;
+
+    log!(DEBUG, "Starting canister {}", canister_id);
+    runtime
+        .start_canister(canister_id)
+        .await
+        .map_err(UpgradeLedgerSuiteRolloutError::StartCanisterError)
+}
+
+/// Deletes a snapshot taken during the rollout and forgets about it.
+/// Deleting is best effort: a leftover snapshot does not affect the canister,
+/// but must be deleted manually before the canister can be part of another rollout.
+async fn delete_canister_snapshot<R: CanisterRuntime>(
+    token_id: &TokenId,
+    canister_id: Principal,
+    snapshot_id: SnapshotId,
+    runtime: &R,
+) {
+    if let Err(e) = runtime
+        .delete_canister_snapshot(canister_id, snapshot_id.clone())
+        .await
+    {
+        log!(
+            INFO,
+            "[upgrade_ledger_suite_rollout]: failed to delete snapshot {} of canister {}: {:?}",
+            snapshot_id,
+            canister_id,
+            e
+        );
+    }
+    mutate_state(|s| {
+        if let Some(progress) = s
+            .ledger_suite_rollout_mut()
+            .and_then(|rollout| rollout.ledger_suite_mut(token_id))
+        {
+            progress.snapshots.remove(&canister_id);
+        }
+    });
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum InvalidManageInstalledCanistersError {
     WasmHashError(WasmHashError),
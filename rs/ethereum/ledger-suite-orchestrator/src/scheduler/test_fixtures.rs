@@ -20,6 +20,8 @@ pub fn dai() -> Erc20Token {
 pub fn dai_metadata() -> CanistersMetadata {
     CanistersMetadata {
         token_symbol: "ckDAI".to_string(),
+        balance_diff_accounting: false,
+        decimals: Some(18),
     }
 }
 
@@ -39,6 +41,8 @@ pub fn usdc_token_id() -> TokenId {
 pub fn usdc_metadata() -> CanistersMetadata {
     CanistersMetadata {
         token_symbol: "ckUSDC".to_string(),
+        balance_diff_accounting: false,
+        decimals: Some(6),
     }
 }
 
@@ -77,6 +81,8 @@ pub fn usdt_token_id() -> TokenId {
 pub fn usdt_metadata() -> CanistersMetadata {
     CanistersMetadata {
         token_symbol: "ckUSDT".to_string(),
+        balance_diff_accounting: false,
+        decimals: Some(6),
     }
 }
 pub fn cketh_token_symbol() -> TokenSymbol {
@@ -100,6 +106,8 @@ pub fn cketh_ledger_suite() -> Canisters {
         archives: vec!["xob7s-iqaaa-aaaar-qacra-cai".parse().unwrap()],
         metadata: CanistersMetadata {
             token_symbol: cketh_token_symbol().to_string(),
+            balance_diff_accounting: false,
+            decimals: None,
         },
     }
 }
@@ -457,6 +457,8 @@ mod notify_erc_20_added {
                 address: usdc.address().to_string(),
                 ckerc20_token_symbol: usdc_metadata.token_symbol,
                 ckerc20_ledger_id: LEDGER_PRINCIPAL,
+                balance_diff_accounting: Some(false),
+                decimals: usdc_metadata.decimals,
             },
             Ok(()),
         );
@@ -554,6 +556,8 @@ mod notify_erc_20_added {
                     address: usdc.address().to_string(),
                     ckerc20_token_symbol: usdc_metadata.token_symbol.clone(),
                     ckerc20_ledger_id: LEDGER_PRINCIPAL,
+                    balance_diff_accounting: Some(false),
+                    decimals: usdc_metadata.decimals,
                 },
                 Ok(()),
             );
@@ -1208,6 +1212,1039 @@ mod upgrade_ledger_suite {
     }
 }
 
+mod upgrade_ledger_suite_rollout {
+    use crate::candid::RolloutArg;
+    use crate::management::{CallError, Reason, SnapshotId};
+    use crate::scheduler::test_fixtures::{
+        dai, dai_metadata, usdc, usdc_metadata, usdc_token_id, usdt, usdt_metadata, usdt_token_id,
+    };
+    use crate::scheduler::tests::{
+        execute_now, init_state, mock::MockCanisterRuntime, read_archive_wasm_hash,
+        read_index_wasm_hash, read_ledger_wasm_hash, task_queue_from_state, INDEX_PRINCIPAL,
+        LEDGER_PRINCIPAL,
+    };
+    use crate::scheduler::{
+        LedgerSuiteHealthError, Task, TaskError, UpgradeLedgerSuite, UpgradeLedgerSuiteError,
+        UpgradeLedgerSuiteRolloutError, UpgradeOrchestratorArgs, ROLLOUT_HEALTH_CHECK_TIMEOUT,
+        ROLLOUT_UPGRADE_TIMEOUT,
+    };
+    use crate::state::test_fixtures::new_state;
+    use crate::state::{
+        mutate_state, read_state, Index, InvalidRolloutArgError, Ledger, LedgerHealth,
+        LedgerSuiteRollback, LedgerSuiteRollout, LedgerSuiteUpgradeProgress,
+        LedgerSuiteUpgradeStatus, LedgerSuiteVersion, RolloutBatchStatus, TokenId, WasmHash,
+        INDEX_BYTECODE, LEDGER_BYTECODE,
+    };
+    use candid::{Nat, Principal};
+    use ic_icrc1_index_ng::Status as IndexStatus;
+    use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+    use icrc_ledger_types::icrc3::archive::QueryArchiveFn;
+    use icrc_ledger_types::icrc3::blocks::{
+        ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResult,
+    };
+    use mockall::Sequence;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+    use std::sync::Arc;
+
+    #[test]
+    fn should_upgrade_canary_first_and_then_in_batches() {
+        let mut state = new_state();
+        state.record_new_erc20_token(dai(), dai_metadata());
+        state.record_new_erc20_token(usdc(), usdc_metadata());
+        state.record_new_erc20_token(usdt(), usdt_metadata());
+        state.init_ledger_suite_version(previous_version());
+
+        let rollout = LedgerSuiteRollout::validate(
+            &state,
+            ledger_suite_upgrade(),
+            RolloutArg {
+                canary_token_symbol: Some("ckUSDT".to_string()),
+                batch_size: Some(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            batches(&rollout),
+            vec![
+                vec![usdt_token_id()],
+                vec![TokenId::from(dai())],
+                vec![usdc_token_id()]
+            ]
+        );
+        assert_eq!(rollout.previous_version(), &previous_version());
+
+        let rollout = LedgerSuiteRollout::validate(
+            &state,
+            ledger_suite_upgrade(),
+            RolloutArg {
+                canary_token_symbol: None,
+                batch_size: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            batches(&rollout),
+            vec![
+                vec![TokenId::from(dai())],
+                vec![usdc_token_id(), usdt_token_id()]
+            ]
+        );
+        assert!(rollout
+            .batches()
+            .iter()
+            .all(|batch| batch.status == RolloutBatchStatus::Pending));
+    }
+
+    #[test]
+    fn should_reject_invalid_rollout_args() {
+        let mut state = new_state();
+        state.init_ledger_suite_version(previous_version());
+        let default_args = RolloutArg {
+            canary_token_symbol: None,
+            batch_size: None,
+        };
+
+        assert_eq!(
+            LedgerSuiteRollout::validate(&state, ledger_suite_upgrade(), default_args.clone()),
+            Err(InvalidRolloutArgError::NoManagedLedgerSuites)
+        );
+
+        state.record_new_erc20_token(usdc(), usdc_metadata());
+        assert_eq!(
+            LedgerSuiteRollout::validate(
+                &state,
+                ledger_suite_upgrade(),
+                RolloutArg {
+                    canary_token_symbol: Some("ckBTC".to_string()),
+                    ..default_args.clone()
+                }
+            ),
+            Err(InvalidRolloutArgError::UnknownCanary("ckBTC".to_string()))
+        );
+        assert_eq!(
+            LedgerSuiteRollout::validate(
+                &state,
+                ledger_suite_upgrade(),
+                RolloutArg {
+                    batch_size: Some(0),
+                    ..default_args.clone()
+                }
+            ),
+            Err(InvalidRolloutArgError::InvalidBatchSize(0))
+        );
+
+        let rollout =
+            LedgerSuiteRollout::validate(&state, ledger_suite_upgrade(), default_args.clone())
+                .unwrap();
+        state.start_ledger_suite_rollout(rollout);
+        assert_eq!(
+            LedgerSuiteRollout::validate(&state, ledger_suite_upgrade(), default_args),
+            Err(InvalidRolloutArgError::RolloutInProgress)
+        );
+    }
+
+    #[tokio::test]
+    async fn should_take_snapshot_once_before_upgrading() {
+        init_state_with_usdc_rollout();
+        let task = Task::UpgradeLedgerSuite(
+            UpgradeLedgerSuite::builder(usdc_token_id())
+                .ledger_wasm_hash(read_ledger_wasm_hash())
+                .rollout(true)
+                .build(),
+        );
+        let mut runtime = MockCanisterRuntime::new();
+        let mut seq = Sequence::new();
+        runtime
+            .expect_stop_canister()
+            .withf(|&id| id == LEDGER_PRINCIPAL)
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(Ok(()));
+        runtime
+            .expect_take_canister_snapshot()
+            .withf(|&id| id == LEDGER_PRINCIPAL)
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(Ok(ledger_snapshot_id()));
+        runtime
+            .expect_upgrade_canister()
+            .withf(|&id, _| id == LEDGER_PRINCIPAL)
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(Ok(()));
+        runtime
+            .expect_start_canister()
+            .withf(|&id| id == LEDGER_PRINCIPAL)
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(Ok(()));
+
+        assert_eq!(execute_now(task.clone(), &runtime).await, Ok(()));
+        assert_eq!(
+            usdc_rollout_progress().snapshots,
+            BTreeMap::from([(LEDGER_PRINCIPAL, ledger_snapshot_id())])
+        );
+        assert_eq!(
+            usdc_rollout_progress().status,
+            LedgerSuiteUpgradeStatus::Upgraded
+        );
+        runtime.checkpoint();
+
+        // the snapshot taken before the first upgrade is kept when upgrading again
+        runtime.expect_stop_canister().times(1).return_const(Ok(()));
+        runtime
+            .expect_upgrade_canister()
+            .times(1)
+            .return_const(Ok(()));
+        runtime
+            .expect_start_canister()
+            .times(1)
+            .return_const(Ok(()));
+
+        assert_eq!(execute_now(task, &runtime).await, Ok(()));
+        assert_eq!(
+            usdc_rollout_progress().snapshots,
+            BTreeMap::from([(LEDGER_PRINCIPAL, ledger_snapshot_id())])
+        );
+    }
+
+    #[tokio::test]
+    async fn should_abort_rollout_when_upgrade_rejected() {
+        init_state_with_usdc_rollout();
+        let task = Task::UpgradeLedgerSuite(
+            UpgradeLedgerSuite::builder(usdc_token_id())
+                .ledger_wasm_hash(read_ledger_wasm_hash())
+                .rollout(true)
+                .build(),
+        );
+        let upgrade_error = CallError {
+            method: "install_code".to_string(),
+            reason: Reason::CanisterError("Canister trapped in post_upgrade".to_string()),
+        };
+        let mut runtime = MockCanisterRuntime::new();
+        runtime.expect_stop_canister().times(1).return_const(Ok(()));
+        runtime
+            .expect_take_canister_snapshot()
+            .times(1)
+            .return_const(Ok(ledger_snapshot_id()));
+        runtime
+            .expect_upgrade_canister()
+            .times(1)
+            .return_const(Err(upgrade_error.clone()));
+
+        let error = execute_now(task, &runtime)
+            .await
+            .expect_err("upgrade rejected");
+
+        let expected_error = UpgradeLedgerSuiteError::UpgradeCanisterError(upgrade_error);
+        assert!(!error.is_recoverable());
+        assert_eq!(
+            error,
+            TaskError::UpgradeLedgerSuiteError(UpgradeLedgerSuiteError::RolloutAborted {
+                token_id: usdc_token_id(),
+                error: Box::new(expected_error.clone()),
+            })
+        );
+        assert_eq!(
+            usdc_rollout_progress().status,
+            LedgerSuiteUpgradeStatus::Failed {
+                reason: format!("{:?}", expected_error)
+            }
+        );
+        assert_eq!(task_queue_from_state(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn should_complete_rollout_when_batch_healthy() {
+        init_state_with_usdc_rollout();
+
+        let mut runtime = MockCanisterRuntime::new();
+        runtime.expect_time().return_const(0_u64);
+        runtime.expect_global_timer_set().return_const(());
+        expect_icrc3_log_length(&mut runtime, LEDGER_PRINCIPAL, 10, 2);
+        expect_icrc1_total_supply(&mut runtime, LEDGER_PRINCIPAL, 1_000);
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        assert_eq!(
+            current_batch_status(),
+            RolloutBatchStatus::Upgrading { since: 0 }
+        );
+        assert_eq!(usdc_rollout_progress().baseline, Some(baseline()));
+        assert_eq!(
+            task_queue_from_state()
+                .into_iter()
+                .map(|execution| execution.task_type)
+                .collect::<Vec<_>>(),
+            vec![
+                Task::UpgradeLedgerSuite(
+                    ledger_suite_upgrade()
+                        .into_builder(usdc_token_id())
+                        .rollout(true)
+                        .build()
+                ),
+                Task::UpgradeLedgerSuiteRollout
+            ]
+        );
+
+        mutate_usdc_rollout_progress(|progress| {
+            progress.status = LedgerSuiteUpgradeStatus::Upgraded;
+            progress.snapshots = BTreeMap::from([
+                (LEDGER_PRINCIPAL, ledger_snapshot_id()),
+                (INDEX_PRINCIPAL, index_snapshot_id()),
+            ]);
+        });
+        let mut runtime = MockCanisterRuntime::new();
+        runtime.expect_time().return_const(1_u64);
+        runtime.expect_global_timer_set().return_const(());
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        assert_eq!(
+            current_batch_status(),
+            RolloutBatchStatus::CheckingHealth { since: 1 }
+        );
+        assert_eq!(
+            read_state(|s| s.ledger_suite_version().cloned()),
+            Some(previous_version())
+        );
+
+        // a new block was recorded since the upgrade
+        let mut runtime = MockCanisterRuntime::new();
+        runtime.expect_time().return_const(2_u64);
+        runtime.expect_global_timer_set().return_const(());
+        expect_icrc3_log_length(&mut runtime, LEDGER_PRINCIPAL, 11, 2);
+        expect_icrc1_total_supply(&mut runtime, LEDGER_PRINCIPAL, 1_010);
+        expect_icrc3_get_blocks(
+            &mut runtime,
+            LEDGER_PRINCIPAL,
+            10,
+            1,
+            GetBlocksResult {
+                log_length: Nat::from(11_u64),
+                blocks: vec![block_with_id(10, mint_block(10))],
+                archived_blocks: vec![],
+            },
+        );
+        expect_index_status(&mut runtime, INDEX_PRINCIPAL, 11);
+        runtime
+            .expect_delete_canister_snapshot()
+            .times(2)
+            .return_const(Ok(()));
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        assert_eq!(current_batch_status(), RolloutBatchStatus::Healthy);
+        assert_eq!(usdc_rollout_progress().snapshots, BTreeMap::default());
+        assert!(!read_state(|s| s.is_ledger_suite_rollout_in_progress()));
+        assert_eq!(
+            read_state(|s| s.ledger_suite_version().cloned()),
+            Some(new_version())
+        );
+    }
+
+    #[tokio::test]
+    async fn should_accept_total_supply_consistent_with_new_blocks() {
+        init_state_with_usdc_rollout();
+
+        // 1_000 + 100 minted - 5 burned - 10 approval fee - 10 transfer fee
+        assert_eq!(
+            check_health_with_new_blocks(1_075).await,
+            RolloutBatchStatus::Healthy
+        );
+        assert_eq!(
+            read_state(|s| s.ledger_suite_version().cloned()),
+            Some(new_version())
+        );
+    }
+
+    #[tokio::test]
+    async fn should_roll_back_when_total_supply_inconsistent_with_new_blocks() {
+        init_state_with_usdc_rollout();
+
+        assert_eq!(
+            check_health_with_new_blocks(1_085).await,
+            RolloutBatchStatus::RollingBack {
+                reason: format!(
+                    "{:?} is unhealthy: {:?}",
+                    usdc_token_id(),
+                    LedgerSuiteHealthError::TotalSupplyMismatch {
+                        ledger_id: LEDGER_PRINCIPAL,
+                        before: Nat::from(1_000_u64),
+                        minted: Nat::from(100_u64),
+                        burned: Nat::from(25_u64),
+                        after: Nat::from(1_085_u64),
+                    }
+                ),
+            }
+        );
+        assert_eq!(
+            read_state(|s| s.ledger_suite_version().cloned()),
+            Some(previous_version())
+        );
+    }
+
+    /// Checks the health of the USDC ledger suite, whose ledger recorded 5 new blocks since the upgrade,
+    /// 2 of which were already archived.
+    async fn check_health_with_new_blocks(total_supply: u64) -> RolloutBatchStatus {
+        const ARCHIVE_PRINCIPAL: Principal = Principal::from_slice(&[4_u8; 29]);
+
+        mutate_usdc_rollout_progress(|progress| {
+            progress.baseline = Some(baseline());
+            progress.status = LedgerSuiteUpgradeStatus::Upgraded;
+        });
+        mutate_current_batch_status(RolloutBatchStatus::CheckingHealth { since: 0 });
+
+        let mut runtime = MockCanisterRuntime::new();
+        runtime.expect_time().return_const(0_u64);
+        runtime.expect_global_timer_set().return_const(());
+        expect_icrc3_log_length(&mut runtime, LEDGER_PRINCIPAL, 15, 2);
+        expect_icrc1_total_supply(&mut runtime, LEDGER_PRINCIPAL, total_supply);
+        expect_icrc3_get_blocks(
+            &mut runtime,
+            LEDGER_PRINCIPAL,
+            10,
+            5,
+            GetBlocksResult {
+                log_length: Nat::from(15_u64),
+                blocks: vec![
+                    block_with_id(12, approve_block(10)),
+                    block_with_id(13, transfer_block(10, false)),
+                    block_with_id(14, transfer_block(10, true)),
+                ],
+                archived_blocks: vec![ArchivedBlocks {
+                    args: vec![GetBlocksRequest {
+                        start: Nat::from(10_u64),
+                        length: Nat::from(2_u64),
+                    }],
+                    callback: QueryArchiveFn::new(ARCHIVE_PRINCIPAL, "icrc3_get_blocks"),
+                }],
+            },
+        );
+        expect_icrc3_get_blocks(
+            &mut runtime,
+            ARCHIVE_PRINCIPAL,
+            10,
+            2,
+            GetBlocksResult {
+                log_length: Nat::from(0_u64),
+                blocks: vec![
+                    block_with_id(10, mint_block(100)),
+                    block_with_id(11, burn_block(5)),
+                ],
+                archived_blocks: vec![],
+            },
+        );
+        runtime
+            .expect_call_canister()
+            .withf(|&canister_id, method, _args: &()| {
+                canister_id == INDEX_PRINCIPAL && method == "status"
+            })
+            .returning(|_, _, _| {
+                Ok(IndexStatus {
+                    num_blocks_synced: Nat::from(15_u64),
+                })
+            });
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        current_batch_status()
+    }
+
+    #[tokio::test]
+    async fn should_roll_back_batch_when_upgrade_times_out() {
+        init_state_with_usdc_rollout();
+        mutate_usdc_rollout_progress(|progress| progress.baseline = Some(baseline()));
+        mutate_current_batch_status(RolloutBatchStatus::Upgrading { since: 0 });
+
+        let mut runtime = MockCanisterRuntime::new();
+        runtime
+            .expect_time()
+            .return_const(ROLLOUT_UPGRADE_TIMEOUT.as_nanos() as u64 - 1);
+        runtime.expect_global_timer_set().return_const(());
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        assert_eq!(
+            current_batch_status(),
+            RolloutBatchStatus::Upgrading { since: 0 }
+        );
+        assert_eq!(
+            usdc_rollout_progress().status,
+            LedgerSuiteUpgradeStatus::Pending
+        );
+
+        let mut runtime = MockCanisterRuntime::new();
+        runtime
+            .expect_time()
+            .return_const(ROLLOUT_UPGRADE_TIMEOUT.as_nanos() as u64);
+        runtime.expect_global_timer_set().return_const(());
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        assert_eq!(
+            current_batch_status(),
+            RolloutBatchStatus::RollingBack {
+                reason: format!("failed to upgrade {:?}: upgrade timed out", usdc_token_id())
+            }
+        );
+        assert_eq!(
+            usdc_rollout_progress().status,
+            LedgerSuiteUpgradeStatus::Failed {
+                reason: "upgrade timed out".to_string()
+            }
+        );
+
+        // the remaining upgrade of the ledger suite is dropped
+        let runtime = MockCanisterRuntime::new();
+        let task = Task::UpgradeLedgerSuite(
+            ledger_suite_upgrade()
+                .into_builder(usdc_token_id())
+                .rollout(true)
+                .build(),
+        );
+        assert_eq!(execute_now(task, &runtime).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn should_roll_back_unhealthy_batch() {
+        init_state_with_usdc_rollout();
+        mutate_usdc_rollout_progress(|progress| {
+            progress.baseline = Some(baseline());
+            progress.status = LedgerSuiteUpgradeStatus::Upgraded;
+            progress.snapshots = BTreeMap::from([
+                (LEDGER_PRINCIPAL, ledger_snapshot_id()),
+                (INDEX_PRINCIPAL, index_snapshot_id()),
+            ]);
+        });
+        mutate_state(|s| {
+            s.ledger_suite_rollout_mut()
+                .and_then(|rollout| rollout.current_batch_mut())
+                .unwrap()
+                .status = RolloutBatchStatus::CheckingHealth { since: 0 }
+        });
+
+        let mut runtime = MockCanisterRuntime::new();
+        runtime
+            .expect_time()
+            .return_const(ROLLOUT_HEALTH_CHECK_TIMEOUT.as_nanos() as u64);
+        runtime.expect_global_timer_set().return_const(());
+        expect_icrc3_log_length(&mut runtime, LEDGER_PRINCIPAL, 10, 2);
+        expect_icrc1_total_supply(&mut runtime, LEDGER_PRINCIPAL, 1_000);
+        expect_index_status(&mut runtime, INDEX_PRINCIPAL, 5);
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        let reason = match current_batch_status() {
+            RolloutBatchStatus::RollingBack { reason } => reason,
+            status => panic!("unexpected batch status: {:?}", status),
+        };
+        assert!(reason.contains("IndexNotSynced"), "{}", reason);
+
+        // the ledger is still running and records a new block after its log length was checked,
+        // right before it is stopped to be rolled back
+        let ledger_log_length = Arc::new(AtomicU64::new(10));
+        let mut runtime = MockCanisterRuntime::new();
+        let log_length = ledger_log_length.clone();
+        runtime
+            .expect_call_canister()
+            .withf(|&canister_id, method, args: &Vec<GetBlocksRequest>| {
+                canister_id == LEDGER_PRINCIPAL && method == "icrc3_get_blocks" && args.is_empty()
+            })
+            .times(1)
+            .returning(move |_, _, _| {
+                Ok(GetBlocksResult {
+                    log_length: Nat::from(log_length.load(AtomicOrdering::SeqCst)),
+                    blocks: vec![],
+                    archived_blocks: vec![],
+                })
+            });
+        runtime
+            .expect_stop_canister()
+            .withf(|&id| id == INDEX_PRINCIPAL)
+            .times(1)
+            .return_const(Ok(()));
+        let log_length = ledger_log_length.clone();
+        runtime
+            .expect_stop_canister()
+            .withf(|&id| id == LEDGER_PRINCIPAL)
+            .times(1)
+            .returning(move |_| {
+                log_length.fetch_add(1, AtomicOrdering::SeqCst);
+                Ok(())
+            });
+        runtime
+            .expect_upgrade_canister()
+            .withf(|&id, module| id == INDEX_PRINCIPAL && module == INDEX_BYTECODE)
+            .times(1)
+            .return_const(Ok(()));
+        runtime
+            .expect_upgrade_canister()
+            .withf(|&id, module| id == LEDGER_PRINCIPAL && module == LEDGER_BYTECODE)
+            .times(1)
+            .return_const(Ok(()));
+        runtime
+            .expect_start_canister()
+            .times(2)
+            .return_const(Ok(()));
+        runtime
+            .expect_delete_canister_snapshot()
+            .times(2)
+            .return_const(Ok(()));
+        runtime.expect_load_canister_snapshot().never();
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        assert_eq!(
+            current_batch_status(),
+            RolloutBatchStatus::RolledBack { reason }
+        );
+        assert_eq!(ledger_log_length.load(AtomicOrdering::SeqCst), 11);
+        assert_eq!(
+            usdc_rollout_progress().rollback,
+            Some(LedgerSuiteRollback::Downgrade)
+        );
+        assert_eq!(usdc_rollout_progress().snapshots, BTreeMap::default());
+        assert!(!read_state(|s| s.is_ledger_suite_rollout_in_progress()));
+        assert_eq!(
+            read_state(|s| s.ledger_suite_version().cloned()),
+            Some(previous_version())
+        );
+    }
+
+    #[tokio::test]
+    async fn should_downgrade_ledger_suite_when_ledger_recorded_new_blocks() {
+        init_state_with_usdc_rollout();
+        mutate_usdc_rollout_progress(|progress| {
+            progress.baseline = Some(baseline());
+            progress.status = LedgerSuiteUpgradeStatus::Upgraded;
+            progress.snapshots = BTreeMap::from([
+                (LEDGER_PRINCIPAL, ledger_snapshot_id()),
+                (INDEX_PRINCIPAL, index_snapshot_id()),
+            ]);
+        });
+        mutate_state(|s| {
+            s.ledger_suite_rollout_mut()
+                .and_then(|rollout| rollout.current_batch_mut())
+                .unwrap()
+                .status = RolloutBatchStatus::RollingBack {
+                reason: "index not synced".to_string(),
+            }
+        });
+
+        let mut runtime = MockCanisterRuntime::new();
+        expect_icrc3_log_length(&mut runtime, LEDGER_PRINCIPAL, 11, 1);
+        for (canister_id, wasm_module) in [
+            (LEDGER_PRINCIPAL, LEDGER_BYTECODE.to_vec()),
+            (INDEX_PRINCIPAL, INDEX_BYTECODE.to_vec()),
+        ] {
+            let mut seq = Sequence::new();
+            runtime
+                .expect_stop_canister()
+                .withf(move |&id| id == canister_id)
+                .times(1)
+                .in_sequence(&mut seq)
+                .return_const(Ok(()));
+            runtime
+                .expect_upgrade_canister()
+                .withf(move |&id, module| id == canister_id && module == &wasm_module)
+                .times(1)
+                .in_sequence(&mut seq)
+                .return_const(Ok(()));
+            runtime
+                .expect_start_canister()
+                .withf(move |&id| id == canister_id)
+                .times(1)
+                .in_sequence(&mut seq)
+                .return_const(Ok(()));
+            runtime
+                .expect_delete_canister_snapshot()
+                .withf(move |&id, _| id == canister_id)
+                .times(1)
+                .in_sequence(&mut seq)
+                .return_const(Ok(()));
+        }
+        runtime.expect_load_canister_snapshot().never();
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        assert_eq!(
+            current_batch_status(),
+            RolloutBatchStatus::RolledBack {
+                reason: "index not synced".to_string()
+            }
+        );
+        assert_eq!(usdc_rollout_progress().snapshots, BTreeMap::default());
+        assert_eq!(
+            read_state(|s| s.ledger_suite_version().cloned()),
+            Some(previous_version())
+        );
+    }
+
+    #[tokio::test]
+    async fn should_restore_ledger_suite_when_ledger_did_not_restart_after_upgrade() {
+        init_state_with_usdc_rollout();
+        mutate_usdc_rollout_progress(|progress| {
+            progress.baseline = Some(baseline());
+            progress.status = LedgerSuiteUpgradeStatus::Failed {
+                reason: "upgrade rejected".to_string(),
+            };
+            progress.snapshots = BTreeMap::from([
+                (LEDGER_PRINCIPAL, ledger_snapshot_id()),
+                (INDEX_PRINCIPAL, index_snapshot_id()),
+            ]);
+        });
+        mutate_current_batch_status(RolloutBatchStatus::RollingBack {
+            reason: "upgrade rejected".to_string(),
+        });
+        let load_error = CallError {
+            method: "load_canister_snapshot".to_string(),
+            reason: Reason::TransientInternalError("timeout".to_string()),
+        };
+
+        let mut runtime = MockCanisterRuntime::new();
+        runtime
+            .expect_call_canister()
+            .withf(|&canister_id, method, args: &Vec<GetBlocksRequest>| {
+                canister_id == LEDGER_PRINCIPAL && method == "icrc3_get_blocks" && args.is_empty()
+            })
+            .times(1)
+            .return_const(Err::<GetBlocksResult, _>(CallError {
+                method: "icrc3_get_blocks".to_string(),
+                reason: Reason::CanisterError(format!("Canister {} is stopped", LEDGER_PRINCIPAL)),
+            }));
+        runtime.expect_stop_canister().times(2).return_const(Ok(()));
+        runtime
+            .expect_start_canister()
+            .times(1)
+            .return_const(Ok(()));
+        runtime
+            .expect_delete_canister_snapshot()
+            .times(1)
+            .return_const(Ok(()));
+        let mut seq = Sequence::new();
+        runtime
+            .expect_load_canister_snapshot()
+            .withf(|&id, snapshot_id| id == INDEX_PRINCIPAL && snapshot_id == &index_snapshot_id())
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(Ok(()));
+        runtime
+            .expect_load_canister_snapshot()
+            .withf(|&id, snapshot_id| {
+                id == LEDGER_PRINCIPAL && snapshot_id == &ledger_snapshot_id()
+            })
+            .times(1)
+            .in_sequence(&mut seq)
+            .return_const(Err(load_error.clone()));
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Err(TaskError::UpgradeLedgerSuiteRolloutError(
+                UpgradeLedgerSuiteRolloutError::LoadCanisterSnapshotError(load_error)
+            ))
+        );
+        assert_eq!(
+            usdc_rollout_progress().rollback,
+            Some(LedgerSuiteRollback::RestoreSnapshots)
+        );
+        runtime.checkpoint();
+
+        // the retry keeps restoring, even though the ledger was stopped by the rollback itself
+        runtime
+            .expect_call_canister::<Vec<GetBlocksRequest>, GetBlocksResult>()
+            .never();
+        runtime
+            .expect_stop_canister()
+            .withf(|&id| id == LEDGER_PRINCIPAL)
+            .times(1)
+            .return_const(Ok(()));
+        runtime
+            .expect_load_canister_snapshot()
+            .withf(|&id, snapshot_id| {
+                id == LEDGER_PRINCIPAL && snapshot_id == &ledger_snapshot_id()
+            })
+            .times(1)
+            .return_const(Ok(()));
+        runtime
+            .expect_start_canister()
+            .withf(|&id| id == LEDGER_PRINCIPAL)
+            .times(1)
+            .return_const(Ok(()));
+        runtime
+            .expect_delete_canister_snapshot()
+            .withf(|&id, _| id == LEDGER_PRINCIPAL)
+            .times(1)
+            .return_const(Ok(()));
+
+        assert_eq!(
+            execute_now(Task::UpgradeLedgerSuiteRollout, &runtime).await,
+            Ok(())
+        );
+        assert_eq!(
+            current_batch_status(),
+            RolloutBatchStatus::RolledBack {
+                reason: "upgrade rejected".to_string()
+            }
+        );
+        assert_eq!(usdc_rollout_progress().snapshots, BTreeMap::default());
+    }
+
+    fn init_state_with_usdc_rollout() {
+        init_state();
+        let usdc = usdc();
+        mutate_state(|s| {
+            s.record_new_erc20_token(usdc.clone(), usdc_metadata());
+            s.record_created_canister::<Ledger>(&usdc, LEDGER_PRINCIPAL);
+            s.record_installed_canister::<Ledger>(&usdc, WasmHash::default());
+            s.record_created_canister::<Index>(&usdc, INDEX_PRINCIPAL);
+            s.record_installed_canister::<Index>(&usdc, WasmHash::default());
+            s.init_ledger_suite_version(previous_version());
+            let rollout = LedgerSuiteRollout::validate(
+                s,
+                ledger_suite_upgrade(),
+                RolloutArg {
+                    canary_token_symbol: None,
+                    batch_size: None,
+                },
+            )
+            .unwrap();
+            s.start_ledger_suite_rollout(rollout);
+        });
+    }
+
+    fn ledger_suite_upgrade() -> UpgradeOrchestratorArgs {
+        UpgradeOrchestratorArgs {
+            ledger_compressed_wasm_hash: Some(WasmHash::from([1_u8; 32])),
+            index_compressed_wasm_hash: Some(WasmHash::from([2_u8; 32])),
+            archive_compressed_wasm_hash: None,
+        }
+    }
+
+    fn previous_version() -> LedgerSuiteVersion {
+        LedgerSuiteVersion {
+            ledger_compressed_wasm_hash: read_ledger_wasm_hash(),
+            index_compressed_wasm_hash: read_index_wasm_hash(),
+            archive_compressed_wasm_hash: read_archive_wasm_hash(),
+        }
+    }
+
+    fn new_version() -> LedgerSuiteVersion {
+        LedgerSuiteVersion {
+            ledger_compressed_wasm_hash: WasmHash::from([1_u8; 32]),
+            index_compressed_wasm_hash: WasmHash::from([2_u8; 32]),
+            archive_compressed_wasm_hash: read_archive_wasm_hash(),
+        }
+    }
+
+    fn baseline() -> LedgerHealth {
+        LedgerHealth {
+            total_supply: Nat::from(1_000_u64),
+            log_length: Nat::from(10_u64),
+        }
+    }
+
+    fn ledger_snapshot_id() -> SnapshotId {
+        SnapshotId::from(vec![1_u8; 16])
+    }
+
+    fn index_snapshot_id() -> SnapshotId {
+        SnapshotId::from(vec![2_u8; 16])
+    }
+
+    fn batches(rollout: &LedgerSuiteRollout) -> Vec<Vec<TokenId>> {
+        rollout
+            .batches()
+            .iter()
+            .map(|batch| batch.ledger_suites.keys().cloned().collect())
+            .collect()
+    }
+
+    fn current_batch_status() -> RolloutBatchStatus {
+        read_state(|s| {
+            s.ledger_suite_rollout().unwrap().batches()[0]
+                .status
+                .clone()
+        })
+    }
+
+    fn mutate_current_batch_status(status: RolloutBatchStatus) {
+        mutate_state(|s| {
+            s.ledger_suite_rollout_mut()
+                .and_then(|rollout| rollout.current_batch_mut())
+                .unwrap()
+                .status = status
+        });
+    }
+
+    fn usdc_rollout_progress() -> LedgerSuiteUpgradeProgress {
+        read_state(|s| {
+            s.ledger_suite_rollout()
+                .and_then(|rollout| rollout.ledger_suite(&usdc_token_id()))
+                .cloned()
+                .unwrap()
+        })
+    }
+
+    fn mutate_usdc_rollout_progress<F: FnOnce(&mut LedgerSuiteUpgradeProgress)>(f: F) {
+        mutate_state(|s| {
+            f(s.ledger_suite_rollout_mut()
+                .and_then(|rollout| rollout.ledger_suite_mut(&usdc_token_id()))
+                .unwrap())
+        });
+    }
+
+    fn expect_icrc3_log_length(
+        runtime: &mut MockCanisterRuntime,
+        expected_canister_id: Principal,
+        log_length: u64,
+        times: usize,
+    ) {
+        runtime
+            .expect_call_canister()
+            .withf(move |&canister_id, method, args: &Vec<GetBlocksRequest>| {
+                canister_id == expected_canister_id
+                    && method == "icrc3_get_blocks"
+                    && args.is_empty()
+            })
+            .times(times)
+            .return_const(Ok(GetBlocksResult {
+                log_length: Nat::from(log_length),
+                blocks: vec![],
+                archived_blocks: vec![],
+            }));
+    }
+
+    fn expect_icrc3_get_blocks(
+        runtime: &mut MockCanisterRuntime,
+        expected_canister_id: Principal,
+        start: u64,
+        length: u64,
+        result: GetBlocksResult,
+    ) {
+        let expected_args = vec![GetBlocksRequest {
+            start: Nat::from(start),
+            length: Nat::from(length),
+        }];
+        runtime
+            .expect_call_canister()
+            .withf(move |&canister_id, method, args: &Vec<GetBlocksRequest>| {
+                canister_id == expected_canister_id
+                    && method == "icrc3_get_blocks"
+                    && args == &expected_args
+            })
+            .times(1)
+            .return_const(Ok(result));
+    }
+
+    fn block_with_id(id: u64, block: ICRC3Value) -> BlockWithId {
+        BlockWithId {
+            id: Nat::from(id),
+            block,
+        }
+    }
+
+    fn block(tx: Vec<(&str, ICRC3Value)>, fields: Vec<(&str, ICRC3Value)>) -> ICRC3Value {
+        let tx = ICRC3Value::Map(
+            tx.into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        );
+        ICRC3Value::Map(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .chain([("tx".to_string(), tx)])
+                .collect(),
+        )
+    }
+
+    fn text(value: &str) -> ICRC3Value {
+        ICRC3Value::Text(value.to_string())
+    }
+
+    fn nat(value: u64) -> ICRC3Value {
+        ICRC3Value::Nat(Nat::from(value))
+    }
+
+    fn mint_block(amount: u64) -> ICRC3Value {
+        block(vec![("op", text("mint")), ("amt", nat(amount))], vec![])
+    }
+
+    fn burn_block(amount: u64) -> ICRC3Value {
+        block(vec![("op", text("burn")), ("amt", nat(amount))], vec![])
+    }
+
+    fn approve_block(fee: u64) -> ICRC3Value {
+        block(
+            vec![("op", text("approve")), ("amt", nat(1_000))],
+            vec![("fee", nat(fee))],
+        )
+    }
+
+    fn transfer_block(fee: u64, with_fee_collector: bool) -> ICRC3Value {
+        let mut fields = vec![("fee", nat(fee))];
+        if with_fee_collector {
+            fields.push(("fee_col_block", nat(0)));
+        }
+        block(
+            vec![("op", text("xfer")), ("amt", nat(500)), ("fee", nat(fee))],
+            fields,
+        )
+    }
+
+    fn expect_icrc1_total_supply(
+        runtime: &mut MockCanisterRuntime,
+        expected_canister_id: Principal,
+        total_supply: u64,
+    ) {
+        runtime
+            .expect_call_canister()
+            .withf(move |&canister_id, method, _args: &()| {
+                canister_id == expected_canister_id && method == "icrc1_total_supply"
+            })
+            .times(1)
+            .return_const(Ok::<Nat, CallError>(Nat::from(total_supply)));
+    }
+
+    fn expect_index_status(
+        runtime: &mut MockCanisterRuntime,
+        expected_canister_id: Principal,
+        num_blocks_synced: u64,
+    ) {
+        runtime
+            .expect_call_canister()
+            .withf(move |&canister_id, method, _args: &()| {
+                canister_id == expected_canister_id && method == "status"
+            })
+            .times(1)
+            .returning(move |_, _, _| {
+                Ok(IndexStatus {
+                    num_blocks_synced: Nat::from(num_blocks_synced),
+                })
+            });
+    }
+}
+
 mod run_task {
     use crate::candid::AddCkErc20Token;
     use crate::guard::TimerGuard;
@@ -1383,6 +2420,8 @@ mod run_task {
             address: usdc().address().to_string(),
             ckerc20_token_symbol: usdc_metadata().token_symbol.clone(),
             ckerc20_ledger_id: LEDGER_PRINCIPAL,
+            balance_diff_accounting: Some(false),
+            decimals: usdc_metadata().decimals,
         }
     }
 }
@@ -1420,6 +2459,7 @@ fn usdc_install_args() -> InstallLedgerSuiteArgs {
         ledger_init_arg: ledger_init_arg(),
         ledger_compressed_wasm_hash: read_ledger_wasm_hash(),
         index_compressed_wasm_hash: read_index_wasm_hash(),
+        balance_diff_accounting: false,
     }
 }
 
@@ -1609,7 +2649,7 @@ orchestrator_tasks_duration_seconds_count{task="maybe_top_up",result="err"} 1 12
 }
 
 mod mock {
-    use crate::management::CanisterRuntime;
+    use crate::management::{CanisterRuntime, SnapshotId};
     use crate::scheduler::CallError;
     use async_trait::async_trait;
     use candid::CandidType;
@@ -1654,6 +2694,23 @@ mod mock {
                 wasm_module:Vec<u8>,
             ) -> Result<(), CallError>;
 
+            async fn take_canister_snapshot(
+                &self,
+                canister_id: Principal,
+            ) -> Result<SnapshotId, CallError>;
+
+            async fn load_canister_snapshot(
+                &self,
+                canister_id: Principal,
+                snapshot_id: SnapshotId,
+            ) -> Result<(), CallError>;
+
+            async fn delete_canister_snapshot(
+                &self,
+                canister_id: Principal,
+                snapshot_id: SnapshotId,
+            ) -> Result<(), CallError>;
+
             async fn canister_cycles(
                 &self,
                 canister_id: Principal,
@@ -1681,7 +2738,9 @@ mod mock {
 mod install_ledger_suite_args {
     use crate::candid::{AddErc20Arg, InitArg, LedgerInitArg};
     use crate::scheduler::tests::{usdc_metadata, MINTER_PRINCIPAL};
-    use crate::scheduler::{ChainId, Erc20Token, InstallLedgerSuiteArgs, InvalidAddErc20ArgError};
+    use crate::scheduler::{
+        ChainId, Erc20Token, InstallLedgerSuiteArgs, InvalidAddErc20ArgError, MAX_ERC20_DECIMALS,
+    };
     use crate::state::test_fixtures::{expect_panic_with_message, new_state, new_state_from};
     use crate::state::{GitCommitHash, IndexWasm, LedgerSuiteVersion, LedgerWasm, WasmHash};
     use crate::storage::test_fixtures::{
@@ -1726,6 +2785,43 @@ mod install_ledger_suite_args {
         );
     }
 
+    #[test]
+    fn should_error_if_decimals_too_large() {
+        let mut state = new_state_from(InitArg {
+            minter_id: Some(MINTER_PRINCIPAL),
+            ..Default::default()
+        });
+        let wasm_store = wasm_store_with_icrc1_ledger_suite();
+        state.update_ledger_suite_version(embedded_ledger_suite_version());
+        let mut arg = valid_add_erc20_arg();
+        arg.ledger_init_arg.decimals = MAX_ERC20_DECIMALS + 1;
+
+        assert_eq!(
+            InstallLedgerSuiteArgs::validate_add_erc20(&state, &wasm_store, arg),
+            Err(InvalidAddErc20ArgError::InvalidDecimals(
+                MAX_ERC20_DECIMALS + 1
+            ))
+        );
+    }
+
+    #[test]
+    fn should_accept_balance_diff_accounting() {
+        let mut state = new_state_from(InitArg {
+            minter_id: Some(MINTER_PRINCIPAL),
+            ..Default::default()
+        });
+        let wasm_store = wasm_store_with_icrc1_ledger_suite();
+        state.update_ledger_suite_version(embedded_ledger_suite_version());
+        let arg = AddErc20Arg {
+            balance_diff_accounting: Some(true),
+            ..valid_add_erc20_arg()
+        };
+
+        let result = InstallLedgerSuiteArgs::validate_add_erc20(&state, &wasm_store, arg).unwrap();
+
+        assert!(result.balance_diff_accounting);
+    }
+
     proptest! {
         #[test]
         fn queue_holds_one_copy_of_each_task(
@@ -1861,6 +2957,7 @@ mod install_ledger_suite_args {
                 index_compressed_wasm_hash: IndexWasm::from(crate::state::INDEX_BYTECODE)
                     .hash()
                     .clone(),
+                balance_diff_accounting: false,
             }
         );
     }
@@ -1878,6 +2975,7 @@ mod install_ledger_suite_args {
                 token_symbol: "USDC".to_string(),
                 token_logo: "".to_string(),
             },
+            balance_diff_accounting: None,
         }
     }
 
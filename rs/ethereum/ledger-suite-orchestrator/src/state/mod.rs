@@ -3,11 +3,14 @@ pub mod test_fixtures;
 #[cfg(test)]
 mod tests;
 
-use crate::candid::{CyclesManagement, InitArg};
-use crate::scheduler::{Erc20Token, InvalidManageInstalledCanistersError, Task};
+use crate::candid::{CyclesManagement, InitArg, RolloutArg};
+use crate::management::SnapshotId;
+use crate::scheduler::{
+    Erc20Token, InvalidManageInstalledCanistersError, Task, UpgradeOrchestratorArgs,
+};
 use crate::storage::memory::{state_memory, StableMemory};
 use crate::storage::WasmHashError;
-use candid::Principal;
+use candid::{Nat, Principal};
 use ic_cdk::trap;
 use ic_stable_structures::{storable::Bound, Cell, Storable};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -28,6 +31,9 @@ pub(crate) const ARCHIVE_NODE_BYTECODE: &[u8] =
 const WASM_HASH_LENGTH: usize = 32;
 const GIT_COMMIT_HASH_LENGTH: usize = 20;
 
+/// Maximum number of ledger suites upgraded together after the canary, unless specified otherwise.
+pub const DEFAULT_ROLLOUT_BATCH_SIZE: u64 = 5;
+
 thread_local! {
     pub static STATE: RefCell<Cell<ConfigState, StableMemory>> = RefCell::new(Cell::init(
    state_memory(), ConfigState::default())
@@ -323,6 +329,12 @@ pub struct Canisters {
 pub struct CanistersMetadata {
     #[serde(rename = "ckerc20_token_symbol")]
     pub token_symbol: String,
+    #[serde(default)]
+    pub balance_diff_accounting: bool,
+    /// Number of decimals of the ERC-20 token, which is forwarded to the minter.
+    /// `None` for ledger suites added before it was recorded.
+    #[serde(default)]
+    pub decimals: Option<u8>,
 }
 
 impl Canisters {
@@ -517,6 +529,8 @@ pub struct State {
     pub active_tasks: BTreeSet<Task>,
     #[serde(default)]
     ledger_suite_version: Option<LedgerSuiteVersion>,
+    #[serde(default)]
+    ledger_suite_rollout: Option<LedgerSuiteRollout>,
 }
 
 impl State {
@@ -569,6 +583,29 @@ impl State {
         self.ledger_suite_version = Some(new_version);
     }
 
+    /// Returns the last staged upgrade of the managed ledger suites, which may be over.
+    pub fn ledger_suite_rollout(&self) -> Option<&LedgerSuiteRollout> {
+        self.ledger_suite_rollout.as_ref()
+    }
+
+    pub fn ledger_suite_rollout_mut(&mut self) -> Option<&mut LedgerSuiteRollout> {
+        self.ledger_suite_rollout.as_mut()
+    }
+
+    pub fn is_ledger_suite_rollout_in_progress(&self) -> bool {
+        self.ledger_suite_rollout
+            .as_ref()
+            .map_or(false, |rollout| rollout.is_in_progress())
+    }
+
+    pub fn start_ledger_suite_rollout(&mut self, rollout: LedgerSuiteRollout) {
+        assert!(
+            !self.is_ledger_suite_rollout_in_progress(),
+            "BUG: ledger suite rollout already in progress"
+        );
+        self.ledger_suite_rollout = Some(rollout);
+    }
+
     fn managed_canisters_mut(&mut self, token_id: &TokenId) -> Option<&mut Canisters> {
         self.managed_canisters.get_mut(token_id)
     }
@@ -751,6 +788,7 @@ impl TryFrom<InitArg> for State {
             more_controller_ids,
             minter_id,
             ledger_suite_version: Default::default(),
+            ledger_suite_rollout: Default::default(),
             active_tasks: Default::default(),
         };
         state.validate_config()?;
@@ -819,6 +857,8 @@ impl From<InstalledLedgerSuite> for Canisters {
             archives: value.archives,
             metadata: CanistersMetadata {
                 token_symbol: value.token_symbol.to_string(),
+                balance_diff_accounting: false,
+                decimals: None,
             },
         }
     }
@@ -880,3 +920,212 @@ impl InstalledLedgerSuite {
         })
     }
 }
+
+/// Staged upgrade of the managed ledger suites.
+///
+/// Ledger suites are upgraded batch by batch, where the first batch only contains the canary.
+/// A batch is only upgraded once all previous batches are healthy,
+/// and a batch that fails to upgrade or whose health check fails is rolled back,
+/// which halts the rollout. The new ledger suite version, used for example to install
+/// the ledger suites of new ERC-20 tokens, is only recorded once the canary is healthy.
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct LedgerSuiteRollout {
+    upgrade: UpgradeOrchestratorArgs,
+    /// Ledger suite version before the rollout, restored when a batch is rolled back.
+    previous_version: LedgerSuiteVersion,
+    batches: Vec<RolloutBatch>,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct RolloutBatch {
+    pub status: RolloutBatchStatus,
+    pub ledger_suites: BTreeMap<TokenId, LedgerSuiteUpgradeProgress>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub enum RolloutBatchStatus {
+    /// Waiting for the previous batches to be healthy.
+    Pending,
+    /// The ledger suites of the batch are being upgraded
+    /// since the given timestamp (in nanoseconds since the epoch).
+    Upgrading { since: u64 },
+    /// All ledger suites of the batch were upgraded and their health is checked
+    /// since the given timestamp (in nanoseconds since the epoch).
+    CheckingHealth { since: u64 },
+    /// All ledger suites of the batch were upgraded and are healthy.
+    Healthy,
+    /// The canisters of the batch are being restored from their snapshots.
+    RollingBack { reason: String },
+    /// The canisters of the batch were restored from their snapshots.
+    RolledBack { reason: String },
+}
+
+impl Display for RolloutBatchStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RolloutBatchStatus::Pending => write!(f, "Pending"),
+            RolloutBatchStatus::Upgrading { .. } => write!(f, "Upgrading"),
+            RolloutBatchStatus::CheckingHealth { .. } => write!(f, "Checking health"),
+            RolloutBatchStatus::Healthy => write!(f, "Healthy"),
+            RolloutBatchStatus::RollingBack { reason } => write!(f, "Rolling back: {}", reason),
+            RolloutBatchStatus::RolledBack { reason } => write!(f, "Rolled back: {}", reason),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub struct LedgerSuiteUpgradeProgress {
+    /// Health of the ledger suite right before its upgrade.
+    pub baseline: Option<LedgerHealth>,
+    /// Snapshots of the canisters of the ledger suite, taken right before upgrading them.
+    pub snapshots: BTreeMap<Principal, SnapshotId>,
+    pub status: LedgerSuiteUpgradeStatus,
+    /// How the ledger suite is rolled back, decided once when the rollback starts,
+    /// so that a retried rollback does not switch to a different method.
+    #[serde(default)]
+    pub rollback: Option<LedgerSuiteRollback>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub enum LedgerSuiteRollback {
+    /// Load the snapshots taken before upgrading the canisters.
+    RestoreSnapshots,
+    /// Upgrade the canisters back to the wasms of the previous ledger suite version,
+    /// which keeps any block recorded since the upgrade.
+    Downgrade,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub enum LedgerSuiteUpgradeStatus {
+    #[default]
+    Pending,
+    Upgraded,
+    Failed {
+        reason: String,
+    },
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct LedgerHealth {
+    pub total_supply: Nat,
+    pub log_length: Nat,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum InvalidRolloutArgError {
+    RolloutInProgress,
+    NoManagedLedgerSuites,
+    UnknownCanary(String),
+    InvalidBatchSize(u64),
+}
+
+impl LedgerSuiteRollout {
+    pub fn validate(
+        state: &State,
+        upgrade: UpgradeOrchestratorArgs,
+        args: RolloutArg,
+    ) -> Result<LedgerSuiteRollout, InvalidRolloutArgError> {
+        if state.is_ledger_suite_rollout_in_progress() {
+            return Err(InvalidRolloutArgError::RolloutInProgress);
+        }
+        let batch_size = args.batch_size.unwrap_or(DEFAULT_ROLLOUT_BATCH_SIZE);
+        if batch_size == 0 {
+            return Err(InvalidRolloutArgError::InvalidBatchSize(batch_size));
+        }
+        let mut token_ids: Vec<_> = state.all_managed_tokens_ids_iter().collect();
+        let canary_index = match &args.canary_token_symbol {
+            Some(symbol) => token_ids
+                .iter()
+                .position(|token_id| {
+                    state
+                        .managed_canisters(token_id)
+                        .map_or(false, |c| &c.metadata.token_symbol == symbol)
+                })
+                .ok_or(InvalidRolloutArgError::UnknownCanary(symbol.clone()))?,
+            None if token_ids.is_empty() => {
+                return Err(InvalidRolloutArgError::NoManagedLedgerSuites)
+            }
+            None => 0,
+        };
+        let canary = token_ids.remove(canary_index);
+        let batches = once(vec![canary])
+            .chain(
+                token_ids
+                    .chunks(usize::try_from(batch_size).unwrap_or(usize::MAX))
+                    .map(|chunk| chunk.to_vec()),
+            )
+            .map(|token_ids| RolloutBatch {
+                status: RolloutBatchStatus::Pending,
+                ledger_suites: token_ids
+                    .into_iter()
+                    .map(|token_id| (token_id, LedgerSuiteUpgradeProgress::default()))
+                    .collect(),
+            })
+            .collect();
+        Ok(Self {
+            upgrade,
+            previous_version: state
+                .ledger_suite_version()
+                .cloned()
+                .expect("BUG: missing ledger suite version"),
+            batches,
+        })
+    }
+
+    pub fn upgrade(&self) -> &UpgradeOrchestratorArgs {
+        &self.upgrade
+    }
+
+    pub fn previous_version(&self) -> &LedgerSuiteVersion {
+        &self.previous_version
+    }
+
+    /// Ledger suite version after the rollout.
+    pub fn new_version(&self) -> LedgerSuiteVersion {
+        self.upgrade
+            .clone()
+            .new_ledger_suite_version(self.previous_version.clone())
+    }
+
+    pub fn batches(&self) -> &[RolloutBatch] {
+        &self.batches
+    }
+
+    /// Returns the index of the batch being upgraded,
+    /// or `None` if all batches are healthy or if a batch was rolled back.
+    pub fn current_batch_index(&self) -> Option<usize> {
+        self.batches
+            .iter()
+            .position(|batch| batch.status != RolloutBatchStatus::Healthy)
+            .filter(|&index| {
+                !matches!(
+                    self.batches[index].status,
+                    RolloutBatchStatus::RolledBack { .. }
+                )
+            })
+    }
+
+    pub fn current_batch_mut(&mut self) -> Option<&mut RolloutBatch> {
+        self.current_batch_index()
+            .map(|index| &mut self.batches[index])
+    }
+
+    pub fn is_in_progress(&self) -> bool {
+        self.current_batch_index().is_some()
+    }
+
+    pub fn ledger_suite(&self, token_id: &TokenId) -> Option<&LedgerSuiteUpgradeProgress> {
+        self.batches
+            .iter()
+            .find_map(|batch| batch.ledger_suites.get(token_id))
+    }
+
+    pub fn ledger_suite_mut(
+        &mut self,
+        token_id: &TokenId,
+    ) -> Option<&mut LedgerSuiteUpgradeProgress> {
+        self.batches
+            .iter_mut()
+            .find_map(|batch| batch.ledger_suites.get_mut(token_id))
+    }
+}
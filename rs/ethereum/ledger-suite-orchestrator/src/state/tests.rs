@@ -485,6 +485,7 @@ mod schema_upgrades {
                 minter_id,
                 active_tasks,
                 ledger_suite_version,
+                ledger_suite_rollout: _,
             }: State,
         ) -> Self {
             Self {
@@ -513,6 +514,10 @@ mod schema_upgrades {
                 state_after_upgrade.ledger_suite_version,
                 None
             );
+            assert_eq!(
+                state_after_upgrade.ledger_suite_rollout,
+                None
+            );
         }
     }
 }
@@ -124,6 +124,7 @@ impl LedgerSuiteOrchestrator {
                 archive_compressed_wasm_hash: None,
                 cycles_management: None,
                 manage_ledger_suites: None,
+                rollout: None,
             },
         ))
     }
@@ -199,6 +200,7 @@ impl LedgerSuiteOrchestrator {
                 archive_compressed_wasm_hash: None,
                 cycles_management: None,
                 manage_ledger_suites: Some(manage_installed_canister),
+                rollout: None,
             },
         ))
     }
@@ -409,6 +411,7 @@ pub fn usdc() -> AddErc20Arg {
     AddErc20Arg {
         contract: usdc_erc20_contract(),
         ledger_init_arg: ledger_init_arg("Chain-Key USD Coin", "ckUSDC"),
+        balance_diff_accounting: None,
     }
 }
 
@@ -423,6 +426,7 @@ pub fn usdt() -> AddErc20Arg {
     AddErc20Arg {
         contract: usdt_erc20_contract(),
         ledger_init_arg: ledger_init_arg("Chain-Key Tether USD", "ckUSDT"),
+        balance_diff_accounting: None,
     }
 }
 
@@ -68,6 +68,7 @@ fn should_spawn_ledger_with_correct_init_args() {
         .add_erc20_token(AddErc20Arg {
             contract: usdc_erc20_contract(),
             ledger_init_arg: realistic_usdc_ledger_init_arg,
+            balance_diff_accounting: None,
         })
         .expect_new_ledger_and_index_canisters()
         .assert_ledger_icrc1_fee(2_000_000_000_000_u64)
@@ -131,6 +132,7 @@ fn should_change_cycles_for_canister_creation() {
                     ..Default::default()
                 }),
                 manage_ledger_suites: None,
+                rollout: None,
             },
         ))
         .unwrap();
@@ -277,6 +279,7 @@ fn should_reject_upgrade_with_invalid_args() {
         archive_compressed_wasm_hash: None,
         cycles_management: None,
         manage_ledger_suites: None,
+        rollout: None,
     };
 
     test_upgrade_with_invalid_args(
@@ -548,6 +551,7 @@ fn should_not_change_ledger_suite_version_when_registering_embedded_wasms_a_seco
             archive_compressed_wasm_hash: None,
             cycles_management: None,
             manage_ledger_suites: None,
+            rollout: None,
         },
     );
 
@@ -661,6 +665,7 @@ mod upgrade {
                 archive_compressed_wasm_hash: None,
                 cycles_management: None,
                 manage_ledger_suites: None,
+                rollout: None,
             },
         );
 
@@ -741,6 +746,7 @@ mod upgrade {
                 archive_compressed_wasm_hash: Some(embedded_archive_wasm_hash.to_string()),
                 cycles_management: None,
                 manage_ledger_suites: None,
+                rollout: None,
             },
         );
         orchestrator.advance_time_for_upgrade();
@@ -836,6 +842,7 @@ mod upgrade {
                 archive_compressed_wasm_hash: Some(embedded_archive_wasm_hash.to_string()),
                 cycles_management: None,
                 manage_ledger_suites: None,
+                rollout: None,
             },
         );
 
@@ -907,6 +914,7 @@ mod upgrade {
                 archive_compressed_wasm_hash: None,
                 cycles_management: None,
                 manage_ledger_suites: None,
+                rollout: None,
             },
         );
 
@@ -969,6 +977,7 @@ mod upgrade {
                     archive_compressed_wasm_hash: None,
                     cycles_management: None,
                     manage_ledger_suites: None,
+                    rollout: None,
                 },
             );
 
@@ -1041,6 +1050,7 @@ mod upgrade {
                 archive_compressed_wasm_hash: Some(embedded_archive_wasm_hash.to_string()),
                 cycles_management: None,
                 manage_ledger_suites: None,
+                rollout: None,
             },
         );
 
@@ -1130,6 +1140,7 @@ mod upgrade {
                     archive_compressed_wasm_hash: Some(embedded_archive_wasm_hash.to_string()),
                     cycles_management: None,
                     manage_ledger_suites: None,
+                    rollout: None,
                 },
             );
 
@@ -42,8 +42,8 @@ use ic_types::{
 pub use metrics::IngressFilterMetrics;
 pub use query_handler::InternalHttpQueryHandler;
 use query_handler::{HttpQueryHandler, QueryScheduler, QuerySchedulerFlag};
-pub use scheduler::RoundSchedule;
 use scheduler::SchedulerImpl;
+pub use scheduler::{ExecutedMessage, ExecutedMessageObserver, RoundSchedule};
 use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
 
@@ -103,6 +103,37 @@ impl ExecutionServices {
         state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
         fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
         completed_execution_messages_tx: Sender<(MessageId, Height)>,
+    ) -> ExecutionServices {
+        Self::setup_execution_with_executed_message_observer(
+            logger,
+            metrics_registry,
+            own_subnet_id,
+            own_subnet_type,
+            scheduler_config,
+            config,
+            cycles_account_manager,
+            state_reader,
+            fd_factory,
+            completed_execution_messages_tx,
+            None,
+        )
+    }
+
+    /// Same as `setup_execution()`, additionally letting the given observer observe
+    /// every message and task executed on a canister by the scheduler.
+    #[allow(clippy::type_complexity, clippy::too_many_arguments)]
+    pub fn setup_execution_with_executed_message_observer(
+        logger: ReplicaLogger,
+        metrics_registry: &MetricsRegistry,
+        own_subnet_id: SubnetId,
+        own_subnet_type: SubnetType,
+        scheduler_config: SchedulerConfig,
+        config: Config,
+        cycles_account_manager: Arc<CyclesAccountManager>,
+        state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+        completed_execution_messages_tx: Sender<(MessageId, Height)>,
+        executed_message_observer: Option<Arc<dyn ExecutedMessageObserver>>,
     ) -> ExecutionServices {
         let hypervisor = Arc::new(Hypervisor::new(
             config.clone(),
@@ -180,7 +211,7 @@ impl ExecutionServices {
             ingress_filter_metrics.clone(),
         );
 
-        let scheduler = Box::new(SchedulerImpl::new(
+        let mut scheduler = SchedulerImpl::new(
             scheduler_config,
             own_subnet_id,
             Arc::clone(&ingress_history_writer) as Arc<_>,
@@ -192,7 +223,11 @@ impl ExecutionServices {
             config.rate_limiting_of_instructions,
             config.deterministic_time_slicing,
             Arc::clone(&fd_factory),
-        ));
+        );
+        if let Some(observer) = executed_message_observer {
+            scheduler = scheduler.with_executed_message_observer(observer);
+        }
+        let scheduler = Box::new(scheduler);
 
         Self {
             ingress_filter,
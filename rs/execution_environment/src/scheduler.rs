@@ -128,6 +128,32 @@ impl SchedulerRoundLimits {
     }
 }
 
+/// A message or task executed by the scheduler.
+#[derive(Clone, Debug)]
+pub struct ExecutedMessage {
+    pub round: ExecutionRound,
+    pub canister_id: CanisterId,
+    /// The description of the executed message or task, e.g.,
+    /// `Ingress, method name transfer,` or `paused execution`.
+    pub description: String,
+    /// The ingress message whose status was updated by the execution, if any.
+    pub ingress_status: Option<(MessageId, IngressStatus)>,
+    pub instructions_used: NumInstructions,
+    /// The cycles consumed by the canister during the execution, or during
+    /// its last slice if the execution was spread over several rounds.
+    pub consumed_cycles: NominalCycles,
+}
+
+/// Observes the individual messages and tasks executed by the scheduler, e.g., to
+/// compare their outcomes and resource usage across replays of the same blocks.
+///
+/// The observer is called from the execution threads. The executions of different
+/// canisters may be observed in any order, those of a single canister are observed
+/// in the order in which they were executed.
+pub trait ExecutedMessageObserver: Send + Sync {
+    fn observe(&self, message: ExecutedMessage);
+}
+
 ////////////////////////////////////////////////////////////////////////
 /// Scheduler Implementation
 
@@ -144,6 +170,7 @@ pub(crate) struct SchedulerImpl {
     rate_limiting_of_instructions: FlagStatus,
     deterministic_time_slicing: FlagStatus,
     fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+    executed_message_observer: Option<Arc<dyn ExecutedMessageObserver>>,
 }
 
 impl SchedulerImpl {
@@ -416,9 +443,19 @@ impl SchedulerImpl {
             rate_limiting_of_instructions,
             deterministic_time_slicing,
             fd_factory,
+            executed_message_observer: None,
         }
     }
 
+    /// Lets the given observer observe every message and task executed on a canister.
+    pub(crate) fn with_executed_message_observer(
+        mut self,
+        observer: Arc<dyn ExecutedMessageObserver>,
+    ) -> Self {
+        self.executed_message_observer = Some(observer);
+        self
+    }
+
     /// Makes progress in executing long-running `install_code` messages.
     fn advance_long_running_install_code(
         &self,
@@ -955,6 +992,7 @@ impl SchedulerImpl {
                     compute_allocation_used: round_limits.compute_allocation_used,
                 };
                 let config = &self.config;
+                let executed_message_observer = self.executed_message_observer.clone();
                 scope.execute(move || {
                     *result = execute_canisters_on_thread(
                         canisters,
@@ -969,6 +1007,7 @@ impl SchedulerImpl {
                         deterministic_time_slicing,
                         round_limits,
                         subnet_size,
+                        executed_message_observer,
                     );
                 });
             }
@@ -1985,6 +2024,7 @@ fn execute_canisters_on_thread(
     deterministic_time_slicing: FlagStatus,
     mut round_limits: RoundLimits,
     subnet_size: usize,
+    executed_message_observer: Option<Arc<dyn ExecutedMessageObserver>>,
 ) -> ExecutionThreadResult {
     // Since this function runs on a helper thread, we cannot use a nested scope
     // here. Instead, we propagate metrics to the outer scope manually via
@@ -2039,6 +2079,7 @@ fn execute_canisters_on_thread(
             let timer = metrics.msg_execution_duration.start_timer();
 
             let instructions_before = round_limits.instructions;
+            let consumed_cycles_before = canister.system_state.canister_metrics.consumed_cycles;
             let canister_had_paused_execution = canister.has_paused_execution();
             let ExecuteCanisterResult {
                 canister: new_canister,
@@ -2056,6 +2097,19 @@ fn execute_canisters_on_thread(
                 &mut round_limits,
                 subnet_size,
             );
+            if let (Some(observer), Some(instructions_used)) =
+                (&executed_message_observer, instructions_used)
+            {
+                let consumed_cycles = new_canister.system_state.canister_metrics.consumed_cycles;
+                observer.observe(ExecutedMessage {
+                    round: round_id,
+                    canister_id: new_canister.canister_id(),
+                    description: description.clone().unwrap_or_default(),
+                    ingress_status: ingress_status.clone(),
+                    instructions_used,
+                    consumed_cycles: consumed_cycles - consumed_cycles_before,
+                });
+            }
             ingress_results.extend(ingress_status);
             let round_instructions_executed =
                 as_num_instructions(instructions_before - round_limits.instructions);
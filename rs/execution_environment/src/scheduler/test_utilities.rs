@@ -68,7 +68,7 @@ use crate::{
     as_round_instructions, ExecutionEnvironment, Hypervisor, IngressHistoryWriterImpl, RoundLimits,
 };
 
-use super::SchedulerImpl;
+use super::{ExecutedMessageObserver, SchedulerImpl};
 use crate::metrics::MeasurementScope;
 use ic_crypto_prng::{Csprng, RandomnessPurpose::ExecutionThread};
 use ic_types::time::UNIX_EPOCH;
@@ -664,6 +664,7 @@ pub(crate) struct SchedulerTestBuilder {
     metrics_registry: MetricsRegistry,
     round_summary: Option<ExecutionRoundSummary>,
     canister_snapshot_flag: bool,
+    executed_message_observer: Option<Arc<dyn ExecutedMessageObserver>>,
 }
 
 impl Default for SchedulerTestBuilder {
@@ -689,6 +690,7 @@ impl Default for SchedulerTestBuilder {
             metrics_registry: MetricsRegistry::new(),
             round_summary: None,
             canister_snapshot_flag: true,
+            executed_message_observer: None,
         }
     }
 }
@@ -758,6 +760,16 @@ impl SchedulerTestBuilder {
         }
     }
 
+    pub fn with_executed_message_observer(
+        self,
+        executed_message_observer: Arc<dyn ExecutedMessageObserver>,
+    ) -> Self {
+        Self {
+            executed_message_observer: Some(executed_message_observer),
+            ..self
+        }
+    }
+
     pub fn with_canister_snapshots(self, canister_snapshot_flag: bool) -> Self {
         Self {
             canister_snapshot_flag,
@@ -906,7 +918,7 @@ impl SchedulerTestBuilder {
             self.scheduler_config
                 .canister_snapshot_baseline_instructions,
         );
-        let scheduler = SchedulerImpl::new(
+        let mut scheduler = SchedulerImpl::new(
             self.scheduler_config,
             self.own_subnet_id,
             ingress_history_writer,
@@ -919,6 +931,9 @@ impl SchedulerTestBuilder {
             deterministic_time_slicing,
             Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
         );
+        if let Some(observer) = self.executed_message_observer {
+            scheduler = scheduler.with_executed_message_observer(observer);
+        }
         SchedulerTest {
             state: Some(state),
             next_canister_id: 0,
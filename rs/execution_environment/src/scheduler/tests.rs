@@ -108,6 +108,53 @@ fn can_fully_execute_canisters_with_one_input_message_each() {
     );
 }
 
+#[derive(Default)]
+struct RecordingExecutedMessageObserver(std::sync::Mutex<Vec<ExecutedMessage>>);
+
+impl ExecutedMessageObserver for RecordingExecutedMessageObserver {
+    fn observe(&self, message: ExecutedMessage) {
+        self.0.lock().unwrap().push(message);
+    }
+}
+
+#[test]
+fn executed_message_observer_observes_every_executed_message() {
+    let observer = Arc::new(RecordingExecutedMessageObserver::default());
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 2,
+            max_instructions_per_round: NumInstructions::from(1 << 30),
+            instruction_overhead_per_execution: NumInstructions::from(0),
+            instruction_overhead_per_canister: NumInstructions::from(0),
+            ..SchedulerConfig::application_subnet()
+        })
+        .with_executed_message_observer(observer.clone())
+        .build();
+
+    let canister_id = test.create_canister();
+    let first = test.send_ingress(canister_id, ingress(5));
+    let second = test.send_ingress(canister_id, ingress(7));
+
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    let observed = observer.0.lock().unwrap();
+    assert_eq!(observed.len(), 2);
+    for (message, (message_id, instructions)) in observed.iter().zip([(first, 5), (second, 7)]) {
+        let instructions = NumInstructions::from(instructions);
+        assert_eq!(message.round, test.last_round());
+        assert_eq!(message.canister_id, canister_id);
+        assert_eq!(
+            message.ingress_status.as_ref().map(|(id, _)| id),
+            Some(&message_id)
+        );
+        assert_eq!(message.instructions_used, instructions);
+        assert_eq!(
+            message.consumed_cycles,
+            NominalCycles::from(test.execution_cost(instructions))
+        );
+    }
+}
+
 #[test]
 fn stops_executing_messages_when_heap_delta_capacity_reached() {
     let mut test = SchedulerTestBuilder::new()
@@ -69,6 +69,8 @@ pub struct SendTransactionRequest {
     pub network: i32,
     #[prost(bytes = "vec", tag = "2")]
     pub transaction: ::prost::alloc::vec::Vec<u8>,
+    #[prost(enumeration = "Chain", tag = "3")]
+    pub chain: i32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -149,6 +151,8 @@ pub struct GetSuccessorsRequestInitial {
     pub processed_block_hashes: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
     #[prost(bytes = "vec", tag = "3")]
     pub anchor: ::prost::alloc::vec::Vec<u8>,
+    #[prost(enumeration = "Chain", tag = "4")]
+    pub chain: i32,
 }
 /// A response containing new successor blocks from the Bitcoin network.
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -209,3 +213,36 @@ impl Network {
         }
     }
 }
+/// The UTXO chain of a request. Requests without a chain are Bitcoin requests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Chain {
+    Unspecified = 0,
+    Bitcoin = 1,
+    Litecoin = 2,
+    Dogecoin = 3,
+}
+impl Chain {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Chain::Unspecified => "CHAIN_UNSPECIFIED",
+            Chain::Bitcoin => "CHAIN_BITCOIN",
+            Chain::Litecoin => "CHAIN_LITECOIN",
+            Chain::Dogecoin => "CHAIN_DOGECOIN",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "CHAIN_UNSPECIFIED" => Some(Self::Unspecified),
+            "CHAIN_BITCOIN" => Some(Self::Bitcoin),
+            "CHAIN_LITECOIN" => Some(Self::Litecoin),
+            "CHAIN_DOGECOIN" => Some(Self::Dogecoin),
+            _ => None,
+        }
+    }
+}
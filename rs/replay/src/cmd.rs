@@ -15,6 +15,29 @@ impl std::str::FromStr for ClapSubnetId {
     }
 }
 
+/// A canister ID and the path to a Wasm module, given as `<canister_id>=<wasm_path>`.
+#[derive(Clone)]
+pub struct ClapCanisterWasm {
+    pub canister_id: CanisterId,
+    pub wasm_path: PathBuf,
+}
+
+impl std::str::FromStr for ClapCanisterWasm {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (canister_id, wasm_path) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected <canister_id>=<wasm_path>, got {:?}", s))?;
+        let canister_id = PrincipalId::from_str(canister_id)
+            .map_err(|e| format!("Unable to parse canister_id {:?}", e))
+            .map(CanisterId::unchecked_from_principal)?;
+        Ok(ClapCanisterWasm {
+            canister_id,
+            wasm_path: PathBuf::from(wasm_path),
+        })
+    }
+}
+
 #[derive(Parser)]
 #[clap(version = "1.0")]
 pub struct ReplayToolArgs {
@@ -63,6 +86,16 @@ pub enum SubCommand {
     /// Restore from the backup.
     RestoreFromBackup(RestoreFromBackupCmd),
 
+    /// Replay the backup twice, once as recorded and once with the given canisters
+    /// upgraded to different Wasm modules, and report the divergences between both runs.
+    /// Neither the state nor the registry local store are modified.
+    WhatIf(WhatIfCmd),
+
+    /// Replay the backup and export the ingress messages received by the given
+    /// canisters, together with their results, as JSON lines.
+    /// Neither the state nor the registry local store are modified.
+    ExportIngress(ExportIngressCmd),
+
     /// The replay will add a test Neuron to the Governance canister
     /// and the corresponding account in the ledger.
     WithNeuronForTests(WithNeuronCmd),
@@ -116,6 +149,57 @@ pub struct RestoreFromBackupCmd {
     pub start_height: u64,
 }
 
+#[derive(Clone, Parser)]
+pub struct WhatIfCmd {
+    /// Registry local store path
+    pub registry_local_store_path: PathBuf,
+    /// Backup spool path
+    pub backup_spool_path: PathBuf,
+    /// The replica version to be restored
+    pub replica_version: String,
+    /// Height from which the replay should happen
+    pub start_height: u64,
+    /// Canister to upgrade before replaying, given as `<canister_id>=<wasm_path>`.
+    /// The upgrade argument is the empty Candid argument list.
+    #[clap(long = "canister-wasm", required = true)]
+    pub canister_wasms: Vec<ClapCanisterWasm>,
+    /// Controller sending the upgrades. Required if an upgraded canister has more
+    /// than one controller; defaults to the only controller otherwise.
+    #[clap(long)]
+    pub sender: Option<PrincipalId>,
+    /// Number of heights after which the outcomes of ingress messages are collected.
+    /// Instructions and cycles are recorded for every message executed on the
+    /// upgraded canisters, independently of this interval.
+    #[clap(long, default_value = "50")]
+    pub observation_interval: u64,
+    /// Write the divergences as JSON to this file.
+    #[clap(long)]
+    pub report_file: Option<PathBuf>,
+}
+
+#[derive(Clone, Parser)]
+pub struct ExportIngressCmd {
+    /// Registry local store path
+    pub registry_local_store_path: PathBuf,
+    /// Backup spool path
+    pub backup_spool_path: PathBuf,
+    /// The replica version to be restored
+    pub replica_version: String,
+    /// Height from which the replay should happen
+    pub start_height: u64,
+    /// File the JSON lines are written to
+    pub output_file: PathBuf,
+    /// Only export ingress messages received by this canister, including management
+    /// canister calls targeting it. Can be given multiple times; if not given, the
+    /// ingress messages received by all canisters are exported.
+    #[clap(long = "canister-id")]
+    pub canister_ids: Vec<CanisterId>,
+    /// Number of heights after which the results of the collected messages are looked up.
+    /// Results of messages taking longer than the ingress history retains them are lost.
+    #[clap(long, default_value = "50")]
+    pub observation_interval: u64,
+}
+
 #[derive(Clone, Parser)]
 pub struct RestoreFromBackup2Cmd {
     /// Registry local store path
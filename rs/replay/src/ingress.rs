@@ -4,6 +4,7 @@ use crate::cmd::{
 };
 use candid::{decode_one, Encode};
 use ic_canister_client::{prepare_update, Agent, Sender};
+use ic_management_canister_types::{self as ic00, CanisterInstallMode, InstallCodeArgs, Payload};
 use ic_nervous_system_common::ledger;
 use ic_nns_common::pb::v1::NeuronId;
 use ic_nns_constants::{GOVERNANCE_CANISTER_ID, LEDGER_CANISTER_ID, REGISTRY_CANISTER_ID};
@@ -388,6 +389,32 @@ pub fn cmd_remove_subnet(
     }
 }
 
+/// Creates an ingress message upgrading the given canister to the given Wasm module,
+/// sent on behalf of the given controller of the canister.
+pub fn upgrade_canister(
+    controller: &PrincipalId,
+    canister_id: CanisterId,
+    wasm_module: Vec<u8>,
+    expiry: Time,
+) -> Result<SignedIngress, String> {
+    let payload = InstallCodeArgs::new(
+        CanisterInstallMode::Upgrade,
+        canister_id,
+        wasm_module,
+        Encode!().expect("Couldn't candid-encode empty upgrade argument"),
+        None,
+        None,
+    )
+    .encode();
+    make_signed_ingress(
+        &agent_with_principal_as_sender(controller),
+        ic00::IC_00,
+        &ic00::Method::InstallCode.to_string(),
+        payload,
+        expiry,
+    )
+}
+
 fn show_mutation_type(mutation_type: i32) -> &'static str {
     use ic_registry_transport::pb::v1::registry_mutation::Type;
     match mutation_type {
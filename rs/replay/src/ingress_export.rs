@@ -0,0 +1,212 @@
+//! Export of the ingress messages received by canisters, together with their
+//! execution results, from the finalized blocks replayed from a backup.
+use crate::{player::ReplayObserver, what_if::MessageOutcome};
+use ic_consensus_utils::pool_reader::PoolReader;
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    ingress::IngressStatus,
+    messages::{extract_effective_canister_id, MessageId, SignedIngress},
+    CanisterId, Height, PrincipalId, SubnetId,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+    ops::RangeInclusive,
+};
+
+/// An ingress message included in a finalized block, as exported in a JSON line.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ExportedIngress {
+    /// The height of the block including the message.
+    pub height: Height,
+    pub message_id: String,
+    pub sender: PrincipalId,
+    pub receiver: CanisterId,
+    pub method_name: String,
+    /// Hex-encoded argument.
+    pub argument: String,
+    pub ingress_expiry: u64,
+    /// The execution result, or `None` if the message didn't finish executing
+    /// by the end of the replay or its result was already pruned from the ingress history.
+    pub result: Option<MessageOutcome>,
+}
+
+/// Collects the ingress messages received by the given canisters (or by any canister,
+/// if none is given) and writes them as JSON lines once their results are known.
+/// Management canister calls count as received by the canister they target.
+pub struct IngressExporter<W: Write> {
+    canister_ids: BTreeSet<CanisterId>,
+    subnet_id: SubnetId,
+    pending: BTreeMap<MessageId, ExportedIngress>,
+    writer: W,
+    exported: usize,
+}
+
+impl<W: Write> IngressExporter<W> {
+    pub fn new(canister_ids: BTreeSet<CanisterId>, subnet_id: SubnetId, writer: W) -> Self {
+        Self {
+            canister_ids,
+            subnet_id,
+            pending: BTreeMap::new(),
+            writer,
+            exported: 0,
+        }
+    }
+
+    fn include(&mut self, height: Height, ingress: &SignedIngress) {
+        let receiver = ingress.canister_id();
+        if !self.canister_ids.is_empty() && !self.canister_ids.contains(&receiver) {
+            // Payloads that can't be decoded are rejected by the management canister
+            // and thus not attributed to any canister.
+            let effective_canister_id =
+                extract_effective_canister_id(ingress.content(), self.subnet_id)
+                    .ok()
+                    .flatten();
+            if !effective_canister_id.is_some_and(|id| self.canister_ids.contains(&id)) {
+                return;
+            }
+        }
+        self.pending.insert(
+            ingress.id(),
+            ExportedIngress {
+                height,
+                message_id: ingress.id().to_string(),
+                sender: ingress.sender().get(),
+                receiver,
+                method_name: ingress.method_name(),
+                argument: hex::encode(ingress.method_arg()),
+                ingress_expiry: ingress.expiry_time().as_nanos_since_unix_epoch(),
+                result: None,
+            },
+        );
+    }
+
+    fn write(&mut self, exported_ingress: &ExportedIngress) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, exported_ingress)?;
+        self.writer.write_all(b"\n")?;
+        self.exported += 1;
+        Ok(())
+    }
+
+    /// Writes the messages whose results are still unknown and flushes the writer.
+    /// Returns the total number of exported messages.
+    pub fn finish(&mut self) -> io::Result<usize> {
+        for exported_ingress in std::mem::take(&mut self.pending).into_values() {
+            self.write(&exported_ingress)?;
+        }
+        self.writer.flush()?;
+        Ok(self.exported)
+    }
+}
+
+impl<W: Write> ReplayObserver for IngressExporter<W> {
+    /// Collects the ingress messages from the finalized blocks at the given heights
+    /// and exports all collected messages whose execution has finished.
+    fn observe(
+        &mut self,
+        pool: &PoolReader<'_>,
+        heights: RangeInclusive<Height>,
+        state: &ReplicatedState,
+    ) {
+        let mut height = *heights.start();
+        while height <= *heights.end() {
+            let block = pool
+                .get_finalized_block(height)
+                .unwrap_or_else(|| panic!("Finalized block is not found at height {}", height));
+            let payload = block.payload.as_ref();
+            if !payload.is_summary() {
+                let messages: Vec<SignedIngress> =
+                    Vec::try_from(payload.as_data().batch.ingress.clone()).unwrap_or_else(|err| {
+                        panic!("Couldn't read the ingress payload at {}: {:?}", height, err)
+                    });
+                for ingress in &messages {
+                    self.include(height, ingress);
+                }
+            }
+            height = height.increment();
+        }
+
+        let ingress_history = &state.metadata.ingress_history;
+        let finished: Vec<_> = self
+            .pending
+            .keys()
+            .filter(|message_id| match ingress_history.get(message_id) {
+                Some(IngressStatus::Known { state, .. }) => state.is_terminal(),
+                Some(IngressStatus::Unknown) | None => false,
+            })
+            .cloned()
+            .collect();
+        for message_id in finished {
+            let mut exported_ingress = self.pending.remove(&message_id).unwrap();
+            if let Some(IngressStatus::Known { state, .. }) = ingress_history.get(&message_id) {
+                exported_ingress.result = MessageOutcome::from_ingress_state(state);
+            }
+            self.write(&exported_ingress)
+                .unwrap_or_else(|err| panic!("Couldn't export ingress message: {}", err));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_management_canister_types::{CanisterIdRecord, Payload, IC_00};
+    use ic_test_utilities_types::{
+        ids::{canister_test_id, subnet_test_id, user_test_id},
+        messages::SignedIngressBuilder,
+    };
+
+    #[test]
+    fn test_include_filters_by_receiver() {
+        let mut exporter = IngressExporter::new(
+            BTreeSet::from([canister_test_id(1)]),
+            subnet_test_id(1),
+            vec![],
+        );
+        let ingress = |canister_id| {
+            SignedIngressBuilder::new()
+                .canister_id(canister_id)
+                .sender(user_test_id(7))
+                .method_name("transfer")
+                .method_payload(vec![1, 2, 3])
+                .build()
+        };
+        exporter.include(Height::from(5), &ingress(canister_test_id(1)));
+        exporter.include(Height::from(5), &ingress(canister_test_id(2)));
+        assert_eq!(exporter.finish().unwrap(), 1);
+
+        let line = String::from_utf8(exporter.writer.clone()).unwrap();
+        let exported: ExportedIngress = serde_json::from_str(line.trim_end()).unwrap();
+        assert_eq!(exported.height, Height::from(5));
+        assert_eq!(exported.sender, user_test_id(7).get());
+        assert_eq!(exported.receiver, canister_test_id(1));
+        assert_eq!(exported.method_name, "transfer");
+        assert_eq!(exported.argument, "010203");
+        assert_eq!(exported.result, None);
+    }
+    #[test]
+    fn test_include_matches_effective_canister_id_of_management_calls() {
+        let mut exporter = IngressExporter::new(
+            BTreeSet::from([canister_test_id(1)]),
+            subnet_test_id(1),
+            vec![],
+        );
+        let management_call = |canister_id| {
+            SignedIngressBuilder::new()
+                .canister_id(IC_00)
+                .sender(user_test_id(7))
+                .method_name("stop_canister")
+                .method_payload(CanisterIdRecord::from(canister_id).encode())
+                .build()
+        };
+        exporter.include(Height::from(5), &management_call(canister_test_id(1)));
+        exporter.include(Height::from(5), &management_call(canister_test_id(2)));
+        assert_eq!(exporter.finish().unwrap(), 1);
+
+        let line = String::from_utf8(exporter.writer.clone()).unwrap();
+        let exported: ExportedIngress = serde_json::from_str(line.trim_end()).unwrap();
+        assert_eq!(exported.receiver, IC_00);
+        assert_eq!(exported.method_name, "stop_canister");
+    }
+}
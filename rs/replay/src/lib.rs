@@ -12,11 +12,18 @@
 //! state (after all past blocks have been executed). All of them are meant to
 //! help recover NNS subnet where the registry canister resides.
 //!
+//! The `export-ingress` sub-command extracts the ingress messages received by
+//! canisters and their results from a backup as JSON lines.
+//!
+//! The `what-if` sub-command replays the artifacts stored as backup against
+//! upgraded canisters and reports how their outcomes diverge from the original run.
+//!
 //! Use `ic-replay --help` to find out more.
 
 use crate::{
     cmd::{ReplayToolArgs, SubCommand},
     ingress::*,
+    ingress_export::IngressExporter,
     player::{Player, ReplayResult},
 };
 use ic_canister_client::{Agent, Sender};
@@ -25,15 +32,22 @@ use ic_nns_constants::GOVERNANCE_CANISTER_ID;
 use ic_protobuf::{registry::subnet::v1::InitialNiDkgTranscriptRecord, types::v1 as pb};
 use ic_types::ReplicaVersion;
 use prost::Message;
-use std::{cell::RefCell, convert::TryFrom, rc::Rc};
+use std::{
+    cell::RefCell,
+    convert::TryFrom,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
 
 mod backup;
 pub mod cmd;
 pub mod ingress;
+pub mod ingress_export;
 mod mocks;
 pub mod player;
 mod registry_helper;
 mod validator;
+pub mod what_if;
 
 /// Replays the past blocks and creates a checkpoint of the latest state.
 /// # An example of how to set the arguments
@@ -119,12 +133,53 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                 &cmd.registry_local_store_path,
                 subnet_id,
                 cmd.start_height,
+                None,
             )
             .with_replay_target_height(target_height);
             *res_clone.borrow_mut() = player.restore(cmd.start_height + 1);
             return;
         }
 
+        if let Some(SubCommand::ExportIngress(cmd)) = subcmd {
+            let _enter_guard = rt.enter();
+            let output_file = std::fs::File::create(&cmd.output_file)
+                .unwrap_or_else(|err| panic!("Couldn't create {:?}: {}", cmd.output_file, err));
+            let exporter = Arc::new(Mutex::new(IngressExporter::new(
+                cmd.canister_ids.iter().cloned().collect(),
+                subnet_id,
+                std::io::BufWriter::new(output_file),
+            )));
+            let mut player = Player::new_for_backup_copy(
+                cfg,
+                ReplicaVersion::try_from(cmd.replica_version.as_str())
+                    .expect("Couldn't parse the replica version"),
+                &cmd.backup_spool_path,
+                &cmd.registry_local_store_path,
+                subnet_id,
+                cmd.start_height,
+                None,
+            )
+            .with_replay_target_height(target_height)
+            .with_observer(Box::new(exporter.clone()), cmd.observation_interval);
+            *res_clone.borrow_mut() = player.restore(cmd.start_height + 1);
+            let exported = exporter
+                .lock()
+                .unwrap()
+                .finish()
+                .expect("Couldn't write the exported ingress messages");
+            println!(
+                "Exported {} ingress messages to {:?}",
+                exported, cmd.output_file
+            );
+            return;
+        }
+
+        if let Some(SubCommand::WhatIf(cmd)) = subcmd {
+            let _enter_guard = rt.enter();
+            *res_clone.borrow_mut() = what_if::what_if(cfg, cmd, subnet_id, target_height);
+            return;
+        }
+
         {
             let _enter_guard = rt.enter();
             let player = match (subcmd.as_ref(), target_height) {
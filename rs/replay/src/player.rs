@@ -3,6 +3,7 @@ use crate::{
     backup::{cup_file_name, rename_file},
     ingress::IngressWithPrinter,
     validator::{InvalidArtifact, ReplayValidator},
+    what_if::{CanisterUpgrade, UpgradingMessageRouting},
 };
 use ic_artifact_pool::{
     certification_pool::CertificationPoolImpl,
@@ -19,7 +20,7 @@ use ic_crypto_test_utils_ni_dkg::{
     dummy_initial_dkg_transcript_with_master_key, sign_message, SecretKeyBytes,
 };
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{ExecutedMessageObserver, ExecutionServices};
 use ic_interfaces::{
     certification::CertificationPool,
     execution_environment::{IngressHistoryReader, QueryExecutionError, QueryExecutionService},
@@ -51,6 +52,7 @@ use ic_registry_transport::{
     deserialize_get_value_response, serialize_get_changes_since_request,
     serialize_get_value_request,
 };
+use ic_replicated_state::ReplicatedState;
 use ic_state_manager::StateManagerImpl;
 use ic_types::{
     batch::{Batch, BatchMessages, BlockmakerMetrics},
@@ -64,7 +66,7 @@ use ic_types::{
     },
     ingress::{IngressState, IngressStatus, WasmResult},
     malicious_flags::MaliciousFlags,
-    messages::{CertificateDelegation, Query, QuerySource},
+    messages::{CertificateDelegation, MessageId, Query, QuerySource},
     signature::ThresholdSignature,
     time::current_time,
     CryptoHashOfPartialState, CryptoHashOfState, Height, NodeId, PrincipalId, Randomness,
@@ -74,9 +76,10 @@ use rand::{rngs::StdRng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use slog_async::AsyncGuard;
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::RangeInclusive,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 use tempfile::TempDir;
@@ -111,6 +114,29 @@ pub enum ReplayError {
 
 pub type ReplayResult = Result<StateParams, ReplayError>;
 
+/// Observes the finalized blocks and the resulting states during a restore from backup.
+pub trait ReplayObserver {
+    /// Called once the batches of the finalized blocks at the given heights
+    /// have been executed, resulting in the given state.
+    fn observe(
+        &mut self,
+        pool: &PoolReader<'_>,
+        heights: RangeInclusive<Height>,
+        state: &ReplicatedState,
+    );
+}
+
+impl<T: ReplayObserver> ReplayObserver for Arc<Mutex<T>> {
+    fn observe(
+        &mut self,
+        pool: &PoolReader<'_>,
+        heights: RangeInclusive<Height>,
+        state: &ReplicatedState,
+    ) {
+        self.lock().unwrap().observe(pool, heights, state)
+    }
+}
+
 /// The main ic-replay component that sets up consensus and execution
 /// environment to replay past blocks.
 pub struct Player {
@@ -136,7 +162,15 @@ pub struct Player {
     // The target height until which the state will be replayed.
     // None means finalized height.
     replay_target_height: Option<u64>,
+    // Observes the replayed blocks and states every `observation_interval` heights.
+    observer: Option<Box<dyn ReplayObserver>>,
+    observation_interval: u64,
+    // Set if canister upgrades are injected into the first replayed batch.
+    upgrading_message_routing: Option<Arc<UpgradingMessageRouting>>,
     runtime: Runtime,
+    // Temporary copies of the state and the registry local store, if any.
+    // Declared last so that it is only removed once the state manager is dropped.
+    copy_dir: Option<TempDir>,
 }
 
 impl Player {
@@ -149,6 +183,7 @@ impl Player {
         registry_local_store_path: &Path,
         subnet_id: SubnetId,
         start_height: u64,
+        executed_message_observer: Option<Arc<dyn ExecutedMessageObserver>>,
     ) -> Self {
         let (log, _async_log_guard) = new_replica_logger_from_config(&cfg.logger);
 
@@ -207,11 +242,54 @@ impl Player {
             replica_version,
             log,
             _async_log_guard,
+            executed_message_observer,
         );
         player.tmp_dir = Some(tmp_dir);
         player
     }
 
+    /// Create and return a `Player` for restoring states from backups, which operates on
+    /// temporary copies of the state and the registry local store, leaving both untouched.
+    pub fn new_for_backup_copy(
+        mut cfg: Config,
+        replica_version: ReplicaVersion,
+        backup_spool_path: &Path,
+        registry_local_store_path: &Path,
+        subnet_id: SubnetId,
+        start_height: u64,
+        executed_message_observer: Option<Arc<dyn ExecutedMessageObserver>>,
+    ) -> Self {
+        let copy_dir = tempfile::Builder::new()
+            .prefix("replay_copy_")
+            .tempdir()
+            .expect("Couldn't create a temporary directory");
+        let state_root = copy_dir.path().join("ic_state");
+        println!(
+            "Copying the state {:?} to {:?}...",
+            cfg.state_manager.state_root(),
+            state_root
+        );
+        copy_dir_all(&cfg.state_manager.state_root(), &state_root)
+            .expect("Couldn't copy the state");
+        cfg.state_manager = ic_config::state_manager::Config::new(state_root);
+        let local_store_path = copy_dir.path().join("ic_registry_local_store");
+        copy_dir_all(registry_local_store_path, &local_store_path)
+            .expect("Couldn't copy the registry local store");
+        cfg.registry_client.local_store = local_store_path.clone();
+
+        let mut player = Player::new_for_backup(
+            cfg,
+            replica_version,
+            backup_spool_path,
+            &local_store_path,
+            subnet_id,
+            start_height,
+            executed_message_observer,
+        );
+        player.copy_dir = Some(copy_dir);
+        player
+    }
+
     /// Create and return a `Player` from a replica configuration object for
     /// subnet recovery.
     pub fn new(cfg: Config, subnet_id: SubnetId) -> Self {
@@ -253,6 +331,7 @@ impl Player {
             replica_version,
             log,
             _async_log_guard,
+            None,
         )
     }
 
@@ -266,6 +345,7 @@ impl Player {
         replica_version: ReplicaVersion,
         log: ReplicaLogger,
         _async_log_guard: AsyncGuard,
+        executed_message_observer: Option<Arc<dyn ExecutedMessageObserver>>,
     ) -> Self {
         println!("Setting default replica version {}", replica_version);
         if ReplicaVersion::set_default_version(replica_version.clone()).is_err() {
@@ -307,7 +387,7 @@ impl Player {
             MaliciousFlags::default(),
         ));
         let (completed_execution_messages_tx, _) = tokio::sync::mpsc::channel(1);
-        let execution_service = ExecutionServices::setup_execution(
+        let execution_service = ExecutionServices::setup_execution_with_executed_message_observer(
             log.clone(),
             &metrics_registry,
             subnet_id,
@@ -318,6 +398,7 @@ impl Player {
             Arc::clone(&state_manager) as Arc<_>,
             state_manager.get_fd_factory(),
             completed_execution_messages_tx,
+            executed_message_observer,
         );
         let message_routing = Arc::new(MessageRoutingImpl::new(
             state_manager.clone(),
@@ -385,7 +466,11 @@ impl Player {
             _async_log_guard,
             tmp_dir: None,
             replay_target_height: None,
+            observer: None,
+            observation_interval: 1,
+            upgrading_message_routing: None,
             runtime,
+            copy_dir: None,
         }
     }
 
@@ -395,6 +480,38 @@ impl Player {
         self
     }
 
+    /// Let the given observer observe the replayed blocks and states every `interval`
+    /// heights during the restore.
+    pub fn with_observer(mut self, observer: Box<dyn ReplayObserver>, interval: u64) -> Self {
+        self.observer = Some(observer);
+        self.observation_interval = interval.max(1);
+        self
+    }
+
+    /// Upgrade the given canisters in the first replayed batch. As the state diverges
+    /// from the recorded one, state hashes are no longer compared to those in CUPs.
+    pub fn with_canister_upgrades(mut self, upgrades: Vec<CanisterUpgrade>) -> Self {
+        let message_routing = Arc::new(UpgradingMessageRouting::new(
+            self.message_routing.clone(),
+            upgrades,
+        ));
+        self.message_routing = message_routing.clone();
+        self.upgrading_message_routing = Some(message_routing);
+        self
+    }
+
+    /// Returns the IDs of the injected upgrade messages.
+    pub fn upgrade_messages(&self) -> BTreeSet<MessageId> {
+        self.upgrading_message_routing
+            .as_ref()
+            .map(|message_routing| message_routing.upgrade_messages())
+            .unwrap_or_default()
+    }
+
+    pub fn state_manager(&self) -> &StateManagerImpl {
+        &self.state_manager
+    }
+
     /// In case a consensus pool was supplied, replay past finalized but
     /// un-executed blocks by delivering ingress messages for execution,
     /// and make a full checkpoint of the latest state when they all finish.
@@ -726,6 +843,39 @@ impl Player {
         last_batch_height
     }
 
+    /// Deliver finalized batches since last expected batch height in steps of
+    /// `observation_interval` heights and observe the state after each step.
+    fn deliver_and_observe_batches(&mut self) -> Height {
+        let pool = PoolReader::new(self.consensus_pool.as_ref().unwrap());
+        let finalized_height = pool.get_finalized_height();
+        let target_height = self
+            .replay_target_height
+            .map(Height::from)
+            .map_or(finalized_height, |height| height.min(finalized_height));
+        loop {
+            let expected_batch_height = self.message_routing.expected_batch_height();
+            let step_height = target_height
+                .min(expected_batch_height + Height::from(self.observation_interval - 1));
+            let last_batch_height = self.deliver_batches(
+                self.message_routing.as_ref(),
+                &pool,
+                self.membership.as_ref().unwrap(),
+                Some(step_height),
+            );
+            self.wait_for_state(last_batch_height);
+            if let Some(observer) = self.observer.as_mut() {
+                observer.observe(
+                    &pool,
+                    expected_batch_height..=last_batch_height,
+                    &self.state_manager.get_latest_state().take(),
+                );
+            }
+            if last_batch_height >= target_height || last_batch_height < step_height {
+                break last_batch_height;
+            }
+        }
+    }
+
     fn deliver_extra_batch<F: FnMut(&Player, Time) -> Vec<IngressWithPrinter>>(
         &self,
         message_routing: &dyn MessageRouting,
@@ -1085,12 +1235,16 @@ impl Player {
                 &mut invalid_artifacts,
             );
 
-            let last_batch_height = self.deliver_batches(
-                self.message_routing.as_ref(),
-                &PoolReader::new(self.consensus_pool.as_ref().unwrap()),
-                self.membership.as_ref().unwrap(),
-                self.replay_target_height.map(Height::from),
-            );
+            let last_batch_height = if self.observer.is_some() {
+                self.deliver_and_observe_batches()
+            } else {
+                self.deliver_batches(
+                    self.message_routing.as_ref(),
+                    &PoolReader::new(self.consensus_pool.as_ref().unwrap()),
+                    self.membership.as_ref().unwrap(),
+                    self.replay_target_height.map(Height::from),
+                )
+            };
             self.wait_for_state(last_batch_height);
             if let Some(height) = target_height {
                 if last_batch_height >= height {
@@ -1215,10 +1369,12 @@ impl Player {
             return Ok(());
         }
 
-        // Verify state hash against the state hash in the CUP
-        if get_state_hash(&*self.state_manager, last_cup.height())
-            .expect("No state hash at a current CUP height found")
-            != last_cup.content.state_hash
+        // Verify state hash against the state hash in the CUP, unless the state
+        // deliberately diverges from the recorded one due to injected canister upgrades.
+        if self.upgrading_message_routing.is_none()
+            && get_state_hash(&*self.state_manager, last_cup.height())
+                .expect("No state hash at a current CUP height found")
+                != last_cup.content.state_hash
         {
             println!(
                 "The state hash of the CUP at height {:?} differs from the local state's hash",
@@ -1249,6 +1405,20 @@ impl Player {
     }
 }
 
+fn copy_dir_all(src: &Path, dst: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let dst = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &dst)?;
+        } else {
+            std::fs::copy(entry.path(), dst)?;
+        }
+    }
+    Ok(())
+}
+
 /// Return the set of signers that created multiple valid certification shares for the same height
 fn find_malicious_nodes(
     certification_pool: &CertificationPoolImpl,
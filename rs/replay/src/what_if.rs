@@ -0,0 +1,635 @@
+//! Support for the `what-if` mode of the replay tool: the blocks from a backup
+//! are replayed twice, once as recorded and once with some canisters upgraded to
+//! different Wasm modules, and the outcomes of both runs are compared.
+use crate::{
+    cmd::WhatIfCmd,
+    ingress::upgrade_canister,
+    player::{Player, ReplayError, ReplayObserver, ReplayResult, StateParams},
+};
+use ic_config::Config;
+use ic_consensus_utils::pool_reader::PoolReader;
+use ic_execution_environment::{ExecutedMessage, ExecutedMessageObserver};
+use ic_interfaces::messaging::{MessageRouting, MessageRoutingError};
+use ic_interfaces_state_manager::StateReader;
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    batch::Batch,
+    ingress::{IngressState, IngressStatus, WasmResult},
+    messages::MessageId,
+    CanisterId, Height, PrincipalId, ReplicaVersion, SubnetId,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+// Expiry of the injected upgrade messages relative to the time of the batch they are inducted in.
+const UPGRADE_INGRESS_EXPIRY: Duration = Duration::from_secs(4 * 60);
+
+/// The terminal outcome of an ingress message.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum MessageOutcome {
+    /// Hex-encoded reply.
+    Reply(String),
+    Reject(String),
+    Error(String),
+}
+
+impl MessageOutcome {
+    /// Returns the outcome of an ingress message in the given state, if it is terminal
+    /// and its reply or error has not been pruned from the ingress history yet.
+    pub fn from_ingress_state(state: &IngressState) -> Option<Self> {
+        match state {
+            IngressState::Completed(WasmResult::Reply(reply)) => {
+                Some(MessageOutcome::Reply(hex::encode(reply)))
+            }
+            IngressState::Completed(WasmResult::Reject(reject)) => {
+                Some(MessageOutcome::Reject(reject.clone()))
+            }
+            IngressState::Failed(error) => Some(MessageOutcome::Error(error.to_string())),
+            IngressState::Received | IngressState::Processing | IngressState::Done => None,
+        }
+    }
+}
+
+/// An ingress message with a terminal outcome.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ObservedMessage {
+    /// The height at which the outcome was first observed.
+    pub height: Height,
+    pub receiver: PrincipalId,
+    pub outcome: MessageOutcome,
+}
+
+/// Identifies the executions of a message across the original and the patched run.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub enum ExecutionKey {
+    /// The executions updating the status of the given ingress message, i.e., the
+    /// execution of the message itself and of the responses completing it.
+    Ingress(String),
+    /// The `index`-th execution on the canister at the given height not updating the
+    /// status of an ingress message, e.g., of an inter-canister request or a timer.
+    Other {
+        height: Height,
+        canister_id: CanisterId,
+        index: u64,
+    },
+}
+
+/// The instructions and cycles used by the executions of a message on a canister.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub struct ObservedExecution {
+    /// The height of the first execution.
+    pub height: Height,
+    pub canister_id: CanisterId,
+    /// The description of the first execution, e.g., `Ingress, method name transfer,`.
+    pub description: String,
+    pub executions: u64,
+    pub instructions: u64,
+    pub consumed_cycles: u128,
+}
+
+/// Message outcomes and executions observed during a replay.
+#[derive(Clone, Debug, Default)]
+pub struct WhatIfObservations {
+    messages: BTreeMap<MessageId, ObservedMessage>,
+    executions: BTreeMap<ExecutionKey, ObservedExecution>,
+    // The number of executions per height and canister not updating an ingress status.
+    other_executions: BTreeMap<(Height, CanisterId), u64>,
+}
+
+impl WhatIfObservations {
+    /// Records the given execution, adding it to the previous executions
+    /// updating the status of the same ingress message, if any.
+    fn record_execution(&mut self, message: ExecutedMessage) {
+        let height = Height::from(message.round.get());
+        let key = match &message.ingress_status {
+            Some((message_id, _)) => ExecutionKey::Ingress(message_id.to_string()),
+            None => {
+                let index = self
+                    .other_executions
+                    .entry((height, message.canister_id))
+                    .or_default();
+                *index += 1;
+                ExecutionKey::Other {
+                    height,
+                    canister_id: message.canister_id,
+                    index: *index - 1,
+                }
+            }
+        };
+        let execution = self
+            .executions
+            .entry(key)
+            .or_insert_with(|| ObservedExecution {
+                height,
+                canister_id: message.canister_id,
+                description: message.description,
+                executions: 0,
+                instructions: 0,
+                consumed_cycles: 0,
+            });
+        execution.executions += 1;
+        execution.instructions += message.instructions_used.get();
+        execution.consumed_cycles += message.consumed_cycles.get();
+    }
+}
+
+impl ReplayObserver for WhatIfObservations {
+    /// Records the terminal ingress outcomes not observed before.
+    fn observe(
+        &mut self,
+        _pool: &PoolReader<'_>,
+        heights: RangeInclusive<Height>,
+        state: &ReplicatedState,
+    ) {
+        let height = *heights.end();
+        for (message_id, status) in state.metadata.ingress_history.statuses() {
+            let IngressStatus::Known {
+                receiver,
+                state: ingress_state,
+                ..
+            } = status
+            else {
+                continue;
+            };
+            let Some(outcome) = MessageOutcome::from_ingress_state(ingress_state) else {
+                continue;
+            };
+            self.messages
+                .entry(message_id.clone())
+                .or_insert_with(|| ObservedMessage {
+                    height,
+                    receiver: *receiver,
+                    outcome,
+                });
+        }
+    }
+}
+
+/// Records the executions of messages on the tracked canisters into the shared observations.
+struct ExecutionRecorder {
+    canister_ids: BTreeSet<CanisterId>,
+    observations: Arc<Mutex<WhatIfObservations>>,
+}
+
+impl ExecutedMessageObserver for ExecutionRecorder {
+    fn observe(&self, message: ExecutedMessage) {
+        if self.canister_ids.contains(&message.canister_id) {
+            self.observations.lock().unwrap().record_execution(message);
+        }
+    }
+}
+
+/// A difference between the original and the patched run.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+pub enum Divergence {
+    /// An ingress message has a different outcome (or no terminal outcome) in one of the runs.
+    Message {
+        message_id: String,
+        original: Option<ObservedMessage>,
+        patched: Option<ObservedMessage>,
+    },
+    /// The executions of a message on a tracked canister used different instructions
+    /// or cycles, or happened in one of the runs only.
+    Execution {
+        key: ExecutionKey,
+        original: Option<ObservedExecution>,
+        patched: Option<ObservedExecution>,
+    },
+}
+
+/// Compares the observations of the original and the patched run. Messages
+/// in `ignored_messages` (i.e., the injected upgrades) are not compared.
+pub fn compare(
+    original: &WhatIfObservations,
+    patched: &WhatIfObservations,
+    ignored_messages: &BTreeSet<MessageId>,
+) -> Vec<Divergence> {
+    let message_ids: BTreeSet<_> = original
+        .messages
+        .keys()
+        .chain(patched.messages.keys())
+        .filter(|message_id| !ignored_messages.contains(message_id))
+        .collect();
+    let message_divergences = message_ids.into_iter().filter_map(|message_id| {
+        let original = original.messages.get(message_id);
+        let patched = patched.messages.get(message_id);
+        let diverges = match (original, patched) {
+            (Some(original), Some(patched)) => original.outcome != patched.outcome,
+            _ => true,
+        };
+        diverges.then(|| Divergence::Message {
+            message_id: message_id.to_string(),
+            original: original.cloned(),
+            patched: patched.cloned(),
+        })
+    });
+
+    let ignored_keys: BTreeSet<_> = ignored_messages
+        .iter()
+        .map(|message_id| ExecutionKey::Ingress(message_id.to_string()))
+        .collect();
+    let keys: BTreeSet<_> = original
+        .executions
+        .keys()
+        .chain(patched.executions.keys())
+        .filter(|key| !ignored_keys.contains(key))
+        .collect();
+    let execution_divergences = keys.into_iter().filter_map(|key| {
+        let original = original.executions.get(key);
+        let patched = patched.executions.get(key);
+        (original != patched).then(|| Divergence::Execution {
+            key: key.clone(),
+            original: original.cloned(),
+            patched: patched.cloned(),
+        })
+    });
+
+    message_divergences.chain(execution_divergences).collect()
+}
+
+/// A canister upgrade to be injected into the first replayed batch.
+#[derive(Clone)]
+pub struct CanisterUpgrade {
+    pub controller: PrincipalId,
+    pub canister_id: CanisterId,
+    pub wasm_module: Vec<u8>,
+}
+
+/// Wraps `MessageRouting` to induct ingress messages upgrading canisters
+/// together with the first delivered batch.
+pub(crate) struct UpgradingMessageRouting {
+    inner: Arc<dyn MessageRouting>,
+    pending_upgrades: Mutex<Vec<CanisterUpgrade>>,
+    upgrade_messages: Mutex<BTreeSet<MessageId>>,
+}
+
+impl UpgradingMessageRouting {
+    pub(crate) fn new(inner: Arc<dyn MessageRouting>, upgrades: Vec<CanisterUpgrade>) -> Self {
+        Self {
+            inner,
+            pending_upgrades: Mutex::new(upgrades),
+            upgrade_messages: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Returns the IDs of the injected upgrade messages.
+    pub(crate) fn upgrade_messages(&self) -> BTreeSet<MessageId> {
+        self.upgrade_messages.lock().unwrap().clone()
+    }
+}
+
+impl MessageRouting for UpgradingMessageRouting {
+    fn deliver_batch(&self, mut batch: Batch) -> Result<(), MessageRoutingError> {
+        let mut pending_upgrades = self.pending_upgrades.lock().unwrap();
+        if pending_upgrades.is_empty() {
+            return self.inner.deliver_batch(batch);
+        }
+        let upgrades = pending_upgrades
+            .iter()
+            .map(|upgrade| {
+                upgrade_canister(
+                    &upgrade.controller,
+                    upgrade.canister_id,
+                    upgrade.wasm_module.clone(),
+                    batch.time + UPGRADE_INGRESS_EXPIRY,
+                )
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "Couldn't create upgrade of {}: {}",
+                        upgrade.canister_id, err
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        let message_ids = upgrades.iter().map(|ingress| ingress.id()).collect();
+        batch.messages.signed_ingress_msgs.splice(0..0, upgrades);
+        self.inner.deliver_batch(batch)?;
+        // Only consider the upgrades injected once the batch was accepted.
+        pending_upgrades.clear();
+        *self.upgrade_messages.lock().unwrap() = message_ids;
+        Ok(())
+    }
+
+    fn expected_batch_height(&self) -> Height {
+        self.inner.expected_batch_height()
+    }
+}
+
+/// Replays the backup described by `cmd` twice, as recorded and with the canisters
+/// upgraded to the given Wasm modules, and reports the divergences of both runs.
+/// Both runs operate on temporary copies of the state and the registry local store.
+pub fn what_if(
+    cfg: Config,
+    cmd: &WhatIfCmd,
+    subnet_id: SubnetId,
+    target_height: Option<u64>,
+) -> ReplayResult {
+    let mut wasm_modules = BTreeMap::new();
+    for canister_wasm in &cmd.canister_wasms {
+        let wasm_module = fs::read(&canister_wasm.wasm_path).unwrap_or_else(|err| {
+            panic!(
+                "Couldn't read Wasm module {:?}: {}",
+                canister_wasm.wasm_path, err
+            )
+        });
+        wasm_modules.insert(canister_wasm.canister_id, wasm_module);
+    }
+    let canister_ids: BTreeSet<_> = wasm_modules.keys().cloned().collect();
+
+    println!("Replaying the original run...");
+    let (_, (original, _)) = replay_copy(&cfg, cmd, subnet_id, target_height, &canister_ids, None)?;
+    println!("Replaying the patched run...");
+    let (state_params, patched) = replay_copy(
+        &cfg,
+        cmd,
+        subnet_id,
+        target_height,
+        &canister_ids,
+        Some(wasm_modules),
+    )?;
+    let (observations, upgrade_messages) = patched;
+    for message_id in &upgrade_messages {
+        match observations.messages.get(message_id) {
+            Some(ObservedMessage {
+                outcome: MessageOutcome::Reply(_),
+                ..
+            }) => {}
+            observed => panic!("Upgrade {} has failed: {:?}", message_id, observed),
+        }
+    }
+
+    let divergences = compare(&original, &observations, &upgrade_messages);
+    for divergence in &divergences {
+        println!("{:?}", divergence);
+    }
+    println!(
+        "Found {} divergences up to height {}",
+        divergences.len(),
+        state_params.height
+    );
+    if let Some(report_file) = &cmd.report_file {
+        let report =
+            serde_json::to_string_pretty(&divergences).expect("Couldn't serialize the divergences");
+        fs::write(report_file, report)
+            .unwrap_or_else(|err| panic!("Couldn't write report {:?}: {}", report_file, err));
+    }
+    Ok(state_params)
+}
+
+/// Replays the backup on temporary copies of the state and the registry local store,
+/// upgrading the canisters to the given Wasm modules first (if any).
+fn replay_copy(
+    cfg: &Config,
+    cmd: &WhatIfCmd,
+    subnet_id: SubnetId,
+    target_height: Option<u64>,
+    canister_ids: &BTreeSet<CanisterId>,
+    wasm_modules: Option<BTreeMap<CanisterId, Vec<u8>>>,
+) -> Result<(StateParams, (WhatIfObservations, BTreeSet<MessageId>)), ReplayError> {
+    let observations = Arc::new(Mutex::new(WhatIfObservations::default()));
+    let execution_recorder = ExecutionRecorder {
+        canister_ids: canister_ids.clone(),
+        observations: observations.clone(),
+    };
+    let mut player = Player::new_for_backup_copy(
+        cfg.clone(),
+        ReplicaVersion::try_from(cmd.replica_version.as_str())
+            .expect("Couldn't parse the replica version"),
+        &cmd.backup_spool_path,
+        &cmd.registry_local_store_path,
+        subnet_id,
+        cmd.start_height,
+        Some(Arc::new(execution_recorder)),
+    )
+    .with_replay_target_height(target_height)
+    .with_observer(Box::new(observations.clone()), cmd.observation_interval);
+    if let Some(wasm_modules) = wasm_modules {
+        let state = player.state_manager().get_latest_state().take();
+        let upgrades = wasm_modules
+            .into_iter()
+            .map(|(canister_id, wasm_module)| {
+                let canister = state
+                    .canister_state(&canister_id)
+                    .unwrap_or_else(|| panic!("Canister {} doesn't exist", canister_id));
+                let controller = upgrade_sender(&canister.system_state.controllers, cmd.sender)
+                    .unwrap_or_else(|err| panic!("Can't upgrade {}: {}", canister_id, err));
+                CanisterUpgrade {
+                    controller,
+                    canister_id,
+                    wasm_module,
+                }
+            })
+            .collect();
+        player = player.with_canister_upgrades(upgrades);
+    }
+
+    let state_params = player.restore(cmd.start_height + 1)?;
+    let observations = std::mem::take(&mut *observations.lock().unwrap());
+    Ok((state_params, (observations, player.upgrade_messages())))
+}
+
+/// Returns the controller sending the upgrade of a canister with the given
+/// controllers: the given sender, if any, or else the only controller.
+fn upgrade_sender(
+    controllers: &BTreeSet<PrincipalId>,
+    sender: Option<PrincipalId>,
+) -> Result<PrincipalId, String> {
+    match sender {
+        Some(sender) if controllers.contains(&sender) => Ok(sender),
+        Some(sender) => Err(format!("{} is not a controller", sender)),
+        None => match controllers.len() {
+            0 => Err("the canister has no controller".to_string()),
+            1 => Ok(*controllers.first().unwrap()),
+            _ => Err(format!(
+                "the canister has {} controllers, choose one with --sender",
+                controllers.len()
+            )),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_types::ids::{canister_test_id, message_test_id, user_test_id};
+    use ic_types::{
+        nominal_cycles::NominalCycles, time::UNIX_EPOCH, ExecutionRound, NumInstructions,
+    };
+
+    fn message(height: u64, outcome: MessageOutcome) -> ObservedMessage {
+        ObservedMessage {
+            height: Height::from(height),
+            receiver: canister_test_id(1).get(),
+            outcome,
+        }
+    }
+
+    #[test]
+    fn test_compare_reports_diverging_messages_only() {
+        let mut original = WhatIfObservations::default();
+        let mut patched = WhatIfObservations::default();
+        let (same, different, missing, upgrade) = (
+            message_test_id(1),
+            message_test_id(2),
+            message_test_id(3),
+            message_test_id(4),
+        );
+        for observations in [&mut original, &mut patched] {
+            observations
+                .messages
+                .insert(same.clone(), message(1, MessageOutcome::Reply("01".into())));
+        }
+        original.messages.insert(
+            different.clone(),
+            message(1, MessageOutcome::Reply("01".into())),
+        );
+        patched.messages.insert(
+            different.clone(),
+            message(1, MessageOutcome::Reject("trapped".into())),
+        );
+        original.messages.insert(
+            missing.clone(),
+            message(2, MessageOutcome::Reply("".into())),
+        );
+        patched.messages.insert(
+            upgrade.clone(),
+            message(1, MessageOutcome::Reply("".into())),
+        );
+
+        let divergences = compare(&original, &patched, &BTreeSet::from([upgrade]));
+        assert_eq!(
+            divergences,
+            vec![
+                Divergence::Message {
+                    message_id: different.to_string(),
+                    original: Some(message(1, MessageOutcome::Reply("01".into()))),
+                    patched: Some(message(1, MessageOutcome::Reject("trapped".into()))),
+                },
+                Divergence::Message {
+                    message_id: missing.to_string(),
+                    original: Some(message(2, MessageOutcome::Reply("".into()))),
+                    patched: None,
+                },
+            ]
+        );
+    }
+
+    fn executed_message(
+        round: u64,
+        ingress_status: Option<MessageId>,
+        instructions: u64,
+    ) -> ExecutedMessage {
+        ExecutedMessage {
+            round: ExecutionRound::from(round),
+            canister_id: canister_test_id(1),
+            description: "Request, method name test,".to_string(),
+            ingress_status: ingress_status.map(|message_id| {
+                (
+                    message_id,
+                    IngressStatus::Known {
+                        receiver: canister_test_id(1).get(),
+                        user_id: user_test_id(1),
+                        time: UNIX_EPOCH,
+                        state: IngressState::Processing,
+                    },
+                )
+            }),
+            instructions_used: NumInstructions::from(instructions),
+            consumed_cycles: NominalCycles::from(10 * instructions as u128),
+        }
+    }
+
+    fn execution(height: u64, executions: u64, instructions: u64) -> ObservedExecution {
+        ObservedExecution {
+            height: Height::from(height),
+            canister_id: canister_test_id(1),
+            description: "Request, method name test,".to_string(),
+            executions,
+            instructions,
+            consumed_cycles: 10 * instructions as u128,
+        }
+    }
+
+    #[test]
+    fn test_record_execution_groups_executions_per_message() {
+        let ingress = message_test_id(1);
+        let mut observations = WhatIfObservations::default();
+        observations.record_execution(executed_message(10, Some(ingress.clone()), 100));
+        observations.record_execution(executed_message(10, None, 5));
+        observations.record_execution(executed_message(10, None, 7));
+        observations.record_execution(executed_message(11, Some(ingress.clone()), 50));
+        observations.record_execution(executed_message(11, None, 3));
+
+        let other = |height: u64, index| ExecutionKey::Other {
+            height: Height::from(height),
+            canister_id: canister_test_id(1),
+            index,
+        };
+        assert_eq!(
+            observations.executions,
+            BTreeMap::from([
+                (
+                    ExecutionKey::Ingress(ingress.to_string()),
+                    execution(10, 2, 150)
+                ),
+                (other(10, 0), execution(10, 1, 5)),
+                (other(10, 1), execution(10, 1, 7)),
+                (other(11, 0), execution(11, 1, 3)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_compare_reports_diverging_executions() {
+        let (same, different, upgrade) =
+            (message_test_id(1), message_test_id(2), message_test_id(3));
+        let mut original = WhatIfObservations::default();
+        let mut patched = WhatIfObservations::default();
+        for observations in [&mut original, &mut patched] {
+            observations.record_execution(executed_message(10, Some(same.clone()), 100));
+            observations.record_execution(executed_message(10, None, 5));
+        }
+        original.record_execution(executed_message(10, Some(different.clone()), 100));
+        patched.record_execution(executed_message(10, Some(different.clone()), 120));
+        patched.record_execution(executed_message(10, None, 9));
+        patched.record_execution(executed_message(10, Some(upgrade.clone()), 1_000));
+
+        assert_eq!(
+            compare(&original, &patched, &BTreeSet::from([upgrade])),
+            vec![
+                Divergence::Execution {
+                    key: ExecutionKey::Ingress(different.to_string()),
+                    original: Some(execution(10, 1, 100)),
+                    patched: Some(execution(10, 1, 120)),
+                },
+                Divergence::Execution {
+                    key: ExecutionKey::Other {
+                        height: Height::from(10),
+                        canister_id: canister_test_id(1),
+                        index: 1,
+                    },
+                    original: None,
+                    patched: Some(execution(10, 1, 9)),
+                },
+            ]
+        );
+    }
+    #[test]
+    fn test_upgrade_sender_requires_unambiguous_controller() {
+        let (alice, bob) = (user_test_id(1).get(), user_test_id(2).get());
+        assert_eq!(upgrade_sender(&BTreeSet::from([alice]), None), Ok(alice));
+        assert!(upgrade_sender(&BTreeSet::new(), None).is_err());
+        assert!(upgrade_sender(&BTreeSet::from([alice, bob]), None).is_err());
+        assert_eq!(
+            upgrade_sender(&BTreeSet::from([alice, bob]), Some(bob)),
+            Ok(bob)
+        );
+        assert!(upgrade_sender(&BTreeSet::from([alice]), Some(bob)).is_err());
+    }
+}
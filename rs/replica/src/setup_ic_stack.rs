@@ -3,7 +3,8 @@ use ic_artifact_pool::{
     consensus_pool::ConsensusPoolImpl, ensure_persistent_pool_replica_version_compatibility,
 };
 use ic_btc_adapter_client::{setup_bitcoin_adapter_clients, BitcoinAdapterClients};
-use ic_btc_consensus::BitcoinPayloadBuilder;
+use ic_btc_consensus::{AdapterClients, BitcoinPayloadBuilder};
+use ic_btc_replica_types::Chain;
 use ic_config::{artifact_pool::ArtifactPoolConfig, subnet_config::SubnetConfig, Config};
 use ic_consensus::certification::VerifierImpl;
 use ic_crypto::CryptoComponent;
@@ -278,22 +279,42 @@ pub fn construct_ic_stack(
     let BitcoinAdapterClients {
         btc_testnet_client,
         btc_mainnet_client,
+        ltc_testnet_client,
+        ltc_mainnet_client,
+        doge_testnet_client,
+        doge_mainnet_client,
     } = setup_bitcoin_adapter_clients(
         log.clone(),
         metrics_registry,
         rt_handle_main.clone(),
         config.adapters_config.clone(),
     );
-    let self_validating_payload_builder = Arc::new(BitcoinPayloadBuilder::new(
-        state_manager.clone(),
-        metrics_registry,
-        btc_mainnet_client,
-        btc_testnet_client,
-        subnet_id,
-        registry.clone(),
-        config.bitcoin_payload_builder_config,
-        log.clone(),
-    ));
+    let self_validating_payload_builder = Arc::new(
+        BitcoinPayloadBuilder::new(
+            state_manager.clone(),
+            metrics_registry,
+            btc_mainnet_client,
+            btc_testnet_client,
+            subnet_id,
+            registry.clone(),
+            config.bitcoin_payload_builder_config,
+            log.clone(),
+        )
+        .with_adapter_clients(
+            Chain::Litecoin,
+            AdapterClients {
+                mainnet: ltc_mainnet_client,
+                testnet: ltc_testnet_client,
+            },
+        )
+        .with_adapter_clients(
+            Chain::Dogecoin,
+            AdapterClients {
+                mainnet: doge_mainnet_client,
+                testnet: doge_testnet_client,
+            },
+        ),
+    );
     // ---------- HTTPS OUTCALLS DEPS FOLLOW ----------
     let canister_http_adapter_client = setup_canister_http_client(
         rt_handle_main.clone(),
@@ -212,6 +212,7 @@ fn bitcoin_get_successors() {
                     network: ic_btc_interface::Network::Regtest,
                     anchor: vec![],
                     processed_block_hashes: vec![],
+                    chain: None,
                 }),
             );
 
@@ -250,6 +251,7 @@ fn bitcoin_get_successors_pagination() {
                     network: ic_btc_interface::Network::Regtest,
                     anchor: vec![],
                     processed_block_hashes: vec![],
+                    chain: None,
                 }),
             );
 
@@ -296,6 +298,7 @@ fn bitcoin_get_successors_pagination_invalid_adapter_request() {
                     network: ic_btc_interface::Network::Regtest,
                     anchor: vec![],
                     processed_block_hashes: vec![],
+                    chain: None,
                 }),
             );
 
@@ -330,6 +333,7 @@ fn bitcoin_get_successors_reject() {
                     network: ic_btc_interface::Network::Regtest,
                     anchor: vec![],
                     processed_block_hashes: vec![],
+                    chain: None,
                 }),
             );
 
@@ -361,6 +365,7 @@ fn bitcoin_send_transaction_internal_valid_request() {
                 ic00::BitcoinSendTransactionInternalArgs {
                     network: ic_btc_interface::Network::Regtest,
                     transaction: vec![1, 2, 3],
+                    chain: None,
                 },
             );
 
@@ -390,6 +395,7 @@ fn bitcoin_send_transaction_internal_reject() {
                 ic00::BitcoinSendTransactionInternalArgs {
                     network: ic_btc_interface::Network::Regtest,
                     transaction: vec![1, 2, 3],
+                    chain: None,
                 },
             );
 
@@ -443,6 +449,7 @@ fn bitcoin_send_transaction_internal_no_permissions() {
                 ic00::BitcoinSendTransactionInternalArgs {
                     network: ic_btc_interface::Network::Regtest,
                     transaction: vec![1, 2, 3],
+                    chain: None,
                 },
             );
 
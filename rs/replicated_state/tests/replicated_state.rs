@@ -586,6 +586,7 @@ fn insert_bitcoin_response() {
                 network: Network::Regtest,
                 anchor: vec![],
                 processed_block_hashes: vec![],
+                chain: None,
             },
             time: UNIX_EPOCH,
         }),
@@ -620,6 +621,7 @@ fn insert_bitcoin_get_successor_reject_response() {
                 network: Network::Regtest,
                 anchor: vec![],
                 processed_block_hashes: vec![],
+                chain: None,
             },
             time: UNIX_EPOCH,
         }),
@@ -653,6 +655,7 @@ fn insert_bitcoin_send_transaction_reject_response() {
             payload: SendTransactionRequest {
                 network: Network::Regtest,
                 transaction: vec![],
+                chain: None,
             },
             time: UNIX_EPOCH,
         }),
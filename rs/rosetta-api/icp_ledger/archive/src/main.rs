@@ -1,6 +1,6 @@
 use candid::candid_method;
 use dfn_candid::candid_one;
-use dfn_core::api::{caller, print, stable_memory_size_in_pages};
+use dfn_core::api::{caller, print, stable_memory_size_in_pages, trap_with};
 use dfn_core::{over_init, stable, BytesS};
 use dfn_protobuf::protobuf;
 use ic_ledger_canister_core::range_utils;
@@ -11,6 +11,10 @@ use icp_ledger::{
     Block, BlockRange, BlockRes, CandidBlock, GetBlocksArgs, GetBlocksError, GetBlocksResult,
     GetEncodedBlocksResult, IterBlocksArgs,
 };
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc3::blocks::{
+    BlockWithId, GetBlocksRequest, GetBlocksResult as Icrc3GetBlocksResult,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::RwLock;
 
@@ -317,6 +321,52 @@ fn get_encoded_blocks_blocks_() {
     dfn_core::over(candid_one, get_encoded_blocks);
 }
 
+#[candid_method(query, rename = "icrc3_get_blocks")]
+fn icrc3_get_blocks(reqs: Vec<GetBlocksRequest>) -> Icrc3GetBlocksResult {
+    const MAX_BLOCKS_PER_RESPONSE: u64 = 100;
+
+    let archive_state = ARCHIVE_STATE.read().unwrap();
+    let block_range = range_utils::make_range(
+        archive_state.block_height_offset,
+        archive_state.blocks.len(),
+    );
+    let mut blocks = vec![];
+    for req in reqs {
+        let (start, length) = req
+            .as_start_and_length()
+            .unwrap_or_else(|msg| trap_with(&msg));
+        let max_length = MAX_BLOCKS_PER_RESPONSE.saturating_sub(blocks.len() as u64);
+        if max_length == 0 {
+            break;
+        }
+        let requested_range = range_utils::make_range(start, length.min(max_length) as usize);
+        let effective_range = match range_utils::intersect(&block_range, &requested_range) {
+            Ok(range) => range,
+            Err(range_utils::NoIntersection) => continue,
+        };
+        for id in effective_range {
+            let encoded_block = archive_state.blocks[(id - block_range.start) as usize].clone();
+            let block = Block::decode(encoded_block).expect("failed to decode a block");
+            blocks.push(BlockWithId {
+                id: candid::Nat::from(id),
+                block: ICRC3Value::from(block),
+            });
+        }
+    }
+    Icrc3GetBlocksResult {
+        // We return the local log length because the archive
+        // knows only about its local blocks.
+        log_length: candid::Nat::from(archive_state.blocks.len()),
+        blocks,
+        archived_blocks: vec![],
+    }
+}
+
+#[export_name = "canister_query icrc3_get_blocks"]
+fn icrc3_get_blocks_() {
+    dfn_core::over(candid_one, icrc3_get_blocks);
+}
+
 #[export_name = "canister_query __get_candid_interface_tmp_hack"]
 fn get_canidid_interface() {
     dfn_core::over(candid_one, |()| -> &'static str {
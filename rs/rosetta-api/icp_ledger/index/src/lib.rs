@@ -1,7 +1,8 @@
-use candid::{CandidType, Deserialize, Principal};
+use candid::{CandidType, Deserialize, Nat, Principal};
 use ic_ledger_core::block::EncodedBlock;
 use ic_ledger_core::timestamp::TimeStamp;
 use icp_ledger::{AccountIdentifier, Block, BlockIndex, Memo, Operation};
+use icrc_ledger_types::icrc1::account::Account;
 use serde_bytes::ByteBuf;
 
 pub mod logs;
@@ -19,6 +20,18 @@ pub struct GetBlocksResponse {
     // The blocks in the requested range.
     pub blocks: Vec<EncodedBlock>,
 }
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetAccountTransactionsArgs {
+    pub account: Account,
+    // The txid of the last transaction seen by the client.
+    // If None then the results will start from the most recent
+    // txid. If set then the results will start from the next
+    // most recent txid after start (start won't be included).
+    pub start: Option<Nat>,
+    // Maximum number of transactions to fetch.
+    pub max_results: Nat,
+}
+
 #[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct GetAccountIdentifierTransactionsArgs {
     pub account_identifier: AccountIdentifier,
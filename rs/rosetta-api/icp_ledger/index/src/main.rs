@@ -7,10 +7,10 @@ use ic_cdk_timers::TimerId;
 use ic_icp_index::logs::{P0, P1};
 use ic_icp_index::{
     GetAccountIdentifierTransactionsArgs, GetAccountIdentifierTransactionsResponse,
-    GetAccountIdentifierTransactionsResult, GetAccountTransactionsResult, InitArg, Log, LogEntry,
-    Priority, SettledTransaction, SettledTransactionWithId, Status,
+    GetAccountIdentifierTransactionsResult, GetAccountTransactionsArgs,
+    GetAccountTransactionsResult, InitArg, Log, LogEntry, Priority, SettledTransaction,
+    SettledTransactionWithId, Status,
 };
-use ic_icrc1_index_ng::GetAccountTransactionsArgs;
 use ic_ledger_canister_core::runtime::total_memory_size_bytes;
 use ic_ledger_core::block::{BlockType, EncodedBlock};
 use ic_stable_structures::memory_manager::{MemoryId, VirtualMemory};
@@ -301,6 +301,17 @@ impl Ledger {
                 CTE::AllowanceChanged { .. } => todo!(),
                 CTE::SelfApproval { .. } => todo!(),
                 CTE::BadBurn { .. } => todo!(),
+                // The ICP ledger never records spending policies, so these errors are not
+                // expected. Reject the transaction rather than trapping if they occur.
+                CTE::ApprovalDoesNotExist => {
+                    PaymentError::Reject("the approval does not exist".to_string())
+                }
+                CTE::InvalidSpendingPolicy => {
+                    PaymentError::Reject("spending policies are not supported".to_string())
+                }
+                CTE::RecipientNotAllowed => PaymentError::Reject(
+                    "the recipient is not allowed by the spending policy".to_string(),
+                ),
             }
         })
     }
@@ -32,15 +32,21 @@ use icp_ledger::{
     GetBlocksArgs, InitArgs, IterBlocksArgs, LedgerCanisterPayload, Memo, Name, Operation,
     PaymentError, QueryBlocksResponse, QueryEncodedBlocksResponse, SendArgs, Subaccount, Symbol,
     TipOfChainRes, TotalSupplyArgs, Transaction, TransferArgs, TransferError, TransferFee,
-    TransferFeeArgs, MEMO_SIZE_BYTES,
+    TransferFeeArgs, ICP_APPROVE_BLOCK_TYPE, ICP_BURN_BLOCK_TYPE, ICP_MINT_BLOCK_TYPE,
+    ICP_TRANSFER_BLOCK_TYPE, MEMO_SIZE_BYTES,
 };
 use icrc_ledger_types::icrc1::transfer::TransferError as Icrc1TransferError;
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
+    icrc::generic_value::ICRC3Value,
     icrc21::lib::build_icrc21_consent_info_for_icrc1_and_icrc2_endpoints,
-    icrc3::archive::QueryArchiveFn,
+    icrc3::archive::{GetArchivesArgs, GetArchivesResult, ICRC3ArchiveInfo, QueryArchiveFn},
+    icrc3::blocks::{
+        ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate,
+        SupportedBlockType,
+    },
 };
 use icrc_ledger_types::{
     icrc1::account::Account, icrc2::transfer_from::TransferFromArgs,
@@ -57,7 +63,7 @@ use on_wire::IntoWire;
 use std::cell::RefCell;
 use std::io::{Read, Write};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::{Arc, RwLock},
     time::Duration,
 };
@@ -1240,6 +1246,140 @@ fn query_blocks_() {
     over(candid_one, query_blocks)
 }
 
+#[candid_method(query, rename = "icrc3_get_blocks")]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    const MAX_BLOCKS_PER_RESPONSE: u64 = 100;
+
+    let ledger = LEDGER.read().unwrap();
+    let mut blocks = vec![];
+    let mut archived_blocks_by_callback = BTreeMap::new();
+    for arg in args {
+        let (start, length) = arg
+            .as_start_and_length()
+            .unwrap_or_else(|msg| trap_with(&msg));
+        let max_length = MAX_BLOCKS_PER_RESPONSE.saturating_sub(blocks.len() as u64);
+        if max_length == 0 {
+            break;
+        }
+        let locations = block_locations(&*ledger, start, max_length.min(length) as usize);
+        for (id, enc_block) in locations.local_blocks.clone().zip(
+            ledger
+                .blockchain
+                .block_slice(locations.local_blocks.clone())
+                .iter(),
+        ) {
+            let block =
+                Block::decode(enc_block.clone()).expect("bug: failed to decode encoded block");
+            blocks.push(BlockWithId {
+                id: Nat::from(id),
+                block: ICRC3Value::from(block),
+            });
+        }
+        for (canister_id, slice) in locations.archived_blocks {
+            let callback = QueryArchiveFn::<Vec<GetBlocksRequest>, GetBlocksResult>::new(
+                Principal::from(canister_id),
+                "icrc3_get_blocks",
+            );
+            archived_blocks_by_callback
+                .entry(callback)
+                .or_insert(vec![])
+                .push(GetBlocksRequest {
+                    start: Nat::from(slice.start),
+                    length: Nat::from(range_utils::range_len(&slice)),
+                });
+        }
+        if blocks.len() as u64 >= MAX_BLOCKS_PER_RESPONSE {
+            break;
+        }
+    }
+    let archived_blocks = archived_blocks_by_callback
+        .into_iter()
+        .map(|(callback, args)| ArchivedBlocks { args, callback })
+        .collect();
+    GetBlocksResult {
+        log_length: Nat::from(ledger.blockchain.chain_length()),
+        blocks,
+        archived_blocks,
+    }
+}
+
+#[export_name = "canister_query icrc3_get_blocks"]
+fn icrc3_get_blocks_() {
+    over(candid_one, icrc3_get_blocks)
+}
+
+#[candid_method(query, rename = "icrc3_get_archives")]
+fn icrc3_get_archives(args: GetArchivesArgs) -> GetArchivesResult {
+    let ledger = LEDGER.read().unwrap();
+    let archive_guard = ledger.blockchain.archive.read().unwrap();
+    archive_guard
+        .iter()
+        .flat_map(|archive| {
+            archive
+                .index()
+                .into_iter()
+                .filter_map(|((start, end), canister_id)| {
+                    let canister_id = Principal::from(canister_id);
+                    if let Some(from) = args.from {
+                        if canister_id <= from {
+                            return None;
+                        }
+                    }
+                    Some(ICRC3ArchiveInfo {
+                        canister_id,
+                        start: Nat::from(start),
+                        end: Nat::from(end),
+                    })
+                })
+        })
+        .collect()
+}
+
+#[export_name = "canister_query icrc3_get_archives"]
+fn icrc3_get_archives_() {
+    over(candid_one, icrc3_get_archives)
+}
+
+/// The blocks returned by `icrc3_get_blocks` have ICP-specific block types and
+/// are chained by `icp_phash`, the hash of the protobuf-encoded parent block,
+/// rather than by the ICRC-3 `phash` (see `From<Block> for ICRC3Value`). The
+/// ledger certifies that protobuf hash of the last block, which existing
+/// clients verify `query_blocks` and `tip_of_chain` against, and there is no
+/// ICRC-3 hash of the last block to certify. So this endpoint always returns
+/// `None` and the ledger does not advertise ICRC-3 in
+/// `icrc1_supported_standards`.
+#[candid_method(query, rename = "icrc3_supported_block_types")]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    [
+        ICP_BURN_BLOCK_TYPE,
+        ICP_MINT_BLOCK_TYPE,
+        ICP_TRANSFER_BLOCK_TYPE,
+        ICP_APPROVE_BLOCK_TYPE,
+    ]
+    .into_iter()
+    .map(|block_type| SupportedBlockType {
+        block_type: block_type.to_string(),
+        url: "https://github.com/dfinity/ic/blob/master/rs/rosetta-api/icp_ledger/ledger.did"
+            .to_string(),
+    })
+    .collect()
+}
+
+#[export_name = "canister_query icrc3_supported_block_types"]
+fn icrc3_supported_block_types_() {
+    over(candid_one, |()| icrc3_supported_block_types())
+}
+
+#[candid_method(query, rename = "icrc3_get_tip_certificate")]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    None
+}
+
+#[export_name = "canister_query icrc3_get_tip_certificate"]
+fn icrc3_get_tip_certificate_() {
+    over(candid_one, |()| icrc3_get_tip_certificate())
+}
+
 #[export_name = "canister_query icrc1_minting_account"]
 fn icrc1_minting_account_candid() {
     over(candid_one, |()| icrc1_minting_account())
@@ -339,6 +339,94 @@ fn check_memo() {
     }
 }
 
+#[test]
+fn check_icrc3_block_types() {
+    use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+    use icrc_ledger_types::icrc3::blocks::{GetBlocksRequest, SupportedBlockType};
+
+    let env = StateMachine::new();
+    let payload = LedgerCanisterInitPayload::builder()
+        .minting_account(MINTER.into())
+        .token_symbol_and_name("ICP", "Internet Computer")
+        .build()
+        .unwrap();
+    let ledger_id = env
+        .install_canister(
+            ledger_wasm(),
+            CandidOne(payload).into_bytes().unwrap(),
+            None,
+        )
+        .expect("Unable to install the Ledger canister");
+
+    let user = Account {
+        owner: PrincipalId::new_user_test_id(10).0,
+        subaccount: None,
+    };
+    transfer(&env, ledger_id, MINTER, user, 100_000_000).expect("mint failed");
+    transfer(&env, ledger_id, user, MINTER, 10_000_000).expect("burn failed");
+    transfer(
+        &env,
+        ledger_id,
+        user,
+        Account {
+            owner: PrincipalId::new_user_test_id(11).0,
+            subaccount: None,
+        },
+        10_000_000,
+    )
+    .expect("transfer failed");
+
+    let supported_block_types = Decode!(
+        &env.query(ledger_id, "icrc3_supported_block_types", Encode!().unwrap())
+            .expect("failed to query icrc3_supported_block_types")
+            .bytes(),
+        Vec<SupportedBlockType>
+    )
+    .unwrap()
+    .into_iter()
+    .map(|block_type| block_type.block_type)
+    .collect::<Vec<_>>();
+    assert_eq!(
+        supported_block_types,
+        vec!["icp_burn", "icp_mint", "icp_xfer", "icp_approve"]
+    );
+
+    let blocks = Decode!(
+        &env.query(
+            ledger_id,
+            "icrc3_get_blocks",
+            Encode!(&vec![GetBlocksRequest {
+                start: Nat::from(0_u64),
+                length: Nat::from(10_u64),
+            }])
+            .unwrap()
+        )
+        .expect("failed to query icrc3_get_blocks")
+        .bytes(),
+        icrc_ledger_types::icrc3::blocks::GetBlocksResult
+    )
+    .unwrap()
+    .blocks;
+    let btypes = blocks
+        .into_iter()
+        .map(|block| {
+            let ICRC3Value::Map(map) = block.block else {
+                panic!("expected the block to be a map");
+            };
+            let Some(ICRC3Value::Map(tx)) = map.get("tx") else {
+                panic!("expected the block to have a transaction");
+            };
+            assert_eq!(tx.get("op"), None);
+            assert_eq!(map.get("phash"), None);
+            match map.get("btype") {
+                Some(ICRC3Value::Text(btype)) => btype.clone(),
+                btype => panic!("unexpected btype: {:?}", btype),
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(btypes, vec!["icp_mint", "icp_burn", "icp_xfer"]);
+}
+
 fn assert_candid_block_equals_icp_ledger_block(
     candid_blocks: Vec<CandidBlock>,
     icp_ledger_blocks: Vec<Block>,
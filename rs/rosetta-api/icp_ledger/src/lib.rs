@@ -1,4 +1,4 @@
-use candid::CandidType;
+use candid::{CandidType, Nat};
 use dfn_protobuf::ProtoBuf;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_crypto_sha2::Sha256;
@@ -12,6 +12,7 @@ use ic_ledger_core::{
 };
 use ic_ledger_hash_of::HashOf;
 use ic_ledger_hash_of::HASH_LENGTH;
+use icrc_ledger_types::icrc::generic_value::{ICRC3Map, ICRC3Value};
 use icrc_ledger_types::icrc1::account::Account;
 use on_wire::{FromWire, IntoWire};
 use serde::{Deserialize, Serialize};
@@ -1038,6 +1039,136 @@ impl TryFrom<CandidBlock> for Block {
     }
 }
 
+/// The ICRC-3 block types of ICP blocks, in the order of `Operation`.
+///
+/// ICP blocks are not ICRC-1/ICRC-2 blocks: they have no standard accounts and
+/// no ICRC-3 `phash`. They therefore use their own block types, which clients
+/// must not interpret as `1burn`, `1mint`, `1xfer`, `2xfer` or `2approve`.
+pub const ICP_BURN_BLOCK_TYPE: &str = "icp_burn";
+pub const ICP_MINT_BLOCK_TYPE: &str = "icp_mint";
+pub const ICP_TRANSFER_BLOCK_TYPE: &str = "icp_xfer";
+pub const ICP_APPROVE_BLOCK_TYPE: &str = "icp_approve";
+
+/// Maps an ICP block to a generic ICRC-3 `Value`.
+///
+/// The block type is stored under the top-level `btype` key (see
+/// `ICP_TRANSFER_BLOCK_TYPE` and friends) and the transaction has no `op`
+/// field, so the block is not mistaken for an ICRC-1 block. ICP blocks differ
+/// from ICRC-3 blocks in ways that cannot be mapped, so the fields that would
+/// not have their ICRC-3 meaning are stored under extra keys rather than under
+/// the standard ones:
+/// * `from_account_id`, `to_account_id` and `spender_account_id` hold the
+///   32-byte account identifiers of the block. ICP blocks only record account
+///   identifiers, which are hashes of the owner and subaccount, so the standard
+///   `from`, `to` and `spender` accounts are omitted.
+/// * `icp_phash` holds the hash of the protobuf-encoded parent block. The
+///   standard `phash` is the representation-independent hash of the parent
+///   `Value`, which depends on the whole history including archived blocks, so
+///   it is omitted.
+/// * `icp_memo` holds the legacy `u64` memo. The standard `memo` blob holds
+///   the ICRC-1 memo if the block has one and the big-endian bytes of the
+///   legacy memo otherwise.
+///
+/// Amounts are in e8s and timestamps in nanoseconds since the UNIX epoch.
+impl From<Block> for ICRC3Value {
+    fn from(block: Block) -> Self {
+        fn account_id(account: AccountIdentifier) -> ICRC3Value {
+            ICRC3Value::Blob(ByteBuf::from(account.to_address().to_vec()))
+        }
+        fn tokens(tokens: Tokens) -> ICRC3Value {
+            ICRC3Value::Nat(Nat::from(tokens.get_e8s()))
+        }
+        fn timestamp(timestamp: TimeStamp) -> ICRC3Value {
+            ICRC3Value::Nat(Nat::from(timestamp.as_nanos_since_unix_epoch()))
+        }
+
+        let Transaction {
+            operation,
+            memo,
+            created_at_time,
+            icrc1_memo,
+        } = block.transaction;
+
+        let mut tx = ICRC3Map::new();
+        let btype = match operation {
+            Operation::Burn {
+                from,
+                amount,
+                spender,
+            } => {
+                tx.insert("from_account_id".to_string(), account_id(from));
+                tx.insert("amt".to_string(), tokens(amount));
+                if let Some(spender) = spender {
+                    tx.insert("spender_account_id".to_string(), account_id(spender));
+                }
+                ICP_BURN_BLOCK_TYPE
+            }
+            Operation::Mint { to, amount } => {
+                tx.insert("to_account_id".to_string(), account_id(to));
+                tx.insert("amt".to_string(), tokens(amount));
+                ICP_MINT_BLOCK_TYPE
+            }
+            Operation::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+                spender,
+            } => {
+                tx.insert("from_account_id".to_string(), account_id(from));
+                tx.insert("to_account_id".to_string(), account_id(to));
+                tx.insert("amt".to_string(), tokens(amount));
+                tx.insert("fee".to_string(), tokens(fee));
+                if let Some(spender) = spender {
+                    tx.insert("spender_account_id".to_string(), account_id(spender));
+                }
+                ICP_TRANSFER_BLOCK_TYPE
+            }
+            Operation::Approve {
+                from,
+                spender,
+                allowance,
+                expected_allowance,
+                expires_at,
+                fee,
+            } => {
+                tx.insert("from_account_id".to_string(), account_id(from));
+                tx.insert("spender_account_id".to_string(), account_id(spender));
+                tx.insert("amt".to_string(), tokens(allowance));
+                tx.insert("fee".to_string(), tokens(fee));
+                if let Some(expected_allowance) = expected_allowance {
+                    tx.insert("expected_allowance".to_string(), tokens(expected_allowance));
+                }
+                if let Some(expires_at) = expires_at {
+                    tx.insert("expires_at".to_string(), timestamp(expires_at));
+                }
+                ICP_APPROVE_BLOCK_TYPE
+            }
+        };
+        let memo_bytes = match icrc1_memo {
+            Some(icrc1_memo) => icrc1_memo,
+            None => ByteBuf::from(memo.0.to_be_bytes().to_vec()),
+        };
+        tx.insert("memo".to_string(), ICRC3Value::Blob(memo_bytes));
+        tx.insert("icp_memo".to_string(), ICRC3Value::Nat(Nat::from(memo.0)));
+        if let Some(created_at_time) = created_at_time {
+            tx.insert("ts".to_string(), timestamp(created_at_time));
+        }
+
+        let mut map = ICRC3Map::new();
+        map.insert("btype".to_string(), ICRC3Value::Text(btype.to_string()));
+        if let Some(parent_hash) = block.parent_hash {
+            map.insert(
+                "icp_phash".to_string(),
+                ICRC3Value::Blob(ByteBuf::from(parent_hash.as_slice().to_vec())),
+            );
+        }
+        map.insert("ts".to_string(), timestamp(block.timestamp));
+        map.insert("tx".to_string(), ICRC3Value::Map(tx));
+        ICRC3Value::Map(map)
+    }
+}
+
 /// Argument taken by the transfer fee endpoint
 ///
 /// The reason it is a struct is so that it can be extended -- e.g., to be able
@@ -1400,4 +1531,52 @@ mod test {
             prop_assert_eq!(block, decoded)
         })
     }
+
+    #[test]
+    fn test_block_to_icrc3_value() {
+        proptest!(|(block in arb_block())| {
+            let value = ICRC3Value::from(block.clone());
+            let ICRC3Value::Map(map) = value else {
+                panic!("expected the block to be mapped to an ICRC3Value::Map");
+            };
+            prop_assert_eq!(map.get("phash"), None);
+            prop_assert_eq!(
+                map.get("icp_phash"),
+                block
+                    .parent_hash
+                    .map(|h| ICRC3Value::Blob(ByteBuf::from(h.as_slice().to_vec())))
+                    .as_ref()
+            );
+            prop_assert_eq!(
+                map.get("ts"),
+                Some(&ICRC3Value::Nat(Nat::from(
+                    block.timestamp.as_nanos_since_unix_epoch()
+                )))
+            );
+            let Some(ICRC3Value::Map(tx)) = map.get("tx") else {
+                panic!("expected the transaction to be mapped to an ICRC3Value::Map");
+            };
+            let expected_btype = match block.transaction.operation {
+                Operation::Burn { .. } => ICP_BURN_BLOCK_TYPE,
+                Operation::Mint { .. } => ICP_MINT_BLOCK_TYPE,
+                Operation::Transfer { .. } => ICP_TRANSFER_BLOCK_TYPE,
+                Operation::Approve { .. } => ICP_APPROVE_BLOCK_TYPE,
+            };
+            prop_assert_eq!(map.get("btype"), Some(&ICRC3Value::Text(expected_btype.to_string())));
+            prop_assert_eq!(tx.get("op"), None);
+            for key in ["from", "to", "spender"] {
+                prop_assert_eq!(tx.get(key), None);
+            }
+            let expected_memo = block
+                .transaction
+                .icrc1_memo
+                .clone()
+                .unwrap_or_else(|| ByteBuf::from(block.transaction.memo.0.to_be_bytes().to_vec()));
+            prop_assert_eq!(tx.get("memo"), Some(&ICRC3Value::Blob(expected_memo)));
+            prop_assert_eq!(
+                tx.get("icp_memo"),
+                Some(&ICRC3Value::Nat(Nat::from(block.transaction.memo.0)))
+            );
+        })
+    }
 }
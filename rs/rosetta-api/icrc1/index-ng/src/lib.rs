@@ -17,6 +17,9 @@ pub enum IndexArg {
 pub struct InitArg {
     pub ledger_id: Principal,
     pub retrieve_blocks_from_ledger_interval_seconds: Option<u64>,
+    // If true then the index initializes the balances from the snapshot
+    // of the ledger state, if any, instead of syncing from the first block.
+    pub bootstrap_from_ledger_snapshot: Option<bool>,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -44,6 +47,39 @@ pub struct GetAccountTransactionsArgs {
     pub start: Option<BlockIndex>,
     // Maximum number of transactions to fetch.
     pub max_results: Nat,
+    // If set then only the transactions matching the filter are returned.
+    pub filter: Option<TransactionFilter>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum TransactionKind {
+    Mint,
+    Burn,
+    Transfer,
+    Approve,
+    SetSpendingPolicy,
+    RevokeAllApprovals,
+    // An entry of an icrc4_transfer_batch call.
+    BatchTransfer,
+}
+
+/// Restricts the transactions returned by `get_account_transactions`.
+/// Every field that is set must match for a transaction to be returned.
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct TransactionFilter {
+    // Only transactions with a timestamp greater than or equal to this one.
+    pub start_timestamp: Option<u64>,
+    // Only transactions with a timestamp strictly lower than this one.
+    pub end_timestamp: Option<u64>,
+    // Only transactions of one of these kinds.
+    pub kinds: Option<Vec<TransactionKind>>,
+    // Only transactions that involve this account besides the requested one,
+    // i.e. as sender, receiver or spender.
+    pub counterparty: Option<Account>,
+    // Only transactions with an amount greater than or equal to this one.
+    pub min_amount: Option<Nat>,
+    // Only transactions with an amount lower than or equal to this one.
+    pub max_amount: Option<Nat>,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
@@ -58,6 +94,9 @@ pub struct GetAccountTransactionsResponse {
     pub transactions: Vec<TransactionWithId>,
     // The txid of the oldest transaction the account has
     pub oldest_tx_id: Option<BlockIndex>,
+    // Set only if a filter was given and older transactions of the account
+    // may still match it. Pass it as `start` to continue the search.
+    pub last_scanned_tx_id: Option<BlockIndex>,
 }
 
 #[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
@@ -6,13 +6,19 @@ use ic_cdk::trap;
 use ic_cdk_macros::{init, post_upgrade, query};
 use ic_cdk_timers::TimerId;
 use ic_crypto_sha2::Sha256;
+use ic_crypto_tree_hash::{LookupStatus, MixedHashTree};
 use ic_icrc1::blocks::{encoded_block_to_generic_block, generic_block_to_encoded_block};
 use ic_icrc1::endpoints::StandardRecord;
+use ic_icrc1::snapshot::{
+    chain_balance, state_hash, GetSnapshotEntriesArgs, LedgerSnapshotInfo, SnapshotBalance,
+    SnapshotBalances, EMPTY_ENTRIES_HASH,
+};
 use ic_icrc1::{Block, Operation};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, GetBlocksMethod, IndexArg, InitArg, ListSubaccountsArgs, Log,
-    LogEntry, Status, TransactionWithId, UpgradeArg, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsError,
+    GetAccountTransactionsResponse, GetAccountTransactionsResult, GetBlocksMethod, IndexArg,
+    InitArg, ListSubaccountsArgs, Log, LogEntry, Status, TransactionFilter, TransactionKind,
+    TransactionWithId, UpgradeArg, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_ledger_canister_core::runtime::total_memory_size_bytes;
 use ic_ledger_core::block::{BlockIndex as BlockIndex64, BlockType, EncodedBlock};
@@ -28,17 +34,18 @@ use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc3::archive::{ArchivedRange, QueryBlockArchiveFn};
 use icrc_ledger_types::icrc3::blocks::{
     ArchivedBlocks, BlockRange, BlockWithId, GenericBlock, GetBlocksRequest, GetBlocksResponse,
-    GetBlocksResult,
+    GetBlocksResult, ICRC3DataCertificate,
 };
 use icrc_ledger_types::icrc3::transactions::Transaction;
 use num_traits::ToPrimitive;
 use scopeguard::guard;
 use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::cmp::Reverse;
 use std::collections::{BTreeMap, HashMap};
-use std::convert::TryFrom;
+use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::io::Read;
@@ -58,6 +65,18 @@ const ACCOUNT_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
 
 const DEFAULT_RETRIEVE_BLOCKS_FROM_LEDGER_INTERVAL: Duration = Duration::from_secs(1);
 
+/// The maximum number of blocks of an account examined by a single filtered
+/// [get_account_transactions] request.
+const MAX_BLOCKS_SCANNED_PER_FILTERED_REQUEST: usize = 10_000;
+
+/// The number of balances requested to the ledger per call while
+/// bootstrapping from the ledger snapshot.
+const SNAPSHOT_ENTRIES_PER_REQUEST: u64 = 10_000;
+
+/// The maximum number of balances removed by a single run of [build_index]
+/// when the bootstrap from the ledger snapshot restarts from scratch.
+const MAX_BALANCES_REMOVED_PER_RUN: usize = 10_000;
+
 #[cfg(not(feature = "u256-tokens"))]
 type Tokens = ic_icrc1_tokens_u64::U64;
 
@@ -136,6 +155,51 @@ struct State {
     /// index. Lower values will result in a more responsive UI, but higher costs due to increased
     /// cycle burn for the index, ledger and archive(s).
     retrieve_blocks_from_ledger_interval: Option<Duration>,
+
+    /// Equals to `true` if the index must initialize the balances from the
+    /// snapshot of the ledger state before fetching any block.
+    #[serde(default)]
+    bootstrap_from_ledger_snapshot: bool,
+
+    /// The progress of the download of the ledger snapshot, if one is running.
+    #[serde(default)]
+    bootstrap: Option<SnapshotBootstrap>,
+
+    /// Equals to `true` while the balances of an aborted bootstrap are being
+    /// removed, see [reset_bootstrap]. No block is indexed in the meantime.
+    #[serde(default)]
+    bootstrap_reset_pending: bool,
+
+    /// The index of the first block stored in [BLOCKS]. It is greater than 0
+    /// only if the index was bootstrapped from a ledger snapshot.
+    #[serde(default)]
+    first_block_index: BlockIndex64,
+
+    /// The hash of the last block included in the ledger snapshot. The first
+    /// block indexed after the snapshot must have this hash as parent hash.
+    #[serde(default)]
+    snapshot_last_block_hash: Option<[u8; 32]>,
+
+    /// The fee collector of the ledger snapshot and the index of the block
+    /// where it was set, if that block precedes [State::first_block_index].
+    #[serde(default)]
+    snapshot_fee_collector: Option<(Account, BlockIndex64)>,
+}
+
+/// The data of the ledger snapshot that the index is downloading.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct SnapshotBootstrap {
+    num_blocks: u64,
+    last_block_hash: Option<[u8; 32]>,
+    num_balances: u64,
+    transfer_fee: Tokens,
+    fee_collector: Option<(Account, BlockIndex64)>,
+    allowances_hash: [u8; 32],
+    state_hash: [u8; 32],
+    /// The index of the next balance to fetch from the ledger.
+    next_balance: u64,
+    /// The hash of the chain of the balances fetched so far.
+    balances_hash: [u8; 32],
 }
 
 impl State {
@@ -157,6 +221,12 @@ impl Default for State {
             fee_collectors: Default::default(),
             last_fee: None,
             retrieve_blocks_from_ledger_interval: None,
+            bootstrap_from_ledger_snapshot: false,
+            bootstrap: None,
+            bootstrap_reset_pending: false,
+            first_block_index: 0,
+            snapshot_last_block_hash: None,
+            snapshot_fee_collector: None,
         }
     }
 }
@@ -265,11 +335,29 @@ fn with_account_data<R>(f: impl FnOnce(&mut AccountDataMap) -> R) -> R {
 /// because all blocks stored in the transaction log should be decodable
 /// (see [append_blocks]). If not then something is wrong with the log.
 fn get_decoded_block(block_index: BlockIndex64) -> Option<Block<Tokens>> {
-    with_blocks(|blocks| blocks.get(block_index))
+    get_block(block_index)
         .map(EncodedBlock::from)
         .map(|block| decode_encoded_block_or_trap(block_index, block))
 }
 
+/// A helper function that returns the encoded block stored in the block log
+/// at the given index or None if there is no block at that index.
+fn get_block(block_index: BlockIndex64) -> Option<Vec<u8>> {
+    let position = block_index.checked_sub(first_block_index())?;
+    with_blocks(|blocks| blocks.get(position))
+}
+
+/// Returns the index of the first block stored in the block log.
+fn first_block_index() -> BlockIndex64 {
+    with_state(|state| state.first_block_index)
+}
+
+/// Returns the length of the chain indexed, i.e. the index of the next block
+/// to append to the block log.
+fn chain_length() -> BlockIndex64 {
+    first_block_index() + with_blocks(|blocks| blocks.len())
+}
+
 /// A helper function to access the balance of an account.
 fn get_balance(account: Account) -> Tokens {
     with_account_data(|account_data| {
@@ -300,6 +388,7 @@ fn init(index_arg: Option<IndexArg>) {
     let InitArg {
         ledger_id,
         retrieve_blocks_from_ledger_interval_seconds,
+        bootstrap_from_ledger_snapshot,
     } = match index_arg {
         Some(IndexArg::Init(arg)) => arg,
         _ => trap("Index initialization must take in input an InitArg argument"),
@@ -310,6 +399,7 @@ fn init(index_arg: Option<IndexArg>) {
         state.ledger_id = ledger_id;
         state.retrieve_blocks_from_ledger_interval =
             retrieve_blocks_from_ledger_interval_seconds.map(Duration::from_secs);
+        state.bootstrap_from_ledger_snapshot = bootstrap_from_ledger_snapshot.unwrap_or_default();
     });
 
     // set the first build_index to be called after init
@@ -548,6 +638,290 @@ async fn find_get_blocks_method() -> GetBlocksMethod {
     get_blocks_method
 }
 
+async fn get_ledger_snapshot_info() -> Option<Option<LedgerSnapshotInfo>> {
+    let ledger_id = with_state(|state| state.ledger_id);
+    log!(P1, "[get_ledger_snapshot_info]: making the call...");
+    let res = measured_call(
+        "build_index.get_ledger_snapshot_info.encode",
+        "build_index.get_ledger_snapshot_info.decode",
+        ledger_id,
+        "get_ledger_snapshot_info",
+        &(),
+    )
+    .await;
+    match res {
+        Ok(res) => Some(res),
+        Err(err) => {
+            log!(
+                P0,
+                "[get_ledger_snapshot_info] failed to get the snapshot info: {}",
+                err
+            );
+            None
+        }
+    }
+}
+
+async fn get_ledger_snapshot_balances(start: u64) -> Option<Option<SnapshotBalances>> {
+    let ledger_id = with_state(|state| state.ledger_id);
+    let req = GetSnapshotEntriesArgs {
+        start,
+        length: SNAPSHOT_ENTRIES_PER_REQUEST,
+    };
+    log!(P1, "[get_ledger_snapshot_balances]: making the call...");
+    let res = measured_call(
+        "build_index.get_ledger_snapshot_balances.encode",
+        "build_index.get_ledger_snapshot_balances.decode",
+        ledger_id,
+        "get_ledger_snapshot_balances",
+        &req,
+    )
+    .await;
+    match res {
+        Ok(res) => Some(res),
+        Err(err) => {
+            log!(
+                P0,
+                "[get_ledger_snapshot_balances] failed to get the snapshot balances: {}",
+                err
+            );
+            None
+        }
+    }
+}
+
+async fn get_ledger_tip_certificate() -> Option<Option<ICRC3DataCertificate>> {
+    let ledger_id = with_state(|state| state.ledger_id);
+    log!(P1, "[get_ledger_tip_certificate]: making the call...");
+    let res = measured_call(
+        "build_index.icrc3_get_tip_certificate.encode",
+        "build_index.icrc3_get_tip_certificate.decode",
+        ledger_id,
+        "icrc3_get_tip_certificate",
+        &(),
+    )
+    .await;
+    match res {
+        Ok(res) => Some(res),
+        Err(err) => {
+            log!(
+                P0,
+                "[get_ledger_tip_certificate] failed to get the tip certificate: {}",
+                err
+            );
+            None
+        }
+    }
+}
+
+/// Checks that the hash tree of the tip certificate of the ledger certifies
+/// `state_hash` as the hash of the ledger snapshot. The certificate itself is
+/// empty when the ledger is called by a canister, because the response of a
+/// replicated call is already authenticated. If it is present then the
+/// certified data of the ledger must match the hash tree.
+fn verify_certified_snapshot_hash(
+    ledger_id: Principal,
+    tip_certificate: ICRC3DataCertificate,
+    state_hash: &[u8; 32],
+) -> Result<(), String> {
+    let hash_tree: MixedHashTree = ciborium::de::from_reader(tip_certificate.hash_tree.as_slice())
+        .map_err(|err| format!("failed to decode the hash tree: {}", err))?;
+    match hash_tree.lookup(&[b"snapshot_hash"]) {
+        LookupStatus::Found(MixedHashTree::Leaf(hash)) if hash == state_hash => {}
+        LookupStatus::Found(MixedHashTree::Leaf(_)) => {
+            return Err("the certified snapshot hash differs from the state hash".to_string())
+        }
+        _ => return Err("the hash tree has no snapshot hash".to_string()),
+    }
+    if !tip_certificate.certificate.is_empty() {
+        #[derive(Deserialize)]
+        struct Certificate {
+            tree: MixedHashTree,
+        }
+        let certificate: Certificate =
+            ciborium::de::from_reader(tip_certificate.certificate.as_slice())
+                .map_err(|err| format!("failed to decode the certificate: {}", err))?;
+        let path: [&[u8]; 3] = [b"canister", ledger_id.as_slice(), b"certified_data"];
+        match certificate.tree.lookup(&path) {
+            LookupStatus::Found(MixedHashTree::Leaf(certified_data))
+                if certified_data[..] == hash_tree.digest().0[..] => {}
+            _ => return Err("the certified data doesn't match the hash tree".to_string()),
+        }
+    }
+    Ok(())
+}
+
+fn to_hash(bytes: &ByteBuf) -> Option<[u8; 32]> {
+    bytes.as_slice().try_into().ok()
+}
+
+impl TryFrom<LedgerSnapshotInfo> for SnapshotBootstrap {
+    type Error = String;
+
+    fn try_from(info: LedgerSnapshotInfo) -> Result<Self, Self::Error> {
+        let last_block_hash = match info.last_block_hash {
+            Some(hash) => Some(to_hash(&hash).ok_or("invalid last_block_hash")?),
+            None => None,
+        };
+        Ok(Self {
+            num_blocks: info.num_blocks,
+            last_block_hash,
+            num_balances: info.num_balances,
+            transfer_fee: Tokens::try_from(info.transfer_fee)
+                .map_err(|err| format!("invalid transfer_fee: {}", err))?,
+            fee_collector: info.fee_collector.zip(info.fee_collector_block_index),
+            allowances_hash: to_hash(&info.allowances_hash).ok_or("invalid allowances_hash")?,
+            state_hash: to_hash(&info.state_hash).ok_or("invalid state_hash")?,
+            next_balance: 0,
+            balances_hash: EMPTY_ENTRIES_HASH,
+        })
+    }
+}
+
+/// Removes up to [MAX_BALANCES_REMOVED_PER_RUN] of the balances downloaded so
+/// far so that the bootstrap can restart from scratch, and returns `true` once
+/// all of them are removed. This is safe because no block has been indexed yet.
+fn reset_bootstrap() -> bool {
+    let done = with_account_data(|account_data| {
+        let keys: Vec<_> = account_data
+            .iter()
+            .take(MAX_BALANCES_REMOVED_PER_RUN)
+            .map(|(key, _)| key)
+            .collect();
+        for key in keys {
+            account_data.remove(&key);
+        }
+        account_data.is_empty()
+    });
+    mutate_state(|state| {
+        state.bootstrap = None;
+        state.bootstrap_reset_pending = !done;
+    });
+    done
+}
+
+/// Initializes the balances from the snapshot of the ledger state. The
+/// download can span several runs of [build_index] and is verified
+/// against the state hash of the snapshot before any block is indexed.
+/// If the ledger has no snapshot then the index syncs from the first block.
+async fn bootstrap_from_ledger_snapshot() -> Option<()> {
+    if chain_length() > 0 {
+        mutate_state(|state| state.bootstrap_from_ledger_snapshot = false);
+        return Some(());
+    }
+    let mut bootstrap = match with_state(|state| state.bootstrap.clone()) {
+        Some(bootstrap) => bootstrap,
+        None => match get_ledger_snapshot_info().await? {
+            None => {
+                log!(
+                    P1,
+                    "[bootstrap_from_ledger_snapshot]: the ledger has no snapshot, syncing from the first block"
+                );
+                mutate_state(|state| state.bootstrap_from_ledger_snapshot = false);
+                return Some(());
+            }
+            Some(info) => match SnapshotBootstrap::try_from(info) {
+                Ok(bootstrap) => {
+                    mutate_state(|state| state.bootstrap = Some(bootstrap.clone()));
+                    bootstrap
+                }
+                Err(err) => {
+                    log!(
+                        P0,
+                        "[bootstrap_from_ledger_snapshot]: invalid snapshot info: {}",
+                        err
+                    );
+                    return None;
+                }
+            },
+        },
+    };
+
+    while bootstrap.next_balance < bootstrap.num_balances {
+        let page = get_ledger_snapshot_balances(bootstrap.next_balance).await?;
+        let page = match page {
+            Some(page) if to_hash(&page.state_hash) == Some(bootstrap.state_hash) => page,
+            _ => {
+                log!(
+                    P1,
+                    "[bootstrap_from_ledger_snapshot]: the ledger snapshot changed, restarting the bootstrap"
+                );
+                reset_bootstrap();
+                return None;
+            }
+        };
+        if page.balances.is_empty() {
+            log!(
+                P0,
+                "[bootstrap_from_ledger_snapshot]: the ledger returned no balances from index {} but the snapshot has {} balances",
+                bootstrap.next_balance,
+                bootstrap.num_balances,
+            );
+            return None;
+        }
+        for SnapshotBalance { account, balance } in page.balances {
+            bootstrap.balances_hash = chain_balance(&bootstrap.balances_hash, &account, &balance);
+            let balance = Tokens::try_from(balance).unwrap_or_else(|err| {
+                trap(&format!(
+                    "Invalid balance in the ledger snapshot for account {}: {}",
+                    account, err
+                ))
+            });
+            change_balance(account, |_| balance);
+            bootstrap.next_balance += 1;
+        }
+        mutate_state(|state| state.bootstrap = Some(bootstrap.clone()));
+    }
+
+    let expected_state_hash = state_hash(
+        bootstrap.num_blocks,
+        bootstrap.last_block_hash.as_ref(),
+        &bootstrap.balances_hash,
+        &bootstrap.allowances_hash,
+    );
+    if expected_state_hash != bootstrap.state_hash {
+        log!(
+            P0,
+            "[bootstrap_from_ledger_snapshot]: the balances don't match the state hash of the snapshot, syncing from the first block"
+        );
+        mutate_state(|state| state.bootstrap_from_ledger_snapshot = false);
+        return reset_bootstrap().then_some(());
+    }
+
+    let ledger_id = with_state(|state| state.ledger_id);
+    let certified = match get_ledger_tip_certificate().await? {
+        Some(tip_certificate) => {
+            verify_certified_snapshot_hash(ledger_id, tip_certificate, &bootstrap.state_hash)
+        }
+        None => Err("the ledger has no tip certificate".to_string()),
+    };
+    if let Err(err) = certified {
+        log!(
+            P0,
+            "[bootstrap_from_ledger_snapshot]: the ledger doesn't certify the snapshot ({}), syncing from the first block",
+            err
+        );
+        mutate_state(|state| state.bootstrap_from_ledger_snapshot = false);
+        return reset_bootstrap().then_some(());
+    }
+
+    log!(
+        P1,
+        "[bootstrap_from_ledger_snapshot]: initialized {} balances from the snapshot at block {}",
+        bootstrap.num_balances,
+        bootstrap.num_blocks,
+    );
+    mutate_state(|state| {
+        state.first_block_index = bootstrap.num_blocks;
+        state.snapshot_last_block_hash = bootstrap.last_block_hash;
+        state.snapshot_fee_collector = bootstrap.fee_collector;
+        state.last_fee = Some(bootstrap.transfer_fee);
+        state.bootstrap = None;
+        state.bootstrap_from_ledger_snapshot = false;
+    });
+    Some(())
+}
+
 pub async fn build_index() -> Option<()> {
     if with_state(|state| state.is_build_index_running) {
         return None;
@@ -560,6 +934,12 @@ pub async fn build_index() -> Option<()> {
             state.is_build_index_running = false;
         });
     });
+    if with_state(|state| state.bootstrap_reset_pending) && !reset_bootstrap() {
+        return None;
+    }
+    if with_state(|state| state.bootstrap_from_ledger_snapshot) {
+        bootstrap_from_ledger_snapshot().await?;
+    }
     let num_indexed = match find_get_blocks_method().await {
         GetBlocksMethod::GetBlocks => fetch_blocks_via_get_blocks().await?,
         GetBlocksMethod::ICRC3GetBlocks => fetch_blocks_via_icrc3().await?,
@@ -577,7 +957,7 @@ pub async fn build_index() -> Option<()> {
 
 async fn fetch_blocks_via_get_blocks() -> Option<u64> {
     let mut num_indexed = 0;
-    let next_id = with_blocks(|blocks| blocks.len());
+    let next_id = chain_length();
     let res = get_blocks_from_ledger(next_id).await?;
     for archived in res.archived_blocks {
         let mut remaining = archived.length.clone();
@@ -603,7 +983,7 @@ async fn fetch_blocks_via_get_blocks() -> Option<u64> {
 async fn fetch_blocks_via_icrc3() -> Option<u64> {
     // The current number of blocks is also the id of the next
     // block to query from the Ledger.
-    let previous_num_blocks = with_blocks(|blocks| blocks.len());
+    let previous_num_blocks = chain_length();
     let res = icrc3_get_blocks_from_ledger(previous_num_blocks).await?;
 
     // The Ledger should return archives in order but there is
@@ -623,7 +1003,7 @@ async fn fetch_blocks_via_icrc3() -> Option<u64> {
         while arg.length != 0u64 {
             // sanity check that the next index to fetch is the correct
             // one, i.e. next_id + num_indexed
-            let expected_id = with_blocks(|blocks| blocks.len());
+            let expected_id = chain_length();
             if arg.start != expected_id {
                 log!(
                     P0,
@@ -661,7 +1041,7 @@ async fn fetch_blocks_via_icrc3() -> Option<u64> {
     }
 
     append_icrc3_blocks(res.blocks)?;
-    let num_blocks = with_blocks(|blocks| blocks.len());
+    let num_blocks = chain_length();
     match num_blocks.checked_sub(previous_num_blocks) {
         None => panic!("The number of blocks {} is smaller than the number of blocks before indexing {}. This is impossible. I'm trapping to reset the state", num_blocks, previous_num_blocks),
         Some(new_blocks_indexed) => Some(new_blocks_indexed),
@@ -689,6 +1069,18 @@ fn append_block(block_index: BlockIndex64, block: GenericBlock) {
 
         let decoded_block = decode_encoded_block_or_trap(block_index, block);
 
+        // the first block after the ledger snapshot must be chained to it
+        if block_index == first_block_index() && block_index > 0 {
+            let parent_hash = decoded_block.parent_hash.map(|hash| hash.into_bytes());
+            let snapshot_last_block_hash = with_state(|state| state.snapshot_last_block_hash);
+            if parent_hash != snapshot_last_block_hash {
+                trap(&format!(
+                    "The parent hash {:?} of block {} doesn't match the hash {:?} of the last block of the ledger snapshot",
+                    parent_hash, block_index, snapshot_last_block_hash
+                ));
+            }
+        }
+
         // add the block idx to the indices
         with_account_block_ids(|account_block_ids| {
             for account in get_accounts(&decoded_block) {
@@ -707,7 +1099,7 @@ fn append_block(block_index: BlockIndex64, block: GenericBlock) {
 fn append_blocks(new_blocks: Vec<GenericBlock>) {
     // the index of the next block that we
     // are going to append
-    let mut block_index = with_blocks(|blocks| blocks.len());
+    let mut block_index = chain_length();
     for block in new_blocks {
         append_block(block_index, block);
         block_index += 1;
@@ -716,7 +1108,7 @@ fn append_blocks(new_blocks: Vec<GenericBlock>) {
 
 fn append_icrc3_blocks(new_blocks: Vec<BlockWithId>) -> Option<()> {
     let mut blocks = vec![];
-    let start_id = with_blocks(|blocks| blocks.len());
+    let start_id = chain_length();
     for BlockWithId { id, block } in new_blocks {
         // sanity check
         let expected_id = start_id + blocks.len() as u64;
@@ -780,6 +1172,12 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
                 amount,
                 fee,
                 ..
+            }
+            | Operation::BatchTransfer {
+                from,
+                to,
+                amount,
+                fee,
             } => {
                 let fee = block.effective_fee.or(fee).unwrap_or_else(|| {
                     ic_cdk::trap(&format!(
@@ -831,6 +1229,16 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
 
                 debit(block_index, from, fee);
             }
+            Operation::SetSpendingPolicy { from, fee, .. }
+            | Operation::RevokeAllApprovals { from, fee } => {
+                let fee = fee.or(block.effective_fee).unwrap_or_else(|| {
+                    ic_cdk::trap(&format!(
+                        "Block {} changes approvals but has no fee or effective fee!",
+                        block_index
+                    ))
+                });
+                debit(block_index, from, fee);
+            }
         },
     );
 }
@@ -880,6 +1288,9 @@ fn get_accounts(block: &Block<Tokens>) -> Vec<Account> {
         Operation::Mint { to, .. } => vec![to],
         Operation::Transfer { from, to, .. } => vec![from, to],
         Operation::Approve { from, .. } => vec![from],
+        Operation::SetSpendingPolicy { from, .. } => vec![from],
+        Operation::RevokeAllApprovals { from, .. } => vec![from],
+        Operation::BatchTransfer { from, to, .. } => vec![from, to],
     }
 }
 
@@ -887,6 +1298,12 @@ fn get_fee_collector(block_index: BlockIndex64, block: &Block<Tokens>) -> Option
     if block.fee_collector.is_some() {
         block.fee_collector
     } else if let Some(fee_collector_block_index) = block.fee_collector_block_index {
+        if fee_collector_block_index < first_block_index() {
+            return match with_state(|state| state.snapshot_fee_collector) {
+                Some((fee_collector, index)) if index == fee_collector_block_index => Some(fee_collector),
+                _ => ic_cdk::trap(&format!("Block at index {} has fee_collector_block_index {} but that block precedes the ledger snapshot and the snapshot has no such fee_collector", block_index, fee_collector_block_index)),
+            };
+        }
         let block = get_decoded_block(fee_collector_block_index)
             .unwrap_or_else(||
                 ic_cdk::trap(&format!("Block at index {} has fee_collector_block_index {} but there is no block at that index", block_index, fee_collector_block_index)));
@@ -918,7 +1335,7 @@ fn decode_icrc1_block(_txid: u64, bytes: Vec<u8>) -> GenericBlock {
 #[query]
 #[candid_method(query)]
 fn get_blocks(req: GetBlocksRequest) -> ic_icrc1_index_ng::GetBlocksResponse {
-    let chain_length = with_blocks(|blocks| blocks.len());
+    let chain_length = chain_length();
     let (start, length) = req
         .as_start_and_length()
         .unwrap_or_else(|msg| ic_cdk::api::trap(&msg));
@@ -932,10 +1349,11 @@ fn get_blocks(req: GetBlocksRequest) -> ic_icrc1_index_ng::GetBlocksResponse {
 
 fn decode_block_range<R>(start: u64, length: u64, decoder: impl Fn(u64, Vec<u8>) -> R) -> Vec<R> {
     let length = length.min(with_state(|opts| opts.max_blocks_per_response));
+    let first_block_index = first_block_index();
     with_blocks(|blocks| {
-        let limit = blocks.len().min(start.saturating_add(length));
-        (start..limit)
-            .map(|i| decoder(start + i, blocks.get(i).unwrap()))
+        let limit = (first_block_index + blocks.len()).min(start.saturating_add(length));
+        (start.max(first_block_index)..limit)
+            .map(|i| decoder(i, blocks.get(i - first_block_index).unwrap()))
             .collect()
     })
 }
@@ -960,33 +1378,43 @@ fn get_account_transactions(arg: GetAccountTransactionsArgs) -> GetAccountTransa
     let start = arg
         .start
         .map_or(u64::MAX, |n| n.0.to_u64().expect("start must be a u64!"));
-    let key = account_block_ids_key(arg.account, start);
     let mut transactions = vec![];
-    let indices = with_account_block_ids(|account_block_ids| {
-        account_block_ids
-            .range(key..)
-            // old txs of the requested account and skip the start index
-            .take_while(|(k, _)| k.0 == key.0)
-            .filter(|(k, _)| k.1 .0 < start)
-            .take(length)
-            .map(|(k, _)| k.1 .0)
-            .collect::<Vec<BlockIndex64>>()
-    });
-    for id in indices {
-        let block = with_blocks(|blocks| {
-            blocks.get(id).unwrap_or_else(|| {
-                trap(&format!(
-                    "Block {} not found in the block log, account blocks map is corrupted!",
-                    id
-                ))
-            })
-        });
-        let transaction = encoded_block_bytes_to_flat_transaction(id, block);
-        let transaction_with_idx = TransactionWithId {
-            id: id.into(),
-            transaction,
-        };
-        transactions.push(transaction_with_idx);
+    let mut last_scanned_tx_id = None;
+    match arg.filter {
+        None => {
+            let key = account_block_ids_key(arg.account, start);
+            let indices = with_account_block_ids(|account_block_ids| {
+                account_block_ids
+                    .range(key..)
+                    // old txs of the requested account and skip the start index
+                    .take_while(|(k, _)| k.0 == key.0)
+                    .filter(|(k, _)| k.1 .0 < start)
+                    .take(length)
+                    .map(|(k, _)| k.1 .0)
+                    .collect::<Vec<BlockIndex64>>()
+            });
+            for id in indices {
+                let transaction =
+                    encoded_block_bytes_to_flat_transaction(id, get_block_or_trap(id));
+                transactions.push(TransactionWithId {
+                    id: id.into(),
+                    transaction,
+                });
+            }
+        }
+        Some(filter) => {
+            validate_transaction_filter(&filter)
+                .map_err(|message| GetAccountTransactionsError { message })?;
+            let (blocks, last_scanned) =
+                find_account_blocks_matching(arg.account, start, length, &filter);
+            for (id, block) in blocks {
+                transactions.push(TransactionWithId {
+                    id: id.into(),
+                    transaction: block.into(),
+                });
+            }
+            last_scanned_tx_id = last_scanned.map(|tx_id| tx_id.into());
+        }
     }
     let oldest_tx_id = get_oldest_tx_id(arg.account).map(|tx_id| tx_id.into());
     let balance = get_balance(arg.account).into();
@@ -994,6 +1422,176 @@ fn get_account_transactions(arg: GetAccountTransactionsArgs) -> GetAccountTransa
         balance,
         transactions,
         oldest_tx_id,
+        last_scanned_tx_id,
+    })
+}
+
+fn validate_transaction_filter(filter: &TransactionFilter) -> Result<(), String> {
+    if let (Some(start), Some(end)) = (filter.start_timestamp, filter.end_timestamp) {
+        if start > end {
+            return Err(format!(
+                "start_timestamp {} must not be greater than end_timestamp {}",
+                start, end
+            ));
+        }
+    }
+    if let (Some(min), Some(max)) = (&filter.min_amount, &filter.max_amount) {
+        if min > max {
+            return Err(format!(
+                "min_amount {} must not be greater than max_amount {}",
+                min, max
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns up to `length` blocks of `account` older than `start` that match
+/// `filter`, newest first, together with the index of the last examined block
+/// if the search stopped before exhausting the candidates.
+///
+/// At most [MAX_BLOCKS_SCANNED_PER_FILTERED_REQUEST] blocks are examined to
+/// keep the query within the instruction limit.
+fn find_account_blocks_matching(
+    account: Account,
+    start: BlockIndex64,
+    length: usize,
+    filter: &TransactionFilter,
+) -> (Vec<(BlockIndex64, Block<Tokens>)>, Option<BlockIndex64>) {
+    // Blocks are appended in timestamp order, so the blocks at or after
+    // end_timestamp can be skipped without looking at the account's blocks.
+    let start = match filter.end_timestamp {
+        Some(end_timestamp) => start.min(first_block_index_at_or_after(end_timestamp)),
+        None => start,
+    };
+    let key = account_block_ids_key(account, start);
+    let mut blocks = vec![];
+    let mut scanned = 0;
+    let mut last_scanned = None;
+    with_account_block_ids(|account_block_ids| {
+        for (k, _) in account_block_ids
+            .range(key..)
+            .take_while(|(k, _)| k.0 == key.0)
+            .filter(|(k, _)| k.1 .0 < start)
+        {
+            if blocks.len() == length || scanned == MAX_BLOCKS_SCANNED_PER_FILTERED_REQUEST {
+                return;
+            }
+            let id = k.1 .0;
+            let block = decode_encoded_block_or_trap(id, EncodedBlock::from(get_block_or_trap(id)));
+            if filter
+                .start_timestamp
+                .is_some_and(|start_timestamp| block.timestamp < start_timestamp)
+            {
+                // All the remaining blocks are older.
+                last_scanned = None;
+                return;
+            }
+            scanned += 1;
+            last_scanned = Some(id);
+            if block_matches_filter(account, &block, filter) {
+                blocks.push((id, block));
+            }
+        }
+        // No more blocks for the account.
+        last_scanned = None;
+    });
+    (blocks, last_scanned)
+}
+
+fn block_matches_filter(
+    account: Account,
+    block: &Block<Tokens>,
+    filter: &TransactionFilter,
+) -> bool {
+    let zero = Tokens::zero();
+    let (kind, amount, accounts) = match &block.transaction.operation {
+        Operation::Mint { to, amount } => (TransactionKind::Mint, amount, vec![*to]),
+        Operation::Burn {
+            from,
+            spender,
+            amount,
+        } => (
+            TransactionKind::Burn,
+            amount,
+            std::iter::once(*from).chain(*spender).collect(),
+        ),
+        Operation::Transfer {
+            from,
+            to,
+            spender,
+            amount,
+            ..
+        } => (
+            TransactionKind::Transfer,
+            amount,
+            [*from, *to].into_iter().chain(*spender).collect(),
+        ),
+        Operation::Approve {
+            from,
+            spender,
+            amount,
+            ..
+        } => (TransactionKind::Approve, amount, vec![*from, *spender]),
+        Operation::SetSpendingPolicy {
+            from,
+            spender,
+            period_cap,
+            ..
+        } => (
+            TransactionKind::SetSpendingPolicy,
+            period_cap.as_ref().unwrap_or(&zero),
+            vec![*from, *spender],
+        ),
+        Operation::RevokeAllApprovals { from, .. } => {
+            (TransactionKind::RevokeAllApprovals, &zero, vec![*from])
+        }
+        Operation::BatchTransfer {
+            from, to, amount, ..
+        } => (TransactionKind::BatchTransfer, amount, vec![*from, *to]),
+    };
+    if let Some(kinds) = &filter.kinds {
+        if !kinds.contains(&kind) {
+            return false;
+        }
+    }
+    if let Some(counterparty) = filter.counterparty {
+        if counterparty == account || !accounts.contains(&counterparty) {
+            return false;
+        }
+    }
+    let amount: Nat = amount.clone().into();
+    if filter.min_amount.as_ref().is_some_and(|min| &amount < min) {
+        return false;
+    }
+    if filter.max_amount.as_ref().is_some_and(|max| &amount > max) {
+        return false;
+    }
+    true
+}
+
+/// Returns the index of the first block with a timestamp greater than or
+/// equal to `timestamp`, or the number of blocks if there is no such block.
+fn first_block_index_at_or_after(timestamp: u64) -> BlockIndex64 {
+    let (mut lo, mut hi) = (first_block_index(), chain_length());
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let block = decode_encoded_block_or_trap(mid, EncodedBlock::from(get_block_or_trap(mid)));
+        if block.timestamp < timestamp {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+fn get_block_or_trap(block_index: BlockIndex64) -> Vec<u8> {
+    get_block(block_index).unwrap_or_else(|| {
+        trap(&format!(
+            "Block {} not found in the block log, account blocks map is corrupted!",
+            block_index
+        ))
     })
 }
 
@@ -1037,7 +1635,7 @@ fn icrc1_balance_of(account: Account) -> Nat {
 #[query]
 #[candid_method(query)]
 fn status() -> Status {
-    let num_blocks_synced = with_blocks(|blocks| blocks.len().into());
+    let num_blocks_synced = chain_length().into();
     Status { num_blocks_synced }
 }
 
@@ -39,6 +39,7 @@ fn install_and_upgrade(
     let args = IndexArg::Init(ic_icrc1_index_ng::InitArg {
         ledger_id: Principal::from(ledger_id),
         retrieve_blocks_from_ledger_interval_seconds: install_interval,
+        bootstrap_from_ledger_snapshot: None,
     });
     let index_id = env.install_canister_with_cycles(
         index_ng_wasm(),
@@ -281,6 +282,7 @@ fn should_consume_expected_amount_of_cycles() {
             InitArg {
                 ledger_id: Principal::from(ledger_id),
                 retrieve_blocks_from_ledger_interval_seconds: initial_interval,
+                bootstrap_from_ledger_snapshot: None,
             },
         );
 
@@ -9,7 +9,8 @@ use ic_base_types::{CanisterId, PrincipalId};
 use ic_icrc1_index_ng::{
     FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
     GetAccountTransactionsResult, GetBlocksResponse, IndexArg, InitArg as IndexInitArg,
-    ListSubaccountsArgs, TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    ListSubaccountsArgs, TransactionFilter, TransactionKind, TransactionWithId,
+    DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_icrc1_ledger::{ChangeFeeCollector, LedgerArgument, UpgradeArgs as LedgerUpgradeArgs};
 use ic_icrc1_test_utils::{
@@ -22,6 +23,7 @@ use icrc_ledger_types::icrc1::transfer::{BlockIndex, TransferArg, TransferError}
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
 use icrc_ledger_types::icrc3::transactions::{Mint, Transaction, Transfer};
+use icrc_ledger_types::icrc4::batch_transfer::TransferBatchResult;
 use num_traits::cast::ToPrimitive;
 use proptest::test_runner::{Config as TestRunnerConfig, TestRunner};
 use std::collections::HashSet;
@@ -57,6 +59,7 @@ fn upgrade_ledger(
         feature_flags: None,
         accounts_overflow_trim_quantity: None,
         change_archive_options: None,
+        take_snapshot: None,
     }));
     env.upgrade_canister(ledger_id, ledger_wasm(), Encode!(&args).unwrap())
         .unwrap()
@@ -66,6 +69,7 @@ fn index_init_arg_without_interval(ledger_id: CanisterId) -> IndexInitArg {
     IndexInitArg {
         ledger_id: Principal::from(ledger_id),
         retrieve_blocks_from_ledger_interval_seconds: None,
+        bootstrap_from_ledger_snapshot: None,
     }
 }
 
@@ -204,6 +208,24 @@ fn transfer(
     icrc1_transfer(env, ledger_id, owner.into(), req)
 }
 
+fn icrc4_transfer_batch(
+    env: &StateMachine,
+    ledger_id: CanisterId,
+    caller: PrincipalId,
+    args: Vec<TransferArg>,
+) -> Vec<BlockIndex> {
+    let req = Encode!(&args).expect("Failed to encode TransferBatchArgs");
+    let res = env
+        .execute_ingress_as(caller, ledger_id, "icrc4_transfer_batch", req)
+        .expect("Failed to transfer a batch of tokens")
+        .bytes();
+    Decode!(&res, Vec<TransferBatchResult>)
+        .expect("Failed to decode Vec<TransferBatchResult>")
+        .into_iter()
+        .map(|res| res.expect("Failed to transfer a batch entry"))
+        .collect()
+}
+
 fn icrc2_approve(
     env: &StateMachine,
     ledger_id: CanisterId,
@@ -282,10 +304,23 @@ fn get_account_transactions(
     start: Option<u64>,
     max_results: u64,
 ) -> GetAccountTransactionsResponse {
+    get_filtered_account_transactions(env, index_id, account, start, max_results, None)
+        .expect("Failed to perform GetAccountTransactionsArgs")
+}
+
+fn get_filtered_account_transactions(
+    env: &StateMachine,
+    index_id: CanisterId,
+    account: Account,
+    start: Option<u64>,
+    max_results: u64,
+    filter: Option<TransactionFilter>,
+) -> GetAccountTransactionsResult {
     let req = GetAccountTransactionsArgs {
         account,
         start: start.map(|n| n.into()),
         max_results: max_results.into(),
+        filter,
     };
     let req = Encode!(&req).expect("Failed to encode GetAccountTransactionsArgs");
     let res = env
@@ -294,7 +329,6 @@ fn get_account_transactions(
         .bytes();
     Decode!(&res, GetAccountTransactionsResult)
         .expect("Failed to decode GetAccountTransactionsArgs")
-        .expect("Failed to perform GetAccountTransactionsArgs")
 }
 
 fn list_subaccounts(
@@ -772,6 +806,8 @@ fn test_get_account_transactions_pagination() {
                     }),
                     transfer: None,
                     approve: None,
+                    set_spending_policy: None,
+                    revoke_all_approvals: None,
                     timestamp: 0,
                 },
                 transaction,
@@ -955,6 +991,52 @@ fn test_post_upgrade_start_timer() {
     wait_until_sync_is_completed(env, index_id, ledger_id);
 }
 
+#[test]
+fn test_bootstrap_from_ledger_snapshot() {
+    let env = &StateMachine::new();
+    let minter = minter_identity();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000), (account(2, 0), 20_000_000)],
+        default_archive_options(),
+        None,
+        minter.sender().unwrap(),
+    );
+    transfer(env, ledger_id, account(1, 0), account(3, 0), 1_000_000);
+
+    let args = LedgerArgument::Upgrade(Some(LedgerUpgradeArgs {
+        take_snapshot: Some(true),
+        ..LedgerUpgradeArgs::default()
+    }));
+    env.upgrade_canister(ledger_id, ledger_wasm(), Encode!(&args).unwrap())
+        .unwrap();
+    transfer(env, ledger_id, account(2, 0), account(3, 0), 2_000_000);
+    // The snapshot entries are kept in stable memory across upgrades.
+    let args = LedgerArgument::Upgrade(None);
+    env.upgrade_canister(ledger_id, ledger_wasm(), Encode!(&args).unwrap())
+        .unwrap();
+
+    let index_id = install_index_ng(
+        env,
+        IndexInitArg {
+            bootstrap_from_ledger_snapshot: Some(true),
+            ..index_init_arg_without_interval(ledger_id)
+        },
+    );
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    // Only the block created after the snapshot is stored in the index.
+    let blocks = index_get_blocks(env, index_id, 0, 10);
+    assert_eq!(blocks.chain_length, 4);
+    assert_eq!(blocks.blocks.len(), 1);
+    for owner in 1..=3 {
+        assert_eq!(
+            icrc1_balance_of(env, ledger_id, account(owner, 0)),
+            icrc1_balance_of(env, index_id, account(owner, 0))
+        );
+    }
+}
+
 #[test]
 fn test_oldest_tx_id() {
     let env = &StateMachine::new();
@@ -1029,6 +1111,220 @@ fn test_oldest_tx_id() {
     assert_eq!(get_fee_collectors_ranges(env, index_id).ranges, vec![]);
 }
 
+#[test]
+fn test_get_account_transactions_with_filter() {
+    let env = &StateMachine::new();
+    let minter = minter_identity().sender().unwrap();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)],
+        default_archive_options(),
+        None,
+        minter,
+    );
+    let index_id = install_index_ng(env, index_init_arg_without_interval(ledger_id));
+
+    let nanos = |t: SystemTime| t.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_nanos() as u64;
+
+    env.advance_time(Duration::from_secs(24 * 60 * 60));
+    let day1 = nanos(env.time());
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 100_000); // block 1
+    env.advance_time(Duration::from_secs(24 * 60 * 60));
+    let day2 = nanos(env.time());
+    transfer(env, ledger_id, account(1, 0), account(3, 0), 200_000); // block 2
+    approve(env, ledger_id, account(1, 0), account(2, 0), 1_000_000); // block 3
+    env.advance_time(Duration::from_secs(24 * 60 * 60));
+    let day3 = nanos(env.time());
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 300_000); // block 4
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let ids = |filter: TransactionFilter| -> Vec<u64> {
+        get_filtered_account_transactions(
+            env,
+            index_id,
+            account(1, 0),
+            None,
+            u64::MAX,
+            Some(filter),
+        )
+        .expect("Failed to perform GetAccountTransactionsArgs")
+        .transactions
+        .into_iter()
+        .map(|tx| tx.id.0.to_u64().unwrap())
+        .collect()
+    };
+
+    assert_eq!(ids(TransactionFilter::default()), vec![4, 3, 2, 1, 0]);
+    assert_eq!(
+        ids(TransactionFilter {
+            kinds: Some(vec![TransactionKind::Transfer]),
+            ..Default::default()
+        }),
+        vec![4, 2, 1]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            kinds: Some(vec![TransactionKind::Mint, TransactionKind::Approve]),
+            ..Default::default()
+        }),
+        vec![3, 0]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            counterparty: Some(account(2, 0)),
+            ..Default::default()
+        }),
+        vec![4, 3, 1]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            min_amount: Some(150_000u64.into()),
+            max_amount: Some(300_000u64.into()),
+            ..Default::default()
+        }),
+        vec![4, 2]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            start_timestamp: Some(day2),
+            end_timestamp: Some(day3),
+            ..Default::default()
+        }),
+        vec![3, 2]
+    );
+    assert_eq!(
+        ids(TransactionFilter {
+            start_timestamp: Some(day1),
+            end_timestamp: Some(day3),
+            counterparty: Some(account(2, 0)),
+            kinds: Some(vec![TransactionKind::Transfer]),
+            ..Default::default()
+        }),
+        vec![1]
+    );
+
+    // Page through the transfers one at a time.
+    let transfers = TransactionFilter {
+        kinds: Some(vec![TransactionKind::Transfer]),
+        ..Default::default()
+    };
+    let mut start = None;
+    let mut pages = vec![];
+    loop {
+        let res = get_filtered_account_transactions(
+            env,
+            index_id,
+            account(1, 0),
+            start,
+            1,
+            Some(transfers.clone()),
+        )
+        .expect("Failed to perform GetAccountTransactionsArgs");
+        pages.push(
+            res.transactions
+                .into_iter()
+                .map(|tx| tx.id.0.to_u64().unwrap())
+                .collect::<Vec<_>>(),
+        );
+        match res.last_scanned_tx_id {
+            Some(id) => start = Some(id.0.to_u64().unwrap()),
+            None => break,
+        }
+    }
+    assert_eq!(pages, vec![vec![4], vec![2], vec![1], vec![]]);
+
+    // Without a filter there is no continuation.
+    assert_eq!(
+        get_account_transactions(env, index_id, account(1, 0), None, 1).last_scanned_tx_id,
+        None
+    );
+
+    // Invalid ranges are rejected.
+    assert!(get_filtered_account_transactions(
+        env,
+        index_id,
+        account(1, 0),
+        None,
+        u64::MAX,
+        Some(TransactionFilter {
+            start_timestamp: Some(day3),
+            end_timestamp: Some(day1),
+            ..Default::default()
+        }),
+    )
+    .is_err());
+}
+
+#[test]
+fn test_icrc4_transfer_batch() {
+    let env = &StateMachine::new();
+    let minter = minter_identity().sender().unwrap();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)],
+        default_archive_options(),
+        None,
+        minter,
+    );
+    let index_id = install_index_ng(env, index_init_arg_without_interval(ledger_id));
+
+    let entry = |to: Account, amount: u64| TransferArg {
+        from_subaccount: account(1, 0).subaccount,
+        to,
+        fee: None,
+        amount: amount.into(),
+        created_at_time: None,
+        memo: None,
+    };
+    let block_indices = icrc4_transfer_batch(
+        env,
+        ledger_id,
+        account(1, 0).owner.into(),
+        vec![entry(account(2, 0), 100_000), entry(account(3, 0), 200_000)],
+    );
+    assert_eq!(block_indices, vec![Nat::from(1u64), Nat::from(2u64)]);
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 300_000); // block 3
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    assert_ledger_index_parity(env, ledger_id, index_id);
+    for account in [account(1, 0), account(2, 0), account(3, 0)] {
+        assert_eq!(
+            icrc1_balance_of(env, ledger_id, account),
+            icrc1_balance_of(env, index_id, account)
+        );
+    }
+
+    let txs = get_account_transactions(env, index_id, account(3, 0), None, u64::MAX).transactions;
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].transaction.kind, "batch_xfer");
+    let batch_transfer = txs[0].transaction.transfer.as_ref().unwrap();
+    assert_eq!(batch_transfer.from, account(1, 0));
+    assert_eq!(batch_transfer.to, account(3, 0));
+    assert_eq!(batch_transfer.amount, Nat::from(200_000u64));
+    assert_eq!(batch_transfer.fee, Some(Nat::from(FEE)));
+
+    let ids = |kinds: Vec<TransactionKind>| -> Vec<u64> {
+        get_filtered_account_transactions(
+            env,
+            index_id,
+            account(1, 0),
+            None,
+            u64::MAX,
+            Some(TransactionFilter {
+                kinds: Some(kinds),
+                ..Default::default()
+            }),
+        )
+        .expect("Failed to perform GetAccountTransactionsArgs")
+        .transactions
+        .into_iter()
+        .map(|tx| tx.id.0.to_u64().unwrap())
+        .collect()
+    };
+    assert_eq!(ids(vec![TransactionKind::BatchTransfer]), vec![2, 1]);
+    assert_eq!(ids(vec![TransactionKind::Transfer]), vec![3]);
+}
+
 #[track_caller]
 fn assert_contain_same_elements<T: Debug + Eq + Hash>(vl: Vec<T>, vr: Vec<T>) {
     assert_eq!(
@@ -1372,6 +1668,7 @@ mod metrics {
         InitArg {
             ledger_id,
             retrieve_blocks_from_ledger_interval_seconds: None,
+            bootstrap_from_ledger_snapshot: None,
         }
     }
 }
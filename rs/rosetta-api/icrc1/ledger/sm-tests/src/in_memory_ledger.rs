@@ -5,7 +5,7 @@ use ic_agent::identity::Identity;
 use ic_base_types::CanisterId;
 use ic_icrc1::Operation;
 use ic_icrc1_test_utils::{ArgWithCaller, LedgerEndpointArg};
-use ic_ledger_core::approvals::Allowance;
+use ic_ledger_core::approvals::{Allowance, MAX_REVOKED_APPROVALS};
 use ic_ledger_core::timestamp::TimeStamp;
 use ic_ledger_core::tokens::{TokensType, Zero};
 use ic_state_machine_tests::StateMachine;
@@ -53,6 +53,8 @@ trait InMemoryLedgerState {
         index: usize,
     );
     fn process_mint(&mut self, to: &Self::AccountId, amount: &Self::Tokens);
+    fn process_revoke_all_approvals(&mut self, from: &Self::AccountId, fee: &Option<Self::Tokens>);
+    fn process_set_spending_policy(&mut self, from: &Self::AccountId, fee: &Option<Self::Tokens>);
     fn process_transfer(
         &mut self,
         from: &Self::AccountId,
@@ -298,6 +300,36 @@ where
             .unwrap_or_else(|| panic!("Total supply overflow"));
     }
 
+    fn process_revoke_all_approvals(&mut self, from: &Self::AccountId, fee: &Option<Self::Tokens>) {
+        self.burn_fee(from, fee);
+        // Like the ledger, revoke at most MAX_REVOKED_APPROVALS allowances: first those of
+        // the spenders greater than `from` in ascending order, then the smaller ones in
+        // descending order.
+        let mut spenders: Vec<AccountId> = self
+            .allowances
+            .keys()
+            .map(|key| key.clone().into())
+            .filter(|(account, _spender)| account == from)
+            .map(|(_account, spender)| spender)
+            .collect();
+        spenders.sort();
+        let split = spenders.partition_point(|spender| spender < from);
+        let (before, after) = spenders.split_at(split);
+        for spender in after
+            .iter()
+            .chain(before.iter().rev())
+            .take(MAX_REVOKED_APPROVALS)
+        {
+            self.allowances.remove(&K::from((from, spender)));
+        }
+    }
+
+    fn process_set_spending_policy(&mut self, from: &Self::AccountId, fee: &Option<Self::Tokens>) {
+        // Spending policies only restrict how an existing allowance may be used,
+        // so the only effect on balances and allowances is the fee.
+        self.burn_fee(from, fee);
+    }
+
     fn collect_fee(&mut self, from: &AccountId, amount: &Option<Tokens>) {
         if let Some(amount) = amount {
             self.decrease_balance(from, amount);
@@ -380,6 +412,18 @@ impl InMemoryLedger<ApprovalKey, Account, Tokens> {
                     &fee.or(block.effective_fee),
                     TimeStamp::from_nanos_since_unix_epoch(block.timestamp),
                 ),
+                Operation::SetSpendingPolicy { from, fee, .. } => {
+                    state.process_set_spending_policy(from, &fee.or(block.effective_fee))
+                }
+                Operation::RevokeAllApprovals { from, fee } => {
+                    state.process_revoke_all_approvals(from, &fee.or(block.effective_fee))
+                }
+                Operation::BatchTransfer {
+                    from,
+                    to,
+                    amount,
+                    fee,
+                } => state.process_transfer(from, to, &None, amount, &fee.or(block.effective_fee)),
             }
             state.validate_invariants();
         }
@@ -24,6 +24,10 @@ use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg, TransferError};
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::spending_policy::{
+    PeriodCap, RevokeAllApprovalsArgs, RevokeAllApprovalsError, SetSpendingPolicyArgs,
+    SetSpendingPolicyError, SpendingPolicy,
+};
 use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
 use icrc_ledger_types::icrc21::errors::ErrorInfo;
 use icrc_ledger_types::icrc21::errors::Icrc21Error;
@@ -42,6 +46,7 @@ use icrc_ledger_types::icrc3::transactions::GetTransactionsResponse;
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
 use icrc_ledger_types::icrc3::transactions::TransactionRange;
 use icrc_ledger_types::icrc3::transactions::Transfer;
+use icrc_ledger_types::icrc4::batch_transfer::{TransferBatchArgs, TransferBatchResult};
 use num_traits::ToPrimitive;
 use proptest::prelude::*;
 use proptest::test_runner::{Config as TestRunnerConfig, TestCaseResult, TestRunner};
@@ -232,6 +237,29 @@ fn send_transfer(
     .map(|n| n.0.to_u64().unwrap())
 }
 
+fn send_transfer_batch(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: Principal,
+    args: &TransferBatchArgs,
+) -> Vec<Result<BlockIndex, TransferError>> {
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(from),
+            ledger,
+            "icrc4_transfer_batch",
+            Encode!(args).unwrap()
+        )
+        .expect("failed to execute a transfer batch")
+        .bytes(),
+        Vec<TransferBatchResult>
+    )
+    .expect("failed to decode icrc4_transfer_batch response")
+    .into_iter()
+    .map(|r| r.map(|n| n.0.to_u64().unwrap()))
+    .collect()
+}
+
 pub fn system_time_to_nanos(t: SystemTime) -> u64 {
     t.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_nanos() as u64
 }
@@ -614,6 +642,69 @@ pub fn get_allowance(
     .expect("failed to decode allowance response")
 }
 
+pub fn send_set_spending_policy(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: Principal,
+    arg: &SetSpendingPolicyArgs,
+) -> Result<BlockIndex, SetSpendingPolicyError> {
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(from),
+            ledger,
+            "set_spending_policy",
+            Encode!(arg)
+            .unwrap()
+        )
+        .expect("failed to set the spending policy")
+        .bytes(),
+        Result<Nat, SetSpendingPolicyError>
+    )
+    .expect("failed to decode set_spending_policy response")
+    .map(|n| n.0.to_u64().unwrap())
+}
+
+pub fn send_revoke_all_approvals(
+    env: &StateMachine,
+    ledger: CanisterId,
+    from: Principal,
+    arg: &RevokeAllApprovalsArgs,
+) -> Result<BlockIndex, RevokeAllApprovalsError> {
+    Decode!(
+        &env.execute_ingress_as(
+            PrincipalId(from),
+            ledger,
+            "revoke_all_approvals",
+            Encode!(arg)
+            .unwrap()
+        )
+        .expect("failed to revoke all approvals")
+        .bytes(),
+        Result<Nat, RevokeAllApprovalsError>
+    )
+    .expect("failed to decode revoke_all_approvals response")
+    .map(|n| n.0.to_u64().unwrap())
+}
+
+pub fn get_spending_policy(
+    env: &StateMachine,
+    ledger: CanisterId,
+    account: impl Into<Account>,
+    spender: impl Into<Account>,
+) -> Option<SpendingPolicy> {
+    let arg = AllowanceArgs {
+        account: account.into(),
+        spender: spender.into(),
+    };
+    Decode!(
+        &env.query(ledger, "spending_policy", Encode!(&arg).unwrap())
+            .expect("failed to query the spending policy")
+            .bytes(),
+        Option<SpendingPolicy>
+    )
+    .expect("failed to decode spending_policy response")
+}
+
 fn arb_amount() -> impl Strategy<Value = Tokens> {
     any::<u64>().prop_map(|n| Tokens::try_from(Nat::from(n)).unwrap())
 }
@@ -949,7 +1040,10 @@ where
         standards.push(standard.name);
     }
     standards.sort();
-    assert_eq!(standards, vec!["ICRC-1", "ICRC-2", "ICRC-21", "ICRC-3"]);
+    assert_eq!(
+        standards,
+        vec!["ICRC-1", "ICRC-2", "ICRC-21", "ICRC-3", "ICRC-4"]
+    );
 }
 
 pub fn test_total_supply<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
@@ -1003,6 +1097,184 @@ where
     assert_eq!(6_000_000u64, balance_of(&env, canister_id, p2.0));
 }
 
+pub fn test_icrc4_transfer_batch<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let p1 = PrincipalId::new_user_test_id(1);
+    let p2 = PrincipalId::new_user_test_id(2);
+    let p3 = PrincipalId::new_user_test_id(3);
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(p1.0), 1_000_000)],
+    );
+    let now = system_time_to_nanos(env.time());
+
+    let to_p2 = TransferArg {
+        from_subaccount: None,
+        to: p2.0.into(),
+        fee: None,
+        amount: Nat::from(100_000u32),
+        created_at_time: Some(now),
+        memo: None,
+    };
+    let results = send_transfer_batch(
+        &env,
+        canister_id,
+        p1.0,
+        &vec![
+            to_p2.clone(),
+            TransferArg {
+                to: p3.0.into(),
+                ..to_p2.clone()
+            },
+            // Same as the first entry => deduplicated.
+            to_p2.clone(),
+            TransferArg {
+                to: p3.0.into(),
+                amount: Nat::from(10_000_000u32),
+                created_at_time: None,
+                ..to_p2.clone()
+            },
+            // Burn.
+            TransferArg {
+                to: MINTER,
+                created_at_time: None,
+                ..to_p2.clone()
+            },
+        ],
+    );
+    assert_eq!(
+        results,
+        vec![
+            Ok(1),
+            Ok(2),
+            Err(TransferError::Duplicate {
+                duplicate_of: Nat::from(1u64)
+            }),
+            Err(TransferError::InsufficientFunds {
+                balance: Nat::from(1_000_000u64 - 2 * (100_000 + FEE))
+            }),
+            Ok(3),
+        ]
+    );
+    assert_eq!(100_000, balance_of(&env, canister_id, p2.0));
+    assert_eq!(100_000, balance_of(&env, canister_id, p3.0));
+    assert_eq!(
+        1_000_000 - 3 * 100_000 - 2 * FEE,
+        balance_of(&env, canister_id, p1.0)
+    );
+
+    // Entries of a batch are deduplicated against earlier batch entries, but not against
+    // single transfers, since their operations differ.
+    assert_eq!(
+        send_transfer_batch(&env, canister_id, p1.0, &vec![to_p2.clone()]),
+        vec![Err(TransferError::Duplicate {
+            duplicate_of: Nat::from(1u64)
+        })]
+    );
+    assert_eq!(send_transfer(&env, canister_id, p1.0, &to_p2), Ok(4));
+
+    // Transfer entries are recorded as batch_xfer blocks, mints and burns as regular
+    // ICRC-1 blocks.
+    let blocks = get_all_ledger_and_archive_blocks(&env, canister_id);
+    assert_eq!(blocks.len(), 5);
+    assert_eq!(
+        blocks[1].transaction.operation,
+        Operation::BatchTransfer {
+            from: Account::from(p1.0),
+            to: Account::from(p2.0),
+            amount: Tokens::from(100_000u64),
+            fee: None,
+        }
+    );
+    assert_eq!(blocks[1].effective_fee, Some(Tokens::from(FEE)));
+    assert!(matches!(
+        blocks[2].transaction.operation,
+        Operation::BatchTransfer { to, .. } if to == Account::from(p3.0)
+    ));
+    assert!(matches!(
+        blocks[3].transaction.operation,
+        Operation::Burn { spender: None, .. }
+    ));
+    assert!(matches!(
+        blocks[4].transaction.operation,
+        Operation::Transfer { to, spender: None, .. } if to == Account::from(p2.0)
+    ));
+
+    // The batch entries are exposed with the batch_xfer kind through the candid endpoints.
+    let txs = get_transactions(&env, canister_id.get().0, 1, 2).transactions;
+    assert_eq!(txs[0].kind, "batch_xfer");
+    assert_eq!(
+        txs[0].transfer.as_ref().map(|transfer| transfer.to),
+        Some(Account::from(p2.0))
+    );
+    assert_eq!(
+        txs[0].transfer.as_ref().and_then(|t| t.fee.clone()),
+        Some(Nat::from(FEE))
+    );
+    assert_eq!(
+        Transaction::<Tokens>::try_from(txs[1].clone()).map(|tx| tx.operation),
+        Ok(blocks[2].transaction.operation.clone())
+    );
+
+    let max_batch_size = Decode!(
+        &env.query(
+            canister_id,
+            "icrc4_maximum_update_batch_size",
+            Encode!().unwrap()
+        )
+        .expect("failed to query the maximum batch size")
+        .bytes(),
+        Option<Nat>
+    )
+    .expect("failed to decode icrc4_maximum_update_batch_size response")
+    .expect("the ledger should limit the batch size")
+    .0
+    .to_usize()
+    .unwrap();
+    let err = env
+        .execute_ingress_as(
+            p1,
+            canister_id,
+            "icrc4_transfer_batch",
+            Encode!(&vec![to_p2.clone(); max_batch_size + 1]).unwrap(),
+        )
+        .unwrap_err();
+    err.assert_contains(ErrorCode::CanisterCalledTrap, "above the allowed limit");
+
+    // A memo that is too long in a later entry rejects the batch before any entry is applied.
+    let err = env
+        .execute_ingress_as(
+            p1,
+            canister_id,
+            "icrc4_transfer_batch",
+            Encode!(&vec![
+                TransferArg {
+                    created_at_time: None,
+                    ..to_p2.clone()
+                },
+                TransferArg {
+                    created_at_time: None,
+                    memo: Some(Memo::from(vec![0u8; 33])),
+                    ..to_p2
+                },
+            ])
+            .unwrap(),
+        )
+        .unwrap_err();
+    err.assert_contains(
+        ErrorCode::CanisterCalledTrap,
+        "the memo field size of 33 bytes of transfer 1",
+    );
+    assert_eq!(200_000, balance_of(&env, canister_id, p2.0));
+    assert_eq!(
+        get_all_ledger_and_archive_blocks(&env, canister_id).len(),
+        5
+    );
+}
+
 pub fn test_tx_deduplication<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
 where
     T: CandidType,
@@ -3415,6 +3687,305 @@ where
     assert_eq!(total_supply(&env, canister_id), 60_000);
 }
 
+pub fn default_set_spending_policy_args(spender: impl Into<Account>) -> SetSpendingPolicyArgs {
+    SetSpendingPolicyArgs {
+        from_subaccount: None,
+        spender: spender.into(),
+        period_cap: None,
+        allowed_recipients: None,
+        fee: Some(Nat::from(FEE)),
+        memo: None,
+        created_at_time: None,
+    }
+}
+
+pub fn test_set_spending_policy<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    const PERIOD: Duration = Duration::from_secs(60 * 60);
+
+    let from = PrincipalId::new_user_test_id(1);
+    let spender = PrincipalId::new_user_test_id(2);
+    let to = PrincipalId::new_user_test_id(3);
+    let other = PrincipalId::new_user_test_id(4);
+
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![(Account::from(from.0), 1_000_000)],
+    );
+
+    let policy_args = SetSpendingPolicyArgs {
+        period_cap: Some(PeriodCap {
+            amount: Nat::from(100_000u32),
+            period_nanos: PERIOD.as_nanos() as u64,
+        }),
+        allowed_recipients: Some(vec![to.0.into()]),
+        ..default_set_spending_policy_args(spender.0)
+    };
+
+    // A policy can only be attached to an existing approval.
+    assert_eq!(
+        send_set_spending_policy(&env, canister_id, from.0, &policy_args),
+        Err(SetSpendingPolicyError::ApprovalDoesNotExist)
+    );
+    send_approval(
+        &env,
+        canister_id,
+        from.0,
+        &default_approve_args(spender.0, 500_000),
+    )
+    .expect("approval failed");
+    assert_eq!(
+        get_spending_policy(&env, canister_id, from.0, spender.0),
+        None
+    );
+
+    assert_eq!(
+        send_set_spending_policy(
+            &env,
+            canister_id,
+            from.0,
+            &SetSpendingPolicyArgs {
+                period_cap: Some(PeriodCap {
+                    amount: Nat::from(100_000u32),
+                    period_nanos: 0,
+                }),
+                ..policy_args.clone()
+            }
+        ),
+        Err(SetSpendingPolicyError::InvalidPeriod)
+    );
+    assert_eq!(
+        send_set_spending_policy(
+            &env,
+            canister_id,
+            from.0,
+            &SetSpendingPolicyArgs {
+                allowed_recipients: Some(
+                    (0..101)
+                        .map(|i| Account::from(PrincipalId::new_user_test_id(100 + i).0))
+                        .collect()
+                ),
+                ..policy_args.clone()
+            }
+        ),
+        Err(SetSpendingPolicyError::TooManyAllowedRecipients {
+            max_allowed_recipients: Nat::from(100u32)
+        })
+    );
+    assert_eq!(
+        send_set_spending_policy(
+            &env,
+            canister_id,
+            from.0,
+            &SetSpendingPolicyArgs {
+                fee: Some(Nat::from(FEE + 1)),
+                ..policy_args.clone()
+            }
+        ),
+        Err(SetSpendingPolicyError::BadFee {
+            expected_fee: Nat::from(FEE)
+        })
+    );
+
+    assert_eq!(
+        send_set_spending_policy(&env, canister_id, from.0, &policy_args),
+        Ok(2)
+    );
+    // `from` paid 2 fees (approval and spending policy).
+    assert_eq!(balance_of(&env, canister_id, from.0), 1_000_000 - 2 * FEE);
+    let policy = get_spending_policy(&env, canister_id, from.0, spender.0)
+        .expect("the spending policy is missing");
+    assert_eq!(policy.period_cap, policy_args.period_cap);
+    assert_eq!(policy.allowed_recipients, Some(vec![to.0.into()]));
+    assert_eq!(policy.spent_in_period, Nat::from(0u8));
+
+    // The spender cannot send tokens to other recipients.
+    let err = send_transfer_from(
+        &env,
+        canister_id,
+        spender.0,
+        &default_transfer_from_args(from.0, other.0, 10_000),
+    )
+    .unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            TransferFromError::GenericError { error_code, .. } if *error_code == Nat::from(1u8)
+        ),
+        "unexpected error: {:?}",
+        err
+    );
+
+    // The spender can use the cap, including fees, within the period.
+    send_transfer_from(
+        &env,
+        canister_id,
+        spender.0,
+        &default_transfer_from_args(from.0, to.0, 50_000),
+    )
+    .expect("transfer_from failed");
+    assert_eq!(
+        get_spending_policy(&env, canister_id, from.0, spender.0)
+            .unwrap()
+            .spent_in_period,
+        Nat::from(50_000 + FEE)
+    );
+    assert_eq!(
+        send_transfer_from(
+            &env,
+            canister_id,
+            spender.0,
+            &default_transfer_from_args(from.0, to.0, 50_000),
+        ),
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(100_000 - 50_000 - FEE)
+        })
+    );
+
+    // The cap is available again in the next period.
+    env.advance_time(PERIOD);
+    send_transfer_from(
+        &env,
+        canister_id,
+        spender.0,
+        &default_transfer_from_args(from.0, to.0, 50_000),
+    )
+    .expect("transfer_from failed");
+    assert_eq!(balance_of(&env, canister_id, to.0), 100_000);
+    assert_eq!(
+        get_allowance(&env, canister_id, from.0, spender.0).allowance,
+        Nat::from(500_000 - 2 * (50_000 + FEE))
+    );
+
+    // Setting neither a cap nor recipients removes the policy.
+    send_set_spending_policy(
+        &env,
+        canister_id,
+        from.0,
+        &default_set_spending_policy_args(spender.0),
+    )
+    .expect("failed to remove the spending policy");
+    assert_eq!(
+        get_spending_policy(&env, canister_id, from.0, spender.0),
+        None
+    );
+    send_transfer_from(
+        &env,
+        canister_id,
+        spender.0,
+        &default_transfer_from_args(from.0, other.0, 100_000),
+    )
+    .expect("transfer_from failed");
+}
+
+pub fn test_revoke_all_approvals<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
+where
+    T: CandidType,
+{
+    let from = PrincipalId::new_user_test_id(1);
+    let other = PrincipalId::new_user_test_id(2);
+    let spenders: Vec<_> = (10..13).map(PrincipalId::new_user_test_id).collect();
+
+    let (env, canister_id) = setup(
+        ledger_wasm,
+        encode_init_args,
+        vec![
+            (Account::from(from.0), 1_000_000),
+            (Account::from(other.0), 1_000_000),
+        ],
+    );
+
+    for spender in &spenders {
+        send_approval(
+            &env,
+            canister_id,
+            from.0,
+            &default_approve_args(spender.0, 100_000),
+        )
+        .expect("approval failed");
+    }
+    send_approval(
+        &env,
+        canister_id,
+        other.0,
+        &default_approve_args(spenders[0].0, 100_000),
+    )
+    .expect("approval failed");
+    send_set_spending_policy(
+        &env,
+        canister_id,
+        from.0,
+        &SetSpendingPolicyArgs {
+            allowed_recipients: Some(vec![other.0.into()]),
+            ..default_set_spending_policy_args(spenders[0].0)
+        },
+    )
+    .expect("failed to set the spending policy");
+
+    let revoke_args = RevokeAllApprovalsArgs {
+        from_subaccount: None,
+        fee: Some(Nat::from(FEE)),
+        memo: None,
+        created_at_time: None,
+    };
+    assert_eq!(
+        send_revoke_all_approvals(
+            &env,
+            canister_id,
+            from.0,
+            &RevokeAllApprovalsArgs {
+                fee: Some(Nat::from(FEE + 1)),
+                ..revoke_args.clone()
+            }
+        ),
+        Err(RevokeAllApprovalsError::BadFee {
+            expected_fee: Nat::from(FEE)
+        })
+    );
+    assert_eq!(
+        send_revoke_all_approvals(&env, canister_id, from.0, &revoke_args),
+        Ok(7)
+    );
+    // `from` paid 5 fees (3 approvals, the spending policy and the revocation).
+    assert_eq!(balance_of(&env, canister_id, from.0), 1_000_000 - 5 * FEE);
+    for spender in &spenders {
+        assert_eq!(
+            get_allowance(&env, canister_id, from.0, spender.0).allowance,
+            Nat::from(0u8)
+        );
+    }
+    assert_eq!(
+        get_spending_policy(&env, canister_id, from.0, spenders[0].0),
+        None
+    );
+    assert_eq!(
+        send_transfer_from(
+            &env,
+            canister_id,
+            spenders[0].0,
+            &default_transfer_from_args(from.0, other.0, 10_000),
+        ),
+        Err(TransferFromError::InsufficientAllowance {
+            allowance: Nat::from(0u8)
+        })
+    );
+    // The approvals of other accounts are untouched.
+    assert_eq!(
+        get_allowance(&env, canister_id, other.0, spenders[0].0).allowance,
+        Nat::from(100_000u32)
+    );
+
+    // Revoking without approvals only charges the fee.
+    assert_eq!(
+        send_revoke_all_approvals(&env, canister_id, from.0, &revoke_args),
+        Ok(8)
+    );
+    assert_eq!(balance_of(&env, canister_id, from.0), 1_000_000 - 6 * FEE);
+}
+
 pub fn test_balances_overflow<T>(ledger_wasm: Vec<u8>, encode_init_args: fn(InitArgs) -> T)
 where
     T: CandidType,
@@ -0,0 +1,96 @@
+//! Calls with a bounded-wait (best-effort) response.
+//!
+//! The calls of `ic_cdk` 0.13 are guaranteed-response calls: the ledger keeps a
+//! call context open until the callee responds, so a callee that never responds
+//! prevents the ledger from stopping and thus from being upgraded. The system
+//! answers a bounded-wait call at the latest when its timeout expires.
+
+use candid::utils::{encode_args, ArgumentEncoder};
+use candid::Principal;
+use ic_cdk::api::call::RejectionCode;
+
+#[cfg(target_arch = "wasm32")]
+mod ic0 {
+    #[link(wasm_import_module = "ic0")]
+    extern "C" {
+        pub fn call_new(
+            callee_src: usize,
+            callee_size: usize,
+            name_src: usize,
+            name_size: usize,
+            reply_fun: usize,
+            reply_env: usize,
+            reject_fun: usize,
+            reject_env: usize,
+        );
+        pub fn call_data_append(src: usize, size: usize);
+        pub fn call_with_best_effort_response(timeout_seconds: u32);
+        pub fn call_perform() -> u32;
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(clippy::too_many_arguments)]
+mod ic0 {
+    fn wrong_arch(method: &str) -> ! {
+        panic!("{} only works in a canister", method)
+    }
+
+    pub unsafe fn call_new(
+        _callee_src: usize,
+        _callee_size: usize,
+        _name_src: usize,
+        _name_size: usize,
+        _reply_fun: usize,
+        _reply_env: usize,
+        _reject_fun: usize,
+        _reject_env: usize,
+    ) {
+        wrong_arch("call_new")
+    }
+    pub unsafe fn call_data_append(_src: usize, _size: usize) {
+        wrong_arch("call_data_append")
+    }
+    pub unsafe fn call_with_best_effort_response(_timeout_seconds: u32) {
+        wrong_arch("call_with_best_effort_response")
+    }
+    pub unsafe fn call_perform() -> u32 {
+        wrong_arch("call_perform")
+    }
+}
+
+/// Calls `method` of `callee` with `args` and a bounded-wait response that
+/// times out after `timeout_seconds`. Like `ic_cdk::api::call::notify`, the
+/// response is ignored: the callbacks point to an invalid table index.
+///
+/// Traps if the subnet does not support bounded-wait calls.
+pub fn notify_with_bounded_wait<T: ArgumentEncoder>(
+    callee: Principal,
+    method: &str,
+    args: T,
+    timeout_seconds: u32,
+) -> Result<(), RejectionCode> {
+    let args = encode_args(args).expect("failed to encode the arguments");
+    let callee = callee.as_slice();
+    // SAFETY: the pointers and sizes come from live slices, and the call is
+    // performed before they are dropped.
+    let code = unsafe {
+        ic0::call_new(
+            callee.as_ptr() as usize,
+            callee.len(),
+            method.as_ptr() as usize,
+            method.len(),
+            usize::MAX,
+            usize::MAX,
+            usize::MAX,
+            usize::MAX,
+        );
+        ic0::call_data_append(args.as_ptr() as usize, args.len());
+        ic0::call_with_best_effort_response(timeout_seconds);
+        ic0::call_perform()
+    };
+    match code {
+        0 => Ok(()),
+        code => Err(RejectionCode::from(code)),
+    }
+}
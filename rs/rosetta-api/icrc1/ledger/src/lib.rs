@@ -8,10 +8,14 @@ use candid::{
     types::number::{Int, Nat},
     CandidType, Principal,
 };
-use ic_base_types::PrincipalId;
+use ic_base_types::{PrincipalId, PrincipalIdClass};
 use ic_canister_log::{log, Sink};
 use ic_crypto_tree_hash::{Label, MixedHashTree};
 use ic_icrc1::blocks::encoded_block_to_generic_block;
+use ic_icrc1::snapshot::{
+    self, LedgerSnapshotInfo, SnapshotAllowance, SnapshotAllowances, SnapshotBalance,
+    SnapshotBalances,
+};
 use ic_icrc1::{Block, LedgerAllowances, LedgerBalances, Transaction};
 use ic_ledger_canister_core::archive::Archive;
 pub use ic_ledger_canister_core::archive::ArchiveOptions;
@@ -23,7 +27,7 @@ use ic_ledger_canister_core::{
     range_utils,
 };
 use ic_ledger_core::{
-    approvals::{AllowanceTable, HeapAllowancesData},
+    approvals::{Allowance, AllowanceTable, HeapAllowancesData},
     balances::Balances,
     block::{BlockIndex, BlockType, EncodedBlock, FeeCollector},
     timestamp::TimeStamp,
@@ -31,7 +35,7 @@ use ic_ledger_core::{
 };
 use ic_ledger_hash_of::HashOf;
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
-use ic_stable_structures::DefaultMemoryImpl;
+use ic_stable_structures::{DefaultMemoryImpl, StableLog};
 use icrc_ledger_types::icrc3::transactions::Transaction as Tx;
 use icrc_ledger_types::icrc3::{blocks::GetBlocksResponse, transactions::GetTransactionsResponse};
 use icrc_ledger_types::{
@@ -41,12 +45,13 @@ use icrc_ledger_types::{
 use icrc_ledger_types::{
     icrc::generic_value::ICRC3Value,
     icrc1::account::Account,
+    icrc1::transfer_notification::RegisterTransferNotificationError,
     icrc3::{
         archive::{GetArchivesArgs, GetArchivesResult, ICRC3ArchiveInfo, QueryArchiveFn},
         blocks::{ArchivedBlocks, GetBlocksRequest, GetBlocksResult},
     },
 };
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use std::borrow::Cow;
 use std::cell::RefCell;
@@ -64,6 +69,13 @@ const MAX_TRANSACTIONS_IN_WINDOW: usize = 3_000_000;
 const MAX_TRANSACTIONS_TO_PURGE: usize = 100_000;
 
 const DEFAULT_MAX_MEMO_LENGTH: u16 = 32;
+/// The maximum number of snapshot entries returned by a single request.
+const MAX_SNAPSHOT_ENTRIES_PER_REQUEST: u64 = 10_000;
+/// The maximum number of accounts of a single canister with a registered
+/// transfer notification. Only canisters can register, so the total number of
+/// registrations is bounded by the number of canisters times this limit.
+const MAX_TRANSFER_NOTIFICATIONS_PER_OWNER: usize = 10;
+const MAX_NOTIFICATION_METHOD_NAME_LENGTH: usize = 100;
 
 #[derive(Clone, Debug)]
 pub struct Icrc1ArchiveWasm;
@@ -320,6 +332,10 @@ pub struct UpgradeArgs {
     pub accounts_overflow_trim_quantity: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub change_archive_options: Option<ChangeArchiveOptions>,
+    /// If true, the ledger replaces its snapshot with one of the balances and
+    /// allowances at the current chain length.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_snapshot: Option<bool>,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
@@ -330,6 +346,13 @@ pub enum LedgerArgument {
 }
 
 const UPGRADES_MEMORY_ID: MemoryId = MemoryId::new(0);
+const SNAPSHOT_BALANCES_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
+const SNAPSHOT_BALANCES_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
+const SNAPSHOT_ALLOWANCES_INDEX_MEMORY_ID: MemoryId = MemoryId::new(3);
+const SNAPSHOT_ALLOWANCES_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+
+type VM = VirtualMemory<DefaultMemoryImpl>;
+type SnapshotLog = StableLog<Vec<u8>, VM, VM>;
 
 thread_local! {
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
@@ -339,6 +362,70 @@ thread_local! {
     // The memory where the ledger must write and read its state during an upgrade.
     pub static UPGRADES_MEMORY: RefCell<VirtualMemory<DefaultMemoryImpl>> = MEMORY_MANAGER.with(|memory_manager|
         RefCell::new(memory_manager.borrow().get(UPGRADES_MEMORY_ID)));
+
+    // The CBOR-encoded entries of the ledger snapshot. They live in stable memory so that
+    // they are neither copied to the heap nor serialized on upgrades.
+    static SNAPSHOT_BALANCES: RefCell<SnapshotLog> = RefCell::new(
+        init_snapshot_log(SNAPSHOT_BALANCES_INDEX_MEMORY_ID, SNAPSHOT_BALANCES_DATA_MEMORY_ID)
+    );
+
+    static SNAPSHOT_ALLOWANCES: RefCell<SnapshotLog> = RefCell::new(
+        init_snapshot_log(SNAPSHOT_ALLOWANCES_INDEX_MEMORY_ID, SNAPSHOT_ALLOWANCES_DATA_MEMORY_ID)
+    );
+}
+
+fn snapshot_memory(memory_id: MemoryId) -> VM {
+    MEMORY_MANAGER.with(|memory_manager| memory_manager.borrow().get(memory_id))
+}
+
+fn init_snapshot_log(index_memory_id: MemoryId, data_memory_id: MemoryId) -> SnapshotLog {
+    SnapshotLog::init(
+        snapshot_memory(index_memory_id),
+        snapshot_memory(data_memory_id),
+    )
+    .expect("failed to initialize the snapshot log")
+}
+
+/// Replaces the content of `log` with the CBOR encoding of the given entries
+/// and returns the number of entries written.
+fn write_snapshot_log<T: Serialize>(
+    log: &'static std::thread::LocalKey<RefCell<SnapshotLog>>,
+    index_memory_id: MemoryId,
+    data_memory_id: MemoryId,
+    entries: impl Iterator<Item = T>,
+) -> u64 {
+    log.with_borrow_mut(|log| {
+        *log = SnapshotLog::new(
+            snapshot_memory(index_memory_id),
+            snapshot_memory(data_memory_id),
+        );
+        for entry in entries {
+            let mut buf = vec![];
+            ciborium::ser::into_writer(&entry, &mut buf)
+                .expect("bug: failed to encode a snapshot entry");
+            log.append(&buf)
+                .expect("failed to append an entry to the snapshot log");
+        }
+        log.len()
+    })
+}
+
+/// Decodes the entries of `log` in the range `[start, start + length)`.
+fn read_snapshot_log<T: DeserializeOwned>(
+    log: &'static std::thread::LocalKey<RefCell<SnapshotLog>>,
+    start: u64,
+    length: u64,
+) -> Vec<T> {
+    log.with_borrow(|log| {
+        let end = start.saturating_add(length).min(log.len());
+        (start..end)
+            .map(|idx| {
+                let entry = log.get(idx).expect("bug: missing snapshot entry");
+                ciborium::de::from_reader(&entry[..])
+                    .unwrap_or_else(|err| panic!("bug: invalid snapshot entry {}: {}", idx, err))
+            })
+            .collect()
+    })
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -372,6 +459,92 @@ pub struct Ledger<Tokens: TokensType> {
     maximum_number_of_accounts: usize,
     #[serde(default = "default_accounts_overflow_trim_quantity")]
     accounts_overflow_trim_quantity: usize,
+
+    #[serde(default)]
+    snapshot: Option<LedgerSnapshot<Tokens>>,
+
+    /// The methods to notify after each block that credits an account.
+    #[serde(default)]
+    transfer_notifications: BTreeMap<Account, String>,
+}
+
+/// A copy of the balances and of the allowances of the ledger after applying
+/// the first `num_blocks` blocks. Index canisters use it to bootstrap without
+/// replaying the whole chain. The entries are stored in stable memory, see
+/// [SNAPSHOT_BALANCES] and [SNAPSHOT_ALLOWANCES].
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct LedgerSnapshot<Tokens: TokensType> {
+    num_blocks: u64,
+    last_block_hash: Option<HashOf<EncodedBlock>>,
+    timestamp: TimeStamp,
+    total_supply: Tokens,
+    transfer_fee: Tokens,
+    fee_collector: Option<FeeCollector<Account>>,
+    num_balances: u64,
+    num_allowances: u64,
+    balances_hash: snapshot::Hash,
+    allowances_hash: snapshot::Hash,
+    state_hash: snapshot::Hash,
+}
+
+impl<Tokens: TokensType> LedgerSnapshot<Tokens> {
+    pub fn info(&self) -> LedgerSnapshotInfo {
+        LedgerSnapshotInfo {
+            num_blocks: self.num_blocks,
+            last_block_hash: self
+                .last_block_hash
+                .map(|hash| ByteBuf::from(hash.as_slice().to_vec())),
+            timestamp: self.timestamp.as_nanos_since_unix_epoch(),
+            num_balances: self.num_balances,
+            num_allowances: self.num_allowances,
+            total_supply: self.total_supply.clone().into(),
+            transfer_fee: self.transfer_fee.clone().into(),
+            fee_collector: self.fee_collector.as_ref().map(|fc| fc.fee_collector),
+            fee_collector_block_index: self.fee_collector.as_ref().and_then(|fc| fc.block_index),
+            balances_hash: ByteBuf::from(self.balances_hash.to_vec()),
+            allowances_hash: ByteBuf::from(self.allowances_hash.to_vec()),
+            state_hash: ByteBuf::from(self.state_hash.to_vec()),
+        }
+    }
+
+    pub fn balances(&self, start: u64, length: u64) -> SnapshotBalances {
+        SnapshotBalances {
+            state_hash: ByteBuf::from(self.state_hash.to_vec()),
+            balances: read_snapshot_log::<(Account, Tokens)>(
+                &SNAPSHOT_BALANCES,
+                start,
+                length.min(MAX_SNAPSHOT_ENTRIES_PER_REQUEST),
+            )
+            .into_iter()
+            .map(|(account, balance)| SnapshotBalance {
+                account,
+                balance: balance.into(),
+            })
+            .collect(),
+        }
+    }
+
+    pub fn allowances(&self, start: u64, length: u64) -> SnapshotAllowances {
+        SnapshotAllowances {
+            state_hash: ByteBuf::from(self.state_hash.to_vec()),
+            allowances: read_snapshot_log::<((Account, Account), Allowance<Tokens>)>(
+                &SNAPSHOT_ALLOWANCES,
+                start,
+                length.min(MAX_SNAPSHOT_ENTRIES_PER_REQUEST),
+            )
+            .into_iter()
+            .map(|((account, spender), allowance)| SnapshotAllowance {
+                account,
+                spender,
+                allowance: allowance.amount.into(),
+                expires_at: allowance
+                    .expires_at
+                    .map(|expires_at| expires_at.as_nanos_since_unix_epoch()),
+            })
+            .collect(),
+        }
+    }
 }
 
 fn default_maximum_number_of_accounts() -> usize {
@@ -464,6 +637,8 @@ impl<Tokens: TokensType> Ledger<Tokens> {
                 .unwrap_or_else(|| ACCOUNTS_OVERFLOW_TRIM_QUANTITY.try_into().unwrap())
                 .try_into()
                 .unwrap(),
+            snapshot: None,
+            transfer_notifications: BTreeMap::new(),
         };
 
         for (account, balance) in initial_balances.into_iter() {
@@ -679,6 +854,132 @@ impl<Tokens: TokensType> Ledger<Tokens> {
                 change_archive_options.apply(archive);
             }
         }
+        if args.take_snapshot == Some(true) {
+            let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+            self.take_snapshot(now);
+            log!(
+                sink,
+                "[ledger] took a snapshot of {} balances at chain length {}",
+                self.balances.store.len(),
+                self.blockchain.chain_length()
+            );
+        }
+    }
+
+    /// Replaces the current snapshot with one of the balances and of the
+    /// non-expired allowances at the current chain length. The entries are
+    /// streamed to stable memory while they are hashed.
+    pub fn take_snapshot(&mut self, now: TimeStamp) {
+        let mut balances_hash = snapshot::EMPTY_ENTRIES_HASH;
+        let num_balances = write_snapshot_log(
+            &SNAPSHOT_BALANCES,
+            SNAPSHOT_BALANCES_INDEX_MEMORY_ID,
+            SNAPSHOT_BALANCES_DATA_MEMORY_ID,
+            self.balances.store.iter().map(|(account, balance)| {
+                balances_hash =
+                    snapshot::chain_balance(&balances_hash, account, &balance.clone().into());
+                (account, balance)
+            }),
+        );
+        let mut allowances_hash = snapshot::EMPTY_ENTRIES_HASH;
+        let num_allowances = write_snapshot_log(
+            &SNAPSHOT_ALLOWANCES,
+            SNAPSHOT_ALLOWANCES_INDEX_MEMORY_ID,
+            SNAPSHOT_ALLOWANCES_DATA_MEMORY_ID,
+            self.approvals
+                .iter()
+                .filter(|(_, allowance)| {
+                    allowance
+                        .expires_at
+                        .map_or(true, |expires_at| expires_at > now)
+                })
+                .map(|((account, spender), allowance)| {
+                    allowances_hash = snapshot::chain_allowance(
+                        &allowances_hash,
+                        account,
+                        spender,
+                        &allowance.amount.clone().into(),
+                        allowance
+                            .expires_at
+                            .map(|expires_at| expires_at.as_nanos_since_unix_epoch()),
+                    );
+                    ((account, spender), allowance)
+                }),
+        );
+        let num_blocks = self.blockchain.chain_length();
+        let last_block_hash = self.blockchain.last_hash;
+        let state_hash = snapshot::state_hash(
+            num_blocks,
+            last_block_hash.map(|hash| hash.into_bytes()).as_ref(),
+            &balances_hash,
+            &allowances_hash,
+        );
+
+        self.snapshot = Some(LedgerSnapshot {
+            num_blocks,
+            last_block_hash,
+            timestamp: now,
+            total_supply: self.balances.total_supply(),
+            transfer_fee: self.transfer_fee.clone(),
+            fee_collector: self.fee_collector.clone(),
+            num_balances,
+            num_allowances,
+            balances_hash,
+            allowances_hash,
+            state_hash,
+        });
+    }
+
+    pub fn snapshot(&self) -> Option<&LedgerSnapshot<Tokens>> {
+        self.snapshot.as_ref()
+    }
+
+    /// Registers `method` of the account owner to be notified after each
+    /// block that credits `account`, replacing any previous registration.
+    /// Only canisters can register, since only canisters can be notified and
+    /// a registration is free.
+    pub fn register_transfer_notification(
+        &mut self,
+        account: Account,
+        method: String,
+    ) -> Result<(), RegisterTransferNotificationError> {
+        if PrincipalId(account.owner).class() != Ok(PrincipalIdClass::Opaque) {
+            return Err(RegisterTransferNotificationError::CallerNotACanister);
+        }
+        if method.len() > MAX_NOTIFICATION_METHOD_NAME_LENGTH {
+            return Err(RegisterTransferNotificationError::MethodNameTooLong {
+                max_length: MAX_NOTIFICATION_METHOD_NAME_LENGTH as u64,
+            });
+        }
+        if !self.transfer_notifications.contains_key(&account) {
+            let owner_registrations = self
+                .transfer_notifications
+                .range(
+                    Account {
+                        owner: account.owner,
+                        subaccount: None,
+                    }..,
+                )
+                .take_while(|(registered, _)| registered.owner == account.owner)
+                .count();
+            if owner_registrations >= MAX_TRANSFER_NOTIFICATIONS_PER_OWNER {
+                return Err(
+                    RegisterTransferNotificationError::TooManyRegistrationsForOwner {
+                        limit: MAX_TRANSFER_NOTIFICATIONS_PER_OWNER as u64,
+                    },
+                );
+            }
+        }
+        self.transfer_notifications.insert(account, method);
+        Ok(())
+    }
+
+    pub fn unregister_transfer_notification(&mut self, account: &Account) {
+        self.transfer_notifications.remove(account);
+    }
+
+    pub fn transfer_notification(&self, account: &Account) -> Option<&String> {
+        self.transfer_notifications.get(account)
     }
 
     /// Returns the root hash of the certified ledger state.
@@ -692,15 +993,27 @@ impl<Tokens: TokensType> Ledger<Tokens> {
         match self.blockchain().last_hash {
             Some(hash) => {
                 let last_block_index = self.blockchain().chain_length().checked_sub(1).unwrap();
+                let tip_hash = MixedHashTree::Labeled(
+                    Label::from("tip_hash"),
+                    Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
+                );
+                // Labels must be sorted: last_block_index < snapshot_hash < tip_hash.
+                let right = match &self.snapshot {
+                    Some(snapshot) => MixedHashTree::Fork(Box::new((
+                        MixedHashTree::Labeled(
+                            Label::from("snapshot_hash"),
+                            Box::new(MixedHashTree::Leaf(snapshot.state_hash.to_vec())),
+                        ),
+                        tip_hash,
+                    ))),
+                    None => tip_hash,
+                };
                 MixedHashTree::Fork(Box::new((
                     MixedHashTree::Labeled(
                         Label::from("last_block_index"),
                         Box::new(MixedHashTree::Leaf(last_block_index.to_be_bytes().to_vec())),
                     ),
-                    MixedHashTree::Labeled(
-                        Label::from("tip_hash"),
-                        Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
-                    ),
+                    right,
                 )))
             }
             None => MixedHashTree::Empty,
@@ -1,9 +1,10 @@
 #[cfg(feature = "canbench-rs")]
 mod benches;
+mod bounded_wait;
 
 use candid::candid_method;
 use candid::types::number::Nat;
-use ic_canister_log::{declare_log_buffer, export};
+use ic_canister_log::{declare_log_buffer, export, log};
 use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
 use ic_cdk::api::stable::StableReader;
 #[cfg(not(feature = "next-migration-version-memory-manager"))]
@@ -14,6 +15,7 @@ use ic_cdk_macros::init;
 use ic_cdk_macros::{post_upgrade, pre_upgrade, query, update};
 use ic_icrc1::{
     endpoints::{convert_transfer_error, StandardRecord},
+    snapshot::{GetSnapshotEntriesArgs, LedgerSnapshotInfo, SnapshotAllowances, SnapshotBalances},
     Operation, Transaction,
 };
 use ic_icrc1_ledger::UPGRADES_MEMORY;
@@ -30,6 +32,10 @@ use ic_stable_structures::reader::{BufferedReader, Reader};
 #[cfg(feature = "next-migration-version-memory-manager")]
 use ic_stable_structures::writer::{BufferedWriter, Writer};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::spending_policy::{
+    PeriodCap, RevokeAllApprovalsArgs, RevokeAllApprovalsError, SetSpendingPolicyArgs,
+    SetSpendingPolicyError, SpendingPolicy,
+};
 use icrc_ledger_types::icrc21::{
     errors::Icrc21Error, lib::build_icrc21_consent_info_for_icrc1_and_icrc2_endpoints,
     requests::ConsentMessageRequest, responses::ConsentInfo,
@@ -46,7 +52,10 @@ use icrc_ledger_types::{
     },
 };
 use icrc_ledger_types::{
-    icrc1::account::Account,
+    icrc1::account::{Account, Subaccount},
+    icrc1::transfer_notification::{
+        RegisterTransferNotificationArgs, RegisterTransferNotificationError, TransferNotification,
+    },
     icrc2::allowance::{Allowance, AllowanceArgs},
 };
 use icrc_ledger_types::{
@@ -59,14 +68,32 @@ use icrc_ledger_types::{
 use icrc_ledger_types::{
     icrc1::transfer::{TransferArg, TransferError},
     icrc2::transfer_from::{TransferFromArgs, TransferFromError},
+    icrc4::batch_transfer::{TransferBatchArgs, TransferBatchResult},
 };
 use num_traits::{bounds::Bounded, ToPrimitive};
 use serde_bytes::ByteBuf;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use std::io::{Read, Write};
+use std::time::Duration;
 
 const MAX_MESSAGE_SIZE: u64 = 1024 * 1024;
 
+/// The maximum number of transfers accepted by a single `icrc4_transfer_batch` call.
+const MAX_TRANSFER_BATCH_SIZE: usize = 1_000;
+
+/// The maximum number of recipients a spending policy can allow.
+const MAX_ALLOWED_RECIPIENTS: usize = 100;
+
+/// The timeout of the bounded-wait calls that deliver transfer notifications.
+const NOTIFICATION_TIMEOUT_SECONDS: u32 = 60;
+
+/// The maximum number of transfer notifications delivered by a single timer.
+const MAX_NOTIFICATIONS_PER_ROUND: usize = 100;
+
+/// The maximum number of transfer notifications waiting to be delivered.
+const MAX_PENDING_NOTIFICATIONS: usize = 10_000;
+
 #[cfg(not(feature = "u256-tokens"))]
 pub type Tokens = ic_icrc1_tokens_u64::U64;
 
@@ -77,6 +104,8 @@ thread_local! {
     static LEDGER: RefCell<Option<Ledger<Tokens>>> = const { RefCell::new(None) };
     static PRE_UPGRADE_INSTRUCTIONS_CONSUMED: RefCell<u64> = const { RefCell::new(0) };
     static POST_UPGRADE_INSTRUCTIONS_CONSUMED: RefCell<u64> = const { RefCell::new(0) };
+    static PENDING_NOTIFICATIONS: RefCell<VecDeque<(String, TransferNotification)>> = const { RefCell::new(VecDeque::new()) };
+    static NOTIFICATION_TIMER_SET: Cell<bool> = const { Cell::new(false) };
 }
 
 declare_log_buffer!(name = LOG, capacity = 1000);
@@ -230,6 +259,8 @@ fn post_upgrade(args: Option<LedgerArgument>) {
             LedgerArgument::Upgrade(upgrade_args) => {
                 if let Some(upgrade_args) = upgrade_args {
                     Access::with_ledger_mut(|ledger| ledger.upgrade(&LOG, upgrade_args));
+                    // The upgrade can take a new snapshot, which is part of the certified state.
+                    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
                 }
             }
         }
@@ -466,6 +497,7 @@ async fn execute_transfer(
         amount,
         memo,
         created_at_time,
+        false,
     )?;
 
     // NB. we need to set the certified data before the first async call to make sure that the
@@ -476,6 +508,10 @@ async fn execute_transfer(
     Ok(Nat::from(block_idx))
 }
 
+/// Applies a transfer, mint or burn. Transfers between two regular accounts are recorded
+/// as `batch_xfer` operations if `batch_entry` is set, i.e., for entries of
+/// `icrc4_transfer_batch`, and as `xfer` operations otherwise.
+#[allow(clippy::too_many_arguments)]
 fn execute_transfer_not_async(
     from_account: Account,
     to: Account,
@@ -484,8 +520,9 @@ fn execute_transfer_not_async(
     amount: Nat,
     memo: Option<Memo>,
     created_at_time: Option<u64>,
+    batch_entry: bool,
 ) -> Result<BlockIndex, ic_ledger_canister_core::ledger::TransferError<Tokens>> {
-    Access::with_ledger_mut(|ledger| {
+    let (block_idx, notification) = Access::with_ledger_mut(|ledger| {
         let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
         let created_at_time = created_at_time.map(TimeStamp::from_nanos_since_unix_epoch);
 
@@ -560,7 +597,18 @@ fn execute_transfer_not_async(
                     expected_fee: expected_fee_tokens,
                 });
             }
-            (
+            let tx = if batch_entry {
+                Transaction {
+                    operation: Operation::BatchTransfer {
+                        from: from_account,
+                        to,
+                        amount,
+                        fee: fee.map(|_| expected_fee_tokens),
+                    },
+                    created_at_time: created_at_time.map(|t| t.as_nanos_since_unix_epoch()),
+                    memo,
+                }
+            } else {
                 Transaction::transfer(
                     from_account,
                     to,
@@ -569,14 +617,135 @@ fn execute_transfer_not_async(
                     fee.map(|_| expected_fee_tokens),
                     created_at_time,
                     memo,
-                ),
-                expected_fee_tokens,
-            )
+                )
+            };
+            (tx, expected_fee_tokens)
         };
 
+        let notification = match &tx.operation {
+            Operation::Mint { to, amount } => Some((None, *to, amount.clone())),
+            Operation::Transfer {
+                from, to, amount, ..
+            }
+            | Operation::BatchTransfer {
+                from, to, amount, ..
+            } => Some((Some(*from), *to, amount.clone())),
+            _ => None,
+        };
+        let memo = tx.memo.clone();
         let (block_idx, _) = apply_transaction(ledger, tx, now, effective_fee)?;
-        Ok(block_idx)
-    })
+        let notification = notification.and_then(|(from, to, amount)| {
+            let method = ledger.transfer_notification(&to)?;
+            Some((
+                method.clone(),
+                TransferNotification {
+                    block_index: Nat::from(block_idx),
+                    to,
+                    from,
+                    amount: amount.into(),
+                    memo,
+                },
+            ))
+        });
+        Ok((block_idx, notification))
+    })?;
+
+    if let Some((method, notification)) = notification {
+        notify_transfer(method, notification);
+    }
+    Ok(block_idx)
+}
+
+/// Queues a notification of the owner of the credited account. The queued
+/// notifications are delivered with bounded-wait calls made from a timer, i.e.,
+/// in a separate message: the transfer is committed even if the call fails, and
+/// a receiver that never responds cannot keep a call context of the ledger
+/// open. A notification that does not fit into the queue or cannot be
+/// delivered is dropped, and the pending notifications are lost on upgrade.
+fn notify_transfer(method: String, notification: TransferNotification) {
+    let queued = PENDING_NOTIFICATIONS.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        if pending.len() >= MAX_PENDING_NOTIFICATIONS {
+            return false;
+        }
+        pending.push_back((method, notification));
+        true
+    });
+    if !queued {
+        log!(
+            LOG,
+            "[notify_transfer]: dropped a notification, {} notifications are pending",
+            MAX_PENDING_NOTIFICATIONS
+        );
+        return;
+    }
+    schedule_notifications();
+}
+
+fn schedule_notifications() {
+    if NOTIFICATION_TIMER_SET.with(|set| set.replace(true)) {
+        return;
+    }
+    ic_cdk_timers::set_timer(Duration::ZERO, deliver_notifications);
+}
+
+/// Delivers at most `MAX_NOTIFICATIONS_PER_ROUND` pending notifications and
+/// schedules the delivery of the remaining ones in a later round.
+fn deliver_notifications() {
+    NOTIFICATION_TIMER_SET.with(|set| set.set(false));
+    let batch: Vec<_> = PENDING_NOTIFICATIONS.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        let len = pending.len().min(MAX_NOTIFICATIONS_PER_ROUND);
+        pending.drain(..len).collect()
+    });
+    for (method, notification) in batch {
+        let receiver = notification.to.owner;
+        if let Err(code) = bounded_wait::notify_with_bounded_wait(
+            receiver,
+            &method,
+            (notification,),
+            NOTIFICATION_TIMEOUT_SECONDS,
+        ) {
+            log!(
+                LOG,
+                "[notify_transfer]: failed to notify {} via {}: {:?}",
+                receiver,
+                method,
+                code
+            );
+        }
+    }
+    if PENDING_NOTIFICATIONS.with(|pending| !pending.borrow().is_empty()) {
+        schedule_notifications();
+    }
+}
+
+#[update]
+#[candid_method(update)]
+fn register_transfer_notification(
+    arg: RegisterTransferNotificationArgs,
+) -> Result<(), RegisterTransferNotificationError> {
+    let account = Account {
+        owner: ic_cdk::api::caller(),
+        subaccount: arg.subaccount,
+    };
+    Access::with_ledger_mut(|ledger| ledger.register_transfer_notification(account, arg.method))
+}
+
+#[update]
+#[candid_method(update)]
+fn unregister_transfer_notification(subaccount: Option<Subaccount>) {
+    let account = Account {
+        owner: ic_cdk::api::caller(),
+        subaccount,
+    };
+    Access::with_ledger_mut(|ledger| ledger.unregister_transfer_notification(&account))
+}
+
+#[query]
+#[candid_method(query)]
+fn transfer_notification(account: Account) -> Option<String> {
+    Access::with_ledger(|ledger| ledger.transfer_notification(&account).cloned())
 }
 
 #[update]
@@ -606,6 +775,92 @@ async fn icrc1_transfer(arg: TransferArg) -> Result<Nat, TransferError> {
     })
 }
 
+/// Executes the given transfers from accounts of the caller, in order, as if each of them
+/// was a separate `icrc1_transfer` call, and returns the result of each entry.
+///
+/// Every successful entry is recorded in its own block: transfers between two regular
+/// accounts as `batch_xfer` operations (block type `4xfer`), and mints and burns as
+/// regular ICRC-1 mint and burn blocks. Since the operation is part of the transaction
+/// hash, a transfer entry is deduplicated against earlier batch entries with the same
+/// arguments, but not against `icrc1_transfer` calls.
+///
+/// Arguments that would make `icrc1_transfer` trap (a batch that is too large or a memo
+/// that is too long) are rejected before any entry is applied, and the errors of
+/// individual entries are returned instead of trapping. Should the call still trap
+/// while applying the entries (e.g., because it runs out of instructions), the whole
+/// batch is reverted and none of its entries is recorded.
+#[update]
+#[candid_method(update)]
+async fn icrc4_transfer_batch(args: TransferBatchArgs) -> Vec<TransferBatchResult> {
+    if args.len() > MAX_TRANSFER_BATCH_SIZE {
+        ic_cdk::trap(&format!(
+            "the batch contains {} transfers, which is above the allowed limit of {}",
+            args.len(),
+            MAX_TRANSFER_BATCH_SIZE
+        ));
+    }
+    let max_memo_length = Access::with_ledger(|ledger| ledger.max_memo_length()) as usize;
+    if let Some((index, memo)) = args
+        .iter()
+        .enumerate()
+        .filter_map(|(index, arg)| Some((index, arg.memo.as_ref()?)))
+        .find(|(_, memo)| memo.0.len() > max_memo_length)
+    {
+        ic_cdk::trap(&format!(
+            "the memo field size of {} bytes of transfer {} is above the allowed limit of {} bytes",
+            memo.0.len(),
+            index,
+            max_memo_length
+        ));
+    }
+    let caller = ic_cdk::api::caller();
+    // NB. all entries are applied within the same message without yielding, so no other
+    // transaction can interleave with the batch. Each entry is deduplicated against the
+    // ledger (and the preceding entries) by its transaction hash and created_at_time.
+    let results: Vec<TransferBatchResult> = args
+        .into_iter()
+        .map(|arg| {
+            let from_account = Account {
+                owner: caller,
+                subaccount: arg.from_subaccount,
+            };
+            execute_transfer_not_async(
+                from_account,
+                arg.to,
+                None,
+                arg.fee,
+                arg.amount,
+                arg.memo,
+                arg.created_at_time,
+                true,
+            )
+            .map(Nat::from)
+            .map_err(convert_transfer_error)
+            .map_err(|err| {
+                // Unlike icrc1_transfer, do not trap, as that would revert the entries
+                // applied before this one.
+                TransferError::try_from(err).unwrap_or_else(|message| TransferError::GenericError {
+                    error_code: Nat::from(0u64),
+                    message,
+                })
+            })
+        })
+        .collect();
+
+    // NB. we need to set the certified data before the first async call to make sure that the
+    // blockchain state agrees with the certificate while archiving is in progress.
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    results
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc4_maximum_update_batch_size() -> Option<Nat> {
+    Some(Nat::from(MAX_TRANSFER_BATCH_SIZE))
+}
+
 #[update]
 #[candid_method(update)]
 async fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromError> {
@@ -673,6 +928,10 @@ fn supported_standards() -> Vec<StandardRecord> {
             name: "ICRC-3".to_string(),
             url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3".to_string(),
         },
+        StandardRecord {
+            name: "ICRC-4".to_string(),
+            url: "https://github.com/dfinity/ICRC/tree/main/ICRCs/ICRC-4".to_string(),
+        },
         StandardRecord {
             name: "ICRC-21".to_string(),
             url: "https://github.com/dfinity/wg-identity-authentication/blob/main/topics/ICRC-21/icrc_21_consent_msg.md".to_string(),
@@ -805,6 +1064,165 @@ fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
     })
 }
 
+#[update]
+#[candid_method(update)]
+async fn set_spending_policy(arg: SetSpendingPolicyArgs) -> Result<Nat, SetSpendingPolicyError> {
+    let block_idx = Access::with_ledger_mut(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+
+        let from_account = Account {
+            owner: ic_cdk::api::caller(),
+            subaccount: arg.from_subaccount,
+        };
+        match arg.memo.as_ref() {
+            Some(memo) if memo.0.len() > ledger.max_memo_length() as usize => {
+                ic_cdk::trap("the memo field is too large")
+            }
+            _ => {}
+        };
+        let (period_cap, period) = match arg.period_cap {
+            Some(cap) => (
+                Some(Tokens::try_from(cap.amount).unwrap_or_else(|_| Tokens::max_value())),
+                Some(cap.period_nanos),
+            ),
+            None => (None, None),
+        };
+
+        if arg
+            .allowed_recipients
+            .as_ref()
+            .is_some_and(|recipients| recipients.len() > MAX_ALLOWED_RECIPIENTS)
+        {
+            return Err(SetSpendingPolicyError::TooManyAllowedRecipients {
+                max_allowed_recipients: Nat::from(MAX_ALLOWED_RECIPIENTS),
+            });
+        }
+
+        let expected_fee_tokens = ledger.transfer_fee();
+        let expected_fee: Nat = expected_fee_tokens.into();
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+            return Err(SetSpendingPolicyError::BadFee { expected_fee });
+        }
+
+        let tx = Transaction {
+            operation: Operation::SetSpendingPolicy {
+                from: from_account,
+                spender: arg.spender,
+                period_cap,
+                period,
+                allowed_recipients: arg.allowed_recipients,
+                fee: arg.fee.map(|_| expected_fee_tokens),
+            },
+            created_at_time: arg.created_at_time,
+            memo: arg.memo,
+        };
+
+        let (block_idx, _) = apply_transaction(ledger, tx, now, expected_fee_tokens)
+            .map_err(convert_transfer_error)
+            .map_err(|err| {
+                let err: SetSpendingPolicyError = match err.try_into() {
+                    Ok(err) => err,
+                    Err(err) => ic_cdk::trap(&err),
+                };
+                err
+            })?;
+        Ok(block_idx)
+    })?;
+
+    // NB. we need to set the certified data before the first async call to make sure that the
+    // blockchain state agrees with the certificate while archiving is in progress.
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(block_idx))
+}
+
+#[query]
+#[candid_method(query)]
+fn spending_policy(arg: AllowanceArgs) -> Option<SpendingPolicy> {
+    Access::with_ledger(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+        if ledger
+            .approvals()
+            .allowance(&arg.account, &arg.spender, now)
+            .amount
+            .is_zero()
+        {
+            return None;
+        }
+        let policy = ledger
+            .approvals()
+            .spending_policy(&arg.account, &arg.spender)?
+            .at(now);
+        Some(SpendingPolicy {
+            period_cap: policy.period_cap.map(|cap| PeriodCap {
+                amount: cap.amount.into(),
+                period_nanos: cap.period_nanos,
+            }),
+            allowed_recipients: policy
+                .allowed_recipients
+                .map(|recipients| recipients.into_iter().collect()),
+            period_start: policy.period_start.as_nanos_since_unix_epoch(),
+            spent_in_period: policy.spent_in_period.into(),
+        })
+    })
+}
+
+/// Revokes the approvals of the caller's account. A single call revokes at most
+/// `ic_ledger_core::approvals::MAX_REVOKED_APPROVALS` approvals, so accounts with
+/// more approvals need several calls.
+#[update]
+#[candid_method(update)]
+async fn revoke_all_approvals(arg: RevokeAllApprovalsArgs) -> Result<Nat, RevokeAllApprovalsError> {
+    let block_idx = Access::with_ledger_mut(|ledger| {
+        let now = TimeStamp::from_nanos_since_unix_epoch(ic_cdk::api::time());
+
+        let from_account = Account {
+            owner: ic_cdk::api::caller(),
+            subaccount: arg.from_subaccount,
+        };
+        match arg.memo.as_ref() {
+            Some(memo) if memo.0.len() > ledger.max_memo_length() as usize => {
+                ic_cdk::trap("the memo field is too large")
+            }
+            _ => {}
+        };
+
+        let expected_fee_tokens = ledger.transfer_fee();
+        let expected_fee: Nat = expected_fee_tokens.into();
+        if arg.fee.is_some() && arg.fee.as_ref() != Some(&expected_fee) {
+            return Err(RevokeAllApprovalsError::BadFee { expected_fee });
+        }
+
+        let tx = Transaction {
+            operation: Operation::RevokeAllApprovals {
+                from: from_account,
+                fee: arg.fee.map(|_| expected_fee_tokens),
+            },
+            created_at_time: arg.created_at_time,
+            memo: arg.memo,
+        };
+
+        let (block_idx, _) = apply_transaction(ledger, tx, now, expected_fee_tokens)
+            .map_err(convert_transfer_error)
+            .map_err(|err| {
+                let err: RevokeAllApprovalsError = match err.try_into() {
+                    Ok(err) => err,
+                    Err(err) => ic_cdk::trap(&err),
+                };
+                err
+            })?;
+        Ok(block_idx)
+    })?;
+
+    // NB. we need to set the certified data before the first async call to make sure that the
+    // blockchain state agrees with the certificate while archiving is in progress.
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    Ok(Nat::from(block_idx))
+}
+
 #[query]
 #[candid_method(query)]
 fn icrc3_get_archives(args: GetArchivesArgs) -> GetArchivesResult {
@@ -814,7 +1232,11 @@ fn icrc3_get_archives(args: GetArchivesArgs) -> GetArchivesResult {
 #[query]
 #[candid_method(query)]
 fn icrc3_get_tip_certificate() -> Option<icrc_ledger_types::icrc3::blocks::ICRC3DataCertificate> {
-    let certificate = ByteBuf::from(ic_cdk::api::data_certificate()?);
+    // The data certificate is only available in non-replicated queries. Canisters calling
+    // this method get the hash tree with an empty certificate: the response of a
+    // replicated call is already authenticated, and the hash tree lets them check the
+    // values the ledger certifies, e.g. the snapshot hash.
+    let certificate = ByteBuf::from(ic_cdk::api::data_certificate().unwrap_or_default());
     let hash_tree = Access::with_ledger(|ledger| ledger.construct_hash_tree());
     let mut tree_buf = vec![];
     ciborium::ser::into_writer(&hash_tree, &mut tree_buf).unwrap();
@@ -824,6 +1246,32 @@ fn icrc3_get_tip_certificate() -> Option<icrc_ledger_types::icrc3::blocks::ICRC3
     })
 }
 
+#[query]
+#[candid_method(query)]
+fn get_ledger_snapshot_info() -> Option<LedgerSnapshotInfo> {
+    Access::with_ledger(|ledger| ledger.snapshot().map(|snapshot| snapshot.info()))
+}
+
+#[query]
+#[candid_method(query)]
+fn get_ledger_snapshot_balances(args: GetSnapshotEntriesArgs) -> Option<SnapshotBalances> {
+    Access::with_ledger(|ledger| {
+        ledger
+            .snapshot()
+            .map(|snapshot| snapshot.balances(args.start, args.length))
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_ledger_snapshot_allowances(args: GetSnapshotEntriesArgs) -> Option<SnapshotAllowances> {
+    Access::with_ledger(|ledger| {
+        ledger
+            .snapshot()
+            .map(|snapshot| snapshot.allowances(args.start, args.length))
+    })
+}
+
 #[query]
 #[candid_method(query)]
 fn icrc3_supported_block_types() -> Vec<icrc_ledger_types::icrc3::blocks::SupportedBlockType> {
@@ -850,6 +1298,21 @@ fn icrc3_supported_block_types() -> Vec<icrc_ledger_types::icrc3::blocks::Suppor
             url: "https://github.com/dfinity/ICRC-1/blob/main/standards/ICRC-2/README.md"
                 .to_string(),
         },
+        SupportedBlockType {
+            block_type: "2spend_policy".to_string(),
+            url: "https://github.com/dfinity/ic/blob/master/rs/rosetta-api/icrc1/ledger/block.cddl"
+                .to_string(),
+        },
+        SupportedBlockType {
+            block_type: "2revoke_all".to_string(),
+            url: "https://github.com/dfinity/ic/blob/master/rs/rosetta-api/icrc1/ledger/block.cddl"
+                .to_string(),
+        },
+        SupportedBlockType {
+            block_type: "4xfer".to_string(),
+            url: "https://github.com/dfinity/ic/blob/master/rs/rosetta-api/icrc1/ledger/block.cddl"
+                .to_string(),
+        },
     ]
 }
 
@@ -615,3 +615,172 @@ fn test_approval_burn_from() {
     assert_eq!(ctx.balances().account_balance(&spender), Tokens::ZERO);
     assert_eq!(ctx.balances().total_supply().get_e8s(), 90_000);
 }
+
+#[test]
+fn test_snapshot_entries_match_state_hash() {
+    use ic_icrc1::snapshot;
+
+    let now = ts(12345678);
+    let mut ctx = Ledger::from_init_args(DummyLogger, default_init_args(), now);
+
+    for n in 1..=5 {
+        ctx.balances_mut()
+            .mint(&test_account_id(n), tokens(100_000 * n))
+            .unwrap();
+    }
+    let approve = |spender: u64, expires_at: Option<TimeStamp>| Transaction {
+        operation: Operation::Approve {
+            from: test_account_id(1),
+            spender: test_account_id(spender),
+            amount: tokens(1_000),
+            expected_allowance: None,
+            expires_at: expires_at.map(|ts| ts.as_nanos_since_unix_epoch()),
+            fee: Some(tokens(10_000)),
+        },
+        created_at_time: None,
+        memo: None,
+    };
+    approve(2, None).apply(&mut ctx, now, Tokens::ZERO).unwrap();
+    approve(3, Some(now + Duration::from_secs(10)))
+        .apply(&mut ctx, now, Tokens::ZERO)
+        .unwrap();
+
+    assert!(ctx.snapshot().is_none());
+    let later = now + Duration::from_secs(20);
+    ctx.take_snapshot(later);
+    let snapshot = ctx.snapshot().unwrap();
+    let info = snapshot.info();
+
+    assert_eq!(info.num_blocks, 0);
+    assert_eq!(info.num_balances, 5);
+    // The allowance of the third account expired before the snapshot.
+    assert_eq!(info.num_allowances, 1);
+    assert_eq!(info.timestamp, later.as_nanos_since_unix_epoch());
+
+    let mut balances_hash = snapshot::EMPTY_ENTRIES_HASH;
+    let mut start = 0;
+    loop {
+        let page = snapshot.balances(start, 2);
+        assert_eq!(page.state_hash, info.state_hash);
+        if page.balances.is_empty() {
+            break;
+        }
+        for entry in &page.balances {
+            balances_hash = snapshot::chain_balance(&balances_hash, &entry.account, &entry.balance);
+        }
+        start += page.balances.len() as u64;
+    }
+    assert_eq!(start, 5);
+    assert_eq!(balances_hash.to_vec(), info.balances_hash.to_vec());
+
+    let allowances = snapshot.allowances(0, 10).allowances;
+    assert_eq!(allowances.len(), 1);
+    assert_eq!(allowances[0].spender, test_account_id(2));
+    let allowances_hash = snapshot::chain_allowance(
+        &snapshot::EMPTY_ENTRIES_HASH,
+        &allowances[0].account,
+        &allowances[0].spender,
+        &allowances[0].allowance,
+        allowances[0].expires_at,
+    );
+    assert_eq!(allowances_hash.to_vec(), info.allowances_hash.to_vec());
+
+    assert_eq!(
+        snapshot::state_hash(0, None, &balances_hash, &allowances_hash).to_vec(),
+        info.state_hash.to_vec()
+    );
+
+    // A new snapshot replaces the entries of the previous one.
+    ctx.balances_mut()
+        .burn(&test_account_id(5), tokens(500_000))
+        .unwrap();
+    ctx.take_snapshot(later);
+    let snapshot = ctx.snapshot().unwrap();
+    assert_eq!(snapshot.info().num_balances, 4);
+    let balances = snapshot.balances(0, 10).balances;
+    assert_eq!(balances.len(), 4);
+    assert!(balances
+        .iter()
+        .all(|entry| entry.account != test_account_id(5)));
+}
+
+#[test]
+fn test_transfer_notification_registrations() {
+    use icrc_ledger_types::icrc1::transfer_notification::RegisterTransferNotificationError;
+
+    let now = ts(12345678);
+    let mut ctx = Ledger::from_init_args(DummyLogger, default_init_args(), now);
+    let account = test_account_id(1);
+
+    assert_eq!(ctx.transfer_notification(&account), None);
+    ctx.register_transfer_notification(account, "on_deposit".to_string())
+        .unwrap();
+    ctx.register_transfer_notification(account, "on_credit".to_string())
+        .unwrap();
+    assert_eq!(
+        ctx.transfer_notification(&account),
+        Some(&"on_credit".to_string())
+    );
+    // Registrations are per account, not per owner.
+    let subaccount = Account {
+        subaccount: Some([1; 32]),
+        ..account
+    };
+    assert_eq!(ctx.transfer_notification(&subaccount), None);
+
+    assert_eq!(
+        ctx.register_transfer_notification(account, "m".repeat(101)),
+        Err(RegisterTransferNotificationError::MethodNameTooLong { max_length: 100 })
+    );
+    // Only canisters can register.
+    for owner in [
+        candid::Principal::anonymous(),
+        PrincipalId::new_self_authenticating(&[1, 2, 3]).0,
+    ] {
+        assert_eq!(
+            ctx.register_transfer_notification(
+                Account {
+                    owner,
+                    subaccount: None,
+                },
+                "on_credit".to_string()
+            ),
+            Err(RegisterTransferNotificationError::CallerNotACanister)
+        );
+    }
+
+    ctx.unregister_transfer_notification(&account);
+    assert_eq!(ctx.transfer_notification(&account), None);
+}
+
+#[test]
+fn test_transfer_notification_registrations_per_owner() {
+    use icrc_ledger_types::icrc1::transfer_notification::RegisterTransferNotificationError;
+
+    let now = ts(12345678);
+    let mut ctx = Ledger::from_init_args(DummyLogger, default_init_args(), now);
+    let owner = test_account_id(1).owner;
+    let subaccount = |n: u8| Account {
+        owner,
+        subaccount: Some([n; 32]),
+    };
+
+    for n in 0..10 {
+        ctx.register_transfer_notification(subaccount(n), "on_credit".to_string())
+            .unwrap();
+    }
+    assert_eq!(
+        ctx.register_transfer_notification(subaccount(10), "on_credit".to_string()),
+        Err(RegisterTransferNotificationError::TooManyRegistrationsForOwner { limit: 10 })
+    );
+    // Replacing a registration does not count against the limit.
+    ctx.register_transfer_notification(subaccount(0), "on_deposit".to_string())
+        .unwrap();
+    // The limit applies per owner.
+    ctx.register_transfer_notification(test_account_id(2), "on_credit".to_string())
+        .unwrap();
+
+    ctx.unregister_transfer_notification(&subaccount(0));
+    ctx.register_transfer_notification(subaccount(10), "on_credit".to_string())
+        .unwrap();
+}
@@ -0,0 +1,121 @@
+use candid::{CandidType, Decode, Encode, Nat, Principal};
+use ic_base_types::PrincipalId;
+use ic_icrc1_ledger::{InitArgsBuilder, LedgerArgument};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{BlockIndex, Memo, TransferArg, TransferError};
+use icrc_ledger_types::icrc1::transfer_notification::{
+    RegisterTransferNotificationError, TransferNotification,
+};
+use pocket_ic::{PocketIc, PocketIcBuilder, WasmResult};
+
+const FEE: u64 = 10_000;
+const STARTING_CYCLES: u128 = 100_000_000_000_000;
+
+fn wasm_from_env(var: &str) -> Vec<u8> {
+    let path = std::env::var(var).unwrap_or_else(|_| panic!("{} is not set", var));
+    std::fs::read(&path).unwrap_or_else(|err| panic!("failed to read {}: {}", path, err))
+}
+
+fn install(pocket_ic: &PocketIc, wasm: Vec<u8>, arg: Vec<u8>) -> Principal {
+    let canister_id = pocket_ic.create_canister();
+    pocket_ic.add_cycles(canister_id, STARTING_CYCLES);
+    pocket_ic.install_canister(canister_id, wasm, arg, None);
+    canister_id
+}
+
+fn update<T: CandidType + for<'a> candid::Deserialize<'a>>(
+    pocket_ic: &PocketIc,
+    canister_id: Principal,
+    caller: Principal,
+    method: &str,
+    arg: Vec<u8>,
+) -> T {
+    match pocket_ic
+        .update_call(canister_id, caller, method, arg)
+        .unwrap_or_else(|err| panic!("failed to call {}: {}", method, err))
+    {
+        WasmResult::Reply(reply) => Decode!(&reply, T).unwrap(),
+        WasmResult::Reject(reject) => panic!("{} was rejected: {}", method, reject),
+    }
+}
+
+#[test]
+fn should_notify_the_receiver_canister() {
+    let sender = PrincipalId::new_user_test_id(1).0;
+    // Bounded-wait calls are not yet available on the mainnet.
+    let pocket_ic = PocketIcBuilder::new()
+        .with_application_subnet()
+        .with_nonmainnet_features(true)
+        .build();
+    let ledger = install(
+        &pocket_ic,
+        wasm_from_env("IC_ICRC1_LEDGER_WASM_PATH"),
+        Encode!(&LedgerArgument::Init(
+            InitArgsBuilder::for_tests()
+                .with_minting_account(PrincipalId::new_user_test_id(1_000).0)
+                .with_initial_balance(sender, 10_000_000u64)
+                .with_transfer_fee(FEE)
+                .build()
+        ))
+        .unwrap(),
+    );
+    let receiver = install(
+        &pocket_ic,
+        wasm_from_env("IC_ICRC1_TEST_NOTIFIED_WASM_PATH"),
+        Encode!().unwrap(),
+    );
+
+    let registered: Result<(), RegisterTransferNotificationError> = update(
+        &pocket_ic,
+        receiver,
+        Principal::anonymous(),
+        "register",
+        Encode!(&ledger).unwrap(),
+    );
+    registered.expect("failed to register the transfer notification");
+
+    let memo = Memo::from(42u64);
+    let block_index: Result<BlockIndex, TransferError> = update(
+        &pocket_ic,
+        ledger,
+        sender,
+        "icrc1_transfer",
+        Encode!(&TransferArg {
+            from_subaccount: None,
+            to: receiver.into(),
+            fee: None,
+            created_at_time: None,
+            memo: Some(memo.clone()),
+            amount: Nat::from(1_000_000u64),
+        })
+        .unwrap(),
+    );
+    let block_index = block_index.expect("the transfer failed");
+
+    // The notification is sent from a timer in a later round.
+    for _ in 0..5 {
+        pocket_ic.tick();
+    }
+    let notifications = match pocket_ic
+        .query_call(
+            receiver,
+            Principal::anonymous(),
+            "notifications",
+            Encode!().unwrap(),
+        )
+        .expect("failed to query the notifications")
+    {
+        WasmResult::Reply(reply) => Decode!(&reply, Vec<TransferNotification>).unwrap(),
+        WasmResult::Reject(reject) => panic!("notifications was rejected: {}", reject),
+    };
+    assert_eq!(
+        notifications,
+        vec![TransferNotification {
+            block_index,
+            to: Account::from(receiver),
+            from: Some(Account::from(sender)),
+            amount: Nat::from(1_000_000u64),
+            memo: Some(memo),
+        }]
+    );
+}
@@ -0,0 +1,39 @@
+//! A canister that registers for transfer notifications of an ICRC-1 ledger
+//! and records the notifications it receives.
+use candid::Principal;
+use ic_cdk_macros::{query, update};
+use icrc_ledger_types::icrc1::transfer_notification::{
+    RegisterTransferNotificationArgs, RegisterTransferNotificationError, TransferNotification,
+};
+use std::cell::RefCell;
+
+thread_local! {
+    static NOTIFICATIONS: RefCell<Vec<TransferNotification>> = const { RefCell::new(vec![]) };
+}
+
+#[update]
+async fn register(ledger: Principal) -> Result<(), RegisterTransferNotificationError> {
+    let (result,): (Result<(), RegisterTransferNotificationError>,) = ic_cdk::call(
+        ledger,
+        "register_transfer_notification",
+        (RegisterTransferNotificationArgs {
+            subaccount: None,
+            method: "on_credit".to_string(),
+        },),
+    )
+    .await
+    .expect("failed to call register_transfer_notification");
+    result
+}
+
+#[update]
+fn on_credit(notification: TransferNotification) {
+    NOTIFICATIONS.with(|n| n.borrow_mut().push(notification));
+}
+
+#[query]
+fn notifications() -> Vec<TransferNotification> {
+    NOTIFICATIONS.with(|n| n.borrow().clone())
+}
+
+fn main() {}
@@ -195,6 +195,21 @@ fn test_upgrade_archive_options() {
     );
 }
 
+#[test]
+fn test_set_spending_policy() {
+    ic_icrc1_ledger_sm_tests::test_set_spending_policy(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_revoke_all_approvals() {
+    ic_icrc1_ledger_sm_tests::test_revoke_all_approvals(ledger_wasm(), encode_init_args);
+}
+
+#[test]
+fn test_icrc4_transfer_batch() {
+    ic_icrc1_ledger_sm_tests::test_icrc4_transfer_batch(ledger_wasm(), encode_init_args);
+}
+
 #[test]
 fn test_tx_deduplication() {
     ic_icrc1_ledger_sm_tests::test_tx_deduplication(ledger_wasm(), encode_init_args);
@@ -1,5 +1,5 @@
 use super::storage_operations;
-use crate::common::storage::types::{MetadataEntry, RosettaBlock};
+use crate::common::storage::types::{MetadataEntry, RosettaBlock, TableNames};
 use anyhow::{bail, Result};
 use candid::Nat;
 use icrc_ledger_types::icrc1::account::Account;
@@ -10,26 +10,44 @@ use std::{path::Path, sync::Mutex};
 #[derive(Debug)]
 pub struct StorageClient {
     storage_connection: Mutex<Connection>,
+    tables: TableNames,
 }
 
 impl StorageClient {
     /// Constructs a new SQLite in-persistent store.
     pub fn new_persistent(db_file_path: &Path) -> anyhow::Result<Self> {
+        Self::new_persistent_with_namespace(db_file_path, None)
+    }
+
+    /// Constructs a new SQLite in-persistent store whose tables are prefixed with the given namespace.
+    /// Several stores with different namespaces can share the same database file.
+    pub fn new_persistent_with_namespace(
+        db_file_path: &Path,
+        namespace: Option<String>,
+    ) -> anyhow::Result<Self> {
         std::fs::create_dir_all(db_file_path.parent().unwrap())?;
         let connection = rusqlite::Connection::open(db_file_path)?;
-        Self::new(connection)
+        Self::new(connection, TableNames::new(namespace))
     }
 
     /// Constructs a new SQLite in-memory store.
     pub fn new_in_memory() -> anyhow::Result<Self> {
         let connection = rusqlite::Connection::open_in_memory()?;
-        Self::new(connection)
+        Self::new(connection, TableNames::default())
     }
 
-    fn new(connection: rusqlite::Connection) -> anyhow::Result<Self> {
+    fn new(connection: rusqlite::Connection, tables: TableNames) -> anyhow::Result<Self> {
         let storage_client = Self {
             storage_connection: Mutex::new(connection),
+            tables,
         };
+        // Concurrent writers to the same database file, e.g., the synchronizers of several
+        // ledgers, wait for each other instead of failing.
+        storage_client
+            .storage_connection
+            .lock()
+            .unwrap()
+            .busy_timeout(std::time::Duration::from_secs(60))?;
         storage_client
             .storage_connection
             .lock()
@@ -39,28 +57,33 @@ impl StorageClient {
         Ok(storage_client)
     }
 
+    /// The names of the tables this store reads from and writes to.
+    pub fn table_names(&self) -> &TableNames {
+        &self.tables
+    }
+
     // Gets a block with a certain index. Returns `None` if no block exists in the database with that index. Returns an error if multiple blocks with that index exist.
     pub fn get_block_at_idx(&self, block_idx: u64) -> anyhow::Result<Option<RosettaBlock>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_block_at_idx(&open_connection, block_idx)
+        storage_operations::get_block_at_idx(&open_connection, &self.tables, block_idx)
     }
 
     // Gets a block with a certain hash. Returns `None` if no block exists in the database with that hash. Returns an error if multiple blocks with that hash exist.
     pub fn get_block_by_hash(&self, hash: ByteBuf) -> anyhow::Result<Option<RosettaBlock>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_block_by_hash(&open_connection, hash)
+        storage_operations::get_block_by_hash(&open_connection, &self.tables, hash)
     }
 
     // Gets the block with the highest block index. Returns `None` if no block exists in the database.
     pub fn get_block_with_highest_block_idx(&self) -> anyhow::Result<Option<RosettaBlock>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_block_with_highest_block_idx(&open_connection)
+        storage_operations::get_block_with_highest_block_idx(&open_connection, &self.tables)
     }
 
     // Gets the block with the lowest block index. Returns `None` if no block exists in the database.
     pub fn get_block_with_lowest_block_idx(&self) -> anyhow::Result<Option<RosettaBlock>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_block_with_lowest_block_idx(&open_connection)
+        storage_operations::get_block_with_lowest_block_idx(&open_connection, &self.tables)
     }
 
     // Returns a range of blocks including the start index and the end index.
@@ -72,7 +95,12 @@ impl StorageClient {
         end_index: u64,
     ) -> anyhow::Result<Vec<RosettaBlock>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_blocks_by_index_range(&open_connection, start_index, end_index)
+        storage_operations::get_blocks_by_index_range(
+            &open_connection,
+            &self.tables,
+            start_index,
+            end_index,
+        )
     }
 
     /// Returns all the gaps in the stored blockchain.
@@ -80,7 +108,7 @@ impl StorageClient {
     /// Exp.: If there exists exactly one gap between the indices [a+1,b-1], then this function will return a vector with a single entry that contains the tuple of blocks [(Block(a),Block(b))].
     pub fn get_blockchain_gaps(&self) -> anyhow::Result<Vec<(RosettaBlock, RosettaBlock)>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_blockchain_gaps(&open_connection)
+        storage_operations::get_blockchain_gaps(&open_connection, &self.tables)
     }
 
     // Gets a transaction with a certain hash. Returns [] if no transaction exists in the database with that hash. Returns a vector with multiple entries if more than one transaction
@@ -96,6 +124,7 @@ impl StorageClient {
             .collect::<Vec<crate::common::storage::types::IcrcTransaction>>())
     }
 
+    // Executes a custom query for blocks. The query has to read from the blocks table given by `table_names`.
     pub fn get_blocks_by_custom_query<P>(
         &self,
         sql_query: String,
@@ -113,7 +142,7 @@ impl StorageClient {
         hash: ByteBuf,
     ) -> anyhow::Result<Vec<RosettaBlock>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_blocks_by_transaction_hash(&open_connection, hash)
+        storage_operations::get_blocks_by_transaction_hash(&open_connection, &self.tables, hash)
     }
 
     // Gets a transaction with a certain index. Returns None if no transaction exists in the database with that index. Returns an error if multiple transactions with that index exist.
@@ -128,28 +157,32 @@ impl StorageClient {
 
     pub fn read_metadata(&self) -> anyhow::Result<Vec<MetadataEntry>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_metadata(&open_connection)
+        storage_operations::get_metadata(&open_connection, &self.tables)
     }
 
     pub fn write_metadata(&self, metadata: Vec<MetadataEntry>) -> anyhow::Result<()> {
         let mut open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::store_metadata(&mut open_connection, metadata)
+        storage_operations::store_metadata(&mut open_connection, &self.tables, metadata)
     }
 
     fn create_tables(&self) -> Result<(), rusqlite::Error> {
         let open_connection = self.storage_connection.lock().unwrap();
         open_connection.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS metadata (
+            &format!(
+                r#"
+            CREATE TABLE IF NOT EXISTS {} (
                 key TEXT PRIMARY KEY,
                 value BLOB NOT NULL
             );
             "#,
+                self.tables.metadata
+            ),
             [],
         )?;
         open_connection.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS blocks (
+            &format!(
+                r#"
+            CREATE TABLE IF NOT EXISTS {} (
                 idx INTEGER NOT NULL PRIMARY KEY,
                 hash BLOB NOT NULL,
                 serialized_block BLOB NOT NULL,
@@ -172,11 +205,14 @@ impl StorageClient {
                 approval_expires_at INTEGER
             )
             "#,
+                self.tables.blocks
+            ),
             [],
         )?;
         open_connection.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS account_balances (
+            &format!(
+                r#"
+            CREATE TABLE IF NOT EXISTS {} (
                 block_idx INTEGER NOT NULL,
                 principal BLOB NOT NULL,
                 subaccount BLOB NOT NULL,
@@ -184,29 +220,43 @@ impl StorageClient {
                 PRIMARY KEY(principal,subaccount,block_idx)
             )
             "#,
+                self.tables.account_balances
+            ),
             [],
         )?;
         open_connection.execute(
-            r#"
-            CREATE INDEX IF NOT EXISTS block_idx_account_balances 
-            ON account_balances(block_idx)
+            &format!(
+                r#"
+            CREATE INDEX IF NOT EXISTS {} 
+            ON {}(block_idx)
             "#,
+                self.tables.namespaced("block_idx_account_balances"),
+                self.tables.account_balances
+            ),
             [],
         )?;
 
         open_connection.execute(
-            r#"
-        CREATE INDEX IF NOT EXISTS tx_hash_index 
-        ON blocks(tx_hash)
+            &format!(
+                r#"
+        CREATE INDEX IF NOT EXISTS {} 
+        ON {}(tx_hash)
         "#,
+                self.tables.namespaced("tx_hash_index"),
+                self.tables.blocks
+            ),
             [],
         )?;
 
         open_connection.execute(
-            r#"
-        CREATE INDEX IF NOT EXISTS block_hash_index 
-        ON blocks(hash)
+            &format!(
+                r#"
+        CREATE INDEX IF NOT EXISTS {} 
+        ON {}(hash)
         "#,
+                self.tables.namespaced("block_hash_index"),
+                self.tables.blocks
+            ),
             [],
         )?;
 
@@ -217,7 +267,7 @@ impl StorageClient {
     // This function does NOT populate the account_balance table.
     pub fn store_blocks(&self, blocks: Vec<RosettaBlock>) -> anyhow::Result<()> {
         let mut open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::store_blocks(&mut open_connection, blocks)
+        storage_operations::store_blocks(&mut open_connection, &self.tables, blocks)
     }
 
     // Extracts the information from the transaction and blocks table and fills the account balance table with that information
@@ -227,14 +277,17 @@ impl StorageClient {
             bail!("Tried to update account balances but there exist gaps in the database.",);
         }
         let mut open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::update_account_balances(&mut open_connection)
+        storage_operations::update_account_balances(&mut open_connection, &self.tables)
     }
 
     /// Retrieves the highest block index in the account balance table.
     /// Returns None if the account balance table is empty.
     pub fn get_highest_block_idx_in_account_balance_table(&self) -> Result<Option<u64>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_highest_block_idx_in_account_balance_table(&open_connection)
+        storage_operations::get_highest_block_idx_in_account_balance_table(
+            &open_connection,
+            &self.tables,
+        )
     }
 
     // Retrieves the account balance at a certain block height
@@ -245,19 +298,46 @@ impl StorageClient {
         block_idx: u64,
     ) -> anyhow::Result<Option<Nat>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_account_balance_at_block_idx(&open_connection, account, block_idx)
+        storage_operations::get_account_balance_at_block_idx(
+            &open_connection,
+            &self.tables,
+            account,
+            block_idx,
+        )
     }
 
     // Retrieves the account balance at the heighest block height in the database
     // Returns None if the account does not exist in the database
     pub fn get_account_balance(&self, account: &Account) -> anyhow::Result<Option<Nat>> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_account_balance_at_highest_block_idx(&open_connection, account)
+        storage_operations::get_account_balance_at_highest_block_idx(
+            &open_connection,
+            &self.tables,
+            account,
+        )
+    }
+
+    // Retrieves the balances of the account after each block that changed it, most recent first.
+    // Only blocks with an index of at most max_block_idx are considered and at most limit balances are returned.
+    pub fn get_account_balance_history(
+        &self,
+        account: &Account,
+        max_block_idx: u64,
+        limit: u64,
+    ) -> anyhow::Result<Vec<(u64, Nat)>> {
+        let open_connection = self.storage_connection.lock().unwrap();
+        storage_operations::get_account_balance_history(
+            &open_connection,
+            &self.tables,
+            account,
+            max_block_idx,
+            limit,
+        )
     }
 
     pub fn get_block_count(&self) -> anyhow::Result<u64> {
         let open_connection = self.storage_connection.lock().unwrap();
-        storage_operations::get_block_count(&open_connection)
+        storage_operations::get_block_count(&open_connection, &self.tables)
     }
 }
 
@@ -454,6 +534,22 @@ mod tests {
                assert_eq!(metadata_write, metadata_read);
            }
 
+           #[test]
+           fn test_namespaces_share_database_file(block in blocks_strategy::<U64>(arb_amount())) {
+               let tmpdir = create_tmp_dir();
+               let file_path = tmpdir.path().join("db.sqlite");
+               let storage_client_1 = StorageClient::new_persistent_with_namespace(&file_path, Some("ledger1".to_string())).unwrap();
+               let storage_client_2 = StorageClient::new_persistent_with_namespace(&file_path, Some("ledger2".to_string())).unwrap();
+               let rosetta_block = RosettaBlock::from_encoded_block(&block.encode(), 0).unwrap();
+               storage_client_1.store_blocks(vec![rosetta_block.clone()]).unwrap();
+
+               // The blocks of one namespace are not visible in another namespace of the same database.
+               assert_eq!(storage_client_1.get_block_at_idx(0).unwrap(), Some(rosetta_block));
+               assert_eq!(storage_client_1.get_block_count().unwrap(), 1);
+               assert_eq!(storage_client_2.get_block_at_idx(0).unwrap(), None);
+               assert_eq!(storage_client_2.get_block_count().unwrap(), 0);
+           }
+
            #[test]
            fn test_updating_account_balances_for_blockchain_with_gaps(blockchain in valid_blockchain_with_gaps_strategy::<U256>(1000)){
                let storage_client_memory = StorageClient::new_in_memory().unwrap();
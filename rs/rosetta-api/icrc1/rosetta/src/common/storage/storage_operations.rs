@@ -1,4 +1,4 @@
-use crate::common::storage::types::RosettaBlock;
+use crate::common::storage::types::{RosettaBlock, TableNames};
 use crate::common::utils::utils::create_progress_bar_if_needed;
 use crate::MetadataEntry;
 use anyhow::{bail, Context};
@@ -15,19 +15,24 @@ use std::str::FromStr;
 
 pub fn store_metadata(
     connection: &mut Connection,
+    tables: &TableNames,
     metadata: Vec<MetadataEntry>,
 ) -> anyhow::Result<()> {
     let insert_tx = connection.transaction()?;
 
     for entry in metadata.into_iter() {
-        insert_tx.prepare_cached("INSERT INTO metadata (key, value) VALUES (?1, ?2) ON CONFLICT (key) DO UPDATE SET value = excluded.value;")?.execute(params![entry.key.clone(), entry.value])?;
+        insert_tx.prepare_cached(&format!("INSERT INTO {} (key, value) VALUES (?1, ?2) ON CONFLICT (key) DO UPDATE SET value = excluded.value;", tables.metadata))?.execute(params![entry.key.clone(), entry.value])?;
     }
     insert_tx.commit()?;
     Ok(())
 }
 
-pub fn get_metadata(connection: &Connection) -> anyhow::Result<Vec<MetadataEntry>> {
-    let mut stmt_metadata = connection.prepare_cached("SELECT key, value FROM metadata")?;
+pub fn get_metadata(
+    connection: &Connection,
+    tables: &TableNames,
+) -> anyhow::Result<Vec<MetadataEntry>> {
+    let mut stmt_metadata =
+        connection.prepare_cached(&format!("SELECT key, value FROM {}", tables.metadata))?;
     let rows = stmt_metadata.query_map(params![], |row| {
         Ok(MetadataEntry {
             key: row.get(0)?,
@@ -42,13 +47,17 @@ pub fn get_metadata(connection: &Connection) -> anyhow::Result<Vec<MetadataEntry
     Ok(result)
 }
 
-pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()> {
+pub fn update_account_balances(
+    connection: &mut Connection,
+    tables: &TableNames,
+) -> anyhow::Result<()> {
     // Utility method that tries to fetch the balance from the cache first and, if
     // no balance has been found, fetches it from the database
     fn get_account_balance_with_cache(
         account: &Account,
         index: u64,
         connection: &mut Connection,
+        tables: &TableNames,
         account_balances_cache: &mut HashMap<Account, BTreeMap<u64, Nat>>,
     ) -> anyhow::Result<Option<Nat>> {
         // Either fetch the balance from the cache or from the database
@@ -58,7 +67,7 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
                 .map(|(_, balance)| balance.clone())
         }) {
             Some(balance) => Ok(balance),
-            None => get_account_balance_at_block_idx(connection, account, index),
+            None => get_account_balance_at_block_idx(connection, tables, account, index),
         }
     }
 
@@ -67,11 +76,16 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
         amount: Nat,
         index: u64,
         connection: &mut Connection,
+        tables: &TableNames,
         account_balances_cache: &mut HashMap<Account, BTreeMap<u64, Nat>>,
     ) -> anyhow::Result<()> {
-        let new_balance = if let Some(balance) =
-            get_account_balance_with_cache(&account, index, connection, account_balances_cache)?
-        {
+        let new_balance = if let Some(balance) = get_account_balance_with_cache(
+            &account,
+            index,
+            connection,
+            tables,
+            account_balances_cache,
+        )? {
             Nat(balance.0.checked_sub(&amount.0).with_context(|| {
                 format!(
                     "Underflow while debiting account {} for amount {} at index {} (balance: {})",
@@ -93,11 +107,16 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
         amount: Nat,
         index: u64,
         connection: &mut Connection,
+        tables: &TableNames,
         account_balances_cache: &mut HashMap<Account, BTreeMap<u64, Nat>>,
     ) -> anyhow::Result<()> {
-        let new_balance = if let Some(balance) =
-            get_account_balance_with_cache(&account, index, connection, account_balances_cache)?
-        {
+        let new_balance = if let Some(balance) = get_account_balance_with_cache(
+            &account,
+            index,
+            connection,
+            tables,
+            account_balances_cache,
+        )? {
             Nat(balance.0.checked_add(&amount.0).with_context(|| {
                 format!(
                     "Overflow while crediting an account {} for amount {} at index {} (balance: {})",
@@ -116,9 +135,10 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
 
     // The next block to be updated is the highest block index in the account balance table + 1 if the table is not empty and 0 otherwise
     let next_block_to_be_updated =
-        get_highest_block_idx_in_account_balance_table(connection)?.map_or(0, |idx| idx + 1);
+        get_highest_block_idx_in_account_balance_table(connection, tables)?
+            .map_or(0, |idx| idx + 1);
     let highest_block_idx =
-        get_block_with_highest_block_idx(connection)?.map_or(0, |block| block.index);
+        get_block_with_highest_block_idx(connection, tables)?.map_or(0, |block| block.index);
 
     // If the blocks and account_balance tables show the same max block height then there is nothing that needs to be synced
     if highest_block_idx < next_block_to_be_updated {
@@ -131,7 +151,8 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
     const BATCH_SIZE: u64 = 100000;
     let mut batch_start_idx = next_block_to_be_updated;
     let mut batch_end_idx = batch_start_idx + BATCH_SIZE;
-    let mut rosetta_blocks = get_blocks_by_index_range(connection, batch_start_idx, batch_end_idx)?;
+    let mut rosetta_blocks =
+        get_blocks_by_index_range(connection, tables, batch_start_idx, batch_end_idx)?;
 
     // For faster inserts, keep a cache of the account balances within a batch range in memory
     // This also makes the inserting of the account balances batchable and therefore faster
@@ -147,6 +168,7 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
                         amount,
                         rosetta_block.index,
                         connection,
+                        tables,
                         &mut account_balances_cache,
                     )?;
                 }
@@ -156,6 +178,7 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
                         amount,
                         rosetta_block.index,
                         connection,
+                        tables,
                         &mut account_balances_cache,
                     )?;
                 }
@@ -168,11 +191,36 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
                         fee,
                         rosetta_block.index,
                         connection,
+                        tables,
+                        &mut account_balances_cache,
+                    )?;
+                }
+                crate::common::storage::types::IcrcOperation::SetSpendingPolicy {
+                    from, ..
+                }
+                | crate::common::storage::types::IcrcOperation::RevokeAllApprovals {
+                    from, ..
+                } => {
+                    let fee = rosetta_block
+                        .get_fee_paid()?
+                        .unwrap_or(Nat(BigUint::zero()));
+                    debit(
+                        from,
+                        fee,
+                        rosetta_block.index,
+                        connection,
+                        tables,
                         &mut account_balances_cache,
                     )?;
                 }
                 crate::common::storage::types::IcrcOperation::Transfer {
                     from, to, amount, ..
+                }
+                | crate::common::storage::types::IcrcOperation::BatchTransfer {
+                    from,
+                    to,
+                    amount,
+                    ..
                 } => {
                     let fee = rosetta_block
                         .get_fee_paid()?
@@ -187,6 +235,7 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
                         amount,
                         rosetta_block.index,
                         connection,
+                        tables,
                         &mut account_balances_cache,
                     )?;
                     debit(
@@ -194,6 +243,7 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
                         payable_amount,
                         rosetta_block.index,
                         connection,
+                        tables,
                         &mut account_balances_cache,
                     )?;
 
@@ -203,6 +253,7 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
                             fee,
                             rosetta_block.index,
                             connection,
+                            tables,
                             &mut account_balances_cache,
                         )?;
                     }
@@ -218,7 +269,7 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
         for (account, block_idx_new_balances) in account_balances_cache.drain() {
             for (block_idx, new_balance) in block_idx_new_balances {
                 insert_tx
-                    .prepare_cached("INSERT INTO account_balances (block_idx, principal, subaccount, amount) VALUES (:block_idx, :principal, :subaccount, :amount)")?
+                    .prepare_cached(&format!("INSERT INTO {} (block_idx, principal, subaccount, amount) VALUES (:block_idx, :principal, :subaccount, :amount)", tables.account_balances))?
                     .execute(named_params! {
                         ":block_idx": block_idx,
                         ":principal": account.owner.as_slice(),
@@ -230,11 +281,12 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
         insert_tx.commit()?;
 
         // Fetch the next batch of blocks
-        batch_start_idx = get_highest_block_idx_in_account_balance_table(connection)?
+        batch_start_idx = get_highest_block_idx_in_account_balance_table(connection, tables)?
             .context("No blocks in account balance table after inserting")?
             + 1;
         batch_end_idx = batch_start_idx + BATCH_SIZE;
-        rosetta_blocks = get_blocks_by_index_range(connection, batch_start_idx, batch_end_idx)?;
+        rosetta_blocks =
+            get_blocks_by_index_range(connection, tables, batch_start_idx, batch_end_idx)?;
     }
     if let Some(pb) = pb {
         pb.finish_with_message("Account Balances have been updated successfully");
@@ -245,6 +297,7 @@ pub fn update_account_balances(connection: &mut Connection) -> anyhow::Result<()
 // Stores a batch of RosettaBlocks
 pub fn store_blocks(
     connection: &mut Connection,
+    tables: &TableNames,
     rosetta_blocks: Vec<RosettaBlock>,
 ) -> anyhow::Result<()> {
     let insert_tx = connection.transaction()?;
@@ -272,7 +325,7 @@ pub fn store_blocks(
                 Some(*to.effective_subaccount()),
                 None,
                 None,
-                amount,
+                Some(amount),
                 None,
                 None,
                 None,
@@ -291,7 +344,7 @@ pub fn store_blocks(
                 Some(*to.effective_subaccount()),
                 None,
                 None,
-                amount,
+                Some(amount),
                 None,
                 fee,
                 None,
@@ -304,7 +357,7 @@ pub fn store_blocks(
                 None,
                 None,
                 None,
-                amount,
+                Some(amount),
                 None,
                 None,
                 None,
@@ -324,14 +377,64 @@ pub fn store_blocks(
                 None,
                 Some(spender.owner),
                 Some(*spender.effective_subaccount()),
-                amount,
+                Some(amount),
                 expected_allowance,
                 fee,
                 expires_at,
             ),
+            crate::common::storage::types::IcrcOperation::SetSpendingPolicy {
+                from,
+                spender,
+                period_cap,
+                fee,
+                ..
+            } => (
+                "set_spending_policy",
+                Some(from.owner),
+                Some(*from.effective_subaccount()),
+                None,
+                None,
+                Some(spender.owner),
+                Some(*spender.effective_subaccount()),
+                period_cap,
+                None,
+                fee,
+                None,
+            ),
+            crate::common::storage::types::IcrcOperation::BatchTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => (
+                "batch_transfer",
+                Some(from.owner),
+                Some(*from.effective_subaccount()),
+                Some(to.owner),
+                Some(*to.effective_subaccount()),
+                None,
+                None,
+                Some(amount),
+                None,
+                fee,
+                None,
+            ),
+            crate::common::storage::types::IcrcOperation::RevokeAllApprovals { from, fee } => (
+                "revoke_all_approvals",
+                Some(from.owner),
+                Some(*from.effective_subaccount()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                fee,
+                None,
+            ),
         };
-        insert_tx.prepare_cached(
-        "INSERT OR IGNORE INTO blocks (idx, hash, serialized_block, parent_hash, timestamp,tx_hash,operation_type,from_principal,from_subaccount,to_principal,to_subaccount,spender_principal,spender_subaccount,memo,amount,expected_allowance,fee,transaction_created_at_time,approval_expires_at) VALUES (:idx, :hash, :serialized_block, :parent_hash, :timestamp,:tx_hash,:operation_type,:from_principal,:from_subaccount,:to_principal,:to_subaccount,:spender_principal,:spender_subaccount,:memo,:amount,:expected_allowance,:fee,:transaction_created_at_time,:approval_expires_at)")?
+        insert_tx.prepare_cached(&format!(
+        "INSERT OR IGNORE INTO {} (idx, hash, serialized_block, parent_hash, timestamp,tx_hash,operation_type,from_principal,from_subaccount,to_principal,to_subaccount,spender_principal,spender_subaccount,memo,amount,expected_allowance,fee,transaction_created_at_time,approval_expires_at) VALUES (:idx, :hash, :serialized_block, :parent_hash, :timestamp,:tx_hash,:operation_type,:from_principal,:from_subaccount,:to_principal,:to_subaccount,:spender_principal,:spender_subaccount,:memo,:amount,:expected_allowance,:fee,:transaction_created_at_time,:approval_expires_at)", tables.blocks))?
                     .execute(named_params! {
                         ":idx":rosetta_block.index, 
                         ":hash":rosetta_block.clone().get_block_hash().as_slice().to_vec(), 
@@ -347,7 +450,7 @@ pub fn store_blocks(
                         ":spender_principal":spender_principal.map(|x| x.as_slice().to_vec()),
                         ":spender_subaccount":spender_subaccount,
                         ":memo":transaction.memo.map(|x| x.0.as_slice().to_vec()),
-                        ":amount":amount.to_string(),
+                        ":amount":amount.map(|amount| amount.to_string()),
                         ":expected_allowance":expected_allowance.map(|ea| ea.to_string()),
                         ":fee":fee.map(|fee| fee.to_string()),
                         ":transaction_created_at_time":transaction.created_at_time,
@@ -362,11 +465,12 @@ pub fn store_blocks(
 // Returns an Error if the query fails.
 pub fn get_block_at_idx(
     connection: &Connection,
+    tables: &TableNames,
     block_idx: u64,
 ) -> anyhow::Result<Option<RosettaBlock>> {
     let command = format!(
-        "SELECT idx,serialized_block FROM blocks WHERE idx = {}",
-        block_idx
+        "SELECT idx,serialized_block FROM {} WHERE idx = {}",
+        tables.blocks, block_idx
     );
     let mut stmt = connection.prepare_cached(&command)?;
     read_single_block(&mut stmt, params![])
@@ -377,11 +481,12 @@ pub fn get_block_at_idx(
 // Returns an Error if the query fails.
 fn get_block_at_next_idx(
     connection: &Connection,
+    tables: &TableNames,
     block_idx: u64,
 ) -> anyhow::Result<Option<RosettaBlock>> {
     let command = format!(
-        "SELECT idx,serialized_block FROM blocks WHERE idx > {} ORDER BY idx ASC LIMIT 1",
-        block_idx
+        "SELECT idx,serialized_block FROM {} WHERE idx > {} ORDER BY idx ASC LIMIT 1",
+        tables.blocks, block_idx
     );
     let mut stmt = connection.prepare_cached(&command)?;
     read_single_block(&mut stmt, params![])
@@ -391,52 +496,66 @@ fn get_block_at_next_idx(
 // Returns an Error if the query fails.
 pub fn get_block_by_hash(
     connection: &Connection,
+    tables: &TableNames,
     hash: ByteBuf,
 ) -> anyhow::Result<Option<RosettaBlock>> {
-    let mut stmt =
-        connection.prepare_cached("SELECT idx,serialized_block FROM blocks WHERE hash = ?1")?;
+    let mut stmt = connection.prepare_cached(&format!(
+        "SELECT idx,serialized_block FROM {} WHERE hash = ?1",
+        tables.blocks
+    ))?;
     read_single_block(&mut stmt, params![hash.as_slice().to_vec()])
 }
 
 pub fn get_block_with_highest_block_idx(
     connection: &Connection,
+    tables: &TableNames,
 ) -> anyhow::Result<Option<RosettaBlock>> {
-    let command =
-        "SELECT idx,serialized_block FROM blocks WHERE idx = (SELECT MAX(idx) FROM blocks)";
-    let mut stmt = connection.prepare_cached(command)?;
+    let command = format!(
+        "SELECT idx,serialized_block FROM {0} WHERE idx = (SELECT MAX(idx) FROM {0})",
+        tables.blocks
+    );
+    let mut stmt = connection.prepare_cached(&command)?;
     read_single_block(&mut stmt, params![])
 }
 
 pub fn get_block_with_lowest_block_idx(
     connection: &Connection,
+    tables: &TableNames,
 ) -> anyhow::Result<Option<RosettaBlock>> {
-    let command =
-        "SELECT idx,serialized_block FROM blocks WHERE idx = (SELECT MIN(idx) FROM blocks)";
-    let mut stmt = connection.prepare_cached(command)?;
+    let command = format!(
+        "SELECT idx,serialized_block FROM {0} WHERE idx = (SELECT MIN(idx) FROM {0})",
+        tables.blocks
+    );
+    let mut stmt = connection.prepare_cached(&command)?;
     read_single_block(&mut stmt, params![])
 }
 
 pub fn get_blocks_by_index_range(
     connection: &Connection,
+    tables: &TableNames,
     start_index: u64,
     end_index: u64,
 ) -> anyhow::Result<Vec<RosettaBlock>> {
-    let command = "SELECT idx,serialized_block FROM blocks WHERE idx>= ?1 AND idx<=?2";
-    let mut stmt = connection.prepare_cached(command)?;
+    let command = format!(
+        "SELECT idx,serialized_block FROM {} WHERE idx>= ?1 AND idx<=?2",
+        tables.blocks
+    );
+    let mut stmt = connection.prepare_cached(&command)?;
     read_blocks(&mut stmt, params![start_index, end_index])
 }
 
 pub fn get_blockchain_gaps(
     connection: &Connection,
+    tables: &TableNames,
 ) -> anyhow::Result<Vec<(RosettaBlock, RosettaBlock)>> {
     // Search for blocks, such that there is no block with index+1.
-    let command = "SELECT b1.idx,b1.serialized_block FROM blocks b1 WHERE not exists(select 1 from blocks b2 where b2.idx = b1.idx + 1)";
-    let mut stmt = connection.prepare_cached(command)?;
+    let command = format!("SELECT b1.idx,b1.serialized_block FROM {0} b1 WHERE not exists(select 1 from {0} b2 where b2.idx = b1.idx + 1)", tables.blocks);
+    let mut stmt = connection.prepare_cached(&command)?;
     let gap_starts = read_blocks(&mut stmt, params![])?;
     let mut gap_limits = vec![];
 
     for gap_start in gap_starts {
-        let gap_end = get_block_at_next_idx(connection, gap_start.index)?;
+        let gap_end = get_block_at_next_idx(connection, tables, gap_start.index)?;
         if let Some(gap_end) = gap_end {
             gap_limits.push((gap_start, gap_end));
         }
@@ -445,9 +564,9 @@ pub fn get_blockchain_gaps(
     Ok(gap_limits)
 }
 
-pub fn get_block_count(connection: &Connection) -> anyhow::Result<u64> {
-    let command = "SELECT COUNT(*) FROM blocks";
-    let mut stmt = connection.prepare_cached(command)?;
+pub fn get_block_count(connection: &Connection, tables: &TableNames) -> anyhow::Result<u64> {
+    let command = format!("SELECT COUNT(*) FROM {}", tables.blocks);
+    let mut stmt = connection.prepare_cached(&command)?;
     let mut rows = stmt.query(params![])?;
     let count: u64 = rows.next()?.unwrap().get(0)?;
     Ok(count)
@@ -457,18 +576,25 @@ pub fn get_block_count(connection: &Connection) -> anyhow::Result<u64> {
 // Returns an Error if the query fails.
 pub fn get_blocks_by_transaction_hash(
     connection: &Connection,
+    tables: &TableNames,
     hash: ByteBuf,
 ) -> anyhow::Result<Vec<RosettaBlock>> {
-    let mut stmt =
-        connection.prepare_cached("SELECT idx,serialized_block FROM blocks WHERE tx_hash = ?1")?;
+    let mut stmt = connection.prepare_cached(&format!(
+        "SELECT idx,serialized_block FROM {} WHERE tx_hash = ?1",
+        tables.blocks
+    ))?;
     read_blocks(&mut stmt, params![hash.as_slice().to_vec()])
 }
 
 pub fn get_highest_block_idx_in_account_balance_table(
     connection: &Connection,
+    tables: &TableNames,
 ) -> anyhow::Result<Option<u64>> {
     match connection
-        .prepare_cached("SELECT block_idx FROM account_balances WHERE block_idx = (SELECT MAX(block_idx) FROM account_balances)")?
+        .prepare_cached(&format!(
+            "SELECT block_idx FROM {0} WHERE block_idx = (SELECT MAX(block_idx) FROM {0})",
+            tables.account_balances
+        ))?
         .query_map(params![], |row| row.get(0))?
         .next()
     {
@@ -479,26 +605,29 @@ pub fn get_highest_block_idx_in_account_balance_table(
 
 pub fn get_account_balance_at_highest_block_idx(
     connection: &Connection,
+    tables: &TableNames,
     account: &Account,
 ) -> anyhow::Result<Option<Nat>> {
-    get_account_balance_at_block_idx(connection, account, i64::MAX as u64)
+    get_account_balance_at_block_idx(connection, tables, account, i64::MAX as u64)
 }
 
 pub fn get_account_balance_at_block_idx(
     connection: &Connection,
+    tables: &TableNames,
     account: &Account,
     block_idx: u64,
 ) -> anyhow::Result<Option<Nat>> {
     Ok(connection
-        .prepare_cached(
+        .prepare_cached(&format!(
             "SELECT amount \
-             FROM account_balances \
+             FROM {} \
              WHERE principal = :principal \
              AND subaccount = :subaccount \
              AND block_idx <= :block_idx \
              ORDER BY block_idx \
              DESC LIMIT 1",
-        )?
+            tables.account_balances
+        ))?
         .query(named_params! {
             ":principal": account.owner.as_slice(),
             ":subaccount": account.effective_subaccount(),
@@ -517,6 +646,42 @@ pub fn get_account_balance_at_block_idx(
         .transpose()?)
 }
 
+// Returns the balances of the account after each block that changed it, starting with the most recent block at or below max_block_idx.
+// Returns at most limit balances.
+pub fn get_account_balance_history(
+    connection: &Connection,
+    tables: &TableNames,
+    account: &Account,
+    max_block_idx: u64,
+    limit: u64,
+) -> anyhow::Result<Vec<(u64, Nat)>> {
+    let mut stmt = connection.prepare_cached(&format!(
+        "SELECT block_idx, amount \
+         FROM {} \
+         WHERE principal = :principal \
+         AND subaccount = :subaccount \
+         AND block_idx <= :max_block_idx \
+         ORDER BY block_idx DESC \
+         LIMIT :limit",
+        tables.account_balances
+    ))?;
+    let rows = stmt.query_map(
+        named_params! {
+            ":principal": account.owner.as_slice(),
+            ":subaccount": account.effective_subaccount(),
+            ":max_block_idx": max_block_idx,
+            ":limit": limit,
+        },
+        |row| Ok((row.get(0)?, row.get::<_, String>(1)?)),
+    )?;
+    let mut result = vec![];
+    for row in rows {
+        let (block_idx, amount) = row?;
+        result.push((block_idx, Nat::from_str(&amount)?));
+    }
+    Ok(result)
+}
+
 pub fn get_blocks_by_custom_query<P>(
     connection: &Connection,
     sql_query: String,
@@ -82,6 +82,9 @@ impl RosettaBlock {
                 IcrcOperation::Transfer { fee, .. } => fee,
                 IcrcOperation::Approve { fee, .. } => fee,
                 IcrcOperation::Burn { .. } => None,
+                IcrcOperation::SetSpendingPolicy { fee, .. } => fee,
+                IcrcOperation::RevokeAllApprovals { fee, .. } => fee,
+                IcrcOperation::BatchTransfer { fee, .. } => fee,
             }))
     }
 
@@ -299,6 +302,24 @@ pub enum IcrcOperation {
         expires_at: Option<u64>,
         fee: Option<Nat>,
     },
+    SetSpendingPolicy {
+        from: Account,
+        spender: Account,
+        period_cap: Option<Nat>,
+        period: Option<u64>,
+        allowed_recipients: Option<Vec<Account>>,
+        fee: Option<Nat>,
+    },
+    RevokeAllApprovals {
+        from: Account,
+        fee: Option<Nat>,
+    },
+    BatchTransfer {
+        from: Account,
+        to: Account,
+        amount: Nat,
+        fee: Option<Nat>,
+    },
 }
 
 impl TryFrom<BTreeMap<String, Value>> for IcrcOperation {
@@ -306,7 +327,7 @@ impl TryFrom<BTreeMap<String, Value>> for IcrcOperation {
 
     fn try_from(map: BTreeMap<String, Value>) -> anyhow::Result<Self> {
         const FIELD_PREFIX: &[&str] = &["tx"];
-        let amount: Nat = get_field(&map, FIELD_PREFIX, "amt")?;
+        let amount = || get_field::<Nat>(&map, FIELD_PREFIX, "amt");
         let fee: Option<Nat> = get_opt_field(&map, FIELD_PREFIX, "fee")?;
         match get_field::<String>(&map, FIELD_PREFIX, "op")?.as_str() {
             "burn" => {
@@ -315,12 +336,15 @@ impl TryFrom<BTreeMap<String, Value>> for IcrcOperation {
                 Ok(Self::Burn {
                     from,
                     spender,
-                    amount,
+                    amount: amount()?,
                 })
             }
             "mint" => {
                 let to: Account = get_field(&map, FIELD_PREFIX, "to")?;
-                Ok(Self::Mint { to, amount })
+                Ok(Self::Mint {
+                    to,
+                    amount: amount()?,
+                })
             }
             "xfer" => {
                 let from: Account = get_field(&map, FIELD_PREFIX, "from")?;
@@ -330,7 +354,7 @@ impl TryFrom<BTreeMap<String, Value>> for IcrcOperation {
                     from,
                     to,
                     spender,
-                    amount,
+                    amount: amount()?,
                     fee,
                 })
             }
@@ -343,14 +367,52 @@ impl TryFrom<BTreeMap<String, Value>> for IcrcOperation {
                 Ok(Self::Approve {
                     from,
                     spender,
-                    amount,
+                    amount: amount()?,
                     fee,
                     expected_allowance,
                     expires_at,
                 })
             }
+            "spend_policy" => {
+                let from: Account = get_field(&map, FIELD_PREFIX, "from")?;
+                let spender: Account = get_field(&map, FIELD_PREFIX, "spender")?;
+                let period_cap: Option<Nat> = get_opt_field(&map, FIELD_PREFIX, "amt")?;
+                let period = get_opt_field::<u64>(&map, FIELD_PREFIX, "period")?;
+                let allowed_recipients =
+                    get_opt_field::<Vec<Value>>(&map, FIELD_PREFIX, "recipients")?
+                        .map(|recipients| {
+                            recipients
+                                .into_iter()
+                                .map(Account::try_from)
+                                .collect::<Result<Vec<_>, _>>()
+                        })
+                        .transpose()
+                        .map_err(|err| anyhow!("Error decoding field 'tx.recipients': {err}"))?;
+                Ok(Self::SetSpendingPolicy {
+                    from,
+                    spender,
+                    period_cap,
+                    period,
+                    allowed_recipients,
+                    fee,
+                })
+            }
+            "revoke_all" => {
+                let from: Account = get_field(&map, FIELD_PREFIX, "from")?;
+                Ok(Self::RevokeAllApprovals { from, fee })
+            }
+            "batch_xfer" => {
+                let from: Account = get_field(&map, FIELD_PREFIX, "from")?;
+                let to: Account = get_field(&map, FIELD_PREFIX, "to")?;
+                Ok(Self::BatchTransfer {
+                    from,
+                    to,
+                    amount: amount()?,
+                    fee,
+                })
+            }
             found => {
-                bail!("Expected field 'op' to be 'burn', 'mint', 'xfer' or 'approve' but found {found}")
+                bail!("Expected field 'op' to be 'burn', 'mint', 'xfer', 'approve', 'spend_policy', 'revoke_all' or 'batch_xfer' but found {found}")
             }
         }
     }
@@ -421,6 +483,54 @@ impl From<IcrcOperation> for BTreeMap<String, Value> {
                     map.insert("fee".to_string(), Value::Nat(fee));
                 }
             }
+            Op::SetSpendingPolicy {
+                from,
+                spender,
+                period_cap,
+                period,
+                allowed_recipients,
+                fee,
+            } => {
+                map.insert("op".to_string(), Value::text("spend_policy"));
+                map.insert("from".to_string(), Value::from(from));
+                map.insert("spender".to_string(), Value::from(spender));
+                if let Some(period_cap) = period_cap {
+                    map.insert("amt".to_string(), Value::Nat(period_cap));
+                }
+                if let Some(period) = period {
+                    map.insert("period".to_string(), Value::Nat(Nat::from(period)));
+                }
+                if let Some(allowed_recipients) = allowed_recipients {
+                    map.insert(
+                        "recipients".to_string(),
+                        Value::Array(allowed_recipients.into_iter().map(Value::from).collect()),
+                    );
+                }
+                if let Some(fee) = fee {
+                    map.insert("fee".to_string(), Value::Nat(fee));
+                }
+            }
+            Op::RevokeAllApprovals { from, fee } => {
+                map.insert("op".to_string(), Value::text("revoke_all"));
+                map.insert("from".to_string(), Value::from(from));
+                if let Some(fee) = fee {
+                    map.insert("fee".to_string(), Value::Nat(fee));
+                }
+            }
+            Op::BatchTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => {
+                map.insert("op".to_string(), Value::text("batch_xfer"));
+                map.insert("from".to_string(), Value::from(from));
+                map.insert("to".to_string(), Value::from(to));
+                map.insert("amt".to_string(), Value::Nat(amount));
+                if let Some(fee) = fee {
+                    map.insert("fee".to_string(), Value::Nat(fee));
+                }
+            }
         }
         map
     }
@@ -483,6 +593,50 @@ impl MetadataEntry {
     }
 }
 
+/// The names of the tables storing the data of a single ledger.
+/// When several ledgers share one database, the tables of every ledger are prefixed
+/// with the namespace of that ledger, so that the ledgers do not overwrite each other's data.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TableNames {
+    namespace: Option<String>,
+    pub metadata: String,
+    pub blocks: String,
+    pub account_balances: String,
+}
+
+impl TableNames {
+    pub fn new(namespace: Option<String>) -> Self {
+        let mut table_names = Self {
+            namespace,
+            metadata: String::new(),
+            blocks: String::new(),
+            account_balances: String::new(),
+        };
+        table_names.metadata = table_names.namespaced("metadata");
+        table_names.blocks = table_names.namespaced("blocks");
+        table_names.account_balances = table_names.namespaced("account_balances");
+        table_names
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Returns the (quoted, if needed) name of the table or index `name` in this namespace.
+    pub fn namespaced(&self, name: &str) -> String {
+        match &self.namespace {
+            None => name.to_string(),
+            Some(namespace) => format!("\"{}_{}\"", namespace.replace('"', ""), name),
+        }
+    }
+}
+
+impl Default for TableNames {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
 impl<T> From<ic_icrc1::Operation<T>> for IcrcOperation
 where
     T: TokensType,
@@ -531,6 +685,36 @@ where
                 amount: amount.into(),
                 fee: fee.map(Into::into),
             },
+            Op::SetSpendingPolicy {
+                from,
+                spender,
+                period_cap,
+                period,
+                allowed_recipients,
+                fee,
+            } => Self::SetSpendingPolicy {
+                from,
+                spender,
+                period_cap: period_cap.map(Into::into),
+                period,
+                allowed_recipients,
+                fee: fee.map(Into::into),
+            },
+            Op::RevokeAllApprovals { from, fee } => Self::RevokeAllApprovals {
+                from,
+                fee: fee.map(Into::into),
+            },
+            Op::BatchTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => Self::BatchTransfer {
+                from,
+                to,
+                amount: amount.into(),
+                fee: fee.map(Into::into),
+            },
         }
     }
 }
@@ -648,8 +832,60 @@ mod tests {
             })
     }
 
+    fn arb_set_spending_policy() -> impl Strategy<Value = IcrcOperation> {
+        (
+            arb_account(),                         // from
+            arb_account(),                         // spender
+            option::of((arb_nat(), any::<u64>())), // period_cap and period
+            option::of(vec(arb_account(), 0..5)),  // allowed_recipients
+            option::of(arb_nat()),                 // fee
+        )
+            .prop_map(|(from, spender, cap, allowed_recipients, fee)| {
+                let (period_cap, period) = cap.unzip();
+                IcrcOperation::SetSpendingPolicy {
+                    from,
+                    spender,
+                    period_cap,
+                    period,
+                    allowed_recipients,
+                    fee,
+                }
+            })
+    }
+
+    fn arb_revoke_all_approvals() -> impl Strategy<Value = IcrcOperation> {
+        (
+            arb_account(),         // from
+            option::of(arb_nat()), // fee
+        )
+            .prop_map(|(from, fee)| IcrcOperation::RevokeAllApprovals { from, fee })
+    }
+
+    fn arb_batch_transfer() -> impl Strategy<Value = IcrcOperation> {
+        (
+            arb_account(),         // from
+            arb_account(),         // to
+            arb_nat(),             // amount
+            option::of(arb_nat()), // fee
+        )
+            .prop_map(|(from, to, amount, fee)| IcrcOperation::BatchTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            })
+    }
+
     fn arb_op() -> impl Strategy<Value = IcrcOperation> {
-        prop_oneof![arb_approve(), arb_burn(), arb_mint(), arb_transfer(),]
+        prop_oneof![
+            arb_approve(),
+            arb_burn(),
+            arb_mint(),
+            arb_transfer(),
+            arb_set_spending_policy(),
+            arb_revoke_all_approvals(),
+            arb_batch_transfer(),
+        ]
     }
 
     fn arb_memo() -> impl Strategy<Value = Memo> {
@@ -884,6 +1120,67 @@ mod tests {
                 assert_eq!(amount.into(), rosetta_amount, "amount");
                 assert_eq!(fee.map(|t| t.into()), rosetta_fee, "fee");
             }
+            (
+                ic_icrc1::Operation::SetSpendingPolicy {
+                    from,
+                    spender,
+                    period_cap,
+                    period,
+                    allowed_recipients,
+                    fee,
+                },
+                IcrcOperation::SetSpendingPolicy {
+                    from: rosetta_from,
+                    spender: rosetta_spender,
+                    period_cap: rosetta_period_cap,
+                    period: rosetta_period,
+                    allowed_recipients: rosetta_allowed_recipients,
+                    fee: rosetta_fee,
+                },
+            ) => {
+                assert_eq!(from, rosetta_from, "from");
+                assert_eq!(spender, rosetta_spender, "spender");
+                assert_eq!(
+                    period_cap.map(|t| t.into()),
+                    rosetta_period_cap,
+                    "period_cap"
+                );
+                assert_eq!(period, rosetta_period, "period");
+                assert_eq!(
+                    allowed_recipients, rosetta_allowed_recipients,
+                    "allowed_recipients"
+                );
+                assert_eq!(fee.map(|t| t.into()), rosetta_fee, "fee");
+            }
+            (
+                ic_icrc1::Operation::RevokeAllApprovals { from, fee },
+                IcrcOperation::RevokeAllApprovals {
+                    from: rosetta_from,
+                    fee: rosetta_fee,
+                },
+            ) => {
+                assert_eq!(from, rosetta_from, "from");
+                assert_eq!(fee.map(|t| t.into()), rosetta_fee, "fee");
+            }
+            (
+                ic_icrc1::Operation::BatchTransfer {
+                    from,
+                    to,
+                    amount,
+                    fee,
+                },
+                IcrcOperation::BatchTransfer {
+                    from: rosetta_from,
+                    to: rosetta_to,
+                    amount: rosetta_amount,
+                    fee: rosetta_fee,
+                },
+            ) => {
+                assert_eq!(from, rosetta_from, "from");
+                assert_eq!(to, rosetta_to, "to");
+                assert_eq!(amount.into(), rosetta_amount, "amount");
+                assert_eq!(fee.map(|t| t.into()), rosetta_fee, "fee");
+            }
             (l, r) => panic!(
                 "Found different type of operations. Operation:{l:?} rosetta's Operation:{r:?}"
             ),
@@ -202,6 +202,9 @@ pub enum OperationType {
     Approve,
     Fee,
     FeeCollector,
+    SetSpendingPolicy,
+    RevokeAllApprovals,
+    BatchTransfer,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
@@ -244,6 +247,39 @@ impl TryFrom<Option<ObjectMap>> for ApproveMetadata {
     }
 }
 
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SpendingPolicyMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period_cap: Option<Amount>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_recipients: Option<Vec<AccountIdentifier>>,
+}
+
+impl TryFrom<SpendingPolicyMetadata> for ObjectMap {
+    type Error = anyhow::Error;
+    fn try_from(d: SpendingPolicyMetadata) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(v) => match v {
+                serde_json::Value::Object(ob) => Ok(ob),
+                _ => anyhow::bail!("Could not convert SpendingPolicyMetadata to ObjectMap. Expected type Object but received: {:?}",v)
+            },
+            Err(err) => anyhow::bail!("Could not convert SpendingPolicyMetadata to ObjectMap: {:?}",err),
+        }
+    }
+}
+
+impl TryFrom<Option<ObjectMap>> for SpendingPolicyMetadata {
+    type Error = anyhow::Error;
+    fn try_from(o: Option<ObjectMap>) -> anyhow::Result<Self> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default()))
+            .context("Could not parse SpendingPolicyMetadata from JSON object")
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub struct TransactionMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
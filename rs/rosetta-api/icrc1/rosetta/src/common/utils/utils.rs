@@ -4,9 +4,12 @@ use crate::{
     common::{
         constants::{DEFAULT_BLOCKCHAIN, MIN_PROGRESS_BAR},
         storage::storage_client::StorageClient,
-        types::{ApproveMetadata, BlockMetadata, OperationType, TransactionMetadata},
+        types::{
+            ApproveMetadata, BlockMetadata, OperationType, SpendingPolicyMetadata,
+            TransactionMetadata,
+        },
     },
-    AppState,
+    AppState, MultiTokenAppState,
 };
 use anyhow::{bail, Context};
 use candid::Nat;
@@ -19,6 +22,7 @@ use rosetta_core::{
 };
 use serde_bytes::ByteBuf;
 use std::fmt::Write;
+use std::sync::Arc;
 use std::time::Duration;
 
 pub fn verify_network_id(
@@ -40,6 +44,33 @@ pub fn verify_network_id(
     Ok(())
 }
 
+/// Returns the state of the ledger identified by the given network identifier.
+/// Fails if none of the ledgers served by this instance matches the network identifier.
+pub fn get_state_from_network_id(
+    network_identifier: &NetworkIdentifier,
+    state: &MultiTokenAppState,
+) -> anyhow::Result<Arc<AppState>> {
+    let token_state = state
+        .token_states
+        .get(&network_identifier.network)
+        .with_context(|| {
+            format!(
+                "Network Identifiers did not match: Expected one of {:?} | Actual {:?}",
+                state
+                    .token_states
+                    .keys()
+                    .map(|ledger_id| NetworkIdentifier::new(
+                        DEFAULT_BLOCKCHAIN.to_owned(),
+                        ledger_id.clone()
+                    ))
+                    .collect::<Vec<_>>(),
+                network_identifier
+            )
+        })?;
+    verify_network_id(network_identifier, token_state)?;
+    Ok(token_state.clone())
+}
+
 pub fn convert_timestamp_to_millis(timestamp_nanos: u64) -> anyhow::Result<u64> {
     let millis = Duration::from_nanos(timestamp_nanos).as_millis();
     u64::try_from(millis).context(format!(
@@ -130,6 +161,9 @@ pub fn rosetta_core_operations_to_icrc1_operation(
         Burn,
         Transfer,
         Approve,
+        SetSpendingPolicy,
+        RevokeAllApprovals,
+        BatchTransfer,
     }
 
     // A builder which helps depict the icrc1 Operation and allows for an arbitrary order of rosetta_core Operations
@@ -143,6 +177,7 @@ pub fn rosetta_core_operations_to_icrc1_operation(
         expected_allowance: Option<Nat>,
         expires_at: Option<u64>,
         allowance: Option<Nat>,
+        spending_policy: Option<SpendingPolicyMetadata>,
     }
 
     impl IcrcOperationBuilder {
@@ -157,6 +192,7 @@ pub fn rosetta_core_operations_to_icrc1_operation(
                 expected_allowance: None,
                 expires_at: None,
                 allowance: None,
+                spending_policy: None,
             }
         }
 
@@ -205,6 +241,11 @@ pub fn rosetta_core_operations_to_icrc1_operation(
             self
         }
 
+        pub fn with_spending_policy(mut self, spending_policy: SpendingPolicyMetadata) -> Self {
+            self.spending_policy = Some(spending_policy);
+            self
+        }
+
         pub fn build(self) -> anyhow::Result<crate::common::storage::types::IcrcOperation> {
             Ok(match self.icrc_operation.context("Icrc Operation type needs to be of type Mint, Burn, Transfer or Approve")? {
                 IcrcOperation::Mint => {
@@ -252,6 +293,40 @@ pub fn rosetta_core_operations_to_icrc1_operation(
                     expected_allowance: self.expected_allowance,
                     expires_at: self.expires_at,
                 }},
+                IcrcOperation::SetSpendingPolicy => {
+                    if self.to.is_some() {
+                        bail!("To AccountIdentifier field is not allowed for SetSpendingPolicy operation")
+                    }
+                    let spending_policy = self.spending_policy.context("Spending policy metadata needs to be populated for SetSpendingPolicy operation")?;
+                    crate::common::storage::types::IcrcOperation::SetSpendingPolicy{
+                    from: self.from.context("From AccountIdentifier field needs to be populated for SetSpendingPolicy operation")?.try_into()?,
+                    spender: self.spender.context("Spender AccountIdentifier field needs to be populated for SetSpendingPolicy operation")?.try_into()?,
+                    period_cap: spending_policy.period_cap.map(Nat::try_from).transpose()?,
+                    period: spending_policy.period,
+                    allowed_recipients: spending_policy.allowed_recipients.map(|recipients| recipients.into_iter().map(|recipient| recipient.try_into()).collect::<anyhow::Result<Vec<_>>>()).transpose()?,
+                    fee: self.fee,
+                }},
+                IcrcOperation::RevokeAllApprovals => {
+                    if self.to.is_some() {
+                        bail!("To AccountIdentifier field is not allowed for RevokeAllApprovals operation")
+                    }
+                    if self.spender.is_some() {
+                        bail!("Spender AccountIdentifier field is not allowed for RevokeAllApprovals operation")
+                    }
+                    crate::common::storage::types::IcrcOperation::RevokeAllApprovals{
+                    from: self.from.context("From AccountIdentifier field needs to be populated for RevokeAllApprovals operation")?.try_into()?,
+                    fee: self.fee,
+                }},
+                IcrcOperation::BatchTransfer => {
+                    if self.spender.is_some() {
+                        bail!("Spender AccountIdentifier field is not allowed for BatchTransfer operation")
+                    }
+                    crate::common::storage::types::IcrcOperation::BatchTransfer{
+                    from: self.from.context("From AccountIdentifier field needs to be populated for BatchTransfer operation")?.try_into()?,
+                    to: self.to.context("To AccountIdentifier field needs to be populated for BatchTransfer operation")?.try_into()?,
+                    amount: self.amount.context("Amount field needs to be populated for BatchTransfer operation")?,
+                    fee: self.fee,
+                }},
             })
         }
     }
@@ -304,6 +379,23 @@ pub fn rosetta_core_operations_to_icrc1_operation(
                 .with_amount(Nat::try_from(amount)?)
             }
 
+            OperationType::BatchTransfer => {
+                let amount = operation
+                    .amount
+                    .context("Amount field needs to be populated for BatchTransfer operation")?;
+                let account = operation.account.context(
+                    "AccountIdentifier field needs to be populated for BatchTransfer operation",
+                )?;
+
+                if amount.value.starts_with('-') {
+                    icrc1_operation_builder.with_from_accountidentifier(account)
+                } else {
+                    icrc1_operation_builder.with_to_accountidentifier(account)
+                }
+                .with_icrc_operation(IcrcOperation::BatchTransfer)
+                .with_amount(Nat::try_from(amount)?)
+            }
+
             OperationType::Approve => {
                 let metadata = ApproveMetadata::try_from(operation.metadata)?;
                 let from_account = operation.account.context(
@@ -359,6 +451,24 @@ pub fn rosetta_core_operations_to_icrc1_operation(
                 )?;
                 icrc1_operation_builder.with_spender_accountidentifier(spender)
             }
+            OperationType::SetSpendingPolicy => {
+                let metadata = SpendingPolicyMetadata::try_from(operation.metadata)?;
+                let from_account = operation.account.context(
+                    "From AccountIdentifier field needs to be populated for SetSpendingPolicy operation",
+                )?;
+                icrc1_operation_builder
+                    .with_icrc_operation(IcrcOperation::SetSpendingPolicy)
+                    .with_from_accountidentifier(from_account)
+                    .with_spending_policy(metadata)
+            }
+            OperationType::RevokeAllApprovals => {
+                let from_account = operation.account.context(
+                    "From AccountIdentifier field needs to be populated for RevokeAllApprovals operation",
+                )?;
+                icrc1_operation_builder
+                    .with_icrc_operation(IcrcOperation::RevokeAllApprovals)
+                    .with_from_accountidentifier(from_account)
+            }
             // We do not have to convert this Operation on the icrc1 side as the crate::common::storage::types::IcrcOperation does not know anything about the FeeCollector
             OperationType::FeeCollector => icrc1_operation_builder,
         };
@@ -543,6 +653,158 @@ pub fn icrc1_operation_to_rosetta_core_operations(
                 ));
             }
         }
+
+        crate::common::storage::types::IcrcOperation::SetSpendingPolicy {
+            from,
+            spender,
+            period_cap,
+            period,
+            allowed_recipients,
+            fee,
+        } => {
+            operations.push(rosetta_core::objects::Operation::new(
+                0,
+                OperationType::SetSpendingPolicy.to_string(),
+                Some(from.into()),
+                None,
+                None,
+                Some(
+                    SpendingPolicyMetadata {
+                        period_cap: period_cap.map(|period_cap| {
+                            Amount::new(BigInt::from(period_cap.0), currency.clone())
+                        }),
+                        period,
+                        allowed_recipients: allowed_recipients.map(|recipients| {
+                            recipients
+                                .into_iter()
+                                .map(|recipient| recipient.into())
+                                .collect()
+                        }),
+                    }
+                    .try_into()?,
+                ),
+            ));
+
+            operations.push(rosetta_core::objects::Operation::new(
+                1,
+                OperationType::Spender.to_string(),
+                Some(spender.into()),
+                None,
+                None,
+                None,
+            ));
+
+            if let Some(fee_paid) = fee_payed {
+                operations.push(rosetta_core::objects::Operation::new(
+                    2,
+                    OperationType::Fee.to_string(),
+                    Some(from.into()),
+                    Some(Amount::new(
+                        BigInt::from_biguint(num_bigint::Sign::Minus, fee_paid.0),
+                        currency,
+                    )),
+                    None,
+                    // If the fee inside the operation is set that means the User set the fee and the Ledger did nothing
+                    Some(
+                        FeeMetadata {
+                            fee_set_by: match fee {
+                                Some(_) => FeeSetter::User,
+                                None => FeeSetter::Ledger,
+                            },
+                        }
+                        .try_into()?,
+                    ),
+                ));
+            }
+        }
+
+        crate::common::storage::types::IcrcOperation::BatchTransfer {
+            from,
+            to,
+            amount,
+            fee,
+        } => {
+            operations.push(rosetta_core::objects::Operation::new(
+                0,
+                OperationType::BatchTransfer.to_string(),
+                Some(to.into()),
+                Some(rosetta_core::objects::Amount::new(
+                    BigInt::from(amount.0.clone()),
+                    currency.clone(),
+                )),
+                None,
+                None,
+            ));
+
+            operations.push(rosetta_core::objects::Operation::new(
+                1,
+                OperationType::BatchTransfer.to_string(),
+                Some(from.into()),
+                Some(rosetta_core::objects::Amount::new(
+                    BigInt::from_biguint(num_bigint::Sign::Minus, amount.0),
+                    currency.clone(),
+                )),
+                None,
+                None,
+            ));
+
+            if let Some(fee_paid) = fee_payed {
+                operations.push(rosetta_core::objects::Operation::new(
+                    2,
+                    OperationType::Fee.to_string(),
+                    Some(from.into()),
+                    Some(Amount::new(
+                        BigInt::from_biguint(num_bigint::Sign::Minus, fee_paid.0),
+                        currency,
+                    )),
+                    None,
+                    // If the fee inside the operation is set that means the User set the fee and the Ledger did nothing
+                    Some(
+                        FeeMetadata {
+                            fee_set_by: match fee {
+                                Some(_) => FeeSetter::User,
+                                None => FeeSetter::Ledger,
+                            },
+                        }
+                        .try_into()?,
+                    ),
+                ));
+            }
+        }
+
+        crate::common::storage::types::IcrcOperation::RevokeAllApprovals { from, fee } => {
+            operations.push(rosetta_core::objects::Operation::new(
+                0,
+                OperationType::RevokeAllApprovals.to_string(),
+                Some(from.into()),
+                None,
+                None,
+                None,
+            ));
+
+            if let Some(fee_paid) = fee_payed {
+                operations.push(rosetta_core::objects::Operation::new(
+                    1,
+                    OperationType::Fee.to_string(),
+                    Some(from.into()),
+                    Some(Amount::new(
+                        BigInt::from_biguint(num_bigint::Sign::Minus, fee_paid.0),
+                        currency,
+                    )),
+                    None,
+                    // If the fee inside the operation is set that means the User set the fee and the Ledger did nothing
+                    Some(
+                        FeeMetadata {
+                            fee_set_by: match fee {
+                                Some(_) => FeeSetter::User,
+                                None => FeeSetter::Ledger,
+                            },
+                        }
+                        .try_into()?,
+                    ),
+                ));
+            }
+        }
     };
 
     Ok(operations)
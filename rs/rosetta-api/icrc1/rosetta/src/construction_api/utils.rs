@@ -230,6 +230,15 @@ pub fn build_icrc1_ledger_canister_method_args(
         crate::common::storage::types::IcrcOperation::Mint { .. } => {
             bail!("Mint Operation not supported")
         }
+        crate::common::storage::types::IcrcOperation::SetSpendingPolicy { .. } => {
+            bail!("SetSpendingPolicy Operation not supported")
+        }
+        crate::common::storage::types::IcrcOperation::RevokeAllApprovals { .. } => {
+            bail!("RevokeAllApprovals Operation not supported")
+        }
+        crate::common::storage::types::IcrcOperation::BatchTransfer { .. } => {
+            bail!("BatchTransfer Operation not supported")
+        }
         crate::common::storage::types::IcrcOperation::Approve {
             from,
             spender,
@@ -297,6 +306,15 @@ fn extract_caller_principal_from_icrc1_ledger_operation(
         crate::common::storage::types::IcrcOperation::Mint { .. } => {
             bail!("Mint Operation not supported")
         }
+        crate::common::storage::types::IcrcOperation::SetSpendingPolicy { .. } => {
+            bail!("SetSpendingPolicy Operation not supported")
+        }
+        crate::common::storage::types::IcrcOperation::RevokeAllApprovals { .. } => {
+            bail!("RevokeAllApprovals Operation not supported")
+        }
+        crate::common::storage::types::IcrcOperation::BatchTransfer { .. } => {
+            bail!("BatchTransfer Operation not supported")
+        }
         crate::common::storage::types::IcrcOperation::Approve { from, .. } => from.owner,
         crate::common::storage::types::IcrcOperation::Transfer { from, spender, .. } => {
             spender.unwrap_or(*from).owner
@@ -18,6 +18,9 @@ use crate::common::{
 };
 use crate::data_api::types::QueryBlockRangeRequest;
 use crate::data_api::types::QueryBlockRangeResponse;
+use crate::data_api::types::{
+    AccountBalanceHistoryRequest, AccountBalanceHistoryResponse, BalanceAtBlock,
+};
 use candid::Nat;
 use candid::Principal;
 use ic_ledger_core::tokens::Zero;
@@ -29,12 +32,14 @@ use rosetta_core::response_types::SearchTransactionsResponse;
 use rosetta_core::{identifiers::*, miscellaneous::Version, objects::*, response_types::*};
 use strum::IntoEnumIterator;
 
-pub fn network_list(ledger_id: &Principal) -> NetworkListResponse {
+pub fn network_list(ledger_ids: &[Principal]) -> NetworkListResponse {
     NetworkListResponse {
-        network_identifiers: vec![NetworkIdentifier::new(
-            DEFAULT_BLOCKCHAIN.to_owned(),
-            ledger_id.to_string(),
-        )],
+        network_identifiers: ledger_ids
+            .iter()
+            .map(|ledger_id| {
+                NetworkIdentifier::new(DEFAULT_BLOCKCHAIN.to_owned(), ledger_id.to_string())
+            })
+            .collect(),
     }
 }
 
@@ -336,8 +341,10 @@ pub fn search_transactions(
     }
 
     // Base query to fetch the blocks
-    let mut command =
-        String::from("SELECT idx,serialized_block FROM blocks WHERE idx <= :max_block_idx ");
+    let mut command = format!(
+        "SELECT idx,serialized_block FROM {} WHERE idx <= :max_block_idx ",
+        storage_client.table_names().blocks
+    );
     let mut parameters: Vec<(&str, Box<dyn rusqlite::ToSql>)> = Vec::new();
 
     parameters.push((":max_block_idx", Box::new(start_idx)));
@@ -443,6 +450,63 @@ pub fn search_transactions(
     })
 }
 
+pub fn events_blocks(
+    storage_client: &StorageClient,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<EventsBlocksResponse, Error> {
+    // Without any stored block there are no events yet.
+    let max_sequence = match storage_client
+        .get_block_with_highest_block_idx()
+        .map_err(|e| Error::unable_to_find_block(&e))?
+    {
+        Some(block) => block.index,
+        None => {
+            return Ok(EventsBlocksResponse {
+                max_sequence: 0,
+                events: vec![],
+            })
+        }
+    };
+
+    let limit = match limit {
+        Some(limit) => u64::try_from(limit).map_err(|_| {
+            Error::request_processing_error(&format!("Limit must be non-negative: {}", limit))
+        })?,
+        None => MAX_BLOCKS_PER_QUERY_BLOCK_RANGE_REQUEST,
+    }
+    .min(MAX_BLOCKS_PER_QUERY_BLOCK_RANGE_REQUEST);
+
+    // If no offset is given, the events up to the tip are returned.
+    let start_idx = match offset {
+        Some(offset) => u64::try_from(offset).map_err(|_| {
+            Error::request_processing_error(&format!("Offset must be non-negative: {}", offset))
+        })?,
+        None => (max_sequence + 1).saturating_sub(limit),
+    };
+
+    let mut events = vec![];
+    if limit > 0 && start_idx <= max_sequence {
+        let end_idx = max_sequence.min(start_idx.saturating_add(limit - 1));
+        for rosetta_block in storage_client
+            .get_blocks_by_index_range(start_idx, end_idx)
+            .map_err(|e| Error::unable_to_find_block(&e))?
+        {
+            events.push(BlockEvent {
+                sequence: rosetta_block.index as i64,
+                block_identifier: rosetta_block.get_block_identifier(),
+                // ICRC-1 ledgers are final, blocks are never removed.
+                type_: BlockEventType::BlockAdded,
+            });
+        }
+    }
+
+    Ok(EventsBlocksResponse {
+        max_sequence: max_sequence as i64,
+        events,
+    })
+}
+
 pub fn initial_sync_is_completed(
     storage_client: &StorageClient,
     sync_state: Arc<Mutex<Option<bool>>>,
@@ -510,6 +574,55 @@ pub fn call(
                 idempotent,
             ))
         }
+        "account_balance_history" => {
+            let account_balance_history = AccountBalanceHistoryRequest::try_from(parameters)
+                .map_err(|err| Error::parsing_unsuccessful(&err))?;
+            let account = Account::try_from(account_balance_history.account_identifier)
+                .map_err(|err| Error::parsing_unsuccessful(&err))?;
+            let highest_processed_block_idx = storage_client
+                .get_highest_block_idx_in_account_balance_table()
+                .map_err(|err| Error::unable_to_find_block(&err))?
+                .unwrap_or(0);
+            let max_block_idx = account_balance_history
+                .max_block_index
+                .unwrap_or(highest_processed_block_idx)
+                .min(highest_processed_block_idx);
+            let limit = account_balance_history
+                .limit
+                .unwrap_or(MAX_BLOCKS_PER_QUERY_BLOCK_RANGE_REQUEST)
+                .min(MAX_BLOCKS_PER_QUERY_BLOCK_RANGE_REQUEST);
+
+            let mut balances = vec![];
+            for (block_idx, balance) in storage_client
+                .get_account_balance_history(&account, max_block_idx, limit)
+                .map_err(|err| Error::unable_to_find_account_balance(&err))?
+            {
+                let block = storage_client
+                    .get_block_at_idx(block_idx)
+                    .map_err(|err| Error::unable_to_find_block(&err))?
+                    .ok_or_else(|| {
+                        Error::unable_to_find_block(&format!(
+                            "Block at index {} could not be found",
+                            block_idx
+                        ))
+                    })?;
+                balances.push(BalanceAtBlock {
+                    block_identifier: block.get_block_identifier(),
+                    balance: Amount::new(BigInt::from(balance), currency.clone()),
+                });
+            }
+            // The balances of blocks below the highest processed block never change.
+            let idempotent = account_balance_history
+                .max_block_index
+                .map_or(false, |max_block_index| {
+                    max_block_index <= highest_processed_block_idx
+                });
+            Ok(CallResponse::new(
+                ObjectMap::try_from(AccountBalanceHistoryResponse { balances })
+                    .map_err(|err| Error::parsing_unsuccessful(&err))?,
+                idempotent,
+            ))
+        }
         _ => Err(Error::processing_construction_failed(&format!(
             "Method {} not supported",
             method_name
@@ -582,6 +695,93 @@ mod test {
                     }
                     }
 
+                    #[test]
+                    fn test_events_blocks_service(blockchain in valid_blockchain_strategy::<U256>(BLOCKCHAIN_LENGTH)){
+                        let storage_client_memory = Arc::new(StorageClient::new_in_memory().unwrap());
+                        let mut rosetta_blocks = vec![];
+                        for block in blockchain.into_iter() {
+                            // We only push Mint blocks since `update_account_balances` will
+                            // complain if we e.g., transfer from an account with no balance.
+                            if let ic_icrc1::Operation::Mint{..} = block.transaction.operation {
+                                rosetta_blocks.push(RosettaBlock::from_generic_block(encoded_block_to_generic_block(&block.encode()), rosetta_blocks.len() as u64).unwrap());
+                            }
+                        }
+
+                        // Without stored blocks there are no events.
+                        assert_eq!(events_blocks(&storage_client_memory, None, None).unwrap(), EventsBlocksResponse {
+                            max_sequence: 0,
+                            events: vec![],
+                        });
+                        if !rosetta_blocks.is_empty() {
+                            storage_client_memory.store_blocks(rosetta_blocks.clone()).unwrap();
+                            let max_sequence = rosetta_blocks.len() as i64 - 1;
+
+                            // All blocks are returned in order when starting at the genesis block.
+                            let response = events_blocks(&storage_client_memory, Some(0), None).unwrap();
+                            assert_eq!(response.max_sequence, max_sequence);
+                            assert_eq!(response.events, rosetta_blocks.iter().map(|block| BlockEvent {
+                                sequence: block.index as i64,
+                                block_identifier: block.clone().get_block_identifier(),
+                                type_: BlockEventType::BlockAdded,
+                            }).collect::<Vec<_>>());
+
+                            // Without an offset the events up to the tip are returned.
+                            let response = events_blocks(&storage_client_memory, None, Some(1)).unwrap();
+                            assert_eq!(response.events.len(), 1);
+                            assert_eq!(response.events[0].sequence, max_sequence);
+
+                            // An offset beyond the tip returns no events.
+                            let response = events_blocks(&storage_client_memory, Some(max_sequence + 1), None).unwrap();
+                            assert!(response.events.is_empty());
+
+                            assert!(events_blocks(&storage_client_memory, Some(-1), None).is_err());
+                        }
+                    }
+
+                    #[test]
+                    fn test_account_balance_history(blockchain in valid_blockchain_strategy::<U256>(BLOCKCHAIN_LENGTH)){
+                        let storage_client_memory = Arc::new(StorageClient::new_in_memory().unwrap());
+                        let mut rosetta_blocks = vec![];
+                        for block in blockchain.into_iter() {
+                            if let ic_icrc1::Operation::Mint{..} = block.transaction.operation {
+                                rosetta_blocks.push(RosettaBlock::from_generic_block(encoded_block_to_generic_block(&block.encode()), rosetta_blocks.len() as u64).unwrap());
+                            }
+                        }
+                        if !rosetta_blocks.is_empty() {
+                            storage_client_memory.store_blocks(rosetta_blocks.clone()).unwrap();
+                            storage_client_memory.update_account_balances().unwrap();
+                            let currency = Currency::new("ICP".to_string(), 8);
+
+                            for rosetta_block in rosetta_blocks.iter() {
+                                let to = match rosetta_block.get_transaction().operation {
+                                    IcrcOperation::Mint { to, .. } => to,
+                                    _ => unreachable!("only mint blocks are stored"),
+                                };
+                                let response = call(
+                                    &storage_client_memory,
+                                    "account_balance_history",
+                                    ObjectMap::try_from(AccountBalanceHistoryRequest {
+                                        account_identifier: to.into(),
+                                        max_block_index: Some(rosetta_block.index),
+                                        limit: None,
+                                    })
+                                    .unwrap(),
+                                    currency.clone(),
+                                )
+                                .unwrap();
+                                assert!(response.idempotent);
+                                let history = AccountBalanceHistoryResponse::try_from(response.result).unwrap();
+
+                                // The most recent balance is the one right after this block.
+                                let balance = storage_client_memory.get_account_balance_at_block_idx(&to, rosetta_block.index).unwrap().unwrap();
+                                assert_eq!(history.balances[0].block_identifier, rosetta_block.clone().get_block_identifier());
+                                assert_eq!(history.balances[0].balance, Amount::new(BigInt::from(balance), currency.clone()));
+                                // Each balance in the history is the balance at a block with a lower index.
+                                assert!(history.balances.windows(2).all(|w| w[0].block_identifier.index > w[1].block_identifier.index));
+                            }
+                        }
+                    }
+
                     #[test]
                     fn test_block_service(blockchain in valid_blockchain_strategy::<U256>(BLOCKCHAIN_LENGTH)){
                         let storage_client_memory = Arc::new(StorageClient::new_in_memory().unwrap());
@@ -1074,6 +1274,22 @@ mod test {
                         );
                         assert_eq!(result.len(), num_of_approve_transactions);
 
+                        search_transactions_request.type_ = Some("BATCH_TRANSFER".to_string());
+                        let num_of_batch_transfer_transactions = rosetta_blocks
+                            .iter()
+                            .filter(|block| {
+                                matches!(
+                                    block.block.transaction.operation,
+                                    IcrcOperation::BatchTransfer { .. }
+                                )
+                            })
+                            .count();
+                        let result = traverse_all_transactions(
+                            &storage_client_memory,
+                            search_transactions_request.clone(),
+                        );
+                        assert_eq!(result.len(), num_of_batch_transfer_transactions);
+
                         search_transactions_request = SearchTransactionsRequest {
                             ..Default::default()
                         };
@@ -1085,6 +1301,9 @@ mod test {
                                 IcrcOperation::Mint { to, .. } => to,
                                 IcrcOperation::Burn { from, .. } => from,
                                 IcrcOperation::Approve { from, .. } => from,
+                                IcrcOperation::SetSpendingPolicy { from, .. } => from,
+                                IcrcOperation::RevokeAllApprovals { from, .. } => from,
+                                IcrcOperation::BatchTransfer { from, .. } => from,
                             }
                             .into(),
                         );
@@ -1104,6 +1323,15 @@ mod test {
                                             .try_into()
                                             .unwrap(),
                                     ),
+                                IcrcOperation::BatchTransfer { from, to, .. } => [from, to]
+                                    .contains(
+                                        &search_transactions_request
+                                            .account_identifier
+                                            .clone()
+                                            .unwrap()
+                                            .try_into()
+                                            .unwrap(),
+                                    ),
                                 IcrcOperation::Mint { to, .. } => {
                                     to == search_transactions_request
                                         .account_identifier
@@ -1122,15 +1350,25 @@ mod test {
                                             .try_into()
                                             .unwrap(),
                                     ),
-                                IcrcOperation::Approve { from, spender, .. } => [from, spender]
-                                    .contains(
+                                IcrcOperation::Approve { from, spender, .. }
+                                | IcrcOperation::SetSpendingPolicy { from, spender, .. } => {
+                                    [from, spender].contains(
                                         &search_transactions_request
                                             .account_identifier
                                             .clone()
                                             .unwrap()
                                             .try_into()
                                             .unwrap(),
-                                    ),
+                                    )
+                                }
+                                IcrcOperation::RevokeAllApprovals { from, .. } => {
+                                    from == search_transactions_request
+                                        .account_identifier
+                                        .clone()
+                                        .unwrap()
+                                        .try_into()
+                                        .unwrap()
+                                }
                             })
                             .count();
 
@@ -60,3 +60,73 @@ impl TryFrom<ObjectMap> for QueryBlockRangeResponse {
         })
     }
 }
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct AccountBalanceHistoryRequest {
+    pub account_identifier: rosetta_core::identifiers::AccountIdentifier,
+    /// Only balances at or below this block index are returned. Defaults to the highest processed block.
+    pub max_block_index: Option<u64>,
+    /// The maximum number of balances to return.
+    pub limit: Option<u64>,
+}
+
+impl TryFrom<AccountBalanceHistoryRequest> for ObjectMap {
+    type Error = anyhow::Error;
+    fn try_from(d: AccountBalanceHistoryRequest) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(v) => match v {
+                serde_json::Value::Object(ob) => Ok(ob),
+                _ => anyhow::bail!("Could not convert AccountBalanceHistoryRequest to ObjectMap. Expected type Object but received: {:?}",v)
+            },Err(err) => anyhow::bail!("Could not convert AccountBalanceHistoryRequest to ObjectMap: {:?}",err),
+        }
+    }
+}
+
+impl TryFrom<ObjectMap> for AccountBalanceHistoryRequest {
+    type Error = String;
+    fn try_from(o: ObjectMap) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o)).map_err(|e| {
+            format!(
+                "Could not parse AccountBalanceHistoryRequest from JSON object: {}",
+                e
+            )
+        })
+    }
+}
+
+/// The balance of an account right after the block that changed it.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct BalanceAtBlock {
+    pub block_identifier: rosetta_core::identifiers::BlockIdentifier,
+    pub balance: rosetta_core::objects::Amount,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct AccountBalanceHistoryResponse {
+    /// The balances of the account, sorted from the most recent to the oldest block.
+    pub balances: Vec<BalanceAtBlock>,
+}
+
+impl TryFrom<AccountBalanceHistoryResponse> for ObjectMap {
+    type Error = anyhow::Error;
+    fn try_from(d: AccountBalanceHistoryResponse) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(v) => match v {
+                serde_json::Value::Object(ob) => Ok(ob),
+                _ => anyhow::bail!("Could not convert AccountBalanceHistoryResponse to ObjectMap. Expected type Object but received: {:?}",v)
+            },Err(err) => anyhow::bail!("Could not convert AccountBalanceHistoryResponse to ObjectMap: {:?}",err),
+        }
+    }
+}
+
+impl TryFrom<ObjectMap> for AccountBalanceHistoryResponse {
+    type Error = String;
+    fn try_from(o: ObjectMap) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o)).map_err(|e| {
+            format!(
+                "Could not parse AccountBalanceHistoryResponse from JSON object: {}",
+                e
+            )
+        })
+    }
+}
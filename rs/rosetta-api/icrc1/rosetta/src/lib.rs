@@ -8,7 +8,7 @@ use icrc_ledger_types::icrc3::archive::ArchiveInfo;
 use num_traits::ToPrimitive;
 use rosetta_core::objects::Currency;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     sync::{Arc, Mutex},
 };
 use tokio::sync::Mutex as AsyncMutex;
@@ -26,6 +26,23 @@ pub struct AppState {
     pub metadata: Metadata,
 }
 
+/// The state of all the ledgers served by a single Rosetta instance.
+/// Every ledger is identified by its own network identifier, whose network is the ledger id.
+pub struct MultiTokenAppState {
+    pub token_states: BTreeMap<String, Arc<AppState>>,
+}
+
+impl MultiTokenAppState {
+    pub fn new(token_states: Vec<Arc<AppState>>) -> Self {
+        Self {
+            token_states: token_states
+                .into_iter()
+                .map(|state| (state.ledger_id.to_string(), state))
+                .collect(),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Metadata {
     pub symbol: String,
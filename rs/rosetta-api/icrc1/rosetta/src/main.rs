@@ -16,7 +16,7 @@ use ic_icrc_rosetta::{
     construction_api::endpoints::*,
     data_api::endpoints::*,
     ledger_blocks_synchronization::blocks_synchronizer::start_synching_blocks,
-    AppState, Metadata,
+    AppState, Metadata, MultiTokenAppState,
 };
 use ic_sys::fs::write_string_using_tmp_file;
 use icrc_ledger_agent::{CallMode, Icrc1Agent};
@@ -56,14 +56,21 @@ enum NetworkType {
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
-    ledger_id: CanisterId,
+    /// The ICRC-1 ledger to connect to.
+    /// Can be given multiple times to serve several ledgers from a single instance. Each ledger is
+    /// identified by a network identifier whose network is the ledger id.
+    /// With more than one ledger, the tables of each ledger are namespaced by its ledger id in the store.
+    #[arg(short, long, required = true)]
+    ledger_id: Vec<CanisterId>,
 
     /// The symbol of the ICRC-1 token.
     /// If set Rosetta will check the symbol against the ledger it connects to. If the symbol does not match, it will exit.
+    /// Can only be set if Rosetta connects to a single ledger.
     #[arg(long)]
     icrc1_symbol: Option<String>,
 
+    /// The decimals of the ICRC-1 token.
+    /// Can only be set if Rosetta connects to a single ledger.
     #[arg(long)]
     icrc1_decimals: Option<u8>,
 
@@ -140,6 +147,25 @@ impl Args {
     fn are_metadata_args_set(&self) -> bool {
         self.icrc1_symbol.is_some() && self.icrc1_decimals.is_some()
     }
+
+    /// Returns the namespace of the tables of the given ledger in the store.
+    /// A single ledger uses tables without namespace, such that stores created
+    /// before Rosetta supported multiple ledgers remain usable.
+    fn storage_namespace(&self, ledger_id: &CanisterId) -> Option<String> {
+        if self.ledger_id.len() > 1 {
+            Some(ledger_id.to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Opens a new connection to the persistent store of the given ledger.
+    fn new_persistent_storage(&self, ledger_id: &CanisterId) -> Result<StorageClient> {
+        StorageClient::new_persistent_with_namespace(
+            &self.store_file,
+            self.storage_namespace(ledger_id),
+        )
+    }
 }
 
 fn init_logs(log_level: Level, log_file_path: &PathBuf) -> anyhow::Result<WorkerGuard> {
@@ -284,10 +310,9 @@ async fn main() -> Result<()> {
 
     let _guard = init_logs(args.log_level, &args.log_file)?;
 
-    let storage = Arc::new(match args.store_type {
-        StoreType::InMemory => StorageClient::new_in_memory()?,
-        StoreType::File => StorageClient::new_persistent(&args.store_file)?,
-    });
+    if args.ledger_id.len() > 1 && (args.icrc1_symbol.is_some() || args.icrc1_decimals.is_some()) {
+        bail!("'icrc1-symbol' and 'icrc1-decimals' can only be specified for a single ledger.");
+    }
 
     let network_url = args.effective_network_url();
 
@@ -312,52 +337,66 @@ async fn main() -> Result<()> {
         ic_agent.status().await?.replica_health_status
     );
 
-    let icrc1_agent = Arc::new(Icrc1Agent {
-        agent: ic_agent,
-        ledger_canister_id: args.ledger_id.into(),
-    });
+    let mut token_states = vec![];
+    for ledger_id in args.ledger_id.iter() {
+        let storage = Arc::new(match args.store_type {
+            StoreType::InMemory => StorageClient::new_in_memory()?,
+            StoreType::File => args.new_persistent_storage(ledger_id)?,
+        });
 
-    let metadata = load_metadata(&args, &icrc1_agent, &storage).await?;
-    if let Some(token_symbol) = args.icrc1_symbol.clone() {
-        if metadata.symbol != token_symbol {
-            bail!(
-                "Provided symbol does not match symbol retrieved in online mode. Expected: {}, Got: {}",
-                metadata.symbol, token_symbol
-            );
-        }
-    }
+        let icrc1_agent = Arc::new(Icrc1Agent {
+            agent: ic_agent.clone(),
+            ledger_canister_id: (*ledger_id).into(),
+        });
 
-    info!(
-        "ICRC Rosetta is connected to the ICRC-1 ledger: {}",
-        args.ledger_id
-    );
-    info!(
-        "The token symbol of the ICRC-1 ledger is: {}",
-        metadata.symbol
-    );
+        let metadata = load_metadata(&args, &icrc1_agent, &storage).await?;
+        if let Some(token_symbol) = args.icrc1_symbol.clone() {
+            if metadata.symbol != token_symbol {
+                bail!(
+                    "Provided symbol does not match symbol retrieved in online mode. Expected: {}, Got: {}",
+                    metadata.symbol, token_symbol
+                );
+            }
+        }
 
-    let shared_state = Arc::new(AppState {
-        icrc1_agent: icrc1_agent.clone(),
-        ledger_id: args.ledger_id,
-        synched: Arc::new(Mutex::new(None)),
-        storage: storage.clone(),
-        archive_canister_ids: Arc::new(AsyncMutex::new(vec![])),
-        metadata,
-    });
+        info!(
+            "ICRC Rosetta is connected to the ICRC-1 ledger: {}",
+            ledger_id
+        );
+        info!(
+            "The token symbol of the ICRC-1 ledger is: {}",
+            metadata.symbol
+        );
+
+        token_states.push(Arc::new(AppState {
+            icrc1_agent,
+            ledger_id: *ledger_id,
+            synched: Arc::new(Mutex::new(None)),
+            storage,
+            archive_canister_ids: Arc::new(AsyncMutex::new(vec![])),
+            metadata,
+        }));
+    }
+    let shared_state = Arc::new(MultiTokenAppState::new(token_states.clone()));
 
     if args.exit_on_sync {
         if args.offline {
             bail!("'exit-on-sync' and 'offline' parameters cannot be specified at the same time.");
         }
 
-        info!("Starting to sync blocks");
-        start_synching_blocks(
-            icrc1_agent.clone(),
-            storage.clone(),
-            *MAXIMUM_BLOCKS_PER_REQUEST,
-            Arc::new(AsyncMutex::new(vec![])),
-        )
-        .await?;
+        for token_state in token_states.iter() {
+            info!(
+                "Starting to sync blocks of ledger {}",
+                token_state.ledger_id
+            );
+            start_synching_blocks(
+                token_state.icrc1_agent.clone(),
+                token_state.storage.clone(),
+                *MAXIMUM_BLOCKS_PER_REQUEST,
+                Arc::new(AsyncMutex::new(vec![])),
+            )
+            .await?;
+        }
 
         process::exit(0);
     }
@@ -373,6 +412,7 @@ async fn main() -> Result<()> {
         .route("/account/balance", post(account_balance))
         .route("/block/transaction", post(block_transaction))
         .route("/search/transactions", post(search_transactions))
+        .route("/events/blocks", post(events_blocks))
         .route("/mempool", post(mempool))
         .route("/mempool/transaction", post(mempool_transaction))
         .route("/construction/derive", post(construction_derive))
@@ -395,7 +435,7 @@ async fn main() -> Result<()> {
     let rosetta_url = format!("0.0.0.0:{}", args.get_port());
     let tcp_listener = TcpListener::bind(rosetta_url.clone()).await?;
 
-    if let Some(port_file) = args.port_file {
+    if let Some(port_file) = &args.port_file {
         write_string_using_tmp_file(
             port_file,
             tcp_listener.local_addr()?.port().to_string().as_str(),
@@ -403,38 +443,43 @@ async fn main() -> Result<()> {
     }
 
     if !args.offline {
-        tokio::task::spawn_blocking(move || {
-            let mut sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
-
+        // Every ledger is synchronized by its own task, such that a ledger that is
+        // slow or unavailable does not hold back the other ledgers.
+        for token_state in token_states {
             let block_sync_storage = match args.store_type {
-                StoreType::InMemory => storage.clone(),
-                StoreType::File => {
-                    Arc::new(StorageClient::new_persistent(&args.store_file).unwrap())
-                }
+                StoreType::InMemory => token_state.storage.clone(),
+                StoreType::File => Arc::new(args.new_persistent_storage(&token_state.ledger_id)?),
             };
 
-            tokio::runtime::Handle::current().block_on(async {
-                loop {
-                    if let Err(e) = start_synching_blocks(
-                        icrc1_agent.clone(),
-                        block_sync_storage.clone(),
-                        *MAXIMUM_BLOCKS_PER_REQUEST,
-                        shared_state.clone().archive_canister_ids.clone(),
-                    )
-                    .await
-                    {
-                        error!("Error while syncing blocks: {}", e);
-                        sync_wait_secs =
-                            std::cmp::min(sync_wait_secs * 2, MAX_BLOCK_SYNC_WAIT_SECS);
-                        info!("Retrying in {} seconds.", sync_wait_secs);
-                    } else {
-                        sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
+            tokio::task::spawn_blocking(move || {
+                let mut sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
+
+                tokio::runtime::Handle::current().block_on(async {
+                    loop {
+                        if let Err(e) = start_synching_blocks(
+                            token_state.icrc1_agent.clone(),
+                            block_sync_storage.clone(),
+                            *MAXIMUM_BLOCKS_PER_REQUEST,
+                            token_state.archive_canister_ids.clone(),
+                        )
+                        .await
+                        {
+                            error!(
+                                "Error while syncing blocks of ledger {}: {}",
+                                token_state.ledger_id, e
+                            );
+                            sync_wait_secs =
+                                std::cmp::min(sync_wait_secs * 2, MAX_BLOCK_SYNC_WAIT_SECS);
+                            info!("Retrying in {} seconds.", sync_wait_secs);
+                        } else {
+                            sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
+                        }
+
+                        tokio::time::sleep(std::time::Duration::from_secs(sync_wait_secs)).await;
                     }
-
-                    tokio::time::sleep(std::time::Duration::from_secs(sync_wait_secs)).await;
-                }
+                });
             });
-        });
+        }
     }
 
     info!("Starting Rosetta server");
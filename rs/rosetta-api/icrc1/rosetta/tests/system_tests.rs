@@ -1006,6 +1006,9 @@ fn test_construction_submit() {
                         let fee = match icrc1_transaction.operation {
                             ic_icrc1::Operation::Transfer { fee, .. } => fee,
                             ic_icrc1::Operation::Approve { fee, .. } => fee,
+                            ic_icrc1::Operation::SetSpendingPolicy { fee, .. } => fee,
+                            ic_icrc1::Operation::RevokeAllApprovals { fee, .. } => fee,
+                            ic_icrc1::Operation::BatchTransfer { fee, .. } => fee,
                             ic_icrc1::Operation::Mint { .. } => None,
                             ic_icrc1::Operation::Burn { .. } => None,
                         };
@@ -1503,6 +1506,89 @@ fn test_search_transactions() {
         .unwrap()
 }
 
+#[test]
+fn test_events_blocks() {
+    let mut runner = TestRunner::new(TestRunnerConfig {
+        max_shrink_iters: 0,
+        cases: *NUM_TEST_CASES,
+        ..Default::default()
+    });
+
+    runner
+        .run(
+            &(valid_transactions_strategy(
+                (*MINTING_IDENTITY).clone(),
+                DEFAULT_TRANSFER_FEE,
+                50,
+                SystemTime::now(),
+            )
+            .no_shrink()),
+            |args_with_caller| {
+                let rt = Runtime::new().unwrap();
+                let setup = Setup::builder().build();
+
+                rt.block_on(async {
+                    let env = RosettaTestingEnvironmentBuilder::new(&setup)
+                        .with_args_with_caller(args_with_caller.clone())
+                        .build()
+                        .await;
+                    wait_for_rosetta_block(&env.rosetta_client, env.network_identifier.clone(), 0)
+                        .await;
+
+                    if !args_with_caller.is_empty() {
+                        let rosetta_blocks = get_rosetta_blocks_from_icrc1_ledger(
+                            env.icrc1_agent,
+                            0,
+                            *MAX_BLOCKS_PER_REQUEST,
+                        )
+                        .await;
+                        wait_for_rosetta_block(
+                            &env.rosetta_client,
+                            env.network_identifier.clone(),
+                            rosetta_blocks.last().unwrap().index,
+                        )
+                        .await;
+
+                        let events_blocks_response = env
+                            .rosetta_client
+                            .events_blocks(env.network_identifier.clone(), Some(0), None)
+                            .await
+                            .expect("Unable to call events_blocks");
+                        assert_eq!(
+                            events_blocks_response.max_sequence,
+                            rosetta_blocks.last().unwrap().index as i64
+                        );
+                        assert_eq!(
+                            events_blocks_response.events,
+                            rosetta_blocks
+                                .into_iter()
+                                .map(|block| BlockEvent {
+                                    sequence: block.index as i64,
+                                    block_identifier: block.get_block_identifier(),
+                                    type_: BlockEventType::BlockAdded,
+                                })
+                                .collect::<Vec<_>>()
+                        );
+
+                        let events_blocks_response = env
+                            .rosetta_client
+                            .events_blocks(env.network_identifier.clone(), None, Some(1))
+                            .await
+                            .expect("Unable to call events_blocks");
+                        assert_eq!(events_blocks_response.events.len(), 1);
+                        assert_eq!(
+                            events_blocks_response.events[0].sequence,
+                            events_blocks_response.max_sequence
+                        );
+                    }
+
+                    Ok(())
+                })
+            },
+        )
+        .unwrap()
+}
+
 #[test]
 fn test_cli_data() {
     let mut runner = TestRunner::new(TestRunnerConfig {
@@ -78,6 +78,16 @@ pub fn encoded_block_to_generic_block(encoded_block: &EncodedBlock) -> GenericBl
     icrc1_block_from_value(value, 0).expect("failed to decode encoded block")
 }
 
+/// Same as [encoded_block_to_generic_block], but returns an error instead of panicking if the
+/// block is not valid CBOR or contains values that cannot be represented as a [GenericBlock].
+pub fn try_encoded_block_to_generic_block(
+    encoded_block: &EncodedBlock,
+) -> Result<GenericBlock, String> {
+    let value: CiboriumValue = ciborium::de::from_reader(encoded_block.as_slice())
+        .map_err(|e| format!("failed to decode block: {}", e))?;
+    icrc1_block_from_value(value, 0).map_err(|e| format!("failed to decode encoded block: {}", e))
+}
+
 #[derive(Debug, Error)]
 enum ValueDecodingError {
     #[error("CBOR value depth must not exceed {max_depth}")]
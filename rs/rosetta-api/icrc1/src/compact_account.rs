@@ -98,3 +98,36 @@ pub mod opt {
         }
     }
 }
+
+pub mod opt_vec {
+    use super::*;
+
+    pub fn serialize<S>(accs: &Option<Vec<Account>>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        accs.as_ref()
+            .map(|accs| {
+                accs.iter()
+                    .map(|acc| CompactAccount::from(*acc))
+                    .collect::<Vec<_>>()
+            })
+            .serialize(s)
+    }
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Option<Vec<Account>>, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        type OptionalCompactAccounts = Option<Vec<CompactAccount>>;
+        match OptionalCompactAccounts::deserialize(d)? {
+            Some(compact_accounts) => compact_accounts
+                .into_iter()
+                .map(Account::try_from)
+                .collect::<Result<Vec<_>, _>>()
+                .map(Some)
+                .map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
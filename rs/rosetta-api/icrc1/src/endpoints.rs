@@ -5,10 +5,19 @@ use ic_ledger_canister_core::ledger::TransferError as CoreTransferError;
 use ic_ledger_core::tokens::TokensType;
 use icrc_ledger_types::icrc1::transfer::TransferError;
 use icrc_ledger_types::icrc2::approve::ApproveError;
+use icrc_ledger_types::icrc2::spending_policy::{
+    PeriodCap, RevokeAllApprovalsError, SetSpendingPolicyError,
+};
 use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
-use icrc_ledger_types::icrc3::transactions::{Approve, Burn, Mint, Transaction, Transfer};
+use icrc_ledger_types::icrc3::transactions::{
+    Approve, Burn, Mint, RevokeAllApprovals, SetSpendingPolicy, Transaction, Transfer,
+};
 use serde::Deserialize;
 
+/// The `error_code` of the `GenericError` returned by `icrc2_transfer_from`
+/// when the spending policy of the allowance does not allow the recipient.
+pub const RECIPIENT_NOT_ALLOWED_ERROR_CODE: u64 = 1;
+
 pub fn convert_transfer_error<Tokens: TokensType>(
     err: CoreTransferError<Tokens>,
 ) -> EndpointsTransferError<Tokens> {
@@ -52,6 +61,9 @@ impl<Tokens: TokensType> TryFrom<EndpointsTransferError<Tokens>> for TransferErr
             CTE::SelfApproval { .. } => {
                 return Err("SelfApproval error should not happen for transfer".to_string());
             }
+            CTE::ApprovalDoesNotExist | CTE::InvalidSpendingPolicy | CTE::RecipientNotAllowed => {
+                return Err("spending policy errors should not happen for transfer".to_string());
+            }
             CTE::BadBurn { min_burn_amount } => TE::BadBurn {
                 min_burn_amount: min_burn_amount.into(),
             },
@@ -94,6 +106,9 @@ impl<Tokens: TokensType> TryFrom<EndpointsTransferError<Tokens>> for ApproveErro
             CTE::SelfApproval { .. } => {
                 return Err("self-approvals are not allowed".to_string());
             }
+            CTE::ApprovalDoesNotExist | CTE::InvalidSpendingPolicy | CTE::RecipientNotAllowed => {
+                return Err("spending policy errors should not happen for approval".to_string());
+            }
             CTE::BadBurn { .. } => {
                 return Err("BadBurn error should not happen for Approve".to_string());
             }
@@ -134,6 +149,16 @@ impl<Tokens: TokensType> TryFrom<EndpointsTransferError<Tokens>> for TransferFro
             CTE::SelfApproval { .. } => {
                 return Err("self approval not implemented for TransferFromError".to_string());
             }
+            CTE::RecipientNotAllowed => TFE::GenericError {
+                error_code: Nat::from(RECIPIENT_NOT_ALLOWED_ERROR_CODE),
+                message: "the spending policy of the allowance does not allow this recipient"
+                    .to_string(),
+            },
+            CTE::ApprovalDoesNotExist | CTE::InvalidSpendingPolicy => {
+                return Err(
+                    "spending policy errors should not happen for transfer_from".to_string()
+                );
+            }
             CTE::BadBurn { min_burn_amount } => TFE::BadBurn {
                 min_burn_amount: min_burn_amount.into(),
             },
@@ -141,6 +166,82 @@ impl<Tokens: TokensType> TryFrom<EndpointsTransferError<Tokens>> for TransferFro
     }
 }
 
+impl<Tokens: TokensType> TryFrom<EndpointsTransferError<Tokens>> for SetSpendingPolicyError {
+    type Error = String;
+    fn try_from(err: EndpointsTransferError<Tokens>) -> Result<Self, Self::Error> {
+        use ic_ledger_canister_core::ledger::TransferError as CTE;
+        use SetSpendingPolicyError as SPE;
+
+        Ok(match err.0 {
+            CTE::BadFee { expected_fee } => SPE::BadFee {
+                expected_fee: expected_fee.into(),
+            },
+            CTE::InsufficientFunds { balance } => SPE::InsufficientFunds {
+                balance: balance.into(),
+            },
+            CTE::TxTooOld { .. } => SPE::TooOld,
+            CTE::TxCreatedInFuture { ledger_time } => SPE::CreatedInFuture {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            CTE::TxThrottled => SPE::TemporarilyUnavailable,
+            CTE::TxDuplicate { duplicate_of } => SPE::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            },
+            CTE::ApprovalDoesNotExist => SPE::ApprovalDoesNotExist,
+            CTE::InvalidSpendingPolicy => SPE::InvalidPeriod,
+            CTE::InsufficientAllowance { .. }
+            | CTE::ExpiredApproval { .. }
+            | CTE::AllowanceChanged { .. }
+            | CTE::SelfApproval
+            | CTE::RecipientNotAllowed
+            | CTE::BadBurn { .. } => {
+                return Err(format!(
+                    "{:?} error should not happen for spending policies",
+                    err.0
+                ));
+            }
+        })
+    }
+}
+
+impl<Tokens: TokensType> TryFrom<EndpointsTransferError<Tokens>> for RevokeAllApprovalsError {
+    type Error = String;
+    fn try_from(err: EndpointsTransferError<Tokens>) -> Result<Self, Self::Error> {
+        use ic_ledger_canister_core::ledger::TransferError as CTE;
+        use RevokeAllApprovalsError as RAE;
+
+        Ok(match err.0 {
+            CTE::BadFee { expected_fee } => RAE::BadFee {
+                expected_fee: expected_fee.into(),
+            },
+            CTE::InsufficientFunds { balance } => RAE::InsufficientFunds {
+                balance: balance.into(),
+            },
+            CTE::TxTooOld { .. } => RAE::TooOld,
+            CTE::TxCreatedInFuture { ledger_time } => RAE::CreatedInFuture {
+                ledger_time: ledger_time.as_nanos_since_unix_epoch(),
+            },
+            CTE::TxThrottled => RAE::TemporarilyUnavailable,
+            CTE::TxDuplicate { duplicate_of } => RAE::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            },
+            CTE::InsufficientAllowance { .. }
+            | CTE::ExpiredApproval { .. }
+            | CTE::AllowanceChanged { .. }
+            | CTE::SelfApproval
+            | CTE::ApprovalDoesNotExist
+            | CTE::InvalidSpendingPolicy
+            | CTE::RecipientNotAllowed
+            | CTE::BadBurn { .. } => {
+                return Err(format!(
+                    "{:?} error should not happen for approval revocations",
+                    err.0
+                ));
+            }
+        })
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct StandardRecord {
     pub name: String,
@@ -159,6 +260,8 @@ impl<Tokens: TokensType> From<Block<Tokens>> for Transaction {
             burn: None,
             transfer: None,
             approve: None,
+            set_spending_policy: None,
+            revoke_all_approvals: None,
             timestamp: b.timestamp,
         };
         let created_at_time = b.transaction.created_at_time;
@@ -228,6 +331,58 @@ impl<Tokens: TokensType> From<Block<Tokens>> for Transaction {
                     memo,
                 });
             }
+            Operation::SetSpendingPolicy {
+                from,
+                spender,
+                period_cap,
+                period,
+                allowed_recipients,
+                fee,
+            } => {
+                tx.kind = "spend_policy".to_string();
+                tx.set_spending_policy = Some(SetSpendingPolicy {
+                    from,
+                    spender,
+                    period_cap: period_cap
+                        .zip(period)
+                        .map(|(amount, period_nanos)| PeriodCap {
+                            amount: amount.into(),
+                            period_nanos,
+                        }),
+                    allowed_recipients,
+                    fee: fee.or(b.effective_fee).map(Into::into),
+                    created_at_time,
+                    memo,
+                });
+            }
+            Operation::RevokeAllApprovals { from, fee } => {
+                tx.kind = "revoke_all".to_string();
+                tx.revoke_all_approvals = Some(RevokeAllApprovals {
+                    from,
+                    fee: fee.or(b.effective_fee).map(Into::into),
+                    created_at_time,
+                    memo,
+                });
+            }
+            Operation::BatchTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => {
+                // Batch entries are transfers, so they keep the transfer representation
+                // and are only distinguished by their kind.
+                tx.kind = "batch_xfer".to_string();
+                tx.transfer = Some(Transfer {
+                    from,
+                    to,
+                    spender: None,
+                    amount: amount.into(),
+                    fee: fee.or(b.effective_fee).map(Into::into),
+                    created_at_time,
+                    memo,
+                });
+            }
         }
 
         tx
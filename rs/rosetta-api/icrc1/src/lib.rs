@@ -2,12 +2,13 @@ pub mod blocks;
 mod compact_account;
 pub mod endpoints;
 pub mod hash;
+pub mod snapshot;
 pub(crate) mod known_tags;
 
 use ciborium::tag::Required;
 use ic_ledger_canister_core::ledger::{LedgerContext, LedgerTransaction, TxApplyError};
 use ic_ledger_core::{
-    approvals::{AllowanceTable, HeapAllowancesData},
+    approvals::{AllowanceTable, HeapAllowancesData, PeriodCap},
     balances::Balances,
     block::{BlockType, EncodedBlock, FeeCollector},
     timestamp::TimeStamp,
@@ -76,6 +77,46 @@ pub enum Operation<Tokens: TokensType> {
         #[serde(skip_serializing_if = "Option::is_none")]
         fee: Option<Tokens>,
     },
+    #[serde(rename = "spend_policy")]
+    SetSpendingPolicy {
+        #[serde(with = "compact_account")]
+        from: Account,
+        #[serde(with = "compact_account")]
+        spender: Account,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[serde(rename = "amt")]
+        period_cap: Option<Tokens>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        period: Option<u64>,
+        #[serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "compact_account::opt_vec"
+        )]
+        #[serde(rename = "recipients")]
+        allowed_recipients: Option<Vec<Account>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee: Option<Tokens>,
+    },
+    #[serde(rename = "revoke_all")]
+    RevokeAllApprovals {
+        #[serde(with = "compact_account")]
+        from: Account,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee: Option<Tokens>,
+    },
+    /// A transfer that is an entry of an `icrc4_transfer_batch` call.
+    #[serde(rename = "batch_xfer")]
+    BatchTransfer {
+        #[serde(with = "compact_account")]
+        from: Account,
+        #[serde(with = "compact_account")]
+        to: Account,
+        #[serde(rename = "amt")]
+        amount: Tokens,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fee: Option<Tokens>,
+    },
 }
 
 // A [Transaction] but flattened meaning that [Operation]
@@ -113,8 +154,11 @@ struct FlattenedTransaction<Tokens: TokensType> {
     #[serde(with = "compact_account::opt")]
     spender: Option<Account>,
 
+    // Only the `spend_policy` operation can omit the amount.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "amt")]
-    amount: Tokens,
+    amount: Option<Tokens>,
 
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -127,23 +171,38 @@ struct FlattenedTransaction<Tokens: TokensType> {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     expires_at: Option<u64>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    period: Option<u64>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(with = "compact_account::opt_vec")]
+    recipients: Option<Vec<Account>>,
 }
 
 impl<Tokens: TokensType> TryFrom<FlattenedTransaction<Tokens>> for Transaction<Tokens> {
     type Error = String;
 
     fn try_from(value: FlattenedTransaction<Tokens>) -> Result<Self, Self::Error> {
+        let amount = |op: &str| {
+            value
+                .amount
+                .clone()
+                .ok_or(format!("`amt` field required for `{}` operation", op))
+        };
         let operation = match value.op.as_str() {
             "burn" => Operation::Burn {
                 from: value
                     .from
                     .ok_or("`from` field required for `burn` operation")?,
-                amount: value.amount,
+                amount: amount("burn")?,
                 spender: value.spender,
             },
             "mint" => Operation::Mint {
                 to: value.to.ok_or("`to` field required for `mint` operation")?,
-                amount: value.amount,
+                amount: amount("mint")?,
             },
             "xfer" => Operation::Transfer {
                 from: value
@@ -151,7 +210,7 @@ impl<Tokens: TokensType> TryFrom<FlattenedTransaction<Tokens>> for Transaction<T
                     .ok_or("`from` field required for `xfer` operation")?,
                 spender: value.spender,
                 to: value.to.ok_or("`to` field required for `xfer` operation")?,
-                amount: value.amount,
+                amount: amount("xfer")?,
                 fee: value.fee,
             },
             "approve" => Operation::Approve {
@@ -161,11 +220,39 @@ impl<Tokens: TokensType> TryFrom<FlattenedTransaction<Tokens>> for Transaction<T
                 spender: value
                     .spender
                     .ok_or("`spender` field required for `approve` operation")?,
-                amount: value.amount,
+                amount: amount("approve")?,
                 expected_allowance: value.expected_allowance,
                 expires_at: value.expires_at,
                 fee: value.fee,
             },
+            "spend_policy" => Operation::SetSpendingPolicy {
+                from: value
+                    .from
+                    .ok_or("`from` field required for `spend_policy` operation")?,
+                spender: value
+                    .spender
+                    .ok_or("`spender` field required for `spend_policy` operation")?,
+                period_cap: value.amount,
+                period: value.period,
+                allowed_recipients: value.recipients,
+                fee: value.fee,
+            },
+            "revoke_all" => Operation::RevokeAllApprovals {
+                from: value
+                    .from
+                    .ok_or("`from` field required for `revoke_all` operation")?,
+                fee: value.fee,
+            },
+            "batch_xfer" => Operation::BatchTransfer {
+                from: value
+                    .from
+                    .ok_or("`from` field required for `batch_xfer` operation")?,
+                to: value
+                    .to
+                    .ok_or("`to` field required for `batch_xfer` operation")?,
+                amount: amount("batch_xfer")?,
+                fee: value.fee,
+            },
             unknown_op => return Err(format!("Unknown operation name {}", unknown_op)),
         };
         Ok(Transaction {
@@ -188,29 +275,44 @@ impl<Tokens: TokensType> From<Transaction<Tokens>> for FlattenedTransaction<Toke
                 Mint { .. } => "mint",
                 Transfer { .. } => "xfer",
                 Approve { .. } => "approve",
+                SetSpendingPolicy { .. } => "spend_policy",
+                RevokeAllApprovals { .. } => "revoke_all",
+                BatchTransfer { .. } => "batch_xfer",
             }
             .into(),
             from: match &t.operation {
-                Transfer { from, .. } | Burn { from, .. } | Approve { from, .. } => Some(*from),
+                Transfer { from, .. }
+                | Burn { from, .. }
+                | Approve { from, .. }
+                | SetSpendingPolicy { from, .. }
+                | RevokeAllApprovals { from, .. }
+                | BatchTransfer { from, .. } => Some(*from),
                 _ => None,
             },
             to: match &t.operation {
-                Mint { to, .. } | Transfer { to, .. } => Some(*to),
+                Mint { to, .. } | Transfer { to, .. } | BatchTransfer { to, .. } => Some(*to),
                 _ => None,
             },
             spender: match &t.operation {
                 Transfer { spender, .. } | Burn { spender, .. } => spender.to_owned(),
-                Approve { spender, .. } => Some(*spender),
+                Approve { spender, .. } | SetSpendingPolicy { spender, .. } => Some(*spender),
                 _ => None,
             },
             amount: match &t.operation {
                 Burn { amount, .. }
                 | Mint { amount, .. }
                 | Transfer { amount, .. }
-                | Approve { amount, .. } => amount.clone(),
+                | Approve { amount, .. }
+                | BatchTransfer { amount, .. } => Some(amount.clone()),
+                SetSpendingPolicy { period_cap, .. } => period_cap.to_owned(),
+                RevokeAllApprovals { .. } => None,
             },
             fee: match &t.operation {
-                Transfer { fee, .. } | Approve { fee, .. } => fee.to_owned(),
+                Transfer { fee, .. }
+                | Approve { fee, .. }
+                | SetSpendingPolicy { fee, .. }
+                | RevokeAllApprovals { fee, .. }
+                | BatchTransfer { fee, .. } => fee.to_owned(),
                 _ => None,
             },
             expected_allowance: match &t.operation {
@@ -223,6 +325,16 @@ impl<Tokens: TokensType> From<Transaction<Tokens>> for FlattenedTransaction<Toke
                 Approve { expires_at, .. } => expires_at.to_owned(),
                 _ => None,
             },
+            period: match &t.operation {
+                SetSpendingPolicy { period, .. } => period.to_owned(),
+                _ => None,
+            },
+            recipients: match &t.operation {
+                SetSpendingPolicy {
+                    allowed_recipients, ..
+                } => allowed_recipients.to_owned(),
+                _ => None,
+            },
         }
     }
 }
@@ -343,6 +455,13 @@ impl<Tokens: TokensType> LedgerTransaction for Transaction<Tokens> {
                         allowance: allowance.amount,
                     });
                 }
+                context.approvals().check_spending_policy(
+                    from,
+                    &spender.unwrap(),
+                    Some(to),
+                    &used_allowance,
+                    now,
+                )?;
                 context
                     .balances_mut()
                     .transfer(from, to, amount.clone(), fee, fee_collector)?;
@@ -363,6 +482,13 @@ impl<Tokens: TokensType> LedgerTransaction for Transaction<Tokens> {
                             allowance: allowance.amount,
                         });
                     }
+                    context.approvals().check_spending_policy(
+                        from,
+                        &spender.unwrap(),
+                        None,
+                        amount,
+                        now,
+                    )?;
                 }
                 context.balances_mut().burn(from, amount.clone())?;
                 if spender.is_some() && from != &spender.unwrap() {
@@ -403,6 +529,65 @@ impl<Tokens: TokensType> LedgerTransaction for Transaction<Tokens> {
                     return Err(e);
                 }
             }
+            Operation::SetSpendingPolicy {
+                from,
+                spender,
+                period_cap,
+                period,
+                allowed_recipients,
+                fee,
+            } => {
+                let period_cap = match (period_cap, period) {
+                    (Some(amount), Some(period_nanos)) => Some(PeriodCap {
+                        amount: amount.clone(),
+                        period_nanos: *period_nanos,
+                    }),
+                    (None, None) => None,
+                    _ => return Err(TxApplyError::InvalidSpendingPolicy),
+                };
+                context
+                    .balances_mut()
+                    .burn(from, fee.clone().unwrap_or(effective_fee.clone()))?;
+                let result = context
+                    .approvals_mut()
+                    .set_spending_policy(
+                        from,
+                        spender,
+                        period_cap,
+                        allowed_recipients
+                            .as_ref()
+                            .map(|accounts| accounts.iter().cloned().collect()),
+                        now,
+                    )
+                    .map_err(TxApplyError::from);
+                if let Err(e) = result {
+                    context
+                        .balances_mut()
+                        .mint(from, fee.clone().unwrap_or(effective_fee))
+                        .expect("bug: failed to refund spending policy fee");
+                    return Err(e);
+                }
+            }
+            Operation::RevokeAllApprovals { from, fee } => {
+                context
+                    .balances_mut()
+                    .burn(from, fee.clone().unwrap_or(effective_fee))?;
+                context.approvals_mut().revoke_all(from);
+            }
+            Operation::BatchTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => {
+                context.balances_mut().transfer(
+                    from,
+                    to,
+                    amount.clone(),
+                    fee.clone().unwrap_or(effective_fee),
+                    fee_collector,
+                )?;
+            }
         }
         Ok(())
     }
@@ -482,6 +667,23 @@ impl<Tokens: TokensType> TryFrom<icrc_ledger_types::icrc3::transactions::Transac
         if let Some(transfer) = value.transfer {
             let amount = Tokens::try_from(transfer.amount)
                 .map_err(|_| "Could not convert Nat to Tokens".to_string())?;
+            if value.kind == "batch_xfer" {
+                let fee = transfer
+                    .fee
+                    .map(Tokens::try_from)
+                    .transpose()
+                    .map_err(|_| "Could not convert Nat to Tokens".to_string())?;
+                return Ok(Self {
+                    operation: Operation::BatchTransfer {
+                        from: transfer.from,
+                        to: transfer.to,
+                        amount,
+                        fee,
+                    },
+                    created_at_time: transfer.created_at_time,
+                    memo: transfer.memo,
+                });
+            }
             match transfer.fee {
                 Some(fee) => {
                     let fee = Tokens::try_from(fee)
@@ -597,7 +799,10 @@ impl<Tokens: TokensType> BlockType for Block<Tokens> {
     ) -> Self {
         let effective_fee = match &transaction.operation {
             Operation::Transfer { fee, .. } => fee.is_none().then_some(effective_fee),
-            Operation::Approve { fee, .. } => fee.is_none().then_some(effective_fee),
+            Operation::Approve { fee, .. }
+            | Operation::SetSpendingPolicy { fee, .. }
+            | Operation::RevokeAllApprovals { fee, .. }
+            | Operation::BatchTransfer { fee, .. } => fee.is_none().then_some(effective_fee),
             _ => None,
         };
         let (fee_collector, fee_collector_block_index) = match fee_collector {
@@ -0,0 +1,144 @@
+//! Types and hashing of the ledger state snapshots.
+//!
+//! A snapshot captures the balances and the allowances of the ledger after
+//! applying the first `num_blocks` blocks. Clients, e.g. the index canister,
+//! download the entries page by page and recompute the `state_hash` to make
+//! sure that the content they received is the one committed by the ledger.
+//!
+//! The balances and the allowances are hashed as two independent chains
+//! `h_0 = [0; 32]`, `h_{i+1} = sha256(h_i || encode(entry_i))` so that a
+//! client can verify the entries incrementally and independently of the
+//! page size it uses.
+use candid::{CandidType, Nat};
+use ic_crypto_sha2::Sha256;
+use icrc_ledger_types::icrc1::account::Account;
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+
+pub type Hash = [u8; 32];
+
+/// The value of an empty chain of entries.
+pub const EMPTY_ENTRIES_HASH: Hash = [0u8; 32];
+
+const STATE_HASH_DOMAIN: &[u8] = b"ic-icrc1-ledger-snapshot";
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct LedgerSnapshotInfo {
+    /// The number of blocks reflected in the snapshot, i.e. the index of
+    /// the first block that has not been applied to the snapshotted state.
+    pub num_blocks: u64,
+    /// The hash of the block with index `num_blocks - 1`, if any.
+    pub last_block_hash: Option<ByteBuf>,
+    /// The time at which the snapshot was taken.
+    pub timestamp: u64,
+    pub num_balances: u64,
+    pub num_allowances: u64,
+    pub total_supply: Nat,
+    pub transfer_fee: Nat,
+    /// The fee collector at the time of the snapshot and the index of the
+    /// block where it was first recorded.
+    pub fee_collector: Option<Account>,
+    pub fee_collector_block_index: Option<u64>,
+    pub balances_hash: ByteBuf,
+    pub allowances_hash: ByteBuf,
+    /// The hash certified by the ledger, see [state_hash].
+    pub state_hash: ByteBuf,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetSnapshotEntriesArgs {
+    pub start: u64,
+    pub length: u64,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct SnapshotBalance {
+    pub account: Account,
+    pub balance: Nat,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct SnapshotAllowance {
+    pub account: Account,
+    pub spender: Account,
+    pub allowance: Nat,
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct SnapshotBalances {
+    /// The state hash of the snapshot the balances belong to. Clients must
+    /// restart from scratch if it changes between two pages.
+    pub state_hash: ByteBuf,
+    /// The balances sorted by account, starting from the requested index.
+    pub balances: Vec<SnapshotBalance>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct SnapshotAllowances {
+    pub state_hash: ByteBuf,
+    /// The allowances sorted by (account, spender), starting from the requested index.
+    pub allowances: Vec<SnapshotAllowance>,
+}
+
+fn write_account(hasher: &mut Sha256, account: &Account) {
+    let owner = account.owner.as_slice();
+    hasher.write(&[owner.len() as u8]);
+    hasher.write(owner);
+    hasher.write(account.effective_subaccount());
+}
+
+fn write_nat(hasher: &mut Sha256, n: &Nat) {
+    let mut buf = vec![];
+    n.encode(&mut buf).expect("bug: failed to encode nat");
+    hasher.write(&buf);
+}
+
+/// Extends the chain of balances with the given entry.
+pub fn chain_balance(prev: &Hash, account: &Account, balance: &Nat) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.write(prev);
+    write_account(&mut hasher, account);
+    write_nat(&mut hasher, balance);
+    hasher.finish()
+}
+
+/// Extends the chain of allowances with the given entry.
+pub fn chain_allowance(
+    prev: &Hash,
+    account: &Account,
+    spender: &Account,
+    allowance: &Nat,
+    expires_at: Option<u64>,
+) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.write(prev);
+    write_account(&mut hasher, account);
+    write_account(&mut hasher, spender);
+    write_nat(&mut hasher, allowance);
+    match expires_at {
+        Some(expires_at) => {
+            hasher.write(&[1]);
+            hasher.write(&expires_at.to_be_bytes());
+        }
+        None => hasher.write(&[0]),
+    }
+    hasher.finish()
+}
+
+/// Returns the hash that commits to the whole snapshot, i.e. to the position
+/// in the chain of blocks and to both chains of entries.
+pub fn state_hash(
+    num_blocks: u64,
+    last_block_hash: Option<&Hash>,
+    balances_hash: &Hash,
+    allowances_hash: &Hash,
+) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.write(STATE_HASH_DOMAIN);
+    hasher.write(&num_blocks.to_be_bytes());
+    hasher.write(last_block_hash.unwrap_or(&EMPTY_ENTRIES_HASH));
+    hasher.write(balances_hash);
+    hasher.write(allowances_hash);
+    hasher.finish()
+}
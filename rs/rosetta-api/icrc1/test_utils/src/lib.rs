@@ -200,6 +200,15 @@ pub fn blocks_strategy<Tokens: TokensType>(
                 let effective_fee = match transaction.operation {
                     Operation::Transfer { ref fee, .. } => fee.clone().is_none().then_some(arb_fee),
                     Operation::Approve { ref fee, .. } => fee.clone().is_none().then_some(arb_fee),
+                    Operation::SetSpendingPolicy { ref fee, .. } => {
+                        fee.clone().is_none().then_some(arb_fee)
+                    }
+                    Operation::RevokeAllApprovals { ref fee, .. } => {
+                        fee.clone().is_none().then_some(arb_fee)
+                    }
+                    Operation::BatchTransfer { ref fee, .. } => {
+                        fee.clone().is_none().then_some(arb_fee)
+                    }
                     Operation::Burn { .. } => None,
                     Operation::Mint { .. } => None,
                 };
@@ -434,6 +443,9 @@ impl TransactionsAndBalances {
             }
             Operation::Transfer {
                 from, to, amount, ..
+            }
+            | Operation::BatchTransfer {
+                from, to, amount, ..
             } => {
                 self.credit(to, amount.get_e8s());
                 assert_eq!(tx.from(), from);
@@ -455,6 +467,15 @@ impl TransactionsAndBalances {
                     .or_insert(amount);
                 self.debit(from, fee);
             }
+            Operation::SetSpendingPolicy { from, .. } => {
+                assert_eq!(tx.from(), from);
+                self.debit(from, fee);
+            }
+            Operation::RevokeAllApprovals { from, .. } => {
+                assert_eq!(tx.from(), from);
+                self.allowances.retain(|(owner, _), _| *owner != from);
+                self.debit(from, fee);
+            }
         };
         self.transactions.push(tx);
     }
@@ -944,6 +965,27 @@ where
         })
 }
 
+pub fn arb_batch_transfer<Tokens, S>(
+    arb_tokens: fn() -> S,
+) -> impl Strategy<Value = Operation<Tokens>>
+where
+    Tokens: TokensType,
+    S: Strategy<Value = Tokens>,
+{
+    (
+        arb_account(),
+        arb_account(),
+        arb_tokens(),
+        proptest::option::of(arb_tokens()),
+    )
+        .prop_map(|(from, to, amount, fee)| Operation::BatchTransfer {
+            from,
+            to,
+            amount,
+            fee,
+        })
+}
+
 pub fn arb_approve<Tokens, S>(arb_tokens: fn() -> S) -> impl Strategy<Value = Operation<Tokens>>
 where
     Tokens: TokensType,
@@ -994,6 +1036,43 @@ where
         })
 }
 
+pub fn arb_set_spending_policy<Tokens, S>(
+    arb_tokens: fn() -> S,
+) -> impl Strategy<Value = Operation<Tokens>>
+where
+    Tokens: TokensType,
+    S: Strategy<Value = Tokens>,
+{
+    (
+        arb_account(),
+        arb_account(),
+        proptest::option::of((arb_tokens(), 1..u64::MAX)),
+        proptest::option::of(proptest::collection::vec(arb_account(), 0..5)),
+        proptest::option::of(arb_tokens()),
+    )
+        .prop_map(|(from, spender, period_cap, allowed_recipients, fee)| {
+            Operation::SetSpendingPolicy {
+                from,
+                spender,
+                period_cap: period_cap.clone().map(|(amount, _)| amount),
+                period: period_cap.map(|(_, period)| period),
+                allowed_recipients,
+                fee,
+            }
+        })
+}
+
+pub fn arb_revoke_all_approvals<Tokens, S>(
+    arb_tokens: fn() -> S,
+) -> impl Strategy<Value = Operation<Tokens>>
+where
+    Tokens: TokensType,
+    S: Strategy<Value = Tokens>,
+{
+    (arb_account(), proptest::option::of(arb_tokens()))
+        .prop_map(|(from, fee)| Operation::RevokeAllApprovals { from, fee })
+}
+
 pub fn arb_operation<Tokens, S>(arb_tokens: fn() -> S) -> impl Strategy<Value = Operation<Tokens>>
 where
     Tokens: TokensType,
@@ -1003,7 +1082,10 @@ where
         arb_transfer(arb_tokens),
         arb_mint(arb_tokens),
         arb_burn(arb_tokens),
-        arb_approve(arb_tokens)
+        arb_approve(arb_tokens),
+        arb_set_spending_policy(arb_tokens),
+        arb_revoke_all_approvals(arb_tokens),
+        arb_batch_transfer(arb_tokens)
     ]
 }
 
@@ -42,6 +42,7 @@ fn should_upgrade_and_downgrade_ledger_canister_suite() {
         IndexInitArg {
             ledger_id: Principal::from(ledger_id),
             retrieve_blocks_from_ledger_interval_seconds: None,
+            bootstrap_from_ledger_snapshot: None,
         },
     );
 
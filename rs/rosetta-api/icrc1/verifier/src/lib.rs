@@ -0,0 +1,548 @@
+//! Offline verification of the block history of an ICRC-1 ledger.
+//!
+//! The verifier downloads all the blocks of the ledger and its archives and
+//! checks that
+//! 1. every block is linked to the previous one via its parent hash,
+//! 2. the hash of the last block matches the tip certified by the ledger,
+//! 3. the total supply and the balances obtained by replaying the blocks
+//!    match `icrc1_total_supply` and `icrc1_balance_of`, for every account
+//!    touched by a block, including the accounts whose balance went back to zero.
+//!
+//! The total supply and the balances are read with replicated (update) calls,
+//! so that the answers are agreed upon by the subnet rather than returned by a
+//! single, possibly malicious, replica.
+use candid::Nat;
+use ic_icrc1::blocks::generic_block_to_encoded_block;
+use ic_icrc1::hash::{hash_cbor, Hash};
+use ic_icrc1::{Block, Operation};
+use ic_icrc1_tokens_u256::U256;
+use ic_ledger_core::block::BlockType;
+use icrc_ledger_agent::{CallMode, Icrc1Agent};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::archive::{ArchivedRange, QueryBlockArchiveFn};
+use icrc_ledger_types::icrc3::blocks::{GenericBlock, GetBlocksRequest};
+use num_traits::ToPrimitive;
+use std::collections::{BTreeMap, HashMap};
+
+/// The maximum number of times the verifier downloads the new blocks because
+/// the ledger produced blocks while the state was being compared.
+const MAX_SYNC_ATTEMPTS: usize = 3;
+
+/// Replays the blocks of a ledger, checking that they form a valid chain.
+#[derive(Clone, Debug, Default)]
+pub struct ChainVerifier {
+    chain_length: u64,
+    last_hash: Option<Hash>,
+    /// The fee collectors set explicitly in blocks, by block index.
+    fee_collectors: HashMap<u64, Account>,
+    /// The last transfer fee, used for approve blocks without fee.
+    last_fee: Option<Nat>,
+    /// The balances of all the accounts touched by a block, including
+    /// accounts with a zero balance.
+    balances: BTreeMap<Account, Nat>,
+    total_supply: Nat,
+}
+
+impl ChainVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of blocks verified so far.
+    pub fn chain_length(&self) -> u64 {
+        self.chain_length
+    }
+
+    /// The hash of the last block verified, if any.
+    pub fn last_hash(&self) -> Option<Hash> {
+        self.last_hash
+    }
+
+    pub fn balances(&self) -> &BTreeMap<Account, Nat> {
+        &self.balances
+    }
+
+    pub fn total_supply(&self) -> &Nat {
+        &self.total_supply
+    }
+
+    /// Verifies that `block` extends the chain and applies it to the balances.
+    pub fn push(&mut self, block: GenericBlock) -> Result<(), String> {
+        let block_index = self.chain_length;
+        let encoded_block = generic_block_to_encoded_block(block)
+            .map_err(|err| format!("Block {} cannot be encoded: {}", block_index, err))?;
+        let hash = hash_cbor(encoded_block.as_slice())
+            .map_err(|err| format!("Block {} cannot be hashed: {}", block_index, err))?;
+        let block = Block::<U256>::decode(encoded_block)
+            .map_err(|err| format!("Block {} cannot be decoded: {}", block_index, err))?;
+
+        let parent_hash = block.parent_hash.map(|hash| hash.into_bytes());
+        if parent_hash != self.last_hash {
+            return Err(format!(
+                "Block {} has parent hash {} but the hash of the previous block is {}",
+                block_index,
+                display_hash(parent_hash),
+                display_hash(self.last_hash),
+            ));
+        }
+
+        self.apply(block_index, &block)?;
+        if let Some(fee_collector) = block.fee_collector {
+            self.fee_collectors.insert(block_index, fee_collector);
+        }
+        self.last_hash = Some(hash);
+        self.chain_length += 1;
+        Ok(())
+    }
+
+    fn apply(&mut self, block_index: u64, block: &Block<U256>) -> Result<(), String> {
+        let effective_fee = block.effective_fee.map(Nat::from);
+        match &block.transaction.operation {
+            Operation::Mint { to, amount } => {
+                self.credit(*to, Nat::from(*amount));
+                self.total_supply += Nat::from(*amount);
+            }
+            Operation::Burn { from, amount, .. } => {
+                self.debit(block_index, *from, Nat::from(*amount))?;
+                self.total_supply -= Nat::from(*amount);
+            }
+            Operation::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+                ..
+            }
+            | Operation::BatchTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => {
+                let fee = effective_fee
+                    .or(fee.map(Nat::from))
+                    .ok_or_else(|| format!("Transfer block {} has no fee", block_index))?;
+                self.last_fee = Some(fee.clone());
+                self.debit(block_index, *from, Nat::from(*amount) + fee.clone())?;
+                self.credit(*to, Nat::from(*amount));
+                match self.fee_collector(block_index, block)? {
+                    Some(fee_collector) => self.credit(fee_collector, fee),
+                    None => self.total_supply -= fee,
+                }
+            }
+            Operation::Approve { from, fee, .. } => {
+                // Some early approve blocks have no fee, the ledger charged
+                // the transfer fee in effect at that time.
+                let fee = fee
+                    .map(Nat::from)
+                    .or(effective_fee)
+                    .or_else(|| self.last_fee.clone())
+                    .ok_or_else(|| format!("Approve block {} has no fee", block_index))?;
+                self.debit(block_index, *from, fee.clone())?;
+                self.total_supply -= fee;
+            }
+            Operation::SetSpendingPolicy { from, fee, .. }
+            | Operation::RevokeAllApprovals { from, fee } => {
+                let fee = fee
+                    .map(Nat::from)
+                    .or(effective_fee)
+                    .ok_or_else(|| format!("Block {} has no fee", block_index))?;
+                self.debit(block_index, *from, fee.clone())?;
+                self.total_supply -= fee;
+            }
+        }
+        Ok(())
+    }
+
+    fn fee_collector(
+        &self,
+        block_index: u64,
+        block: &Block<U256>,
+    ) -> Result<Option<Account>, String> {
+        match (block.fee_collector, block.fee_collector_block_index) {
+            (Some(fee_collector), _) => Ok(Some(fee_collector)),
+            (None, Some(fee_collector_block_index)) => self
+                .fee_collectors
+                .get(&fee_collector_block_index)
+                .copied()
+                .map(Some)
+                .ok_or_else(|| {
+                    format!(
+                        "Block {} has fee_collector_block_index {} but that block has no fee_collector",
+                        block_index, fee_collector_block_index
+                    )
+                }),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn credit(&mut self, account: Account, amount: Nat) {
+        *self.balances.entry(account).or_default() += amount;
+    }
+
+    fn debit(&mut self, block_index: u64, account: Account, amount: Nat) -> Result<(), String> {
+        let balance = self.balances.entry(account).or_default();
+        if *balance < amount {
+            return Err(format!(
+                "Block {} debits {} from account {} whose balance is {}",
+                block_index, amount, account, balance
+            ));
+        }
+        *balance -= amount;
+        Ok(())
+    }
+}
+
+fn display_hash(hash: Option<Hash>) -> String {
+    hash.map(hex::encode).unwrap_or_else(|| "none".to_string())
+}
+
+/// The result of a verification of a ledger.
+#[derive(Clone, Debug)]
+pub struct VerificationReport {
+    pub chain_length: u64,
+    pub tip_hash: Option<Hash>,
+    pub total_supply: Nat,
+    pub num_accounts: usize,
+    /// The differences between the replayed state and the ledger state.
+    pub mismatches: Vec<String>,
+}
+
+impl VerificationReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Downloads the blocks of the ledger with index in
+/// `[verifier.chain_length(), end)` and pushes them to `verifier`.
+pub async fn sync_blocks(
+    agent: &Icrc1Agent,
+    verifier: &mut ChainVerifier,
+    end: u64,
+    max_blocks_per_request: u64,
+) -> Result<(), String> {
+    while verifier.chain_length() < end {
+        let start = verifier.chain_length();
+        let length = (end - start).min(max_blocks_per_request);
+        let res = agent
+            .get_blocks(GetBlocksRequest {
+                start: Nat::from(start),
+                length: Nat::from(length),
+            })
+            .await
+            .map_err(|err| format!("Failed to get blocks from the ledger: {}", err))?;
+
+        let mut archived_blocks = res.archived_blocks;
+        archived_blocks.sort_by(|a, b| a.start.cmp(&b.start));
+        for archived in archived_blocks {
+            sync_archived_blocks(agent, verifier, archived).await?;
+        }
+
+        let first_index = res.first_index.0.to_u64().unwrap_or(u64::MAX);
+        if !res.blocks.is_empty() && first_index != verifier.chain_length() {
+            return Err(format!(
+                "The ledger returned blocks starting at {} but the next block to verify is {}",
+                first_index,
+                verifier.chain_length()
+            ));
+        }
+        for block in res.blocks {
+            verifier.push(block)?;
+        }
+        if verifier.chain_length() == start {
+            return Err(format!("The ledger returned no block at index {}", start));
+        }
+    }
+    Ok(())
+}
+
+async fn sync_archived_blocks(
+    agent: &Icrc1Agent,
+    verifier: &mut ChainVerifier,
+    archived: ArchivedRange<QueryBlockArchiveFn>,
+) -> Result<(), String> {
+    let end = archived.start.clone() + archived.length.clone();
+    if archived.start != verifier.chain_length() {
+        return Err(format!(
+            "The ledger returned archived blocks starting at {} but the next block to verify is {}",
+            archived.start,
+            verifier.chain_length()
+        ));
+    }
+    // The archive can return less blocks than requested.
+    while verifier.chain_length() < end {
+        let start = Nat::from(verifier.chain_length());
+        let range = ArchivedRange {
+            length: end.clone() - start.clone(),
+            start,
+            callback: archived.callback.clone(),
+        };
+        let res = agent.get_blocks_from_archive(range).await.map_err(|err| {
+            format!(
+                "Failed to get blocks from the archive {}: {}",
+                archived.callback.canister_id, err
+            )
+        })?;
+        if res.blocks.is_empty() {
+            return Err(format!(
+                "The archive {} returned no block at index {}",
+                archived.callback.canister_id,
+                verifier.chain_length()
+            ));
+        }
+        for block in res.blocks {
+            verifier.push(block)?;
+        }
+    }
+    Ok(())
+}
+
+/// Downloads and verifies all the blocks of the ledger, then compares the
+/// replayed state with the certified tip, the total supply and the balances
+/// returned by the ledger.
+///
+/// The ledger state is read with replicated calls after the blocks are downloaded.
+/// If the ledger produces blocks in the meantime then the new blocks are
+/// downloaded and the comparison restarts.
+pub async fn verify_ledger(
+    agent: &Icrc1Agent,
+    max_blocks_per_request: u64,
+) -> Result<VerificationReport, String> {
+    let mut verifier = ChainVerifier::new();
+    for _ in 0..MAX_SYNC_ATTEMPTS {
+        let tip = certified_tip(agent).await?;
+        let chain_length = tip.map_or(0, |(_, index)| index + 1);
+        if chain_length < verifier.chain_length() {
+            return Err(format!(
+                "The certified chain length {} is lower than the number of blocks already verified {}",
+                chain_length,
+                verifier.chain_length()
+            ));
+        }
+        sync_blocks(agent, &mut verifier, chain_length, max_blocks_per_request).await?;
+
+        let mut mismatches = vec![];
+        let tip_hash = tip.map(|(hash, _)| hash);
+        if verifier.last_hash() != tip_hash {
+            mismatches.push(format!(
+                "The hash of the last block is {} but the certified tip hash is {}",
+                display_hash(verifier.last_hash()),
+                display_hash(tip_hash),
+            ));
+        }
+
+        let total_supply = agent
+            .total_supply(CallMode::Update)
+            .await
+            .map_err(|err| format!("Failed to get the total supply: {}", err))?;
+        if &total_supply != verifier.total_supply() {
+            mismatches.push(format!(
+                "The total supply of the ledger is {} but the blocks sum up to {}",
+                total_supply,
+                verifier.total_supply()
+            ));
+        }
+
+        for (account, expected_balance) in verifier.balances() {
+            let balance = agent
+                .balance_of(*account, CallMode::Update)
+                .await
+                .map_err(|err| format!("Failed to get the balance of {}: {}", account, err))?;
+            if &balance != expected_balance {
+                mismatches.push(format!(
+                    "The balance of {} is {} but the blocks sum up to {}",
+                    account, balance, expected_balance
+                ));
+            }
+        }
+
+        if certified_tip(agent).await? == tip {
+            return Ok(VerificationReport {
+                chain_length: verifier.chain_length(),
+                tip_hash,
+                total_supply: verifier.total_supply().clone(),
+                num_accounts: verifier.balances().len(),
+                mismatches,
+            });
+        }
+    }
+    Err(format!(
+        "The ledger produced new blocks during each of the {} verification attempts",
+        MAX_SYNC_ATTEMPTS
+    ))
+}
+
+async fn certified_tip(agent: &Icrc1Agent) -> Result<Option<(Hash, u64)>, String> {
+    let tip = agent
+        .get_certified_chain_tip()
+        .await
+        .map_err(|err| format!("Failed to get the certified chain tip: {}", err))?;
+    tip.map(|(hash, index)| {
+        index
+            .0
+            .to_u64()
+            .map(|index| (hash, index))
+            .ok_or_else(|| format!("Invalid certified block index {}", index))
+    })
+    .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+    use ic_icrc1::blocks::encoded_block_to_generic_block;
+    use ic_icrc1::Transaction;
+    use ic_ledger_core::block::FeeCollector;
+    use ic_ledger_core::timestamp::TimeStamp;
+
+    fn account(n: u64) -> Account {
+        Account {
+            owner: Principal::from_slice(&n.to_be_bytes()),
+            subaccount: None,
+        }
+    }
+
+    fn block(
+        parent: Option<&GenericBlock>,
+        operation: Operation<U256>,
+        fee_collector: Option<FeeCollector<Account>>,
+    ) -> GenericBlock {
+        let parent_hash = parent.map(|block| {
+            Block::<U256>::block_hash(&generic_block_to_encoded_block(block.clone()).unwrap())
+        });
+        let transaction = Transaction {
+            operation,
+            created_at_time: None,
+            memo: None,
+        };
+        let block = Block::from_transaction(
+            parent_hash,
+            transaction,
+            TimeStamp::from_nanos_since_unix_epoch(0),
+            U256::from(10u64),
+            fee_collector,
+        );
+        encoded_block_to_generic_block(&block.encode())
+    }
+
+    fn mint(to: Account, amount: u64) -> Operation<U256> {
+        Operation::Mint {
+            to,
+            amount: U256::from(amount),
+        }
+    }
+
+    fn transfer(from: Account, to: Account, amount: u64) -> Operation<U256> {
+        Operation::Transfer {
+            from,
+            to,
+            spender: None,
+            amount: U256::from(amount),
+            fee: None,
+        }
+    }
+
+    #[test]
+    fn should_replay_balances_and_total_supply() {
+        let b0 = block(None, mint(account(1), 1_000), None);
+        let b1 = block(
+            Some(&b0),
+            transfer(account(1), account(2), 100),
+            Some(FeeCollector::from(account(3))),
+        );
+        let b2 = block(
+            Some(&b1),
+            transfer(account(2), account(1), 50),
+            Some(FeeCollector {
+                fee_collector: account(3),
+                block_index: Some(1),
+            }),
+        );
+        let mut verifier = ChainVerifier::new();
+        for block in [b0, b1, b2.clone()] {
+            verifier.push(block).unwrap();
+        }
+
+        assert_eq!(verifier.chain_length(), 3);
+        assert_eq!(
+            verifier.last_hash(),
+            Some(hash_cbor(generic_block_to_encoded_block(b2).unwrap().as_slice()).unwrap())
+        );
+        assert_eq!(verifier.balances()[&account(1)], Nat::from(940u64));
+        assert_eq!(verifier.balances()[&account(2)], Nat::from(40u64));
+        assert_eq!(verifier.balances()[&account(3)], Nat::from(20u64));
+        assert_eq!(verifier.total_supply(), &Nat::from(1_000u64));
+    }
+
+    #[test]
+    fn should_burn_the_fee_without_fee_collector() {
+        let b0 = block(None, mint(account(1), 1_000), None);
+        let b1 = block(Some(&b0), transfer(account(1), account(2), 100), None);
+        let mut verifier = ChainVerifier::new();
+        verifier.push(b0).unwrap();
+        verifier.push(b1).unwrap();
+
+        assert_eq!(verifier.total_supply(), &Nat::from(990u64));
+        assert_eq!(verifier.balances()[&account(1)], Nat::from(890u64));
+    }
+
+    #[test]
+    fn should_replay_batch_transfers() {
+        let b0 = block(None, mint(account(1), 1_000), None);
+        let b1 = block(
+            Some(&b0),
+            Operation::BatchTransfer {
+                from: account(1),
+                to: account(2),
+                amount: U256::from(100u64),
+                fee: None,
+            },
+            Some(FeeCollector::from(account(3))),
+        );
+        let mut verifier = ChainVerifier::new();
+        verifier.push(b0).unwrap();
+        verifier.push(b1).unwrap();
+
+        assert_eq!(verifier.balances()[&account(1)], Nat::from(890u64));
+        assert_eq!(verifier.balances()[&account(2)], Nat::from(100u64));
+        assert_eq!(verifier.balances()[&account(3)], Nat::from(10u64));
+        assert_eq!(verifier.total_supply(), &Nat::from(1_000u64));
+    }
+
+    #[test]
+    fn should_keep_accounts_with_zero_balance() {
+        let b0 = block(None, mint(account(1), 1_000), None);
+        let b1 = block(Some(&b0), transfer(account(1), account(2), 990), None);
+        let mut verifier = ChainVerifier::new();
+        verifier.push(b0).unwrap();
+        verifier.push(b1).unwrap();
+
+        assert_eq!(verifier.balances()[&account(1)], Nat::from(0u64));
+        assert_eq!(verifier.balances()[&account(2)], Nat::from(990u64));
+        assert_eq!(verifier.balances().len(), 2);
+    }
+
+    #[test]
+    fn should_reject_broken_parent_hash() {
+        let b0 = block(None, mint(account(1), 1_000), None);
+        let b1 = block(None, mint(account(2), 1_000), None);
+        let mut verifier = ChainVerifier::new();
+        verifier.push(b0).unwrap();
+
+        let err = verifier.push(b1).unwrap_err();
+        assert!(err.contains("Block 1 has parent hash none"), "{}", err);
+    }
+
+    #[test]
+    fn should_reject_overdraft() {
+        let b0 = block(None, mint(account(1), 100), None);
+        let b1 = block(Some(&b0), transfer(account(1), account(2), 100), None);
+        let mut verifier = ChainVerifier::new();
+        verifier.push(b0).unwrap();
+
+        let err = verifier.push(b1).unwrap_err();
+        assert!(err.contains("Block 1 debits 110"), "{}", err);
+    }
+}
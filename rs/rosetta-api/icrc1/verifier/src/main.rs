@@ -0,0 +1,81 @@
+use anyhow::{bail, Context, Result};
+use candid::Principal;
+use clap::Parser;
+use ic_agent::{
+    agent::http_transport::reqwest_transport::ReqwestTransport, identity::AnonymousIdentity, Agent,
+};
+use ic_icrc1_verifier::verify_ledger;
+use icrc_ledger_agent::Icrc1Agent;
+use url::Url;
+
+const MAINNET_URL: &str = "https://ic0.app";
+
+#[derive(Debug, Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// The ICRC-1 ledger to verify.
+    #[arg(short, long)]
+    ledger_id: Principal,
+
+    /// The URL of the replica to connect to, e.g. a local replica or a PocketIC instance.
+    #[arg(long, default_value = MAINNET_URL)]
+    network_url: String,
+
+    /// Fetches the root key from the replica. Only use this with a local replica or PocketIC,
+    /// never with the mainnet.
+    #[arg(long, default_value_t = false)]
+    fetch_root_key: bool,
+
+    /// The maximum number of blocks requested to the ledger per call.
+    #[arg(long, default_value_t = 2_000)]
+    max_blocks_per_request: u64,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let agent = Agent::builder()
+        .with_identity(AnonymousIdentity)
+        .with_transport(ReqwestTransport::create(
+            Url::parse(&args.network_url)
+                .context(format!("Failed to parse URL {}", args.network_url))?,
+        )?)
+        .build()?;
+    if args.fetch_root_key {
+        agent.fetch_root_key().await?;
+    }
+    let agent = Icrc1Agent {
+        agent,
+        ledger_canister_id: args.ledger_id,
+    };
+
+    let report = verify_ledger(&agent, args.max_blocks_per_request)
+        .await
+        .map_err(anyhow::Error::msg)
+        .context(format!("Failed to verify the ledger {}", args.ledger_id))?;
+
+    println!("ledger: {}", args.ledger_id);
+    println!("blocks verified: {}", report.chain_length);
+    println!(
+        "tip hash: {}",
+        report
+            .tip_hash
+            .map(hex::encode)
+            .unwrap_or_else(|| "none".to_string())
+    );
+    println!("total supply: {}", report.total_supply);
+    println!("accounts checked: {}", report.num_accounts);
+    for mismatch in &report.mismatches {
+        println!("MISMATCH: {}", mismatch);
+    }
+    if !report.is_ok() {
+        bail!(
+            "The ledger {} failed verification with {} mismatch(es)",
+            args.ledger_id,
+            report.mismatches.len()
+        );
+    }
+    println!("OK");
+    Ok(())
+}
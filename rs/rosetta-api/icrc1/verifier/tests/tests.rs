@@ -0,0 +1,106 @@
+use candid::{Decode, Encode, Nat, Principal};
+use ic_agent::{
+    agent::http_transport::reqwest_transport::ReqwestTransport, identity::AnonymousIdentity, Agent,
+};
+use ic_base_types::PrincipalId;
+use ic_icrc1_ledger::{InitArgsBuilder, LedgerArgument};
+use ic_icrc1_verifier::verify_ledger;
+use ic_ledger_canister_core::archive::ArchiveOptions;
+use icrc_ledger_agent::Icrc1Agent;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{BlockIndex, TransferArg, TransferError};
+use pocket_ic::{PocketIc, PocketIcBuilder, WasmResult};
+use tokio::runtime::Runtime;
+
+const FEE: u64 = 10_000;
+const STARTING_CYCLES: u128 = 100_000_000_000_000;
+
+fn ledger_wasm() -> Vec<u8> {
+    let path =
+        std::env::var("IC_ICRC1_LEDGER_WASM_PATH").expect("IC_ICRC1_LEDGER_WASM_PATH is not set");
+    std::fs::read(&path).unwrap_or_else(|err| panic!("failed to read {}: {}", path, err))
+}
+
+fn install_ledger(pocket_ic: &PocketIc, initial_balance: (Account, u64)) -> Principal {
+    let init_args = InitArgsBuilder::for_tests()
+        .with_minting_account(PrincipalId::new_user_test_id(1_000).0)
+        .with_initial_balance(initial_balance.0, initial_balance.1)
+        .with_transfer_fee(FEE)
+        .with_archive_options(ArchiveOptions {
+            trigger_threshold: 10,
+            num_blocks_to_archive: 5,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            more_controller_ids: None,
+            cycles_for_archive_creation: Some(0),
+            max_transactions_per_response: None,
+        })
+        .build();
+    let canister_id = pocket_ic.create_canister();
+    pocket_ic.add_cycles(canister_id, STARTING_CYCLES);
+    pocket_ic.install_canister(
+        canister_id,
+        ledger_wasm(),
+        Encode!(&LedgerArgument::Init(init_args)).unwrap(),
+        None,
+    );
+    canister_id
+}
+
+fn transfer(pocket_ic: &PocketIc, ledger: Principal, from: Principal, to: Principal, amount: u64) {
+    let arg = TransferArg {
+        from_subaccount: None,
+        to: to.into(),
+        fee: None,
+        created_at_time: None,
+        memo: None,
+        amount: Nat::from(amount),
+    };
+    let reply = match pocket_ic
+        .update_call(ledger, from, "icrc1_transfer", Encode!(&arg).unwrap())
+        .expect("failed to call icrc1_transfer")
+    {
+        WasmResult::Reply(reply) => reply,
+        WasmResult::Reject(reject) => panic!("icrc1_transfer was rejected: {}", reject),
+    };
+    Decode!(&reply, Result<BlockIndex, TransferError>)
+        .unwrap()
+        .expect("the transfer failed");
+}
+
+#[test]
+fn should_verify_archived_blocks_and_zero_balances() {
+    let p1 = PrincipalId::new_user_test_id(1).0;
+    let p2 = PrincipalId::new_user_test_id(2).0;
+    let p3 = PrincipalId::new_user_test_id(3).0;
+
+    let mut pocket_ic = PocketIcBuilder::new().with_nns_subnet().build();
+    let ledger = install_ledger(&pocket_ic, (p1.into(), 10_000_000));
+    for _ in 0..15 {
+        transfer(&pocket_ic, ledger, p1, p2, 100_000);
+    }
+    // `p2` sends its whole balance, so its balance goes back to zero.
+    transfer(&pocket_ic, ledger, p2, p3, 15 * 100_000 - FEE);
+
+    let endpoint = pocket_ic.make_live(None);
+    let report = Runtime::new().unwrap().block_on(async {
+        let agent = Agent::builder()
+            .with_identity(AnonymousIdentity)
+            .with_transport(ReqwestTransport::create(endpoint).unwrap())
+            .build()
+            .unwrap();
+        agent.fetch_root_key().await.unwrap();
+        let agent = Icrc1Agent {
+            agent,
+            ledger_canister_id: ledger,
+        };
+        verify_ledger(&agent, 4).await.unwrap()
+    });
+
+    assert!(report.is_ok(), "mismatches: {:?}", report.mismatches);
+    assert_eq!(report.chain_length, 17);
+    assert_eq!(report.total_supply, Nat::from(10_000_000 - 16 * FEE));
+    // `p1`, `p2` (with a zero balance) and `p3`.
+    assert_eq!(report.num_accounts, 3);
+}
@@ -0,0 +1,76 @@
+use candid::{CandidType, Deserialize, Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::BlockIndex;
+use icrc_ledger_types::icrc3::blocks::BlockWithId;
+
+/// The maximum number of blocks to return in a single [get_account_transactions] request.
+pub const DEFAULT_MAX_BLOCKS_PER_RESPONSE: u64 = 2000;
+
+/// The maximum number of token ids to return in a single [icrc7_tokens_of] request.
+pub const DEFAULT_MAX_TAKE_VALUE: u64 = 1000;
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub enum IndexArg {
+    Init(InitArg),
+    Upgrade(UpgradeArg),
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct InitArg {
+    pub ledger_id: Principal,
+    pub retrieve_blocks_from_ledger_interval_seconds: Option<u64>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct UpgradeArg {
+    pub ledger_id: Option<Principal>,
+    pub retrieve_blocks_from_ledger_interval_seconds: Option<u64>,
+}
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetAccountTransactionsArgs {
+    pub account: Account,
+    // The txid of the last transaction seen by the client.
+    // If None then the results will start from the most recent
+    // txid. If set then the results will start from the next
+    // most recent txid after start (start won't be included).
+    pub start: Option<BlockIndex>,
+    // Maximum number of transactions to fetch.
+    pub max_results: Nat,
+}
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetAccountTransactionsResponse {
+    // The number of tokens owned by the account.
+    pub balance: Nat,
+    // The ICRC-3 blocks involving the account, most recent first.
+    pub transactions: Vec<BlockWithId>,
+    // The txid of the oldest transaction the account has
+    pub oldest_tx_id: Option<BlockIndex>,
+}
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetAccountTransactionsError {
+    pub message: String,
+}
+
+pub type GetAccountTransactionsResult =
+    Result<GetAccountTransactionsResponse, GetAccountTransactionsError>;
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct Status {
+    pub num_blocks_synced: BlockIndex,
+}
+
+#[derive(Clone, Debug, Deserialize, serde::Serialize)]
+pub struct LogEntry {
+    pub timestamp: u64,
+    pub file: String,
+    pub line: u32,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, serde::Serialize)]
+pub struct Log {
+    pub entries: Vec<LogEntry>,
+}
@@ -0,0 +1,676 @@
+use candid::{candid_method, Nat, Principal};
+use ic_canister_log::{export as export_logs, log};
+use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use ic_cdk::trap;
+use ic_cdk_macros::{init, post_upgrade, query};
+use ic_cdk_timers::TimerId;
+use ic_crypto_sha2::Sha256;
+use ic_icrc1::blocks::{generic_block_to_encoded_block, try_encoded_block_to_generic_block};
+use ic_icrc7::{token_id_from_nat, Block, Operation, TokenId};
+use ic_icrc7_index::{
+    GetAccountTransactionsArgs, GetAccountTransactionsResponse, GetAccountTransactionsResult,
+    IndexArg, InitArg, Log, LogEntry, Status, UpgradeArg, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    DEFAULT_MAX_TAKE_VALUE,
+};
+use ic_ledger_canister_core::runtime::total_memory_size_bytes;
+use ic_ledger_core::block::{BlockIndex as BlockIndex64, EncodedBlock};
+use ic_stable_structures::memory_manager::{MemoryId, VirtualMemory};
+use ic_stable_structures::storable::Bound;
+use ic_stable_structures::{
+    memory_manager::MemoryManager, DefaultMemoryImpl, StableBTreeMap, StableCell, StableLog,
+    Storable,
+};
+use icrc_ledger_types::icrc::generic_value::{ICRC3Value, Value};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::blocks::{
+    ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResult,
+};
+use num_traits::ToPrimitive;
+use scopeguard::guard;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+pub mod logs;
+
+use crate::logs::{P0, P1};
+
+const STATE_MEMORY_ID: MemoryId = MemoryId::new(0);
+const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
+const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
+const ACCOUNT_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(3);
+const ACCOUNT_TOKENS_MEMORY_ID: MemoryId = MemoryId::new(4);
+
+const DEFAULT_RETRIEVE_BLOCKS_FROM_LEDGER_INTERVAL: Duration = Duration::from_secs(1);
+
+type VM = VirtualMemory<DefaultMemoryImpl>;
+type StateCell = StableCell<State, VM>;
+type BlockLog = StableLog<Vec<u8>, VM, VM>;
+// The block indexes are stored in reverse order because the blocks/transactions
+// are returned in reversed order.
+type AccountBlockIdsMapKey = ([u8; Sha256::DIGEST_LEN], Reverse<u64>);
+type AccountBlockIdsMap = StableBTreeMap<AccountBlockIdsMapKey, (), VM>;
+// The token ids are stored big-endian so that the keys of an account are
+// sorted by token id.
+type AccountTokensMapKey = ([u8; Sha256::DIGEST_LEN], [u8; 16]);
+type AccountTokensMap = StableBTreeMap<AccountTokensMapKey, (), VM>;
+
+thread_local! {
+    /// Static memory manager to manage the memory available for stable structures.
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+
+    /// Scalar state of the index.
+    static STATE: RefCell<StateCell> = with_memory_manager(|memory_manager| {
+        RefCell::new(StateCell::init(memory_manager.get(STATE_MEMORY_ID), State::default())
+            .expect("failed to initialize stable cell"))
+    });
+
+    /// Append-only list of encoded blocks stored in stable memory.
+    static BLOCKS: RefCell<BlockLog> = with_memory_manager(|memory_manager| {
+        RefCell::new(BlockLog::init(memory_manager.get(BLOCK_LOG_INDEX_MEMORY_ID), memory_manager.get(BLOCK_LOG_DATA_MEMORY_ID))
+            .expect("failed to initialize stable log"))
+    });
+
+    /// Map that contains the block ids of an account.
+    /// The account is hashed to save space.
+    static ACCOUNT_BLOCK_IDS: RefCell<AccountBlockIdsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AccountBlockIdsMap::init(memory_manager.get(ACCOUNT_BLOCK_IDS_MEMORY_ID)))
+    });
+
+    /// Map that contains the tokens currently owned by an account.
+    /// The account is hashed to save space.
+    static ACCOUNT_TOKENS: RefCell<AccountTokensMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AccountTokensMap::init(memory_manager.get(ACCOUNT_TOKENS_MEMORY_ID)))
+    });
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct State {
+    /// Equals to `true` while the [build_index] task runs.
+    is_build_index_running: bool,
+
+    /// The principal of the ledger canister that is indexed by this index.
+    ledger_id: Principal,
+
+    /// The maximum number of transactions returned by [get_account_transactions].
+    max_blocks_per_response: u64,
+
+    /// The interval for retrieving blocks from the ledger and archive(s) for (re)building the
+    /// index.
+    retrieve_blocks_from_ledger_interval: Option<Duration>,
+}
+
+impl State {
+    pub fn retrieve_blocks_from_ledger_interval(&self) -> Duration {
+        self.retrieve_blocks_from_ledger_interval
+            .unwrap_or(DEFAULT_RETRIEVE_BLOCKS_FROM_LEDGER_INTERVAL)
+    }
+}
+
+// NOTE: the default configuration is dysfunctional, but it's convenient to have
+// a Default impl for the initialization of the [STATE] variable above.
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            is_build_index_running: false,
+            ledger_id: Principal::management_canister(),
+            max_blocks_per_response: DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+            retrieve_blocks_from_ledger_interval: None,
+        }
+    }
+}
+
+impl Storable for State {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut buf = vec![];
+        ciborium::ser::into_writer(self, &mut buf).expect("failed to encode index config");
+        Cow::Owned(buf)
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        ciborium::de::from_reader(&bytes[..]).expect("failed to decode index options")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+/// A helper function to access the scalar state.
+fn with_state<R>(f: impl FnOnce(&State) -> R) -> R {
+    STATE.with(|cell| f(cell.borrow().get()))
+}
+
+/// A helper function to change the scalar state.
+fn mutate_state(f: impl FnOnce(&mut State)) {
+    STATE
+        .with(|cell| {
+            let mut borrowed = cell.borrow_mut();
+            let mut state = borrowed.get().clone();
+            f(&mut state);
+            borrowed.set(state)
+        })
+        .expect("failed to set index state");
+}
+
+/// A helper function to access the memory manager.
+fn with_memory_manager<R>(f: impl FnOnce(&MemoryManager<DefaultMemoryImpl>) -> R) -> R {
+    MEMORY_MANAGER.with(|cell| f(&cell.borrow()))
+}
+
+/// A helper function to access the block list.
+fn with_blocks<R>(f: impl FnOnce(&BlockLog) -> R) -> R {
+    BLOCKS.with(|cell| f(&cell.borrow()))
+}
+
+/// A helper function to access the account block ids.
+fn with_account_block_ids<R>(f: impl FnOnce(&mut AccountBlockIdsMap) -> R) -> R {
+    ACCOUNT_BLOCK_IDS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the tokens owned by accounts.
+fn with_account_tokens<R>(f: impl FnOnce(&mut AccountTokensMap) -> R) -> R {
+    ACCOUNT_TOKENS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+#[init]
+#[candid_method(init)]
+fn init(index_arg: Option<IndexArg>) {
+    let InitArg {
+        ledger_id,
+        retrieve_blocks_from_ledger_interval_seconds,
+    } = match index_arg {
+        Some(IndexArg::Init(arg)) => arg,
+        _ => trap("Index initialization must take in input an InitArg argument"),
+    };
+
+    // stable memory initialization
+    mutate_state(|state| {
+        state.ledger_id = ledger_id;
+        state.retrieve_blocks_from_ledger_interval =
+            retrieve_blocks_from_ledger_interval_seconds.map(Duration::from_secs);
+    });
+
+    // set the first build_index to be called after init
+    set_build_index_timer(with_state(|state| {
+        state.retrieve_blocks_from_ledger_interval()
+    }));
+}
+
+#[post_upgrade]
+fn post_upgrade(index_arg: Option<IndexArg>) {
+    match index_arg {
+        Some(IndexArg::Upgrade(upgrade)) => {
+            log!(P1, "Possible upgrade configuration changes: {:#?}", upgrade,);
+
+            let UpgradeArg {
+                ledger_id,
+                retrieve_blocks_from_ledger_interval_seconds,
+            } = upgrade;
+
+            mutate_state(|state| {
+                if let Some(new_value) = ledger_id {
+                    state.ledger_id = new_value;
+                }
+
+                if let Some(new_value) = retrieve_blocks_from_ledger_interval_seconds {
+                    state.retrieve_blocks_from_ledger_interval =
+                        Some(Duration::from_secs(new_value));
+                }
+            });
+        }
+        Some(IndexArg::Init(..)) => trap("Index upgrade argument cannot be of variant Init"),
+        _ => (),
+    };
+
+    // set the first build_index to be called after init
+    set_build_index_timer(with_state(|state| {
+        state.retrieve_blocks_from_ledger_interval()
+    }));
+}
+
+async fn icrc3_get_blocks_from_ledger(start: u64) -> Option<GetBlocksResult> {
+    let (ledger_id, length) = with_state(|state| (state.ledger_id, state.max_blocks_per_response));
+    let req = vec![GetBlocksRequest {
+        start: Nat::from(start),
+        length: Nat::from(length),
+    }];
+    log!(P1, "[icrc3_get_blocks_from_ledger]: making the call...");
+    match ic_cdk::api::call::call::<_, (GetBlocksResult,)>(ledger_id, "icrc3_get_blocks", (req,))
+        .await
+    {
+        Ok((res,)) => Some(res),
+        Err((code, msg)) => {
+            log!(
+                P0,
+                "[icrc3_get_blocks_from_ledger] failed to get blocks: code: {:#?} message: {}",
+                code,
+                msg
+            );
+            None
+        }
+    }
+}
+
+async fn icrc3_get_blocks_from_archive(archived: &ArchivedBlocks) -> Option<GetBlocksResult> {
+    match ic_cdk::api::call::call::<_, (GetBlocksResult,)>(
+        archived.callback.canister_id,
+        &archived.callback.method,
+        (archived.args.clone(),),
+    )
+    .await
+    {
+        Ok((res,)) => Some(res),
+        Err((code, msg)) => {
+            log!(
+                P0,
+                "[icrc3_get_blocks_from_archive] failed to get blocks: code: {:#?} message: {}",
+                code,
+                msg
+            );
+            None
+        }
+    }
+}
+
+pub async fn build_index() -> Option<()> {
+    if with_state(|state| state.is_build_index_running) {
+        return None;
+    }
+    mutate_state(|state| {
+        state.is_build_index_running = true;
+    });
+    let _reset_is_build_index_running_flag_guard = guard((), |_| {
+        mutate_state(|state| {
+            state.is_build_index_running = false;
+        });
+    });
+    let num_indexed = fetch_blocks_via_icrc3().await?;
+    log!(P1, "Indexed: {}", num_indexed);
+    Some(())
+}
+
+async fn fetch_blocks_via_icrc3() -> Option<u64> {
+    // The current number of blocks is also the id of the next
+    // block to query from the Ledger.
+    let previous_num_blocks = with_blocks(|blocks| blocks.len());
+    let res = icrc3_get_blocks_from_ledger(previous_num_blocks).await?;
+
+    // The Ledger should return archives in order but there is
+    // no guarantee of this. In order to avoid issues we sort
+    // and rearrange the archived_blocks.
+    let mut archived_blocks = BTreeMap::new();
+    for ArchivedBlocks { args, callback } in res.archived_blocks {
+        for arg in args {
+            archived_blocks.insert(arg, callback.clone());
+        }
+    }
+
+    for (mut arg, callback) in archived_blocks.into_iter() {
+        // The archive can return less than arg.length blocks.
+        // The client canister must make sure to call icrc3_get_blocks
+        // until all blocks in `arg` have been retrieved.
+        while arg.length != 0u64 {
+            let expected_id = with_blocks(|blocks| blocks.len());
+            if arg.start != expected_id {
+                log!(
+                    P0,
+                    "[fetch_blocks_via_icrc3]: wrong start index in archive args. Expected: {} actual: {}",
+                    expected_id,
+                    arg.start,
+                );
+                return None;
+            }
+
+            let archived = ArchivedBlocks {
+                args: vec![arg.clone()],
+                callback: callback.clone(),
+            };
+            let res = icrc3_get_blocks_from_archive(&archived).await?;
+
+            // sanity check: the index does not support nested archives
+            if !res.archived_blocks.is_empty() {
+                log!(
+                    P0,
+                    "[fetch_blocks_via_icrc3]: The archive callback {:?} with arg {:?} returned one or more archived blocks and the index is currently not supporting nested archived blocks.",
+                    callback,
+                    arg,
+                );
+                return None;
+            }
+            if res.blocks.is_empty() {
+                log!(
+                    P0,
+                    "[fetch_blocks_via_icrc3]: The archive callback {:?} with arg {:?} returned no blocks.",
+                    callback,
+                    arg,
+                );
+                return None;
+            }
+
+            // change `arg` for the next iteration
+            arg.start += res.blocks.len();
+            arg.length -= res.blocks.len();
+
+            append_icrc3_blocks(res.blocks)?;
+        }
+    }
+
+    append_icrc3_blocks(res.blocks)?;
+    let num_blocks = with_blocks(|blocks| blocks.len());
+    match num_blocks.checked_sub(previous_num_blocks) {
+        None => panic!("The number of blocks {} is smaller than the number of blocks before indexing {}. This is impossible. I'm trapping to reset the state", num_blocks, previous_num_blocks),
+        Some(new_blocks_indexed) => Some(new_blocks_indexed),
+    }
+}
+
+fn set_build_index_timer(after: Duration) -> TimerId {
+    ic_cdk_timers::set_timer_interval(after, || {
+        ic_cdk::spawn(async {
+            let _ = build_index().await;
+        })
+    })
+}
+
+/// Appends the blocks to the block log and updates the indices.
+///
+/// Stops at the first block that is out of order or that is not a valid
+/// ICRC-7 block so that the next run of [build_index] retries from there.
+fn append_icrc3_blocks(new_blocks: Vec<BlockWithId>) -> Option<()> {
+    for BlockWithId { id, block } in new_blocks {
+        let block_index = with_blocks(|blocks| blocks.len());
+        if id != block_index {
+            log!(
+                P0,
+                "[append_icrc3_blocks]: wrong block index returned by ledger. Expected: {} actual: {}",
+                block_index,
+                id,
+            );
+            return None;
+        }
+        // This conversion is safe as `Value`
+        // can represent any `ICRC3Value`.
+        let block = Value::from(block);
+        let decoded_block = match Block::try_from(block.clone()) {
+            Ok(decoded_block) => decoded_block,
+            Err(err) => {
+                log!(
+                    P0,
+                    "[append_icrc3_blocks]: block {} is not a valid ICRC-7 block: {}",
+                    block_index,
+                    err,
+                );
+                return None;
+            }
+        };
+        let encoded_block = generic_block_to_encoded_block(block).unwrap_or_else(|err| {
+            trap(&format!("failed to encode block {}: {}", block_index, err))
+        });
+        append_block(block_index, encoded_block, &decoded_block);
+    }
+    Some(())
+}
+
+fn append_block(block_index: BlockIndex64, encoded_block: EncodedBlock, block: &Block) {
+    // append the encoded block to the block log
+    with_blocks(|blocks| {
+        blocks
+            .append(&encoded_block.into_vec())
+            .unwrap_or_else(|_| trap("no space left"))
+    });
+
+    // add the block idx to the indices
+    with_account_block_ids(|account_block_ids| {
+        for account in block.transaction.operation.accounts() {
+            account_block_ids.insert(account_block_ids_key(account, block_index), ());
+        }
+    });
+
+    // change the owner of the token, if any
+    let (from, to, token_id) = match block.transaction.operation {
+        Operation::Mint { to, token_id, .. } => (None, Some(to), token_id),
+        Operation::Burn { from, token_id } => (Some(from), None, token_id),
+        Operation::Transfer { from, to, token_id }
+        | Operation::TransferFrom {
+            from, to, token_id, ..
+        } => (Some(from), Some(to), token_id),
+        Operation::ApproveToken { .. }
+        | Operation::ApproveCollection { .. }
+        | Operation::RevokeToken { .. }
+        | Operation::RevokeCollection { .. } => return,
+    };
+    with_account_tokens(|account_tokens| {
+        if let Some(from) = from {
+            account_tokens.remove(&account_tokens_key(from, token_id));
+        }
+        if let Some(to) = to {
+            account_tokens.insert(account_tokens_key(to, token_id), ());
+        }
+    });
+}
+
+pub fn account_sha256(account: Account) -> [u8; Sha256::DIGEST_LEN] {
+    let mut hasher = Sha256::new();
+    account.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn account_block_ids_key(account: Account, block_index: BlockIndex64) -> AccountBlockIdsMapKey {
+    (account_sha256(account), Reverse(block_index))
+}
+
+fn account_tokens_key(account: Account, token_id: TokenId) -> AccountTokensMapKey {
+    (account_sha256(account), token_id.to_be_bytes())
+}
+
+fn get_block_or_trap(block_index: BlockIndex64) -> ICRC3Value {
+    let bytes = with_blocks(|blocks| blocks.get(block_index))
+        .unwrap_or_else(|| trap(&format!("Block {} not found in the block log", block_index)));
+    try_encoded_block_to_generic_block(&EncodedBlock::from(bytes))
+        .map(ICRC3Value::from)
+        .unwrap_or_else(|err| trap(&format!("failed to decode block {}: {}", block_index, err)))
+}
+
+fn get_balance(account: Account) -> u64 {
+    let account_hash = account_sha256(account);
+    with_account_tokens(|account_tokens| {
+        account_tokens
+            .range((account_hash, [0; 16])..)
+            .take_while(|(key, _)| key.0 == account_hash)
+            .count() as u64
+    })
+}
+
+fn get_oldest_tx_id(account: Account) -> Option<BlockIndex64> {
+    // There is no easy way to get the oldest index for an account
+    // in one step. Instead, we do it in two steps:
+    // 1. check if index 0 is owned by the account
+    // 2. if not then return the oldest index of the account that
+    //    is not 0 via iter_upper_bound
+    let last_key = account_block_ids_key(account, 0);
+    with_account_block_ids(|account_block_ids| {
+        account_block_ids.get(&last_key).map(|_| 0).or_else(|| {
+            account_block_ids
+                .iter_upper_bound(&last_key)
+                .take_while(|(k, _)| k.0 == account_sha256(account))
+                .next()
+                .map(|(key, _)| key.1 .0)
+        })
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn ledger_id() -> Principal {
+    with_state(|state| state.ledger_id)
+}
+
+#[query]
+#[candid_method(query)]
+fn get_account_transactions(arg: GetAccountTransactionsArgs) -> GetAccountTransactionsResult {
+    let length = arg
+        .max_results
+        .0
+        .to_u64()
+        .expect("The length must be a u64!")
+        .min(with_state(|opts| opts.max_blocks_per_response))
+        .min(usize::MAX as u64) as usize;
+    let start = arg
+        .start
+        .map_or(u64::MAX, |n| n.0.to_u64().expect("start must be a u64!"));
+    let key = account_block_ids_key(arg.account, start);
+    let indices = with_account_block_ids(|account_block_ids| {
+        account_block_ids
+            .range(key..)
+            // old txs of the requested account and skip the start index
+            .take_while(|(k, _)| k.0 == key.0)
+            .filter(|(k, _)| k.1 .0 < start)
+            .take(length)
+            .map(|(k, _)| k.1 .0)
+            .collect::<Vec<BlockIndex64>>()
+    });
+    let transactions = indices
+        .into_iter()
+        .map(|id| BlockWithId {
+            id: id.into(),
+            block: get_block_or_trap(id),
+        })
+        .collect();
+    Ok(GetAccountTransactionsResponse {
+        balance: get_balance(arg.account).into(),
+        transactions,
+        oldest_tx_id: get_oldest_tx_id(arg.account).map(|tx_id| tx_id.into()),
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_balance_of(accounts: Vec<Account>) -> Vec<Nat> {
+    accounts
+        .into_iter()
+        .map(|account| get_balance(account).into())
+        .collect()
+}
+
+/// Returns the tokens owned by the account in ascending order, starting after `prev`.
+#[query]
+#[candid_method(query)]
+fn icrc7_tokens_of(account: Account, prev: Option<Nat>, take: Option<Nat>) -> Vec<Nat> {
+    let take = take
+        .and_then(|take| take.0.to_u64())
+        .unwrap_or(DEFAULT_MAX_TAKE_VALUE)
+        .min(DEFAULT_MAX_TAKE_VALUE) as usize;
+    let account_hash = account_sha256(account);
+    let start = match prev {
+        Some(prev) => match token_id_from_nat(&prev).and_then(|prev| prev.checked_add(1)) {
+            Some(start) => start,
+            None => return vec![],
+        },
+        None => TokenId::MIN,
+    };
+    with_account_tokens(|account_tokens| {
+        account_tokens
+            .range((account_hash, start.to_be_bytes())..)
+            .take_while(|(key, _)| key.0 == account_hash)
+            .take(take)
+            .map(|(key, _)| Nat::from(TokenId::from_be_bytes(key.1)))
+            .collect()
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn status() -> Status {
+    let num_blocks_synced = with_blocks(|blocks| blocks.len().into());
+    Status { num_blocks_synced }
+}
+
+#[query(hidden = true, decoding_quota = 10000)]
+fn http_request(req: HttpRequest) -> HttpResponse {
+    if req.path() == "/metrics" {
+        let mut writer =
+            ic_metrics_encoder::MetricsEncoder::new(vec![], ic_cdk::api::time() as i64 / 1_000_000);
+
+        match encode_metrics(&mut writer) {
+            Ok(()) => HttpResponseBuilder::ok()
+                .header("Content-Type", "text/plain; version=0.0.4")
+                .with_body_and_content_length(writer.into_inner())
+                .build(),
+            Err(err) => {
+                HttpResponseBuilder::server_error(format!("Failed to encode metrics: {}", err))
+                    .build()
+            }
+        }
+    } else if req.path() == "/logs" {
+        let mut entries: Log = Default::default();
+        for entry in export_logs(&P0) {
+            entries.entries.push(LogEntry {
+                timestamp: entry.timestamp,
+                file: entry.file.to_string(),
+                line: entry.line,
+                message: entry.message,
+            });
+        }
+        HttpResponseBuilder::ok()
+            .header("Content-Type", "application/json; charset=utf-8")
+            .with_body_and_content_length(serde_json::to_string(&entries).unwrap_or_default())
+            .build()
+    } else {
+        HttpResponseBuilder::not_found().build()
+    }
+}
+
+pub fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()> {
+    w.encode_gauge(
+        "index_stable_memory_pages",
+        ic_cdk::api::stable::stable64_size() as f64,
+        "Size of the stable memory allocated by this canister measured in 64K Wasm pages.",
+    )?;
+    w.encode_gauge(
+        "index_total_memory_bytes",
+        total_memory_size_bytes() as f64,
+        "Total amount of memory (heap, stable memory, etc) that has been allocated by this canister.",
+    )?;
+    let cycle_balance = ic_cdk::api::canister_balance128() as f64;
+    w.encode_gauge(
+        "index_cycle_balance",
+        cycle_balance,
+        "Cycle balance on this canister.",
+    )?;
+    w.gauge_vec("cycle_balance", "Cycle balance on this canister.")?
+        .value(&[("canister", "icrc7-index")], cycle_balance)?;
+    w.encode_gauge(
+        "index_number_of_blocks",
+        with_blocks(|blocks| blocks.len()) as f64,
+        "Total number of blocks stored in the stable memory.",
+    )?;
+    Ok(())
+}
+
+fn main() {}
+
+#[cfg(test)]
+candid::export_service!();
+
+#[test]
+fn check_candid_interface() {
+    use candid_parser::utils::{service_equal, CandidSource};
+    use std::path::PathBuf;
+
+    let new_interface = __export_service();
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let old_interface = manifest_dir.join("index.did");
+    service_equal(
+        CandidSource::Text(&new_interface),
+        CandidSource::File(old_interface.as_path()),
+    )
+    .unwrap_or_else(|e| {
+        panic!(
+            "the index interface is not compatible with {}: {:?}",
+            old_interface.display(),
+            e
+        )
+    });
+}
@@ -0,0 +1,195 @@
+use candid::{CandidType, Decode, Encode, Nat, Principal};
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_icrc7_index::{
+    GetAccountTransactionsArgs, GetAccountTransactionsResult, IndexArg, InitArg, Status,
+};
+use ic_icrc7_ledger::{ArchiveOptions, InitArgs, LedgerArgument, MintArg, MintResult};
+use ic_state_machine_tests::StateMachine;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc7::transfer::{TransferArg, TransferResult};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const MAX_ATTEMPTS_FOR_INDEX_SYNC_WAIT: u8 = 100;
+
+fn index_wasm() -> Vec<u8> {
+    ic_test_utilities_load_wasm::load_wasm(
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        "ic-icrc7-index",
+        &[],
+    )
+}
+
+fn ledger_wasm() -> Vec<u8> {
+    ic_test_utilities_load_wasm::load_wasm(
+        PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
+            .parent()
+            .unwrap()
+            .join("ledger"),
+        "ic-icrc7-ledger",
+        &[],
+    )
+}
+
+fn minter() -> Principal {
+    PrincipalId::new_user_test_id(1).0
+}
+
+fn account(n: u64) -> Account {
+    Account::from(PrincipalId::new_user_test_id(n).0)
+}
+
+fn install_ledger(env: &StateMachine) -> CanisterId {
+    let args = LedgerArgument::Init(InitArgs {
+        minting_account: Account::from(minter()),
+        symbol: "XNFT".to_string(),
+        name: "Test Collection".to_string(),
+        description: None,
+        logo: None,
+        supply_cap: None,
+        max_memo_size: None,
+        archive_options: ArchiveOptions {
+            trigger_threshold: 10,
+            num_blocks_to_archive: 5,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            more_controller_ids: None,
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+    });
+    env.install_canister(ledger_wasm(), Encode!(&args).unwrap(), None)
+        .unwrap()
+}
+
+fn install_index(env: &StateMachine, ledger_id: CanisterId) -> CanisterId {
+    let args = Some(IndexArg::Init(InitArg {
+        ledger_id: ledger_id.into(),
+        retrieve_blocks_from_ledger_interval_seconds: None,
+    }));
+    env.install_canister(index_wasm(), Encode!(&args).unwrap(), None)
+        .unwrap()
+}
+
+fn update<T: CandidType + for<'a> candid::Deserialize<'a>>(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    caller: Principal,
+    method: &str,
+    payload: Vec<u8>,
+) -> T {
+    Decode!(
+        &env.execute_ingress_as(PrincipalId(caller), canister_id, method, payload)
+            .unwrap_or_else(|e| panic!("failed to call {}: {}", method, e))
+            .bytes(),
+        T
+    )
+    .unwrap()
+}
+
+fn query<T: CandidType + for<'a> candid::Deserialize<'a>>(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    method: &str,
+    payload: Vec<u8>,
+) -> T {
+    Decode!(
+        &env.query(canister_id, method, payload)
+            .unwrap_or_else(|e| panic!("failed to query {}: {}", method, e))
+            .bytes(),
+        T
+    )
+    .unwrap()
+}
+
+fn mint(env: &StateMachine, ledger_id: CanisterId, to: Account, token_id: u64) {
+    let args = vec![MintArg {
+        to,
+        token_id: Nat::from(token_id),
+        metadata: vec![],
+        memo: None,
+        created_at_time: None,
+    }];
+    let results: Vec<Option<MintResult>> =
+        update(env, ledger_id, minter(), "mint", Encode!(&args).unwrap());
+    results[0].clone().unwrap().expect("failed to mint");
+}
+
+fn transfer(env: &StateMachine, ledger_id: CanisterId, from: Account, to: Account, token_id: u64) {
+    let args = vec![TransferArg {
+        from_subaccount: from.subaccount,
+        to,
+        token_id: Nat::from(token_id),
+        memo: None,
+        created_at_time: None,
+    }];
+    let results: Vec<Option<TransferResult>> = update(
+        env,
+        ledger_id,
+        from.owner,
+        "icrc7_transfer",
+        Encode!(&args).unwrap(),
+    );
+    results[0].clone().unwrap().expect("failed to transfer");
+}
+
+fn wait_until_sync_is_completed(env: &StateMachine, index_id: CanisterId, num_blocks: u64) {
+    for _ in 0..MAX_ATTEMPTS_FOR_INDEX_SYNC_WAIT {
+        env.advance_time(Duration::from_secs(60));
+        env.tick();
+        let status: Status = query(env, index_id, "status", Encode!().unwrap());
+        if status.num_blocks_synced == num_blocks {
+            return;
+        }
+    }
+    panic!("the index canister was unable to sync all the blocks with the ledger");
+}
+
+fn tokens_of(env: &StateMachine, index_id: CanisterId, account: Account) -> Vec<Nat> {
+    query(
+        env,
+        index_id,
+        "icrc7_tokens_of",
+        Encode!(&account, &Option::<Nat>::None, &Option::<Nat>::None).unwrap(),
+    )
+}
+
+#[test]
+fn test_index_syncs_with_ledger_and_archive() {
+    let env = StateMachine::new();
+    let ledger_id = install_ledger(&env);
+    let index_id = install_index(&env, ledger_id);
+
+    // Enough blocks to trigger archiving.
+    for token_id in 0..10 {
+        mint(&env, ledger_id, account(2), token_id);
+    }
+    transfer(&env, ledger_id, account(2), account(3), 4);
+    transfer(&env, ledger_id, account(3), account(4), 4);
+    wait_until_sync_is_completed(&env, index_id, 12);
+
+    assert_eq!(
+        tokens_of(&env, index_id, account(2)),
+        [0u64, 1, 2, 3, 5, 6, 7, 8, 9].map(Nat::from).to_vec()
+    );
+    assert_eq!(tokens_of(&env, index_id, account(3)), Vec::<Nat>::new());
+    assert_eq!(tokens_of(&env, index_id, account(4)), vec![Nat::from(4u64)]);
+
+    let res: GetAccountTransactionsResult = query(
+        &env,
+        index_id,
+        "get_account_transactions",
+        Encode!(&GetAccountTransactionsArgs {
+            account: account(3),
+            start: None,
+            max_results: Nat::from(10u64),
+        })
+        .unwrap(),
+    );
+    let res = res.expect("failed to get the account transactions");
+    assert_eq!(res.balance, Nat::from(0u64));
+    let ids: Vec<Nat> = res.transactions.into_iter().map(|tx| tx.id).collect();
+    assert_eq!(ids, vec![Nat::from(11u64), Nat::from(10u64)]);
+    assert_eq!(res.oldest_tx_id, Some(Nat::from(10u64)));
+}
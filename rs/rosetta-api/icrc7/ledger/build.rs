@@ -0,0 +1,25 @@
+use std::env::{self};
+use std::path::PathBuf;
+
+fn main() {
+    let cargo_manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let archive_path = match env::var_os("IC_ICRC7_ARCHIVE_WASM_PATH") {
+        Some(wasm_path) => PathBuf::from(wasm_path),
+        None => cargo_manifest_dir
+            .join("../wasm/ic-icrc7-archive.wasm.gz")
+            .canonicalize()
+            .unwrap_or_else(|e| {
+                panic!(
+                    "failed to find the archive wasm ({}); build the ICRC-7 archive canister and set IC_ICRC7_ARCHIVE_WASM_PATH",
+                    e
+                )
+            }),
+    };
+
+    println!("cargo:rerun-if-changed={}", archive_path.display());
+    println!("cargo:rerun-if-env-changed=IC_ICRC7_ARCHIVE_WASM_PATH");
+    println!(
+        "cargo:rustc-env=IC_ICRC7_ARCHIVE_WASM_PATH={}",
+        archive_path.display()
+    );
+}
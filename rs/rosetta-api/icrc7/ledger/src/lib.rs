@@ -0,0 +1,1110 @@
+pub mod cdk_runtime;
+
+#[cfg(test)]
+mod tests;
+
+use crate::cdk_runtime::CdkRuntime;
+use candid::{CandidType, Nat, Principal};
+use ic_crypto_tree_hash::{Label, MixedHashTree};
+use ic_icrc1::blocks::{
+    encoded_block_to_generic_block, generic_block_to_encoded_block,
+    try_encoded_block_to_generic_block,
+};
+use ic_icrc7::{token_id_from_nat, Block, Operation, TokenId, Transaction};
+pub use ic_ledger_canister_core::archive::ArchiveOptions;
+use ic_ledger_canister_core::{
+    archive::ArchiveCanisterWasm, blockchain::Blockchain, ledger::blockchain_block_locations,
+    range_utils,
+};
+use ic_ledger_core::block::{BlockIndex, EncodedBlock};
+use icrc_ledger_types::icrc::generic_value::{ICRC3Value, Value};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::Memo;
+use icrc_ledger_types::icrc3::archive::{
+    GetArchivesArgs, GetArchivesResult, ICRC3ArchiveInfo, QueryArchiveFn,
+};
+use icrc_ledger_types::icrc3::blocks::{
+    ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResult,
+};
+use icrc_ledger_types::icrc37::approve::{
+    ApprovalInfo, ApproveCollectionArg, ApproveCollectionError, ApproveCollectionResult,
+    ApproveTokenArg, ApproveTokenError, ApproveTokenResult, CollectionApproval, IsApprovedArg,
+    TokenApproval,
+};
+use icrc_ledger_types::icrc37::revoke::{
+    RevokeCollectionApprovalArg, RevokeCollectionApprovalError, RevokeCollectionApprovalResult,
+    RevokeTokenApprovalArg, RevokeTokenApprovalError, RevokeTokenApprovalResult,
+};
+use icrc_ledger_types::icrc37::transfer_from::{
+    TransferFromArg, TransferFromError, TransferFromResult,
+};
+use icrc_ledger_types::icrc7::transfer::{TransferArg, TransferError, TransferResult};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::ops::Bound::{Excluded, Included, Unbounded};
+use std::time::Duration;
+
+const TRANSACTION_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+const PERMITTED_DRIFT: Duration = Duration::from_secs(2 * 60);
+/// The maximum number of blocks the ledger returns for a single
+/// icrc3_get_blocks request.
+const MAX_BLOCKS_PER_RESPONSE: u64 = 100;
+
+pub const DEFAULT_MAX_MEMO_SIZE: u32 = 32;
+pub const DEFAULT_MAX_QUERY_BATCH_SIZE: u64 = 100;
+pub const DEFAULT_MAX_UPDATE_BATCH_SIZE: u64 = 20;
+pub const DEFAULT_TAKE_VALUE: u64 = 100;
+pub const DEFAULT_MAX_TAKE_VALUE: u64 = 1_000;
+pub const DEFAULT_MAX_APPROVALS_PER_TOKEN_OR_COLLECTION: u64 = 100;
+pub const DEFAULT_MAX_REVOKE_APPROVALS: u64 = 100;
+
+/// The error code of the GenericError returned when the memo exceeds the maximum memo size.
+pub const MEMO_TOO_LARGE_ERROR_CODE: u64 = 1;
+/// The error code of the GenericError returned when an account has too many approvals.
+pub const TOO_MANY_APPROVALS_ERROR_CODE: u64 = 2;
+/// The error code of the GenericError returned when an approval expires in the past.
+pub const APPROVAL_EXPIRED_ERROR_CODE: u64 = 3;
+/// The error code of the GenericBatchError returned when a batch is too large.
+pub const BATCH_TOO_LARGE_ERROR_CODE: u64 = 4;
+
+#[derive(Clone, Debug)]
+pub struct Icrc7ArchiveWasm;
+
+impl ArchiveCanisterWasm for Icrc7ArchiveWasm {
+    fn archive_wasm() -> Cow<'static, [u8]> {
+        Cow::Borrowed(include_bytes!(env!("IC_ICRC7_ARCHIVE_WASM_PATH")))
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct InitArgs {
+    /// The account allowed to mint new tokens.
+    pub minting_account: Account,
+    pub symbol: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub logo: Option<String>,
+    pub supply_cap: Option<Nat>,
+    pub max_memo_size: Option<u32>,
+    pub archive_options: ArchiveOptions,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct UpgradeArgs {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memo_size: Option<u32>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum LedgerArgument {
+    Init(InitArgs),
+    Upgrade(Option<UpgradeArgs>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct MintArg {
+    pub to: Account,
+    pub token_id: Nat,
+    pub metadata: Vec<(String, ICRC3Value)>,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub enum MintError {
+    // The caller is not the minting account.
+    Unauthorized,
+    TokenIdAlreadyExists,
+    SupplyCapReached,
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: Nat },
+    GenericError { error_code: Nat, message: String },
+    GenericBatchError { error_code: Nat, message: String },
+}
+
+pub type MintResult = Result<Nat, MintError>;
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct BurnArg {
+    #[serde(default)]
+    pub from_subaccount: Option<Subaccount>,
+    pub token_id: Nat,
+    #[serde(default)]
+    pub memo: Option<Memo>,
+    #[serde(default)]
+    pub created_at_time: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub enum BurnError {
+    // The caller does not own the token.
+    Unauthorized,
+    NonExistingTokenId,
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: Nat },
+    GenericError { error_code: Nat, message: String },
+    GenericBatchError { error_code: Nat, message: String },
+}
+
+pub type BurnResult = Result<Nat, BurnError>;
+
+/// Errors that every update of the ledger can return.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum CommonError {
+    MemoTooLarge { max_memo_size: u32 },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: BlockIndex },
+}
+
+macro_rules! impl_from_common_error {
+    ($error:ident, |$duplicate_of:ident| $duplicate:expr) => {
+        impl From<CommonError> for $error {
+            fn from(err: CommonError) -> Self {
+                match err {
+                    CommonError::MemoTooLarge { max_memo_size } => Self::GenericError {
+                        error_code: Nat::from(MEMO_TOO_LARGE_ERROR_CODE),
+                        message: format!("the memo field size exceeds {} bytes", max_memo_size),
+                    },
+                    CommonError::TooOld => Self::TooOld,
+                    CommonError::CreatedInFuture { ledger_time } => {
+                        Self::CreatedInFuture { ledger_time }
+                    }
+                    CommonError::Duplicate { duplicate_of } => {
+                        let $duplicate_of = duplicate_of;
+                        $duplicate
+                    }
+                }
+            }
+        }
+    };
+    ($error:ident) => {
+        impl_from_common_error!($error, |duplicate_of| Self::Duplicate {
+            duplicate_of: Nat::from(duplicate_of),
+        });
+    };
+}
+
+impl_from_common_error!(TransferError);
+impl_from_common_error!(TransferFromError);
+impl_from_common_error!(MintError);
+impl_from_common_error!(BurnError);
+// ICRC-37 approvals and revocations are not deduplicated, so they never fail with
+// CommonError::Duplicate.
+impl_from_common_error!(ApproveTokenError, |duplicate_of| unreachable!(
+    "approvals are not deduplicated (duplicate of {})",
+    duplicate_of
+));
+impl_from_common_error!(ApproveCollectionError, |duplicate_of| unreachable!(
+    "approvals are not deduplicated (duplicate of {})",
+    duplicate_of
+));
+impl_from_common_error!(RevokeTokenApprovalError, |duplicate_of| unreachable!(
+    "revocations are not deduplicated (duplicate of {})",
+    duplicate_of
+));
+impl_from_common_error!(RevokeCollectionApprovalError, |duplicate_of| unreachable!(
+    "revocations are not deduplicated (duplicate of {})",
+    duplicate_of
+));
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Token {
+    owner: Account,
+    /// The metadata of the token as a generic map, encoded like a block.
+    metadata: EncodedBlock,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+struct Approval {
+    expires_at: Option<u64>,
+    created_at_time: u64,
+    memo: Option<Memo>,
+}
+
+impl Approval {
+    fn is_active(&self, now: u64) -> bool {
+        self.expires_at.map_or(true, |expires_at| now < expires_at)
+    }
+
+    fn to_approval_info(&self, spender: Account, owner: &Account) -> ApprovalInfo {
+        ApprovalInfo {
+            spender,
+            from_subaccount: owner.subaccount,
+            expires_at: self.expires_at,
+            memo: self.memo.clone(),
+            created_at_time: self.created_at_time,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Ledger {
+    blockchain: Blockchain<CdkRuntime, Icrc7ArchiveWasm>,
+    minting_account: Account,
+    symbol: String,
+    name: String,
+    description: Option<String>,
+    logo: Option<String>,
+    supply_cap: Option<TokenId>,
+    max_memo_size: u32,
+
+    tokens: BTreeMap<TokenId, Token>,
+    /// The tokens of each account, used to answer `icrc7_tokens_of` queries.
+    owner_tokens: BTreeSet<(Account, TokenId)>,
+    /// The approvals of each token, indexed by spender.
+    /// The approvals of a token are dropped when the token changes owner.
+    token_approvals: BTreeMap<TokenId, BTreeMap<Account, Approval>>,
+    /// The collection approvals of each owner, indexed by spender.
+    collection_approvals: BTreeMap<Account, BTreeMap<Account, Approval>>,
+
+    /// The hashes of the recent transactions with a created_at_time,
+    /// used to deduplicate transactions.
+    transactions_by_hash: BTreeMap<[u8; 32], BlockIndex>,
+    /// The recent transactions with a created_at_time and the time
+    /// at which they were recorded, in recording order.
+    transactions_by_time: VecDeque<(u64, [u8; 32])>,
+}
+
+impl Ledger {
+    pub fn from_init_args(args: InitArgs) -> Self {
+        Self {
+            blockchain: Blockchain::new_with_archive(args.archive_options),
+            minting_account: args.minting_account,
+            symbol: args.symbol,
+            name: args.name,
+            description: args.description,
+            logo: args.logo,
+            supply_cap: args.supply_cap.map(|cap| {
+                token_id_from_nat(&cap)
+                    .unwrap_or_else(|| panic!("supply cap {} does not fit into 128 bits", cap))
+            }),
+            max_memo_size: args.max_memo_size.unwrap_or(DEFAULT_MAX_MEMO_SIZE),
+            tokens: BTreeMap::new(),
+            owner_tokens: BTreeSet::new(),
+            token_approvals: BTreeMap::new(),
+            collection_approvals: BTreeMap::new(),
+            transactions_by_hash: BTreeMap::new(),
+            transactions_by_time: VecDeque::new(),
+        }
+    }
+
+    pub fn upgrade(&mut self, args: UpgradeArgs) {
+        if let Some(symbol) = args.symbol {
+            self.symbol = symbol;
+        }
+        if let Some(name) = args.name {
+            self.name = name;
+        }
+        if let Some(description) = args.description {
+            self.description = Some(description);
+        }
+        if let Some(logo) = args.logo {
+            self.logo = Some(logo);
+        }
+        if let Some(max_memo_size) = args.max_memo_size {
+            if max_memo_size < self.max_memo_size {
+                panic!(
+                    "The max memo size can only be increased from {} not decreased to {}",
+                    self.max_memo_size, max_memo_size
+                );
+            }
+            self.max_memo_size = max_memo_size;
+        }
+    }
+
+    pub fn blockchain(&self) -> &Blockchain<CdkRuntime, Icrc7ArchiveWasm> {
+        &self.blockchain
+    }
+
+    pub fn blockchain_mut(&mut self) -> &mut Blockchain<CdkRuntime, Icrc7ArchiveWasm> {
+        &mut self.blockchain
+    }
+
+    pub fn minting_account(&self) -> &Account {
+        &self.minting_account
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn logo(&self) -> Option<&str> {
+        self.logo.as_deref()
+    }
+
+    pub fn total_supply(&self) -> u64 {
+        self.tokens.len() as u64
+    }
+
+    pub fn supply_cap(&self) -> Option<Nat> {
+        self.supply_cap.map(Nat::from)
+    }
+
+    pub fn max_memo_size(&self) -> u32 {
+        self.max_memo_size
+    }
+
+    pub fn tx_window(&self) -> Duration {
+        TRANSACTION_WINDOW
+    }
+
+    pub fn permitted_drift(&self) -> Duration {
+        PERMITTED_DRIFT
+    }
+
+    /// Returns the ICRC-7 metadata of the collection.
+    pub fn collection_metadata(&self) -> Vec<(String, ICRC3Value)> {
+        let mut metadata = vec![
+            (
+                "icrc7:symbol".to_string(),
+                ICRC3Value::Text(self.symbol.clone()),
+            ),
+            (
+                "icrc7:name".to_string(),
+                ICRC3Value::Text(self.name.clone()),
+            ),
+            (
+                "icrc7:total_supply".to_string(),
+                ICRC3Value::Nat(Nat::from(self.total_supply())),
+            ),
+            (
+                "icrc7:max_memo_size".to_string(),
+                ICRC3Value::Nat(Nat::from(self.max_memo_size)),
+            ),
+            (
+                "icrc7:tx_window".to_string(),
+                ICRC3Value::Nat(Nat::from(TRANSACTION_WINDOW.as_nanos() as u64)),
+            ),
+            (
+                "icrc7:permitted_drift".to_string(),
+                ICRC3Value::Nat(Nat::from(PERMITTED_DRIFT.as_nanos() as u64)),
+            ),
+        ];
+        if let Some(description) = &self.description {
+            metadata.push((
+                "icrc7:description".to_string(),
+                ICRC3Value::Text(description.clone()),
+            ));
+        }
+        if let Some(logo) = &self.logo {
+            metadata.push(("icrc7:logo".to_string(), ICRC3Value::Text(logo.clone())));
+        }
+        if let Some(supply_cap) = self.supply_cap {
+            metadata.push((
+                "icrc7:supply_cap".to_string(),
+                ICRC3Value::Nat(Nat::from(supply_cap)),
+            ));
+        }
+        metadata
+    }
+
+    fn get_token(&self, token_id: &Nat) -> Option<(TokenId, &Token)> {
+        let token_id = token_id_from_nat(token_id)?;
+        self.tokens.get(&token_id).map(|token| (token_id, token))
+    }
+
+    pub fn owner_of(&self, token_id: &Nat) -> Option<Account> {
+        self.get_token(token_id).map(|(_, token)| token.owner)
+    }
+
+    pub fn token_metadata(&self, token_id: &Nat) -> Option<Vec<(String, ICRC3Value)>> {
+        let (_, token) = self.get_token(token_id)?;
+        let metadata = try_encoded_block_to_generic_block(&token.metadata)
+            .and_then(Value::as_map)
+            .unwrap_or_else(|e| panic!("bug: invalid metadata of token {}: {}", token_id, e));
+        Some(
+            metadata
+                .into_iter()
+                .map(|(key, value)| (key, ICRC3Value::from(value)))
+                .collect(),
+        )
+    }
+
+    pub fn balance_of(&self, account: &Account) -> u64 {
+        self.owner_tokens
+            .range((*account, TokenId::MIN)..=(*account, TokenId::MAX))
+            .count() as u64
+    }
+
+    /// Returns up to `take` token ids in ascending order, starting after `prev`.
+    pub fn tokens(&self, prev: Option<Nat>, take: usize) -> Vec<Nat> {
+        let start = match prev {
+            Some(prev) => match token_id_from_nat(&prev) {
+                Some(prev) => Excluded(prev),
+                None => return vec![],
+            },
+            None => Unbounded,
+        };
+        self.tokens
+            .range((start, Unbounded))
+            .take(take)
+            .map(|(token_id, _)| Nat::from(*token_id))
+            .collect()
+    }
+
+    /// Returns up to `take` token ids of the given account in ascending order,
+    /// starting after `prev`.
+    pub fn tokens_of(&self, account: Account, prev: Option<Nat>, take: usize) -> Vec<Nat> {
+        let start = match prev {
+            Some(prev) => match token_id_from_nat(&prev) {
+                Some(prev) => Excluded((account, prev)),
+                None => return vec![],
+            },
+            None => Included((account, TokenId::MIN)),
+        };
+        self.owner_tokens
+            .range((start, Unbounded))
+            .take_while(|(owner, _)| owner == &account)
+            .map(|(_, token_id)| *token_id)
+            .take(take)
+            .map(Nat::from)
+            .collect()
+    }
+
+    fn active_approval<'a>(
+        approvals: Option<&'a BTreeMap<Account, Approval>>,
+        spender: &Account,
+        now: u64,
+    ) -> Option<&'a Approval> {
+        approvals?
+            .get(spender)
+            .filter(|approval| approval.is_active(now))
+    }
+
+    fn has_approval(
+        &self,
+        spender: &Account,
+        token_id: TokenId,
+        owner: &Account,
+        now: u64,
+    ) -> bool {
+        Self::active_approval(self.token_approvals.get(&token_id), spender, now).is_some()
+            || Self::active_approval(self.collection_approvals.get(owner), spender, now).is_some()
+    }
+
+    pub fn is_approved(&self, arg: &IsApprovedArg, now: u64) -> bool {
+        let Some((token_id, token)) = self.get_token(&arg.token_id) else {
+            return false;
+        };
+        let owner = Account {
+            owner: token.owner.owner,
+            subaccount: arg.from_subaccount,
+        };
+        token.owner == owner && self.has_approval(&arg.spender, token_id, &owner, now)
+    }
+
+    /// Returns up to `take` active approvals of the token, ordered by spender,
+    /// starting after the spender of `prev`.
+    pub fn get_token_approvals(
+        &self,
+        token_id: &Nat,
+        prev: Option<TokenApproval>,
+        take: usize,
+        now: u64,
+    ) -> Vec<TokenApproval> {
+        let Some((token_id, token)) = self.get_token(token_id) else {
+            return vec![];
+        };
+        let Some(approvals) = self.token_approvals.get(&token_id) else {
+            return vec![];
+        };
+        let start = prev.map_or(Unbounded, |prev| Excluded(prev.approval_info.spender));
+        approvals
+            .range((start, Unbounded))
+            .filter(|(_, approval)| approval.is_active(now))
+            .take(take)
+            .map(|(spender, approval)| TokenApproval {
+                token_id: Nat::from(token_id),
+                approval_info: approval.to_approval_info(*spender, &token.owner),
+            })
+            .collect()
+    }
+
+    /// Returns up to `take` active collection approvals of the owner, ordered by spender,
+    /// starting after the spender of `prev`.
+    pub fn get_collection_approvals(
+        &self,
+        owner: Account,
+        prev: Option<CollectionApproval>,
+        take: usize,
+        now: u64,
+    ) -> Vec<CollectionApproval> {
+        let Some(approvals) = self.collection_approvals.get(&owner) else {
+            return vec![];
+        };
+        let start = prev.map_or(Unbounded, |prev| Excluded(prev.spender));
+        approvals
+            .range((start, Unbounded))
+            .filter(|(_, approval)| approval.is_active(now))
+            .take(take)
+            .map(|(spender, approval)| approval.to_approval_info(*spender, &owner))
+            .collect()
+    }
+
+    fn check_memo(&self, memo: &Option<Memo>) -> Result<(), CommonError> {
+        match memo {
+            Some(memo) if memo.0.len() > self.max_memo_size as usize => {
+                Err(CommonError::MemoTooLarge {
+                    max_memo_size: self.max_memo_size,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_created_at_time(created_at_time: Option<u64>, now: u64) -> Result<(), CommonError> {
+        let Some(created_at_time) = created_at_time else {
+            return Ok(());
+        };
+        let window = (TRANSACTION_WINDOW + PERMITTED_DRIFT).as_nanos() as u64;
+        if created_at_time.saturating_add(window) < now {
+            return Err(CommonError::TooOld);
+        }
+        if created_at_time > now.saturating_add(PERMITTED_DRIFT.as_nanos() as u64) {
+            return Err(CommonError::CreatedInFuture { ledger_time: now });
+        }
+        Ok(())
+    }
+
+    /// Checks the fields shared by all the transactions and, if `deduplicate` is true,
+    /// whether the transaction is a duplicate of a recent one.
+    fn validate_transaction(
+        &mut self,
+        tx: &Transaction,
+        deduplicate: bool,
+        now: u64,
+    ) -> Result<(), CommonError> {
+        self.purge_old_transactions(now);
+        self.check_memo(&tx.memo)?;
+        Self::check_created_at_time(tx.created_at_time, now)?;
+        if deduplicate && tx.created_at_time.is_some() {
+            if let Some(duplicate_of) = self.transactions_by_hash.get(&tx.hash()) {
+                return Err(CommonError::Duplicate {
+                    duplicate_of: *duplicate_of,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn purge_old_transactions(&mut self, now: u64) {
+        // A transaction with a created_at_time can be recorded up to PERMITTED_DRIFT
+        // before its created_at_time and is a possible duplicate until
+        // TRANSACTION_WINDOW + PERMITTED_DRIFT after it.
+        let retention = (TRANSACTION_WINDOW + PERMITTED_DRIFT * 2).as_nanos() as u64;
+        while let Some((recorded_at, hash)) = self.transactions_by_time.front() {
+            if recorded_at.saturating_add(retention) >= now {
+                break;
+            }
+            self.transactions_by_hash.remove(hash);
+            self.transactions_by_time.pop_front();
+        }
+    }
+
+    /// Appends the transaction to the blockchain and returns the index of its block.
+    fn record_transaction(&mut self, tx: Transaction, deduplicate: bool, now: u64) -> BlockIndex {
+        let hash = (deduplicate && tx.created_at_time.is_some()).then(|| tx.hash());
+        let block = Block {
+            parent_hash: self.blockchain.last_hash,
+            transaction: tx,
+            timestamp: now,
+        };
+        let block_index = self
+            .blockchain
+            .add_block(block)
+            .unwrap_or_else(|e| panic!("bug: failed to add a block: {}", e));
+        if let Some(hash) = hash {
+            self.transactions_by_hash.insert(hash, block_index);
+            self.transactions_by_time.push_back((now, hash));
+        }
+        block_index
+    }
+
+    fn set_owner(&mut self, token_id: TokenId, new_owner: Account) {
+        let token = self
+            .tokens
+            .get_mut(&token_id)
+            .expect("bug: changing the owner of a non-existing token");
+        self.owner_tokens.remove(&(token.owner, token_id));
+        self.owner_tokens.insert((new_owner, token_id));
+        token.owner = new_owner;
+        // Approvals are granted by the owner, so they do not survive a change of owner.
+        self.token_approvals.remove(&token_id);
+    }
+
+    pub fn mint(&mut self, caller: Principal, arg: MintArg, now: u64) -> MintResult {
+        if caller != self.minting_account.owner {
+            return Err(MintError::Unauthorized);
+        }
+        let token_id = token_id_from_nat(&arg.token_id).ok_or_else(|| MintError::GenericError {
+            error_code: Nat::from(0u64),
+            message: format!("token id {} does not fit into 128 bits", arg.token_id),
+        })?;
+        if self.tokens.contains_key(&token_id) {
+            return Err(MintError::TokenIdAlreadyExists);
+        }
+        if self
+            .supply_cap
+            .is_some_and(|cap| self.tokens.len() as u128 >= cap)
+        {
+            return Err(MintError::SupplyCapReached);
+        }
+        let metadata = Value::Map(
+            arg.metadata
+                .into_iter()
+                .map(|(key, value)| (key, Value::from(value)))
+                .collect(),
+        );
+        let encoded_metadata = generic_block_to_encoded_block(metadata.clone()).map_err(|e| {
+            MintError::GenericError {
+                error_code: Nat::from(0u64),
+                message: format!("invalid metadata: {}", e),
+            }
+        })?;
+        let tx = Transaction {
+            operation: Operation::Mint {
+                to: arg.to,
+                token_id,
+                metadata,
+            },
+            memo: arg.memo,
+            created_at_time: arg.created_at_time,
+        };
+        self.validate_transaction(&tx, true, now)?;
+        let block_index = self.record_transaction(tx, true, now);
+        self.tokens.insert(
+            token_id,
+            Token {
+                owner: arg.to,
+                metadata: encoded_metadata,
+            },
+        );
+        self.owner_tokens.insert((arg.to, token_id));
+        Ok(Nat::from(block_index))
+    }
+
+    pub fn burn(&mut self, caller: Principal, arg: BurnArg, now: u64) -> BurnResult {
+        let from = Account {
+            owner: caller,
+            subaccount: arg.from_subaccount,
+        };
+        let (token_id, token) = self
+            .get_token(&arg.token_id)
+            .ok_or(BurnError::NonExistingTokenId)?;
+        if token.owner != from {
+            return Err(BurnError::Unauthorized);
+        }
+        let tx = Transaction {
+            operation: Operation::Burn { from, token_id },
+            memo: arg.memo,
+            created_at_time: arg.created_at_time,
+        };
+        self.validate_transaction(&tx, true, now)?;
+        let block_index = self.record_transaction(tx, true, now);
+        self.tokens.remove(&token_id);
+        self.owner_tokens.remove(&(from, token_id));
+        self.token_approvals.remove(&token_id);
+        Ok(Nat::from(block_index))
+    }
+
+    pub fn transfer(&mut self, caller: Principal, arg: TransferArg, now: u64) -> TransferResult {
+        let from = Account {
+            owner: caller,
+            subaccount: arg.from_subaccount,
+        };
+        let (token_id, token) = self
+            .get_token(&arg.token_id)
+            .ok_or(TransferError::NonExistingTokenId)?;
+        if token.owner != from {
+            return Err(TransferError::Unauthorized);
+        }
+        if arg.to == from {
+            return Err(TransferError::InvalidRecipient);
+        }
+        let tx = Transaction {
+            operation: Operation::Transfer {
+                from,
+                to: arg.to,
+                token_id,
+            },
+            memo: arg.memo,
+            created_at_time: arg.created_at_time,
+        };
+        self.validate_transaction(&tx, true, now)?;
+        let block_index = self.record_transaction(tx, true, now);
+        self.set_owner(token_id, arg.to);
+        Ok(Nat::from(block_index))
+    }
+
+    pub fn transfer_from(
+        &mut self,
+        caller: Principal,
+        arg: TransferFromArg,
+        now: u64,
+    ) -> TransferFromResult {
+        let spender = Account {
+            owner: caller,
+            subaccount: arg.spender_subaccount,
+        };
+        let (token_id, token) = self
+            .get_token(&arg.token_id)
+            .ok_or(TransferFromError::NonExistingTokenId)?;
+        if token.owner != arg.from || !self.has_approval(&spender, token_id, &arg.from, now) {
+            return Err(TransferFromError::Unauthorized);
+        }
+        if arg.to == arg.from {
+            return Err(TransferFromError::InvalidRecipient);
+        }
+        let tx = Transaction {
+            operation: Operation::TransferFrom {
+                spender,
+                from: arg.from,
+                to: arg.to,
+                token_id,
+            },
+            memo: arg.memo,
+            created_at_time: arg.created_at_time,
+        };
+        self.validate_transaction(&tx, true, now)?;
+        let block_index = self.record_transaction(tx, true, now);
+        self.set_owner(token_id, arg.to);
+        Ok(Nat::from(block_index))
+    }
+
+    pub fn approve_token(
+        &mut self,
+        caller: Principal,
+        arg: ApproveTokenArg,
+        now: u64,
+    ) -> ApproveTokenResult {
+        let info = arg.approval_info;
+        let from = Account {
+            owner: caller,
+            subaccount: info.from_subaccount,
+        };
+        if info.spender == from {
+            return Err(ApproveTokenError::InvalidSpender);
+        }
+        let (token_id, token) = self
+            .get_token(&arg.token_id)
+            .ok_or(ApproveTokenError::NonExistingTokenId)?;
+        if token.owner != from {
+            return Err(ApproveTokenError::Unauthorized);
+        }
+        if info.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(ApproveTokenError::GenericError {
+                error_code: Nat::from(APPROVAL_EXPIRED_ERROR_CODE),
+                message: "the approval expires in the past".to_string(),
+            });
+        }
+        let approvals = self.token_approvals.get(&token_id);
+        if approvals.is_some_and(|approvals| {
+            !approvals.contains_key(&info.spender)
+                && approvals.len() as u64 >= DEFAULT_MAX_APPROVALS_PER_TOKEN_OR_COLLECTION
+        }) {
+            return Err(ApproveTokenError::GenericError {
+                error_code: Nat::from(TOO_MANY_APPROVALS_ERROR_CODE),
+                message: format!(
+                    "the token has more than {} approvals",
+                    DEFAULT_MAX_APPROVALS_PER_TOKEN_OR_COLLECTION
+                ),
+            });
+        }
+        let tx = Transaction {
+            operation: Operation::ApproveToken {
+                from,
+                spender: info.spender,
+                token_id,
+                expires_at: info.expires_at,
+            },
+            memo: info.memo.clone(),
+            created_at_time: Some(info.created_at_time),
+        };
+        self.validate_transaction(&tx, false, now)?;
+        let block_index = self.record_transaction(tx, false, now);
+        self.token_approvals.entry(token_id).or_default().insert(
+            info.spender,
+            Approval {
+                expires_at: info.expires_at,
+                created_at_time: info.created_at_time,
+                memo: info.memo,
+            },
+        );
+        Ok(Nat::from(block_index))
+    }
+
+    pub fn approve_collection(
+        &mut self,
+        caller: Principal,
+        arg: ApproveCollectionArg,
+        now: u64,
+    ) -> ApproveCollectionResult {
+        let info = arg.approval_info;
+        let from = Account {
+            owner: caller,
+            subaccount: info.from_subaccount,
+        };
+        if info.spender == from {
+            return Err(ApproveCollectionError::InvalidSpender);
+        }
+        if info.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err(ApproveCollectionError::GenericError {
+                error_code: Nat::from(APPROVAL_EXPIRED_ERROR_CODE),
+                message: "the approval expires in the past".to_string(),
+            });
+        }
+        let approvals = self.collection_approvals.get(&from);
+        if approvals.is_some_and(|approvals| {
+            !approvals.contains_key(&info.spender)
+                && approvals.len() as u64 >= DEFAULT_MAX_APPROVALS_PER_TOKEN_OR_COLLECTION
+        }) {
+            return Err(ApproveCollectionError::GenericError {
+                error_code: Nat::from(TOO_MANY_APPROVALS_ERROR_CODE),
+                message: format!(
+                    "the account has more than {} collection approvals",
+                    DEFAULT_MAX_APPROVALS_PER_TOKEN_OR_COLLECTION
+                ),
+            });
+        }
+        let tx = Transaction {
+            operation: Operation::ApproveCollection {
+                from,
+                spender: info.spender,
+                expires_at: info.expires_at,
+            },
+            memo: info.memo.clone(),
+            created_at_time: Some(info.created_at_time),
+        };
+        self.validate_transaction(&tx, false, now)?;
+        let block_index = self.record_transaction(tx, false, now);
+        self.collection_approvals.entry(from).or_default().insert(
+            info.spender,
+            Approval {
+                expires_at: info.expires_at,
+                created_at_time: info.created_at_time,
+                memo: info.memo,
+            },
+        );
+        Ok(Nat::from(block_index))
+    }
+
+    pub fn revoke_token_approvals(
+        &mut self,
+        caller: Principal,
+        arg: RevokeTokenApprovalArg,
+        now: u64,
+    ) -> RevokeTokenApprovalResult {
+        let from = Account {
+            owner: caller,
+            subaccount: arg.from_subaccount,
+        };
+        let (token_id, token) = self
+            .get_token(&arg.token_id)
+            .ok_or(RevokeTokenApprovalError::NonExistingTokenId)?;
+        if token.owner != from {
+            return Err(RevokeTokenApprovalError::Unauthorized);
+        }
+        let approvals = self.token_approvals.get(&token_id);
+        let exists = match &arg.spender {
+            Some(spender) => approvals.is_some_and(|approvals| approvals.contains_key(spender)),
+            None => approvals.is_some_and(|approvals| !approvals.is_empty()),
+        };
+        if !exists {
+            return Err(RevokeTokenApprovalError::ApprovalDoesNotExist);
+        }
+        let tx = Transaction {
+            operation: Operation::RevokeToken {
+                from,
+                spender: arg.spender,
+                token_id,
+            },
+            memo: arg.memo,
+            created_at_time: arg.created_at_time,
+        };
+        self.validate_transaction(&tx, false, now)?;
+        let block_index = self.record_transaction(tx, false, now);
+        match arg.spender {
+            Some(spender) => {
+                if let Some(approvals) = self.token_approvals.get_mut(&token_id) {
+                    approvals.remove(&spender);
+                    if approvals.is_empty() {
+                        self.token_approvals.remove(&token_id);
+                    }
+                }
+            }
+            None => {
+                self.token_approvals.remove(&token_id);
+            }
+        }
+        Ok(Nat::from(block_index))
+    }
+
+    pub fn revoke_collection_approvals(
+        &mut self,
+        caller: Principal,
+        arg: RevokeCollectionApprovalArg,
+        now: u64,
+    ) -> RevokeCollectionApprovalResult {
+        let from = Account {
+            owner: caller,
+            subaccount: arg.from_subaccount,
+        };
+        let approvals = self.collection_approvals.get(&from);
+        let exists = match &arg.spender {
+            Some(spender) => approvals.is_some_and(|approvals| approvals.contains_key(spender)),
+            None => approvals.is_some_and(|approvals| !approvals.is_empty()),
+        };
+        if !exists {
+            return Err(RevokeCollectionApprovalError::ApprovalDoesNotExist);
+        }
+        let tx = Transaction {
+            operation: Operation::RevokeCollection {
+                from,
+                spender: arg.spender,
+            },
+            memo: arg.memo,
+            created_at_time: arg.created_at_time,
+        };
+        self.validate_transaction(&tx, false, now)?;
+        let block_index = self.record_transaction(tx, false, now);
+        match arg.spender {
+            Some(spender) => {
+                if let Some(approvals) = self.collection_approvals.get_mut(&from) {
+                    approvals.remove(&spender);
+                    if approvals.is_empty() {
+                        self.collection_approvals.remove(&from);
+                    }
+                }
+            }
+            None => {
+                self.collection_approvals.remove(&from);
+            }
+        }
+        Ok(Nat::from(block_index))
+    }
+
+    /// Returns the root hash of the certified ledger state.
+    /// The canister code must call set_certified_data with the value this function returns after
+    /// each successful modification of the ledger.
+    pub fn root_hash(&self) -> [u8; 32] {
+        self.construct_hash_tree().digest().0
+    }
+
+    pub fn construct_hash_tree(&self) -> MixedHashTree {
+        match self.blockchain.last_hash {
+            Some(hash) => {
+                let last_block_index = self.blockchain.chain_length().checked_sub(1).unwrap();
+                MixedHashTree::Fork(Box::new((
+                    MixedHashTree::Labeled(
+                        Label::from("last_block_index"),
+                        Box::new(MixedHashTree::Leaf(last_block_index.to_be_bytes().to_vec())),
+                    ),
+                    MixedHashTree::Labeled(
+                        Label::from("tip_hash"),
+                        Box::new(MixedHashTree::Leaf(hash.as_slice().to_vec())),
+                    ),
+                )))
+            }
+            None => MixedHashTree::Empty,
+        }
+    }
+
+    pub fn icrc3_get_archives(&self, args: GetArchivesArgs) -> GetArchivesResult {
+        self.blockchain
+            .archive
+            .read()
+            .expect("Unable to access the archives")
+            .iter()
+            .flat_map(|archive| {
+                archive
+                    .index()
+                    .into_iter()
+                    .filter_map(|((start, end), canister_id)| {
+                        let canister_id = Principal::from(canister_id);
+                        if let Some(from) = args.from {
+                            if canister_id <= from {
+                                return None;
+                            }
+                        }
+                        Some(ICRC3ArchiveInfo {
+                            canister_id,
+                            start: Nat::from(start),
+                            end: Nat::from(end),
+                        })
+                    })
+            })
+            .collect()
+    }
+
+    pub fn icrc3_get_blocks(&self, args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+        let mut blocks = vec![];
+        let mut archived_blocks_by_callback = BTreeMap::new();
+        for arg in args {
+            let (start, length) = match arg.as_start_and_length() {
+                Ok(start_and_length) => start_and_length,
+                Err(_) => continue,
+            };
+            let max_length = MAX_BLOCKS_PER_RESPONSE.saturating_sub(blocks.len() as u64);
+            if max_length == 0 {
+                break;
+            }
+            let length = max_length.min(length).min(usize::MAX as u64) as usize;
+            let locations = blockchain_block_locations(&self.blockchain, start, length);
+            let local_blocks = self.blockchain.block_slice(locations.local_blocks.clone());
+            for (id, block) in (locations.local_blocks.start..).zip(local_blocks) {
+                blocks.push(BlockWithId {
+                    id: Nat::from(id),
+                    block: ICRC3Value::from(encoded_block_to_generic_block(block)),
+                });
+            }
+            for (canister_id, slice) in locations.archived_blocks {
+                let callback = QueryArchiveFn::<Vec<GetBlocksRequest>, GetBlocksResult>::new(
+                    canister_id.get().0,
+                    "icrc3_get_blocks",
+                );
+                archived_blocks_by_callback
+                    .entry(callback)
+                    .or_insert(vec![])
+                    .push(GetBlocksRequest {
+                        start: Nat::from(slice.start),
+                        length: Nat::from(range_utils::range_len(&slice)),
+                    });
+            }
+            if blocks.len() as u64 >= MAX_BLOCKS_PER_RESPONSE {
+                break;
+            }
+        }
+        let archived_blocks = archived_blocks_by_callback
+            .into_iter()
+            .map(|(callback, args)| ArchivedBlocks { args, callback })
+            .collect();
+        GetBlocksResult {
+            log_length: Nat::from(self.blockchain.chain_length()),
+            blocks,
+            archived_blocks,
+        }
+    }
+}
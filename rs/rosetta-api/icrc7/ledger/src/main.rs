@@ -0,0 +1,632 @@
+use candid::{candid_method, Nat, Principal};
+use ic_canister_log::{declare_log_buffer, export};
+use ic_canisters_http_types::{HttpRequest, HttpResponse, HttpResponseBuilder};
+use ic_cdk::api::stable::{StableReader, StableWriter};
+use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
+use ic_icrc1::endpoints::StandardRecord;
+use ic_icrc7::{ICRC37_URL, ICRC7_URL};
+use ic_icrc7_ledger::cdk_runtime::CdkRuntime;
+use ic_icrc7_ledger::{
+    BurnArg, BurnError, BurnResult, Icrc7ArchiveWasm, Ledger, LedgerArgument, MintArg, MintError,
+    MintResult, BATCH_TOO_LARGE_ERROR_CODE, DEFAULT_MAX_APPROVALS_PER_TOKEN_OR_COLLECTION,
+    DEFAULT_MAX_QUERY_BATCH_SIZE, DEFAULT_MAX_REVOKE_APPROVALS, DEFAULT_MAX_TAKE_VALUE,
+    DEFAULT_MAX_UPDATE_BATCH_SIZE, DEFAULT_TAKE_VALUE,
+};
+use ic_ledger_canister_core::blockchain::Blockchain;
+use ic_ledger_canister_core::ledger::{archive_blockchain_blocks, BlockchainAccess};
+use ic_ledger_canister_core::runtime::total_memory_size_bytes;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::archive::{GetArchivesArgs, GetArchivesResult};
+use icrc_ledger_types::icrc3::blocks::{
+    GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate, SupportedBlockType,
+};
+use icrc_ledger_types::icrc37::approve::{
+    ApproveCollectionArg, ApproveCollectionError, ApproveCollectionResult, ApproveTokenArg,
+    ApproveTokenError, ApproveTokenResult, CollectionApproval, IsApprovedArg, TokenApproval,
+};
+use icrc_ledger_types::icrc37::revoke::{
+    RevokeCollectionApprovalArg, RevokeCollectionApprovalError, RevokeCollectionApprovalResult,
+    RevokeTokenApprovalArg, RevokeTokenApprovalError, RevokeTokenApprovalResult,
+};
+use icrc_ledger_types::icrc37::transfer_from::{
+    TransferFromArg, TransferFromError, TransferFromResult,
+};
+use icrc_ledger_types::icrc7::transfer::{TransferArg, TransferError, TransferResult};
+use serde_bytes::ByteBuf;
+use std::cell::RefCell;
+
+const MAX_MESSAGE_SIZE: u64 = 1024 * 1024;
+
+thread_local! {
+    static LEDGER: RefCell<Option<Ledger>> = const { RefCell::new(None) };
+}
+
+declare_log_buffer!(name = LOG, capacity = 1000);
+
+fn with_ledger<R>(f: impl FnOnce(&Ledger) -> R) -> R {
+    LEDGER.with(|cell| {
+        f(cell
+            .borrow()
+            .as_ref()
+            .expect("ledger state not initialized"))
+    })
+}
+
+fn with_ledger_mut<R>(f: impl FnOnce(&mut Ledger) -> R) -> R {
+    LEDGER.with(|cell| {
+        f(cell
+            .borrow_mut()
+            .as_mut()
+            .expect("ledger state not initialized"))
+    })
+}
+
+struct Access;
+impl BlockchainAccess for Access {
+    type Runtime = CdkRuntime;
+    type ArchiveWasm = Icrc7ArchiveWasm;
+
+    fn with_blockchain<R>(f: impl FnOnce(&Blockchain<CdkRuntime, Icrc7ArchiveWasm>) -> R) -> R {
+        with_ledger(|ledger| f(ledger.blockchain()))
+    }
+
+    fn with_blockchain_mut<R>(
+        f: impl FnOnce(&mut Blockchain<CdkRuntime, Icrc7ArchiveWasm>) -> R,
+    ) -> R {
+        with_ledger_mut(|ledger| f(ledger.blockchain_mut()))
+    }
+}
+
+#[candid_method(init)]
+#[init]
+fn init(args: LedgerArgument) {
+    match args {
+        LedgerArgument::Init(init_args) => LEDGER.with(|cell| {
+            *cell.borrow_mut() = Some(Ledger::from_init_args(init_args));
+        }),
+        LedgerArgument::Upgrade(_) => {
+            panic!("Cannot initialize the canister with an Upgrade argument. Please provide an Init argument.");
+        }
+    }
+    ic_cdk::api::set_certified_data(&with_ledger(Ledger::root_hash));
+}
+
+#[pre_upgrade]
+fn pre_upgrade() {
+    with_ledger(|ledger| ciborium::ser::into_writer(ledger, StableWriter::default()))
+        .expect("failed to encode ledger state");
+}
+
+#[post_upgrade]
+fn post_upgrade(args: Option<LedgerArgument>) {
+    LEDGER.with(|cell| {
+        *cell.borrow_mut() = Some(
+            ciborium::de::from_reader(StableReader::default())
+                .expect("failed to decode ledger state"),
+        );
+    });
+    match args {
+        Some(LedgerArgument::Upgrade(Some(upgrade_args))) => {
+            with_ledger_mut(|ledger| ledger.upgrade(upgrade_args))
+        }
+        Some(LedgerArgument::Upgrade(None)) | None => {}
+        Some(LedgerArgument::Init(_)) => {
+            panic!("Cannot upgrade the canister with an Init argument. Please provide an Upgrade argument.");
+        }
+    }
+    ic_cdk::api::set_certified_data(&with_ledger(Ledger::root_hash));
+}
+
+fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> std::io::Result<()> {
+    w.encode_gauge(
+        "ledger_stable_memory_pages",
+        ic_cdk::api::stable::stable64_size() as f64,
+        "Size of the stable memory allocated by this canister measured in 64K Wasm pages.",
+    )?;
+    w.encode_gauge(
+        "ledger_total_memory_bytes",
+        total_memory_size_bytes() as f64,
+        "Total amount of memory (heap, stable memory, etc) that has been allocated by this canister.",
+    )?;
+    w.encode_gauge(
+        "ledger_cycle_balance",
+        ic_cdk::api::canister_balance128() as f64,
+        "Cycle balance on the ledger canister.",
+    )?;
+    with_ledger(|ledger| {
+        let blockchain = ledger.blockchain();
+        w.encode_gauge(
+            "ledger_transactions",
+            blockchain.blocks.len() as f64,
+            "Number of transactions stored in the main memory.",
+        )?;
+        w.encode_gauge(
+            "ledger_archived_blocks",
+            blockchain.num_archived_blocks as f64,
+            "Total number of blocks sent to the archive.",
+        )?;
+        w.encode_gauge(
+            "ledger_total_supply",
+            ledger.total_supply() as f64,
+            "Number of tokens in existence.",
+        )?;
+        Ok(())
+    })
+}
+
+#[query(hidden = true)]
+fn http_request(req: HttpRequest) -> HttpResponse {
+    if req.path() == "/metrics" {
+        let mut writer =
+            ic_metrics_encoder::MetricsEncoder::new(vec![], ic_cdk::api::time() as i64 / 1_000_000);
+
+        match encode_metrics(&mut writer) {
+            Ok(()) => HttpResponseBuilder::ok()
+                .header("Content-Type", "text/plain; version=0.0.4")
+                .with_body_and_content_length(writer.into_inner())
+                .build(),
+            Err(err) => {
+                HttpResponseBuilder::server_error(format!("Failed to encode metrics: {}", err))
+                    .build()
+            }
+        }
+    } else if req.path() == "/logs" {
+        use std::io::Write;
+        let mut buf = vec![];
+        for entry in export(&LOG) {
+            writeln!(
+                &mut buf,
+                "{} {}:{} {}",
+                entry.timestamp, entry.file, entry.line, entry.message
+            )
+            .unwrap();
+        }
+        HttpResponseBuilder::ok()
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .with_body_and_content_length(buf)
+            .build()
+    } else {
+        HttpResponseBuilder::not_found().build()
+    }
+}
+
+/// Traps if a query batch exceeds the advertised limit.
+fn check_query_batch_size<T>(args: &[T]) {
+    if args.len() as u64 > DEFAULT_MAX_QUERY_BATCH_SIZE {
+        ic_cdk::trap(&format!(
+            "the batch contains {} elements, the maximum is {}",
+            args.len(),
+            DEFAULT_MAX_QUERY_BATCH_SIZE
+        ));
+    }
+}
+
+fn take_value(take: Option<Nat>) -> usize {
+    take.map_or(DEFAULT_TAKE_VALUE, |take| {
+        u64::try_from(take.0).map_or(DEFAULT_MAX_TAKE_VALUE, |take| {
+            take.min(DEFAULT_MAX_TAKE_VALUE)
+        })
+    }) as usize
+}
+
+/// Applies a batch of update calls one by one and archives blocks afterwards.
+///
+/// Batches exceeding the advertised limit are rejected as a whole with a
+/// single `GenericBatchError`.
+async fn execute_batch<Arg, Err>(
+    args: Vec<Arg>,
+    apply: fn(&mut Ledger, Principal, Arg, u64) -> Result<Nat, Err>,
+    batch_error: fn(Nat, String) -> Err,
+) -> Vec<Option<Result<Nat, Err>>> {
+    if args.len() as u64 > DEFAULT_MAX_UPDATE_BATCH_SIZE {
+        return vec![Some(Err(batch_error(
+            Nat::from(BATCH_TOO_LARGE_ERROR_CODE),
+            format!(
+                "the batch contains {} elements, the maximum is {}",
+                args.len(),
+                DEFAULT_MAX_UPDATE_BATCH_SIZE
+            ),
+        )))];
+    }
+    let caller = ic_cdk::api::caller();
+    let now = ic_cdk::api::time();
+    let results = with_ledger_mut(|ledger| {
+        args.into_iter()
+            .map(|arg| Some(apply(ledger, caller, arg, now)))
+            .collect()
+    });
+    ic_cdk::api::set_certified_data(&with_ledger(Ledger::root_hash));
+    archive_blockchain_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    results
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_collection_metadata() -> Vec<(String, ICRC3Value)> {
+    with_ledger(Ledger::collection_metadata)
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_symbol() -> String {
+    with_ledger(|ledger| ledger.symbol().to_string())
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_name() -> String {
+    with_ledger(|ledger| ledger.name().to_string())
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_description() -> Option<String> {
+    with_ledger(|ledger| ledger.description().map(str::to_string))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_logo() -> Option<String> {
+    with_ledger(|ledger| ledger.logo().map(str::to_string))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_total_supply() -> Nat {
+    Nat::from(with_ledger(Ledger::total_supply))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_supply_cap() -> Option<Nat> {
+    with_ledger(Ledger::supply_cap)
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_max_query_batch_size() -> Option<Nat> {
+    Some(Nat::from(DEFAULT_MAX_QUERY_BATCH_SIZE))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_max_update_batch_size() -> Option<Nat> {
+    Some(Nat::from(DEFAULT_MAX_UPDATE_BATCH_SIZE))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_default_take_value() -> Option<Nat> {
+    Some(Nat::from(DEFAULT_TAKE_VALUE))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_max_take_value() -> Option<Nat> {
+    Some(Nat::from(DEFAULT_MAX_TAKE_VALUE))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_max_memo_size() -> Option<Nat> {
+    Some(Nat::from(with_ledger(Ledger::max_memo_size)))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_atomic_batch_transfers() -> Option<bool> {
+    Some(false)
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_tx_window() -> Option<Nat> {
+    Some(Nat::from(with_ledger(Ledger::tx_window).as_nanos() as u64))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_permitted_drift() -> Option<Nat> {
+    Some(Nat::from(
+        with_ledger(Ledger::permitted_drift).as_nanos() as u64
+    ))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_token_metadata(token_ids: Vec<Nat>) -> Vec<Option<Vec<(String, ICRC3Value)>>> {
+    check_query_batch_size(&token_ids);
+    with_ledger(|ledger| {
+        token_ids
+            .iter()
+            .map(|token_id| ledger.token_metadata(token_id))
+            .collect()
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_owner_of(token_ids: Vec<Nat>) -> Vec<Option<Account>> {
+    check_query_batch_size(&token_ids);
+    with_ledger(|ledger| {
+        token_ids
+            .iter()
+            .map(|token_id| ledger.owner_of(token_id))
+            .collect()
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_balance_of(accounts: Vec<Account>) -> Vec<Nat> {
+    check_query_batch_size(&accounts);
+    with_ledger(|ledger| {
+        accounts
+            .iter()
+            .map(|account| Nat::from(ledger.balance_of(account)))
+            .collect()
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_tokens(prev: Option<Nat>, take: Option<Nat>) -> Vec<Nat> {
+    with_ledger(|ledger| ledger.tokens(prev, take_value(take)))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc7_tokens_of(account: Account, prev: Option<Nat>, take: Option<Nat>) -> Vec<Nat> {
+    with_ledger(|ledger| ledger.tokens_of(account, prev, take_value(take)))
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc7_transfer(args: Vec<TransferArg>) -> Vec<Option<TransferResult>> {
+    execute_batch(args, Ledger::transfer, |error_code, message| {
+        TransferError::GenericBatchError {
+            error_code,
+            message,
+        }
+    })
+    .await
+}
+
+#[update]
+#[candid_method(update)]
+async fn mint(args: Vec<MintArg>) -> Vec<Option<MintResult>> {
+    execute_batch(args, Ledger::mint, |error_code, message| {
+        MintError::GenericBatchError {
+            error_code,
+            message,
+        }
+    })
+    .await
+}
+
+#[update]
+#[candid_method(update)]
+async fn burn(args: Vec<BurnArg>) -> Vec<Option<BurnResult>> {
+    execute_batch(args, Ledger::burn, |error_code, message| {
+        BurnError::GenericBatchError {
+            error_code,
+            message,
+        }
+    })
+    .await
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc37_metadata() -> Vec<(String, ICRC3Value)> {
+    vec![
+        (
+            "icrc37:max_approvals_per_token_or_collection".to_string(),
+            ICRC3Value::Nat(Nat::from(DEFAULT_MAX_APPROVALS_PER_TOKEN_OR_COLLECTION)),
+        ),
+        (
+            "icrc37:max_revoke_approvals".to_string(),
+            ICRC3Value::Nat(Nat::from(DEFAULT_MAX_REVOKE_APPROVALS)),
+        ),
+    ]
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc37_max_approvals_per_token_or_collection() -> Option<Nat> {
+    Some(Nat::from(DEFAULT_MAX_APPROVALS_PER_TOKEN_OR_COLLECTION))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc37_max_revoke_approvals() -> Option<Nat> {
+    Some(Nat::from(DEFAULT_MAX_REVOKE_APPROVALS))
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc37_approve_tokens(args: Vec<ApproveTokenArg>) -> Vec<Option<ApproveTokenResult>> {
+    execute_batch(args, Ledger::approve_token, |error_code, message| {
+        ApproveTokenError::GenericBatchError {
+            error_code,
+            message,
+        }
+    })
+    .await
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc37_approve_collection(
+    args: Vec<ApproveCollectionArg>,
+) -> Vec<Option<ApproveCollectionResult>> {
+    execute_batch(args, Ledger::approve_collection, |error_code, message| {
+        ApproveCollectionError::GenericBatchError {
+            error_code,
+            message,
+        }
+    })
+    .await
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc37_revoke_token_approvals(
+    args: Vec<RevokeTokenApprovalArg>,
+) -> Vec<Option<RevokeTokenApprovalResult>> {
+    execute_batch(
+        args,
+        Ledger::revoke_token_approvals,
+        |error_code, message| RevokeTokenApprovalError::GenericBatchError {
+            error_code,
+            message,
+        },
+    )
+    .await
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc37_revoke_collection_approvals(
+    args: Vec<RevokeCollectionApprovalArg>,
+) -> Vec<Option<RevokeCollectionApprovalResult>> {
+    execute_batch(
+        args,
+        Ledger::revoke_collection_approvals,
+        |error_code, message| RevokeCollectionApprovalError::GenericBatchError {
+            error_code,
+            message,
+        },
+    )
+    .await
+}
+
+#[update]
+#[candid_method(update)]
+async fn icrc37_transfer_from(args: Vec<TransferFromArg>) -> Vec<Option<TransferFromResult>> {
+    execute_batch(args, Ledger::transfer_from, |error_code, message| {
+        TransferFromError::GenericBatchError {
+            error_code,
+            message,
+        }
+    })
+    .await
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc37_is_approved(args: Vec<IsApprovedArg>) -> Vec<bool> {
+    check_query_batch_size(&args);
+    let now = ic_cdk::api::time();
+    with_ledger(|ledger| {
+        args.iter()
+            .map(|arg| ledger.is_approved(arg, now))
+            .collect()
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc37_get_token_approvals(
+    token_id: Nat,
+    prev: Option<TokenApproval>,
+    take: Option<Nat>,
+) -> Vec<TokenApproval> {
+    let now = ic_cdk::api::time();
+    with_ledger(|ledger| ledger.get_token_approvals(&token_id, prev, take_value(take), now))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc37_get_collection_approvals(
+    owner: Account,
+    prev: Option<CollectionApproval>,
+    take: Option<Nat>,
+) -> Vec<CollectionApproval> {
+    let now = ic_cdk::api::time();
+    with_ledger(|ledger| ledger.get_collection_approvals(owner, prev, take_value(take), now))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_get_archives(args: GetArchivesArgs) -> GetArchivesResult {
+    with_ledger(|ledger| ledger.icrc3_get_archives(args))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    with_ledger(|ledger| ledger.icrc3_get_blocks(args))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    let certificate = ByteBuf::from(ic_cdk::api::data_certificate()?);
+    let hash_tree = with_ledger(Ledger::construct_hash_tree);
+    let mut tree_buf = vec![];
+    ciborium::ser::into_writer(&hash_tree, &mut tree_buf).unwrap();
+    Some(ICRC3DataCertificate {
+        certificate,
+        hash_tree: ByteBuf::from(tree_buf),
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    ic_icrc7::supported_block_types()
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc10_supported_standards() -> Vec<StandardRecord> {
+    [
+        ("ICRC-7", ICRC7_URL),
+        (
+            "ICRC-10",
+            "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-10/ICRC-10.md",
+        ),
+        ("ICRC-37", ICRC37_URL),
+        (
+            "ICRC-3",
+            "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, url)| StandardRecord {
+        name: name.to_string(),
+        url: url.to_string(),
+    })
+    .collect()
+}
+
+candid::export_service!();
+
+#[query]
+fn __get_candid_interface_tmp_hack() -> String {
+    __export_service()
+}
+
+fn main() {}
+
+#[test]
+fn check_candid_interface() {
+    use candid_parser::utils::{service_equal, CandidSource};
+
+    let new_interface = __export_service();
+    let manifest_dir = std::path::PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let old_interface = manifest_dir.join("ledger.did");
+    service_equal(
+        CandidSource::Text(&new_interface),
+        CandidSource::File(old_interface.as_path()),
+    )
+    .unwrap_or_else(|e| {
+        panic!(
+            "the ledger interface is not compatible with {}: {:?}",
+            old_interface.display(),
+            e
+        )
+    });
+}
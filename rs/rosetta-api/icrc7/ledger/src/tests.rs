@@ -0,0 +1,483 @@
+use crate::{
+    BurnArg, BurnError, InitArgs, Ledger, MintArg, MintError, UpgradeArgs,
+    MEMO_TOO_LARGE_ERROR_CODE,
+};
+use candid::{Nat, Principal};
+use ic_base_types::PrincipalId;
+use ic_icrc7::{Block, Operation};
+use ic_ledger_canister_core::archive::ArchiveOptions;
+use ic_ledger_core::block::BlockType;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::Memo;
+use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
+use icrc_ledger_types::icrc37::approve::{
+    ApprovalInfo, ApproveCollectionArg, ApproveTokenArg, ApproveTokenError, IsApprovedArg,
+};
+use icrc_ledger_types::icrc37::revoke::{
+    RevokeCollectionApprovalArg, RevokeCollectionApprovalError, RevokeTokenApprovalArg,
+};
+use icrc_ledger_types::icrc37::transfer_from::{TransferFromArg, TransferFromError};
+use icrc_ledger_types::icrc7::transfer::{TransferArg, TransferError};
+
+const NOW: u64 = 1_700_000_000_000_000_000;
+const MINUTE: u64 = 60_000_000_000;
+
+fn principal(n: u64) -> Principal {
+    PrincipalId::new_user_test_id(n).into()
+}
+
+fn account(n: u64) -> Account {
+    Account {
+        owner: principal(n),
+        subaccount: None,
+    }
+}
+
+fn minter() -> Principal {
+    principal(0)
+}
+
+fn default_init_args() -> InitArgs {
+    InitArgs {
+        minting_account: Account {
+            owner: minter(),
+            subaccount: None,
+        },
+        symbol: "XNFT".to_string(),
+        name: "Test Collection".to_string(),
+        description: Some("A collection used in tests".to_string()),
+        logo: None,
+        supply_cap: Some(Nat::from(3u64)),
+        max_memo_size: None,
+        archive_options: ArchiveOptions {
+            trigger_threshold: 1_000,
+            num_blocks_to_archive: 1_000,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            more_controller_ids: None,
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+    }
+}
+
+fn mint_arg(to: Account, token_id: u64) -> MintArg {
+    MintArg {
+        to,
+        token_id: Nat::from(token_id),
+        metadata: vec![(
+            "name".to_string(),
+            ICRC3Value::Text(format!("Token #{}", token_id)),
+        )],
+        memo: None,
+        created_at_time: None,
+    }
+}
+
+fn transfer_arg(to: Account, token_id: u64) -> TransferArg {
+    TransferArg {
+        from_subaccount: None,
+        to,
+        token_id: Nat::from(token_id),
+        memo: None,
+        created_at_time: None,
+    }
+}
+
+fn approval_info(spender: Account) -> ApprovalInfo {
+    ApprovalInfo {
+        spender,
+        from_subaccount: None,
+        expires_at: None,
+        memo: None,
+        created_at_time: NOW,
+    }
+}
+
+fn last_operation(ledger: &Ledger) -> Operation {
+    let encoded = ledger.blockchain().last().unwrap().clone();
+    Block::decode(encoded).unwrap().transaction.operation
+}
+
+#[test]
+fn test_mint_and_query_tokens() {
+    let mut ledger = Ledger::from_init_args(default_init_args());
+
+    assert_eq!(
+        ledger.mint(principal(1), mint_arg(account(1), 1), NOW),
+        Err(MintError::Unauthorized)
+    );
+    assert_eq!(
+        ledger.mint(minter(), mint_arg(account(1), 1), NOW),
+        Ok(Nat::from(0u64))
+    );
+    assert_eq!(
+        ledger.mint(minter(), mint_arg(account(2), 1), NOW),
+        Err(MintError::TokenIdAlreadyExists)
+    );
+    assert_eq!(
+        ledger.mint(minter(), mint_arg(account(1), 5), NOW),
+        Ok(Nat::from(1u64))
+    );
+    assert_eq!(
+        ledger.mint(minter(), mint_arg(account(2), 3), NOW),
+        Ok(Nat::from(2u64))
+    );
+    assert_eq!(
+        ledger.mint(minter(), mint_arg(account(2), 4), NOW),
+        Err(MintError::SupplyCapReached)
+    );
+
+    assert_eq!(ledger.total_supply(), 3);
+    assert_eq!(ledger.owner_of(&Nat::from(1u64)), Some(account(1)));
+    assert_eq!(ledger.owner_of(&Nat::from(2u64)), None);
+    assert_eq!(ledger.balance_of(&account(1)), 2);
+    assert_eq!(ledger.balance_of(&account(3)), 0);
+    assert_eq!(
+        ledger.token_metadata(&Nat::from(5u64)),
+        Some(vec![(
+            "name".to_string(),
+            ICRC3Value::Text("Token #5".to_string())
+        )])
+    );
+    assert_eq!(
+        ledger.tokens(None, 10),
+        vec![Nat::from(1u64), Nat::from(3u64), Nat::from(5u64)]
+    );
+    assert_eq!(
+        ledger.tokens(Some(Nat::from(1u64)), 1),
+        vec![Nat::from(3u64)]
+    );
+    assert_eq!(
+        ledger.tokens_of(account(1), None, 10),
+        vec![Nat::from(1u64), Nat::from(5u64)]
+    );
+    assert_eq!(
+        ledger.tokens_of(account(1), Some(Nat::from(1u64)), 10),
+        vec![Nat::from(5u64)]
+    );
+    assert_eq!(ledger.tokens_of(account(3), None, 10), vec![]);
+}
+
+#[test]
+fn test_transfer() {
+    let mut ledger = Ledger::from_init_args(default_init_args());
+    ledger.mint(minter(), mint_arg(account(1), 1), NOW).unwrap();
+
+    assert_eq!(
+        ledger.transfer(principal(1), transfer_arg(account(2), 2), NOW),
+        Err(TransferError::NonExistingTokenId)
+    );
+    assert_eq!(
+        ledger.transfer(principal(2), transfer_arg(account(3), 1), NOW),
+        Err(TransferError::Unauthorized)
+    );
+    assert_eq!(
+        ledger.transfer(principal(1), transfer_arg(account(1), 1), NOW),
+        Err(TransferError::InvalidRecipient)
+    );
+    assert_eq!(
+        ledger.transfer(principal(1), transfer_arg(account(2), 1), NOW),
+        Ok(Nat::from(1u64))
+    );
+    assert_eq!(
+        last_operation(&ledger),
+        Operation::Transfer {
+            from: account(1),
+            to: account(2),
+            token_id: 1,
+        }
+    );
+    assert_eq!(ledger.owner_of(&Nat::from(1u64)), Some(account(2)));
+    assert_eq!(ledger.tokens_of(account(1), None, 10), vec![]);
+    assert_eq!(
+        ledger.tokens_of(account(2), None, 10),
+        vec![Nat::from(1u64)]
+    );
+}
+
+#[test]
+fn test_transfer_deduplication_and_time_checks() {
+    let mut ledger = Ledger::from_init_args(default_init_args());
+    ledger.mint(minter(), mint_arg(account(1), 1), NOW).unwrap();
+
+    let arg = TransferArg {
+        created_at_time: Some(NOW),
+        ..transfer_arg(account(2), 1)
+    };
+    assert_eq!(
+        ledger.transfer(principal(1), arg.clone(), NOW),
+        Ok(Nat::from(1u64))
+    );
+    // Transfer the token back so that the duplicate transfer would otherwise succeed.
+    ledger
+        .transfer(principal(2), transfer_arg(account(1), 1), NOW)
+        .unwrap();
+    assert_eq!(
+        ledger.transfer(principal(1), arg.clone(), NOW + MINUTE),
+        Err(TransferError::Duplicate {
+            duplicate_of: Nat::from(1u64)
+        })
+    );
+
+    assert_eq!(
+        ledger.transfer(
+            principal(1),
+            TransferArg {
+                created_at_time: Some(NOW + 10 * MINUTE),
+                ..transfer_arg(account(2), 1)
+            },
+            NOW
+        ),
+        Err(TransferError::CreatedInFuture { ledger_time: NOW })
+    );
+    assert_eq!(
+        ledger.transfer(principal(1), arg.clone(), NOW + 25 * 60 * MINUTE),
+        Err(TransferError::TooOld)
+    );
+    assert_eq!(
+        ledger.transfer(
+            principal(1),
+            TransferArg {
+                memo: Some(Memo::from(vec![0u8; 33])),
+                ..transfer_arg(account(2), 1)
+            },
+            NOW
+        ),
+        Err(TransferError::GenericError {
+            error_code: Nat::from(MEMO_TOO_LARGE_ERROR_CODE),
+            message: "the memo field size exceeds 32 bytes".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_token_approval_and_transfer_from() {
+    let mut ledger = Ledger::from_init_args(default_init_args());
+    ledger.mint(minter(), mint_arg(account(1), 1), NOW).unwrap();
+
+    let transfer_from_arg = TransferFromArg {
+        spender_subaccount: None,
+        from: account(1),
+        to: account(3),
+        token_id: Nat::from(1u64),
+        memo: None,
+        created_at_time: None,
+    };
+    assert_eq!(
+        ledger.transfer_from(principal(2), transfer_from_arg.clone(), NOW),
+        Err(TransferFromError::Unauthorized)
+    );
+
+    let approve_arg = ApproveTokenArg {
+        token_id: Nat::from(1u64),
+        approval_info: approval_info(account(2)),
+    };
+    assert_eq!(
+        ledger.approve_token(principal(2), approve_arg.clone(), NOW),
+        Err(ApproveTokenError::Unauthorized)
+    );
+    assert_eq!(
+        ledger.approve_token(
+            principal(1),
+            ApproveTokenArg {
+                token_id: Nat::from(1u64),
+                approval_info: approval_info(account(1)),
+            },
+            NOW
+        ),
+        Err(ApproveTokenError::InvalidSpender)
+    );
+    assert_eq!(
+        ledger.approve_token(principal(1), approve_arg, NOW),
+        Ok(Nat::from(1u64))
+    );
+    let is_approved_arg = IsApprovedArg {
+        spender: account(2),
+        from_subaccount: None,
+        token_id: Nat::from(1u64),
+    };
+    assert!(ledger.is_approved(&is_approved_arg, NOW));
+    assert_eq!(
+        ledger
+            .get_token_approvals(&Nat::from(1u64), None, 10, NOW)
+            .len(),
+        1
+    );
+
+    assert_eq!(
+        ledger.transfer_from(principal(2), transfer_from_arg, NOW),
+        Ok(Nat::from(2u64))
+    );
+    assert_eq!(ledger.owner_of(&Nat::from(1u64)), Some(account(3)));
+    // The approvals of the previous owner are dropped with the transfer.
+    assert!(!ledger.is_approved(&is_approved_arg, NOW));
+    assert_eq!(
+        ledger.get_token_approvals(&Nat::from(1u64), None, 10, NOW),
+        vec![]
+    );
+}
+
+#[test]
+fn test_collection_approval_and_revocation() {
+    let mut ledger = Ledger::from_init_args(default_init_args());
+    ledger.mint(minter(), mint_arg(account(1), 1), NOW).unwrap();
+    ledger.mint(minter(), mint_arg(account(1), 2), NOW).unwrap();
+
+    let approve_arg = ApproveCollectionArg {
+        approval_info: ApprovalInfo {
+            expires_at: Some(NOW + MINUTE),
+            ..approval_info(account(2))
+        },
+    };
+    assert_eq!(
+        ledger.approve_collection(principal(1), approve_arg, NOW),
+        Ok(Nat::from(2u64))
+    );
+    for token_id in [1u64, 2] {
+        let arg = IsApprovedArg {
+            spender: account(2),
+            from_subaccount: None,
+            token_id: Nat::from(token_id),
+        };
+        assert!(ledger.is_approved(&arg, NOW));
+        // Approvals are inactive once expired.
+        assert!(!ledger.is_approved(&arg, NOW + MINUTE));
+    }
+    assert_eq!(
+        ledger
+            .get_collection_approvals(account(1), None, 10, NOW)
+            .len(),
+        1
+    );
+
+    let revoke_arg = RevokeCollectionApprovalArg {
+        spender: Some(account(3)),
+        from_subaccount: None,
+        memo: None,
+        created_at_time: None,
+    };
+    assert_eq!(
+        ledger.revoke_collection_approvals(principal(1), revoke_arg, NOW),
+        Err(RevokeCollectionApprovalError::ApprovalDoesNotExist)
+    );
+    let revoke_arg = RevokeCollectionApprovalArg {
+        spender: None,
+        from_subaccount: None,
+        memo: None,
+        created_at_time: None,
+    };
+    assert_eq!(
+        ledger.revoke_collection_approvals(principal(1), revoke_arg, NOW),
+        Ok(Nat::from(3u64))
+    );
+    assert_eq!(
+        last_operation(&ledger),
+        Operation::RevokeCollection {
+            from: account(1),
+            spender: None,
+        }
+    );
+    assert_eq!(
+        ledger.get_collection_approvals(account(1), None, 10, NOW),
+        vec![]
+    );
+
+    ledger
+        .approve_token(
+            principal(1),
+            ApproveTokenArg {
+                token_id: Nat::from(1u64),
+                approval_info: approval_info(account(2)),
+            },
+            NOW,
+        )
+        .unwrap();
+    let revoke_arg = RevokeTokenApprovalArg {
+        spender: Some(account(2)),
+        from_subaccount: None,
+        token_id: Nat::from(1u64),
+        memo: None,
+        created_at_time: None,
+    };
+    assert_eq!(
+        ledger.revoke_token_approvals(principal(1), revoke_arg, NOW),
+        Ok(Nat::from(5u64))
+    );
+    assert_eq!(
+        ledger.get_token_approvals(&Nat::from(1u64), None, 10, NOW),
+        vec![]
+    );
+}
+
+#[test]
+fn test_burn() {
+    let mut ledger = Ledger::from_init_args(default_init_args());
+    ledger.mint(minter(), mint_arg(account(1), 1), NOW).unwrap();
+
+    let burn_arg = BurnArg {
+        from_subaccount: None,
+        token_id: Nat::from(1u64),
+        memo: None,
+        created_at_time: None,
+    };
+    assert_eq!(
+        ledger.burn(principal(2), burn_arg.clone(), NOW),
+        Err(BurnError::Unauthorized)
+    );
+    assert_eq!(
+        ledger.burn(principal(1), burn_arg.clone(), NOW),
+        Ok(Nat::from(1u64))
+    );
+    assert_eq!(
+        ledger.burn(principal(1), burn_arg, NOW),
+        Err(BurnError::NonExistingTokenId)
+    );
+    assert_eq!(ledger.total_supply(), 0);
+    assert_eq!(ledger.balance_of(&account(1)), 0);
+}
+
+#[test]
+fn test_icrc3_get_blocks_and_certification() {
+    let mut ledger = Ledger::from_init_args(default_init_args());
+    assert_eq!(ledger.root_hash(), ledger.construct_hash_tree().digest().0);
+    for token_id in 1..=3 {
+        ledger
+            .mint(minter(), mint_arg(account(1), token_id), NOW)
+            .unwrap();
+    }
+
+    let result = ledger.icrc3_get_blocks(vec![GetBlocksRequest {
+        start: Nat::from(1u64),
+        length: Nat::from(10u64),
+    }]);
+    assert_eq!(result.log_length, Nat::from(3u64));
+    assert!(result.archived_blocks.is_empty());
+    let ids: Vec<_> = result.blocks.iter().map(|block| block.id.clone()).collect();
+    assert_eq!(ids, vec![Nat::from(1u64), Nat::from(2u64)]);
+
+    // The hash of the last block is the certified tip hash.
+    let tip = result.blocks.last().unwrap().block.clone();
+    assert_eq!(
+        Some(tip.hash().to_vec()),
+        ledger
+            .blockchain()
+            .last_hash
+            .map(|hash| hash.as_slice().to_vec())
+    );
+}
+
+#[test]
+fn test_upgrade() {
+    let mut ledger = Ledger::from_init_args(default_init_args());
+    ledger.upgrade(UpgradeArgs {
+        logo: Some("data:image/png;base64,".to_string()),
+        max_memo_size: Some(64),
+        ..UpgradeArgs::default()
+    });
+    assert_eq!(ledger.symbol(), "XNFT");
+    assert_eq!(ledger.logo(), Some("data:image/png;base64,"));
+    assert_eq!(ledger.max_memo_size(), 64);
+}
@@ -0,0 +1,210 @@
+use candid::{CandidType, Decode, Encode, Nat, Principal};
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_icrc7_ledger::{ArchiveOptions, InitArgs, LedgerArgument, MintArg, MintResult};
+use ic_state_machine_tests::StateMachine;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::archive::{GetArchivesArgs, GetArchivesResult};
+use icrc_ledger_types::icrc3::blocks::{GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate};
+use icrc_ledger_types::icrc7::transfer::{TransferArg, TransferResult};
+use serde_bytes::ByteBuf;
+
+const ARCHIVE_TRIGGER_THRESHOLD: u64 = 10;
+const NUM_BLOCKS_TO_ARCHIVE: u64 = 5;
+
+fn ledger_wasm() -> Vec<u8> {
+    ic_test_utilities_load_wasm::load_wasm(
+        std::env::var("CARGO_MANIFEST_DIR").unwrap(),
+        "ic-icrc7-ledger",
+        &[],
+    )
+}
+
+fn minter() -> Principal {
+    PrincipalId::new_user_test_id(1).0
+}
+
+fn account(n: u64) -> Account {
+    Account::from(PrincipalId::new_user_test_id(n).0)
+}
+
+fn install_ledger(env: &StateMachine) -> CanisterId {
+    let args = LedgerArgument::Init(InitArgs {
+        minting_account: Account::from(minter()),
+        symbol: "XNFT".to_string(),
+        name: "Test Collection".to_string(),
+        description: None,
+        logo: None,
+        supply_cap: None,
+        max_memo_size: None,
+        archive_options: ArchiveOptions {
+            trigger_threshold: ARCHIVE_TRIGGER_THRESHOLD as usize,
+            num_blocks_to_archive: NUM_BLOCKS_TO_ARCHIVE as usize,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            more_controller_ids: None,
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+    });
+    env.install_canister(ledger_wasm(), Encode!(&args).unwrap(), None)
+        .unwrap()
+}
+
+fn update<T: CandidType + for<'a> candid::Deserialize<'a>>(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    caller: Principal,
+    method: &str,
+    payload: Vec<u8>,
+) -> T {
+    Decode!(
+        &env.execute_ingress_as(PrincipalId(caller), canister_id, method, payload)
+            .unwrap_or_else(|e| panic!("failed to call {}: {}", method, e))
+            .bytes(),
+        T
+    )
+    .unwrap()
+}
+
+fn query<T: CandidType + for<'a> candid::Deserialize<'a>>(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    method: &str,
+    payload: Vec<u8>,
+) -> T {
+    Decode!(
+        &env.query(canister_id, method, payload)
+            .unwrap_or_else(|e| panic!("failed to query {}: {}", method, e))
+            .bytes(),
+        T
+    )
+    .unwrap()
+}
+
+fn mint(env: &StateMachine, ledger_id: CanisterId, to: Account, token_id: u64) -> Nat {
+    let args = vec![MintArg {
+        to,
+        token_id: Nat::from(token_id),
+        metadata: vec![],
+        memo: None,
+        created_at_time: None,
+    }];
+    let results: Vec<Option<MintResult>> =
+        update(env, ledger_id, minter(), "mint", Encode!(&args).unwrap());
+    results[0].clone().unwrap().expect("failed to mint")
+}
+
+fn get_blocks(
+    env: &StateMachine,
+    canister_id: CanisterId,
+    start: u64,
+    length: u64,
+) -> GetBlocksResult {
+    let args = vec![GetBlocksRequest {
+        start: Nat::from(start),
+        length: Nat::from(length),
+    }];
+    query(
+        env,
+        canister_id,
+        "icrc3_get_blocks",
+        Encode!(&args).unwrap(),
+    )
+}
+
+#[test]
+fn test_mint_and_transfer() {
+    let env = StateMachine::new();
+    let ledger_id = install_ledger(&env);
+
+    assert_eq!(mint(&env, ledger_id, account(2), 7), Nat::from(0u64));
+
+    let owners: Vec<Option<Account>> = query(
+        &env,
+        ledger_id,
+        "icrc7_owner_of",
+        Encode!(&vec![Nat::from(7u64), Nat::from(8u64)]).unwrap(),
+    );
+    assert_eq!(owners, vec![Some(account(2)), None]);
+
+    let args = vec![TransferArg {
+        from_subaccount: None,
+        to: account(3),
+        token_id: Nat::from(7u64),
+        memo: None,
+        created_at_time: None,
+    }];
+    let results: Vec<Option<TransferResult>> = update(
+        &env,
+        ledger_id,
+        account(2).owner,
+        "icrc7_transfer",
+        Encode!(&args).unwrap(),
+    );
+    assert_eq!(results, vec![Some(Ok(Nat::from(1u64)))]);
+
+    let balances: Vec<Nat> = query(
+        &env,
+        ledger_id,
+        "icrc7_balance_of",
+        Encode!(&vec![account(2), account(3)]).unwrap(),
+    );
+    assert_eq!(balances, vec![Nat::from(0u64), Nat::from(1u64)]);
+
+    let certificate: Option<ICRC3DataCertificate> = query(
+        &env,
+        ledger_id,
+        "icrc3_get_tip_certificate",
+        Encode!().unwrap(),
+    );
+    assert!(certificate.is_some());
+}
+
+#[test]
+fn test_archiving() {
+    let env = StateMachine::new();
+    let ledger_id = install_ledger(&env);
+    for token_id in 0..ARCHIVE_TRIGGER_THRESHOLD {
+        mint(&env, ledger_id, account(2), token_id);
+    }
+
+    let archives: GetArchivesResult = query(
+        &env,
+        ledger_id,
+        "icrc3_get_archives",
+        Encode!(&GetArchivesArgs { from: None }).unwrap(),
+    );
+    assert_eq!(archives.len(), 1);
+    assert_eq!(archives[0].start, Nat::from(0u64));
+    assert_eq!(archives[0].end, Nat::from(NUM_BLOCKS_TO_ARCHIVE - 1));
+
+    let ledger_blocks = get_blocks(&env, ledger_id, 0, ARCHIVE_TRIGGER_THRESHOLD);
+    assert_eq!(
+        ledger_blocks.log_length,
+        Nat::from(ARCHIVE_TRIGGER_THRESHOLD)
+    );
+    assert_eq!(
+        ledger_blocks.blocks.len() as u64,
+        ARCHIVE_TRIGGER_THRESHOLD - NUM_BLOCKS_TO_ARCHIVE
+    );
+    assert_eq!(ledger_blocks.archived_blocks.len(), 1);
+
+    let archive_id = CanisterId::unchecked_from_principal(PrincipalId(archives[0].canister_id));
+    let archived_blocks = get_blocks(&env, archive_id, 0, NUM_BLOCKS_TO_ARCHIVE);
+    assert_eq!(archived_blocks.blocks.len() as u64, NUM_BLOCKS_TO_ARCHIVE);
+
+    // The first non-archived block must link to the last archived one.
+    let last_archived = archived_blocks.blocks.last().unwrap().block.clone();
+    let first_local = ledger_blocks.blocks[0].block.clone();
+    let ICRC3Value::Map(first_local) = first_local else {
+        panic!("blocks must be maps");
+    };
+    assert_eq!(
+        first_local.get("phash"),
+        Some(&ICRC3Value::Blob(ByteBuf::from(
+            last_archived.hash().to_vec()
+        )))
+    );
+}
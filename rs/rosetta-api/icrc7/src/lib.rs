@@ -0,0 +1,441 @@
+#[cfg(test)]
+mod tests;
+
+use candid::Nat;
+use ic_icrc1::blocks::{generic_block_to_encoded_block, try_encoded_block_to_generic_block};
+use ic_icrc1::hash::hash_cbor;
+use ic_ledger_core::block::{BlockType, EncodedBlock, FeeCollector};
+use ic_ledger_core::timestamp::TimeStamp;
+use ic_ledger_hash_of::HashOf;
+use icrc_ledger_types::icrc::generic_value::{Map, Value};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::Memo;
+use icrc_ledger_types::icrc3::blocks::SupportedBlockType;
+use num_traits::ToPrimitive;
+use serde_bytes::ByteBuf;
+
+/// The identifier of a token in the collection.
+///
+/// ICRC-7 token ids are unbounded natural numbers, this ledger only supports
+/// ids that fit into 128 bits.
+pub type TokenId = u128;
+
+pub const MINT_BLOCK_TYPE: &str = "7mint";
+pub const BURN_BLOCK_TYPE: &str = "7burn";
+pub const TRANSFER_BLOCK_TYPE: &str = "7xfer";
+pub const APPROVE_TOKEN_BLOCK_TYPE: &str = "37approve";
+pub const APPROVE_COLLECTION_BLOCK_TYPE: &str = "37approve_coll";
+pub const REVOKE_TOKEN_BLOCK_TYPE: &str = "37revoke";
+pub const REVOKE_COLLECTION_BLOCK_TYPE: &str = "37revoke_coll";
+pub const TRANSFER_FROM_BLOCK_TYPE: &str = "37xfer";
+
+pub const ICRC7_URL: &str = "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-7/ICRC-7.md";
+pub const ICRC37_URL: &str = "https://github.com/dfinity/ICRC/blob/main/ICRCs/ICRC-37/ICRC-37.md";
+
+/// Returns the block types produced by the ledger, as listed by `icrc3_supported_block_types`.
+pub fn supported_block_types() -> Vec<SupportedBlockType> {
+    [
+        (MINT_BLOCK_TYPE, ICRC7_URL),
+        (BURN_BLOCK_TYPE, ICRC7_URL),
+        (TRANSFER_BLOCK_TYPE, ICRC7_URL),
+        (APPROVE_TOKEN_BLOCK_TYPE, ICRC37_URL),
+        (APPROVE_COLLECTION_BLOCK_TYPE, ICRC37_URL),
+        (REVOKE_TOKEN_BLOCK_TYPE, ICRC37_URL),
+        (REVOKE_COLLECTION_BLOCK_TYPE, ICRC37_URL),
+        (TRANSFER_FROM_BLOCK_TYPE, ICRC37_URL),
+    ]
+    .into_iter()
+    .map(|(block_type, url)| SupportedBlockType {
+        block_type: block_type.to_string(),
+        url: url.to_string(),
+    })
+    .collect()
+}
+
+/// Converts a candid token id into a [TokenId].
+/// Returns None if the id does not fit into 128 bits.
+pub fn token_id_from_nat(token_id: &Nat) -> Option<TokenId> {
+    token_id.0.to_u128()
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Operation {
+    Mint {
+        to: Account,
+        token_id: TokenId,
+        /// The metadata of the token, always a [Value::Map].
+        metadata: Value,
+    },
+    Burn {
+        from: Account,
+        token_id: TokenId,
+    },
+    Transfer {
+        from: Account,
+        to: Account,
+        token_id: TokenId,
+    },
+    ApproveToken {
+        from: Account,
+        spender: Account,
+        token_id: TokenId,
+        expires_at: Option<u64>,
+    },
+    ApproveCollection {
+        from: Account,
+        spender: Account,
+        expires_at: Option<u64>,
+    },
+    RevokeToken {
+        from: Account,
+        // If None then the approvals of all spenders have been revoked.
+        spender: Option<Account>,
+        token_id: TokenId,
+    },
+    RevokeCollection {
+        from: Account,
+        // If None then the approvals of all spenders have been revoked.
+        spender: Option<Account>,
+    },
+    TransferFrom {
+        spender: Account,
+        from: Account,
+        to: Account,
+        token_id: TokenId,
+    },
+}
+
+impl Operation {
+    /// Returns the ICRC-3 block type of the operation.
+    pub fn block_type(&self) -> &'static str {
+        match self {
+            Self::Mint { .. } => MINT_BLOCK_TYPE,
+            Self::Burn { .. } => BURN_BLOCK_TYPE,
+            Self::Transfer { .. } => TRANSFER_BLOCK_TYPE,
+            Self::ApproveToken { .. } => APPROVE_TOKEN_BLOCK_TYPE,
+            Self::ApproveCollection { .. } => APPROVE_COLLECTION_BLOCK_TYPE,
+            Self::RevokeToken { .. } => REVOKE_TOKEN_BLOCK_TYPE,
+            Self::RevokeCollection { .. } => REVOKE_COLLECTION_BLOCK_TYPE,
+            Self::TransferFrom { .. } => TRANSFER_FROM_BLOCK_TYPE,
+        }
+    }
+
+    /// Returns the token the operation applies to, if any.
+    pub fn token_id(&self) -> Option<TokenId> {
+        match self {
+            Self::Mint { token_id, .. }
+            | Self::Burn { token_id, .. }
+            | Self::Transfer { token_id, .. }
+            | Self::ApproveToken { token_id, .. }
+            | Self::RevokeToken { token_id, .. }
+            | Self::TransferFrom { token_id, .. } => Some(*token_id),
+            Self::ApproveCollection { .. } | Self::RevokeCollection { .. } => None,
+        }
+    }
+
+    /// Returns all the accounts involved in the operation.
+    pub fn accounts(&self) -> Vec<Account> {
+        match self {
+            Self::Mint { to, .. } => vec![*to],
+            Self::Burn { from, .. } => vec![*from],
+            Self::Transfer { from, to, .. } => vec![*from, *to],
+            Self::ApproveToken { from, spender, .. }
+            | Self::ApproveCollection { from, spender, .. } => vec![*from, *spender],
+            Self::RevokeToken { from, spender, .. } | Self::RevokeCollection { from, spender } => {
+                std::iter::once(*from).chain(*spender).collect()
+            }
+            Self::TransferFrom {
+                spender, from, to, ..
+            } => vec![*spender, *from, *to],
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Transaction {
+    pub operation: Operation,
+    pub memo: Option<Memo>,
+    pub created_at_time: Option<u64>,
+}
+
+impl Transaction {
+    /// Returns the representation-independent hash of the transaction,
+    /// used to deduplicate transactions.
+    pub fn hash(&self) -> [u8; 32] {
+        Value::from(self.clone()).hash()
+    }
+}
+
+impl From<Transaction> for Value {
+    fn from(tx: Transaction) -> Self {
+        fn insert_account(map: &mut Map, key: &str, account: Account) {
+            map.insert(key.to_string(), Value::from(account));
+        }
+
+        let mut map = Map::new();
+        if let Some(token_id) = tx.operation.token_id() {
+            map.insert("tid".to_string(), Value::Nat(Nat::from(token_id)));
+        }
+        match tx.operation {
+            Operation::Mint { to, metadata, .. } => {
+                insert_account(&mut map, "to", to);
+                map.insert("meta".to_string(), metadata);
+            }
+            Operation::Burn { from, .. } => insert_account(&mut map, "from", from),
+            Operation::Transfer { from, to, .. } => {
+                insert_account(&mut map, "from", from);
+                insert_account(&mut map, "to", to);
+            }
+            Operation::ApproveToken {
+                from,
+                spender,
+                expires_at,
+                ..
+            }
+            | Operation::ApproveCollection {
+                from,
+                spender,
+                expires_at,
+            } => {
+                insert_account(&mut map, "from", from);
+                insert_account(&mut map, "spender", spender);
+                if let Some(expires_at) = expires_at {
+                    map.insert("exp".to_string(), Value::Nat64(expires_at));
+                }
+            }
+            Operation::RevokeToken { from, spender, .. }
+            | Operation::RevokeCollection { from, spender } => {
+                insert_account(&mut map, "from", from);
+                if let Some(spender) = spender {
+                    insert_account(&mut map, "spender", spender);
+                }
+            }
+            Operation::TransferFrom {
+                spender, from, to, ..
+            } => {
+                insert_account(&mut map, "spender", spender);
+                insert_account(&mut map, "from", from);
+                insert_account(&mut map, "to", to);
+            }
+        }
+        if let Some(memo) = tx.memo {
+            map.insert("memo".to_string(), Value::Blob(memo.0));
+        }
+        if let Some(created_at_time) = tx.created_at_time {
+            map.insert("ts".to_string(), Value::Nat64(created_at_time));
+        }
+        Value::Map(map)
+    }
+}
+
+fn take_field(map: &mut Map, key: &str) -> Result<Value, String> {
+    map.remove(key)
+        .ok_or_else(|| format!("missing field {}", key))
+}
+
+fn take_account(map: &mut Map, key: &str) -> Result<Account, String> {
+    Account::try_from(take_field(map, key)?).map_err(|e| format!("invalid {}: {}", key, e))
+}
+
+fn take_opt_account(map: &mut Map, key: &str) -> Result<Option<Account>, String> {
+    match map.contains_key(key) {
+        true => take_account(map, key).map(Some),
+        false => Ok(None),
+    }
+}
+
+fn take_opt_u64(map: &mut Map, key: &str) -> Result<Option<u64>, String> {
+    match map.remove(key) {
+        Some(value) => u64::try_from(value)
+            .map(Some)
+            .map_err(|e| format!("invalid {}: {}", key, e)),
+        None => Ok(None),
+    }
+}
+
+fn take_token_id(map: &mut Map) -> Result<TokenId, String> {
+    let token_id =
+        Nat::try_from(take_field(map, "tid")?).map_err(|e| format!("invalid tid: {}", e))?;
+    token_id_from_nat(&token_id).ok_or_else(|| format!("token id {} is too large", token_id))
+}
+
+impl Transaction {
+    fn try_from_value(block_type: &str, value: Value) -> Result<Self, String> {
+        let mut map = value.as_map().map_err(|variant| {
+            format!("expected the transaction to be a Map, found {}", variant)
+        })?;
+        let operation = match block_type {
+            MINT_BLOCK_TYPE => Operation::Mint {
+                to: take_account(&mut map, "to")?,
+                token_id: take_token_id(&mut map)?,
+                metadata: take_field(&mut map, "meta")?,
+            },
+            BURN_BLOCK_TYPE => Operation::Burn {
+                from: take_account(&mut map, "from")?,
+                token_id: take_token_id(&mut map)?,
+            },
+            TRANSFER_BLOCK_TYPE => Operation::Transfer {
+                from: take_account(&mut map, "from")?,
+                to: take_account(&mut map, "to")?,
+                token_id: take_token_id(&mut map)?,
+            },
+            APPROVE_TOKEN_BLOCK_TYPE => Operation::ApproveToken {
+                from: take_account(&mut map, "from")?,
+                spender: take_account(&mut map, "spender")?,
+                token_id: take_token_id(&mut map)?,
+                expires_at: take_opt_u64(&mut map, "exp")?,
+            },
+            APPROVE_COLLECTION_BLOCK_TYPE => Operation::ApproveCollection {
+                from: take_account(&mut map, "from")?,
+                spender: take_account(&mut map, "spender")?,
+                expires_at: take_opt_u64(&mut map, "exp")?,
+            },
+            REVOKE_TOKEN_BLOCK_TYPE => Operation::RevokeToken {
+                from: take_account(&mut map, "from")?,
+                spender: take_opt_account(&mut map, "spender")?,
+                token_id: take_token_id(&mut map)?,
+            },
+            REVOKE_COLLECTION_BLOCK_TYPE => Operation::RevokeCollection {
+                from: take_account(&mut map, "from")?,
+                spender: take_opt_account(&mut map, "spender")?,
+            },
+            TRANSFER_FROM_BLOCK_TYPE => Operation::TransferFrom {
+                spender: take_account(&mut map, "spender")?,
+                from: take_account(&mut map, "from")?,
+                to: take_account(&mut map, "to")?,
+                token_id: take_token_id(&mut map)?,
+            },
+            unknown => return Err(format!("unknown block type {}", unknown)),
+        };
+        let memo = match map.remove("memo") {
+            Some(memo) => Some(Memo(
+                ByteBuf::try_from(memo).map_err(|e| format!("invalid memo: {}", e))?,
+            )),
+            None => None,
+        };
+        let created_at_time = take_opt_u64(&mut map, "ts")?;
+        if let Some(key) = map.keys().next() {
+            return Err(format!("unexpected transaction field {}", key));
+        }
+        Ok(Self {
+            operation,
+            memo,
+            created_at_time,
+        })
+    }
+}
+
+/// A block of the ICRC-7 ledger.
+///
+/// Blocks are stored in the ICRC-3 generic format
+/// `{ phash, ts, btype, tx }` so that they can be served as is by
+/// `icrc3_get_blocks`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Block {
+    pub parent_hash: Option<HashOf<EncodedBlock>>,
+    pub transaction: Transaction,
+    pub timestamp: u64,
+}
+
+impl From<Block> for Value {
+    fn from(block: Block) -> Self {
+        let mut map = Map::new();
+        if let Some(parent_hash) = block.parent_hash {
+            map.insert(
+                "phash".to_string(),
+                Value::blob(parent_hash.as_slice().to_vec()),
+            );
+        }
+        map.insert("ts".to_string(), Value::Nat64(block.timestamp));
+        map.insert(
+            "btype".to_string(),
+            Value::text(block.transaction.operation.block_type()),
+        );
+        map.insert("tx".to_string(), Value::from(block.transaction));
+        Value::Map(map)
+    }
+}
+
+impl TryFrom<Value> for Block {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let mut map = value
+            .as_map()
+            .map_err(|variant| format!("expected the block to be a Map, found {}", variant))?;
+        let parent_hash = match map.remove("phash") {
+            Some(phash) => {
+                let bytes =
+                    ByteBuf::try_from(phash).map_err(|e| format!("invalid phash: {}", e))?;
+                let hash: [u8; 32] = bytes.as_slice().try_into().map_err(|_| {
+                    format!("invalid phash: expected 32 bytes, got {}", bytes.len())
+                })?;
+                Some(HashOf::new(hash))
+            }
+            None => None,
+        };
+        let timestamp =
+            u64::try_from(take_field(&mut map, "ts")?).map_err(|e| format!("invalid ts: {}", e))?;
+        let block_type = String::try_from(take_field(&mut map, "btype")?)
+            .map_err(|e| format!("invalid btype: {}", e))?;
+        let transaction = Transaction::try_from_value(&block_type, take_field(&mut map, "tx")?)?;
+        if let Some(key) = map.keys().next() {
+            return Err(format!("unexpected block field {}", key));
+        }
+        Ok(Self {
+            parent_hash,
+            transaction,
+            timestamp,
+        })
+    }
+}
+
+impl BlockType for Block {
+    type Transaction = Transaction;
+    type AccountId = Account;
+    // Non-fungible tokens do not have fees.
+    type Tokens = ();
+
+    fn encode(self) -> EncodedBlock {
+        generic_block_to_encoded_block(Value::from(self))
+            .expect("bug: failed to encode an ICRC-7 block")
+    }
+
+    fn decode(encoded: EncodedBlock) -> Result<Self, String> {
+        Self::try_from(try_encoded_block_to_generic_block(&encoded)?)
+    }
+
+    fn block_hash(encoded: &EncodedBlock) -> HashOf<EncodedBlock> {
+        hash_cbor(encoded.as_slice())
+            .map(HashOf::new)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "bug: encoded block {} is not hashable cbor: {}",
+                    hex::encode(encoded.as_slice()),
+                    err
+                )
+            })
+    }
+
+    fn parent_hash(&self) -> Option<HashOf<EncodedBlock>> {
+        self.parent_hash
+    }
+
+    fn timestamp(&self) -> TimeStamp {
+        TimeStamp::from_nanos_since_unix_epoch(self.timestamp)
+    }
+
+    fn from_transaction(
+        parent_hash: Option<HashOf<EncodedBlock>>,
+        transaction: Self::Transaction,
+        timestamp: TimeStamp,
+        _effective_fee: Self::Tokens,
+        _fee_collector: Option<FeeCollector<Self::AccountId>>,
+    ) -> Self {
+        Self {
+            parent_hash,
+            transaction,
+            timestamp: timestamp.as_nanos_since_unix_epoch(),
+        }
+    }
+}
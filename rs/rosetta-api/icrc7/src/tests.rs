@@ -0,0 +1,138 @@
+use super::*;
+use candid::Principal;
+
+fn account(id: u64, subaccount: Option<u8>) -> Account {
+    Account {
+        owner: Principal::from_slice(&id.to_be_bytes()),
+        subaccount: subaccount.map(|b| [b; 32]),
+    }
+}
+
+fn operations() -> Vec<Operation> {
+    vec![
+        Operation::Mint {
+            to: account(1, None),
+            token_id: 1,
+            metadata: Value::map([("name", Value::text("Token #1"))]),
+        },
+        Operation::Burn {
+            from: account(1, Some(1)),
+            token_id: u128::MAX,
+        },
+        Operation::Transfer {
+            from: account(1, None),
+            to: account(2, Some(2)),
+            token_id: 3,
+        },
+        Operation::ApproveToken {
+            from: account(1, None),
+            spender: account(2, None),
+            token_id: 4,
+            expires_at: Some(1_000),
+        },
+        Operation::ApproveCollection {
+            from: account(1, None),
+            spender: account(2, None),
+            expires_at: None,
+        },
+        Operation::RevokeToken {
+            from: account(1, None),
+            spender: None,
+            token_id: 5,
+        },
+        Operation::RevokeCollection {
+            from: account(1, None),
+            spender: Some(account(3, Some(3))),
+        },
+        Operation::TransferFrom {
+            spender: account(2, None),
+            from: account(1, None),
+            to: account(3, None),
+            token_id: 6,
+        },
+    ]
+}
+
+#[test]
+fn test_block_encoding_round_trip() {
+    let mut parent_hash = None;
+    for (i, operation) in operations().into_iter().enumerate() {
+        let block = Block {
+            parent_hash,
+            transaction: Transaction {
+                operation,
+                memo: (i % 2 == 0).then(|| Memo::from(vec![i as u8; 4])),
+                created_at_time: (i % 3 == 0).then_some(i as u64),
+            },
+            timestamp: 1_000_000 + i as u64,
+        };
+        let encoded = block.clone().encode();
+        assert_eq!(Block::decode(encoded.clone()), Ok(block.clone()));
+        parent_hash = Some(Block::block_hash(&encoded));
+    }
+}
+
+#[test]
+fn test_block_hash_matches_icrc3_value_hash() {
+    for operation in operations() {
+        let block = Block {
+            parent_hash: Some(HashOf::new([7; 32])),
+            transaction: Transaction {
+                operation,
+                memo: None,
+                created_at_time: Some(1),
+            },
+            timestamp: 2,
+        };
+        let value = Value::from(block.clone());
+        assert_eq!(
+            Block::block_hash(&block.encode()).into_bytes(),
+            value.hash()
+        );
+    }
+}
+
+#[test]
+fn test_block_types() {
+    let block_types: Vec<_> = operations()
+        .iter()
+        .map(|operation| operation.block_type())
+        .collect();
+    assert_eq!(
+        block_types,
+        vec![
+            "7mint",
+            "7burn",
+            "7xfer",
+            "37approve",
+            "37approve_coll",
+            "37revoke",
+            "37revoke_coll",
+            "37xfer",
+        ]
+    );
+}
+
+#[test]
+fn test_decode_rejects_unknown_fields() {
+    let block = Block {
+        parent_hash: None,
+        transaction: Transaction {
+            operation: Operation::Burn {
+                from: account(1, None),
+                token_id: 1,
+            },
+            memo: None,
+            created_at_time: None,
+        },
+        timestamp: 0,
+    };
+
+    let mut map = Value::from(block.clone()).as_map().unwrap();
+    map.insert("btype".to_string(), Value::text("1burn"));
+    assert!(Block::try_from(Value::Map(map)).is_err());
+
+    let mut map = Value::from(block).as_map().unwrap();
+    map.insert("fee".to_string(), Value::Nat64(1));
+    assert!(Block::try_from(Value::Map(map)).is_err());
+}
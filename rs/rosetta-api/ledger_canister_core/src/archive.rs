@@ -40,7 +40,7 @@ pub struct ArchiveOptions {
 /// A scope guard for block archiving.
 /// It sets archiving flag to true on the archive when constructed and disables the flag
 /// when dropped.
-struct ArchivingGuard<Rt: Runtime, Wasm: ArchiveCanisterWasm>(
+pub(crate) struct ArchivingGuard<Rt: Runtime, Wasm: ArchiveCanisterWasm>(
     Arc<RwLock<Option<Archive<Rt, Wasm>>>>,
 );
 
@@ -68,7 +68,9 @@ pub enum ArchivingGuardError {
 }
 
 impl<Rt: Runtime, Wasm: ArchiveCanisterWasm> ArchivingGuard<Rt, Wasm> {
-    fn new(archive: Arc<RwLock<Option<Archive<Rt, Wasm>>>>) -> Result<Self, ArchivingGuardError> {
+    pub(crate) fn new(
+        archive: Arc<RwLock<Option<Archive<Rt, Wasm>>>>,
+    ) -> Result<Self, ArchivingGuardError> {
         let mut archive_guard = archive.write().expect("failed to obtain archive lock");
         match archive_guard.as_mut() {
             Some(archive) => {
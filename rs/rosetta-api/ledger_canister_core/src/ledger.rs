@@ -2,7 +2,8 @@ use crate::{archive::ArchiveCanisterWasm, blockchain::Blockchain, range_utils, r
 use ic_base_types::CanisterId;
 use ic_canister_log::{log, Sink};
 use ic_ledger_core::approvals::{
-    AllowanceTable, AllowancesData, ApproveError, InsufficientAllowance,
+    AllowanceTable, AllowancesData, ApproveError, InsufficientAllowance, PolicyViolation,
+    SpendingPolicyError,
 };
 use ic_ledger_core::tokens::Zero;
 use serde::{Deserialize, Serialize};
@@ -33,6 +34,9 @@ pub enum TxApplyError<Tokens> {
     ExpiredApproval { now: TimeStamp },
     AllowanceChanged { current_allowance: Tokens },
     SelfApproval,
+    ApprovalDoesNotExist,
+    InvalidSpendingPolicy,
+    RecipientNotAllowed,
 }
 
 impl<Tokens> From<BalanceError<Tokens>> for TxApplyError<Tokens> {
@@ -61,6 +65,27 @@ impl<Tokens> From<ApproveError<Tokens>> for TxApplyError<Tokens> {
     }
 }
 
+impl<Tokens> From<SpendingPolicyError> for TxApplyError<Tokens> {
+    fn from(e: SpendingPolicyError) -> Self {
+        match e {
+            SpendingPolicyError::ApprovalDoesNotExist => Self::ApprovalDoesNotExist,
+            SpendingPolicyError::InvalidPeriod => Self::InvalidSpendingPolicy,
+        }
+    }
+}
+
+impl<Tokens> From<PolicyViolation<Tokens>> for TxApplyError<Tokens> {
+    fn from(e: PolicyViolation<Tokens>) -> Self {
+        match e {
+            PolicyViolation::RecipientNotAllowed => Self::RecipientNotAllowed,
+            // The spender can only use what is left in the current period.
+            PolicyViolation::PeriodCapExceeded { remaining } => Self::InsufficientAllowance {
+                allowance: remaining,
+            },
+        }
+    }
+}
+
 pub trait LedgerContext {
     type AccountId: std::hash::Hash + Ord + Eq + Clone;
     type BalancesStore: BalancesStore<AccountId = Self::AccountId, Tokens = Self::Tokens> + Default;
@@ -190,6 +215,21 @@ pub trait LedgerData: LedgerContext {
     fn fee_collector_mut(&mut self) -> Option<&mut FeeCollector<Self::AccountId>>;
 }
 
+/// Gives access to the blockchain of a ledger that does not implement [LedgerData], e.g., a ledger
+/// of non-fungible tokens, so that it can reuse the archiving machinery.
+pub trait BlockchainAccess {
+    type Runtime: Runtime;
+    type ArchiveWasm: ArchiveCanisterWasm;
+
+    /// Executes a function on a blockchain reference.
+    fn with_blockchain<R>(f: impl FnOnce(&Blockchain<Self::Runtime, Self::ArchiveWasm>) -> R) -> R;
+
+    /// Executes a function on a mutable blockchain reference.
+    fn with_blockchain_mut<R>(
+        f: impl FnOnce(&mut Blockchain<Self::Runtime, Self::ArchiveWasm>) -> R,
+    ) -> R;
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub enum TransferError<Tokens> {
     BadFee { expected_fee: Tokens },
@@ -203,6 +243,9 @@ pub enum TransferError<Tokens> {
     TxDuplicate { duplicate_of: BlockIndex },
     AllowanceChanged { current_allowance: Tokens },
     SelfApproval,
+    ApprovalDoesNotExist,
+    InvalidSpendingPolicy,
+    RecipientNotAllowed,
 }
 
 const APPROVE_PRUNE_LIMIT: usize = 100;
@@ -267,6 +310,9 @@ where
                 TransferError::AllowanceChanged { current_allowance }
             }
             TxApplyError::SelfApproval => TransferError::SelfApproval,
+            TxApplyError::ApprovalDoesNotExist => TransferError::ApprovalDoesNotExist,
+            TxApplyError::InvalidSpendingPolicy => TransferError::InvalidSpendingPolicy,
+            TxApplyError::RecipientNotAllowed => TransferError::RecipientNotAllowed,
         })?;
 
     let fee_collector = ledger.fee_collector().cloned();
@@ -544,6 +590,59 @@ pub async fn archive_blocks<LA: LedgerAccess>(sink: impl Sink + Clone, max_messa
     remove_archived_blocks::<LA>(archiving_guard, num_blocks, &sink, result)
 }
 
+/// Same as [archive_blocks], but for ledgers that only expose their blockchain.
+pub async fn archive_blockchain_blocks<BA: BlockchainAccess>(
+    sink: impl Sink + Clone,
+    max_message_size: u64,
+) {
+    use crate::archive::{send_blocks_to_archive, ArchivingGuard};
+
+    let archive_arc = BA::with_blockchain(|blockchain| blockchain.archive.clone());
+
+    // NOTE: this guard will prevent another logical thread to start the archiving process.
+    let _archiving_guard = match ArchivingGuard::new(archive_arc.clone()) {
+        Ok(guard) => guard,
+        Err(ArchivingGuardError::NoArchive) | Err(ArchivingGuardError::AlreadyArchiving) => {
+            return;
+        }
+    };
+
+    let blocks_to_archive = BA::with_blockchain(|blockchain| {
+        let archive_guard = blockchain.archive.read().unwrap();
+        let archive = archive_guard.as_ref().unwrap();
+        blockchain
+            .get_blocks_for_archiving(archive.trigger_threshold, archive.num_blocks_to_archive)
+    });
+    if blocks_to_archive.is_empty() {
+        return;
+    }
+
+    let num_blocks = blocks_to_archive.len();
+    log!(sink, "[ledger] archiving {} blocks", num_blocks);
+
+    let result = send_blocks_to_archive(
+        sink.clone(),
+        archive_arc,
+        blocks_to_archive,
+        max_message_size,
+    )
+    .await;
+
+    BA::with_blockchain_mut(|blockchain| match result {
+        Ok(num_sent_blocks) => blockchain.remove_archived_blocks(num_sent_blocks),
+        Err((num_sent_blocks, FailedToArchiveBlocks(err))) => {
+            blockchain.remove_archived_blocks(num_sent_blocks);
+            log!(
+                sink,
+                "[ledger] archived only {} out of {} blocks; error: {}",
+                num_sent_blocks,
+                num_blocks,
+                err
+            );
+        }
+    });
+}
+
 pub fn blocks_to_archive<LA: LedgerAccess>(
     sink: &impl Sink,
 ) -> Result<(LedgerArchivingGuard<LA>, VecDeque<EncodedBlock>), ArchivingGuardError> {
@@ -602,12 +701,21 @@ pub struct BlockLocations {
 
 /// Returns the locations of the specified block range.
 pub fn block_locations<L: LedgerData>(ledger: &L, start: u64, length: usize) -> BlockLocations {
+    blockchain_block_locations(ledger.blockchain(), start, length)
+}
+
+/// Returns the locations of the specified block range of a blockchain.
+pub fn blockchain_block_locations<Rt: Runtime, Wasm: ArchiveCanisterWasm>(
+    blockchain: &Blockchain<Rt, Wasm>,
+    start: u64,
+    length: usize,
+) -> BlockLocations {
     let requested_range = range_utils::make_range(start, length);
-    let local_range = ledger.blockchain().local_block_range();
+    let local_range = blockchain.local_block_range();
     let local_blocks = range_utils::intersect(&requested_range, &local_range)
         .unwrap_or_else(|_| range_utils::make_range(local_range.start, 0));
 
-    let archive = ledger.blockchain().archive.read().unwrap();
+    let archive = blockchain.archive.read().unwrap();
 
     let archived_blocks: Vec<_> = archive
         .iter()
@@ -1,5 +1,5 @@
 use crate::timestamp::TimeStamp;
-use crate::tokens::{CheckedSub, TokensType, Zero};
+use crate::tokens::{CheckedAdd, CheckedSub, TokensType, Zero};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -16,9 +16,27 @@ pub enum ApproveError<Tokens> {
     SelfApproval,
 }
 
+/// The maximum number of allowances removed by a single [AllowanceTable::revoke_all] call.
+/// Accounts with more allowances need several calls to revoke all of them.
+pub const MAX_REVOKED_APPROVALS: usize = 1_000;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum SpendingPolicyError {
+    ApprovalDoesNotExist,
+    InvalidPeriod,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum PolicyViolation<Tokens> {
+    /// The spender is not allowed to send tokens to the recipient.
+    RecipientNotAllowed,
+    /// The amount exceeds what the spender can still use in the current period.
+    PeriodCapExceeded { remaining: Tokens },
+}
+
 // The implementations of this trait should store the allowance data
-// for (account, spender) pairs and the expirations and arrivals
-// of the allowances. The functions of the trait are meant to be simple
+// for (account, spender) pairs, the spending policies attached to them
+// and the expirations and arrivals of the allowances. The functions of the trait are meant to be simple
 // `insert` and `remove` type functions that can be implemented with
 // regular BTreeMaps or using the stable structures.
 pub trait AllowancesData {
@@ -38,6 +56,24 @@ pub trait AllowancesData {
 
     fn remove_allowance(&mut self, account_spender: &(Self::AccountId, Self::AccountId));
 
+    fn get_policy(
+        &self,
+        account_spender: &(Self::AccountId, Self::AccountId),
+    ) -> Option<SpendingPolicy<Self::AccountId, Self::Tokens>>;
+
+    fn set_policy(
+        &mut self,
+        account_spender: (Self::AccountId, Self::AccountId),
+        policy: SpendingPolicy<Self::AccountId, Self::Tokens>,
+    );
+
+    fn remove_policy(&mut self, account_spender: &(Self::AccountId, Self::AccountId));
+
+    /// Returns the spenders of at most `limit` allowances of the account: first the
+    /// spenders greater than the account in ascending order, then the smaller ones in
+    /// descending order.
+    fn spenders(&self, account: &Self::AccountId, limit: usize) -> Vec<Self::AccountId>;
+
     fn insert_expiry(
         &mut self,
         timestamp: TimeStamp,
@@ -72,6 +108,8 @@ pub trait AllowancesData {
 
     fn len_allowances(&self) -> usize;
 
+    fn len_policies(&self) -> usize;
+
     fn len_expirations(&self) -> usize;
 
     fn len_arrivals(&self) -> usize;
@@ -86,6 +124,8 @@ where
     expiration_queue: BTreeSet<(TimeStamp, (AccountId, AccountId))>,
     #[serde(default = "Default::default")]
     arrival_queue: BTreeSet<(TimeStamp, (AccountId, AccountId))>,
+    #[serde(default = "Default::default")]
+    policies: BTreeMap<(AccountId, AccountId), SpendingPolicy<AccountId, Tokens>>,
 }
 impl<AccountId, Tokens> Default for HeapAllowancesData<AccountId, Tokens>
 where
@@ -96,6 +136,7 @@ where
             allowances: BTreeMap::new(),
             expiration_queue: BTreeSet::new(),
             arrival_queue: BTreeSet::new(),
+            policies: BTreeMap::new(),
         }
     }
 }
@@ -127,6 +168,45 @@ where
         self.allowances.remove(account_spender);
     }
 
+    fn get_policy(
+        &self,
+        account_spender: &(Self::AccountId, Self::AccountId),
+    ) -> Option<SpendingPolicy<Self::AccountId, Self::Tokens>> {
+        self.policies.get(account_spender).cloned()
+    }
+
+    fn set_policy(
+        &mut self,
+        account_spender: (Self::AccountId, Self::AccountId),
+        policy: SpendingPolicy<Self::AccountId, Self::Tokens>,
+    ) {
+        self.policies.insert(account_spender, policy);
+    }
+
+    fn remove_policy(&mut self, account_spender: &(Self::AccountId, Self::AccountId)) {
+        self.policies.remove(account_spender);
+    }
+
+    fn spenders(&self, account: &Self::AccountId, limit: usize) -> Vec<Self::AccountId> {
+        // An account cannot approve itself, so (account, account) is never a key
+        // and splits the allowances of the account into two contiguous ranges.
+        let pivot = (account.clone(), account.clone());
+        let after = self
+            .allowances
+            .range(pivot.clone()..)
+            .take_while(|((a, _), _)| a == account);
+        let before = self
+            .allowances
+            .range(..pivot)
+            .rev()
+            .take_while(|((a, _), _)| a == account);
+        after
+            .chain(before)
+            .take(limit)
+            .map(|((_, spender), _)| spender.clone())
+            .collect()
+    }
+
     fn insert_expiry(
         &mut self,
         timestamp: TimeStamp,
@@ -182,6 +262,10 @@ where
         self.allowances.len()
     }
 
+    fn len_policies(&self) -> usize {
+        self.policies.len()
+    }
+
     fn len_expirations(&self) -> usize {
         self.expiration_queue.len()
     }
@@ -208,6 +292,61 @@ impl<Tokens: Zero> Default for Allowance<Tokens> {
     }
 }
 
+/// Limits the amount a spender can use within each period of fixed length.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct PeriodCap<Tokens> {
+    pub amount: Tokens,
+    pub period_nanos: u64,
+}
+
+/// Additional restrictions on how a spender can use an allowance.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(bound(deserialize = "AccountId: Ord + Deserialize<'de>, Tokens: Deserialize<'de>"))]
+pub struct SpendingPolicy<AccountId, Tokens> {
+    pub period_cap: Option<PeriodCap<Tokens>>,
+    /// If set, the spender can only transfer tokens to these accounts.
+    pub allowed_recipients: Option<BTreeSet<AccountId>>,
+    /// The start of the current period of the cap.
+    pub period_start: TimeStamp,
+    /// The amount used by the spender since `period_start`.
+    pub spent_in_period: Tokens,
+}
+
+impl<AccountId: Ord + Clone, Tokens: TokensType> SpendingPolicy<AccountId, Tokens> {
+    /// Moves the policy to the period containing `now`, resetting the
+    /// spent amount if a new period started.
+    fn roll_period(&mut self, now: TimeStamp) {
+        if let Some(cap) = &self.period_cap {
+            let start = self.period_start.as_nanos_since_unix_epoch();
+            let now = now.as_nanos_since_unix_epoch();
+            if now >= start.saturating_add(cap.period_nanos) {
+                let elapsed_periods = (now - start) / cap.period_nanos;
+                self.period_start = TimeStamp::from_nanos_since_unix_epoch(
+                    start + elapsed_periods * cap.period_nanos,
+                );
+                self.spent_in_period = Tokens::zero();
+            }
+        }
+    }
+
+    /// Returns the policy as of `now`, i.e., tracking the period containing `now`.
+    pub fn at(&self, now: TimeStamp) -> Self {
+        let mut policy = self.clone();
+        policy.roll_period(now);
+        policy
+    }
+
+    /// Returns the amount the spender can still use in the period containing `now`.
+    pub fn remaining_in_period(&self, now: TimeStamp) -> Option<Tokens> {
+        let policy = self.at(now);
+        policy.period_cap.map(|cap| {
+            cap.amount
+                .checked_sub(&policy.spent_in_period)
+                .unwrap_or_else(Tokens::zero)
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(transparent)]
 pub struct AllowanceTable<AD: AllowancesData> {
@@ -250,6 +389,12 @@ where
             self.allowances_data.len_arrivals(),
             self.allowances_data.len_allowances()
         );
+        debug_assert!(
+            self.allowances_data.len_policies() <= self.allowances_data.len_allowances(),
+            "policies length ({}) larger than allowances length ({})",
+            self.allowances_data.len_policies(),
+            self.allowances_data.len_allowances()
+        );
     }
 
     fn with_postconditions_check<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
@@ -346,6 +491,7 @@ where
                             table.allowances_data.remove_expiry(expires_at, key.clone());
                         }
                         table.allowances_data.remove_allowance(&key);
+                        table.allowances_data.remove_policy(&key);
                         return Ok(amount);
                     }
                     table.allowances_data.insert_arrival(now, key.clone());
@@ -414,7 +560,18 @@ where
                                 .allowances_data
                                 .remove_arrival(old_allowance.arrived_at, key.clone());
                             table.allowances_data.remove_allowance(&key);
+                            table.allowances_data.remove_policy(&key);
                         } else {
+                            if let Some(mut policy) = table.allowances_data.get_policy(&key) {
+                                if policy.period_cap.is_some() {
+                                    policy.roll_period(now);
+                                    policy.spent_in_period = policy
+                                        .spent_in_period
+                                        .checked_add(&amount)
+                                        .expect("Overflow when recording policy spending");
+                                    table.allowances_data.set_policy(key.clone(), policy);
+                                }
+                            }
                             table.allowances_data.set_allowance(key, new_allowance);
                         }
                         Ok(rest)
@@ -424,6 +581,107 @@ where
         })
     }
 
+    /// Returns the spending policy attached to the spender's allowance for the account.
+    pub fn spending_policy(
+        &self,
+        account: &AD::AccountId,
+        spender: &AD::AccountId,
+    ) -> Option<SpendingPolicy<AD::AccountId, AD::Tokens>> {
+        self.allowances_data
+            .get_policy(&(account.clone(), spender.clone()))
+    }
+
+    /// Attaches a spending policy to an existing allowance, replacing the
+    /// previous policy. Passing neither a cap nor recipients removes the policy.
+    pub fn set_spending_policy(
+        &mut self,
+        account: &AD::AccountId,
+        spender: &AD::AccountId,
+        period_cap: Option<PeriodCap<AD::Tokens>>,
+        allowed_recipients: Option<BTreeSet<AD::AccountId>>,
+        now: TimeStamp,
+    ) -> Result<(), SpendingPolicyError> {
+        self.with_postconditions_check(|table| {
+            if table.allowance(account, spender, now).amount.is_zero() {
+                return Err(SpendingPolicyError::ApprovalDoesNotExist);
+            }
+            if let Some(cap) = &period_cap {
+                if cap.period_nanos == 0 {
+                    return Err(SpendingPolicyError::InvalidPeriod);
+                }
+            }
+            let key = (account.clone(), spender.clone());
+            if period_cap.is_none() && allowed_recipients.is_none() {
+                table.allowances_data.remove_policy(&key);
+                return Ok(());
+            }
+            table.allowances_data.set_policy(
+                key,
+                SpendingPolicy {
+                    period_cap,
+                    allowed_recipients,
+                    period_start: now,
+                    spent_in_period: AD::Tokens::zero(),
+                },
+            );
+            Ok(())
+        })
+    }
+
+    /// Checks that the spender can use `amount` of the account's allowance
+    /// to pay `to` according to the spending policy of the allowance.
+    /// `to` is `None` for burns, which are not allowed if the policy
+    /// restricts the recipients.
+    pub fn check_spending_policy(
+        &self,
+        account: &AD::AccountId,
+        spender: &AD::AccountId,
+        to: Option<&AD::AccountId>,
+        amount: &AD::Tokens,
+        now: TimeStamp,
+    ) -> Result<(), PolicyViolation<AD::Tokens>> {
+        let policy = match self.spending_policy(account, spender) {
+            Some(policy) => policy,
+            None => return Ok(()),
+        };
+        if let Some(allowed_recipients) = &policy.allowed_recipients {
+            if !to.is_some_and(|to| allowed_recipients.contains(to)) {
+                return Err(PolicyViolation::RecipientNotAllowed);
+            }
+        }
+        if let Some(remaining) = policy.remaining_in_period(now) {
+            if remaining < *amount {
+                return Err(PolicyViolation::PeriodCapExceeded { remaining });
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes at most [MAX_REVOKED_APPROVALS] allowances of the account and their
+    /// spending policies, in the order of [AllowancesData::spenders], so that the work
+    /// of a single call is bounded. Returns the number of removed allowances.
+    pub fn revoke_all(&mut self, account: &AD::AccountId) -> usize {
+        self.with_postconditions_check(|table| {
+            let spenders = table
+                .allowances_data
+                .spenders(account, MAX_REVOKED_APPROVALS);
+            for spender in &spenders {
+                let key = (account.clone(), spender.clone());
+                if let Some(allowance) = table.allowances_data.get_allowance(&key) {
+                    if let Some(expires_at) = allowance.expires_at {
+                        table.allowances_data.remove_expiry(expires_at, key.clone());
+                    }
+                    table
+                        .allowances_data
+                        .remove_arrival(allowance.arrived_at, key.clone());
+                    table.allowances_data.remove_allowance(&key);
+                    table.allowances_data.remove_policy(&key);
+                }
+            }
+            spenders.len()
+        })
+    }
+
     /// Returns a vector of pairs (account, spender) of size min(n, approvals_size)
     /// that represent approvals selected for trimming.
     pub fn select_approvals_to_trim(&self, n: usize) -> Vec<(AD::AccountId, AD::AccountId)> {
@@ -453,6 +711,7 @@ where
                                 .allowances_data
                                 .remove_arrival(allowance.arrived_at, key.clone());
                             table.allowances_data.remove_allowance(&key);
+                            table.allowances_data.remove_policy(&key);
                             pruned += 1;
                         }
                     }
@@ -471,6 +730,18 @@ where
     }
 }
 
+impl<AccountId, Tokens> AllowanceTable<HeapAllowancesData<AccountId, Tokens>>
+where
+    AccountId: Ord + Clone,
+    Tokens: TokensType,
+{
+    /// Iterates over all the allowances, including the expired ones that
+    /// haven't been pruned yet, ordered by (account, spender).
+    pub fn iter(&self) -> impl Iterator<Item = (&(AccountId, AccountId), &Allowance<Tokens>)> {
+        self.allowances_data.allowances.iter()
+    }
+}
+
 fn remote_future() -> TimeStamp {
     TimeStamp::from_nanos_since_unix_epoch(u64::MAX)
 }
@@ -619,3 +619,238 @@ fn expected_allowance_if_zero_no_approval() {
         }
     );
 }
+
+#[test]
+fn spending_policy_requires_approval() {
+    let mut table = TestAllowanceTable::default();
+
+    assert_eq!(
+        table.set_spending_policy(&Account(1), &Account(2), None, None, ts(1)),
+        Err(SpendingPolicyError::ApprovalDoesNotExist)
+    );
+
+    table
+        .approve(
+            &Account(1),
+            &Account(2),
+            tokens(5),
+            Some(ts(10)),
+            ts(1),
+            None,
+        )
+        .unwrap();
+    assert_eq!(
+        table.set_spending_policy(
+            &Account(1),
+            &Account(2),
+            Some(PeriodCap {
+                amount: tokens(1),
+                period_nanos: 0,
+            }),
+            None,
+            ts(1),
+        ),
+        Err(SpendingPolicyError::InvalidPeriod)
+    );
+    assert_eq!(
+        table.set_spending_policy(&Account(1), &Account(2), None, None, ts(10)),
+        Err(SpendingPolicyError::ApprovalDoesNotExist)
+    );
+}
+
+#[test]
+fn spending_policy_period_cap() {
+    let mut table = TestAllowanceTable::default();
+
+    table
+        .approve(&Account(1), &Account(2), tokens(100), None, ts(0), None)
+        .unwrap();
+    table
+        .set_spending_policy(
+            &Account(1),
+            &Account(2),
+            Some(PeriodCap {
+                amount: tokens(10),
+                period_nanos: 100,
+            }),
+            None,
+            ts(0),
+        )
+        .unwrap();
+
+    assert_eq!(
+        table.check_spending_policy(&Account(1), &Account(2), None, &tokens(11), ts(1)),
+        Err(PolicyViolation::PeriodCapExceeded {
+            remaining: tokens(10)
+        })
+    );
+    table
+        .use_allowance(&Account(1), &Account(2), tokens(7), ts(1))
+        .unwrap();
+    assert_eq!(
+        table.check_spending_policy(&Account(1), &Account(2), None, &tokens(4), ts(99)),
+        Err(PolicyViolation::PeriodCapExceeded {
+            remaining: tokens(3)
+        })
+    );
+    assert_eq!(
+        table.check_spending_policy(&Account(1), &Account(2), None, &tokens(3), ts(99)),
+        Ok(())
+    );
+
+    // A new period resets the spent amount.
+    assert_eq!(
+        table.check_spending_policy(&Account(1), &Account(2), None, &tokens(10), ts(250)),
+        Ok(())
+    );
+    table
+        .use_allowance(&Account(1), &Account(2), tokens(10), ts(250))
+        .unwrap();
+    let policy = table.spending_policy(&Account(1), &Account(2)).unwrap();
+    assert_eq!(policy.period_start, ts(200));
+    assert_eq!(policy.spent_in_period, tokens(10));
+    assert_eq!(policy.remaining_in_period(ts(299)), Some(tokens(0)));
+    assert_eq!(policy.remaining_in_period(ts(300)), Some(tokens(10)));
+}
+
+#[test]
+fn spending_policy_allowed_recipients() {
+    let mut table = TestAllowanceTable::default();
+
+    table
+        .approve(&Account(1), &Account(2), tokens(100), None, ts(0), None)
+        .unwrap();
+    table
+        .set_spending_policy(
+            &Account(1),
+            &Account(2),
+            None,
+            Some(BTreeSet::from([Account(3)])),
+            ts(0),
+        )
+        .unwrap();
+
+    assert_eq!(
+        table.check_spending_policy(
+            &Account(1),
+            &Account(2),
+            Some(&Account(3)),
+            &tokens(50),
+            ts(1)
+        ),
+        Ok(())
+    );
+    assert_eq!(
+        table.check_spending_policy(
+            &Account(1),
+            &Account(2),
+            Some(&Account(4)),
+            &tokens(50),
+            ts(1)
+        ),
+        Err(PolicyViolation::RecipientNotAllowed)
+    );
+    assert_eq!(
+        table.check_spending_policy(&Account(1), &Account(2), None, &tokens(50), ts(1)),
+        Err(PolicyViolation::RecipientNotAllowed)
+    );
+    // Other spenders are not affected.
+    table
+        .approve(&Account(1), &Account(5), tokens(100), None, ts(0), None)
+        .unwrap();
+    assert_eq!(
+        table.check_spending_policy(
+            &Account(1),
+            &Account(5),
+            Some(&Account(4)),
+            &tokens(50),
+            ts(1)
+        ),
+        Ok(())
+    );
+
+    // The policy is removed together with the allowance.
+    table
+        .approve(&Account(1), &Account(2), tokens(0), None, ts(2), None)
+        .unwrap();
+    assert!(table.spending_policy(&Account(1), &Account(2)).is_none());
+}
+
+#[test]
+fn revoke_all_removes_only_account_allowances() {
+    let mut table = TestAllowanceTable::default();
+
+    for spender in [0, 2, 3, 5] {
+        table
+            .approve(
+                &Account(1),
+                &Account(spender),
+                tokens(10),
+                Some(ts(100)),
+                ts(0),
+                None,
+            )
+            .unwrap();
+    }
+    table
+        .approve(&Account(0), &Account(1), tokens(10), None, ts(0), None)
+        .unwrap();
+    table
+        .approve(&Account(2), &Account(1), tokens(10), None, ts(0), None)
+        .unwrap();
+    table
+        .set_spending_policy(
+            &Account(1),
+            &Account(3),
+            None,
+            Some(BTreeSet::from([Account(4)])),
+            ts(0),
+        )
+        .unwrap();
+
+    assert_eq!(table.revoke_all(&Account(1)), 4);
+    assert_eq!(table.len(), 2);
+    for spender in [0, 2, 3, 5] {
+        assert_eq!(
+            table.allowance(&Account(1), &Account(spender), ts(1)),
+            Allowance::default()
+        );
+    }
+    assert!(table.spending_policy(&Account(1), &Account(3)).is_none());
+    assert_eq!(table.allowances_data.len_expirations(), 0);
+    assert_eq!(table.revoke_all(&Account(1)), 0);
+}
+
+#[test]
+fn revoke_all_removes_at_most_max_revoked_approvals_per_call() {
+    let mut table = TestAllowanceTable::default();
+    let num_approvals = MAX_REVOKED_APPROVALS as u64 + 5;
+
+    for spender in 0..=num_approvals {
+        if spender != 10 {
+            table
+                .approve(
+                    &Account(10),
+                    &Account(spender),
+                    tokens(10),
+                    None,
+                    ts(0),
+                    None,
+                )
+                .unwrap();
+        }
+    }
+
+    assert_eq!(table.revoke_all(&Account(10)), MAX_REVOKED_APPROVALS);
+    assert_eq!(table.len(), 5);
+    // The spenders greater than the account are revoked first, then the smaller
+    // ones in descending order.
+    for spender in (5..10).chain(11..=num_approvals) {
+        assert_eq!(
+            table.allowance(&Account(10), &Account(spender), ts(1)),
+            Allowance::default()
+        );
+    }
+    assert_eq!(table.revoke_all(&Account(10)), 5);
+    assert_eq!(table.len(), 0);
+}
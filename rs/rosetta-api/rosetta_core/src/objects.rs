@@ -701,6 +701,32 @@ pub struct BlockTransaction {
     pub transaction: Transaction,
 }
 
+/// BlockEvent represents the addition or removal of a BlockIdentifier from
+/// storage. Streaming BlockEvents allows lightweight clients to update their
+/// own state without needing to implement their own syncing logic.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct BlockEvent {
+    /// sequence is the unique identifier of a BlockEvent within the context of a NetworkIdentifier.
+    pub sequence: i64,
+
+    /// The block_identifier uniquely identifies a block in a particular network.
+    pub block_identifier: BlockIdentifier,
+
+    #[serde(rename = "type")]
+    pub type_: BlockEventType,
+}
+
+/// BlockEventType determines if a BlockEvent represents the addition or removal of a block.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub enum BlockEventType {
+    /// A block was added to the canonical chain.
+    #[serde(rename = "block_added")]
+    BlockAdded,
+    /// A block was removed from the canonical chain in a reorg.
+    #[serde(rename = "block_removed")]
+    BlockRemoved,
+}
+
 /// Operator is used by query-related endpoints to determine how to apply
 /// conditions. If this field is not populated, the default and value will be
 /// used.
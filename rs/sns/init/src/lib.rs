@@ -641,6 +641,7 @@ impl SnsInitPayload {
         Some(IndexArg::Init(InitArg {
             ledger_id: Principal::from(sns_canister_ids.ledger),
             retrieve_blocks_from_ledger_interval_seconds: None,
+            bootstrap_from_ledger_snapshot: None,
         }))
     }
 
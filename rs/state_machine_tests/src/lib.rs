@@ -160,7 +160,7 @@ use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::Serialize;
 pub use slog::Level;
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     convert::TryFrom,
     fmt,
     io::{self, stderr},
@@ -579,6 +579,31 @@ impl PocketIngressPool {
     }
 }
 
+/// Configuration of the XNet link from a remote subnet to a `StateMachine`
+/// built using `StateMachineBuilder::build_with_subnets`.
+/// The default configuration delivers messages instantly and without
+/// any limits other than those imposed by the XNet payload builder.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct XNetLinkConfig {
+    /// The number of rounds (of the receiving subnet) that must pass after a message
+    /// has been observed in the stream from the remote subnet before it can be
+    /// included into a block of the receiving subnet.
+    pub latency_rounds: u64,
+    /// The maximum size (in bytes) of a stream slice from the remote subnet
+    /// included into a single block (i.e., the bandwidth of the link per round).
+    pub max_slice_bytes: Option<usize>,
+    /// The maximum number of messages in a stream slice from the remote subnet
+    /// included into a single block.
+    pub max_slice_messages: Option<usize>,
+}
+
+fn min_limit(limit: Option<usize>, other_limit: Option<usize>) -> Option<usize> {
+    match (limit, other_limit) {
+        (Some(limit), Some(other_limit)) => Some(limit.min(other_limit)),
+        (limit, other_limit) => limit.or(other_limit),
+    }
+}
+
 /// Struct mocking the pool of XNet messages required for
 /// instantiating `XNetPayloadBuilderImpl` in `StateMachine`.
 struct PocketXNetSlicePoolImpl {
@@ -587,18 +612,53 @@ struct PocketXNetSlicePoolImpl {
     subnets: Arc<RwLock<BTreeMap<SubnetId, Arc<StateMachine>>>>,
     /// Subnet ID of the `StateMachine` containing the pool.
     own_subnet_id: SubnetId,
+    /// Configuration of the XNet links from remote subnets
+    /// (shared with the `StateMachine` containing the pool).
+    xnet_link_configs: Arc<RwLock<BTreeMap<SubnetId, XNetLinkConfig>>>,
+    /// Ends of the streams from remote subnets (with non-zero link latency)
+    /// and the heights of this subnet at which they were first observed.
+    observed_stream_ends: Mutex<BTreeMap<SubnetId, VecDeque<(Height, StreamIndex)>>>,
 }
 
 impl PocketXNetSlicePoolImpl {
     fn new(
         subnets: Arc<RwLock<BTreeMap<SubnetId, Arc<StateMachine>>>>,
         own_subnet_id: SubnetId,
+        xnet_link_configs: Arc<RwLock<BTreeMap<SubnetId, XNetLinkConfig>>>,
     ) -> Self {
         Self {
             subnets,
             own_subnet_id,
+            xnet_link_configs,
+            observed_stream_ends: Mutex::new(BTreeMap::new()),
         }
     }
+
+    /// Records the end of the stream from a remote subnet observed at the given height
+    /// of this subnet and returns the end of the stream observed at least `latency_rounds`
+    /// rounds ago, i.e., the end of the messages that can be delivered at the given height.
+    fn delivered_stream_end(
+        &self,
+        remote_subnet_id: SubnetId,
+        height: Height,
+        stream_end: StreamIndex,
+        latency_rounds: u64,
+    ) -> Option<StreamIndex> {
+        let mut observed_stream_ends = self.observed_stream_ends.lock().unwrap();
+        let observed = observed_stream_ends.entry(remote_subnet_id).or_default();
+        if observed.back().map(|(_, end)| *end) != Some(stream_end) {
+            observed.push_back((height, stream_end));
+        }
+        let cutoff = Height::new(height.get().saturating_sub(latency_rounds));
+        // Only the latest stream end observed at or before the cutoff is relevant.
+        while observed.len() > 1 && observed[1].0 <= cutoff {
+            observed.pop_front();
+        }
+        observed
+            .front()
+            .filter(|(observed_at, _)| *observed_at <= cutoff)
+            .map(|(_, end)| *end)
+    }
 }
 
 impl XNetSlicePool for PocketXNetSlicePoolImpl {
@@ -614,6 +674,37 @@ impl XNetSlicePool for PocketXNetSlicePoolImpl {
         let subnets = self.subnets.read().unwrap();
         let sm = subnets.get(&subnet_id).unwrap();
         let msg_begin = begin.map(|idx| idx.message_index);
+        let link_config = self
+            .xnet_link_configs
+            .read()
+            .unwrap()
+            .get(&subnet_id)
+            .cloned()
+            .unwrap_or_default();
+        let byte_limit = min_limit(byte_limit, link_config.max_slice_bytes);
+        let mut msg_limit = min_limit(msg_limit, link_config.max_slice_messages);
+        if link_config.latency_rounds > 0 {
+            let height = subnets
+                .get(&self.own_subnet_id)
+                .unwrap()
+                .state_manager
+                .latest_state_height();
+            let remote_state = sm.get_latest_state();
+            let (stream_begin, stream_end) = remote_state
+                .get_stream(&self.own_subnet_id)
+                .map(|stream| (stream.messages_begin(), stream.messages_end()))
+                .unwrap_or_default();
+            let delivered_messages = self
+                .delivered_stream_end(subnet_id, height, stream_end, link_config.latency_rounds)
+                .map(|delivered_end| {
+                    delivered_end
+                        .get()
+                        .saturating_sub(msg_begin.unwrap_or(stream_begin).get())
+                })
+                .unwrap_or(0);
+            // Signals are still delivered even if no messages can be delivered yet.
+            msg_limit = min_limit(msg_limit, Some(delivered_messages as usize));
+        }
         // We set `witness_begin` equal to `msg_begin` since all states are certified.
         let certified_stream = sm.generate_certified_stream_slice(
             self.own_subnet_id,
@@ -808,6 +899,7 @@ pub struct StateMachine {
     pub ingress_filter:
         tower::buffer::Buffer<IngressFilterService, (ProvisionalWhitelist, SignedIngressContent)>,
     payload_builder: Arc<RwLock<Option<PayloadBuilderImpl>>>,
+    xnet_link_configs: Arc<RwLock<BTreeMap<SubnetId, XNetLinkConfig>>>,
     message_routing: SyncMessageRouting,
     pub metrics_registry: MetricsRegistry,
     ingress_history_reader: Box<dyn IngressHistoryReader>,
@@ -890,6 +982,7 @@ pub struct StateMachineBuilder {
     nns_subnet_id: Option<SubnetId>,
     subnet_id: Option<SubnetId>,
     routing_table: RoutingTable,
+    xnet_link_configs: BTreeMap<SubnetId, XNetLinkConfig>,
     enable_canister_snapshots: bool,
     idkg_keys_signing_enabled_status: BTreeMap<MasterPublicKeyId, bool>,
     ecdsa_signature_fee: Option<Cycles>,
@@ -921,6 +1014,7 @@ impl StateMachineBuilder {
             nns_subnet_id: None,
             subnet_id: None,
             routing_table: RoutingTable::new(),
+            xnet_link_configs: BTreeMap::new(),
             idkg_keys_signing_enabled_status: Default::default(),
             ecdsa_signature_fee: None,
             schnorr_signature_fee: None,
@@ -1023,6 +1117,19 @@ impl StateMachineBuilder {
         }
     }
 
+    /// Configures the XNet link from the remote subnet with the given ID
+    /// to the `StateMachine` built using `Self::build_with_subnets`.
+    /// The configuration can be changed later using `StateMachine::set_xnet_link_config`.
+    pub fn with_xnet_link_config(
+        mut self,
+        remote_subnet_id: SubnetId,
+        xnet_link_config: XNetLinkConfig,
+    ) -> Self {
+        self.xnet_link_configs
+            .insert(remote_subnet_id, xnet_link_config);
+        self
+    }
+
     pub fn with_subnet_id(self, subnet_id: SubnetId) -> Self {
         Self {
             subnet_id: Some(subnet_id),
@@ -1195,8 +1302,10 @@ impl StateMachineBuilder {
         subnets: Arc<RwLock<BTreeMap<SubnetId, Arc<StateMachine>>>>,
     ) -> Arc<StateMachine> {
         // Build a `StateMachine` for the subnet with `self.subnet_id`.
+        let xnet_link_configs = self.xnet_link_configs.clone();
         let sm = Arc::new(self.build_internal());
         let subnet_id = sm.get_subnet_id();
+        *sm.xnet_link_configs.write().unwrap() = xnet_link_configs;
 
         // Register this new `StateMachine` in the *shared* association
         // of subnet IDs and their corresponding `StateMachine`s.
@@ -1211,7 +1320,11 @@ impl StateMachineBuilder {
         // Instantiate a `XNetPayloadBuilderImpl`.
         // We need to use a deterministic PRNG - so we use an arbitrary fixed seed, e.g., 42.
         let rng = Arc::new(Some(Mutex::new(StdRng::seed_from_u64(42))));
-        let xnet_slice_pool_impl = Box::new(PocketXNetSlicePoolImpl::new(subnets, subnet_id));
+        let xnet_slice_pool_impl = Box::new(PocketXNetSlicePoolImpl::new(
+            subnets,
+            subnet_id,
+            sm.xnet_link_configs.clone(),
+        ));
         let metrics = Arc::new(XNetPayloadBuilderMetrics::new(&sm.metrics_registry));
         let xnet_payload_builder = XNetPayloadBuilderImpl::new_from_components(
             sm.state_manager.clone(),
@@ -1755,6 +1868,7 @@ impl StateMachine {
             ingress_filter: runtime
                 .block_on(async { TowerBuffer::new(execution_services.ingress_filter, 1) }),
             payload_builder: Arc::new(RwLock::new(None)), // set by `StateMachineBuilder::build_with_subnets`
+            xnet_link_configs: Arc::new(RwLock::new(BTreeMap::new())),
             ingress_history_reader: execution_services.ingress_history_reader,
             message_routing,
             metrics_registry: metrics_registry.clone(),
@@ -1899,6 +2013,20 @@ impl StateMachine {
             .store(checkpoint_interval_length, Ordering::Relaxed);
     }
 
+    /// Sets the configuration of the XNet link from the remote subnet with the given ID
+    /// to this subnet. Only applies if this `StateMachine` was built using
+    /// `StateMachineBuilder::build_with_subnets`.
+    pub fn set_xnet_link_config(
+        &self,
+        remote_subnet_id: SubnetId,
+        xnet_link_config: XNetLinkConfig,
+    ) {
+        self.xnet_link_configs
+            .write()
+            .unwrap()
+            .insert(remote_subnet_id, xnet_link_config);
+    }
+
     /// Returns the latest state.
     pub fn get_latest_state(&self) -> Arc<ReplicatedState> {
         self.state_manager.get_latest_state().take()
@@ -1,13 +1,15 @@
 use ic_config::{execution_environment::Config as HypervisorConfig, subnet_config::SubnetConfig};
+use ic_error_types::RejectCode;
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
 use ic_registry_routing_table::{CanisterIdRange, RoutingTable, CANISTER_IDS_PER_SUBNET};
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
-    finalize_registry, StateMachine, StateMachineBuilder, StateMachineConfig,
+    finalize_registry, StateMachine, StateMachineBuilder, StateMachineConfig, XNetLinkConfig,
 };
 use ic_test_utilities_types::ids::user_test_id;
 use ic_types::{
-    ingress::{IngressStatus, WasmResult},
+    ingress::{IngressState, IngressStatus, WasmResult},
+    messages::MessageId,
     CanisterId, Cycles, SubnetId,
 };
 use ic_universal_canister::{wasm, CallArgs, UNIVERSAL_CANISTER_WASM};
@@ -379,3 +381,308 @@ fn counter_canister_call_test() {
         _ => panic!("unreachable"),
     };
 }
+
+/// Sets up two application subnets connected by XNet links and installs
+/// a universal canister on each of them.
+fn xnet_link_test_setup() -> (Arc<StateMachine>, CanisterId, Arc<StateMachine>, CanisterId) {
+    let registry_data_provider = Arc::new(ProtoRegistryDataProvider::new());
+    let subnets = Arc::new(RwLock::new(BTreeMap::new()));
+    let env1 = test_setup(
+        subnets.clone(),
+        1,
+        SubnetType::Application,
+        registry_data_provider.clone(),
+    );
+    let env2 = test_setup(
+        subnets.clone(),
+        2,
+        SubnetType::Application,
+        registry_data_provider.clone(),
+    );
+
+    let subnet_id1 = env1.get_subnet_id();
+    let subnet_id2 = env2.get_subnet_id();
+    let mut routing_table = RoutingTable::new();
+    routing_table
+        .insert(
+            CanisterIdRange {
+                start: CanisterId::from_u64(0),
+                end: CanisterId::from_u64(CANISTER_IDS_PER_SUBNET - 1),
+            },
+            subnet_id1,
+        )
+        .unwrap();
+    routing_table
+        .insert(
+            CanisterIdRange {
+                start: CanisterId::from_u64(CANISTER_IDS_PER_SUBNET),
+                end: CanisterId::from_u64(2 * CANISTER_IDS_PER_SUBNET - 1),
+            },
+            subnet_id2,
+        )
+        .unwrap();
+    finalize_registry(
+        subnet_id1,
+        routing_table,
+        vec![subnet_id1, subnet_id2],
+        registry_data_provider,
+    );
+    env1.reload_registry();
+    env2.reload_registry();
+
+    let canister_id1 = env1
+        .install_canister_with_cycles(
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+            vec![],
+            None,
+            INITIAL_CYCLES_BALANCE,
+        )
+        .unwrap();
+    let canister_id2 = env2
+        .install_canister_with_cycles(
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+            vec![],
+            None,
+            INITIAL_CYCLES_BALANCE,
+        )
+        .unwrap();
+    (env1, canister_id1, env2, canister_id2)
+}
+
+/// Submits an ingress message to `caller` calling into `callee`, which replies with `reply`.
+fn submit_xnet_call(
+    env: &StateMachine,
+    caller: CanisterId,
+    callee: CanisterId,
+    reply: &[u8],
+) -> MessageId {
+    env.submit_ingress_as(
+        user_test_id(1).get(),
+        caller,
+        "update",
+        wasm()
+            .inter_update(
+                callee,
+                CallArgs::default().other_side(wasm().reply_data(reply)),
+            )
+            .build(),
+    )
+    .unwrap()
+}
+
+fn num_completed(env: &StateMachine, msg_ids: &[MessageId]) -> usize {
+    msg_ids
+        .iter()
+        .filter(|msg_id| {
+            matches!(
+                env.ingress_status(msg_id),
+                IngressStatus::Known {
+                    state: IngressState::Completed(_),
+                    ..
+                }
+            )
+        })
+        .count()
+}
+
+/// Returns the number of messages in the stream from `env` to the given subnet
+/// that have not yet been acknowledged by the remote subnet.
+fn stream_backlog(env: &StateMachine, remote_subnet_id: SubnetId) -> u64 {
+    env.get_latest_state()
+        .get_stream(&remote_subnet_id)
+        .map(|stream| stream.messages_end().get() - stream.messages_begin().get())
+        .unwrap_or_default()
+}
+
+/// Returns the number of messages from the given subnet that have been inducted by `env`.
+fn num_inducted_from(env: &StateMachine, remote_subnet_id: SubnetId) -> u64 {
+    env.get_latest_state()
+        .get_stream(&remote_subnet_id)
+        .map(|stream| stream.signals_end().get())
+        .unwrap_or_default()
+}
+
+#[test]
+fn xnet_link_latency_test() {
+    const LATENCY_ROUNDS: u64 = 3;
+    const MAX_TICKS: usize = 100;
+    let (env1, canister_id1, env2, canister_id2) = xnet_link_test_setup();
+
+    // Messages from the 2nd subnet are only delivered to the 1st subnet
+    // after `LATENCY_ROUNDS` rounds of the 1st subnet.
+    env1.set_xnet_link_config(
+        env2.get_subnet_id(),
+        XNetLinkConfig {
+            latency_rounds: LATENCY_ROUNDS,
+            ..Default::default()
+        },
+    );
+
+    // Invoke a method on the 1st subnet calling into the 2nd subnet.
+    let msg_id = submit_xnet_call(&env1, canister_id1, canister_id2, b"pong");
+    env1.execute_round();
+    env2.execute_round();
+
+    // The response is not delivered to the 1st subnet
+    // during the first `LATENCY_ROUNDS` rounds.
+    for _ in 0..LATENCY_ROUNDS {
+        env1.execute_round();
+        assert!(matches!(
+            env1.ingress_status(&msg_id),
+            IngressStatus::Known {
+                state: IngressState::Processing,
+                ..
+            }
+        ));
+    }
+
+    let wasm_result = env1.await_ingress(msg_id, MAX_TICKS).unwrap();
+    match wasm_result {
+        WasmResult::Reply(bytes) => assert_eq!(bytes, b"pong".to_vec()),
+        _ => panic!("unreachable"),
+    };
+}
+
+/// Checks that exactly one of `NUM_CALLS` responses queued in the stream from the 2nd
+/// subnet is inducted and executed per round of the 1st subnet, given the configured XNet link.
+fn assert_one_response_per_round(xnet_link_config: XNetLinkConfig, reply: &[u8]) {
+    const NUM_CALLS: usize = 3;
+    const MAX_TICKS: usize = 100;
+    let (env1, canister_id1, env2, canister_id2) = xnet_link_test_setup();
+    env1.set_xnet_link_config(env2.get_subnet_id(), xnet_link_config);
+
+    let msg_ids: Vec<_> = (0..NUM_CALLS)
+        .map(|_| submit_xnet_call(&env1, canister_id1, canister_id2, reply))
+        .collect();
+    env1.execute_round();
+    env2.execute_round();
+    env2.execute_round();
+    assert_eq!(
+        stream_backlog(&env2, env1.get_subnet_id()),
+        NUM_CALLS as u64
+    );
+    assert_eq!(num_inducted_from(&env1, env2.get_subnet_id()), 0);
+
+    for round in 1..=NUM_CALLS {
+        env1.execute_round();
+        assert_eq!(num_inducted_from(&env1, env2.get_subnet_id()), round as u64);
+        assert_eq!(num_completed(&env1, &msg_ids), round);
+    }
+
+    for msg_id in msg_ids {
+        let wasm_result = env1.await_ingress(msg_id, MAX_TICKS).unwrap();
+        assert_eq!(wasm_result, WasmResult::Reply(reply.to_vec()));
+    }
+}
+
+#[test]
+fn xnet_link_max_slice_messages_test() {
+    assert_one_response_per_round(
+        XNetLinkConfig {
+            max_slice_messages: Some(1),
+            ..Default::default()
+        },
+        b"pong",
+    );
+}
+
+#[test]
+fn xnet_link_max_slice_bytes_test() {
+    // Only a single response of 100 KB fits into a slice of 150 KB.
+    assert_one_response_per_round(
+        XNetLinkConfig {
+            max_slice_bytes: Some(150 * 1024),
+            ..Default::default()
+        },
+        &[1_u8; 100 * 1024],
+    );
+}
+
+#[test]
+fn xnet_link_backpressure_test() {
+    const NUM_CALLS: u64 = 3;
+    let (env1, canister_id1, env2, canister_id2) = xnet_link_test_setup();
+    let subnet_id2 = env2.get_subnet_id();
+
+    // Requests from the 1st subnet are delivered to the 2nd subnet one at a time,
+    // so they pile up in the stream of the 1st subnet.
+    env2.set_xnet_link_config(
+        env1.get_subnet_id(),
+        XNetLinkConfig {
+            max_slice_messages: Some(1),
+            ..Default::default()
+        },
+    );
+
+    let msg_ids: Vec<_> = (0..NUM_CALLS)
+        .map(|_| submit_xnet_call(&env1, canister_id1, canister_id2, b"pong"))
+        .collect();
+    env1.execute_round();
+    assert_eq!(stream_backlog(&env1, subnet_id2), NUM_CALLS);
+
+    // The backlog only drains at the rate of the link: in each round, the 2nd subnet
+    // inducts and executes a single request, whose response completes a single call
+    // on the 1st subnet.
+    for round in 1..=NUM_CALLS {
+        env2.execute_round();
+        assert_eq!(num_inducted_from(&env2, env1.get_subnet_id()), round);
+        env1.execute_round();
+        assert_eq!(num_inducted_from(&env1, subnet_id2), round);
+        assert_eq!(stream_backlog(&env1, subnet_id2), NUM_CALLS - round);
+        assert_eq!(num_completed(&env1, &msg_ids), round as usize);
+    }
+}
+
+#[test]
+fn xnet_link_reject_and_signals_test() {
+    const LATENCY_ROUNDS: u64 = 3;
+    const MAX_TICKS: usize = 100;
+    let (env1, canister_id1, env2, canister_id2) = xnet_link_test_setup();
+    let subnet_id2 = env2.get_subnet_id();
+    env1.set_xnet_link_config(
+        subnet_id2,
+        XNetLinkConfig {
+            latency_rounds: LATENCY_ROUNDS,
+            ..Default::default()
+        },
+    );
+    env2.stop_canister(canister_id2).unwrap();
+
+    // The request to the stopped canister is rejected by the 2nd subnet.
+    let msg_id = env1
+        .submit_ingress_as(
+            user_test_id(1).get(),
+            canister_id1,
+            "update",
+            wasm()
+                .inter_update(
+                    canister_id2,
+                    CallArgs::default()
+                        .other_side(wasm().reply_data(b"pong"))
+                        .on_reject(wasm().reject_code().int_to_blob().append_and_reply()),
+                )
+                .build(),
+        )
+        .unwrap();
+    env1.execute_round();
+    assert_eq!(stream_backlog(&env1, subnet_id2), 1);
+    env2.execute_round();
+
+    // Signals are not subject to the link latency: the request is acknowledged
+    // (and garbage collected) before the reject response is delivered.
+    env1.execute_round();
+    assert_eq!(stream_backlog(&env1, subnet_id2), 0);
+    assert!(matches!(
+        env1.ingress_status(&msg_id),
+        IngressStatus::Known {
+            state: IngressState::Processing,
+            ..
+        }
+    ));
+
+    let wasm_result = env1.await_ingress(msg_id, MAX_TICKS).unwrap();
+    assert_eq!(
+        wasm_result,
+        WasmResult::Reply((RejectCode::CanisterError as u32).to_le_bytes().to_vec())
+    );
+}
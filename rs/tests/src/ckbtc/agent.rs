@@ -117,6 +117,7 @@ async fn test_retrieve_btc(agent: &CkBtcMinterAgent) {
     let args = RetrieveBtcArgs {
         amount: 42_000,
         address: "".to_string(),
+        fee_priority: None,
     };
     let res = agent
         .retrieve_btc(args)
@@ -53,6 +53,7 @@ pub fn test_ckbtc_addresses(env: TestEnv) {
         let arg = GetBtcAddressArgs {
             owner: None,
             subaccount: None,
+            address_type: None,
         };
         let arg = Encode!(&arg).expect("Error while encoding arg.");
         let res = agent
@@ -74,6 +75,7 @@ pub fn test_ckbtc_addresses(env: TestEnv) {
         let arg = GetBtcAddressArgs {
             owner: None,
             subaccount: None,
+            address_type: None,
         };
         let arg = Encode!(&arg).expect("Error while encoding argument.");
         let res = agent
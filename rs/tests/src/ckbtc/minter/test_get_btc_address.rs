@@ -40,6 +40,7 @@ pub fn test_get_btc_address(env: TestEnv) {
         let arg = GetBtcAddressArgs {
             owner: None,
             subaccount: None,
+            address_type: None,
         };
         let arg = &Encode!(&arg).expect("Error while encoding arg.");
         let res = agent
@@ -140,6 +140,7 @@ pub fn test_heartbeat(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: retrieve_amount,
                 address: destination_btc_address.to_string(),
+                fee_priority: None,
             })
             .await
             .expect("Error while calling retrieve_btc")
@@ -207,6 +208,7 @@ pub fn test_heartbeat(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: retrieve_amount,
                 address: main_btc_address.clone(),
+                fee_priority: None,
             })
             .await;
         assert!(illegal_retrieve_response.is_err());
@@ -303,6 +303,7 @@ pub fn test_kyt(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: retrieve_amount,
                 address: btc_address2.to_string(),
+                fee_priority: None,
             })
             .await
             .expect("Error while calling retrieve_btc");
@@ -341,6 +342,7 @@ pub fn test_kyt(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: retrieve_amount,
                 address: btc_address2.to_string(),
+                fee_priority: None,
             })
             .await
             .expect("Error while calling retrieve_btc")
@@ -178,6 +178,7 @@ pub fn test_retrieve_btc(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: 35_000_000,
                 address: btc_address2.to_string(),
+                fee_priority: None,
             })
             .await
             .expect("Error while calling retrieve_btc")
@@ -202,6 +203,7 @@ pub fn test_retrieve_btc(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: 35_000_000,
                 address: btc_address2.to_string(),
+                fee_priority: None,
             })
             .await
             .expect("Error while calling retrieve_btc");
@@ -217,6 +219,7 @@ pub fn test_retrieve_btc(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: 33,
                 address: btc_address2.to_string(),
+                fee_priority: None,
             })
             .await
             .expect("Error while calling retrieve_btc");
@@ -242,6 +245,7 @@ pub fn test_retrieve_btc(env: TestEnv) {
             .retrieve_btc(RetrieveBtcArgs {
                 amount: 1_000_000,
                 address: btc_address2.to_string(),
+                fee_priority: None,
             })
             .await
             .expect("Error while calling retrieve_btc")
@@ -112,6 +112,7 @@ pub fn ic_xc_ledger_suite_orchestrator_test(env: TestEnv) {
                 archive_compressed_wasm_hash: None,
                 cycles_management: None,
                 manage_ledger_suites: None,
+                rollout: None,
             }),
         )
         .await
@@ -136,6 +137,7 @@ pub fn ic_xc_ledger_suite_orchestrator_test(env: TestEnv) {
             AddErc20Arg {
                 contract: usdc_contract(),
                 ledger_init_arg: usdc_ledger_init_arg(),
+                balance_diff_accounting: None,
             },
         )
         .await